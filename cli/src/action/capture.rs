@@ -0,0 +1,66 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::ArgMatches;
+use splinter::network::capture::{CaptureReader, CaptureRecord};
+
+use super::Action;
+use crate::error::CliError;
+
+pub struct ShowCaptureAction;
+
+impl Action for ShowCaptureAction {
+    fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
+        let args = arg_matches.ok_or(CliError::RequiresArgs)?;
+
+        let file = args.value_of("file").ok_or(CliError::RequiresArgs)?;
+        let peer_id = args.value_of("peer_id");
+        let message_type = args.value_of("message_type");
+        let show_payload = args.is_present("payload");
+
+        let reader = CaptureReader::open(file).map_err(|err| {
+            CliError::ActionError(format!("Unable to open capture {}: {}", file, err))
+        })?;
+
+        for record in reader {
+            let record = record.map_err(|err| {
+                CliError::ActionError(format!("Unable to read capture {}: {}", file, err))
+            })?;
+
+            if !matches(&record, peer_id, message_type) {
+                continue;
+            }
+
+            println!("{}", record);
+            if show_payload {
+                println!("    {}", to_hex(record.payload()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks whether the record is for the given peer, if any, and whether its message type contains
+/// the given message type, if any.
+fn matches(record: &CaptureRecord, peer_id: Option<&str>, message_type: Option<&str>) -> bool {
+    peer_id.map(|id| record.peer_id() == id).unwrap_or(true)
+        && message_type
+            .map(|message_type| record.message_type().contains(message_type))
+            .unwrap_or(true)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
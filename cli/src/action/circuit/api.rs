@@ -151,6 +151,47 @@ impl<'a> SplinterRestClient<'a> {
             })
     }
 
+    pub fn fetch_circuit_history(
+        &self,
+        circuit_id: &str,
+    ) -> Result<Option<CircuitHistorySlice>, CliError> {
+        Client::new()
+            .get(&format!(
+                "{}/admin/circuits/{}/history",
+                self.url, circuit_id
+            ))
+            .header("SplinterProtocolVersion", ADMIN_PROTOCOL_VERSION)
+            .send()
+            .map_err(|err| CliError::ActionError(err.to_string()))
+            .and_then(|res| match res.status() {
+                StatusCode::OK => Ok(Some(
+                    res.json::<CircuitHistorySlice>()
+                        .map_err(|err| CliError::ActionError(err.to_string()))?,
+                )),
+                StatusCode::NOT_FOUND => Ok(None),
+                StatusCode::BAD_REQUEST | StatusCode::INTERNAL_SERVER_ERROR => {
+                    let message = res
+                        .json::<ServerError>()
+                        .map_err(|err| {
+                            CliError::ActionError(format!(
+                                "Unable to parse error response: {}",
+                                err
+                            ))
+                        })?
+                        .message;
+
+                    Err(CliError::ActionError(format!(
+                        "Unable to fetch circuit history: {}",
+                        message
+                    )))
+                }
+                _ => Err(CliError::ActionError(format!(
+                    "Received unknown response status: {}",
+                    res.status()
+                ))),
+            })
+    }
+
     pub fn list_proposals(&self, filter: Option<&str>) -> Result<ProposalListSlice, CliError> {
         let mut request = format!("{}/admin/proposals", self.url);
         if let Some(filter) = filter {
@@ -295,6 +336,57 @@ pub struct CircuitListSlice {
     pub paging: Paging,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CircuitHistorySlice {
+    pub circuit_id: String,
+    pub data: Vec<CircuitRevisionSlice>,
+}
+
+impl fmt::Display for CircuitHistorySlice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut display_string = format!("Circuit: {}\n", self.circuit_id);
+
+        for revision in self.data.iter() {
+            display_string += &format!(
+                "\n    Revision {}: {}\n        Circuit Hash: {}\n",
+                revision.revision, revision.proposal_type, revision.circuit_hash
+            );
+            if let Some(ref previous_hash) = revision.previous_hash {
+                display_string += &format!("        Previous Hash: {}\n", previous_hash);
+            }
+            display_string += &format!("        Committed At: {}\n", revision.committed_at);
+            display_string += &format!(
+                "        Requester: {} ({})\n",
+                revision.requester_node_id, revision.requester
+            );
+            for vote in revision.votes.iter() {
+                display_string += &format!(
+                    "        Vote: {:?} {} ({})\n",
+                    vote.vote, vote.voter_node_id, vote.public_key
+                );
+            }
+            if let Some(ref circuit) = revision.circuit {
+                display_string += &format!("        Members: {}\n", circuit.members.join(", "));
+            }
+        }
+
+        write!(f, "{}", display_string)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CircuitRevisionSlice {
+    pub revision: u64,
+    pub circuit_hash: String,
+    pub previous_hash: Option<String>,
+    pub proposal_type: String,
+    pub requester: String,
+    pub requester_node_id: String,
+    pub votes: Vec<VoteRecord>,
+    pub committed_at: u64,
+    pub circuit: Option<CircuitSlice>,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub struct ProposalSlice {
     pub proposal_type: String,
@@ -23,6 +23,12 @@ use super::defaults::{get_default_value_store, MANAGEMENT_TYPE_KEY, SERVICE_TYPE
 use crate::error::CliError;
 use crate::store::default_value::DefaultValueStore;
 
+#[cfg(feature = "circuit-durability")]
+use splinter::admin::messages::DurabilityType;
+#[cfg(feature = "circuit-persistence")]
+use splinter::admin::messages::PersistenceType;
+#[cfg(feature = "circuit-relay")]
+use splinter::admin::messages::RouteType;
 use splinter::admin::messages::{
     AuthorizationType, CreateCircuit, CreateCircuitBuilder, SplinterNode, SplinterNodeBuilder,
     SplinterServiceBuilder,
@@ -36,6 +42,12 @@ pub struct CreateCircuitMessageBuilder {
     management_type: Option<String>,
     #[cfg(feature = "circuit-auth-type")]
     authorization_type: Option<AuthorizationType>,
+    #[cfg(feature = "circuit-durability")]
+    durability: Option<DurabilityType>,
+    #[cfg(feature = "circuit-persistence")]
+    persistence: Option<PersistenceType>,
+    #[cfg(feature = "circuit-relay")]
+    relays: Vec<String>,
     application_metadata: Vec<u8>,
 }
 
@@ -47,6 +59,12 @@ impl CreateCircuitMessageBuilder {
             management_type: None,
             #[cfg(feature = "circuit-auth-type")]
             authorization_type: None,
+            #[cfg(feature = "circuit-durability")]
+            durability: None,
+            #[cfg(feature = "circuit-persistence")]
+            persistence: None,
+            #[cfg(feature = "circuit-relay")]
+            relays: vec![],
             application_metadata: vec![],
         }
     }
@@ -191,6 +209,45 @@ impl CreateCircuitMessageBuilder {
         Ok(())
     }
 
+    #[cfg(feature = "circuit-durability")]
+    pub fn set_durability(&mut self, durability: &str) -> Result<(), CliError> {
+        let durability_type = match durability {
+            "none" => DurabilityType::NoDurability,
+            "durable" => DurabilityType::Durable,
+            _ => {
+                return Err(CliError::ActionError(format!(
+                    "Invalid durability {}",
+                    durability
+                )))
+            }
+        };
+
+        self.durability = Some(durability_type);
+        Ok(())
+    }
+
+    #[cfg(feature = "circuit-persistence")]
+    pub fn set_persistence(&mut self, persistence: &str) -> Result<(), CliError> {
+        let persistence_type = match persistence {
+            "any" => PersistenceType::Any,
+            "persistent" => PersistenceType::Persistent,
+            _ => {
+                return Err(CliError::ActionError(format!(
+                    "Invalid persistence {}",
+                    persistence
+                )))
+            }
+        };
+
+        self.persistence = Some(persistence_type);
+        Ok(())
+    }
+
+    #[cfg(feature = "circuit-relay")]
+    pub fn add_relay(&mut self, node_id: &str) {
+        self.relays.push(node_id.into());
+    }
+
     pub fn set_application_metadata(&mut self, application_metadata: &[u8]) {
         self.application_metadata = application_metadata.into();
     }
@@ -255,6 +312,25 @@ impl CreateCircuitMessageBuilder {
             None => create_circuit_builder,
         };
 
+        #[cfg(feature = "circuit-durability")]
+        let create_circuit_builder = match self.durability {
+            Some(durability) => create_circuit_builder.with_durability(&durability),
+            None => create_circuit_builder,
+        };
+
+        #[cfg(feature = "circuit-persistence")]
+        let create_circuit_builder = match self.persistence {
+            Some(persistence) => create_circuit_builder.with_persistence(&persistence),
+            None => create_circuit_builder,
+        };
+
+        #[cfg(feature = "circuit-relay")]
+        let create_circuit_builder = if self.relays.is_empty() {
+            create_circuit_builder
+        } else {
+            create_circuit_builder.with_routes(&RouteType::Relayed(self.relays))
+        };
+
         let create_circuit = create_circuit_builder.build().map_err(|err| {
             CliError::ActionError(format!("Failed to build CreateCircuit message: {}", err))
         })?;
@@ -24,7 +24,7 @@ use clap::ArgMatches;
 
 use crate::error::CliError;
 
-use super::Action;
+use super::{print_table, Action};
 use crate::action::DEFAULT_ENDPOINT;
 use builder::CreateCircuitMessageBuilder;
 
@@ -90,6 +90,29 @@ impl Action for CircuitCreateAction {
             None => (),
         }
 
+        #[cfg(feature = "circuit-durability")]
+        {
+            if let Some(durability) = args.value_of("durability") {
+                builder.set_durability(durability)?;
+            }
+        }
+
+        #[cfg(feature = "circuit-persistence")]
+        {
+            if let Some(persistence) = args.value_of("persistence") {
+                builder.set_persistence(persistence)?;
+            }
+        }
+
+        #[cfg(feature = "circuit-relay")]
+        {
+            if let Some(relays) = args.values_of("relay") {
+                for relay in relays {
+                    builder.add_relay(relay);
+                }
+            }
+        }
+
         if let Some(management_type) = args.value_of("management_type") {
             builder.set_management_type(management_type);
         }
@@ -354,6 +377,66 @@ fn vote_on_circuit_proposal(
     }
 }
 
+pub(self) struct CircuitDestroy {
+    circuit_id: String,
+}
+
+pub struct CircuitDestroyAction;
+
+impl Action for CircuitDestroyAction {
+    fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
+        let args = arg_matches.ok_or_else(|| CliError::RequiresArgs)?;
+        let url = args.value_of("url").unwrap_or(DEFAULT_ENDPOINT);
+        let key = args.value_of("private_key_file").unwrap_or("splinter");
+        let circuit_id = match args.value_of("circuit_id") {
+            Some(circuit_id) => circuit_id,
+            None => return Err(CliError::ActionError("Circuit id is required".into())),
+        };
+
+        let client = api::SplinterRestClient::new(url);
+        let private_key_hex = read_private_key(key)?;
+        let requester_node = client.fetch_node_id()?;
+
+        let circuit_destroy = CircuitDestroy {
+            circuit_id: circuit_id.into(),
+        };
+        let signed_payload =
+            payload::make_signed_payload(&requester_node, &private_key_hex, circuit_destroy)?;
+
+        client.submit_admin_payload(signed_payload)
+    }
+}
+
+pub(self) struct CircuitAbandon {
+    circuit_id: String,
+}
+
+pub struct CircuitAbandonAction;
+
+impl Action for CircuitAbandonAction {
+    fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
+        let args = arg_matches.ok_or_else(|| CliError::RequiresArgs)?;
+        let url = args.value_of("url").unwrap_or(DEFAULT_ENDPOINT);
+        let key = args.value_of("private_key_file").unwrap_or("splinter");
+        let circuit_id = match args.value_of("circuit_id") {
+            Some(circuit_id) => circuit_id,
+            None => return Err(CliError::ActionError("Circuit id is required".into())),
+        };
+
+        let client = api::SplinterRestClient::new(url);
+        let private_key_hex = read_private_key(key)?;
+        let requester_node = client.fetch_node_id()?;
+
+        let circuit_abandon = CircuitAbandon {
+            circuit_id: circuit_id.into(),
+        };
+        let signed_payload =
+            payload::make_signed_payload(&requester_node, &private_key_hex, circuit_abandon)?;
+
+        client.submit_admin_payload(signed_payload)
+    }
+}
+
 pub struct CircuitListAction;
 
 impl Action for CircuitListAction {
@@ -476,6 +559,51 @@ fn show_circuit(url: &str, circuit_id: &str, format: &str) -> Result<(), CliErro
     Ok(())
 }
 
+pub struct CircuitHistoryAction;
+
+impl Action for CircuitHistoryAction {
+    fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
+        let args = arg_matches.ok_or_else(|| CliError::RequiresArgs)?;
+
+        let url = args.value_of("url").unwrap_or(DEFAULT_ENDPOINT);
+        let circuit_id = args
+            .value_of("circuit")
+            .ok_or_else(|| CliError::ActionError("Circuit name must be provided".to_string()))?;
+
+        // A value should always be passed because a default is defined
+        let format = args.value_of("format").unwrap_or("human");
+
+        show_circuit_history(url, circuit_id, format)
+    }
+}
+
+fn show_circuit_history(url: &str, circuit_id: &str, format: &str) -> Result<(), CliError> {
+    let client = api::SplinterRestClient::new(url);
+    let history = client.fetch_circuit_history(circuit_id)?.ok_or_else(|| {
+        CliError::ActionError(format!("History for {} does not exist", circuit_id))
+    })?;
+
+    match format {
+        "json" => println!(
+            "\n {}",
+            serde_json::to_string(&history).map_err(|err| CliError::ActionError(format!(
+                "Cannot format circuit history into json: {}",
+                err
+            )))?
+        ),
+        "yaml" => println!(
+            "{}",
+            serde_yaml::to_string(&history).map_err(|err| CliError::ActionError(format!(
+                "Cannot format circuit history into yaml: {}",
+                err
+            )))?
+        ),
+        _ => println!("{}", history),
+    }
+
+    Ok(())
+}
+
 pub struct CircuitProposalsAction;
 
 impl Action for CircuitProposalsAction {
@@ -527,36 +655,3 @@ fn list_proposals(url: &str, filter: Option<&str>, format: &str) -> Result<(), C
 
     Ok(())
 }
-
-// Takes a vec of vecs of strings. The first vec should include the title of the columns.
-// The max length of each column is calculated and is used as the column with when printing the
-// table.
-fn print_table(table: Vec<Vec<String>>) {
-    let mut max_lengths = Vec::new();
-
-    // find the max lengths of the columns
-    for row in table.iter() {
-        for (i, col) in row.iter().enumerate() {
-            if let Some(length) = max_lengths.get_mut(i) {
-                if col.len() > *length {
-                    *length = col.len()
-                }
-            } else {
-                max_lengths.push(col.len())
-            }
-        }
-    }
-
-    // print each row with correct column size
-    for row in table.iter() {
-        let mut col_string = String::from("");
-        for (i, len) in max_lengths.iter().enumerate() {
-            if let Some(value) = row.get(i) {
-                col_string += &format!("{}{} ", value, " ".repeat(*len - value.len()),);
-            } else {
-                col_string += &" ".repeat(*len);
-            }
-        }
-        println!("{}", col_string);
-    }
-}
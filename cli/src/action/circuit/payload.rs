@@ -17,14 +17,15 @@ use protobuf::Message;
 use sawtooth_sdk::signing::secp256k1;
 use splinter::admin::messages::CreateCircuit;
 use splinter::protos::admin::{
-    CircuitCreateRequest, CircuitManagementPayload, CircuitManagementPayload_Action as Action,
+    CircuitAbandon as CircuitAbandonProto, CircuitCreateRequest, CircuitDestroyRequest,
+    CircuitManagementPayload, CircuitManagementPayload_Action as Action,
     CircuitManagementPayload_Header as Header, CircuitProposalVote, CircuitProposalVote_Vote,
 };
 use splinter::signing::{sawtooth, Signer};
 
 use crate::error::CliError;
 
-use super::{CircuitVote, Vote};
+use super::{CircuitAbandon, CircuitDestroy, CircuitVote, Vote};
 
 /// A circuit action that has a type and can be converted into a protobuf-serializable struct.
 pub trait CircuitAction<M: Message> {
@@ -132,3 +133,41 @@ impl ApplyToEnvelope for CircuitProposalVote {
         circuit_management_payload.set_circuit_proposal_vote(self);
     }
 }
+
+impl CircuitAction<CircuitDestroyRequest> for CircuitDestroy {
+    fn action_type(&self) -> Action {
+        Action::CIRCUIT_DESTROY_REQUEST
+    }
+
+    fn into_proto(self) -> Result<CircuitDestroyRequest, CliError> {
+        let mut destroy_request = CircuitDestroyRequest::new();
+        destroy_request.set_circuit_id(self.circuit_id);
+
+        Ok(destroy_request)
+    }
+}
+
+impl ApplyToEnvelope for CircuitDestroyRequest {
+    fn apply(self, circuit_management_payload: &mut CircuitManagementPayload) {
+        circuit_management_payload.set_circuit_destroy_request(self);
+    }
+}
+
+impl CircuitAction<CircuitAbandonProto> for CircuitAbandon {
+    fn action_type(&self) -> Action {
+        Action::CIRCUIT_ABANDON
+    }
+
+    fn into_proto(self) -> Result<CircuitAbandonProto, CliError> {
+        let mut abandon = CircuitAbandonProto::new();
+        abandon.set_circuit_id(self.circuit_id);
+
+        Ok(abandon)
+    }
+}
+
+impl ApplyToEnvelope for CircuitAbandonProto {
+    fn apply(self, circuit_management_payload: &mut CircuitManagementPayload) {
+        circuit_management_payload.set_circuit_abandon(self);
+    }
+}
@@ -13,6 +13,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 pub mod admin;
+#[cfg(feature = "capture")]
+pub mod capture;
 pub mod certs;
 #[cfg(feature = "circuit")]
 pub mod circuit;
@@ -24,6 +26,8 @@ pub mod health;
 pub mod keygen;
 #[cfg(feature = "node-alias")]
 pub mod node;
+#[cfg(feature = "peer")]
+pub mod peer;
 
 use std::collections::HashMap;
 use std::ffi::CString;
@@ -34,7 +38,7 @@ use libc;
 
 use super::error::CliError;
 
-#[cfg(any(feature = "health", feature = "circuit"))]
+#[cfg(any(feature = "health", feature = "circuit", feature = "peer"))]
 const DEFAULT_ENDPOINT: &str = "http://127.0.0.1:8085";
 
 /// A CLI Command Action.
@@ -81,6 +85,40 @@ impl<'s> Action for SubcommandActions<'s> {
     }
 }
 
+// Takes a vec of vecs of strings. The first vec should include the title of the columns.
+// The max length of each column is calculated and is used as the column with when printing the
+// table.
+#[cfg(any(feature = "circuit", feature = "peer"))]
+fn print_table(table: Vec<Vec<String>>) {
+    let mut max_lengths = Vec::new();
+
+    // find the max lengths of the columns
+    for row in table.iter() {
+        for (i, col) in row.iter().enumerate() {
+            if let Some(length) = max_lengths.get_mut(i) {
+                if col.len() > *length {
+                    *length = col.len()
+                }
+            } else {
+                max_lengths.push(col.len())
+            }
+        }
+    }
+
+    // print each row with correct column size
+    for row in table.iter() {
+        let mut col_string = String::from("");
+        for (i, len) in max_lengths.iter().enumerate() {
+            if let Some(value) = row.get(i) {
+                col_string += &format!("{}{} ", value, " ".repeat(*len - value.len()),);
+            } else {
+                col_string += &" ".repeat(*len);
+            }
+        }
+        println!("{}", col_string);
+    }
+}
+
 fn chown(path: &Path, uid: u32, gid: u32) -> Result<(), CliError> {
     let pathstr = path
         .to_str()
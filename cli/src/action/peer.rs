@@ -0,0 +1,203 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ArgMatches;
+use reqwest::{blocking::Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use splinter::protocol::ADMIN_PROTOCOL_VERSION;
+
+use super::{print_table, Action};
+use crate::action::DEFAULT_ENDPOINT;
+use crate::error::CliError;
+
+pub struct ListPeersAction;
+
+impl Action for ListPeersAction {
+    fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
+        let args = arg_matches.ok_or_else(|| CliError::RequiresArgs)?;
+
+        let url = args.value_of("url").unwrap_or(DEFAULT_ENDPOINT);
+        let format = args.value_of("format").unwrap_or("human");
+
+        let peers = Client::new()
+            .get(&format!("{}/admin/peers", url))
+            .header("SplinterProtocolVersion", ADMIN_PROTOCOL_VERSION)
+            .send()
+            .map_err(|err| CliError::ActionError(format!("Unable to list peers: {}", err)))
+            .and_then(|res| match res.status() {
+                StatusCode::OK => res
+                    .json::<PeerListSlice>()
+                    .map_err(|err| CliError::ActionError(err.to_string())),
+                status => Err(error_from_response(res, status, "Unable to list peers")),
+            })?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        let mut data = Vec::new();
+        data.push(vec![
+            "NODE ID".to_string(),
+            "ENDPOINT".to_string(),
+            "AUTH STATE".to_string(),
+            "CONNECTED SINCE".to_string(),
+            "LAST HEARTBEAT".to_string(),
+            "BYTES IN".to_string(),
+            "BYTES OUT".to_string(),
+        ]);
+        peers.data.into_iter().for_each(|peer| {
+            data.push(vec![
+                peer.node_id,
+                peer.endpoint.unwrap_or_else(|| "-".to_string()),
+                peer.auth_state,
+                format_time(peer.connected_since, now, format),
+                format_time(peer.last_heartbeat, now, format),
+                peer.bytes_received.to_string(),
+                peer.bytes_sent.to_string(),
+            ]);
+        });
+
+        if format == "csv" {
+            for row in data {
+                println!("{}", row.join(","))
+            }
+        } else {
+            print_table(data);
+        }
+
+        Ok(())
+    }
+}
+
+pub struct ConnectPeerAction;
+
+impl Action for ConnectPeerAction {
+    fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
+        let args = arg_matches.ok_or_else(|| CliError::RequiresArgs)?;
+
+        let url = args.value_of("url").unwrap_or(DEFAULT_ENDPOINT);
+        let endpoint = args
+            .value_of("endpoint")
+            .ok_or_else(|| CliError::ActionError("Endpoint must be provided".to_string()))?;
+
+        Client::new()
+            .post(&format!("{}/admin/peers", url))
+            .header("SplinterProtocolVersion", ADMIN_PROTOCOL_VERSION)
+            .json(&ConnectPeerRequest {
+                endpoint: endpoint.to_string(),
+            })
+            .send()
+            .map_err(|err| CliError::ActionError(format!("Unable to connect to peer: {}", err)))
+            .and_then(|res| match res.status() {
+                StatusCode::ACCEPTED => Ok(()),
+                status => Err(error_from_response(
+                    res,
+                    status,
+                    "Unable to connect to peer",
+                )),
+            })?;
+
+        info!("Connecting to peer at {}", endpoint);
+        Ok(())
+    }
+}
+
+pub struct DisconnectPeerAction;
+
+impl Action for DisconnectPeerAction {
+    fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
+        let args = arg_matches.ok_or_else(|| CliError::RequiresArgs)?;
+
+        let url = args.value_of("url").unwrap_or(DEFAULT_ENDPOINT);
+        let peer_id = args
+            .value_of("peer_id")
+            .ok_or_else(|| CliError::ActionError("Peer ID must be provided".to_string()))?;
+
+        Client::new()
+            .delete(&format!("{}/admin/peers/{}", url, peer_id))
+            .header("SplinterProtocolVersion", ADMIN_PROTOCOL_VERSION)
+            .send()
+            .map_err(|err| CliError::ActionError(format!("Unable to disconnect peer: {}", err)))
+            .and_then(|res| match res.status() {
+                StatusCode::OK => Ok(()),
+                StatusCode::NOT_FOUND => {
+                    Err(CliError::ActionError(format!("Peer {} not found", peer_id)))
+                }
+                status => Err(error_from_response(
+                    res,
+                    status,
+                    "Unable to disconnect peer",
+                )),
+            })?;
+
+        info!("Disconnected peer {}", peer_id);
+        Ok(())
+    }
+}
+
+/// Formats a time in seconds since the epoch as its age, in seconds, for humans, or as is for CSV.
+fn format_time(time: Option<u64>, now: u64, format: &str) -> String {
+    match time {
+        Some(time) if format == "csv" => time.to_string(),
+        Some(time) => format!("{}s ago", now.saturating_sub(time)),
+        None => "-".to_string(),
+    }
+}
+
+fn error_from_response(
+    res: reqwest::blocking::Response,
+    status: StatusCode,
+    context: &str,
+) -> CliError {
+    match status {
+        StatusCode::BAD_REQUEST | StatusCode::INTERNAL_SERVER_ERROR => {
+            match res.json::<ServerError>() {
+                Ok(err) => CliError::ActionError(format!("{}: {}", context, err.message)),
+                Err(err) => {
+                    CliError::ActionError(format!("Unable to parse error response: {}", err))
+                }
+            }
+        }
+        _ => CliError::ActionError(format!("Received unknown response status: {}", status)),
+    }
+}
+
+#[derive(Deserialize)]
+struct ServerError {
+    message: String,
+}
+
+#[derive(Serialize)]
+struct ConnectPeerRequest {
+    endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct PeerListSlice {
+    data: Vec<PeerSlice>,
+}
+
+#[derive(Deserialize)]
+struct PeerSlice {
+    node_id: String,
+    endpoint: Option<String>,
+    auth_state: String,
+    connected_since: Option<u64>,
+    last_heartbeat: Option<u64>,
+    bytes_sent: u64,
+    bytes_received: u64,
+}
@@ -176,6 +176,50 @@ fn run() -> Result<(), CliError> {
         )
     }
 
+    #[cfg(feature = "capture")]
+    {
+        app = app.subcommand(
+            SubCommand::with_name("capture")
+                .about("Inspect network captures")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("show")
+                        .about(
+                            "Displays the messages in a capture file written by a node\n\
+                             configured with a capture_file",
+                        )
+                        .arg(
+                            Arg::with_name("file")
+                                .takes_value(true)
+                                .required(true)
+                                .help("Path to the capture file"),
+                        )
+                        .arg(
+                            Arg::with_name("peer_id")
+                                .long("peer")
+                                .takes_value(true)
+                                .value_name("peer-id")
+                                .help("Only show messages sent to or received from this peer"),
+                        )
+                        .arg(
+                            Arg::with_name("message_type")
+                                .long("type")
+                                .takes_value(true)
+                                .value_name("message-type")
+                                .help(
+                                    "Only show messages whose type contains this value, such as \
+                                     ADMIN_DIRECT_MESSAGE",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("payload")
+                                .long("payload")
+                                .help("Show the bytes of each message, in hex"),
+                        ),
+                ),
+        );
+    }
+
     #[cfg(feature = "health")]
     {
         app = app.subcommand(
@@ -318,6 +362,45 @@ fn run() -> Result<(), CliError> {
                 .help("Authorization type for the circuit"),
         );
 
+        #[cfg(feature = "circuit-durability")]
+        let create_circuit = create_circuit.arg(
+            Arg::with_name("durability")
+                .long("durability")
+                .possible_values(&["none", "durable"])
+                .default_value("none")
+                .takes_value(true)
+                .help(
+                    "Durability of the circuit; direct messages on durable circuits are queued \
+                     until the node they are sent to acknowledges them",
+                ),
+        );
+
+        #[cfg(feature = "circuit-persistence")]
+        let create_circuit = create_circuit.arg(
+            Arg::with_name("persistence")
+                .long("persistence")
+                .possible_values(&["any", "persistent"])
+                .default_value("any")
+                .takes_value(true)
+                .help(
+                    "Persistence of the circuit; members of persistent circuits keep their \
+                     connections to each other alive, reconnecting when they are lost",
+                ),
+        );
+
+        #[cfg(feature = "circuit-relay")]
+        let create_circuit = create_circuit.arg(
+            Arg::with_name("relay")
+                .long("relay")
+                .value_name("node_id")
+                .takes_value(true)
+                .multiple(true)
+                .help(
+                    "Member of the circuit that direct messages to services on other nodes are \
+                     relayed through, in the order given",
+                ),
+        );
+
         app = app.subcommand(
             SubCommand::with_name("circuit")
                 .about("Provides circuit management functionality")
@@ -363,6 +446,58 @@ fn run() -> Result<(), CliError> {
                                 .help("Reject the proposal"),
                         ),
                 )
+                .subcommand(
+                    SubCommand::with_name("destroy")
+                        .about("Propose that an existing circuit is destroyed")
+                        .arg(
+                            Arg::with_name("url")
+                                .short("U")
+                                .long("url")
+                                .takes_value(true)
+                                .help("URL of Splinter Daemon"),
+                        )
+                        .arg(
+                            Arg::with_name("private_key_file")
+                                .value_name("private-key-file")
+                                .short("k")
+                                .long("key")
+                                .takes_value(true)
+                                .help("Path to private key file"),
+                        )
+                        .arg(
+                            Arg::with_name("circuit_id")
+                                .value_name("circuit-id")
+                                .takes_value(true)
+                                .required(true)
+                                .help("The circuit id of the circuit to destroy"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("abandon")
+                        .about("Leave a circuit without the agreement of the other members")
+                        .arg(
+                            Arg::with_name("url")
+                                .short("U")
+                                .long("url")
+                                .takes_value(true)
+                                .help("URL of Splinter Daemon"),
+                        )
+                        .arg(
+                            Arg::with_name("private_key_file")
+                                .value_name("private-key-file")
+                                .short("k")
+                                .long("key")
+                                .takes_value(true)
+                                .help("Path to private key file"),
+                        )
+                        .arg(
+                            Arg::with_name("circuit_id")
+                                .value_name("circuit-id")
+                                .takes_value(true)
+                                .required(true)
+                                .help("The circuit id of the circuit to abandon"),
+                        ),
+                )
                 .subcommand(
                     SubCommand::with_name("list")
                         .about("List the circuits")
@@ -415,6 +550,32 @@ fn run() -> Result<(), CliError> {
                                 .takes_value(true),
                         ),
                 )
+                .subcommand(
+                    SubCommand::with_name("history")
+                        .about("Show the committed revisions of a circuit")
+                        .arg(
+                            Arg::with_name("url")
+                                .short("U")
+                                .long("url")
+                                .help("The URL of the Splinter daemon REST API")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("circuit")
+                                .help("The circuit ID of the circuit whose history is shown")
+                                .required(true)
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("format")
+                                .short("f")
+                                .long("format")
+                                .help("Output format")
+                                .possible_values(&["human", "yaml", "json"])
+                                .default_value("human")
+                                .takes_value(true),
+                        ),
+                )
                 .subcommand(
                     SubCommand::with_name("proposals")
                         .about("List the circuit proposals")
@@ -558,6 +719,70 @@ fn run() -> Result<(), CliError> {
         );
     }
 
+    #[cfg(feature = "peer")]
+    {
+        app = app.subcommand(
+            SubCommand::with_name("peer")
+                .about("Provides peer management functionality")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("List the peers of a node")
+                        .arg(
+                            Arg::with_name("url")
+                                .short("U")
+                                .long("url")
+                                .help("The URL of the Splinter daemon REST API")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("format")
+                                .short("f")
+                                .long("format")
+                                .help("Output format")
+                                .possible_values(&["human", "csv"])
+                                .default_value("human")
+                                .takes_value(true),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("connect")
+                        .about("Connect a node to the peer at an endpoint")
+                        .arg(
+                            Arg::with_name("url")
+                                .short("U")
+                                .long("url")
+                                .help("The URL of the Splinter daemon REST API")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("endpoint")
+                                .takes_value(true)
+                                .required(true)
+                                .help("Endpoint of the peer"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("disconnect")
+                        .about("Disconnect a node from a peer")
+                        .arg(
+                            Arg::with_name("url")
+                                .short("U")
+                                .long("url")
+                                .help("The URL of the Splinter daemon REST API")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("peer_id")
+                                .takes_value(true)
+                                .value_name("peer-id")
+                                .required(true)
+                                .help("ID of the peer"),
+                        ),
+                ),
+        );
+    }
+
     let matches = app.get_matches();
 
     // set default to info
@@ -595,6 +820,15 @@ fn run() -> Result<(), CliError> {
             SubcommandActions::new().with_command("generate", certs::CertGenAction),
         );
 
+    #[cfg(feature = "capture")]
+    {
+        use action::capture;
+        subcommands = subcommands.with_command(
+            "capture",
+            SubcommandActions::new().with_command("show", capture::ShowCaptureAction),
+        );
+    }
+
     #[cfg(feature = "health")]
     {
         use action::health;
@@ -621,8 +855,11 @@ fn run() -> Result<(), CliError> {
             SubcommandActions::new()
                 .with_command("create", circuit::CircuitCreateAction)
                 .with_command("vote", circuit::CircuitVoteAction)
+                .with_command("destroy", circuit::CircuitDestroyAction)
+                .with_command("abandon", circuit::CircuitAbandonAction)
                 .with_command("list", circuit::CircuitListAction)
                 .with_command("show", circuit::CircuitShowAction)
+                .with_command("history", circuit::CircuitHistoryAction)
                 .with_command("proposals", circuit::CircuitProposalsAction)
                 .with_command(
                     "default",
@@ -651,6 +888,18 @@ fn run() -> Result<(), CliError> {
         )
     }
 
+    #[cfg(feature = "peer")]
+    {
+        use action::peer;
+        subcommands = subcommands.with_command(
+            "peer",
+            SubcommandActions::new()
+                .with_command("list", peer::ListPeersAction)
+                .with_command("connect", peer::ConnectPeerAction)
+                .with_command("disconnect", peer::DisconnectPeerAction),
+        )
+    }
+
     #[cfg(feature = "keygen")]
     {
         use action::keygen;
@@ -13,9 +13,9 @@
 // limitations under the License.
 
 pub use super::service::messages::{
-    AdminServiceEvent, AuthorizationType, CircuitProposal, CircuitProposalVote, CreateCircuit,
-    DurabilityType, PersistenceType, ProposalType, RouteType, SplinterNode, SplinterService, Vote,
-    VoteRecord,
+    AdminServiceEvent, ApprovalPolicy, AuthorizationType, CircuitProposal, CircuitProposalVote,
+    CreateCircuit, DurabilityType, PersistenceType, ProposalType, RouteType, SplinterNode,
+    SplinterService, Vote, VoteRecord,
 };
 
 pub use super::service::messages::builders::{
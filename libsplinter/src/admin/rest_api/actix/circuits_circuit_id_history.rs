@@ -0,0 +1,258 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This module provides the `GET /admin/circuits/{circuit_id}/history` endpoint for fetching the
+//! committed revisions of a circuit in Splinter's state.
+
+use actix_web::{error::BlockingError, web, Error, HttpRequest, HttpResponse};
+use futures::Future;
+
+use crate::circuit::store::CircuitStore;
+use crate::protocol;
+use crate::rest_api::{ErrorResponse, Method, ProtocolVersionRangeGuard, Resource};
+
+use super::super::error::CircuitFetchError;
+use super::super::resources::circuits_circuit_id_history::{
+    CircuitHistoryResponse, CircuitRevisionResponse,
+};
+
+pub fn make_fetch_circuit_history_resource<T: CircuitStore + 'static>(store: T) -> Resource {
+    Resource::build("/admin/circuits/{circuit_id}/history")
+        .add_request_guard(ProtocolVersionRangeGuard::new(
+            protocol::ADMIN_FETCH_CIRCUIT_HISTORY_MIN,
+            protocol::ADMIN_PROTOCOL_VERSION,
+        ))
+        .add_method(Method::Get, move |r, _| {
+            fetch_circuit_history(r, web::Data::new(store.clone()))
+        })
+}
+
+fn fetch_circuit_history<T: CircuitStore + 'static>(
+    request: HttpRequest,
+    store: web::Data<T>,
+) -> Box<dyn Future<Item = HttpResponse, Error = Error>> {
+    let circuit_id = request
+        .match_info()
+        .get("circuit_id")
+        .unwrap_or("")
+        .to_string();
+    Box::new(
+        web::block(move || {
+            let history = store.circuit_history(&circuit_id)?;
+            if history.is_empty() {
+                return Err(CircuitFetchError::NotFound(format!(
+                    "Unable to find history of circuit: {}",
+                    circuit_id
+                )));
+            }
+
+            Ok(CircuitHistoryResponse {
+                data: history
+                    .iter()
+                    .map(|revision| CircuitRevisionResponse::new(&circuit_id, revision))
+                    .collect(),
+                circuit_id,
+            })
+        })
+        .then(|res| match res {
+            Ok(history) => Ok(HttpResponse::Ok().json(history)),
+            Err(err) => match err {
+                BlockingError::Error(err) => match err {
+                    CircuitFetchError::CircuitStoreError(err) => {
+                        error!("{}", err);
+                        Ok(HttpResponse::InternalServerError()
+                            .json(ErrorResponse::internal_error()))
+                    }
+                    CircuitFetchError::NotFound(err) => {
+                        Ok(HttpResponse::NotFound().json(ErrorResponse::not_found(&err)))
+                    }
+                },
+
+                _ => {
+                    error!("{}", err);
+                    Ok(HttpResponse::InternalServerError().json(ErrorResponse::internal_error()))
+                }
+            },
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use reqwest::{blocking::Client, StatusCode, Url};
+
+    use crate::circuit::{
+        directory::CircuitDirectory,
+        history::{CircuitRevision, RevisionVote},
+        AuthorizationType, Circuit, DurabilityType, PersistenceType, RouteType, ServiceDefinition,
+        SplinterState,
+    };
+    use crate::rest_api::{RestApiBuilder, RestApiServerError, RestApiShutdownHandle};
+    use crate::storage::get_storage;
+
+    #[test]
+    /// Tests a GET /admin/circuits/{circuit_id}/history request returns the revisions of the
+    /// circuit, oldest first and chained by circuit hash.
+    fn test_fetch_circuit_history_ok() {
+        let (_shutdown_handle, _join_handle, bind_url) =
+            run_rest_api_on_open_port(vec![make_fetch_circuit_history_resource(
+                filled_splinter_state(),
+            )]);
+
+        let url = Url::parse(&format!(
+            "http://{}/admin/circuits/circuit_1/history",
+            bind_url
+        ))
+        .expect("Failed to parse URL");
+        let req = Client::new()
+            .get(url)
+            .header("SplinterProtocolVersion", protocol::ADMIN_PROTOCOL_VERSION);
+        let resp = req.send().expect("Failed to perform request");
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let history: CircuitHistoryResponse = resp.json().expect("Failed to deserialize body");
+        assert_eq!(history.circuit_id, "circuit_1");
+        assert_eq!(history.data.len(), 2);
+
+        assert_eq!(history.data[0].revision, 1);
+        assert_eq!(history.data[0].circuit_hash, "hash_1");
+        assert_eq!(history.data[0].previous_hash, None);
+        assert_eq!(history.data[0].proposal_type, "Create");
+        assert_eq!(history.data[0].votes[0].vote, "Accept");
+        assert_eq!(history.data[0].votes[0].voter_node_id, "node_2");
+
+        assert_eq!(history.data[1].revision, 2);
+        assert_eq!(history.data[1].previous_hash, Some("hash_1".to_string()));
+        assert_eq!(history.data[1].proposal_type, "Destroy");
+        assert_eq!(history.data[1].circuit, None);
+
+        let circuit = history.data[0]
+            .circuit
+            .as_ref()
+            .expect("Missing circuit in first revision");
+        assert_eq!(circuit.id, "circuit_1");
+        assert_eq!(circuit.members, vec!["node_1", "node_2"]);
+    }
+
+    #[test]
+    /// Tests a GET /admin/circuits/{circuit_id}/history request returns NotFound for a circuit
+    /// without history.
+    fn test_fetch_circuit_history_not_found() {
+        let (_shutdown_handle, _join_handle, bind_url) =
+            run_rest_api_on_open_port(vec![make_fetch_circuit_history_resource(
+                filled_splinter_state(),
+            )]);
+
+        let url = Url::parse(&format!(
+            "http://{}/admin/circuits/Circuit-not-valid/history",
+            bind_url,
+        ))
+        .expect("Failed to parse URL");
+        let req = Client::new()
+            .get(url)
+            .header("SplinterProtocolVersion", protocol::ADMIN_PROTOCOL_VERSION);
+        let resp = req.send().expect("Failed to perform request");
+
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    fn setup_splinter_state() -> SplinterState {
+        let mut storage = get_storage("memory", CircuitDirectory::new).unwrap();
+        let circuit_directory = storage.write().clone();
+        SplinterState::new("memory".to_string(), circuit_directory)
+    }
+
+    fn filled_splinter_state() -> SplinterState {
+        let service_definition =
+            ServiceDefinition::builder("service_1".to_string(), "type_a".to_string())
+                .with_allowed_nodes(vec!["node_1".to_string()])
+                .build();
+
+        let circuit = Circuit::builder()
+            .with_id("circuit_1".into())
+            .with_auth(AuthorizationType::Trust)
+            .with_members(vec!["node_1".to_string(), "node_2".to_string()])
+            .with_roster(vec![service_definition])
+            .with_persistence(PersistenceType::Any)
+            .with_durability(DurabilityType::NoDurability)
+            .with_routes(RouteType::Any)
+            .with_circuit_management_type("circuit_1_type".into())
+            .build()
+            .expect("Should have built a correct circuit");
+
+        let create = CircuitRevision::builder()
+            .with_circuit_hash("hash_1".into())
+            .with_proposal_type("Create".into())
+            .with_requester("0102".into())
+            .with_requester_node_id("node_1".into())
+            .with_votes(vec![RevisionVote::new(
+                "0304".into(),
+                "node_2".into(),
+                true,
+            )])
+            .with_committed_at(1_577_836_800)
+            .with_circuit(circuit)
+            .build()
+            .expect("Unable to build revision");
+
+        let destroy = CircuitRevision::builder()
+            .with_circuit_hash("hash_1".into())
+            .with_proposal_type("Destroy".into())
+            .with_requester("0304".into())
+            .with_requester_node_id("node_2".into())
+            .with_votes(vec![RevisionVote::new(
+                "0102".into(),
+                "node_1".into(),
+                true,
+            )])
+            .with_committed_at(1_577_840_400)
+            .build()
+            .expect("Unable to build revision");
+
+        let mut splinter_state = setup_splinter_state();
+        splinter_state
+            .add_circuit_revision("circuit_1".into(), create)
+            .expect("Unable to add revision");
+        splinter_state
+            .add_circuit_revision("circuit_1".into(), destroy)
+            .expect("Unable to add revision");
+
+        splinter_state
+    }
+
+    fn run_rest_api_on_open_port(
+        resources: Vec<Resource>,
+    ) -> (RestApiShutdownHandle, std::thread::JoinHandle<()>, String) {
+        (10000..20000)
+            .find_map(|port| {
+                let bind_url = format!("127.0.0.1:{}", port);
+                let result = RestApiBuilder::new()
+                    .with_bind(&bind_url)
+                    .add_resources(resources.clone())
+                    .build()
+                    .expect("Failed to build REST API")
+                    .run();
+                match result {
+                    Ok((shutdown_handle, join_handle)) => {
+                        Some((shutdown_handle, join_handle, bind_url))
+                    }
+                    Err(RestApiServerError::BindError(_)) => None,
+                    Err(err) => panic!("Failed to run REST API: {}", err),
+                }
+            })
+            .expect("No port available")
+    }
+}
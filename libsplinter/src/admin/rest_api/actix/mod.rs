@@ -16,6 +16,8 @@
 pub(super) mod circuits;
 #[cfg(feature = "circuit-read")]
 pub(super) mod circuits_circuit_id;
+#[cfg(feature = "circuit-read")]
+pub(super) mod circuits_circuit_id_history;
 #[cfg(feature = "proposal-read")]
 pub(super) mod proposals_read;
 #[cfg(feature = "proposal-read")]
@@ -16,6 +16,7 @@
 use actix_web::{error::BlockingError, web, Error, HttpRequest, HttpResponse};
 use futures::{future::IntoFuture, Future};
 use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::admin::messages::CircuitProposal;
 use crate::admin::service::proposal_store::ProposalStore;
@@ -24,7 +25,7 @@ use crate::rest_api::paging::{get_response_paging_info, DEFAULT_LIMIT, DEFAULT_O
 use crate::rest_api::{ErrorResponse, Method, ProtocolVersionRangeGuard, Resource};
 
 use super::super::error::ProposalListError;
-use super::super::resources::proposals_read::ListProposalsResponse;
+use super::super::resources::proposals_read::{ListProposalsResponse, ProposalResponse};
 
 pub fn make_list_proposals_resource<PS: ProposalStore + 'static>(proposal_store: PS) -> Resource {
     Resource::build("admin/proposals")
@@ -150,8 +151,15 @@ fn query_list_proposals<PS: ProposalStore + 'static>(
     })
     .then(|res| match res {
         Ok((circuits, link, limit, offset, total_count)) => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0);
             Ok(HttpResponse::Ok().json(ListProposalsResponse {
-                data: circuits,
+                data: circuits
+                    .into_iter()
+                    .map(|proposal| ProposalResponse::new(proposal, now))
+                    .collect(),
                 paging: get_response_paging_info(limit, offset, &link, total_count),
             }))
         }
@@ -28,6 +28,8 @@ use crate::rest_api::{Resource, RestResourceProvider};
 use self::actix::circuits::make_list_circuits_resource;
 #[cfg(all(feature = "circuit-read", feature = "rest-api-actix"))]
 use self::actix::circuits_circuit_id::make_fetch_circuit_resource;
+#[cfg(all(feature = "circuit-read", feature = "rest-api-actix"))]
+use self::actix::circuits_circuit_id_history::make_fetch_circuit_history_resource;
 #[cfg(all(feature = "proposal-read", feature = "rest-api-actix"))]
 use self::actix::proposals_read::make_list_proposals_resource;
 #[cfg(all(feature = "proposal-read", feature = "rest-api-actix"))]
@@ -63,6 +65,8 @@ impl RestResourceProvider for AdminService {
 /// * `GET /admin/circuits` - List circuits in Splinter's state
 /// * `GET /admin/circuits/{circuit_id}` - Fetch a specific circuit in Splinter's state by circuit
 ///   ID
+/// * `GET /admin/circuits/{circuit_id}/history` - Fetch the committed revisions of a specific
+///   circuit, oldest first
 #[derive(Clone)]
 pub struct CircuitResourceProvider<T: store::CircuitStore> {
     node_id: String,
@@ -85,6 +89,7 @@ impl<T: store::CircuitStore + 'static> RestResourceProvider for CircuitResourceP
             resources.append(&mut vec![
                 make_fetch_circuit_resource(self.store.clone()),
                 make_list_circuits_resource(self.store.clone()),
+                make_fetch_circuit_history_resource(self.store.clone()),
             ])
         }
         resources
@@ -0,0 +1,76 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::circuit::history::CircuitRevision;
+
+use super::circuits_circuit_id::CircuitResponse;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub(crate) struct CircuitHistoryResponse {
+    pub circuit_id: String,
+    pub data: Vec<CircuitRevisionResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub(crate) struct CircuitRevisionResponse {
+    pub revision: u64,
+    pub circuit_hash: String,
+    pub previous_hash: Option<String>,
+    pub proposal_type: String,
+    pub requester: String,
+    pub requester_node_id: String,
+    pub votes: Vec<RevisionVoteResponse>,
+    pub committed_at: u64,
+    pub circuit: Option<CircuitResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub(crate) struct RevisionVoteResponse {
+    pub public_key: String,
+    pub vote: String,
+    pub voter_node_id: String,
+}
+
+impl CircuitRevisionResponse {
+    pub fn new(circuit_id: &str, revision: &CircuitRevision) -> Self {
+        CircuitRevisionResponse {
+            revision: revision.revision(),
+            circuit_hash: revision.circuit_hash().to_string(),
+            previous_hash: revision.previous_hash().map(String::from),
+            proposal_type: revision.proposal_type().to_string(),
+            requester: revision.requester().to_string(),
+            requester_node_id: revision.requester_node_id().to_string(),
+            votes: revision
+                .votes()
+                .iter()
+                .map(|vote| RevisionVoteResponse {
+                    public_key: vote.public_key().to_string(),
+                    vote: if vote.accept() { "Accept" } else { "Reject" }.to_string(),
+                    voter_node_id: vote.voter_node_id().to_string(),
+                })
+                .collect(),
+            committed_at: revision.committed_at(),
+            circuit: revision.circuit().map(|circuit| CircuitResponse {
+                id: circuit_id.to_string(),
+                auth: circuit.auth().clone(),
+                members: circuit.members().to_vec(),
+                roster: circuit.roster().clone(),
+                persistence: circuit.persistence().clone(),
+                durability: circuit.durability().clone(),
+                routes: circuit.routes().clone(),
+                circuit_management_type: circuit.circuit_management_type().to_string(),
+            }),
+        }
+    }
+}
@@ -16,5 +16,7 @@
 pub(in super::super) mod circuits;
 #[cfg(feature = "circuit-read")]
 pub(in super::super) mod circuits_circuit_id;
+#[cfg(feature = "circuit-read")]
+pub(in super::super) mod circuits_circuit_id_history;
 #[cfg(feature = "proposal-read")]
 pub(in super::super) mod proposals_read;
@@ -17,6 +17,37 @@ use crate::rest_api::paging::Paging;
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub(crate) struct ListProposalsResponse {
-    pub data: Vec<CircuitProposal>,
+    pub data: Vec<ProposalResponse>,
     pub paging: Paging,
 }
+
+/// An open proposal, along with its state at the time of the request.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub(crate) struct ProposalResponse {
+    #[serde(flatten)]
+    pub proposal: CircuitProposal,
+    pub state: ProposalState,
+}
+
+impl ProposalResponse {
+    /// Wraps the proposal, determining its state at the given time, in seconds since the UNIX
+    /// epoch.
+    pub fn new(proposal: CircuitProposal, now: u64) -> Self {
+        let state = if proposal.expires_at != 0 && proposal.expires_at <= now {
+            ProposalState::Expired
+        } else {
+            ProposalState::Pending
+        };
+
+        Self { proposal, state }
+    }
+}
+
+/// The state of an open proposal. An expired proposal is reported until it is removed by the
+/// admin service.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ProposalState {
+    Pending,
+    Expired,
+}
@@ -0,0 +1,336 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rule-based voting on circuit proposals submitted to the admin service.
+//!
+//! An `AutoVoter` evaluates each proposal this node is asked to vote on against a list of
+//! `VotingRule`s. The first rule that matches the proposal decides the vote, which is signed with
+//! a key held by the node. The key must be registered to the node in the key registry, like the
+//! key of any other voter.
+
+use openssl::hash::{hash, MessageDigest};
+use protobuf::Message;
+
+use crate::hex::to_hex;
+use crate::protos::admin::{
+    CircuitManagementPayload, CircuitManagementPayload_Action, CircuitManagementPayload_Header,
+    CircuitProposalVote, CircuitProposalVote_Vote,
+};
+use crate::signing::{error::Error as SigningError, Signer};
+
+use super::error::{AdminSharedError, MarshallingError};
+use super::messages::CircuitProposal;
+
+/// The vote cast when a rule matches a proposal.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleVote {
+    Accept,
+    Reject,
+}
+
+/// A rule that decides the vote on the proposals it matches.
+///
+/// A rule matches a proposal if every criteria that is set matches; a rule without criteria
+/// matches every proposal.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct VotingRule {
+    /// The name of the rule, logged with every vote it decides
+    pub name: String,
+    pub vote: RuleVote,
+    /// The hex-encoded public keys the proposal's requester must be one of
+    #[serde(default)]
+    pub requester_keys: Vec<String>,
+    /// The nodes the proposal's requester node must be one of
+    #[serde(default)]
+    pub requester_nodes: Vec<String>,
+    /// The circuit management types the proposed circuit's type must be one of
+    #[serde(default)]
+    pub management_types: Vec<String>,
+    /// The service types every service of the proposed circuit must be one of
+    #[serde(default)]
+    pub service_types: Vec<String>,
+    /// The nodes every member of the proposed circuit must be one of
+    #[serde(default)]
+    pub members: Vec<String>,
+}
+
+impl VotingRule {
+    /// Returns whether the rule matches the given proposal.
+    pub fn matches(&self, proposal: &CircuitProposal) -> bool {
+        let circuit = &proposal.circuit;
+
+        (self.requester_keys.is_empty()
+            || self
+                .requester_keys
+                .iter()
+                .any(|key| key.eq_ignore_ascii_case(&to_hex(&proposal.requester))))
+            && (self.requester_nodes.is_empty()
+                || self.requester_nodes.contains(&proposal.requester_node_id))
+            && (self.management_types.is_empty()
+                || self
+                    .management_types
+                    .contains(&circuit.circuit_management_type))
+            && (self.service_types.is_empty()
+                || circuit
+                    .roster
+                    .iter()
+                    .all(|service| self.service_types.contains(&service.service_type)))
+            && (self.members.is_empty()
+                || circuit
+                    .members
+                    .iter()
+                    .all(|member| self.members.contains(&member.node_id)))
+    }
+}
+
+/// Votes on proposals according to a list of rules, using a key held by the node.
+pub struct AutoVoter {
+    node_id: String,
+    rules: Vec<VotingRule>,
+    signer: Box<dyn Signer + Send>,
+}
+
+impl AutoVoter {
+    /// Creates an `AutoVoter` that votes on behalf of the given node. Rules are evaluated in the
+    /// order given.
+    pub fn new(node_id: String, rules: Vec<VotingRule>, signer: Box<dyn Signer + Send>) -> Self {
+        AutoVoter {
+            node_id,
+            rules,
+            signer,
+        }
+    }
+
+    /// Returns the first rule that matches the given proposal, if any.
+    pub fn find_rule(&self, proposal: &CircuitProposal) -> Option<&VotingRule> {
+        self.rules.iter().find(|rule| rule.matches(proposal))
+    }
+
+    /// Evaluates the given proposal against the rules, returning a signed vote payload and the
+    /// rule that decided it, or `None` if no rule matched.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `AdminSharedError` if the vote cannot be serialized or signed.
+    pub fn evaluate(
+        &self,
+        proposal: &CircuitProposal,
+    ) -> Result<Option<(CircuitManagementPayload, &VotingRule)>, AdminSharedError> {
+        let rule = match self.find_rule(proposal) {
+            Some(rule) => rule,
+            None => return Ok(None),
+        };
+
+        let mut vote = CircuitProposalVote::new();
+        vote.set_circuit_id(proposal.circuit_id.clone());
+        vote.set_circuit_hash(proposal.circuit_hash.clone());
+        vote.set_vote(match rule.vote {
+            RuleVote::Accept => CircuitProposalVote_Vote::ACCEPT,
+            RuleVote::Reject => CircuitProposalVote_Vote::REJECT,
+        });
+
+        let vote_bytes = vote.write_to_bytes().map_err(MarshallingError::from)?;
+        let hashed_bytes = hash(MessageDigest::sha512(), &vote_bytes)
+            .map_err(|err| SigningError::SigningError(err.to_string()))?;
+
+        let mut header = CircuitManagementPayload_Header::new();
+        header.set_action(CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_VOTE);
+        header.set_payload_sha512(hashed_bytes.to_vec());
+        header.set_requester(self.signer.public_key().to_vec());
+        header.set_requester_node_id(self.node_id.clone());
+        let header_bytes = header.write_to_bytes().map_err(MarshallingError::from)?;
+
+        let signature = self.signer.sign(&header_bytes)?;
+
+        let mut payload = CircuitManagementPayload::new();
+        payload.set_header(header_bytes);
+        payload.set_signature(signature);
+        payload.set_circuit_proposal_vote(vote);
+
+        Ok(Some((payload, rule)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::admin::service::messages::{
+        ApprovalPolicy, AuthorizationType, CreateCircuit, DurabilityType, PersistenceType,
+        ProposalType, RouteType, SplinterNode, SplinterService,
+    };
+    use crate::signing::hash::HashSigner;
+
+    #[test]
+    // test that a rule matches a proposal only if each of its criteria matches
+    fn test_rule_matches() {
+        let proposal = test_proposal();
+
+        assert!(empty_rule("any", RuleVote::Accept).matches(&proposal));
+
+        let rule = VotingRule {
+            requester_nodes: vec!["acme-node-000".into()],
+            management_types: vec!["gameroom".into()],
+            service_types: vec!["scabbard".into()],
+            members: vec!["acme-node-000".into(), "bubba-node-000".into()],
+            requester_keys: vec!["0102AB".into()],
+            ..empty_rule("test", RuleVote::Accept)
+        };
+        assert!(rule.matches(&proposal));
+
+        let rule = VotingRule {
+            requester_nodes: vec!["bubba-node-000".into()],
+            ..empty_rule("test", RuleVote::Accept)
+        };
+        assert!(!rule.matches(&proposal));
+
+        let rule = VotingRule {
+            management_types: vec!["other".into()],
+            ..empty_rule("test", RuleVote::Accept)
+        };
+        assert!(!rule.matches(&proposal));
+
+        let rule = VotingRule {
+            service_types: vec!["other".into()],
+            ..empty_rule("test", RuleVote::Accept)
+        };
+        assert!(!rule.matches(&proposal));
+
+        let rule = VotingRule {
+            members: vec!["acme-node-000".into()],
+            ..empty_rule("test", RuleVote::Accept)
+        };
+        assert!(!rule.matches(&proposal));
+
+        let rule = VotingRule {
+            requester_keys: vec!["0303".into()],
+            ..empty_rule("test", RuleVote::Accept)
+        };
+        assert!(!rule.matches(&proposal));
+    }
+
+    #[test]
+    // test that the first matching rule decides the vote, and that no vote is cast if no rule
+    // matches
+    fn test_evaluate() {
+        let proposal = test_proposal();
+
+        let auto_voter = AutoVoter::new(
+            "bubba-node-000".into(),
+            vec![
+                VotingRule {
+                    management_types: vec!["other".into()],
+                    ..empty_rule("other", RuleVote::Accept)
+                },
+                VotingRule {
+                    requester_nodes: vec!["acme-node-000".into()],
+                    ..empty_rule("reject-acme", RuleVote::Reject)
+                },
+            ],
+            Box::new(HashSigner),
+        );
+
+        let (payload, rule) = auto_voter
+            .evaluate(&proposal)
+            .expect("Unable to evaluate proposal")
+            .expect("No rule matched");
+        assert_eq!(rule.name, "reject-acme");
+
+        let header =
+            protobuf::parse_from_bytes::<CircuitManagementPayload_Header>(payload.get_header())
+                .expect("Unable to parse header");
+        assert_eq!(
+            header.get_action(),
+            CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_VOTE
+        );
+        assert_eq!(header.get_requester_node_id(), "bubba-node-000");
+
+        let vote = payload.get_circuit_proposal_vote();
+        assert_eq!(vote.get_circuit_id(), "01234-ABCDE");
+        assert_eq!(vote.get_circuit_hash(), "8e066d41911817a42ab098eda35a2a2b");
+        assert_eq!(vote.get_vote(), CircuitProposalVote_Vote::REJECT);
+
+        let auto_voter = AutoVoter::new(
+            "bubba-node-000".into(),
+            vec![VotingRule {
+                management_types: vec!["other".into()],
+                ..empty_rule("other", RuleVote::Accept)
+            }],
+            Box::new(HashSigner),
+        );
+        assert!(auto_voter
+            .evaluate(&proposal)
+            .expect("Unable to evaluate proposal")
+            .is_none());
+    }
+
+    fn empty_rule(name: &str, vote: RuleVote) -> VotingRule {
+        VotingRule {
+            name: name.into(),
+            vote,
+            requester_keys: vec![],
+            requester_nodes: vec![],
+            management_types: vec![],
+            service_types: vec![],
+            members: vec![],
+        }
+    }
+
+    fn test_proposal() -> CircuitProposal {
+        CircuitProposal {
+            proposal_type: ProposalType::Create,
+            circuit_id: "01234-ABCDE".into(),
+            circuit_hash: "8e066d41911817a42ab098eda35a2a2b".into(),
+            circuit: CreateCircuit {
+                circuit_id: "01234-ABCDE".into(),
+                roster: vec![
+                    SplinterService {
+                        service_id: "ABCD".into(),
+                        service_type: "scabbard".into(),
+                        allowed_nodes: vec!["acme-node-000".into()],
+                        arguments: vec![],
+                    },
+                    SplinterService {
+                        service_id: "EFGH".into(),
+                        service_type: "scabbard".into(),
+                        allowed_nodes: vec!["bubba-node-000".into()],
+                        arguments: vec![],
+                    },
+                ],
+                members: vec![
+                    SplinterNode {
+                        node_id: "acme-node-000".into(),
+                        endpoint: "tcp://splinterd-node-acme:8044".into(),
+                    },
+                    SplinterNode {
+                        node_id: "bubba-node-000".into(),
+                        endpoint: "tcp://splinterd-node-bubba:8044".into(),
+                    },
+                ],
+                authorization_type: AuthorizationType::Trust,
+                persistence: PersistenceType::Any,
+                durability: DurabilityType::NoDurability,
+                routes: RouteType::Any,
+                circuit_management_type: "gameroom".into(),
+                application_metadata: vec![],
+            },
+            votes: vec![],
+            requester: vec![1, 2, 171],
+            requester_node_id: "acme-node-000".into(),
+            approval_policy: ApprovalPolicy::Unanimous,
+            expires_at: 0,
+        }
+    }
+}
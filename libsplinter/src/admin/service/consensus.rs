@@ -29,7 +29,9 @@ use crate::consensus::{
 };
 use crate::consensus::{ConsensusEngine, StartupState};
 use crate::hex::to_hex;
-use crate::protos::admin::{AdminMessage, AdminMessage_Type, ProposedCircuit};
+use crate::protos::admin::{
+    AdminMessage, AdminMessage_Type, CircuitProposal_ProposalType, ProposedCircuit,
+};
 use crate::protos::two_phase::RequiredVerifiers;
 use crate::service::ServiceError;
 
@@ -179,12 +181,13 @@ impl ProposalManager for AdminProposalManager {
             proposal.summary = expected_hash.as_bytes().into();
 
             let mut required_verifiers = RequiredVerifiers::new();
-            let mut verifiers = vec![];
-            let members = circuit_proposal.get_circuit_proposal().get_members();
-            for member in members {
-                verifiers.push(admin_service_id(member.get_node_id()).as_bytes().to_vec());
-            }
-            required_verifiers.set_verifiers(RepeatedField::from_vec(verifiers));
+            let verifiers = shared.current_consensus_verifiers().clone();
+            required_verifiers.set_verifiers(RepeatedField::from_vec(
+                verifiers
+                    .iter()
+                    .map(|verifier| verifier.as_bytes().to_vec())
+                    .collect(),
+            ));
             let required_verifiers_bytes = required_verifiers
                 .write_to_bytes()
                 .map_err(|err| ProposalManagerError::Internal(Box::new(err)))?;
@@ -200,16 +203,23 @@ impl ProposalManager for AdminProposalManager {
             proposed_circuit.set_circuit_payload(circuit_payload);
             proposed_circuit.set_expected_hash(expected_hash.as_bytes().into());
             proposed_circuit.set_required_verifiers(required_verifiers_bytes);
+            // a node being added to a circuit does not have the circuit yet, so it is sent along
+            if circuit_proposal.get_proposal_type() == CircuitProposal_ProposalType::ADD_NODE {
+                proposed_circuit.set_current_circuit(
+                    shared
+                        .get_circuit_proto(circuit_proposal.get_circuit_id())
+                        .map_err(|err| ProposalManagerError::Internal(Box::new(err)))?,
+                );
+            }
             let mut msg = AdminMessage::new();
             msg.set_message_type(AdminMessage_Type::PROPOSED_CIRCUIT);
             msg.set_proposed_circuit(proposed_circuit);
 
             let envelope_bytes = msg.write_to_bytes().unwrap();
-            for member in members {
-                if member.get_node_id() != shared.node_id() {
-                    network_sender
-                        .send(&admin_service_id(member.get_node_id()), &envelope_bytes)
-                        .unwrap();
+            let local_admin_service_id = admin_service_id(shared.node_id());
+            for verifier in verifiers {
+                if verifier != local_admin_service_id {
+                    network_sender.send(&verifier, &envelope_bytes).unwrap();
                 }
             }
 
@@ -297,13 +307,16 @@ impl ProposalManager for AdminProposalManager {
             .lock()
             .map_err(|_| ServiceError::PoisonedLock("the admin state lock was poisoned".into()))?;
 
-        shared
+        let (_, circuit_payload) = shared
             .remove_pending_consensus_proposals(id)
             .ok_or_else(|| ProposalManagerError::UnknownProposal(id.clone()))?;
 
         shared
             .rollback()
             .map_err(|err| ProposalManagerError::Internal(Box::new(err)))?;
+        shared
+            .discard_joining_circuit(&circuit_payload)
+            .map_err(|err| ProposalManagerError::Internal(Box::new(err)))?;
 
         info!("Rolled back proposal {}", id);
 
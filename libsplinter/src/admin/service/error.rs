@@ -17,7 +17,7 @@ use std::fmt;
 
 use crate::circuit;
 use crate::consensus::error::ProposalManagerError;
-use crate::orchestrator::{InitializeServiceError, ShutdownServiceError};
+use crate::orchestrator::{InitializeServiceError, ShutdownServiceError, UpdateServiceError};
 use crate::service::error::{ServiceError, ServiceSendError};
 use crate::signing;
 
@@ -85,6 +85,33 @@ impl From<ServiceError> for AdminServiceError {
     }
 }
 
+#[derive(Debug)]
+pub enum AdminServiceBuilderError {
+    /// Returned when a field required to build the admin service has not been set.
+    MissingRequiredField(String),
+    /// Returned when a value provided to build the admin service is not valid.
+    InvalidValue(String),
+}
+
+impl Error for AdminServiceBuilderError {}
+
+impl fmt::Display for AdminServiceBuilderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AdminServiceBuilderError::MissingRequiredField(field) => {
+                write!(f, "missing required field: {}", field)
+            }
+            AdminServiceBuilderError::InvalidValue(msg) => write!(f, "invalid value: {}", msg),
+        }
+    }
+}
+
+impl From<AdminServiceBuilderError> for ServiceError {
+    fn from(err: AdminServiceBuilderError) -> Self {
+        ServiceError::UnableToCreate(Box::new(err))
+    }
+}
+
 #[derive(Debug)]
 pub enum AdminSubscriberError {
     UnableToHandleEvent(String),
@@ -118,6 +145,7 @@ pub enum AdminSharedError {
     NoPendingChanges,
     ServiceInitializationFailed(InitializeServiceError),
     ServiceShutdownFailed(Vec<ShutdownServiceError>),
+    ServiceUpdateFailed(UpdateServiceError),
     ServiceSendError(ServiceSendError),
     UnknownAction(String),
     ValidationFailed(String),
@@ -142,6 +170,7 @@ impl Error for AdminSharedError {
             AdminSharedError::NoPendingChanges => None,
             AdminSharedError::ServiceInitializationFailed(err) => Some(err),
             AdminSharedError::ServiceShutdownFailed(_) => None,
+            AdminSharedError::ServiceUpdateFailed(err) => Some(err),
             AdminSharedError::ServiceSendError(err) => Some(err),
             AdminSharedError::UnknownAction(_) => None,
             AdminSharedError::ValidationFailed(_) => None,
@@ -175,6 +204,9 @@ impl fmt::Display for AdminSharedError {
                     .join(", ");
                 write!(f, "failed to shutdown services: {}", err_message)
             }
+            AdminSharedError::ServiceUpdateFailed(err) => {
+                write!(f, "failed to update service: {}", err)
+            }
             AdminSharedError::ServiceSendError(err) => {
                 write!(f, "failed to send service message: {}", err)
             }
@@ -194,6 +226,12 @@ impl fmt::Display for AdminSharedError {
     }
 }
 
+impl From<UpdateServiceError> for AdminSharedError {
+    fn from(err: UpdateServiceError) -> Self {
+        AdminSharedError::ServiceUpdateFailed(err)
+    }
+}
+
 impl From<InitializeServiceError> for AdminSharedError {
     fn from(err: InitializeServiceError) -> Self {
         AdminSharedError::ServiceInitializationFailed(err)
@@ -244,6 +282,21 @@ impl std::fmt::Display for AdminConsensusManagerError {
     }
 }
 
+#[derive(Debug)]
+pub struct ProposalSweeperError(pub Box<dyn Error + Send>);
+
+impl Error for ProposalSweeperError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.0)
+    }
+}
+
+impl std::fmt::Display for ProposalSweeperError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "admin proposal sweeper failed: {}", self.0)
+    }
+}
+
 #[derive(Debug)]
 pub enum AdminError {
     ConsensusFailed(AdminConsensusManagerError),
@@ -327,6 +380,7 @@ impl From<error::ProtobufError> for MarshallingError {
 
 #[derive(Debug)]
 pub enum OpenProposalError {
+    ReadError(String),
     WriteError(String),
     InvalidMessageFormat(MarshallingError),
 }
@@ -334,6 +388,7 @@ pub enum OpenProposalError {
 impl std::error::Error for OpenProposalError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
+            OpenProposalError::ReadError(_) => None,
             OpenProposalError::WriteError(_) => None,
             OpenProposalError::InvalidMessageFormat(err) => Some(err),
         }
@@ -343,6 +398,9 @@ impl std::error::Error for OpenProposalError {
 impl std::fmt::Display for OpenProposalError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
+            OpenProposalError::ReadError(msg) => {
+                write!(f, "Unable to read from persisted storage: {}", msg)
+            }
             OpenProposalError::WriteError(msg) => {
                 write!(f, "Unable to write to persisted storage: {}", msg)
             }
@@ -17,14 +17,14 @@ use std::collections::VecDeque;
 use std::error::Error;
 use std::fmt;
 use std::ops::Bound;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use crate::storage::sets::DurableOrderedSet;
 
 use super::messages::AdminServiceEvent;
 
 /// A simple entry for AdminServiceEvent values, marked with a timestamp
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct EventEntry {
     timestamp: SystemTime,
     event: AdminServiceEvent,
@@ -48,6 +48,21 @@ impl std::borrow::Borrow<SystemTime> for EventEntry {
     }
 }
 
+/// The number of events added to a mailbox between compactions.
+const COMPACTION_THRESHOLD: usize = 100;
+/// The longest time between compactions of a mailbox that events are added to.
+const COMPACTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Limits on the events kept by a mailbox. Events beyond either limit are removed, oldest first,
+/// when the mailbox is compacted.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EventRetention {
+    /// The maximum number of events to keep; if `None`, the number of events is not limited
+    pub max_events: Option<usize>,
+    /// How long to keep each event; if `None`, events are kept regardless of their age
+    pub max_age: Option<Duration>,
+}
+
 /// A Mailbox stores all admin services events that have occurred, ordered by a timestamp generated
 /// upon addition to the mailbox.
 ///
@@ -55,16 +70,34 @@ impl std::borrow::Borrow<SystemTime> for EventEntry {
 #[derive(Clone)]
 pub struct Mailbox {
     durable_set: Box<dyn DurableOrderedSet<EventEntry, SystemTime>>,
+    retention: EventRetention,
+    adds_since_compaction: usize,
+    last_compaction: SystemTime,
 }
 
 impl Mailbox {
     /// Constructs a new event mailbox with the given backing store.
     pub fn new(durable_set: Box<dyn DurableOrderedSet<EventEntry, SystemTime>>) -> Self {
-        Self { durable_set }
+        Self {
+            durable_set,
+            retention: EventRetention::default(),
+            adds_since_compaction: 0,
+            last_compaction: SystemTime::now(),
+        }
+    }
+
+    /// Sets the limits on the events kept by the mailbox. The limits are applied the next time
+    /// the mailbox is compacted.
+    pub fn set_retention(&mut self, retention: EventRetention) {
+        self.retention = retention;
     }
 
     /// Add an event to the mailbox.  Returns the recorded event time and a copy of the event.
     ///
+    /// The mailbox is compacted once every 100 added events, or once a minute, whichever comes
+    /// first; a mailbox with an event limit is also compacted every time that many events have
+    /// been added. Until then, the mailbox may hold more events than its limits allow.
+    ///
     /// # Errors
     ///
     /// Returns a MailboxError if there is an issue with the underlying storage set.
@@ -80,9 +113,75 @@ impl Mailbox {
             MailboxError::with_source("Unable to add event to storage", Box::new(err))
         })?;
 
+        self.adds_since_compaction += 1;
+        let threshold = self
+            .retention
+            .max_events
+            .map(|max_events| max_events.max(1).min(COMPACTION_THRESHOLD))
+            .unwrap_or(COMPACTION_THRESHOLD);
+        let interval_elapsed = entry
+            .timestamp
+            .duration_since(self.last_compaction)
+            .map(|elapsed| elapsed >= COMPACTION_INTERVAL)
+            .unwrap_or(false);
+        if self.adds_since_compaction >= threshold || interval_elapsed {
+            self.compact(entry.timestamp)?;
+        }
+
         Ok((entry.timestamp, entry.event))
     }
 
+    /// Removes the events that are beyond the mailbox's retention limits at the given time.
+    ///
+    /// # Errors
+    ///
+    /// Returns a MailboxError if there is an issue with the underlying storage set.
+    pub fn compact(&mut self, now: SystemTime) -> Result<(), MailboxError> {
+        self.adds_since_compaction = 0;
+        self.last_compaction = now;
+
+        if let Some(cutoff) = self
+            .retention
+            .max_age
+            .and_then(|max_age| now.checked_sub(max_age))
+        {
+            let expired = self
+                .durable_set
+                .range_iter((..&cutoff).into())
+                .map_err(|err| {
+                    MailboxError::with_source("Unable to read expired events", Box::new(err))
+                })?
+                .collect::<Vec<_>>();
+            for entry in expired {
+                self.remove(&entry)?;
+            }
+        }
+
+        if let Some(max_events) = self.retention.max_events {
+            let len =
+                self.durable_set.len().map_err(|err| {
+                    MailboxError::with_source("Unable to count events", Box::new(err))
+                })? as usize;
+            for _ in max_events..len {
+                let oldest = self.durable_set.first().map_err(|err| {
+                    MailboxError::with_source("Unable to read oldest event", Box::new(err))
+                })?;
+                match oldest {
+                    Some(entry) => self.remove(&entry)?,
+                    None => break,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn remove(&mut self, entry: &EventEntry) -> Result<(), MailboxError> {
+        self.durable_set.remove(entry).map(|_| ()).map_err(|err| {
+            MailboxError::with_source("Unable to remove event from storage", Box::new(err))
+        })
+    }
+
     /// Returns an iterator starting from the given timestamp.
     pub fn iter_since(&self, start_time: SystemTime) -> Result<MailboxIter, MailboxError> {
         MailboxIter::new(self.durable_set.clone(), start_time, SystemTime::now())
@@ -316,6 +415,66 @@ mod tests {
         );
     }
 
+    /// Add events to a mailbox with retention limits, and ensure that only the newest events
+    /// within the limits are kept once the mailbox is compacted, which happens every time as many
+    /// events as the event limit have been added.
+    #[test]
+    fn test_retention() {
+        let mut mailbox = Mailbox::new(DurableBTreeSet::new_boxed());
+        mailbox.set_retention(EventRetention {
+            max_events: Some(2),
+            max_age: None,
+        });
+
+        mailbox
+            .add(make_event("circuit_one", "default"))
+            .expect("Unable to add event");
+        mailbox
+            .add(make_event("circuit_two", "default"))
+            .expect("Unable to add event");
+        let (last_time, _) = mailbox
+            .add(make_event("circuit_three", "default"))
+            .expect("Unable to add event");
+
+        assert_eq!(
+            3,
+            mailbox
+                .iter_since(SystemTime::UNIX_EPOCH)
+                .expect("Unable to create an iterator")
+                .count()
+        );
+
+        mailbox
+            .add(make_event("circuit_four", "default"))
+            .expect("Unable to add event");
+
+        assert_eq!(
+            vec![
+                make_event("circuit_three", "default"),
+                make_event("circuit_four", "default"),
+            ],
+            mailbox
+                .iter_since(SystemTime::UNIX_EPOCH)
+                .expect("Unable to create an iterator")
+                .map(|(_, evt)| evt)
+                .collect::<Vec<_>>(),
+        );
+
+        mailbox.set_retention(EventRetention {
+            max_events: None,
+            max_age: Some(Duration::from_secs(60)),
+        });
+        mailbox
+            .compact(last_time + Duration::from_secs(180))
+            .expect("Unable to compact mailbox");
+
+        assert!(mailbox
+            .iter_since(SystemTime::UNIX_EPOCH)
+            .expect("Unable to create an iterator")
+            .next()
+            .is_none());
+    }
+
     fn make_event(circuit_id: &str, event_type: &str) -> AdminServiceEvent {
         AdminServiceEvent::ProposalSubmitted(CircuitProposal {
             proposal_type: ProposalType::Create,
@@ -335,6 +494,8 @@ mod tests {
             votes: vec![],
             requester: vec![],
             requester_node_id: "another-node".into(),
+            approval_policy: messages::ApprovalPolicy::Unanimous,
+            expires_at: 0,
         })
     }
 }
@@ -14,6 +14,8 @@
 
 pub mod builders;
 
+use std::collections::BTreeMap;
+
 use protobuf::{self, RepeatedField};
 
 use crate::hex::{as_hex, deserialize_hex};
@@ -52,6 +54,7 @@ impl CreateCircuit {
 
         let persistence = match proto.get_persistence() {
             admin::Circuit_PersistenceType::ANY_PERSISTENCE => PersistenceType::Any,
+            admin::Circuit_PersistenceType::PERSISTENT => PersistenceType::Persistent,
             admin::Circuit_PersistenceType::UNSET_PERSISTENCE_TYPE => {
                 return Err(MarshallingError::UnsetField(
                     "Unset persistence type".to_string(),
@@ -61,6 +64,7 @@ impl CreateCircuit {
 
         let durability = match proto.get_durability() {
             admin::Circuit_DurabilityType::NO_DURABILITY => DurabilityType::NoDurability,
+            admin::Circuit_DurabilityType::DURABLE => DurabilityType::Durable,
             admin::Circuit_DurabilityType::UNSET_DURABILITY_TYPE => {
                 return Err(MarshallingError::UnsetField(
                     "Unset durability type".to_string(),
@@ -70,6 +74,9 @@ impl CreateCircuit {
 
         let routes = match proto.get_routes() {
             admin::Circuit_RouteType::ANY_ROUTE => RouteType::Any,
+            admin::Circuit_RouteType::RELAYED_ROUTE => {
+                RouteType::Relayed(proto.take_relays().into_vec())
+            }
             admin::Circuit_RouteType::UNSET_ROUTE_TYPE => {
                 return Err(MarshallingError::UnsetField("Unset route type".to_string()));
             }
@@ -127,15 +134,25 @@ impl CreateCircuit {
             PersistenceType::Any => {
                 circuit.set_persistence(admin::Circuit_PersistenceType::ANY_PERSISTENCE);
             }
+            PersistenceType::Persistent => {
+                circuit.set_persistence(admin::Circuit_PersistenceType::PERSISTENT);
+            }
         };
         match self.durability {
             DurabilityType::NoDurability => {
                 circuit.set_durability(admin::Circuit_DurabilityType::NO_DURABILITY);
             }
+            DurabilityType::Durable => {
+                circuit.set_durability(admin::Circuit_DurabilityType::DURABLE);
+            }
         };
 
         match self.routes {
             RouteType::Any => circuit.set_routes(admin::Circuit_RouteType::ANY_ROUTE),
+            RouteType::Relayed(relays) => {
+                circuit.set_routes(admin::Circuit_RouteType::RELAYED_ROUTE);
+                circuit.set_relays(RepeatedField::from_vec(relays));
+            }
         };
 
         let mut create_request = CircuitCreateRequest::new();
@@ -153,6 +170,7 @@ pub enum AuthorizationType {
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub enum PersistenceType {
     Any,
+    Persistent,
 }
 
 impl Default for PersistenceType {
@@ -164,11 +182,14 @@ impl Default for PersistenceType {
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub enum DurabilityType {
     NoDurability,
+    Durable,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub enum RouteType {
     Any,
+    /// Messages are relayed through the given members, in order.
+    Relayed(Vec<String>),
 }
 
 impl Default for RouteType {
@@ -260,6 +281,12 @@ pub struct CircuitProposal {
     #[serde(deserialize_with = "deserialize_hex")]
     pub requester: Vec<u8>,
     pub requester_node_id: String,
+    #[serde(default)]
+    pub approval_policy: ApprovalPolicy,
+    /// The time, in seconds since the UNIX epoch, after which the proposal expires; 0 if the
+    /// proposal does not expire.
+    #[serde(default)]
+    pub expires_at: u64,
 }
 
 impl CircuitProposal {
@@ -270,6 +297,9 @@ impl CircuitProposal {
             admin::CircuitProposal_ProposalType::ADD_NODE => ProposalType::AddNode,
             admin::CircuitProposal_ProposalType::REMOVE_NODE => ProposalType::RemoveNode,
             admin::CircuitProposal_ProposalType::DESTROY => ProposalType::Destroy,
+            admin::CircuitProposal_ProposalType::UPDATE_APPLICATION_METADATA => {
+                ProposalType::UpdateApplicationMetadata
+            }
             admin::CircuitProposal_ProposalType::UNSET_PROPOSAL_TYPE => {
                 return Err(MarshallingError::UnsetField(
                     "Unset proposal type".to_string(),
@@ -291,6 +321,8 @@ impl CircuitProposal {
             votes,
             requester: proto.take_requester(),
             requester_node_id: proto.take_requester_node_id(),
+            approval_policy: ApprovalPolicy::from_proto(proto.take_approval_policy()),
+            expires_at: proto.get_expires_at(),
         })
     }
 
@@ -301,6 +333,9 @@ impl CircuitProposal {
             ProposalType::AddNode => admin::CircuitProposal_ProposalType::ADD_NODE,
             ProposalType::RemoveNode => admin::CircuitProposal_ProposalType::REMOVE_NODE,
             ProposalType::Destroy => admin::CircuitProposal_ProposalType::DESTROY,
+            ProposalType::UpdateApplicationMetadata => {
+                admin::CircuitProposal_ProposalType::UPDATE_APPLICATION_METADATA
+            }
         };
 
         let votes = self
@@ -319,6 +354,8 @@ impl CircuitProposal {
         proposal.set_votes(RepeatedField::from_vec(votes));
         proposal.set_requester(self.requester.to_vec());
         proposal.set_requester_node_id(self.requester_node_id.to_string());
+        proposal.set_approval_policy(self.approval_policy.into_proto());
+        proposal.set_expires_at(self.expires_at);
 
         Ok(proposal)
     }
@@ -331,6 +368,113 @@ pub enum ProposalType {
     AddNode,
     RemoveNode,
     Destroy,
+    UpdateApplicationMetadata,
+}
+
+/// The rule used to decide whether a proposal has been approved by the members of a circuit. The
+/// requester of a proposal counts as approving it.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ApprovalPolicy {
+    /// Every member must approve; a single rejection rejects the proposal.
+    Unanimous,
+    /// More than half of the members must approve.
+    Majority,
+    /// At least `threshold` members must approve.
+    Threshold { threshold: u32 },
+    /// The weights of the approving members must add up to at least `threshold`. Members without
+    /// a weight have a weight of 1.
+    Weighted {
+        threshold: u32,
+        #[serde(default)]
+        weights: BTreeMap<String, u32>,
+    },
+    /// The requester and at least one other member must approve.
+    RequesterPlusOne,
+}
+
+impl Default for ApprovalPolicy {
+    fn default() -> Self {
+        ApprovalPolicy::Unanimous
+    }
+}
+
+impl ApprovalPolicy {
+    pub fn from_proto(mut proto: admin::ApprovalPolicy) -> Self {
+        match proto.get_policy_type() {
+            admin::ApprovalPolicy_PolicyType::UNSET_POLICY_TYPE
+            | admin::ApprovalPolicy_PolicyType::UNANIMOUS => ApprovalPolicy::Unanimous,
+            admin::ApprovalPolicy_PolicyType::MAJORITY => ApprovalPolicy::Majority,
+            admin::ApprovalPolicy_PolicyType::THRESHOLD => ApprovalPolicy::Threshold {
+                threshold: proto.get_threshold(),
+            },
+            admin::ApprovalPolicy_PolicyType::WEIGHTED => ApprovalPolicy::Weighted {
+                threshold: proto.get_threshold(),
+                weights: proto
+                    .take_weights()
+                    .into_iter()
+                    .map(|mut weight| (weight.take_node_id(), weight.get_weight()))
+                    .collect(),
+            },
+            admin::ApprovalPolicy_PolicyType::REQUESTER_PLUS_ONE => {
+                ApprovalPolicy::RequesterPlusOne
+            }
+        }
+    }
+
+    pub fn into_proto(self) -> admin::ApprovalPolicy {
+        let mut policy = admin::ApprovalPolicy::new();
+        match self {
+            ApprovalPolicy::Unanimous => {
+                policy.set_policy_type(admin::ApprovalPolicy_PolicyType::UNANIMOUS)
+            }
+            ApprovalPolicy::Majority => {
+                policy.set_policy_type(admin::ApprovalPolicy_PolicyType::MAJORITY)
+            }
+            ApprovalPolicy::Threshold { threshold } => {
+                policy.set_policy_type(admin::ApprovalPolicy_PolicyType::THRESHOLD);
+                policy.set_threshold(threshold);
+            }
+            ApprovalPolicy::Weighted { threshold, weights } => {
+                policy.set_policy_type(admin::ApprovalPolicy_PolicyType::WEIGHTED);
+                policy.set_threshold(threshold);
+                policy.set_weights(
+                    weights
+                        .into_iter()
+                        .map(|(node_id, weight)| {
+                            let mut node_weight = admin::ApprovalPolicy_NodeWeight::new();
+                            node_weight.set_node_id(node_id);
+                            node_weight.set_weight(weight);
+                            node_weight
+                        })
+                        .collect(),
+                );
+            }
+            ApprovalPolicy::RequesterPlusOne => {
+                policy.set_policy_type(admin::ApprovalPolicy_PolicyType::REQUESTER_PLUS_ONE)
+            }
+        }
+        policy
+    }
+
+    /// Returns the threshold of a `Threshold` or `Weighted` policy.
+    pub fn threshold(&self) -> Option<u32> {
+        match self {
+            ApprovalPolicy::Threshold { threshold }
+            | ApprovalPolicy::Weighted { threshold, .. } => Some(*threshold),
+            _ => None,
+        }
+    }
+
+    /// Returns the weight of the given member's approval.
+    pub fn weight(&self, node_id: &str) -> u64 {
+        match self {
+            ApprovalPolicy::Weighted { weights, .. } => {
+                u64::from(weights.get(node_id).cloned().unwrap_or(1))
+            }
+            _ => 1,
+        }
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
@@ -424,16 +568,44 @@ pub enum AdminServiceEvent {
     ProposalAccepted((CircuitProposal, Vec<u8>)),
     ProposalRejected((CircuitProposal, Vec<u8>)),
     CircuitReady(CircuitProposal),
+    CircuitDestroyed(CircuitProposal),
+    /// A member node has left the circuit; contains the circuit and the abandoning node's id.
+    CircuitAbandoned((CreateCircuit, String)),
+    /// The application metadata of a circuit has changed; contains the accepted proposal, the
+    /// previous metadata and the new metadata.
+    ApplicationMetadataUpdated((CircuitProposal, Vec<u8>, Vec<u8>)),
+    /// A pending proposal has passed its expiration time and has been removed.
+    ProposalExpired(CircuitProposal),
+    /// A pending proposal has been withdrawn by its requester and has been removed.
+    ProposalWithdrawn(CircuitProposal),
 }
 
 impl AdminServiceEvent {
-    pub fn proposal(&self) -> &CircuitProposal {
+    /// Returns the proposal associated with the event, if the event was the result of a proposal.
+    /// `CircuitAbandoned` events are not the result of a proposal.
+    pub fn proposal(&self) -> Option<&CircuitProposal> {
+        match self {
+            AdminServiceEvent::ProposalSubmitted(proposal) => Some(proposal),
+            AdminServiceEvent::ProposalVote((proposal, _)) => Some(proposal),
+            AdminServiceEvent::ProposalAccepted((proposal, _)) => Some(proposal),
+            AdminServiceEvent::ProposalRejected((proposal, _)) => Some(proposal),
+            AdminServiceEvent::CircuitReady(proposal) => Some(proposal),
+            AdminServiceEvent::CircuitDestroyed(proposal) => Some(proposal),
+            AdminServiceEvent::ApplicationMetadataUpdated((proposal, _, _)) => Some(proposal),
+            AdminServiceEvent::ProposalExpired(proposal) => Some(proposal),
+            AdminServiceEvent::ProposalWithdrawn(proposal) => Some(proposal),
+            AdminServiceEvent::CircuitAbandoned(_) => None,
+        }
+    }
+
+    /// Returns the circuit management type of the circuit the event refers to.
+    pub fn circuit_management_type(&self) -> &str {
         match self {
-            AdminServiceEvent::ProposalSubmitted(proposal) => proposal,
-            AdminServiceEvent::ProposalVote((proposal, _)) => proposal,
-            AdminServiceEvent::ProposalAccepted((proposal, _)) => proposal,
-            AdminServiceEvent::ProposalRejected((proposal, _)) => proposal,
-            AdminServiceEvent::CircuitReady(proposal) => proposal,
+            AdminServiceEvent::CircuitAbandoned((circuit, _)) => &circuit.circuit_management_type,
+            _ => self
+                .proposal()
+                .map(|proposal| proposal.circuit.circuit_management_type.as_str())
+                .unwrap_or(""),
         }
     }
 }
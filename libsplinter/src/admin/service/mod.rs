@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod auto_vote;
 mod consensus;
 pub(crate) mod error;
 mod mailbox;
@@ -20,10 +21,12 @@ mod open_proposals;
 #[cfg(feature = "proposal-read")]
 pub(super) mod proposal_store;
 mod shared;
+mod sweeper;
 
 use std::any::Any;
-#[cfg(feature = "service-arg-validation")]
 use std::collections::HashMap;
+#[cfg(feature = "peer-manager")]
+use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
 
@@ -39,6 +42,8 @@ use crate::network::{
     peer::PeerConnector,
 };
 use crate::orchestrator::ServiceOrchestrator;
+#[cfg(feature = "peer-manager")]
+use crate::peer::{PeerManagerError, ProposalMembers};
 use crate::protos::admin::{AdminMessage, AdminMessage_Type, CircuitManagementPayload};
 #[cfg(feature = "service-arg-validation")]
 use crate::service::validation::ServiceArgValidator;
@@ -49,15 +54,24 @@ use crate::service::{
 use crate::signing::SignatureVerifier;
 
 use self::consensus::AdminConsensusManager;
-use self::error::{AdminError, Sha256Error};
+use self::error::{AdminError, AdminServiceBuilderError, Sha256Error};
 #[cfg(feature = "proposal-read")]
 use self::proposal_store::{AdminServiceProposals, ProposalStore};
 use self::shared::AdminServiceShared;
+use self::sweeper::ProposalSweeper;
 
+pub use self::auto_vote::{AutoVoter, RuleVote, VotingRule};
+pub use self::error::AdminServiceBuilderError;
 pub use self::error::AdminServiceError;
 pub use self::error::AdminSubscriberError;
+pub use self::mailbox::EventRetention;
+#[cfg(all(feature = "database", feature = "postgres"))]
+pub use self::open_proposals::run_postgres_migrations as run_proposals_postgres_migrations;
+#[cfg(feature = "sqlite")]
+pub use self::open_proposals::run_sqlite_migrations as run_proposals_sqlite_migrations;
 
 const DEFAULT_COORDINATOR_TIMEOUT_MILLIS: u64 = 30000; // 30 seconds
+const PROPOSAL_SWEEP_INTERVAL_MILLIS: u64 = 60000; // 1 minute
 
 pub trait AdminServiceEventSubscriber: Send {
     fn handle_event(
@@ -113,50 +127,242 @@ pub struct AdminService {
     /// The coordinator timeout for the two-phase commit consensus engine
     coordinator_timeout: Duration,
     consensus: Option<AdminConsensusManager>,
+    sweeper: Option<ProposalSweeper>,
 }
 
-impl AdminService {
-    #![allow(clippy::too_many_arguments)]
-    pub fn new(
-        node_id: &str,
-        orchestrator: ServiceOrchestrator,
-        #[cfg(feature = "service-arg-validation")] service_arg_validators: HashMap<
-            String,
-            Box<dyn ServiceArgValidator + Send>,
-        >,
-        peer_connector: PeerConnector,
-        authorization_inquistor: Box<dyn AuthorizationInquisitor>,
-        splinter_state: SplinterState,
+/// Builds an `AdminService`.
+///
+/// The node ID, orchestrator, peer connector, authorization inquisitor, splinter state, signature
+/// verifier, key registry, key permission manager and storage type are required.
+#[derive(Default)]
+pub struct AdminServiceBuilder {
+    node_id: Option<String>,
+    orchestrator: Option<ServiceOrchestrator>,
+    #[cfg(feature = "service-arg-validation")]
+    service_arg_validators: HashMap<String, Box<dyn ServiceArgValidator + Send>>,
+    peer_connector: Option<PeerConnector>,
+    authorization_inquisitor: Option<Box<dyn AuthorizationInquisitor>>,
+    splinter_state: Option<SplinterState>,
+    signature_verifier: Option<Box<dyn SignatureVerifier + Send>>,
+    key_registry: Option<Box<dyn KeyRegistry>>,
+    key_permission_manager: Option<Box<dyn KeyPermissionManager>>,
+    storage_type: Option<String>,
+    approval_policies: HashMap<String, messages::ApprovalPolicy>,
+    coordinator_timeout: Option<Duration>,
+    proposal_ttl: Option<Duration>,
+    auto_voter: Option<AutoVoter>,
+    event_retention: Option<EventRetention>,
+}
+
+impl AdminServiceBuilder {
+    /// Creates a builder with no fields set.
+    pub fn new() -> Self {
+        AdminServiceBuilder::default()
+    }
+
+    /// Sets the ID of the node the admin service runs on.
+    pub fn with_node_id(mut self, node_id: &str) -> Self {
+        self.node_id = Some(node_id.to_string());
+        self
+    }
+
+    /// Sets the orchestrator that runs the services of the node's circuits.
+    pub fn with_orchestrator(mut self, orchestrator: ServiceOrchestrator) -> Self {
+        self.orchestrator = Some(orchestrator);
+        self
+    }
+
+    /// Sets the validators of service arguments, by service type.
+    #[cfg(feature = "service-arg-validation")]
+    pub fn with_service_arg_validators(
+        mut self,
+        service_arg_validators: HashMap<String, Box<dyn ServiceArgValidator + Send>>,
+    ) -> Self {
+        self.service_arg_validators = service_arg_validators;
+        self
+    }
+
+    /// Sets the peer connector used to connect to the members of circuits.
+    pub fn with_peer_connector(mut self, peer_connector: PeerConnector) -> Self {
+        self.peer_connector = Some(peer_connector);
+        self
+    }
+
+    /// Sets the authorization inquisitor that reports when peers are authorized.
+    pub fn with_authorization_inquisitor(
+        mut self,
+        authorization_inquisitor: Box<dyn AuthorizationInquisitor>,
+    ) -> Self {
+        self.authorization_inquisitor = Some(authorization_inquisitor);
+        self
+    }
+
+    /// Sets the splinter state that circuits are committed to.
+    pub fn with_splinter_state(mut self, splinter_state: SplinterState) -> Self {
+        self.splinter_state = Some(splinter_state);
+        self
+    }
+
+    /// Sets the verifier of the signatures on circuit management payloads.
+    pub fn with_signature_verifier(
+        mut self,
         signature_verifier: Box<dyn SignatureVerifier + Send>,
-        key_registry: Box<dyn KeyRegistry>,
+    ) -> Self {
+        self.signature_verifier = Some(signature_verifier);
+        self
+    }
+
+    /// Sets the key registry used to look up the nodes of requesters.
+    pub fn with_key_registry(mut self, key_registry: Box<dyn KeyRegistry>) -> Self {
+        self.key_registry = Some(key_registry);
+        self
+    }
+
+    /// Sets the key permission manager used to check the permissions of requesters.
+    pub fn with_key_permission_manager(
+        mut self,
         key_permission_manager: Box<dyn KeyPermissionManager>,
-        storage_type: &str,
-        // The coordinator timeout for the two-phase commit consensus engine; if `None`, the
-        // default value will be used (30 seconds).
-        coordinator_timeout: Option<Duration>,
-    ) -> Result<Self, ServiceError> {
-        let coordinator_timeout = coordinator_timeout
+    ) -> Self {
+        self.key_permission_manager = Some(key_permission_manager);
+        self
+    }
+
+    /// Sets the type of storage used for proposals, such as "memory".
+    pub fn with_storage_type(mut self, storage_type: &str) -> Self {
+        self.storage_type = Some(storage_type.to_string());
+        self
+    }
+
+    /// Sets the approval policies of proposals submitted to this node, by circuit management
+    /// type; proposals of other types require unanimous approval. The threshold of a `Threshold`
+    /// or `Weighted` policy must be at least 1.
+    pub fn with_approval_policies(
+        mut self,
+        approval_policies: HashMap<String, messages::ApprovalPolicy>,
+    ) -> Self {
+        self.approval_policies = approval_policies;
+        self
+    }
+
+    /// Sets the coordinator timeout for the two-phase commit consensus engine. If it is not set,
+    /// the default value will be used (30 seconds).
+    pub fn with_coordinator_timeout(mut self, coordinator_timeout: Duration) -> Self {
+        self.coordinator_timeout = Some(coordinator_timeout);
+        self
+    }
+
+    /// Sets how long proposals submitted to this node remain open before they expire. If it is
+    /// not set, proposals do not expire.
+    pub fn with_proposal_ttl(mut self, proposal_ttl: Duration) -> Self {
+        self.proposal_ttl = Some(proposal_ttl);
+        self
+    }
+
+    /// Sets the auto voter that votes on incoming proposals according to its rules. If it is not
+    /// set, all votes must be submitted by hand.
+    pub fn with_auto_voter(mut self, auto_voter: AutoVoter) -> Self {
+        self.auto_voter = Some(auto_voter);
+        self
+    }
+
+    /// Sets the limits on the admin events kept for event subscribers. If they are not set, every
+    /// event is kept by persistent storage, and the most recent events are kept by memory storage.
+    pub fn with_event_retention(mut self, event_retention: EventRetention) -> Self {
+        self.event_retention = Some(event_retention);
+        self
+    }
+
+    /// Builds the admin service.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ServiceError` if a required field is not set or the service cannot be created.
+    pub fn build(self) -> Result<AdminService, ServiceError> {
+        let node_id = self
+            .node_id
+            .ok_or_else(|| AdminServiceBuilderError::MissingRequiredField("node_id".into()))?;
+        let orchestrator = self
+            .orchestrator
+            .ok_or_else(|| AdminServiceBuilderError::MissingRequiredField("orchestrator".into()))?;
+        #[cfg(feature = "service-arg-validation")]
+        let service_arg_validators = self.service_arg_validators;
+        let peer_connector = self.peer_connector.ok_or_else(|| {
+            AdminServiceBuilderError::MissingRequiredField("peer_connector".into())
+        })?;
+        let authorization_inquistor = self.authorization_inquisitor.ok_or_else(|| {
+            AdminServiceBuilderError::MissingRequiredField("authorization_inquisitor".into())
+        })?;
+        let splinter_state = self.splinter_state.ok_or_else(|| {
+            AdminServiceBuilderError::MissingRequiredField("splinter_state".into())
+        })?;
+        let signature_verifier = self.signature_verifier.ok_or_else(|| {
+            AdminServiceBuilderError::MissingRequiredField("signature_verifier".into())
+        })?;
+        let key_registry = self
+            .key_registry
+            .ok_or_else(|| AdminServiceBuilderError::MissingRequiredField("key_registry".into()))?;
+        let key_permission_manager = self.key_permission_manager.ok_or_else(|| {
+            AdminServiceBuilderError::MissingRequiredField("key_permission_manager".into())
+        })?;
+        let storage_type = self
+            .storage_type
+            .ok_or_else(|| AdminServiceBuilderError::MissingRequiredField("storage_type".into()))?;
+        let approval_policies = self.approval_policies;
+        if let Some((circuit_management_type, policy)) = approval_policies
+            .iter()
+            .find(|(_, policy)| policy.threshold() == Some(0))
+        {
+            return Err(AdminServiceBuilderError::InvalidValue(format!(
+                "approval policy {:?} of circuit management type {} has a threshold of 0",
+                policy, circuit_management_type
+            ))
+            .into());
+        }
+        let proposal_ttl = self.proposal_ttl;
+        let auto_voter = self.auto_voter;
+        let event_retention = self.event_retention;
+
+        let coordinator_timeout = self
+            .coordinator_timeout
             .unwrap_or_else(|| Duration::from_millis(DEFAULT_COORDINATOR_TIMEOUT_MILLIS));
 
-        let new_service = Self {
-            service_id: admin_service_id(node_id),
-            admin_service_shared: Arc::new(Mutex::new(AdminServiceShared::new(
-                node_id.to_string(),
-                orchestrator,
-                #[cfg(feature = "service-arg-validation")]
-                service_arg_validators,
-                peer_connector,
-                authorization_inquistor,
-                splinter_state,
-                signature_verifier,
-                key_registry,
-                key_permission_manager,
-                storage_type,
-            )?)),
+        let new_service = AdminService {
+            service_id: admin_service_id(&node_id),
+            admin_service_shared: Arc::new(Mutex::new(
+                AdminServiceShared::new(
+                    node_id,
+                    orchestrator,
+                    #[cfg(feature = "service-arg-validation")]
+                    service_arg_validators,
+                    peer_connector,
+                    authorization_inquistor,
+                    splinter_state,
+                    signature_verifier,
+                    key_registry,
+                    key_permission_manager,
+                    &storage_type,
+                )?
+                .with_approval_policies(approval_policies)
+                .with_proposal_ttl(proposal_ttl)
+                .with_auto_voter(auto_voter),
+            )),
             coordinator_timeout,
             consensus: None,
+            sweeper: None,
         };
 
+        if let Some(event_retention) = event_retention {
+            new_service
+                .admin_service_shared
+                .lock()
+                .map_err(|_| {
+                    ServiceError::PoisonedLock(
+                        "The lock was poisoned while creating the service".into(),
+                    )
+                })?
+                .set_event_retention(event_retention);
+        }
+
         let auth_callback_shared = Arc::clone(&new_service.admin_service_shared);
 
         new_service
@@ -189,6 +395,48 @@ impl AdminService {
 
         Ok(new_service)
     }
+}
+
+impl AdminService {
+    #![allow(clippy::too_many_arguments)]
+    pub fn new(
+        node_id: &str,
+        orchestrator: ServiceOrchestrator,
+        #[cfg(feature = "service-arg-validation")] service_arg_validators: HashMap<
+            String,
+            Box<dyn ServiceArgValidator + Send>,
+        >,
+        peer_connector: PeerConnector,
+        authorization_inquistor: Box<dyn AuthorizationInquisitor>,
+        splinter_state: SplinterState,
+        signature_verifier: Box<dyn SignatureVerifier + Send>,
+        key_registry: Box<dyn KeyRegistry>,
+        key_permission_manager: Box<dyn KeyPermissionManager>,
+        storage_type: &str,
+        // The coordinator timeout for the two-phase commit consensus engine; if `None`, the
+        // default value will be used (30 seconds).
+        coordinator_timeout: Option<Duration>,
+    ) -> Result<Self, ServiceError> {
+        let builder = AdminServiceBuilder::new()
+            .with_node_id(node_id)
+            .with_orchestrator(orchestrator)
+            .with_peer_connector(peer_connector)
+            .with_authorization_inquisitor(authorization_inquistor)
+            .with_splinter_state(splinter_state)
+            .with_signature_verifier(signature_verifier)
+            .with_key_registry(key_registry)
+            .with_key_permission_manager(key_permission_manager)
+            .with_storage_type(storage_type);
+        #[cfg(feature = "service-arg-validation")]
+        let builder = builder.with_service_arg_validators(service_arg_validators);
+
+        let builder = match coordinator_timeout {
+            Some(coordinator_timeout) => builder.with_coordinator_timeout(coordinator_timeout),
+            None => builder,
+        };
+
+        builder.build()
+    }
 
     pub fn commands(&self) -> impl AdminCommands + Clone {
         AdminServiceCommands {
@@ -200,6 +448,14 @@ impl AdminService {
     pub fn proposals(&self) -> impl ProposalStore {
         AdminServiceProposals::new(&self.admin_service_shared)
     }
+
+    /// Returns the members of the open proposals, whose connections the peer manager keeps.
+    #[cfg(feature = "peer-manager")]
+    pub fn proposal_members(&self) -> impl ProposalMembers {
+        AdminServiceCommands {
+            shared: Arc::clone(&self.admin_service_shared),
+        }
+    }
 }
 
 impl Service for AdminService {
@@ -240,6 +496,16 @@ impl Service for AdminService {
 
         self.consensus = Some(consensus);
 
+        // Setup the sweeper that removes expired proposals
+        let sweeper = ProposalSweeper::new(
+            self.service_id().into(),
+            self.admin_service_shared.clone(),
+            Duration::from_millis(PROPOSAL_SWEEP_INTERVAL_MILLIS),
+        )
+        .map_err(|err| ServiceStartError::Internal(Box::new(err)))?;
+
+        self.sweeper = Some(sweeper);
+
         self.admin_service_shared
             .lock()
             .map_err(|_| {
@@ -279,6 +545,13 @@ impl Service for AdminService {
             .shutdown()
             .map_err(|err| ServiceStopError::Internal(Box::new(err)))?;
 
+        // Shutdown the proposal sweeper
+        if let Some(sweeper) = self.sweeper.take() {
+            sweeper
+                .shutdown()
+                .map_err(|err| ServiceStopError::Internal(Box::new(err)))?;
+        }
+
         let mut admin_service_shared = self.admin_service_shared.lock().map_err(|_| {
             ServiceStopError::PoisonedLock("the admin shared lock was poisoned".into())
         })?;
@@ -337,6 +610,16 @@ impl Service for AdminService {
                     ServiceError::PoisonedLock("the admin shared lock was poisoned".into())
                 })?;
 
+                if proposed_circuit.has_current_circuit() {
+                    admin_service_shared
+                        .add_joining_circuit(
+                            proposed_circuit.get_current_circuit().clone(),
+                            circuit_payload,
+                            &message_context.sender,
+                        )
+                        .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+                }
+
                 admin_service_shared.handle_proposed_circuit(
                     proposal,
                     circuit_payload.clone(),
@@ -356,6 +639,19 @@ impl Service for AdminService {
                     .add_ready_member(circuit_id, member_node_id.into())
                     .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))
             }
+            AdminMessage_Type::ABANDONED_CIRCUIT => {
+                let abandoned_circuit = admin_message.get_abandoned_circuit();
+                let circuit_id = abandoned_circuit.get_circuit_id();
+                let member_node_id = abandoned_circuit.get_member_node_id();
+
+                let mut shared = self.admin_service_shared.lock().map_err(|_| {
+                    ServiceError::PoisonedLock("the admin shared lock was poisoned".into())
+                })?;
+
+                shared
+                    .handle_abandoned_circuit(circuit_id, member_node_id, &message_context.sender)
+                    .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))
+            }
             AdminMessage_Type::UNSET => Err(ServiceError::InvalidMessageFormat(Box::new(
                 AdminError::MessageTypeUnset,
             ))),
@@ -421,6 +717,19 @@ impl AdminCommands for AdminServiceCommands {
     }
 }
 
+#[cfg(feature = "peer-manager")]
+impl ProposalMembers for AdminServiceCommands {
+    fn proposal_members(&self) -> Result<HashSet<String>, PeerManagerError> {
+        Ok(self
+            .shared
+            .lock()
+            .map_err(|_| {
+                PeerManagerError::ProposalError("Admin shared lock was lock poisoned".into())
+            })?
+            .proposal_members())
+    }
+}
+
 pub fn admin_service_id(node_id: &str) -> String {
     format!("admin::{}", node_id)
 }
@@ -495,21 +804,18 @@ mod tests {
             .expect("failed to create orchestrator");
 
         let peer_connector = PeerConnector::new(network.clone(), Box::new(transport));
-        let mut admin_service = AdminService::new(
-            "test-node".into(),
-            orchestrator,
-            #[cfg(feature = "service-arg-validation")]
-            HashMap::new(),
-            peer_connector,
-            Box::new(MockAuthInquisitor),
-            state,
-            Box::new(HashVerifier),
-            Box::new(key_registry),
-            Box::new(AllowAllKeyPermissionManager),
-            "memory",
-            None,
-        )
-        .expect("Service should have been created correctly");
+        let mut admin_service = AdminServiceBuilder::new()
+            .with_node_id("test-node")
+            .with_orchestrator(orchestrator)
+            .with_peer_connector(peer_connector)
+            .with_authorization_inquisitor(Box::new(MockAuthInquisitor))
+            .with_splinter_state(state)
+            .with_signature_verifier(Box::new(HashVerifier))
+            .with_key_registry(Box::new(key_registry))
+            .with_key_permission_manager(Box::new(AllowAllKeyPermissionManager))
+            .with_storage_type("memory")
+            .build()
+            .expect("Service should have been created correctly");
 
         let (tx, rx) = channel();
         admin_service
@@ -0,0 +1,200 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A store for open circuit proposals backed by a SQL database.
+
+#[cfg(feature = "postgres")]
+pub mod postgres;
+mod schema;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+use std::collections::BTreeMap;
+
+use diesel::{
+    dsl::{delete, insert_into},
+    prelude::*,
+    r2d2::{ConnectionManager, Pool, PooledConnection},
+};
+
+use crate::database::create_pool;
+
+use super::super::error::OpenProposalError;
+use super::super::messages;
+use super::ProposalStore;
+use schema::circuit_proposals;
+
+/// Creates a proposal store for the database at the given URL. The URL's scheme selects the
+/// backend: `postgres://` or `sqlite://`.
+///
+/// The circuit proposal migrations must have been run against the database.
+pub fn create_proposal_store(
+    database_url: &str,
+) -> Result<Box<dyn ProposalStore>, OpenProposalError> {
+    #[cfg(feature = "postgres")]
+    {
+        if database_url.starts_with("postgres://") {
+            return Ok(Box::new(
+                DieselProposalStore::<diesel::pg::PgConnection>::from_url(database_url)?,
+            ));
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    {
+        if database_url.starts_with("sqlite://") {
+            return Ok(Box::new(DieselProposalStore::<
+                diesel::sqlite::SqliteConnection,
+            >::from_url(database_url)?));
+        }
+    }
+
+    Err(OpenProposalError::ReadError(format!(
+        "Unsupported database URL: {}",
+        database_url
+    )))
+}
+
+#[derive(Insertable, Queryable)]
+#[table_name = "circuit_proposals"]
+struct ProposalModel {
+    circuit_id: String,
+    /// The proposal, as JSON
+    proposal: String,
+}
+
+/// Persists open proposals to a database, using a pool of connections of type `C`.
+pub struct DieselProposalStore<C: diesel::Connection + 'static> {
+    pool: Pool<ConnectionManager<C>>,
+}
+
+impl<C: diesel::Connection + 'static> DieselProposalStore<C> {
+    pub fn new(pool: Pool<ConnectionManager<C>>) -> Self {
+        DieselProposalStore { pool }
+    }
+
+    fn from_url(database_url: &str) -> Result<Self, OpenProposalError> {
+        let pool = create_pool(database_url).map_err(|err| {
+            OpenProposalError::ReadError(format!("Unable to connect to proposal store: {}", err))
+        })?;
+        Ok(Self::new(pool))
+    }
+
+    fn connection(&self) -> Result<PooledConnection<ConnectionManager<C>>, OpenProposalError> {
+        self.pool.get().map_err(|err| {
+            OpenProposalError::ReadError(format!("Unable to get database connection: {}", err))
+        })
+    }
+}
+
+fn load_proposals<C>(
+    conn: &C,
+) -> Result<BTreeMap<String, messages::CircuitProposal>, OpenProposalError>
+where
+    C: diesel::Connection,
+    <C as diesel::Connection>::Backend: 'static,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+{
+    circuit_proposals::table
+        .load::<ProposalModel>(conn)
+        .map_err(|err| OpenProposalError::ReadError(format!("Failed to load proposals: {}", err)))?
+        .into_iter()
+        .map(|model| {
+            let proposal = serde_json::from_str(&model.proposal).map_err(|err| {
+                OpenProposalError::ReadError(format!("Invalid stored proposal: {}", err))
+            })?;
+            Ok((model.circuit_id, proposal))
+        })
+        .collect()
+}
+
+fn remove_proposal<C>(conn: &C, circuit_id: &str) -> Result<(), OpenProposalError>
+where
+    C: diesel::Connection,
+    <C as diesel::Connection>::Backend: 'static,
+{
+    delete(circuit_proposals::table.filter(circuit_proposals::circuit_id.eq(circuit_id)))
+        .execute(conn)
+        .map(|_| ())
+        .map_err(|err| OpenProposalError::WriteError(format!("Failed to remove proposal: {}", err)))
+}
+
+fn to_model(proposal: &messages::CircuitProposal) -> Result<ProposalModel, OpenProposalError> {
+    Ok(ProposalModel {
+        circuit_id: proposal.circuit_id.clone(),
+        proposal: serde_json::to_string(proposal).map_err(|err| {
+            OpenProposalError::WriteError(format!("Unable to serialize proposal: {}", err))
+        })?,
+    })
+}
+
+#[cfg(feature = "postgres")]
+impl ProposalStore for DieselProposalStore<diesel::pg::PgConnection> {
+    fn load_proposals(
+        &self,
+    ) -> Result<BTreeMap<String, messages::CircuitProposal>, OpenProposalError> {
+        load_proposals(&*self.connection()?)
+    }
+
+    fn add_proposal(&self, proposal: &messages::CircuitProposal) -> Result<(), OpenProposalError> {
+        let model = to_model(proposal)?;
+        let conn = self.connection()?;
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            delete(
+                circuit_proposals::table
+                    .filter(circuit_proposals::circuit_id.eq(&model.circuit_id)),
+            )
+            .execute(&*conn)?;
+            insert_into(circuit_proposals::table)
+                .values(&model)
+                .execute(&*conn)?;
+            Ok(())
+        })
+        .map_err(|err| OpenProposalError::WriteError(format!("Failed to add proposal: {}", err)))
+    }
+
+    fn remove_proposal(&self, circuit_id: &str) -> Result<(), OpenProposalError> {
+        remove_proposal(&*self.connection()?, circuit_id)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl ProposalStore for DieselProposalStore<diesel::sqlite::SqliteConnection> {
+    fn load_proposals(
+        &self,
+    ) -> Result<BTreeMap<String, messages::CircuitProposal>, OpenProposalError> {
+        load_proposals(&*self.connection()?)
+    }
+
+    fn add_proposal(&self, proposal: &messages::CircuitProposal) -> Result<(), OpenProposalError> {
+        let model = to_model(proposal)?;
+        let conn = self.connection()?;
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            delete(
+                circuit_proposals::table
+                    .filter(circuit_proposals::circuit_id.eq(&model.circuit_id)),
+            )
+            .execute(&*conn)?;
+            insert_into(circuit_proposals::table)
+                .values(&model)
+                .execute(&*conn)?;
+            Ok(())
+        })
+        .map_err(|err| OpenProposalError::WriteError(format!("Failed to add proposal: {}", err)))
+    }
+
+    fn remove_proposal(&self, circuit_id: &str) -> Result<(), OpenProposalError> {
+        remove_proposal(&*self.connection()?, circuit_id)
+    }
+}
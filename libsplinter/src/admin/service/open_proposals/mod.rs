@@ -12,7 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(feature = "database")]
+mod diesel;
+
 use std::collections::BTreeMap;
+#[cfg(feature = "peer-manager")]
+use std::collections::HashSet;
 
 use serde_derive::{Deserialize, Serialize};
 
@@ -22,18 +27,48 @@ use crate::storage::get_storage;
 use super::error::OpenProposalError;
 use super::messages;
 
+#[cfg(all(feature = "database", feature = "postgres"))]
+pub use self::diesel::postgres::run_migrations as run_postgres_migrations;
+#[cfg(feature = "sqlite")]
+pub use self::diesel::sqlite::run_migrations as run_sqlite_migrations;
+
+/// A backing store for open proposals that persists each change as it is made, rather than
+/// rewriting every proposal.
+pub trait ProposalStore: Send {
+    /// Loads the persisted proposals, keyed by circuit ID.
+    fn load_proposals(
+        &self,
+    ) -> Result<BTreeMap<String, messages::CircuitProposal>, OpenProposalError>;
+
+    /// Adds a proposal, replacing any existing proposal for the same circuit.
+    fn add_proposal(&self, proposal: &messages::CircuitProposal) -> Result<(), OpenProposalError>;
+
+    fn remove_proposal(&self, circuit_id: &str) -> Result<(), OpenProposalError>;
+}
+
 pub struct OpenProposals {
     storage_location: String,
     proposal_registry: ProposalRegistry,
+    // persists each change to the proposals, if they are not stored in a file
+    proposal_store: Option<Box<dyn ProposalStore>>,
 }
 
 impl OpenProposals {
-    /// Constructs a new OpenProposals using the given location.
+    /// Constructs a new OpenProposals using the given location. The location is either a file,
+    /// `memory`, or, with the `database` feature, a `postgres://` or `sqlite://` database URL.
     ///
     /// # Errors
     ///
     /// Returns a `OpenProposalError` if the persisted registry fails to load.
     pub fn new(storage_location: String) -> Result<Self, OpenProposalError> {
+        #[cfg(feature = "database")]
+        {
+            if crate::database::is_database_url(&storage_location) {
+                let proposal_store = diesel::create_proposal_store(&storage_location)?;
+                return Self::with_proposal_store(storage_location, proposal_store);
+            }
+        }
+
         let proposal_registry = get_storage(&storage_location, ProposalRegistry::default)
             .map_err(OpenProposalError::WriteError)?
             .read()
@@ -42,13 +77,54 @@ impl OpenProposals {
         Ok(Self {
             storage_location,
             proposal_registry,
+            proposal_store: None,
         })
     }
 
+    /// Constructs a new OpenProposals that loads the proposals from the given store, and
+    /// persists each change to the store as it is made.
+    pub fn with_proposal_store(
+        storage_location: String,
+        proposal_store: Box<dyn ProposalStore>,
+    ) -> Result<Self, OpenProposalError> {
+        let proposal_registry = ProposalRegistry {
+            proposals: proposal_store.load_proposals()?,
+        };
+
+        Ok(Self {
+            storage_location,
+            proposal_registry,
+            proposal_store: Some(proposal_store),
+        })
+    }
+
+    /// Adds the proposals of the given `OpenProposals` that are not already open, returning the
+    /// number of proposals added. This is used to migrate proposals from one kind of storage to
+    /// another.
+    pub fn import_proposals(&mut self, other: &OpenProposals) -> Result<usize, OpenProposalError> {
+        let mut imported = 0;
+        for (circuit_id, proposal) in &other.proposal_registry.proposals {
+            if !self.has_proposal(circuit_id) {
+                self.add_proposal(proposal.clone().into_proto()?)?;
+                imported += 1;
+            }
+        }
+        Ok(imported)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proposal_registry.proposals.is_empty()
+    }
+
     pub fn add_proposal(
         &mut self,
         circuit_proposal: CircuitProposal,
     ) -> Result<Option<CircuitProposal>, OpenProposalError> {
+        if let Some(proposal_store) = &self.proposal_store {
+            proposal_store.add_proposal(&messages::CircuitProposal::from_proto(
+                circuit_proposal.clone(),
+            )?)?;
+        }
         let proposal = self.proposal_registry.add_proposal(circuit_proposal);
         self.write_open_proposals()?;
 
@@ -59,6 +135,9 @@ impl OpenProposals {
         &mut self,
         circuit_id: &str,
     ) -> Result<Option<CircuitProposal>, OpenProposalError> {
+        if let Some(proposal_store) = &self.proposal_store {
+            proposal_store.remove_proposal(circuit_id)?;
+        }
         let proposal = self.proposal_registry.remove_proposal(circuit_id);
         self.write_open_proposals()?;
 
@@ -77,15 +156,32 @@ impl OpenProposals {
         self.proposal_registry.get_proposals()
     }
 
+    /// Returns the proposals that expire at or before the given time, in seconds since the UNIX
+    /// epoch.
+    pub fn expired_proposals(&self, now: u64) -> Result<Vec<CircuitProposal>, OpenProposalError> {
+        self.proposal_registry.expired_proposals(now)
+    }
+
     pub fn has_proposal(&self, circuit_id: &str) -> bool {
         self.proposal_registry.has_proposal(circuit_id)
     }
 
+    /// Returns the node IDs of the members of every open proposal.
+    #[cfg(feature = "peer-manager")]
+    pub fn proposal_members(&self) -> HashSet<String> {
+        self.proposal_registry.proposal_members()
+    }
+
     pub fn storage_location(&self) -> &str {
         &self.storage_location
     }
 
     fn write_open_proposals(&self) -> Result<(), OpenProposalError> {
+        // Changes have already been persisted if the proposals are backed by a proposal store
+        if self.proposal_store.is_some() {
+            return Ok(());
+        }
+
         // Replace stored key_registry with the current key registry
         let mut storage = get_storage(self.storage_location(), || self.proposal_registry.clone())
             .map_err(OpenProposalError::WriteError)?;
@@ -144,9 +240,26 @@ impl ProposalRegistry {
         }
     }
 
+    pub fn expired_proposals(&self, now: u64) -> Result<Vec<CircuitProposal>, OpenProposalError> {
+        self.proposals
+            .values()
+            .filter(|proposal| proposal.expires_at != 0 && proposal.expires_at <= now)
+            .map(|proposal| Ok(proposal.clone().into_proto()?))
+            .collect()
+    }
+
     pub fn has_proposal(&self, circuit_id: &str) -> bool {
         self.proposals.contains_key(circuit_id)
     }
+
+    #[cfg(feature = "peer-manager")]
+    pub fn proposal_members(&self) -> HashSet<String> {
+        self.proposals
+            .values()
+            .flat_map(|proposal| proposal.circuit.members.iter())
+            .map(|member| member.node_id.clone())
+            .collect()
+    }
 }
 
 /// An iterator over CircuitProposals and the time that each occurred.
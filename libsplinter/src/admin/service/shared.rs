@@ -16,13 +16,16 @@ use std::cell::RefCell;
 use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::env;
 use std::iter::FromIterator;
-use std::time::SystemTime;
+#[cfg(feature = "lmdb-sets")]
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use protobuf::{Message, RepeatedField};
 use std::sync::mpsc::Sender;
 
 use crate::circuit::SplinterState;
 use crate::circuit::{
+    history::{CircuitRevision, RevisionVote},
     service::SplinterNode as StateNode,
     service::{Service, ServiceId},
     AuthorizationType, Circuit as StateCircuit, DurabilityType, PersistenceType, RouteType,
@@ -36,14 +39,15 @@ use crate::network::{
     peer::PeerConnector,
 };
 use crate::orchestrator::{ServiceDefinition, ServiceOrchestrator, ShutdownServiceError};
-#[cfg(feature = "service-arg-validation")]
-use crate::protos::admin::SplinterService;
 use crate::protos::admin::{
-    AdminMessage, AdminMessage_Type, Circuit, CircuitManagementPayload,
+    AbandonedCircuit, AdminMessage, AdminMessage_Type, Circuit, CircuitManagementPayload,
     CircuitManagementPayload_Action, CircuitManagementPayload_Header, CircuitProposal,
-    CircuitProposalVote, CircuitProposalVote_Vote, CircuitProposal_ProposalType,
-    CircuitProposal_VoteRecord, Circuit_AuthorizationType, Circuit_DurabilityType,
-    Circuit_PersistenceType, Circuit_RouteType, MemberReady, SplinterNode,
+    CircuitProposalVote, CircuitProposalVote_Vote, CircuitProposalWithdraw,
+    CircuitProposal_ProposalType, CircuitProposal_VoteRecord, CircuitUpdateAddNodeRequest,
+    CircuitUpdateApplicationMetadataRequest, CircuitUpdateRemoveNodeRequest,
+    CircuitUpdateRosterRequest, Circuit_AuthorizationType, Circuit_DurabilityType,
+    Circuit_PersistenceType, Circuit_RouteType, MemberReady, SplinterNode, SplinterService,
+    SplinterService_Argument,
 };
 use crate::service::error::ServiceError;
 #[cfg(feature = "service-arg-validation")]
@@ -51,10 +55,17 @@ use crate::service::validation::ServiceArgValidator;
 
 use crate::service::ServiceNetworkSender;
 use crate::signing::SignatureVerifier;
+#[cfg(feature = "database")]
+use crate::storage::sets::diesel::create_ordered_set;
+#[cfg(feature = "lmdb-sets")]
+use crate::storage::sets::lmdb::LmdbOrderedSet;
 use crate::storage::sets::mem::DurableBTreeSet;
 
+use super::auto_vote::AutoVoter;
+#[cfg(feature = "database")]
+use super::error::OpenProposalError;
 use super::error::{AdminSharedError, MarshallingError};
-use super::mailbox::Mailbox;
+use super::mailbox::{EventRetention, Mailbox};
 use super::messages;
 use super::open_proposals::OpenProposals;
 #[cfg(feature = "proposal-read")]
@@ -67,6 +78,15 @@ static VOTER_ROLE: &str = "voter";
 static PROPOSER_ROLE: &str = "proposer";
 
 const DEFAULT_IN_MEMORY_EVENT_LIMIT: usize = 100;
+// The file, in the state directory, of the admin events of file-based storage
+#[cfg(feature = "lmdb-sets")]
+const ADMIN_EVENTS_LMDB_FILE: &str = "admin_events.lmdb";
+#[cfg(feature = "database")]
+const ADMIN_EVENTS_SET_NAME: &str = "admin_events";
+
+// The furthest away a proposal's expiration time may be, unless this node's own proposal TTL is
+// longer
+const MAX_PROPOSAL_TTL_SECS: u64 = 2_592_000; // 30 days
 
 pub enum PayloadType {
     Circuit(CircuitManagementPayload),
@@ -79,6 +99,7 @@ pub struct UnpeeredPendingPayload {
     pub message_sender: String,
 }
 
+#[derive(Debug, PartialEq)]
 enum CircuitProposalStatus {
     Accepted,
     Rejected,
@@ -153,6 +174,9 @@ pub struct AdminServiceShared {
     // the list of circuit that have been committed to splinter state but whose services haven't
     // been initialized
     uninitialized_circuits: HashMap<String, UninitializedCircuit>,
+    // the current definitions of existing circuits that this node has been proposed to join,
+    // as received from a member of the circuit
+    joining_circuits: HashMap<String, Circuit>,
     // orchestrator used to initialize and shutdown services
     orchestrator: ServiceOrchestrator,
     // map of service arg validators, by service type
@@ -189,6 +213,14 @@ pub struct AdminServiceShared {
     signature_verifier: Box<dyn SignatureVerifier + Send>,
     key_registry: Box<dyn KeyRegistry>,
     key_permission_manager: Box<dyn KeyPermissionManager>,
+    // the approval policies of proposals submitted to this node, by circuit management type;
+    // proposals of other types require unanimous approval
+    approval_policies: HashMap<String, messages::ApprovalPolicy>,
+    // how long proposals submitted to this node remain open before they expire; proposals do not
+    // expire if this is not set
+    proposal_ttl: Option<Duration>,
+    // votes on proposals this node is asked to vote on, according to the configured rules
+    auto_voter: Option<AutoVoter>,
     proposal_sender: Option<Sender<ProposalUpdate>>,
 }
 
@@ -220,20 +252,53 @@ impl AdminServiceShared {
         let storage_location = match storage_type {
             "yaml" => format!("{}{}", location, "/circuit_proposals.yaml"),
             "memory" => "memory".to_string(),
+            #[cfg(feature = "database")]
+            database_url if crate::database::is_database_url(database_url) => {
+                database_url.to_string()
+            }
             _ => panic!("Storage type is not supported: {}", storage_type),
         };
 
-        let open_proposals = OpenProposals::new(storage_location)
+        #[allow(unused_mut)]
+        let mut open_proposals = OpenProposals::new(storage_location)
             .map_err(|err| ServiceError::UnableToCreate(Box::new(err)))?;
 
-        let event_mailbox = Mailbox::new(DurableBTreeSet::new_boxed_with_bound(
-            std::num::NonZeroUsize::new(DEFAULT_IN_MEMORY_EVENT_LIMIT).unwrap(),
-        ));
+        #[cfg(feature = "database")]
+        {
+            if crate::database::is_database_url(storage_type) {
+                import_yaml_proposals(
+                    &mut open_proposals,
+                    &format!("{}{}", location, "/circuit_proposals.yaml"),
+                )
+                .map_err(|err| ServiceError::UnableToCreate(Box::new(err)))?;
+            }
+        }
+
+        // Events are kept in the database with database storage and, if LMDB sets are enabled, in
+        // an LMDB file in the state directory with yaml storage; otherwise they are kept in memory
+        let event_set = match storage_type {
+            #[cfg(feature = "lmdb-sets")]
+            "yaml" => LmdbOrderedSet::new_boxed(
+                Path::new(&format!("{}/{}", location, ADMIN_EVENTS_LMDB_FILE)),
+                None,
+            )
+            .map_err(|err| ServiceError::UnableToCreate(Box::new(err)))?,
+            #[cfg(feature = "database")]
+            database_url if crate::database::is_database_url(database_url) => {
+                create_ordered_set(database_url, ADMIN_EVENTS_SET_NAME)
+                    .map_err(|err| ServiceError::UnableToCreate(Box::new(err)))?
+            }
+            _ => DurableBTreeSet::new_boxed_with_bound(
+                std::num::NonZeroUsize::new(DEFAULT_IN_MEMORY_EVENT_LIMIT).unwrap(),
+            ),
+        };
+        let event_mailbox = Mailbox::new(event_set);
         Ok(AdminServiceShared {
             node_id,
             network_sender: None,
             open_proposals,
             uninitialized_circuits: Default::default(),
+            joining_circuits: HashMap::new(),
             orchestrator,
             #[cfg(feature = "service-arg-validation")]
             service_arg_validators,
@@ -251,10 +316,35 @@ impl AdminServiceShared {
             signature_verifier,
             key_registry,
             key_permission_manager,
+            approval_policies: HashMap::new(),
+            proposal_ttl: None,
+            auto_voter: None,
             proposal_sender: None,
         })
     }
 
+    /// Sets the approval policies of proposals submitted to this node, by circuit management
+    /// type.
+    pub fn with_approval_policies(
+        mut self,
+        approval_policies: HashMap<String, messages::ApprovalPolicy>,
+    ) -> Self {
+        self.approval_policies = approval_policies;
+        self
+    }
+
+    /// Sets how long proposals submitted to this node remain open before they expire.
+    pub fn with_proposal_ttl(mut self, proposal_ttl: Option<Duration>) -> Self {
+        self.proposal_ttl = proposal_ttl;
+        self
+    }
+
+    /// Sets the auto voter used to vote on proposals this node is asked to vote on.
+    pub fn with_auto_voter(mut self, auto_voter: Option<AutoVoter>) -> Self {
+        self.auto_voter = auto_voter;
+        self
+    }
+
     pub fn node_id(&self) -> &str {
         &self.node_id
     }
@@ -275,6 +365,10 @@ impl AdminServiceShared {
         self.proposal_sender = proposal_sender;
     }
 
+    pub fn set_event_retention(&mut self, retention: EventRetention) {
+        self.event_mailbox.set_retention(retention);
+    }
+
     pub fn pop_pending_circuit_payload(&mut self) -> Option<CircuitManagementPayload> {
         self.pending_circuit_payloads.pop_front()
     }
@@ -310,6 +404,11 @@ impl AdminServiceShared {
             Some(circuit_proposal_context) => {
                 let circuit_proposal = circuit_proposal_context.circuit_proposal;
                 let action = circuit_proposal_context.action;
+                // a withdrawal removes the proposal regardless of the votes it has received
+                if action == CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_WITHDRAW {
+                    return self.commit_withdraw_proposal(circuit_proposal);
+                }
+
                 let circuit_id = circuit_proposal.get_circuit_id();
                 let mgmt_type = circuit_proposal
                     .get_circuit_proposal()
@@ -318,45 +417,36 @@ impl AdminServiceShared {
 
                 match self.check_approved(&circuit_proposal) {
                     Ok(CircuitProposalStatus::Accepted) => {
-                        // commit new circuit
-                        let circuit = circuit_proposal.get_circuit_proposal();
-                        self.update_splinter_state(circuit)?;
-                        // remove approved proposal
-                        self.remove_proposal(&circuit_id)?;
-                        // send message about circuit acceptance
-
-                        let circuit_proposal_proto =
-                            messages::CircuitProposal::from_proto(circuit_proposal.clone())
-                                .map_err(AdminSharedError::InvalidMessageFormat)?;
-                        let event = messages::AdminServiceEvent::ProposalAccepted((
-                            circuit_proposal_proto,
-                            circuit_proposal_context.signer_public_key,
-                        ));
-                        self.send_event(&mgmt_type, event);
-
-                        // send MEMBER_READY message to all other members' admin services
-                        if let Some(ref network_sender) = self.network_sender {
-                            let mut member_ready = MemberReady::new();
-                            member_ready.set_circuit_id(circuit.circuit_id.clone());
-                            member_ready.set_member_node_id(self.node_id.clone());
-                            let mut msg = AdminMessage::new();
-                            msg.set_message_type(AdminMessage_Type::MEMBER_READY);
-                            msg.set_member_ready(member_ready);
-
-                            let envelope_bytes =
-                                msg.write_to_bytes().map_err(MarshallingError::from)?;
-                            for member in circuit.members.iter() {
-                                if member.get_node_id() != self.node_id {
-                                    network_sender.send(
-                                        &admin_service_id(member.get_node_id()),
-                                        &envelope_bytes,
-                                    )?;
-                                }
-                            }
-                        }
-
-                        // add circuit as pending initialization
-                        self.add_uninitialized_circuit(circuit_proposal.clone())
+                        let accepted_proposal = circuit_proposal.clone();
+                        match circuit_proposal.get_proposal_type() {
+                            CircuitProposal_ProposalType::DESTROY => self.commit_destroy_circuit(
+                                circuit_proposal,
+                                circuit_proposal_context.signer_public_key,
+                            ),
+                            CircuitProposal_ProposalType::UPDATE_ROSTER => self
+                                .commit_update_roster(
+                                    circuit_proposal,
+                                    circuit_proposal_context.signer_public_key,
+                                ),
+                            CircuitProposal_ProposalType::ADD_NODE => self.commit_add_node(
+                                circuit_proposal,
+                                circuit_proposal_context.signer_public_key,
+                            ),
+                            CircuitProposal_ProposalType::REMOVE_NODE => self.commit_remove_node(
+                                circuit_proposal,
+                                circuit_proposal_context.signer_public_key,
+                            ),
+                            CircuitProposal_ProposalType::UPDATE_APPLICATION_METADATA => self
+                                .commit_update_application_metadata(
+                                    circuit_proposal,
+                                    circuit_proposal_context.signer_public_key,
+                                ),
+                            _ => self.commit_create_circuit(
+                                circuit_proposal,
+                                circuit_proposal_context.signer_public_key,
+                            ),
+                        }?;
+                        self.add_circuit_revision(&accepted_proposal)
                     }
                     Ok(CircuitProposalStatus::Pending) => {
                         self.add_proposal(circuit_proposal.clone())?;
@@ -365,13 +455,44 @@ impl AdminServiceShared {
                             CircuitManagementPayload_Action::CIRCUIT_CREATE_REQUEST => {
                                 // notify registered application authorization handlers of the
                                 // committed circuit proposal
-                                let event = messages::AdminServiceEvent::ProposalSubmitted(
+                                let proposal =
                                     messages::CircuitProposal::from_proto(circuit_proposal.clone())
-                                        .map_err(AdminSharedError::InvalidMessageFormat)?,
+                                        .map_err(AdminSharedError::InvalidMessageFormat)?;
+                                self.send_event(
+                                    &mgmt_type,
+                                    messages::AdminServiceEvent::ProposalSubmitted(
+                                        proposal.clone(),
+                                    ),
                                 );
-                                self.send_event(&mgmt_type, event);
 
                                 info!("committed changes for new circuit proposal {}", circuit_id);
+                                self.auto_vote(&proposal);
+                                Ok(())
+                            }
+
+                            CircuitManagementPayload_Action::CIRCUIT_DESTROY_REQUEST
+                            | CircuitManagementPayload_Action::CIRCUIT_UPDATE_ROSTER_REQUEST
+                            | CircuitManagementPayload_Action::CIRCUIT_UPDATE_ADD_NODE
+                            | CircuitManagementPayload_Action::CIRCUIT_UPDATE_REMOVE_NODE
+                            | CircuitManagementPayload_Action::CIRCUIT_UPDATE_APPLICATION_METADATA_REQUEST => {
+                                // notify registered application authorization handlers of the
+                                // committed change proposal
+                                let proposal =
+                                    messages::CircuitProposal::from_proto(circuit_proposal.clone())
+                                        .map_err(AdminSharedError::InvalidMessageFormat)?;
+                                self.send_event(
+                                    &mgmt_type,
+                                    messages::AdminServiceEvent::ProposalSubmitted(
+                                        proposal.clone(),
+                                    ),
+                                );
+
+                                info!(
+                                    "committed changes for {:?} proposal of circuit {}",
+                                    circuit_proposal.get_proposal_type(),
+                                    circuit_id
+                                );
+                                self.auto_vote(&proposal);
                                 Ok(())
                             }
 
@@ -399,6 +520,7 @@ impl AdminServiceShared {
                     Ok(CircuitProposalStatus::Rejected) => {
                         // remove circuit
                         self.remove_proposal(&circuit_id)?;
+                        self.joining_circuits.remove(circuit_id);
 
                         let circuit_proposal_proto =
                             messages::CircuitProposal::from_proto(circuit_proposal.clone())
@@ -419,6 +541,427 @@ impl AdminServiceShared {
         }
     }
 
+    /// Commits the withdrawal of a pending proposal by its requester: the proposal is removed and
+    /// registered application authorization handlers are notified.
+    fn commit_withdraw_proposal(
+        &mut self,
+        circuit_proposal: CircuitProposal,
+    ) -> Result<(), AdminSharedError> {
+        let circuit_id = circuit_proposal.get_circuit_id();
+        let mgmt_type = circuit_proposal
+            .get_circuit_proposal()
+            .circuit_management_type
+            .clone();
+
+        self.remove_proposal(&circuit_id)?;
+        self.joining_circuits.remove(circuit_id);
+
+        let event = messages::AdminServiceEvent::ProposalWithdrawn(
+            messages::CircuitProposal::from_proto(circuit_proposal.clone())
+                .map_err(AdminSharedError::InvalidMessageFormat)?,
+        );
+        self.send_event(&mgmt_type, event);
+
+        info!("circuit proposal for {} has been withdrawn", circuit_id);
+        Ok(())
+    }
+
+    /// Evaluates a newly submitted proposal against the configured voting rules and, if a rule
+    /// matches, submits a vote on behalf of this node.
+    ///
+    /// Proposals requested by this node are not evaluated, as the requester may not vote on its
+    /// own proposal. Failures are logged, since the proposal itself has already been committed.
+    fn auto_vote(&mut self, proposal: &messages::CircuitProposal) {
+        if proposal.requester_node_id == self.node_id {
+            return;
+        }
+
+        let (payload, rule_name, vote) = match self.auto_voter {
+            Some(ref auto_voter) => match auto_voter.evaluate(proposal) {
+                Ok(Some((payload, rule))) => (payload, rule.name.clone(), rule.vote),
+                Ok(None) => {
+                    debug!(
+                        "No voting rule matched the proposal for circuit {}",
+                        proposal.circuit_id
+                    );
+                    return;
+                }
+                Err(err) => {
+                    error!(
+                        "Unable to evaluate voting rules for the proposal for circuit {}: {}",
+                        proposal.circuit_id, err
+                    );
+                    return;
+                }
+            },
+            None => return,
+        };
+
+        info!(
+            "Automatically voting {:?} on the proposal for circuit {} (rule: {})",
+            vote, proposal.circuit_id, rule_name
+        );
+        if let Err(err) = self.submit(payload) {
+            error!(
+                "Unable to submit automatic vote on the proposal for circuit {}: {}",
+                proposal.circuit_id, err
+            );
+        }
+    }
+
+    /// Removes every open proposal whose expiration time has passed.
+    ///
+    /// Each member of a proposal expires it independently, using the expiration time recorded in
+    /// the proposal, so no consensus is required.
+    pub fn expire_proposals(&mut self) -> Result<(), AdminSharedError> {
+        let expired_proposals = self.open_proposals.expired_proposals(current_time_secs())?;
+
+        for circuit_proposal in expired_proposals {
+            let circuit_id = circuit_proposal.get_circuit_id();
+            let mgmt_type = circuit_proposal
+                .get_circuit_proposal()
+                .circuit_management_type
+                .clone();
+
+            self.remove_proposal(&circuit_id)?;
+            self.joining_circuits.remove(circuit_id);
+
+            let event = messages::AdminServiceEvent::ProposalExpired(
+                messages::CircuitProposal::from_proto(circuit_proposal.clone())
+                    .map_err(AdminSharedError::InvalidMessageFormat)?,
+            );
+            self.send_event(&mgmt_type, event);
+
+            info!("circuit proposal for {} has expired", circuit_id);
+        }
+
+        Ok(())
+    }
+
+    /// Commits an accepted create proposal: the new circuit is added to splinter state and the
+    /// other members are notified that this node is ready to initialize the circuit's services.
+    fn commit_create_circuit(
+        &mut self,
+        circuit_proposal: CircuitProposal,
+        signer_public_key: Vec<u8>,
+    ) -> Result<(), AdminSharedError> {
+        let circuit_id = circuit_proposal.get_circuit_id();
+        let mgmt_type = circuit_proposal
+            .get_circuit_proposal()
+            .circuit_management_type
+            .clone();
+
+        // commit new circuit
+        let circuit = circuit_proposal.get_circuit_proposal();
+        self.update_splinter_state(circuit)?;
+        // remove approved proposal
+        self.remove_proposal(&circuit_id)?;
+        // send message about circuit acceptance
+
+        let circuit_proposal_proto =
+            messages::CircuitProposal::from_proto(circuit_proposal.clone())
+                .map_err(AdminSharedError::InvalidMessageFormat)?;
+        let event = messages::AdminServiceEvent::ProposalAccepted((
+            circuit_proposal_proto,
+            signer_public_key,
+        ));
+        self.send_event(&mgmt_type, event);
+
+        // send MEMBER_READY message to all other members' admin services
+        if let Some(ref network_sender) = self.network_sender {
+            let mut member_ready = MemberReady::new();
+            member_ready.set_circuit_id(circuit.circuit_id.clone());
+            member_ready.set_member_node_id(self.node_id.clone());
+            let mut msg = AdminMessage::new();
+            msg.set_message_type(AdminMessage_Type::MEMBER_READY);
+            msg.set_member_ready(member_ready);
+
+            let envelope_bytes = msg.write_to_bytes().map_err(MarshallingError::from)?;
+            for member in circuit.members.iter() {
+                if member.get_node_id() != self.node_id {
+                    network_sender
+                        .send(&admin_service_id(member.get_node_id()), &envelope_bytes)?;
+                }
+            }
+        }
+
+        // add circuit as pending initialization
+        self.add_uninitialized_circuit(circuit_proposal.clone())
+    }
+
+    /// Commits an accepted destroy proposal: the circuit's local services are stopped and purged,
+    /// and the circuit is removed from splinter state.
+    fn commit_destroy_circuit(
+        &mut self,
+        circuit_proposal: CircuitProposal,
+        signer_public_key: Vec<u8>,
+    ) -> Result<(), AdminSharedError> {
+        let circuit_id = circuit_proposal.get_circuit_id().to_string();
+        let mgmt_type = circuit_proposal
+            .get_circuit_proposal()
+            .circuit_management_type
+            .clone();
+
+        self.remove_proposal(&circuit_id)?;
+
+        let circuit_proposal_proto = messages::CircuitProposal::from_proto(circuit_proposal)
+            .map_err(AdminSharedError::InvalidMessageFormat)?;
+        let event = messages::AdminServiceEvent::ProposalAccepted((
+            circuit_proposal_proto.clone(),
+            signer_public_key,
+        ));
+        self.send_event(&mgmt_type, event);
+
+        self.remove_circuit(&circuit_id)?;
+
+        let event = messages::AdminServiceEvent::CircuitDestroyed(circuit_proposal_proto);
+        self.send_event(&mgmt_type, event);
+
+        info!("circuit {} has been destroyed", circuit_id);
+        Ok(())
+    }
+
+    /// Commits an accepted roster update. Only the services that were added or removed are
+    /// started or stopped; the circuit's other services are left running.
+    fn commit_update_roster(
+        &mut self,
+        circuit_proposal: CircuitProposal,
+        signer_public_key: Vec<u8>,
+    ) -> Result<(), AdminSharedError> {
+        let circuit_id = circuit_proposal.get_circuit_id().to_string();
+        let updated_circuit = circuit_proposal.get_circuit_proposal();
+        let mgmt_type = updated_circuit.circuit_management_type.clone();
+
+        let current_circuit = self.get_circuit_proto(&circuit_id)?;
+        let removed_services = current_circuit
+            .get_roster()
+            .iter()
+            .filter(|service| !updated_circuit.get_roster().contains(service))
+            .cloned()
+            .collect::<Vec<_>>();
+        let added_services = updated_circuit
+            .get_roster()
+            .iter()
+            .filter(|service| !current_circuit.get_roster().contains(service))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        self.remove_proposal(&circuit_id)?;
+
+        for service in removed_services.iter() {
+            self.splinter_state.remove_service(&ServiceId::new(
+                circuit_id.clone(),
+                service.get_service_id().to_string(),
+            ))?;
+        }
+        self.update_splinter_state(updated_circuit)?;
+
+        let shutdown_result = self.stop_circuit_services(&circuit_id, &removed_services);
+
+        // services of a circuit that is still waiting on its members will be started along with
+        // the rest of the circuit
+        if !self.uninitialized_circuits.contains_key(&circuit_id) {
+            self.start_circuit_services(&circuit_id, &added_services)?;
+        }
+        self.update_circuit_peer_services(&circuit_id, updated_circuit.get_roster())?;
+
+        let circuit_proposal_proto = messages::CircuitProposal::from_proto(circuit_proposal)
+            .map_err(AdminSharedError::InvalidMessageFormat)?;
+        let event = messages::AdminServiceEvent::ProposalAccepted((
+            circuit_proposal_proto,
+            signer_public_key,
+        ));
+        self.send_event(&mgmt_type, event);
+
+        info!("roster of circuit {} has been updated", circuit_id);
+        shutdown_result
+    }
+
+    /// Commits an accepted proposal to add a node to a circuit. On the node being added, this
+    /// adds the circuit to splinter state for the first time.
+    fn commit_add_node(
+        &mut self,
+        circuit_proposal: CircuitProposal,
+        signer_public_key: Vec<u8>,
+    ) -> Result<(), AdminSharedError> {
+        let circuit_id = circuit_proposal.get_circuit_id().to_string();
+        let updated_circuit = circuit_proposal.get_circuit_proposal();
+        let mgmt_type = updated_circuit.circuit_management_type.clone();
+
+        self.remove_proposal(&circuit_id)?;
+        self.update_splinter_state(updated_circuit)?;
+        self.joining_circuits.remove(&circuit_id);
+
+        self.connect_circuit_members(updated_circuit.get_members());
+        self.update_circuit_peer_services(&circuit_id, updated_circuit.get_roster())?;
+
+        let circuit_proposal_proto = messages::CircuitProposal::from_proto(circuit_proposal)
+            .map_err(AdminSharedError::InvalidMessageFormat)?;
+        let event = messages::AdminServiceEvent::ProposalAccepted((
+            circuit_proposal_proto,
+            signer_public_key,
+        ));
+        self.send_event(&mgmt_type, event);
+
+        info!("node has been added to circuit {}", circuit_id);
+        Ok(())
+    }
+
+    /// Commits an accepted proposal to remove a node from a circuit. The removed node's services
+    /// are dropped from the roster; on the removed node itself, the circuit is removed entirely.
+    fn commit_remove_node(
+        &mut self,
+        circuit_proposal: CircuitProposal,
+        signer_public_key: Vec<u8>,
+    ) -> Result<(), AdminSharedError> {
+        let circuit_id = circuit_proposal.get_circuit_id().to_string();
+        let updated_circuit = circuit_proposal.get_circuit_proposal();
+        let mgmt_type = updated_circuit.circuit_management_type.clone();
+
+        self.remove_proposal(&circuit_id)?;
+
+        let result = if updated_circuit
+            .get_members()
+            .iter()
+            .any(|member| member.get_node_id() == self.node_id)
+        {
+            self.remove_circuit_members(&circuit_id, updated_circuit)
+        } else {
+            // this node has been removed from the circuit
+            self.remove_circuit(&circuit_id)
+        };
+
+        let circuit_proposal_proto = messages::CircuitProposal::from_proto(circuit_proposal)
+            .map_err(AdminSharedError::InvalidMessageFormat)?;
+        let event = messages::AdminServiceEvent::ProposalAccepted((
+            circuit_proposal_proto,
+            signer_public_key,
+        ));
+        self.send_event(&mgmt_type, event);
+
+        info!("node has been removed from circuit {}", circuit_id);
+        result
+    }
+
+    /// Replaces a circuit with the given circuit, from which one or more members have been
+    /// removed along with their services. The removed services and any nodes that are no longer
+    /// part of a circuit are dropped from splinter state, and the peers of the remaining local
+    /// services are updated.
+    fn remove_circuit_members(
+        &mut self,
+        circuit_id: &str,
+        updated_circuit: &Circuit,
+    ) -> Result<(), AdminSharedError> {
+        let current_circuit = self.get_circuit_proto(circuit_id)?;
+        let removed_services = current_circuit
+            .get_roster()
+            .iter()
+            .filter(|service| !updated_circuit.get_roster().contains(service))
+            .cloned()
+            .collect::<Vec<_>>();
+        let removed_nodes = current_circuit
+            .get_members()
+            .iter()
+            .filter(|member| {
+                !updated_circuit
+                    .get_members()
+                    .iter()
+                    .any(|updated| updated.get_node_id() == member.get_node_id())
+            })
+            .map(|member| member.get_node_id().to_string())
+            .collect::<Vec<_>>();
+
+        for service in removed_services.iter() {
+            self.splinter_state.remove_service(&ServiceId::new(
+                circuit_id.to_string(),
+                service.get_service_id().to_string(),
+            ))?;
+        }
+        self.update_splinter_state(updated_circuit)?;
+        self.remove_unused_nodes(&removed_nodes)?;
+
+        let shutdown_result = self.stop_circuit_services(circuit_id, &removed_services);
+        self.update_circuit_peer_services(circuit_id, updated_circuit.get_roster())?;
+        shutdown_result
+    }
+
+    /// Commits an accepted application metadata update. Application authorization handlers are
+    /// notified with both the previous and the new metadata.
+    fn commit_update_application_metadata(
+        &mut self,
+        circuit_proposal: CircuitProposal,
+        signer_public_key: Vec<u8>,
+    ) -> Result<(), AdminSharedError> {
+        let circuit_id = circuit_proposal.get_circuit_id().to_string();
+        let updated_circuit = circuit_proposal.get_circuit_proposal();
+        let mgmt_type = updated_circuit.circuit_management_type.clone();
+
+        let previous_metadata = self
+            .get_circuit_proto(&circuit_id)?
+            .take_application_metadata();
+        let new_metadata = updated_circuit.get_application_metadata().to_vec();
+
+        self.remove_proposal(&circuit_id)?;
+        self.update_splinter_state(updated_circuit)?;
+
+        let circuit_proposal_proto = messages::CircuitProposal::from_proto(circuit_proposal)
+            .map_err(AdminSharedError::InvalidMessageFormat)?;
+        let event = messages::AdminServiceEvent::ProposalAccepted((
+            circuit_proposal_proto.clone(),
+            signer_public_key,
+        ));
+        self.send_event(&mgmt_type, event);
+
+        let event = messages::AdminServiceEvent::ApplicationMetadataUpdated((
+            circuit_proposal_proto,
+            previous_metadata,
+            new_metadata,
+        ));
+        self.send_event(&mgmt_type, event);
+
+        info!(
+            "application metadata of circuit {} has been updated",
+            circuit_id
+        );
+        Ok(())
+    }
+
+    /// Records the circuit revision produced by an accepted proposal in the circuit's history,
+    /// along with the votes the proposal received and the resulting circuit definition.
+    fn add_circuit_revision(
+        &mut self,
+        circuit_proposal: &CircuitProposal,
+    ) -> Result<(), AdminSharedError> {
+        let proposal = messages::CircuitProposal::from_proto(circuit_proposal.clone())
+            .map_err(AdminSharedError::InvalidMessageFormat)?;
+
+        let votes = proposal.votes.iter().map(|vote| {
+            RevisionVote::new(
+                to_hex(&vote.public_key),
+                vote.voter_node_id.clone(),
+                vote.vote == messages::Vote::Accept,
+            )
+        });
+
+        let mut builder = CircuitRevision::builder()
+            .with_circuit_hash(proposal.circuit_hash.clone())
+            .with_proposal_type(format!("{:?}", proposal.proposal_type))
+            .with_requester(to_hex(&proposal.requester))
+            .with_requester_node_id(proposal.requester_node_id.clone())
+            .with_votes(votes)
+            .with_committed_at(current_time_secs());
+        if let Some(circuit) = self.splinter_state.circuit(&proposal.circuit_id)? {
+            builder = builder.with_circuit(circuit);
+        }
+        let revision = builder.build().map_err(|err| {
+            AdminSharedError::CommitError(format!("Unable to build circuit revision: {}", err))
+        })?;
+
+        self.splinter_state
+            .add_circuit_revision(proposal.circuit_id, revision)?;
+        Ok(())
+    }
+
     pub fn rollback(&mut self) -> Result<(), AdminSharedError> {
         match self.pending_changes.take() {
             Some(circuit_proposal_context) => info!(
@@ -443,6 +986,7 @@ impl AdminServiceShared {
         self.verify_signature(&circuit_payload).map_err(|_| {
             AdminSharedError::ValidationFailed(String::from("Unable to verify signature"))
         })?;
+        self.validate_approval_policy(&circuit_payload, &header)?;
         match header.get_action() {
             CircuitManagementPayload_Action::CIRCUIT_CREATE_REQUEST => {
                 let mut create_request = circuit_payload.take_circuit_create_request();
@@ -469,6 +1013,8 @@ impl AdminServiceShared {
                 circuit_proposal.set_circuit_proposal(proposed_circuit);
                 circuit_proposal.set_requester(header.get_requester().to_vec());
                 circuit_proposal.set_requester_node_id(header.get_requester_node_id().to_string());
+                circuit_proposal.set_approval_policy(circuit_payload.get_approval_policy().clone());
+                circuit_proposal.set_expires_at(circuit_payload.get_expires_at());
 
                 let expected_hash = sha256(&circuit_proposal)?;
                 self.pending_changes = Some(CircuitProposalContext {
@@ -531,51 +1077,265 @@ impl AdminServiceShared {
                 self.current_consensus_verifiers = verifiers;
                 Ok((expected_hash, circuit_proposal))
             }
-            CircuitManagementPayload_Action::ACTION_UNSET => Err(
-                AdminSharedError::ValidationFailed("Action must be set".to_string()),
-            ),
-            unknown_action => Err(AdminSharedError::ValidationFailed(format!(
-                "Unable to handle {:?}",
-                unknown_action
-            ))),
-        }
-    }
+            CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_WITHDRAW => {
+                let proposal_withdraw = circuit_payload.get_circuit_proposal_withdraw();
+                let signer_public_key = header.get_requester();
 
-    pub fn has_proposal(&self, circuit_id: &str) -> bool {
-        self.open_proposals.has_proposal(circuit_id)
-    }
+                let circuit_proposal =
+                    self.validate_proposal_withdraw(proposal_withdraw, signer_public_key)?;
 
-    /// Propose a new circuit
-    ///
-    /// This operation will propose a new circuit to all the member nodes of the circuit.  If there
-    /// is no peer connection, a connection to the peer will also be established.
-    pub fn propose_circuit(
-        &mut self,
-        payload: CircuitManagementPayload,
-        message_sender: String,
-    ) -> Result<(), ServiceError> {
-        debug!(
-            "received circuit proposal for {}",
-            payload
-                .get_circuit_create_request()
-                .get_circuit()
-                .get_circuit_id()
-        );
+                let verifiers = circuit_proposal
+                    .get_circuit_proposal()
+                    .get_members()
+                    .iter()
+                    .map(|member| admin_service_id(member.get_node_id()))
+                    .collect();
 
-        // get members as vec to payload can be sent to helper function as well
-        let members = payload
-            .get_circuit_create_request()
-            .get_circuit()
-            .get_members()
-            .to_vec();
-        self.check_connected_peers_payload(&members, payload, message_sender)
-    }
+                let expected_hash = sha256(&circuit_proposal)?;
+                self.pending_changes = Some(CircuitProposalContext {
+                    circuit_proposal: circuit_proposal.clone(),
+                    signer_public_key: signer_public_key.to_vec(),
+                    action: CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_WITHDRAW,
+                });
+                self.current_consensus_verifiers = verifiers;
+                Ok((expected_hash, circuit_proposal))
+            }
+            CircuitManagementPayload_Action::CIRCUIT_DESTROY_REQUEST => {
+                let circuit_id = circuit_payload
+                    .get_circuit_destroy_request()
+                    .get_circuit_id()
+                    .to_string();
+                let signer_public_key = header.get_requester();
+                let requester_node_id = header.get_requester_node_id();
 
-    pub fn propose_vote(
-        &mut self,
-        payload: CircuitManagementPayload,
-        message_sender: String,
-    ) -> Result<(), ServiceError> {
+                self.validate_circuit_change(&circuit_id, signer_public_key, requester_node_id)?;
+                debug!("proposing destruction of {}", circuit_id);
+
+                let circuit = self.get_circuit_proto(&circuit_id)?;
+                let mut verifiers = vec![];
+                for member in circuit.get_members() {
+                    verifiers.push(admin_service_id(member.get_node_id()));
+                }
+
+                let mut circuit_proposal = CircuitProposal::new();
+                circuit_proposal.set_proposal_type(CircuitProposal_ProposalType::DESTROY);
+                circuit_proposal.set_circuit_id(circuit_id);
+                circuit_proposal.set_circuit_hash(sha256(&circuit)?);
+                circuit_proposal.set_circuit_proposal(circuit);
+                circuit_proposal.set_requester(signer_public_key.to_vec());
+                circuit_proposal.set_requester_node_id(requester_node_id.to_string());
+                circuit_proposal.set_approval_policy(circuit_payload.get_approval_policy().clone());
+                circuit_proposal.set_expires_at(circuit_payload.get_expires_at());
+
+                let expected_hash = sha256(&circuit_proposal)?;
+                self.pending_changes = Some(CircuitProposalContext {
+                    circuit_proposal: circuit_proposal.clone(),
+                    signer_public_key: signer_public_key.to_vec(),
+                    action: CircuitManagementPayload_Action::CIRCUIT_DESTROY_REQUEST,
+                });
+                self.current_consensus_verifiers = verifiers;
+
+                Ok((expected_hash, circuit_proposal))
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_ROSTER_REQUEST => {
+                let update_request = circuit_payload.get_circuit_update_roster_request();
+                let circuit_id = update_request.get_circuit_id().to_string();
+                let signer_public_key = header.get_requester();
+                let requester_node_id = header.get_requester_node_id();
+
+                self.validate_circuit_change(&circuit_id, signer_public_key, requester_node_id)?;
+                let updated_circuit = self.build_roster_update(update_request)?;
+                debug!("proposing roster update of {}", circuit_id);
+
+                let mut verifiers = vec![];
+                for member in updated_circuit.get_members() {
+                    verifiers.push(admin_service_id(member.get_node_id()));
+                }
+
+                let mut circuit_proposal = CircuitProposal::new();
+                circuit_proposal.set_proposal_type(CircuitProposal_ProposalType::UPDATE_ROSTER);
+                circuit_proposal.set_circuit_id(circuit_id);
+                circuit_proposal.set_circuit_hash(sha256(&updated_circuit)?);
+                circuit_proposal.set_circuit_proposal(updated_circuit);
+                circuit_proposal.set_requester(signer_public_key.to_vec());
+                circuit_proposal.set_requester_node_id(requester_node_id.to_string());
+                circuit_proposal.set_approval_policy(circuit_payload.get_approval_policy().clone());
+                circuit_proposal.set_expires_at(circuit_payload.get_expires_at());
+
+                let expected_hash = sha256(&circuit_proposal)?;
+                self.pending_changes = Some(CircuitProposalContext {
+                    circuit_proposal: circuit_proposal.clone(),
+                    signer_public_key: signer_public_key.to_vec(),
+                    action: CircuitManagementPayload_Action::CIRCUIT_UPDATE_ROSTER_REQUEST,
+                });
+                self.current_consensus_verifiers = verifiers;
+
+                Ok((expected_hash, circuit_proposal))
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_ADD_NODE => {
+                let add_node_request = circuit_payload.get_circuit_update_add_node();
+                let circuit_id = add_node_request.get_circuit_id().to_string();
+                let signer_public_key = header.get_requester();
+                let requester_node_id = header.get_requester_node_id();
+
+                self.validate_circuit_change(&circuit_id, signer_public_key, requester_node_id)?;
+                let updated_circuit = self.build_add_node(add_node_request)?;
+                debug!(
+                    "proposing addition of {} to {}",
+                    add_node_request.get_node().get_node_id(),
+                    circuit_id
+                );
+
+                // the node being added votes on the proposal as well
+                let mut verifiers = vec![];
+                for member in updated_circuit.get_members() {
+                    verifiers.push(admin_service_id(member.get_node_id()));
+                }
+
+                let mut circuit_proposal = CircuitProposal::new();
+                circuit_proposal.set_proposal_type(CircuitProposal_ProposalType::ADD_NODE);
+                circuit_proposal.set_circuit_id(circuit_id);
+                circuit_proposal.set_circuit_hash(sha256(&updated_circuit)?);
+                circuit_proposal.set_circuit_proposal(updated_circuit);
+                circuit_proposal.set_requester(signer_public_key.to_vec());
+                circuit_proposal.set_requester_node_id(requester_node_id.to_string());
+                circuit_proposal.set_approval_policy(circuit_payload.get_approval_policy().clone());
+                circuit_proposal.set_expires_at(circuit_payload.get_expires_at());
+
+                let expected_hash = sha256(&circuit_proposal)?;
+                self.pending_changes = Some(CircuitProposalContext {
+                    circuit_proposal: circuit_proposal.clone(),
+                    signer_public_key: signer_public_key.to_vec(),
+                    action: CircuitManagementPayload_Action::CIRCUIT_UPDATE_ADD_NODE,
+                });
+                self.current_consensus_verifiers = verifiers;
+
+                Ok((expected_hash, circuit_proposal))
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_REMOVE_NODE => {
+                let remove_node_request = circuit_payload.get_circuit_update_remove_node();
+                let circuit_id = remove_node_request.get_circuit_id().to_string();
+                let signer_public_key = header.get_requester();
+                let requester_node_id = header.get_requester_node_id();
+
+                self.validate_circuit_change(&circuit_id, signer_public_key, requester_node_id)?;
+                let updated_circuit = self.build_remove_node(remove_node_request)?;
+                debug!(
+                    "proposing removal of {} from {}",
+                    remove_node_request.get_node_id(),
+                    circuit_id
+                );
+
+                // the node being removed does not vote, but takes part in consensus so that it
+                // learns of its removal
+                let mut verifiers = vec![];
+                for member in self.get_circuit_proto(&circuit_id)?.get_members() {
+                    verifiers.push(admin_service_id(member.get_node_id()));
+                }
+
+                let mut circuit_proposal = CircuitProposal::new();
+                circuit_proposal.set_proposal_type(CircuitProposal_ProposalType::REMOVE_NODE);
+                circuit_proposal.set_circuit_id(circuit_id);
+                circuit_proposal.set_circuit_hash(sha256(&updated_circuit)?);
+                circuit_proposal.set_circuit_proposal(updated_circuit);
+                circuit_proposal.set_requester(signer_public_key.to_vec());
+                circuit_proposal.set_requester_node_id(requester_node_id.to_string());
+                circuit_proposal.set_approval_policy(circuit_payload.get_approval_policy().clone());
+                circuit_proposal.set_expires_at(circuit_payload.get_expires_at());
+
+                let expected_hash = sha256(&circuit_proposal)?;
+                self.pending_changes = Some(CircuitProposalContext {
+                    circuit_proposal: circuit_proposal.clone(),
+                    signer_public_key: signer_public_key.to_vec(),
+                    action: CircuitManagementPayload_Action::CIRCUIT_UPDATE_REMOVE_NODE,
+                });
+                self.current_consensus_verifiers = verifiers;
+
+                Ok((expected_hash, circuit_proposal))
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_APPLICATION_METADATA_REQUEST => {
+                let update_request =
+                    circuit_payload.get_circuit_update_application_metadata_request();
+                let circuit_id = update_request.get_circuit_id().to_string();
+                let signer_public_key = header.get_requester();
+                let requester_node_id = header.get_requester_node_id();
+
+                self.validate_circuit_change(&circuit_id, signer_public_key, requester_node_id)?;
+                let updated_circuit = self.build_application_metadata_update(update_request)?;
+                debug!("proposing application metadata update of {}", circuit_id);
+
+                let mut verifiers = vec![];
+                for member in updated_circuit.get_members() {
+                    verifiers.push(admin_service_id(member.get_node_id()));
+                }
+
+                let mut circuit_proposal = CircuitProposal::new();
+                circuit_proposal
+                    .set_proposal_type(CircuitProposal_ProposalType::UPDATE_APPLICATION_METADATA);
+                circuit_proposal.set_circuit_id(circuit_id);
+                circuit_proposal.set_circuit_hash(sha256(&updated_circuit)?);
+                circuit_proposal.set_circuit_proposal(updated_circuit);
+                circuit_proposal.set_requester(signer_public_key.to_vec());
+                circuit_proposal.set_requester_node_id(requester_node_id.to_string());
+                circuit_proposal.set_approval_policy(circuit_payload.get_approval_policy().clone());
+                circuit_proposal.set_expires_at(circuit_payload.get_expires_at());
+
+                let expected_hash = sha256(&circuit_proposal)?;
+                self.pending_changes = Some(CircuitProposalContext {
+                    circuit_proposal: circuit_proposal.clone(),
+                    signer_public_key: signer_public_key.to_vec(),
+                    action:
+                        CircuitManagementPayload_Action::CIRCUIT_UPDATE_APPLICATION_METADATA_REQUEST,
+                });
+                self.current_consensus_verifiers = verifiers;
+
+                Ok((expected_hash, circuit_proposal))
+            }
+            CircuitManagementPayload_Action::ACTION_UNSET => Err(
+                AdminSharedError::ValidationFailed("Action must be set".to_string()),
+            ),
+            unknown_action => Err(AdminSharedError::ValidationFailed(format!(
+                "Unable to handle {:?}",
+                unknown_action
+            ))),
+        }
+    }
+
+    pub fn has_proposal(&self, circuit_id: &str) -> bool {
+        self.open_proposals.has_proposal(circuit_id)
+    }
+
+    /// Propose a new circuit
+    ///
+    /// This operation will propose a new circuit to all the member nodes of the circuit.  If there
+    /// is no peer connection, a connection to the peer will also be established.
+    pub fn propose_circuit(
+        &mut self,
+        payload: CircuitManagementPayload,
+        message_sender: String,
+    ) -> Result<(), ServiceError> {
+        debug!(
+            "received circuit proposal for {}",
+            payload
+                .get_circuit_create_request()
+                .get_circuit()
+                .get_circuit_id()
+        );
+
+        // get members as vec to payload can be sent to helper function as well
+        let members = payload
+            .get_circuit_create_request()
+            .get_circuit()
+            .get_members()
+            .to_vec();
+        self.check_connected_peers_payload(&members, payload, message_sender)
+    }
+
+    pub fn propose_vote(
+        &mut self,
+        payload: CircuitManagementPayload,
+        message_sender: String,
+    ) -> Result<(), ServiceError> {
         debug!(
             "received circuit vote for {}",
             payload.get_circuit_proposal_vote().get_circuit_id()
@@ -604,6 +1364,22 @@ impl AdminServiceShared {
         )
     }
 
+    /// Propose a change to an existing circuit, such as destroying it or updating its roster
+    ///
+    /// The proposal is sent to all nodes that must vote on the change. As with a new circuit, a
+    /// connection will be established to any of those nodes that is not currently peered.
+    pub fn propose_circuit_change(
+        &mut self,
+        payload: CircuitManagementPayload,
+        message_sender: String,
+    ) -> Result<(), ServiceError> {
+        let members = self
+            .get_payload_members(&payload)
+            .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+        self.check_connected_peers_payload(&members, payload, message_sender)
+    }
+
     fn check_connected_peers_payload(
         &mut self,
         members: &[SplinterNode],
@@ -643,7 +1419,7 @@ impl AdminServiceShared {
         Ok(())
     }
 
-    pub fn submit(&mut self, payload: CircuitManagementPayload) -> Result<(), ServiceError> {
+    pub fn submit(&mut self, mut payload: CircuitManagementPayload) -> Result<(), ServiceError> {
         debug!("Payload submitted: {:?}", payload);
 
         let header =
@@ -652,6 +1428,22 @@ impl AdminServiceShared {
             .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
         self.verify_signature(&payload)?;
 
+        // the approval policy is chosen by the node the payload is submitted to and travels with
+        // the payload, so that every member records the same policy in the proposal; only
+        // payloads that create a proposal have one
+        if let Some(approval_policy) = self
+            .get_payload_approval_policy(&payload, &header)
+            .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?
+        {
+            payload.set_approval_policy(approval_policy.into_proto());
+
+            // likewise, the expiration time of the new proposal is chosen by the node it is
+            // submitted to
+            if let Some(ttl) = self.proposal_ttl {
+                payload.set_expires_at(current_time_secs() + ttl.as_secs());
+            }
+        }
+
         match header.get_action() {
             CircuitManagementPayload_Action::CIRCUIT_CREATE_REQUEST => {
                 let signer_public_key = header.get_requester();
@@ -700,6 +1492,83 @@ impl AdminServiceShared {
 
                 self.propose_vote(payload, "local".to_string())
             }
+            CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_WITHDRAW => {
+                self.validate_proposal_withdraw(
+                    payload.get_circuit_proposal_withdraw(),
+                    header.get_requester(),
+                )
+                .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+                self.propose_circuit_change(payload, "local".to_string())
+            }
+            CircuitManagementPayload_Action::CIRCUIT_DESTROY_REQUEST => {
+                self.validate_circuit_change(
+                    payload.get_circuit_destroy_request().get_circuit_id(),
+                    header.get_requester(),
+                    header.get_requester_node_id(),
+                )
+                .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+                self.propose_circuit_change(payload, "local".to_string())
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_ROSTER_REQUEST => {
+                let update_request = payload.get_circuit_update_roster_request();
+                self.validate_circuit_change(
+                    update_request.get_circuit_id(),
+                    header.get_requester(),
+                    header.get_requester_node_id(),
+                )
+                .and_then(|_| self.build_roster_update(update_request))
+                .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+                self.propose_circuit_change(payload, "local".to_string())
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_ADD_NODE => {
+                let add_node_request = payload.get_circuit_update_add_node();
+                self.validate_circuit_change(
+                    add_node_request.get_circuit_id(),
+                    header.get_requester(),
+                    header.get_requester_node_id(),
+                )
+                .and_then(|_| self.build_add_node(add_node_request))
+                .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+                self.propose_circuit_change(payload, "local".to_string())
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_REMOVE_NODE => {
+                let remove_node_request = payload.get_circuit_update_remove_node();
+                self.validate_circuit_change(
+                    remove_node_request.get_circuit_id(),
+                    header.get_requester(),
+                    header.get_requester_node_id(),
+                )
+                .and_then(|_| self.build_remove_node(remove_node_request))
+                .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+                self.propose_circuit_change(payload, "local".to_string())
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_APPLICATION_METADATA_REQUEST => {
+                let update_request = payload.get_circuit_update_application_metadata_request();
+                self.validate_circuit_change(
+                    update_request.get_circuit_id(),
+                    header.get_requester(),
+                    header.get_requester_node_id(),
+                )
+                .and_then(|_| self.build_application_metadata_update(update_request))
+                .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+                self.propose_circuit_change(payload, "local".to_string())
+            }
+            CircuitManagementPayload_Action::CIRCUIT_ABANDON => {
+                let circuit_id = payload.get_circuit_abandon().get_circuit_id();
+                self.validate_abandon_circuit(
+                    circuit_id,
+                    header.get_requester(),
+                    header.get_requester_node_id(),
+                )
+                .and_then(|_| self.abandon_circuit(circuit_id))
+                .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))
+            }
             CircuitManagementPayload_Action::ACTION_UNSET => {
                 Err(ServiceError::UnableToHandleMessage(Box::new(
                     AdminSharedError::ValidationFailed(String::from("No action specified")),
@@ -724,12 +1593,12 @@ impl AdminServiceShared {
         payload: CircuitManagementPayload,
         message_sender: String,
     ) -> Result<(), ServiceError> {
+        let members = self
+            .get_payload_members(&payload)
+            .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
         let mut unauthorized_peers = vec![];
-        for node in payload
-            .get_circuit_create_request()
-            .get_circuit()
-            .get_members()
-        {
+        for node in members.iter() {
             if self.node_id() != node.get_node_id() {
                 if self.auth_inquisitor().is_authorized(node.get_node_id()) {
                     continue;
@@ -769,6 +1638,170 @@ impl AdminServiceShared {
         }
     }
 
+    /// Returns the nodes that must take part in consensus on the given payload.
+    fn get_payload_members(
+        &self,
+        payload: &CircuitManagementPayload,
+    ) -> Result<Vec<SplinterNode>, AdminSharedError> {
+        let header =
+            protobuf::parse_from_bytes::<CircuitManagementPayload_Header>(payload.get_header())
+                .map_err(MarshallingError::from)?;
+
+        match header.get_action() {
+            CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_VOTE => {
+                let circuit_id = payload.get_circuit_proposal_vote().get_circuit_id();
+                let proposal = self.get_proposal(circuit_id)?.ok_or_else(|| {
+                    AdminSharedError::ValidationFailed(format!(
+                        "Received vote for a proposal that does not exist: circuit id {}",
+                        circuit_id
+                    ))
+                })?;
+                Ok(proposal.get_circuit_proposal().get_members().to_vec())
+            }
+            CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_WITHDRAW => {
+                let circuit_id = payload.get_circuit_proposal_withdraw().get_circuit_id();
+                let proposal = self.get_proposal(circuit_id)?.ok_or_else(|| {
+                    AdminSharedError::ValidationFailed(format!(
+                        "Received withdrawal of a proposal that does not exist: circuit id {}",
+                        circuit_id
+                    ))
+                })?;
+                Ok(proposal.get_circuit_proposal().get_members().to_vec())
+            }
+            CircuitManagementPayload_Action::CIRCUIT_DESTROY_REQUEST => Ok(self
+                .get_circuit_proto(payload.get_circuit_destroy_request().get_circuit_id())?
+                .get_members()
+                .to_vec()),
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_ROSTER_REQUEST => Ok(self
+                .get_circuit_proto(payload.get_circuit_update_roster_request().get_circuit_id())?
+                .get_members()
+                .to_vec()),
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_ADD_NODE => {
+                let add_node_request = payload.get_circuit_update_add_node();
+                let mut members = self
+                    .get_current_circuit(add_node_request.get_circuit_id())?
+                    .get_members()
+                    .to_vec();
+                members.push(add_node_request.get_node().clone());
+                Ok(members)
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_REMOVE_NODE => Ok(self
+                .get_circuit_proto(payload.get_circuit_update_remove_node().get_circuit_id())?
+                .get_members()
+                .to_vec()),
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_APPLICATION_METADATA_REQUEST => {
+                Ok(self
+                    .get_circuit_proto(
+                        payload
+                            .get_circuit_update_application_metadata_request()
+                            .get_circuit_id(),
+                    )?
+                    .get_members()
+                    .to_vec())
+            }
+            _ => Ok(payload
+                .get_circuit_create_request()
+                .get_circuit()
+                .get_members()
+                .to_vec()),
+        }
+    }
+
+    /// Leave a circuit without the agreement of the other members.
+    ///
+    /// The remaining members are notified, after which the circuit's local services are purged
+    /// and the circuit is removed from this node's splinter state.
+    pub fn abandon_circuit(&mut self, circuit_id: &str) -> Result<(), AdminSharedError> {
+        let circuit = self.get_circuit_proto(circuit_id)?;
+
+        if let Some(ref network_sender) = self.network_sender {
+            let mut abandoned_circuit = AbandonedCircuit::new();
+            abandoned_circuit.set_circuit_id(circuit_id.to_string());
+            abandoned_circuit.set_member_node_id(self.node_id.clone());
+            let mut msg = AdminMessage::new();
+            msg.set_message_type(AdminMessage_Type::ABANDONED_CIRCUIT);
+            msg.set_abandoned_circuit(abandoned_circuit);
+
+            let envelope_bytes = msg.write_to_bytes().map_err(MarshallingError::from)?;
+            for member in circuit.get_members() {
+                if member.get_node_id() != self.node_id {
+                    network_sender
+                        .send(&admin_service_id(member.get_node_id()), &envelope_bytes)?;
+                }
+            }
+        }
+
+        self.remove_proposal(circuit_id)?;
+        self.remove_circuit(circuit_id)?;
+
+        let mgmt_type = circuit.get_circuit_management_type().to_string();
+        let event = messages::AdminServiceEvent::CircuitAbandoned((
+            messages::CreateCircuit::from_proto(circuit)
+                .map_err(AdminSharedError::InvalidMessageFormat)?,
+            self.node_id.clone(),
+        ));
+        self.send_event(&mgmt_type, event);
+
+        info!("circuit {} has been abandoned", circuit_id);
+        Ok(())
+    }
+
+    /// Handle notification that another member has abandoned a circuit.
+    ///
+    /// The departed member and the services it runs are removed from the circuit, the peers of
+    /// the remaining local services are updated, and local subscribers are notified.
+    pub fn handle_abandoned_circuit(
+        &mut self,
+        circuit_id: &str,
+        member_node_id: &str,
+        message_sender: &str,
+    ) -> Result<(), AdminSharedError> {
+        if admin_service_id(member_node_id) != message_sender {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "{} cannot abandon circuit {} on behalf of {}",
+                message_sender, circuit_id, member_node_id
+            )));
+        }
+
+        let circuit = self.get_circuit_proto(circuit_id)?;
+        if !circuit
+            .get_members()
+            .iter()
+            .any(|member| member.get_node_id() == member_node_id)
+        {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "{} is not a member of circuit {}",
+                member_node_id, circuit_id
+            )));
+        }
+
+        let mut updated_circuit = circuit.clone();
+        let mut members = updated_circuit.take_members().into_vec();
+        members.retain(|member| member.get_node_id() != member_node_id);
+        updated_circuit.set_members(RepeatedField::from_vec(members));
+        let mut roster = updated_circuit.take_roster().into_vec();
+        roster.retain(|service| {
+            !service
+                .get_allowed_nodes()
+                .iter()
+                .any(|node| node == member_node_id)
+        });
+        updated_circuit.set_roster(RepeatedField::from_vec(roster));
+
+        let result = self.remove_circuit_members(circuit_id, &updated_circuit);
+
+        let mgmt_type = circuit.get_circuit_management_type().to_string();
+        let event = messages::AdminServiceEvent::CircuitAbandoned((
+            messages::CreateCircuit::from_proto(circuit)
+                .map_err(AdminSharedError::InvalidMessageFormat)?,
+            member_node_id.to_string(),
+        ));
+        self.send_event(&mgmt_type, event);
+
+        info!("{} has abandoned circuit {}", member_node_id, circuit_id);
+        result
+    }
+
     pub fn get_events_since(
         &self,
         since_timestamp: &SystemTime,
@@ -781,9 +1814,11 @@ impl AdminServiceShared {
 
         let circuit_management_type = circuit_management_type.to_string();
         Ok(Events {
-            inner: Box::new(events.filter(move |(_, evt)| {
-                evt.proposal().circuit.circuit_management_type == circuit_management_type
-            })),
+            inner: Box::new(
+                events.filter(move |(_, evt)| {
+                    evt.circuit_management_type() == circuit_management_type
+                }),
+            ),
         })
     }
 
@@ -902,6 +1937,12 @@ impl AdminServiceShared {
         self.open_proposals.get_proposals()
     }
 
+    /// Returns the node IDs of the members of every open proposal.
+    #[cfg(feature = "peer-manager")]
+    pub fn proposal_members(&self) -> HashSet<String> {
+        self.open_proposals.proposal_members()
+    }
+
     pub fn remove_proposal(
         &mut self,
         circuit_id: &str,
@@ -1029,13 +2070,242 @@ impl AdminServiceShared {
         signer_public_key: &[u8],
         requester_node_id: &str,
     ) -> Result<(), AdminSharedError> {
-        if requester_node_id.is_empty() {
-            return Err(AdminSharedError::ValidationFailed(
-                "requester_node_id is empty".to_string(),
-            ));
+        self.validate_requester(signer_public_key, requester_node_id)?;
+
+        if self.has_proposal(circuit.get_circuit_id()) {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Ignoring duplicate create proposal of circuit {}",
+                circuit.get_circuit_id()
+            )));
         }
 
-        self.validate_key(signer_public_key)?;
+        if self.splinter_state.has_circuit(circuit.get_circuit_id())? {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Circuit with circuit id {} already exists",
+                circuit.get_circuit_id()
+            )));
+        }
+
+        self.validate_circuit(circuit)?;
+        Ok(())
+    }
+
+    /// Validates a request to change an existing circuit: the requester must be a member of the
+    /// circuit and the circuit must not already have an open proposal.
+    fn validate_circuit_change(
+        &self,
+        circuit_id: &str,
+        signer_public_key: &[u8],
+        requester_node_id: &str,
+    ) -> Result<(), AdminSharedError> {
+        self.validate_requester(signer_public_key, requester_node_id)?;
+
+        if self.has_proposal(circuit_id) {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Circuit {} already has an open proposal",
+                circuit_id
+            )));
+        }
+
+        if !self
+            .get_current_circuit(circuit_id)?
+            .get_members()
+            .iter()
+            .any(|member| member.get_node_id() == requester_node_id)
+        {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Requester node {} is not a member of circuit {}",
+                requester_node_id, circuit_id
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Builds the circuit that results from applying the given roster update to the current
+    /// circuit, validating both the update and the resulting circuit.
+    fn build_roster_update(
+        &self,
+        update_request: &CircuitUpdateRosterRequest,
+    ) -> Result<Circuit, AdminSharedError> {
+        let mut circuit = self.get_circuit_proto(update_request.get_circuit_id())?;
+
+        if update_request.get_add_services().is_empty()
+            && update_request.get_remove_services().is_empty()
+        {
+            return Err(AdminSharedError::ValidationFailed(
+                "Roster update must add or remove at least one service".to_string(),
+            ));
+        }
+
+        let mut roster = circuit.take_roster().into_vec();
+        for service in update_request.get_remove_services() {
+            let len = roster.len();
+            roster.retain(|existing| existing.get_service_id() != service.get_service_id());
+            if roster.len() == len {
+                return Err(AdminSharedError::ValidationFailed(format!(
+                    "Cannot remove service {}; it is not in the roster of circuit {}",
+                    service.get_service_id(),
+                    update_request.get_circuit_id()
+                )));
+            }
+        }
+
+        for service in update_request.get_add_services() {
+            if roster
+                .iter()
+                .any(|existing| existing.get_service_id() == service.get_service_id())
+            {
+                return Err(AdminSharedError::ValidationFailed(format!(
+                    "Service {} is already in the roster of circuit {}",
+                    service.get_service_id(),
+                    update_request.get_circuit_id()
+                )));
+            }
+            roster.push(service.clone());
+        }
+
+        circuit.set_roster(RepeatedField::from_vec(roster));
+        self.validate_circuit(&circuit)?;
+
+        Ok(circuit)
+    }
+
+    /// Builds the circuit that results from replacing the application metadata of the current
+    /// circuit.
+    fn build_application_metadata_update(
+        &self,
+        update_request: &CircuitUpdateApplicationMetadataRequest,
+    ) -> Result<Circuit, AdminSharedError> {
+        let mut circuit = self.get_circuit_proto(update_request.get_circuit_id())?;
+
+        if circuit.get_application_metadata() == update_request.get_application_metedata() {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Application metadata of circuit {} is unchanged",
+                update_request.get_circuit_id()
+            )));
+        }
+
+        circuit.set_application_metadata(update_request.get_application_metedata().to_vec());
+
+        Ok(circuit)
+    }
+
+    /// Builds the circuit that results from adding the requested node to the current circuit,
+    /// validating the resulting circuit.
+    fn build_add_node(
+        &self,
+        add_node_request: &CircuitUpdateAddNodeRequest,
+    ) -> Result<Circuit, AdminSharedError> {
+        let mut circuit = self.get_current_circuit(add_node_request.get_circuit_id())?;
+        let node = add_node_request.get_node();
+
+        if circuit
+            .get_members()
+            .iter()
+            .any(|member| member.get_node_id() == node.get_node_id())
+        {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "{} is already a member of circuit {}",
+                node.get_node_id(),
+                add_node_request.get_circuit_id()
+            )));
+        }
+
+        let mut members = circuit.take_members().into_vec();
+        members.push(node.clone());
+        circuit.set_members(RepeatedField::from_vec(members));
+        self.validate_circuit(&circuit)?;
+
+        Ok(circuit)
+    }
+
+    /// Builds the circuit that results from removing the requested node, along with the services
+    /// it runs, from the current circuit.
+    fn build_remove_node(
+        &self,
+        remove_node_request: &CircuitUpdateRemoveNodeRequest,
+    ) -> Result<Circuit, AdminSharedError> {
+        let mut circuit = self.get_circuit_proto(remove_node_request.get_circuit_id())?;
+        let node_id = remove_node_request.get_node_id();
+
+        let mut members = circuit.take_members().into_vec();
+        let len = members.len();
+        members.retain(|member| member.get_node_id() != node_id);
+        if members.len() == len {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "{} is not a member of circuit {}",
+                node_id,
+                remove_node_request.get_circuit_id()
+            )));
+        }
+        if members.is_empty() {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Cannot remove the last member of circuit {}",
+                remove_node_request.get_circuit_id()
+            )));
+        }
+
+        let mut roster = circuit.take_roster().into_vec();
+        roster.retain(|service| {
+            !service
+                .get_allowed_nodes()
+                .iter()
+                .any(|node| node == node_id)
+        });
+        if roster.is_empty() {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Removing {} would leave circuit {} without services",
+                node_id,
+                remove_node_request.get_circuit_id()
+            )));
+        }
+
+        circuit.set_members(RepeatedField::from_vec(members));
+        circuit.set_roster(RepeatedField::from_vec(roster));
+
+        Ok(circuit)
+    }
+
+    fn validate_abandon_circuit(
+        &self,
+        circuit_id: &str,
+        signer_public_key: &[u8],
+        requester_node_id: &str,
+    ) -> Result<(), AdminSharedError> {
+        self.validate_requester(signer_public_key, requester_node_id)?;
+
+        if requester_node_id != self.node_id {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Circuit {} can only be abandoned by the local node",
+                circuit_id
+            )));
+        }
+
+        if !self.splinter_state.has_circuit(circuit_id)? {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Circuit {} does not exist",
+                circuit_id
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Validates that the signer's key is registered to the requesting node and that it is
+    /// permitted to make proposals.
+    fn validate_requester(
+        &self,
+        signer_public_key: &[u8],
+        requester_node_id: &str,
+    ) -> Result<(), AdminSharedError> {
+        if requester_node_id.is_empty() {
+            return Err(AdminSharedError::ValidationFailed(
+                "requester_node_id is empty".to_string(),
+            ));
+        }
+
+        self.validate_key(signer_public_key)?;
 
         let key_info = self
             .key_registry
@@ -1065,21 +2335,6 @@ impl AdminServiceShared {
                 ))
             })?;
 
-        if self.has_proposal(circuit.get_circuit_id()) {
-            return Err(AdminSharedError::ValidationFailed(format!(
-                "Ignoring duplicate create proposal of circuit {}",
-                circuit.get_circuit_id()
-            )));
-        }
-
-        if self.splinter_state.has_circuit(circuit.get_circuit_id())? {
-            return Err(AdminSharedError::ValidationFailed(format!(
-                "Circuit with circuit id {} already exists",
-                circuit.get_circuit_id()
-            )));
-        }
-
-        self.validate_circuit(circuit)?;
         Ok(())
     }
 
@@ -1113,6 +2368,15 @@ impl AdminServiceShared {
             ));
         }
 
+        #[cfg(not(feature = "circuit-durability"))]
+        {
+            if circuit.get_durability() == Circuit_DurabilityType::DURABLE {
+                return Err(AdminSharedError::ValidationFailed(
+                    "Durable circuits are not supported by this node".to_string(),
+                ));
+            }
+        }
+
         if circuit.get_routes() == Circuit_RouteType::UNSET_ROUTE_TYPE {
             return Err(AdminSharedError::ValidationFailed(
                 "route_type cannot be unset".to_string(),
@@ -1167,6 +2431,33 @@ impl AdminServiceShared {
             ));
         }
 
+        if circuit.get_routes() == Circuit_RouteType::RELAYED_ROUTE {
+            if circuit.get_relays().is_empty() {
+                return Err(AdminSharedError::ValidationFailed(
+                    "A relayed circuit must have relays".to_string(),
+                ));
+            }
+
+            for relay in circuit.get_relays() {
+                if !members.contains(relay) {
+                    return Err(AdminSharedError::ValidationFailed(format!(
+                        "Circuit cannot have a relay that is not in members: {}",
+                        relay
+                    )));
+                }
+            }
+
+            if circuit.get_durability() == Circuit_DurabilityType::DURABLE {
+                return Err(AdminSharedError::ValidationFailed(
+                    "A relayed circuit cannot be durable".to_string(),
+                ));
+            }
+        } else if !circuit.get_relays().is_empty() {
+            return Err(AdminSharedError::ValidationFailed(
+                "Only a relayed circuit may have relays".to_string(),
+            ));
+        }
+
         // check this node is in members
         if !members.contains(&self.node_id) {
             return Err(AdminSharedError::ValidationFailed(format!(
@@ -1320,9 +2611,54 @@ impl AdminServiceShared {
             )));
         }
 
+        let expires_at = circuit_proposal.get_expires_at();
+        if expires_at != 0 && expires_at <= current_time_secs() {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Received vote for an expired proposal: circuit id {}",
+                proposal_vote.circuit_id
+            )));
+        }
+
         Ok(())
     }
 
+    /// Validates the withdrawal of a proposal, returning the proposal being withdrawn.
+    ///
+    /// Only the requester of a proposal may withdraw it, and only while it is still open.
+    fn validate_proposal_withdraw(
+        &self,
+        proposal_withdraw: &CircuitProposalWithdraw,
+        signer_public_key: &[u8],
+    ) -> Result<CircuitProposal, AdminSharedError> {
+        let circuit_id = proposal_withdraw.get_circuit_id();
+
+        self.validate_key(signer_public_key)?;
+
+        let circuit_proposal = self.get_proposal(circuit_id)?.ok_or_else(|| {
+            AdminSharedError::ValidationFailed(format!(
+                "Received withdrawal of a proposal that does not exist: circuit id {}",
+                circuit_id
+            ))
+        })?;
+
+        if circuit_proposal.get_requester() != signer_public_key {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "{} is not the requester of the proposal for circuit {}",
+                to_hex(signer_public_key),
+                circuit_id
+            )));
+        }
+
+        if circuit_proposal.get_circuit_hash() != proposal_withdraw.get_circuit_hash() {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Hash of circuit does not match circuit proposal: {}",
+                circuit_id
+            )));
+        }
+
+        Ok(circuit_proposal)
+    }
+
     fn validate_circuit_management_payload(
         &self,
         payload: &CircuitManagementPayload,
@@ -1358,45 +2694,212 @@ impl AdminServiceShared {
             ));
         };
 
+        self.validate_expiry(payload.get_expires_at())?;
+
+        Ok(())
+    }
+
+    /// Validates the expiration time chosen by the node a payload was submitted to. It may not
+    /// have passed, nor be further away than the longer of this node's proposal TTL and
+    /// `MAX_PROPOSAL_TTL_SECS`. An expiration time of 0 means the proposal does not expire.
+    fn validate_expiry(&self, expires_at: u64) -> Result<(), AdminSharedError> {
+        if expires_at == 0 {
+            return Ok(());
+        }
+
+        let now = current_time_secs();
+        if expires_at <= now {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Proposal expiration time {} has already passed",
+                expires_at
+            )));
+        }
+
+        let max_ttl = self
+            .proposal_ttl
+            .map(|ttl| ttl.as_secs())
+            .unwrap_or(0)
+            .max(MAX_PROPOSAL_TTL_SECS);
+        if expires_at - now > max_ttl {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Proposal expiration time {} is more than {} seconds away",
+                expires_at, max_ttl
+            )));
+        }
+
         Ok(())
     }
 
+    /// Checks whether a proposal has been approved or rejected according to the approval policy
+    /// recorded in the proposal. The requester counts as approving the proposal, and only the
+    /// members of the proposed circuit are counted.
     fn check_approved(
         &self,
         proposal: &CircuitProposal,
     ) -> Result<CircuitProposalStatus, AdminSharedError> {
-        let mut received_votes = HashSet::new();
-        for vote in proposal.get_votes() {
-            if vote.get_vote() == CircuitProposalVote_Vote::REJECT {
-                return Ok(CircuitProposalStatus::Rejected);
-            }
-            received_votes.insert(vote.get_voter_node_id().to_string());
-        }
-
-        let mut required_votes = proposal
+        let members = proposal
             .get_circuit_proposal()
             .get_members()
-            .to_vec()
             .iter()
             .map(|member| member.get_node_id().to_string())
             .collect::<HashSet<String>>();
 
-        required_votes.remove(proposal.get_requester_node_id());
+        let mut approvals = HashSet::new();
+        approvals.insert(proposal.get_requester_node_id().to_string());
+        let mut rejections = HashSet::new();
+        for vote in proposal.get_votes() {
+            match vote.get_vote() {
+                CircuitProposalVote_Vote::ACCEPT => {
+                    approvals.insert(vote.get_voter_node_id().to_string());
+                }
+                CircuitProposalVote_Vote::REJECT => {
+                    approvals.remove(vote.get_voter_node_id());
+                    rejections.insert(vote.get_voter_node_id().to_string());
+                }
+                CircuitProposalVote_Vote::UNSET_VOTE => (),
+            }
+        }
+
+        let policy = messages::ApprovalPolicy::from_proto(proposal.get_approval_policy().clone());
+        let weight = |node_id: &String| policy.weight(node_id);
+
+        let total_weight: u64 = members.iter().map(weight).sum();
+        let approved_weight: u64 = approvals
+            .iter()
+            .filter(|node_id| members.contains(*node_id))
+            .map(weight)
+            .sum();
+        let rejected_weight: u64 = rejections
+            .iter()
+            .filter(|node_id| members.contains(*node_id))
+            .map(weight)
+            .sum();
+
+        let required_weight = match &policy {
+            messages::ApprovalPolicy::Unanimous => total_weight,
+            messages::ApprovalPolicy::Majority => total_weight / 2 + 1,
+            messages::ApprovalPolicy::Threshold { threshold }
+            | messages::ApprovalPolicy::Weighted { threshold, .. } => u64::from(*threshold),
+            messages::ApprovalPolicy::RequesterPlusOne => std::cmp::min(2, total_weight),
+        };
 
-        if required_votes == received_votes {
+        if approved_weight >= required_weight {
             Ok(CircuitProposalStatus::Accepted)
+        } else if total_weight - rejected_weight < required_weight {
+            // the remaining members can no longer approve the proposal
+            Ok(CircuitProposalStatus::Rejected)
         } else {
             Ok(CircuitProposalStatus::Pending)
         }
     }
 
+    /// Returns the approval policy configured on this node for the circuit management type of
+    /// the circuit that the payload creates or changes, if the payload results in a proposal.
+    fn get_payload_approval_policy(
+        &self,
+        payload: &CircuitManagementPayload,
+        header: &CircuitManagementPayload_Header,
+    ) -> Result<Option<messages::ApprovalPolicy>, AdminSharedError> {
+        let circuit_id = match header.get_action() {
+            CircuitManagementPayload_Action::CIRCUIT_CREATE_REQUEST => {
+                let circuit = payload.get_circuit_create_request().get_circuit();
+                return Ok(Some(
+                    self.get_approval_policy(circuit.get_circuit_management_type()),
+                ));
+            }
+            CircuitManagementPayload_Action::CIRCUIT_DESTROY_REQUEST => {
+                payload.get_circuit_destroy_request().get_circuit_id()
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_ROSTER_REQUEST => {
+                payload.get_circuit_update_roster_request().get_circuit_id()
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_ADD_NODE => {
+                payload.get_circuit_update_add_node().get_circuit_id()
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_REMOVE_NODE => {
+                payload.get_circuit_update_remove_node().get_circuit_id()
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_APPLICATION_METADATA_REQUEST => payload
+                .get_circuit_update_application_metadata_request()
+                .get_circuit_id(),
+            _ => return Ok(None),
+        };
+
+        let circuit = self.get_current_circuit(circuit_id)?;
+        Ok(Some(self.get_approval_policy(
+            circuit.get_circuit_management_type(),
+        )))
+    }
+
+    /// Checks that the approval policy carried by the payload is the policy this node enforces
+    /// for the circuit, so a requester cannot pick a weaker policy for its own proposal, and that
+    /// its threshold, if any, can be met by the members of the proposed circuit.
+    fn validate_approval_policy(
+        &self,
+        payload: &CircuitManagementPayload,
+        header: &CircuitManagementPayload_Header,
+    ) -> Result<(), AdminSharedError> {
+        if let Some(expected) = self.get_payload_approval_policy(payload, header)? {
+            let proposed =
+                messages::ApprovalPolicy::from_proto(payload.get_approval_policy().clone());
+            if proposed != expected {
+                return Err(AdminSharedError::ValidationFailed(format!(
+                    "Proposed approval policy {:?} does not match the policy {:?} enforced by \
+                     this node",
+                    proposed, expected
+                )));
+            }
+
+            // the threshold must require at least one approval and must be within reach of the
+            // members of the proposed circuit
+            if let Some(threshold) = expected.threshold() {
+                let mut members = self.get_payload_members(payload)?;
+                if header.get_action()
+                    == CircuitManagementPayload_Action::CIRCUIT_UPDATE_REMOVE_NODE
+                {
+                    let node_id = payload.get_circuit_update_remove_node().get_node_id();
+                    members.retain(|member| member.get_node_id() != node_id);
+                }
+                let total_weight: u64 = members
+                    .iter()
+                    .map(|member| expected.weight(member.get_node_id()))
+                    .sum();
+                if threshold == 0 || u64::from(threshold) > total_weight {
+                    return Err(AdminSharedError::ValidationFailed(format!(
+                        "Approval policy {:?} must have a threshold of at least 1 and at most \
+                         the total weight of the circuit's members, {}",
+                        expected, total_weight
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_approval_policy(&self, circuit_management_type: &str) -> messages::ApprovalPolicy {
+        self.approval_policies
+            .get(circuit_management_type)
+            .cloned()
+            .unwrap_or_default()
+    }
+
     /// Initialize all services that this node should run on the created circuit using the service
     /// orchestrator. This may not include all services if they are not supported locally. It is
     /// expected that some services will be started externally.
     pub fn initialize_services(&mut self, circuit: &Circuit) -> Result<(), AdminSharedError> {
+        self.start_circuit_services(circuit.get_circuit_id(), circuit.get_roster())
+    }
+
+    /// Start the given services of a circuit that this node is allowed to run and the orchestrator
+    /// has a factory for.
+    fn start_circuit_services(
+        &mut self,
+        circuit_id: &str,
+        roster: &[SplinterService],
+    ) -> Result<(), AdminSharedError> {
         // Get all services this node is allowed to run
-        let services = circuit
-            .get_roster()
+        let services = roster
             .iter()
             .filter(|service| {
                 service.allowed_nodes.contains(&self.node_id)
@@ -1410,7 +2913,7 @@ impl AdminServiceShared {
         // Start all services the orchestrator has a factory for
         for service in services {
             let service_definition = ServiceDefinition {
-                circuit: circuit.circuit_id.clone(),
+                circuit: circuit_id.to_string(),
                 service_id: service.service_id.clone(),
                 service_type: service.service_type.clone(),
             };
@@ -1430,14 +2933,51 @@ impl AdminServiceShared {
         Ok(())
     }
 
-    /// Stops all running services
-    pub fn stop_services(&mut self) -> Result<(), AdminSharedError> {
-        let shutdown_errors = self
+    /// Stops the given services of a circuit, if they are running on this node
+    fn stop_circuit_services(
+        &mut self,
+        circuit_id: &str,
+        roster: &[SplinterService],
+    ) -> Result<(), AdminSharedError> {
+        let running_services = self
             .running_services
             .iter()
-            .map(|service| {
-                debug!(
-                    "Stopping service {} in circuit {}",
+            .filter(|running| {
+                running.circuit == circuit_id
+                    && roster
+                        .iter()
+                        .any(|service| service.get_service_id() == running.service_id)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut shutdown_errors = vec![];
+        for service in running_services {
+            debug!(
+                "Stopping service {} in circuit {}",
+                service.service_id, service.circuit
+            );
+            if let Err(err) = self.orchestrator.shutdown_service(&service) {
+                shutdown_errors.push(err);
+            }
+            self.running_services.remove(&service);
+        }
+
+        if shutdown_errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AdminSharedError::ServiceShutdownFailed(shutdown_errors))
+        }
+    }
+
+    /// Stops all running services
+    pub fn stop_services(&mut self) -> Result<(), AdminSharedError> {
+        let shutdown_errors = self
+            .running_services
+            .iter()
+            .map(|service| {
+                debug!(
+                    "Stopping service {} in circuit {}",
                     service.service_type, service.circuit
                 );
                 self.orchestrator.shutdown_service(&service)
@@ -1504,10 +3044,12 @@ impl AdminServiceShared {
             .get_members()
             .iter()
             .map(|node| {
-                StateNode::new(
-                    node.get_node_id().to_string(),
-                    vec![node.get_endpoint().to_string()],
-                )
+                let endpoints = if node.get_endpoints().is_empty() {
+                    vec![node.get_endpoint().to_string()]
+                } else {
+                    node.get_endpoints().to_vec()
+                };
+                StateNode::new(node.get_node_id().to_string(), endpoints)
             })
             .collect();
 
@@ -1544,6 +3086,7 @@ impl AdminServiceShared {
 
         let persistence = match circuit.get_persistence() {
             Circuit_PersistenceType::ANY_PERSISTENCE => PersistenceType::Any,
+            Circuit_PersistenceType::PERSISTENT => PersistenceType::Persistent,
             // This should never happen
             Circuit_PersistenceType::UNSET_PERSISTENCE_TYPE => {
                 return Err(AdminSharedError::CommitError(
@@ -1554,6 +3097,7 @@ impl AdminServiceShared {
 
         let durability = match circuit.get_durability() {
             Circuit_DurabilityType::NO_DURABILITY => DurabilityType::NoDurability,
+            Circuit_DurabilityType::DURABLE => DurabilityType::Durable,
             // This should never happen
             Circuit_DurabilityType::UNSET_DURABILITY_TYPE => {
                 return Err(AdminSharedError::CommitError(
@@ -1564,6 +3108,7 @@ impl AdminServiceShared {
 
         let routes = match circuit.get_routes() {
             Circuit_RouteType::ANY_ROUTE => RouteType::Any,
+            Circuit_RouteType::RELAYED_ROUTE => RouteType::Relayed(circuit.get_relays().to_vec()),
             // This should never happen
             Circuit_RouteType::UNSET_ROUTE_TYPE => {
                 return Err(AdminSharedError::CommitError(
@@ -1586,6 +3131,7 @@ impl AdminServiceShared {
             .with_durability(durability)
             .with_routes(routes)
             .with_circuit_management_type(circuit.get_circuit_management_type().to_string())
+            .with_application_metadata(circuit.get_application_metadata().to_vec())
             .build()
             .map_err(|err| {
                 AdminSharedError::CommitError(format!("Unable build new circuit: {}", err))
@@ -1625,6 +3171,316 @@ impl AdminServiceShared {
         Ok(())
     }
 
+    /// Builds the protobuf representation of a circuit from splinter state.
+    pub fn get_circuit_proto(&self, circuit_id: &str) -> Result<Circuit, AdminSharedError> {
+        let state_circuit = self.splinter_state.circuit(circuit_id)?.ok_or_else(|| {
+            AdminSharedError::ValidationFailed(format!("Circuit {} does not exist", circuit_id))
+        })?;
+
+        let mut members = vec![];
+        for node_id in state_circuit.members().to_vec() {
+            let node = self.splinter_state.node(&node_id)?.ok_or_else(|| {
+                AdminSharedError::ValidationFailed(format!(
+                    "Unable to find member {} of circuit {}",
+                    node_id, circuit_id
+                ))
+            })?;
+
+            let mut member = SplinterNode::new();
+            member.set_node_id(node_id);
+            member.set_endpoint(node.endpoints().get(0).cloned().unwrap_or_default());
+            member.set_endpoints(RepeatedField::from_vec(node.endpoints().to_vec()));
+            members.push(member);
+        }
+
+        let roster = state_circuit
+            .roster()
+            .iter()
+            .map(|service| {
+                let mut proto_service = SplinterService::new();
+                proto_service.set_service_id(service.service_id().to_string());
+                proto_service.set_service_type(service.service_type().to_string());
+                proto_service
+                    .set_allowed_nodes(RepeatedField::from_vec(service.allowed_nodes().to_vec()));
+                proto_service.set_arguments(RepeatedField::from_vec(
+                    service
+                        .arguments()
+                        .iter()
+                        .map(|(key, value)| {
+                            let mut argument = SplinterService_Argument::new();
+                            argument.set_key(key.clone());
+                            argument.set_value(value.clone());
+                            argument
+                        })
+                        .collect(),
+                ));
+                proto_service
+            })
+            .collect::<Vec<_>>();
+
+        let mut circuit = Circuit::new();
+        circuit.set_circuit_id(circuit_id.to_string());
+        circuit.set_members(RepeatedField::from_vec(members));
+        circuit.set_roster(RepeatedField::from_vec(roster));
+        circuit.set_authorization_type(match state_circuit.auth() {
+            AuthorizationType::Trust => Circuit_AuthorizationType::TRUST_AUTHORIZATION,
+        });
+        circuit.set_persistence(match state_circuit.persistence() {
+            PersistenceType::Any => Circuit_PersistenceType::ANY_PERSISTENCE,
+            PersistenceType::Persistent => Circuit_PersistenceType::PERSISTENT,
+        });
+        circuit.set_durability(match state_circuit.durability() {
+            DurabilityType::NoDurability => Circuit_DurabilityType::NO_DURABILITY,
+            DurabilityType::Durable => Circuit_DurabilityType::DURABLE,
+        });
+        match state_circuit.routes() {
+            RouteType::Any => circuit.set_routes(Circuit_RouteType::ANY_ROUTE),
+            RouteType::Relayed(relays) => {
+                circuit.set_routes(Circuit_RouteType::RELAYED_ROUTE);
+                circuit.set_relays(RepeatedField::from_vec(relays.to_vec()));
+            }
+        }
+        circuit.set_circuit_management_type(state_circuit.circuit_management_type().to_string());
+        circuit.set_application_metadata(state_circuit.application_metadata().to_vec());
+
+        Ok(circuit)
+    }
+
+    /// Returns the current definition of a circuit. If this node is not yet a member of the
+    /// circuit, the definition received while being proposed to join it is returned.
+    fn get_current_circuit(&self, circuit_id: &str) -> Result<Circuit, AdminSharedError> {
+        if self.splinter_state.has_circuit(circuit_id)? {
+            self.get_circuit_proto(circuit_id)
+        } else {
+            self.joining_circuits
+                .get(circuit_id)
+                .cloned()
+                .ok_or_else(|| {
+                    AdminSharedError::ValidationFailed(format!(
+                        "Circuit {} does not exist",
+                        circuit_id
+                    ))
+                })
+        }
+    }
+
+    /// Record the current definition of a circuit that this node has been proposed to join.
+    /// Definitions of circuits that this node is already a member of are ignored.
+    ///
+    /// The definition is sent by the proposing member, so it is only accepted along with a
+    /// proposal to add this node to that circuit, from a member of the circuit, and only if no
+    /// other definition of the circuit is already being considered.
+    pub fn add_joining_circuit(
+        &mut self,
+        circuit: Circuit,
+        payload: &CircuitManagementPayload,
+        message_sender: &str,
+    ) -> Result<(), AdminSharedError> {
+        let circuit_id = circuit.get_circuit_id().to_string();
+        if self.splinter_state.has_circuit(&circuit_id)? {
+            return Ok(());
+        }
+
+        let header =
+            protobuf::parse_from_bytes::<CircuitManagementPayload_Header>(payload.get_header())
+                .map_err(MarshallingError::from)?;
+        if header.get_action() != CircuitManagementPayload_Action::CIRCUIT_UPDATE_ADD_NODE {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "A circuit definition was sent with a {:?} proposal for circuit {}",
+                header.get_action(),
+                circuit_id
+            )));
+        }
+
+        let add_node_request = payload.get_circuit_update_add_node();
+        if add_node_request.get_circuit_id() != circuit_id
+            || add_node_request.get_node().get_node_id() != self.node_id
+        {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "The definition of circuit {} was not sent with a proposal to add {} to it",
+                circuit_id, self.node_id
+            )));
+        }
+
+        if !circuit
+            .get_members()
+            .iter()
+            .any(|member| admin_service_id(member.get_node_id()) == message_sender)
+        {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "{} is not a member of circuit {}",
+                message_sender, circuit_id
+            )));
+        }
+
+        if circuit
+            .get_members()
+            .iter()
+            .any(|member| member.get_node_id() == self.node_id)
+        {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "{} is already a member of circuit {}",
+                self.node_id, circuit_id
+            )));
+        }
+
+        // the circuit must be valid once this node has been added to it
+        let mut joined_circuit = circuit.clone();
+        joined_circuit
+            .mut_members()
+            .push(add_node_request.get_node().clone());
+        self.validate_circuit(&joined_circuit)?;
+
+        match self.joining_circuits.get(&circuit_id) {
+            Some(joining_circuit) if joining_circuit != &circuit => {
+                Err(AdminSharedError::ValidationFailed(format!(
+                    "A different definition of circuit {} has already been received",
+                    circuit_id
+                )))
+            }
+            Some(_) => Ok(()),
+            None => {
+                self.joining_circuits.insert(circuit_id, circuit);
+                Ok(())
+            }
+        }
+    }
+
+    /// Discards the definition of a circuit this node was proposed to join if the given payload,
+    /// which was not accepted by consensus, was the proposal to add this node and no proposal
+    /// for the circuit remains open.
+    pub fn discard_joining_circuit(
+        &mut self,
+        payload: &CircuitManagementPayload,
+    ) -> Result<(), AdminSharedError> {
+        let header =
+            protobuf::parse_from_bytes::<CircuitManagementPayload_Header>(payload.get_header())
+                .map_err(MarshallingError::from)?;
+        if header.get_action() != CircuitManagementPayload_Action::CIRCUIT_UPDATE_ADD_NODE {
+            return Ok(());
+        }
+
+        let circuit_id = payload.get_circuit_update_add_node().get_circuit_id();
+        if self.get_proposal(circuit_id)?.is_none() {
+            self.joining_circuits.remove(circuit_id);
+        }
+        Ok(())
+    }
+
+    /// Connects to the members of a circuit that this node is not yet connected to. Failures are
+    /// logged, as the members will be connected to again when a message must be sent to them.
+    fn connect_circuit_members(&mut self, members: &[SplinterNode]) {
+        for member in members {
+            if member.get_node_id() == self.node_id
+                || self.auth_inquisitor.is_authorized(member.get_node_id())
+            {
+                continue;
+            }
+
+            debug!("Connecting to node {:?}", member);
+            if let Err(err) = self
+                .peer_connector
+                .connect_peer(member.get_node_id(), member.get_endpoint())
+            {
+                error!(
+                    "Unable to connect to node {}: {}",
+                    member.get_node_id(),
+                    err
+                );
+            }
+        }
+    }
+
+    /// Updates the peer services of this node's running services on the given circuit; the peers
+    /// of a service are the other services of the same type in the roster.
+    fn update_circuit_peer_services(
+        &mut self,
+        circuit_id: &str,
+        roster: &[SplinterService],
+    ) -> Result<(), AdminSharedError> {
+        for running_service in self
+            .running_services
+            .iter()
+            .filter(|service| service.circuit == circuit_id)
+        {
+            let peer_services = roster
+                .iter()
+                .filter(|service| {
+                    service.get_service_type() == running_service.service_type
+                        && service.get_service_id() != running_service.service_id
+                })
+                .map(|service| service.get_service_id().to_string())
+                .collect::<Vec<_>>();
+
+            self.orchestrator
+                .update_peer_services(running_service, &peer_services)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the given nodes from splinter state if they are no longer a member of any circuit.
+    fn remove_unused_nodes(&mut self, node_ids: &[String]) -> Result<(), AdminSharedError> {
+        let remaining_members = self
+            .splinter_state
+            .circuits()?
+            .values()
+            .flat_map(|circuit| circuit.members().to_vec())
+            .collect::<HashSet<String>>();
+        for node_id in node_ids {
+            if !remaining_members.contains(node_id) {
+                self.splinter_state.remove_node(node_id)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stops and purges the local services of a circuit and removes the circuit, along with any
+    /// nodes that are no longer part of a circuit, from splinter state.
+    fn remove_circuit(&mut self, circuit_id: &str) -> Result<(), AdminSharedError> {
+        let circuit_services = self
+            .running_services
+            .iter()
+            .filter(|service| service.circuit == circuit_id)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let purge_errors = circuit_services
+            .iter()
+            .map(|service| {
+                debug!(
+                    "Purging service {} in circuit {}",
+                    service.service_id, service.circuit
+                );
+                self.running_services.remove(service);
+                self.orchestrator.purge_service(service)
+            })
+            .filter_map(Result::err)
+            .collect::<Vec<ShutdownServiceError>>();
+
+        if let Some(circuit) = self.splinter_state.circuit(circuit_id)? {
+            for service in circuit.roster().iter() {
+                let unique_id =
+                    ServiceId::new(circuit_id.to_string(), service.service_id().to_string());
+                if self.splinter_state.has_service(&unique_id)? {
+                    self.splinter_state.remove_service(&unique_id)?;
+                }
+            }
+
+            self.splinter_state.remove_circuit(circuit_id)?;
+            self.remove_unused_nodes(&circuit.members().to_vec())?;
+        }
+
+        self.uninitialized_circuits.remove(circuit_id);
+
+        if purge_errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AdminSharedError::ServiceShutdownFailed(purge_errors))
+        }
+    }
+
     pub fn add_services_to_directory(&mut self) -> Result<(), AdminSharedError> {
         let circuits = self.splinter_state.circuits()?;
         for (id, circuit) in circuits {
@@ -1667,6 +3523,44 @@ impl AdminServiceShared {
     }
 }
 
+/// Returns the current time, in seconds since the UNIX epoch.
+fn current_time_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Imports the proposals of a YAML proposal file into database-backed open proposals, if they
+/// have none yet. The file is renamed once its proposals have been imported, so it is only
+/// imported once.
+#[cfg(feature = "database")]
+fn import_yaml_proposals(
+    open_proposals: &mut OpenProposals,
+    yaml_location: &str,
+) -> Result<(), OpenProposalError> {
+    if !open_proposals.is_empty() || !std::path::Path::new(yaml_location).exists() {
+        return Ok(());
+    }
+
+    let yaml_proposals = OpenProposals::new(yaml_location.to_string())?;
+    let imported = open_proposals.import_proposals(&yaml_proposals)?;
+
+    let migrated_location = format!("{}.migrated", yaml_location);
+    std::fs::rename(yaml_location, &migrated_location).map_err(|err| {
+        OpenProposalError::WriteError(format!(
+            "Unable to rename {} to {}: {}",
+            yaml_location, migrated_location, err
+        ))
+    })?;
+    info!(
+        "Imported {} proposals from {}; the file has been renamed to {}",
+        imported, yaml_location, migrated_location
+    );
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1674,6 +3568,7 @@ mod tests {
     use protobuf::{Message, RepeatedField};
 
     use crate::circuit::directory::CircuitDirectory;
+    use crate::circuit::history::verify_history;
     use crate::keys::{
         insecure::AllowAllKeyPermissionManager, storage::StorageKeyRegistry, KeyInfo,
     };
@@ -2521,6 +4416,43 @@ mod tests {
         }
     }
 
+    #[cfg(not(feature = "circuit-durability"))]
+    #[test]
+    // test that if a circuit is durable on a node without durable circuit support, an error is
+    // returned
+    fn test_validate_circuit_durable_unsupported() {
+        let state = setup_splinter_state();
+        let peer_connector = setup_peer_connector();
+        let orchestrator = setup_orchestrator();
+        // set up key registry
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut key_registry = StorageKeyRegistry::new("memory".to_string()).unwrap();
+        let key_info = KeyInfo::builder(pub_key.clone(), "node_a".to_string()).build();
+        key_registry.save_key(key_info).unwrap();
+
+        let admin_shared = AdminServiceShared::new(
+            "node_a".into(),
+            orchestrator,
+            #[cfg(feature = "service-arg-validation")]
+            HashMap::new(),
+            peer_connector,
+            Box::new(MockAuthInquisitor),
+            state,
+            Box::new(HashVerifier),
+            Box::new(key_registry),
+            Box::new(AllowAllKeyPermissionManager),
+            "memory",
+        )
+        .unwrap();
+        let mut circuit = setup_test_circuit();
+
+        circuit.set_durability(Circuit_DurabilityType::DURABLE);
+
+        if let Ok(_) = admin_shared.validate_create_circuit(&circuit, &pub_key, "node_a") {
+            panic!("Should have been invalid because durable circuits are not supported");
+        }
+    }
+
     #[test]
     // test that if a circuit does not have route type set an error is returned
     fn test_validate_circuit_no_routes() {
@@ -2556,6 +4488,61 @@ mod tests {
         }
     }
 
+    #[test]
+    // test that if a relayed circuit has no relays, has a relay that is not a member, or is
+    // durable, an error is returned
+    fn test_validate_circuit_invalid_relays() {
+        let state = setup_splinter_state();
+        let peer_connector = setup_peer_connector();
+        let orchestrator = setup_orchestrator();
+        // set up key registry
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut key_registry = StorageKeyRegistry::new("memory".to_string()).unwrap();
+        let key_info = KeyInfo::builder(pub_key.clone(), "node_a".to_string()).build();
+        key_registry.save_key(key_info).unwrap();
+
+        let admin_shared = AdminServiceShared::new(
+            "node_a".into(),
+            orchestrator,
+            #[cfg(feature = "service-arg-validation")]
+            HashMap::new(),
+            peer_connector,
+            Box::new(MockAuthInquisitor),
+            state,
+            Box::new(HashVerifier),
+            Box::new(key_registry),
+            Box::new(AllowAllKeyPermissionManager),
+            "memory",
+        )
+        .unwrap();
+        let mut circuit = setup_test_circuit();
+
+        circuit.set_routes(Circuit_RouteType::RELAYED_ROUTE);
+
+        if let Ok(_) = admin_shared.validate_create_circuit(&circuit, &pub_key, "node_a") {
+            panic!("Should have been invalid because the relayed circuit has no relays");
+        }
+
+        circuit.set_relays(RepeatedField::from_vec(vec!["node_c".to_string()]));
+
+        if let Ok(_) = admin_shared.validate_create_circuit(&circuit, &pub_key, "node_a") {
+            panic!("Should have been invalid because the relay is not a member");
+        }
+
+        circuit.set_relays(RepeatedField::from_vec(vec!["node_b".to_string()]));
+        circuit.set_durability(Circuit_DurabilityType::DURABLE);
+
+        if let Ok(_) = admin_shared.validate_create_circuit(&circuit, &pub_key, "node_a") {
+            panic!("Should have been invalid because the relayed circuit is durable");
+        }
+
+        circuit.set_durability(Circuit_DurabilityType::NO_DURABILITY);
+
+        if let Err(err) = admin_shared.validate_create_circuit(&circuit, &pub_key, "node_a") {
+            panic!("Should have been valid: {}", err);
+        }
+    }
+
     #[test]
     // test that if a circuit does not have circuit_management_type set an error is returned
     fn test_validate_circuit_no_management_type() {
@@ -3004,6 +4991,905 @@ mod tests {
         }
     }
 
+    #[test]
+    // test that a destroy request from a member of an existing circuit is valid
+    fn test_validate_destroy_circuit_valid() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let circuit = setup_test_circuit();
+        admin_shared.update_splinter_state(&circuit).unwrap();
+
+        if let Err(err) = admin_shared.validate_circuit_change("alpha", &pub_key, "node_a") {
+            panic!("Should have been valid: {}", err);
+        }
+    }
+
+    #[test]
+    // test that a destroy request for a circuit that does not exist is invalid
+    fn test_validate_destroy_circuit_does_not_exist() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let admin_shared = setup_admin_shared_with_key(&pub_key, "node_a");
+
+        if let Ok(_) = admin_shared.validate_circuit_change("alpha", &pub_key, "node_a") {
+            panic!("Should have been invalid because the circuit does not exist");
+        }
+    }
+
+    #[test]
+    // test that a destroy request from a node that is not a member of the circuit is invalid
+    fn test_validate_destroy_circuit_not_member() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_for_node("node_a", &pub_key, "node_c");
+        let circuit = setup_test_circuit();
+        admin_shared.update_splinter_state(&circuit).unwrap();
+
+        if let Ok(_) = admin_shared.validate_circuit_change("alpha", &pub_key, "node_c") {
+            panic!("Should have been invalid because the requester is not a member");
+        }
+    }
+
+    #[test]
+    // test that the proto circuit rebuilt from splinter state matches the committed circuit,
+    // including every endpoint of its members and its application metadata; the order of the
+    // roster is not kept in state
+    fn test_get_circuit_proto() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let mut circuit = setup_test_circuit();
+        let mut members = circuit.get_members().to_vec();
+        for member in members.iter_mut() {
+            let endpoint = member.get_endpoint().to_string();
+            member.set_endpoints(RepeatedField::from_vec(vec![endpoint]));
+        }
+        members[0]
+            .mut_endpoints()
+            .push("test://endpoint_a:1".to_string());
+        circuit.set_members(RepeatedField::from_vec(members));
+        admin_shared.update_splinter_state(&circuit).unwrap();
+
+        let mut roster = circuit.get_roster().to_vec();
+        roster.sort_by(|a, b| a.get_service_id().cmp(b.get_service_id()));
+        circuit.set_roster(RepeatedField::from_vec(roster));
+
+        let mut rebuilt = admin_shared.get_circuit_proto("alpha").unwrap();
+        let mut roster = rebuilt.get_roster().to_vec();
+        roster.sort_by(|a, b| a.get_service_id().cmp(b.get_service_id()));
+        rebuilt.set_roster(RepeatedField::from_vec(roster));
+
+        assert_eq!(circuit, rebuilt);
+    }
+
+    #[test]
+    // test that abandoning a circuit removes it and its members from splinter state
+    fn test_abandon_circuit() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let circuit = setup_test_circuit();
+        admin_shared.update_splinter_state(&circuit).unwrap();
+
+        admin_shared
+            .validate_abandon_circuit("alpha", &pub_key, "node_a")
+            .expect("Should have been valid");
+        admin_shared
+            .abandon_circuit("alpha")
+            .expect("Unable to abandon circuit");
+
+        assert!(!admin_shared.splinter_state.has_circuit("alpha").unwrap());
+        assert!(admin_shared
+            .splinter_state
+            .node("node_b")
+            .unwrap()
+            .is_none());
+        assert!(!admin_shared
+            .splinter_state
+            .has_service(&ServiceId::new("alpha".into(), "service_b".into()))
+            .unwrap());
+    }
+
+    #[test]
+    // test that a circuit can only be abandoned on behalf of the local node
+    fn test_validate_abandon_circuit_remote_node() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_for_node("node_a", &pub_key, "node_b");
+        let circuit = setup_test_circuit();
+        admin_shared.update_splinter_state(&circuit).unwrap();
+
+        if let Ok(_) = admin_shared.validate_abandon_circuit("alpha", &pub_key, "node_b") {
+            panic!("Should have been invalid because node_b is not the local node");
+        }
+    }
+
+    #[test]
+    // test that when another member abandons a circuit, the remaining members remove it and its
+    // services from the circuit but keep the circuit
+    fn test_handle_abandoned_circuit() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_b");
+        let circuit = setup_test_circuit();
+        admin_shared.update_splinter_state(&circuit).unwrap();
+
+        admin_shared
+            .handle_abandoned_circuit("alpha", "node_a", &admin_service_id("node_a"))
+            .expect("Unable to handle abandoned circuit");
+
+        let state_circuit = admin_shared
+            .splinter_state
+            .circuit("alpha")
+            .unwrap()
+            .expect("Circuit should not have been removed");
+        assert!(!state_circuit.members().contains("node_a"));
+        assert!(state_circuit.members().contains("node_b"));
+        assert!(!admin_shared
+            .splinter_state
+            .has_service(&ServiceId::new("alpha".into(), "service_a".into()))
+            .unwrap());
+        assert!(admin_shared
+            .splinter_state
+            .node("node_a")
+            .unwrap()
+            .is_none());
+
+        let circuit = admin_shared.get_circuit_proto("alpha").unwrap();
+        assert_eq!(circuit.get_members().len(), 1);
+        assert_eq!(circuit.get_roster().len(), 1);
+        assert_eq!(circuit.get_roster()[0].get_service_id(), "service_b");
+    }
+
+    #[test]
+    // test that a member cannot abandon a circuit on behalf of another member
+    fn test_handle_abandoned_circuit_wrong_sender() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_b");
+        let circuit = setup_test_circuit();
+        admin_shared.update_splinter_state(&circuit).unwrap();
+
+        if let Ok(_) =
+            admin_shared.handle_abandoned_circuit("alpha", "node_a", &admin_service_id("node_c"))
+        {
+            panic!("Should have been invalid because node_c cannot abandon for node_a");
+        }
+        assert!(admin_shared
+            .splinter_state
+            .circuit("alpha")
+            .unwrap()
+            .unwrap()
+            .members()
+            .contains("node_a"));
+    }
+
+    #[test]
+    // test that a roster update adding a new service and removing an existing one produces the
+    // expected circuit
+    fn test_build_roster_update_valid() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let circuit = setup_test_circuit();
+        admin_shared.update_splinter_state(&circuit).unwrap();
+
+        let mut service_c = SplinterService::new();
+        service_c.set_service_id("service_c".to_string());
+        service_c.set_service_type("type_a".to_string());
+        service_c.set_allowed_nodes(RepeatedField::from_vec(vec!["node_a".to_string()]));
+
+        let mut update_request = CircuitUpdateRosterRequest::new();
+        update_request.set_circuit_id("alpha".to_string());
+        update_request.set_add_services(RepeatedField::from_vec(vec![service_c]));
+        update_request.set_remove_services(RepeatedField::from_vec(vec![splinter_service(
+            "service_b",
+            "type_a",
+        )]));
+
+        let updated_circuit = admin_shared
+            .build_roster_update(&update_request)
+            .expect("Should have been valid");
+
+        let mut service_ids = updated_circuit
+            .get_roster()
+            .iter()
+            .map(|service| service.get_service_id().to_string())
+            .collect::<Vec<_>>();
+        service_ids.sort();
+        assert_eq!(
+            service_ids,
+            vec!["service_a".to_string(), "service_c".to_string()]
+        );
+    }
+
+    #[test]
+    // test that a roster update cannot remove a service that is not in the roster or add a
+    // service that already is
+    fn test_build_roster_update_invalid() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let circuit = setup_test_circuit();
+        admin_shared.update_splinter_state(&circuit).unwrap();
+
+        let mut update_request = CircuitUpdateRosterRequest::new();
+        update_request.set_circuit_id("alpha".to_string());
+        update_request.set_remove_services(RepeatedField::from_vec(vec![splinter_service(
+            "service_c",
+            "type_a",
+        )]));
+        if let Ok(_) = admin_shared.build_roster_update(&update_request) {
+            panic!("Should have been invalid because service_c is not in the roster");
+        }
+
+        let mut update_request = CircuitUpdateRosterRequest::new();
+        update_request.set_circuit_id("alpha".to_string());
+        update_request.set_add_services(RepeatedField::from_vec(vec![
+            circuit.get_roster()[0].clone()
+        ]));
+        if let Ok(_) = admin_shared.build_roster_update(&update_request) {
+            panic!("Should have been invalid because the service is already in the roster");
+        }
+    }
+
+    #[test]
+    // test that committing a roster update replaces the roster in splinter state and updates the
+    // service directory
+    fn test_commit_update_roster() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let circuit = setup_test_circuit();
+        admin_shared.update_splinter_state(&circuit).unwrap();
+
+        let mut update_request = CircuitUpdateRosterRequest::new();
+        update_request.set_circuit_id("alpha".to_string());
+        update_request.set_remove_services(RepeatedField::from_vec(vec![splinter_service(
+            "service_b",
+            "type_a",
+        )]));
+        let updated_circuit = admin_shared.build_roster_update(&update_request).unwrap();
+
+        let mut proposal = setup_test_proposal(&updated_circuit);
+        proposal.set_proposal_type(CircuitProposal_ProposalType::UPDATE_ROSTER);
+        admin_shared
+            .commit_update_roster(proposal, pub_key.clone())
+            .expect("Unable to commit roster update");
+
+        let state_circuit = admin_shared
+            .splinter_state
+            .circuit("alpha")
+            .unwrap()
+            .unwrap();
+        assert!(state_circuit.roster().contains("service_a"));
+        assert!(!state_circuit.roster().contains("service_b"));
+        assert!(!admin_shared
+            .splinter_state
+            .has_service(&ServiceId::new("alpha".into(), "service_b".into()))
+            .unwrap());
+    }
+
+    #[test]
+    // test that adding a new node to a circuit produces the expected circuit and that a node
+    // that is already a member cannot be added again
+    fn test_build_add_node() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let circuit = setup_test_circuit();
+        admin_shared.update_splinter_state(&circuit).unwrap();
+
+        let mut add_node_request = CircuitUpdateAddNodeRequest::new();
+        add_node_request.set_circuit_id("alpha".to_string());
+        add_node_request.set_node(splinter_node("node_c", "test://endpoint_c:0"));
+
+        let updated_circuit = admin_shared
+            .build_add_node(&add_node_request)
+            .expect("Should have been valid");
+        let member_ids = updated_circuit
+            .get_members()
+            .iter()
+            .map(|member| member.get_node_id().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            member_ids,
+            vec![
+                "node_a".to_string(),
+                "node_b".to_string(),
+                "node_c".to_string()
+            ]
+        );
+
+        add_node_request.set_node(splinter_node("node_b", "test://endpoint_b:0"));
+        if let Ok(_) = admin_shared.build_add_node(&add_node_request) {
+            panic!("Should have been invalid because node_b is already a member");
+        }
+    }
+
+    #[test]
+    // test that a node being added to a circuit can validate the change using the circuit
+    // definition it received with the proposal
+    fn test_add_joining_circuit() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_for_node("node_a", &pub_key, "node_b");
+        let mut circuit = setup_test_circuit();
+        circuit.set_members(RepeatedField::from_vec(vec![
+            splinter_node("node_b", "test://endpoint_b:0"),
+            splinter_node("node_c", "test://endpoint_c:0"),
+        ]));
+
+        if let Ok(_) = admin_shared.validate_circuit_change("alpha", &pub_key, "node_b") {
+            panic!("Should have been invalid because the circuit is unknown");
+        }
+
+        let mut add_node_request = CircuitUpdateAddNodeRequest::new();
+        add_node_request.set_circuit_id("alpha".to_string());
+        add_node_request.set_node(splinter_node("node_a", "test://endpoint_a:0"));
+        let mut header = admin::CircuitManagementPayload_Header::new();
+        header.set_action(CircuitManagementPayload_Action::CIRCUIT_UPDATE_ADD_NODE);
+        header.set_requester(pub_key.clone());
+        header.set_requester_node_id("node_b".to_string());
+        let mut payload = CircuitManagementPayload::new();
+        payload.set_header(protobuf::Message::write_to_bytes(&header).unwrap());
+        payload.set_action(CircuitManagementPayload_Action::CIRCUIT_UPDATE_ADD_NODE);
+        payload.set_circuit_update_add_node(add_node_request);
+
+        if let Ok(_) = admin_shared.add_joining_circuit(circuit.clone(), &payload, "admin::node_d")
+        {
+            panic!("Should have been invalid because node_d is not a member of the circuit");
+        }
+
+        admin_shared
+            .add_joining_circuit(circuit.clone(), &payload, "admin::node_b")
+            .expect("Unable to add joining circuit");
+        admin_shared
+            .validate_circuit_change("alpha", &pub_key, "node_b")
+            .expect("Should have been valid");
+        assert!(!admin_shared.splinter_state.has_circuit("alpha").unwrap());
+
+        let mut other_circuit = circuit.clone();
+        other_circuit.set_application_metadata(b"other_data".to_vec());
+        if let Ok(_) = admin_shared.add_joining_circuit(other_circuit, &payload, "admin::node_c") {
+            panic!("Should have been invalid because a different definition was received");
+        }
+
+        let member_ids = admin_shared
+            .get_payload_members(&payload)
+            .expect("Unable to get payload members")
+            .iter()
+            .map(|member| member.get_node_id().to_string())
+            .collect::<Vec<_>>();
+        assert!(member_ids.contains(&"node_a".to_string()));
+
+        // the definition is discarded once the proposal to add this node fails
+        admin_shared
+            .discard_joining_circuit(&payload)
+            .expect("Unable to discard joining circuit");
+        if let Ok(_) = admin_shared.validate_circuit_change("alpha", &pub_key, "node_b") {
+            panic!("Should have been invalid because the circuit definition was discarded");
+        }
+    }
+
+    #[test]
+    // test that removing a node from a circuit also removes the services that run on it, and that
+    // a node that is not a member cannot be removed
+    fn test_build_remove_node() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let circuit = setup_test_circuit();
+        admin_shared.update_splinter_state(&circuit).unwrap();
+
+        let mut remove_node_request = CircuitUpdateRemoveNodeRequest::new();
+        remove_node_request.set_circuit_id("alpha".to_string());
+        remove_node_request.set_node_id("node_b".to_string());
+
+        let updated_circuit = admin_shared
+            .build_remove_node(&remove_node_request)
+            .expect("Should have been valid");
+        assert_eq!(updated_circuit.get_members().len(), 1);
+        assert_eq!(updated_circuit.get_members()[0].get_node_id(), "node_a");
+        assert_eq!(updated_circuit.get_roster().len(), 1);
+        assert_eq!(
+            updated_circuit.get_roster()[0].get_service_id(),
+            "service_a"
+        );
+
+        remove_node_request.set_node_id("node_c".to_string());
+        if let Ok(_) = admin_shared.build_remove_node(&remove_node_request) {
+            panic!("Should have been invalid because node_c is not a member");
+        }
+    }
+
+    #[test]
+    // test that committing the removal of a remote node removes its services from state, and that
+    // committing the removal of the local node removes the circuit
+    fn test_commit_remove_node() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let circuit = setup_test_circuit();
+        admin_shared.update_splinter_state(&circuit).unwrap();
+
+        let mut remove_node_request = CircuitUpdateRemoveNodeRequest::new();
+        remove_node_request.set_circuit_id("alpha".to_string());
+        remove_node_request.set_node_id("node_b".to_string());
+        let updated_circuit = admin_shared
+            .build_remove_node(&remove_node_request)
+            .unwrap();
+
+        let mut proposal = setup_test_proposal(&updated_circuit);
+        proposal.set_proposal_type(CircuitProposal_ProposalType::REMOVE_NODE);
+        admin_shared
+            .commit_remove_node(proposal, pub_key.clone())
+            .expect("Unable to commit node removal");
+
+        let state_circuit = admin_shared
+            .splinter_state
+            .circuit("alpha")
+            .unwrap()
+            .unwrap();
+        assert!(!state_circuit.members().contains("node_b"));
+        assert!(!admin_shared
+            .splinter_state
+            .has_service(&ServiceId::new("alpha".into(), "service_b".into()))
+            .unwrap());
+        assert!(admin_shared
+            .splinter_state
+            .node("node_b")
+            .unwrap()
+            .is_none());
+
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_b");
+        admin_shared.update_splinter_state(&circuit).unwrap();
+        let updated_circuit = admin_shared
+            .build_remove_node(&remove_node_request)
+            .unwrap();
+
+        let mut proposal = setup_test_proposal(&updated_circuit);
+        proposal.set_proposal_type(CircuitProposal_ProposalType::REMOVE_NODE);
+        admin_shared
+            .commit_remove_node(proposal, pub_key)
+            .expect("Unable to commit node removal");
+
+        assert!(!admin_shared.splinter_state.has_circuit("alpha").unwrap());
+    }
+
+    #[test]
+    // test that an application metadata update replaces the metadata of the circuit, and that an
+    // update that does not change the metadata is invalid
+    fn test_build_application_metadata_update() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let circuit = setup_test_circuit();
+        admin_shared.update_splinter_state(&circuit).unwrap();
+
+        let mut update_request = CircuitUpdateApplicationMetadataRequest::new();
+        update_request.set_circuit_id("alpha".to_string());
+        update_request.set_application_metedata(b"new_test_data".to_vec());
+
+        let updated_circuit = admin_shared
+            .build_application_metadata_update(&update_request)
+            .expect("Should have been valid");
+        assert_eq!(updated_circuit.get_application_metadata(), b"new_test_data");
+
+        update_request.set_application_metedata(b"test_data".to_vec());
+        if let Ok(_) = admin_shared.build_application_metadata_update(&update_request) {
+            panic!("Should have been invalid because the metadata is unchanged");
+        }
+    }
+
+    #[test]
+    // test that committing an application metadata update persists the new metadata
+    fn test_commit_update_application_metadata() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let circuit = setup_test_circuit();
+        admin_shared.update_splinter_state(&circuit).unwrap();
+
+        let mut update_request = CircuitUpdateApplicationMetadataRequest::new();
+        update_request.set_circuit_id("alpha".to_string());
+        update_request.set_application_metedata(b"new_test_data".to_vec());
+        let updated_circuit = admin_shared
+            .build_application_metadata_update(&update_request)
+            .unwrap();
+
+        let mut proposal = setup_test_proposal(&updated_circuit);
+        proposal.set_proposal_type(CircuitProposal_ProposalType::UPDATE_APPLICATION_METADATA);
+        admin_shared
+            .commit_update_application_metadata(proposal, pub_key)
+            .expect("Unable to commit application metadata update");
+
+        let state_circuit = admin_shared
+            .splinter_state
+            .circuit("alpha")
+            .unwrap()
+            .unwrap();
+        assert_eq!(state_circuit.application_metadata(), b"new_test_data");
+    }
+
+    #[test]
+    // test that a proposal is rejected if the requester sets an approval policy that differs from
+    // the policy this node enforces for the circuit management type
+    fn test_propose_change_rejects_mismatched_approval_policy() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_b");
+
+        let mut request = admin::CircuitCreateRequest::new();
+        request.set_circuit(setup_test_circuit());
+
+        let mut header = admin::CircuitManagementPayload_Header::new();
+        header.set_action(admin::CircuitManagementPayload_Action::CIRCUIT_CREATE_REQUEST);
+        header.set_requester(pub_key);
+        header.set_requester_node_id("node_b".to_string());
+        let mut payload = admin::CircuitManagementPayload::new();
+        payload.set_header(protobuf::Message::write_to_bytes(&header).unwrap());
+        payload.set_circuit_create_request(request);
+        payload
+            .set_approval_policy(messages::ApprovalPolicy::Threshold { threshold: 1 }.into_proto());
+        payload.set_signature(HashSigner.sign(&payload.header).unwrap());
+
+        assert!(admin_shared
+            .validate_approval_policy(&payload, &header)
+            .is_err());
+        match admin_shared.propose_change(payload.clone()) {
+            Err(AdminSharedError::ValidationFailed(_)) => (),
+            res => panic!("Expected ValidationFailed, got {:?}", res),
+        }
+
+        payload.set_approval_policy(messages::ApprovalPolicy::Unanimous.into_proto());
+        assert!(admin_shared
+            .validate_approval_policy(&payload, &header)
+            .is_ok());
+    }
+
+    #[test]
+    // test that a proposal is rejected if the threshold of its approval policy is 0, so that it
+    // would be approved without any votes, or is more than the total weight of the members, so
+    // that it could never be approved
+    fn test_validate_approval_policy_threshold_bounds() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_b");
+
+        let mut request = admin::CircuitCreateRequest::new();
+        request.set_circuit(setup_test_circuit());
+
+        let mut header = admin::CircuitManagementPayload_Header::new();
+        header.set_action(admin::CircuitManagementPayload_Action::CIRCUIT_CREATE_REQUEST);
+        header.set_requester(pub_key);
+        header.set_requester_node_id("node_b".to_string());
+        let mut payload = admin::CircuitManagementPayload::new();
+        payload.set_header(protobuf::Message::write_to_bytes(&header).unwrap());
+        payload.set_circuit_create_request(request);
+
+        let mut check = |policy: messages::ApprovalPolicy| {
+            admin_shared
+                .approval_policies
+                .insert("test_circuit".to_string(), policy.clone());
+            payload.set_approval_policy(policy.into_proto());
+            admin_shared.validate_approval_policy(&payload, &header)
+        };
+
+        assert!(check(messages::ApprovalPolicy::Threshold { threshold: 0 }).is_err());
+        assert!(check(messages::ApprovalPolicy::Threshold { threshold: 3 }).is_err());
+        assert!(check(messages::ApprovalPolicy::Threshold { threshold: 2 }).is_ok());
+
+        let weights: BTreeMap<String, u32> = vec![("node_a".to_string(), 2)].into_iter().collect();
+        assert!(check(messages::ApprovalPolicy::Weighted {
+            threshold: 0,
+            weights: BTreeMap::new(),
+        })
+        .is_err());
+        assert!(check(messages::ApprovalPolicy::Weighted {
+            threshold: 4,
+            weights: weights.clone(),
+        })
+        .is_err());
+        assert!(check(messages::ApprovalPolicy::Weighted {
+            threshold: 3,
+            weights,
+        })
+        .is_ok());
+    }
+
+    #[test]
+    // test that proposals are approved or rejected according to the approval policy recorded in
+    // the proposal; the requester, node_b, counts as approving
+    fn test_check_approved_policies() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let admin_shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let mut circuit = setup_test_circuit();
+        let mut members = circuit.take_members().into_vec();
+        members.push(splinter_node("node_c", "test://endpoint_c:0"));
+        circuit.set_members(RepeatedField::from_vec(members));
+
+        let check = |policy: messages::ApprovalPolicy,
+                     votes: &[(&str, CircuitProposalVote_Vote)]|
+         -> CircuitProposalStatus {
+            let mut proposal = setup_test_proposal(&circuit);
+            proposal.set_approval_policy(policy.into_proto());
+            for (node_id, vote) in votes {
+                let mut vote_record = CircuitProposal_VoteRecord::new();
+                vote_record.set_vote(*vote);
+                vote_record.set_voter_node_id(node_id.to_string());
+                proposal.votes.push(vote_record);
+            }
+            admin_shared.check_approved(&proposal).unwrap()
+        };
+
+        let accept_a = ("node_a", CircuitProposalVote_Vote::ACCEPT);
+        let accept_c = ("node_c", CircuitProposalVote_Vote::ACCEPT);
+        let reject_c = ("node_c", CircuitProposalVote_Vote::REJECT);
+
+        let unanimous = messages::ApprovalPolicy::Unanimous;
+        assert_eq!(
+            check(unanimous.clone(), &[accept_a]),
+            CircuitProposalStatus::Pending
+        );
+        assert_eq!(
+            check(unanimous.clone(), &[accept_a, accept_c]),
+            CircuitProposalStatus::Accepted
+        );
+        assert_eq!(
+            check(unanimous, &[accept_a, reject_c]),
+            CircuitProposalStatus::Rejected
+        );
+
+        let majority = messages::ApprovalPolicy::Majority;
+        assert_eq!(check(majority.clone(), &[]), CircuitProposalStatus::Pending);
+        assert_eq!(
+            check(majority, &[accept_a, reject_c]),
+            CircuitProposalStatus::Accepted
+        );
+
+        let threshold = messages::ApprovalPolicy::Threshold { threshold: 3 };
+        assert_eq!(
+            check(threshold.clone(), &[accept_a]),
+            CircuitProposalStatus::Pending
+        );
+        assert_eq!(
+            check(threshold, &[reject_c]),
+            CircuitProposalStatus::Rejected
+        );
+
+        let weighted = messages::ApprovalPolicy::Weighted {
+            threshold: 4,
+            weights: vec![("node_c".to_string(), 3)].into_iter().collect(),
+        };
+        assert_eq!(
+            check(weighted.clone(), &[accept_a]),
+            CircuitProposalStatus::Pending
+        );
+        assert_eq!(
+            check(weighted, &[accept_c]),
+            CircuitProposalStatus::Accepted
+        );
+
+        let requester_plus_one = messages::ApprovalPolicy::RequesterPlusOne;
+        assert_eq!(
+            check(requester_plus_one.clone(), &[]),
+            CircuitProposalStatus::Pending
+        );
+        assert_eq!(
+            check(requester_plus_one, &[reject_c, accept_a]),
+            CircuitProposalStatus::Accepted
+        );
+    }
+
+    #[test]
+    // test that a vote for a proposal that has passed its expiration time is invalid
+    fn test_validate_proposal_vote_expired() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let admin_shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let circuit = setup_test_circuit();
+        let vote = setup_test_vote(&circuit);
+        let mut proposal = setup_test_proposal(&circuit);
+        proposal.set_expires_at(current_time_secs() + 60);
+
+        if let Err(err) = admin_shared.validate_circuit_vote(&vote, &pub_key, &proposal, "node_a") {
+            panic!("Should have been valid: {}", err);
+        }
+
+        proposal.set_expires_at(1);
+        if let Ok(_) = admin_shared.validate_circuit_vote(&vote, &pub_key, &proposal, "node_a") {
+            panic!("Should have been invalid because the proposal has expired");
+        }
+    }
+
+    #[test]
+    // test that a proposal's expiration time may not have passed, nor be too far away
+    fn test_validate_expiry() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let admin_shared = setup_admin_shared_with_key(&pub_key, "node_a");
+
+        if let Err(err) = admin_shared.validate_expiry(0) {
+            panic!(
+                "A proposal without an expiration time should be valid: {}",
+                err
+            );
+        }
+
+        if let Err(err) = admin_shared.validate_expiry(current_time_secs() + 60) {
+            panic!("Should have been valid: {}", err);
+        }
+
+        if let Ok(_) = admin_shared.validate_expiry(1) {
+            panic!("Should have been invalid because the expiration time has passed");
+        }
+
+        if let Ok(_) =
+            admin_shared.validate_expiry(current_time_secs() + MAX_PROPOSAL_TTL_SECS + 60)
+        {
+            panic!("Should have been invalid because the expiration time is too far away");
+        }
+    }
+
+    #[test]
+    // test that only the requester of an open proposal can withdraw it, and that committing the
+    // withdrawal removes the proposal
+    fn test_withdraw_proposal() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_b");
+        let circuit = setup_test_circuit();
+        let mut proposal = setup_test_proposal(&circuit);
+        proposal.set_requester(pub_key.clone());
+
+        let mut withdraw = CircuitProposalWithdraw::new();
+        withdraw.set_circuit_id("alpha".to_string());
+        withdraw.set_circuit_hash(proposal.get_circuit_hash().to_string());
+
+        // the proposal does not exist yet
+        assert!(admin_shared
+            .validate_proposal_withdraw(&withdraw, &pub_key)
+            .is_err());
+
+        admin_shared.add_proposal(proposal).unwrap();
+
+        let other_key = (1u8..34).collect::<Vec<_>>();
+        assert!(admin_shared
+            .validate_proposal_withdraw(&withdraw, &other_key)
+            .is_err());
+
+        let mut mismatched_withdraw = withdraw.clone();
+        mismatched_withdraw.set_circuit_hash("bad_hash".to_string());
+        assert!(admin_shared
+            .validate_proposal_withdraw(&mismatched_withdraw, &pub_key)
+            .is_err());
+
+        let withdrawn_proposal = admin_shared
+            .validate_proposal_withdraw(&withdraw, &pub_key)
+            .expect("Withdrawal should have been valid");
+        assert_eq!(withdrawn_proposal.get_circuit_id(), "alpha");
+
+        admin_shared
+            .commit_withdraw_proposal(withdrawn_proposal)
+            .expect("Unable to commit withdrawal");
+        assert_eq!(admin_shared.get_proposal("alpha").unwrap(), None);
+    }
+
+    #[test]
+    // test that expiring proposals removes only the open proposals that have passed their
+    // expiration time
+    fn test_expire_proposals() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_a");
+
+        let circuit = setup_test_circuit();
+        let mut expired_proposal = setup_test_proposal(&circuit);
+        expired_proposal.set_expires_at(1);
+        admin_shared.add_proposal(expired_proposal).unwrap();
+
+        let mut circuit = setup_test_circuit();
+        circuit.set_circuit_id("beta".to_string());
+        let mut open_proposal = setup_test_proposal(&circuit);
+        open_proposal.set_expires_at(current_time_secs() + 60);
+        admin_shared.add_proposal(open_proposal).unwrap();
+
+        let mut circuit = setup_test_circuit();
+        circuit.set_circuit_id("gamma".to_string());
+        admin_shared
+            .add_proposal(setup_test_proposal(&circuit))
+            .unwrap();
+
+        admin_shared
+            .expire_proposals()
+            .expect("Unable to expire proposals");
+
+        assert_eq!(admin_shared.get_proposal("alpha").unwrap(), None);
+        assert!(admin_shared.get_proposal("beta").unwrap().is_some());
+        assert!(admin_shared.get_proposal("gamma").unwrap().is_some());
+    }
+
+    #[test]
+    // test that each accepted proposal adds a revision to the circuit's history, chained to the
+    // previous revision and recording the votes and the resulting circuit definition
+    fn test_circuit_revision_history() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut admin_shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let circuit = setup_test_circuit();
+
+        let mut vote_record = CircuitProposal_VoteRecord::new();
+        vote_record.set_vote(CircuitProposalVote_Vote::ACCEPT);
+        vote_record.set_public_key(pub_key.clone());
+        vote_record.set_voter_node_id("node_a".to_string());
+
+        let mut proposal = setup_test_proposal(&circuit);
+        proposal.votes.push(vote_record.clone());
+        admin_shared.pending_changes = Some(CircuitProposalContext {
+            circuit_proposal: proposal,
+            action: CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_VOTE,
+            signer_public_key: pub_key.clone(),
+        });
+        admin_shared.commit().expect("Unable to commit circuit");
+
+        let mut update_request = CircuitUpdateApplicationMetadataRequest::new();
+        update_request.set_circuit_id("alpha".to_string());
+        update_request.set_application_metedata(b"new_test_data".to_vec());
+        let updated_circuit = admin_shared
+            .build_application_metadata_update(&update_request)
+            .unwrap();
+
+        let mut proposal = setup_test_proposal(&updated_circuit);
+        proposal.set_proposal_type(CircuitProposal_ProposalType::UPDATE_APPLICATION_METADATA);
+        proposal.votes.push(vote_record);
+        admin_shared.pending_changes = Some(CircuitProposalContext {
+            circuit_proposal: proposal,
+            action: CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_VOTE,
+            signer_public_key: pub_key.clone(),
+        });
+        admin_shared
+            .commit()
+            .expect("Unable to commit application metadata update");
+
+        let history = admin_shared
+            .splinter_state
+            .circuit_history("alpha")
+            .unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(verify_history(&history));
+
+        assert_eq!(history[0].proposal_type(), "Create");
+        assert_eq!(history[0].circuit_hash(), sha256(&circuit).unwrap());
+        assert_eq!(history[0].requester_node_id(), "node_b");
+        assert_eq!(history[0].votes().len(), 1);
+        assert_eq!(history[0].votes()[0].voter_node_id(), "node_a");
+        assert_eq!(history[0].votes()[0].public_key(), to_hex(&pub_key));
+        assert!(history[0].votes()[0].accept());
+
+        assert_eq!(history[1].proposal_type(), "UpdateApplicationMetadata");
+        assert_eq!(history[1].circuit_hash(), sha256(&updated_circuit).unwrap());
+        assert_eq!(history[1].previous_hash(), Some(history[0].circuit_hash()));
+        assert_eq!(
+            history[1]
+                .circuit()
+                .expect("Revision has no circuit")
+                .application_metadata(),
+            b"new_test_data"
+        );
+    }
+
+    fn setup_admin_shared_with_key(pub_key: &[u8], node_id: &str) -> AdminServiceShared {
+        setup_admin_shared_for_node(node_id, pub_key, node_id)
+    }
+
+    // set up the admin service of the given local node, with the public key registered to the
+    // given node, which may be a remote node
+    fn setup_admin_shared_for_node(
+        local_node_id: &str,
+        pub_key: &[u8],
+        key_node_id: &str,
+    ) -> AdminServiceShared {
+        let state = setup_splinter_state();
+        let peer_connector = setup_peer_connector();
+        let orchestrator = setup_orchestrator();
+        let mut key_registry = StorageKeyRegistry::new("memory".to_string()).unwrap();
+        let key_info = KeyInfo::builder(pub_key.to_vec(), key_node_id.to_string()).build();
+        key_registry.save_key(key_info).unwrap();
+
+        AdminServiceShared::new(
+            local_node_id.into(),
+            orchestrator,
+            #[cfg(feature = "service-arg-validation")]
+            HashMap::new(),
+            peer_connector,
+            Box::new(MockAuthInquisitor),
+            state,
+            Box::new(HashVerifier),
+            Box::new(key_registry),
+            Box::new(AllowAllKeyPermissionManager),
+            "memory",
+        )
+        .unwrap()
+    }
+
     pub fn setup_test_circuit() -> Circuit {
         let mut service_a = SplinterService::new();
         service_a.set_service_id("service_a".to_string());
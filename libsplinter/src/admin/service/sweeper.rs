@@ -0,0 +1,79 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{Builder, JoinHandle};
+use std::time::Duration;
+
+use super::error::ProposalSweeperError;
+use super::shared::AdminServiceShared;
+
+/// Component used by the service to periodically remove expired proposals
+pub struct ProposalSweeper {
+    shutdown_tx: Sender<()>,
+    thread_handle: JoinHandle<()>,
+}
+
+impl ProposalSweeper {
+    /// Start a thread that checks the open proposals for expired proposals at the given interval.
+    pub fn new(
+        service_id: String,
+        shared: Arc<Mutex<AdminServiceShared>>,
+        interval: Duration,
+    ) -> Result<Self, ProposalSweeperError> {
+        let (shutdown_tx, shutdown_rx) = channel();
+
+        let thread_handle = Builder::new()
+            .name(format!("proposal-sweeper-{}", service_id))
+            .spawn(move || loop {
+                match shutdown_rx.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => (),
+                    // either shutdown was requested or the sweeper was dropped
+                    _ => break,
+                }
+
+                match shared.lock() {
+                    Ok(mut shared) => {
+                        if let Err(err) = shared.expire_proposals() {
+                            error!("Unable to expire proposals: {}", err);
+                        }
+                    }
+                    Err(_) => {
+                        error!("admin shared lock was poisoned; stopping proposal sweeper");
+                        break;
+                    }
+                }
+            })
+            .map_err(|err| ProposalSweeperError(Box::new(err)))?;
+
+        Ok(ProposalSweeper {
+            shutdown_tx,
+            thread_handle,
+        })
+    }
+
+    /// Consumes self and shuts down the sweeper thread.
+    pub fn shutdown(self) -> Result<(), ProposalSweeperError> {
+        self.shutdown_tx
+            .send(())
+            .map_err(|err| ProposalSweeperError(Box::new(err)))?;
+
+        self.thread_handle
+            .join()
+            .unwrap_or_else(|err| error!("proposal sweeper thread failed: {:?}", err));
+
+        Ok(())
+    }
+}
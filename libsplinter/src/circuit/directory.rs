@@ -15,6 +15,7 @@ use std::collections::BTreeMap;
 
 use serde_derive::{Deserialize, Serialize};
 
+use crate::circuit::history::{append_revision, link_revision, push_revision, CircuitRevision};
 use crate::circuit::service::SplinterNode;
 use crate::circuit::Circuit;
 
@@ -25,6 +26,10 @@ pub struct CircuitDirectory {
     nodes: BTreeMap<String, SplinterNode>,
     circuits: BTreeMap<String, Circuit>,
 
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    circuit_history: BTreeMap<String, Vec<CircuitRevision>>,
+
     #[serde(skip_serializing)]
     #[serde(default = "Circuit::new_admin")]
     admin_circuit: Circuit,
@@ -41,6 +46,7 @@ impl CircuitDirectory {
         CircuitDirectory {
             nodes: BTreeMap::new(),
             circuits: BTreeMap::new(),
+            circuit_history: BTreeMap::new(),
             admin_circuit: Circuit::new_admin(),
         }
     }
@@ -53,6 +59,38 @@ impl CircuitDirectory {
         self.circuits.insert(name, circuit);
     }
 
+    /// Appends a revision to the history of the given circuit. The history is kept after the
+    /// circuit is removed.
+    pub fn add_circuit_revision(&mut self, circuit_id: String, revision: CircuitRevision) {
+        append_revision(
+            self.circuit_history
+                .entry(circuit_id)
+                .or_insert_with(Vec::new),
+            revision,
+        );
+    }
+
+    /// Appends a revision that has already been numbered and linked, such as one loaded from
+    /// storage, to the history of the given circuit.
+    pub fn add_linked_circuit_revision(&mut self, circuit_id: String, revision: CircuitRevision) {
+        push_revision(
+            self.circuit_history
+                .entry(circuit_id)
+                .or_insert_with(Vec::new),
+            revision,
+        );
+    }
+
+    /// Returns the revision as it would be appended to the history of the given circuit: numbered
+    /// after, and linked to, the circuit's last revision.
+    pub fn next_circuit_revision(
+        &self,
+        circuit_id: &str,
+        revision: CircuitRevision,
+    ) -> CircuitRevision {
+        link_revision(self.circuit_history(circuit_id).unwrap_or(&[]), revision)
+    }
+
     pub fn remove_node(&mut self, id: &str) {
         self.nodes.remove(id);
     }
@@ -84,4 +122,13 @@ impl CircuitDirectory {
     pub fn has_circuit(&self, circuit_name: &str) -> bool {
         self.circuits.contains_key(circuit_name)
     }
+
+    pub fn circuit_history(&self, circuit_id: &str) -> Option<&[CircuitRevision]> {
+        self.circuit_history.get(circuit_id).map(Vec::as_slice)
+    }
+
+    /// Returns the history of every circuit, including circuits that have been removed.
+    pub fn circuit_histories(&self) -> &BTreeMap<String, Vec<CircuitRevision>> {
+        &self.circuit_history
+    }
 }
@@ -0,0 +1,990 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Store-and-forward delivery of direct messages on durable circuits.
+//!
+//! A direct message sent on a circuit with `DurabilityType::Durable` to a service on another node
+//! is added to the outbound queue of that node before it is sent. It stays queued until the node
+//! acknowledges it with a `CircuitDirectMessageAck`, and is sent again when the node reconnects,
+//! or when it has not been acknowledged within a retry interval. The node receiving the message
+//! remembers the messages it has delivered, by circuit, sender and correlation ID, so that a
+//! message sent more than once is only delivered to its recipient once.
+//!
+//! When the queues are opened in a directory, each node's queue is kept in a file named after the
+//! hex-encoded node ID, so that queued messages survive a restart. A queue file is a log of
+//! records, each of which is a big-endian u32 length followed by that many bytes of a
+//! `QueueRecord` protobuf message, which either adds a message to the queue or acknowledges one.
+//! The file is only rewritten, without the acknowledged messages, once they outnumber the queued
+//! ones. The messages delivered on this node are likewise logged to a file of `MessageId`
+//! records, so that they are not delivered again after a restart.
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use protobuf::Message;
+
+use crate::channel::Sender;
+use crate::circuit::handlers::create_message;
+use crate::hex::{parse_hex, to_hex};
+use crate::network::sender::SendRequest;
+use crate::protos::circuit::{CircuitDirectMessage, CircuitDirectMessageAck, CircuitMessageType};
+use crate::protos::durable::{MessageId, QueueRecord, QueuedMessage as QueuedMessageProto};
+
+const QUEUE_FILE_EXTENSION: &str = "queue";
+const DELIVERED_FILE_NAME: &str = "delivered.log";
+const DEFAULT_MAX_DEPTH: usize = 10_000;
+const DEFAULT_MAX_DELIVERED: usize = 100_000;
+// The number of acknowledged messages a queue file may hold before it is rewritten without them,
+// as long as they outnumber the queued messages
+const COMPACTION_THRESHOLD: usize = 1_000;
+
+/// The outbound queues of direct messages on durable circuits, by the ID of the node each
+/// message is sent to, and the record of the messages delivered to services on this node.
+#[derive(Clone)]
+pub struct DurableQueues {
+    shared: Arc<Mutex<SharedQueues>>,
+}
+
+impl DurableQueues {
+    /// Creates empty queues, kept in memory.
+    pub fn new() -> Self {
+        DurableQueues {
+            shared: Arc::new(Mutex::new(SharedQueues::new(None))),
+        }
+    }
+
+    /// Opens the queues kept in the given directory, creating it if it does not exist, and loads
+    /// the messages queued before the node was stopped.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self, DurableQueueError> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+
+        let mut shared = SharedQueues::new(Some(dir.to_path_buf()));
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(QUEUE_FILE_EXTENSION) {
+                continue;
+            }
+
+            let recipient = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| parse_hex(stem).ok())
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .ok_or_else(|| {
+                    DurableQueueError::InvalidQueue(format!(
+                        "queue file is not named after a node ID: {}",
+                        path.display()
+                    ))
+                })?;
+
+            let queue = read_queue_file(&path)?;
+            if !queue.is_empty() {
+                debug!(
+                    "Loaded {} queued message{} for {}",
+                    queue.len(),
+                    if queue.len() == 1 { "" } else { "s" },
+                    recipient
+                );
+                shared.queues.insert(recipient, queue);
+            }
+        }
+
+        let delivered_path = dir.join(DELIVERED_FILE_NAME);
+        if delivered_path.exists() {
+            for key in read_records::<MessageId>(&delivered_path)? {
+                shared.remember_delivered(MessageKey::from_id(&key));
+                shared.delivered_records += 1;
+            }
+        }
+
+        Ok(DurableQueues {
+            shared: Arc::new(Mutex::new(shared)),
+        })
+    }
+
+    /// Sets the number of messages that may be queued for a node, after which messages sent to
+    /// it are refused with `DurableQueueError::QueueFull`; the default is 10000.
+    pub fn with_max_depth(self, max_depth: usize) -> Self {
+        mutex_lock_unwrap!(self.shared).max_depth = max_depth;
+        self
+    }
+
+    /// Adds the given message to the queue of the node it is sent to. A message that is already
+    /// queued, with the same circuit, sender and correlation ID, is not queued again.
+    pub fn enqueue(
+        &self,
+        recipient: &str,
+        message: &CircuitDirectMessage,
+    ) -> Result<(), DurableQueueError> {
+        let mut shared = mutex_lock_unwrap!(self.shared);
+        let key = MessageKey::from_message(message);
+        let max_depth = shared.max_depth;
+        let queue = shared.queues.entry(recipient.to_string()).or_default();
+
+        if queue.keys.contains(&key) {
+            return Ok(());
+        }
+
+        if queue.len() >= max_depth {
+            return Err(DurableQueueError::QueueFull(recipient.to_string()));
+        }
+
+        let queued = QueuedMessage {
+            key,
+            bytes: message
+                .write_to_bytes()
+                .map_err(|err| DurableQueueError::InvalidQueue(err.to_string()))?,
+            queued_at: SystemTime::now(),
+            last_sent: Some(Instant::now()),
+        };
+
+        if let Some(path) = shared.queue_path(recipient) {
+            let mut record = QueueRecord::new();
+            record.set_queued(queued.to_proto());
+            // The message must be on disk before it is sent, so that it is not lost if the node
+            // stops before it is acknowledged
+            append_record(&path, &record, true)?;
+        }
+
+        shared
+            .queues
+            .entry(recipient.to_string())
+            .or_default()
+            .push_back(queued);
+
+        Ok(())
+    }
+
+    /// Removes the acknowledged message from the queue of the node that acknowledged it, returning
+    /// whether the message was queued.
+    pub fn acknowledge(
+        &self,
+        recipient: &str,
+        ack: &CircuitDirectMessageAck,
+    ) -> Result<bool, DurableQueueError> {
+        let mut shared = mutex_lock_unwrap!(self.shared);
+        let key = MessageKey {
+            circuit: ack.get_circuit().to_string(),
+            sender: ack.get_sender().to_string(),
+            correlation_id: ack.get_correlation_id().to_string(),
+        };
+
+        let queue = match shared.queues.get_mut(recipient) {
+            Some(queue) => queue,
+            None => return Ok(false),
+        };
+        if !queue.remove(&key) {
+            return Ok(false);
+        }
+
+        // the queued record and the acknowledgement
+        queue.stale_records += 2;
+        let empty = queue.is_empty();
+        let compact = empty
+            || (queue.stale_records >= COMPACTION_THRESHOLD && queue.stale_records > queue.len());
+        if compact {
+            queue.stale_records = 0;
+        }
+
+        if compact {
+            shared.write_queue(recipient)?;
+            if empty {
+                shared.queues.remove(recipient);
+            }
+        } else if let Some(path) = shared.queue_path(recipient) {
+            let mut record = QueueRecord::new();
+            record.set_acknowledged(key.to_id());
+            // An acknowledgement lost if the node stops only causes the message to be sent
+            // again, which its recipient recognizes as delivered
+            append_record(&path, &record, false)?;
+        }
+
+        Ok(true)
+    }
+
+    /// Sends every message queued for the given node, such as when it reconnects, returning the
+    /// number of messages sent.
+    pub fn redeliver(
+        &self,
+        recipient: &str,
+        sender: &dyn Sender<SendRequest>,
+    ) -> Result<usize, DurableQueueError> {
+        self.send_queued(recipient, None, sender)
+    }
+
+    /// Sends the messages queued for each of the given nodes that have not been sent within the
+    /// retry interval, returning the number of messages sent.
+    pub fn retry(
+        &self,
+        recipients: &[String],
+        retry_interval: Duration,
+        sender: &dyn Sender<SendRequest>,
+    ) -> Result<usize, DurableQueueError> {
+        recipients.iter().try_fold(0, |sent, recipient| {
+            Ok(sent + self.send_queued(recipient, Some(retry_interval), sender)?)
+        })
+    }
+
+    fn send_queued(
+        &self,
+        recipient: &str,
+        retry_interval: Option<Duration>,
+        sender: &dyn Sender<SendRequest>,
+    ) -> Result<usize, DurableQueueError> {
+        // Copy the messages out, so that the lock is not held while they are sent
+        let messages = {
+            let mut shared = mutex_lock_unwrap!(self.shared);
+            let now = Instant::now();
+            match shared.queues.get_mut(recipient) {
+                Some(queue) => queue
+                    .messages
+                    .iter_mut()
+                    .filter(|queued| match (retry_interval, queued.last_sent) {
+                        (Some(interval), Some(last_sent)) => {
+                            now.duration_since(last_sent) >= interval
+                        }
+                        _ => true,
+                    })
+                    .map(|queued| {
+                        queued.last_sent = Some(now);
+                        queued.bytes.clone()
+                    })
+                    .collect::<Vec<_>>(),
+                None => return Ok(0),
+            }
+        };
+
+        let count = messages.len();
+        for bytes in messages {
+            let network_msg_bytes =
+                create_message(bytes, CircuitMessageType::CIRCUIT_DIRECT_MESSAGE)
+                    .map_err(|err| DurableQueueError::InvalidQueue(err.to_string()))?;
+            sender
+                .send(SendRequest::new(recipient.to_string(), network_msg_bytes))
+                .map_err(|err| DurableQueueError::SendError(err.to_string()))?;
+        }
+
+        if count > 0 {
+            debug!(
+                "Redelivered {} queued message{} to {}",
+                count,
+                if count == 1 { "" } else { "s" },
+                recipient
+            );
+        }
+
+        Ok(count)
+    }
+
+    /// Returns whether the given message has already been delivered to its recipient on this node.
+    pub fn is_delivered(&self, message: &CircuitDirectMessage) -> bool {
+        mutex_lock_unwrap!(self.shared)
+            .delivered
+            .contains(&MessageKey::from_message(message))
+    }
+
+    /// Records that the given message has been delivered to its recipient on this node, so that it
+    /// will not be delivered again, even after a restart if the queues were opened in a directory.
+    /// Only the most recently delivered messages are remembered.
+    pub fn mark_delivered(&self, message: &CircuitDirectMessage) -> Result<(), DurableQueueError> {
+        let mut shared = mutex_lock_unwrap!(self.shared);
+        let key = MessageKey::from_message(message);
+        if !shared.remember_delivered(key.clone()) {
+            return Ok(());
+        }
+
+        let path = match shared.dir.as_ref() {
+            Some(dir) => dir.join(DELIVERED_FILE_NAME),
+            None => return Ok(()),
+        };
+
+        if shared.delivered_records >= 2 * DEFAULT_MAX_DELIVERED {
+            // Rewrite the file with only the messages that are still remembered
+            let ids = shared
+                .delivered_order
+                .iter()
+                .map(MessageKey::to_id)
+                .collect::<Vec<_>>();
+            replace_file(&path, &ids)?;
+            shared.delivered_records = ids.len();
+        } else {
+            append_record(&path, &key.to_id(), true)?;
+            shared.delivered_records += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the depth and age of each node's queue, for monitoring.
+    pub fn status(&self) -> Vec<QueueStatus> {
+        let shared = mutex_lock_unwrap!(self.shared);
+        let now = SystemTime::now();
+        shared
+            .queues
+            .iter()
+            .filter(|(_, queue)| !queue.is_empty())
+            .map(|(recipient, queue)| QueueStatus {
+                recipient: recipient.to_string(),
+                depth: queue.len(),
+                oldest_age: queue
+                    .messages
+                    .iter()
+                    .map(|queued| now.duration_since(queued.queued_at).unwrap_or_default())
+                    .max(),
+            })
+            .collect()
+    }
+}
+
+impl Default for DurableQueues {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The depth and age of the queue of messages to a node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueueStatus {
+    recipient: String,
+    depth: usize,
+    oldest_age: Option<Duration>,
+}
+
+impl QueueStatus {
+    /// The ID of the node the queued messages are sent to.
+    pub fn recipient(&self) -> &str {
+        &self.recipient
+    }
+
+    /// The number of messages that have not been acknowledged.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// How long ago the oldest message that has not been acknowledged was queued.
+    pub fn oldest_age(&self) -> Option<Duration> {
+        self.oldest_age
+    }
+}
+
+struct SharedQueues {
+    dir: Option<PathBuf>,
+    max_depth: usize,
+    queues: BTreeMap<String, NodeQueue>,
+    delivered: HashSet<MessageKey>,
+    delivered_order: VecDeque<MessageKey>,
+    /// The number of records in the delivered file
+    delivered_records: usize,
+}
+
+impl SharedQueues {
+    fn new(dir: Option<PathBuf>) -> Self {
+        SharedQueues {
+            dir,
+            max_depth: DEFAULT_MAX_DEPTH,
+            queues: BTreeMap::new(),
+            delivered: HashSet::new(),
+            delivered_order: VecDeque::new(),
+            delivered_records: 0,
+        }
+    }
+
+    /// Remembers that the message with the given key was delivered, forgetting the oldest
+    /// delivered messages beyond the maximum. Returns whether the message was not already known.
+    fn remember_delivered(&mut self, key: MessageKey) -> bool {
+        if !self.delivered.insert(key.clone()) {
+            return false;
+        }
+
+        self.delivered_order.push_back(key);
+        while self.delivered_order.len() > DEFAULT_MAX_DELIVERED {
+            if let Some(oldest) = self.delivered_order.pop_front() {
+                self.delivered.remove(&oldest);
+            }
+        }
+        true
+    }
+
+    fn queue_path(&self, recipient: &str) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| {
+            dir.join(format!(
+                "{}.{}",
+                to_hex(recipient.as_bytes()),
+                QUEUE_FILE_EXTENSION
+            ))
+        })
+    }
+
+    /// Rewrites the given node's queue file with only its queued messages, replacing it so that a
+    /// partially written file is never left in its place. The file is removed if the queue is
+    /// empty.
+    fn write_queue(&self, recipient: &str) -> Result<(), DurableQueueError> {
+        let path = match self.queue_path(recipient) {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let queue = match self.queues.get(recipient) {
+            Some(queue) if !queue.is_empty() => queue,
+            _ => {
+                if path.exists() {
+                    fs::remove_file(&path)?;
+                }
+                return Ok(());
+            }
+        };
+
+        let records = queue
+            .messages
+            .iter()
+            .map(|queued| {
+                let mut record = QueueRecord::new();
+                record.set_queued(queued.to_proto());
+                record
+            })
+            .collect::<Vec<_>>();
+        replace_file(&path, &records)
+    }
+}
+
+/// The messages queued for a node, in the order they were queued.
+#[derive(Default)]
+struct NodeQueue {
+    messages: VecDeque<QueuedMessage>,
+    keys: HashSet<MessageKey>,
+    /// The number of records in the queue file that no longer hold a queued message
+    stale_records: usize,
+}
+
+impl NodeQueue {
+    fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    fn push_back(&mut self, queued: QueuedMessage) {
+        self.keys.insert(queued.key.clone());
+        self.messages.push_back(queued);
+    }
+
+    /// Removes the message with the given key, returning whether it was queued. Messages are
+    /// usually acknowledged in the order they were sent, so the message is searched for from the
+    /// front of the queue.
+    fn remove(&mut self, key: &MessageKey) -> bool {
+        if !self.keys.remove(key) {
+            return false;
+        }
+
+        if let Some(index) = self.messages.iter().position(|queued| &queued.key == key) {
+            self.messages.remove(index);
+        }
+        true
+    }
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct MessageKey {
+    circuit: String,
+    sender: String,
+    correlation_id: String,
+}
+
+impl MessageKey {
+    fn from_message(message: &CircuitDirectMessage) -> Self {
+        MessageKey {
+            circuit: message.get_circuit().to_string(),
+            sender: message.get_sender().to_string(),
+            correlation_id: message.get_correlation_id().to_string(),
+        }
+    }
+
+    fn from_id(id: &MessageId) -> Self {
+        MessageKey {
+            circuit: id.get_circuit().to_string(),
+            sender: id.get_sender().to_string(),
+            correlation_id: id.get_correlation_id().to_string(),
+        }
+    }
+
+    fn to_id(&self) -> MessageId {
+        let mut id = MessageId::new();
+        id.set_circuit(self.circuit.clone());
+        id.set_sender(self.sender.clone());
+        id.set_correlation_id(self.correlation_id.clone());
+        id
+    }
+}
+
+struct QueuedMessage {
+    key: MessageKey,
+    /// The CircuitDirectMessage bytes
+    bytes: Vec<u8>,
+    queued_at: SystemTime,
+    /// When the message was last sent; None if it has not been sent since the queue was opened
+    last_sent: Option<Instant>,
+}
+
+impl QueuedMessage {
+    fn to_proto(&self) -> QueuedMessageProto {
+        let mut proto = QueuedMessageProto::new();
+        proto.set_timestamp(
+            self.queued_at
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_micros() as u64)
+                .unwrap_or(0),
+        );
+        proto.set_message(self.bytes.clone());
+        proto
+    }
+
+    fn from_proto(mut proto: QueuedMessageProto) -> Result<Self, DurableQueueError> {
+        let message: CircuitDirectMessage = protobuf::parse_from_bytes(proto.get_message())
+            .map_err(|err| DurableQueueError::InvalidQueue(err.to_string()))?;
+
+        Ok(QueuedMessage {
+            key: MessageKey::from_message(&message),
+            bytes: proto.take_message(),
+            queued_at: UNIX_EPOCH + Duration::from_micros(proto.get_timestamp()),
+            last_sent: None,
+        })
+    }
+}
+
+fn write_record<W: Write, M: Message>(writer: &mut W, record: &M) -> Result<(), DurableQueueError> {
+    let bytes = record
+        .write_to_bytes()
+        .map_err(|err| DurableQueueError::InvalidQueue(err.to_string()))?;
+    writer.write_u32::<BigEndian>(bytes.len() as u32)?;
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
+/// Appends the record to the file at the given path, creating it if it does not exist. If `sync`
+/// is true, the record is on disk once this returns.
+fn append_record<M: Message>(path: &Path, record: &M, sync: bool) -> Result<(), DurableQueueError> {
+    let mut writer = BufWriter::new(OpenOptions::new().create(true).append(true).open(path)?);
+    write_record(&mut writer, record)?;
+    writer.flush()?;
+    if sync {
+        writer.get_ref().sync_all()?;
+    }
+    Ok(())
+}
+
+/// Replaces the file at the given path with one holding the given records, so that a partially
+/// written file is never left in its place.
+fn replace_file<M: Message>(path: &Path, records: &[M]) -> Result<(), DurableQueueError> {
+    let temp_path = path.with_extension("tmp");
+    {
+        let mut writer = BufWriter::new(File::create(&temp_path)?);
+        for record in records {
+            write_record(&mut writer, record)?;
+        }
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+    }
+    fs::rename(&temp_path, path)?;
+
+    Ok(())
+}
+
+fn read_records<M: Message>(path: &Path) -> Result<Vec<M>, DurableQueueError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut records = vec![];
+
+    loop {
+        let length = match reader.read_u32::<BigEndian>() {
+            Ok(length) => length,
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut bytes = vec![];
+        reader
+            .by_ref()
+            .take(u64::from(length))
+            .read_to_end(&mut bytes)?;
+        if bytes.len() != length as usize {
+            // The node stopped part way through appending this record, before it took effect
+            warn!(
+                "Ignoring incomplete record at the end of {}",
+                path.display()
+            );
+            break;
+        }
+
+        records.push(
+            protobuf::parse_from_bytes(&bytes)
+                .map_err(|err| DurableQueueError::InvalidQueue(err.to_string()))?,
+        );
+    }
+
+    Ok(records)
+}
+
+/// Reads a queue file, applying its acknowledgements to the messages it queued.
+fn read_queue_file(path: &Path) -> Result<NodeQueue, DurableQueueError> {
+    let records = read_records::<QueueRecord>(path)?;
+    let record_count = records.len();
+
+    let mut queue = NodeQueue::default();
+    for mut record in records {
+        if record.has_queued() {
+            queue.push_back(QueuedMessage::from_proto(record.take_queued())?);
+        } else if record.has_acknowledged() {
+            queue.remove(&MessageKey::from_id(record.get_acknowledged()));
+        }
+    }
+    queue.stale_records = record_count - queue.len();
+
+    Ok(queue)
+}
+
+#[derive(Debug)]
+pub enum DurableQueueError {
+    IoError(io::Error),
+    InvalidQueue(String),
+    /// The queue of the node with the given ID is full
+    QueueFull(String),
+    SendError(String),
+}
+
+impl Error for DurableQueueError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DurableQueueError::IoError(err) => Some(err),
+            DurableQueueError::InvalidQueue(_) => None,
+            DurableQueueError::QueueFull(_) => None,
+            DurableQueueError::SendError(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for DurableQueueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DurableQueueError::IoError(err) => write!(f, "unable to access queue: {}", err),
+            DurableQueueError::InvalidQueue(msg) => write!(f, "invalid queued message: {}", msg),
+            DurableQueueError::QueueFull(recipient) => {
+                write!(f, "queue of messages to {} is full", recipient)
+            }
+            DurableQueueError::SendError(msg) => {
+                write!(f, "unable to send queued message: {}", msg)
+            }
+        }
+    }
+}
+
+impl From<io::Error> for DurableQueueError {
+    fn from(err: io::Error) -> Self {
+        DurableQueueError::IoError(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc;
+
+    use tempdir::TempDir;
+
+    use crate::channel::SendError;
+    use crate::protos::circuit::CircuitMessage;
+    use crate::protos::network::NetworkMessage;
+
+    #[derive(Clone)]
+    struct ChannelSender(mpsc::Sender<SendRequest>);
+
+    impl Sender<SendRequest> for ChannelSender {
+        fn send(&self, request: SendRequest) -> Result<(), SendError> {
+            self.0.send(request).map_err(|err| SendError {
+                error: err.to_string(),
+            })
+        }
+
+        fn box_clone(&self) -> Box<dyn Sender<SendRequest>> {
+            Box::new(self.clone())
+        }
+    }
+
+    fn direct_message(correlation_id: &str) -> CircuitDirectMessage {
+        let mut message = CircuitDirectMessage::new();
+        message.set_circuit("alpha".into());
+        message.set_sender("abc".into());
+        message.set_recipient("def".into());
+        message.set_payload(b"test".to_vec());
+        message.set_correlation_id(correlation_id.into());
+        message
+    }
+
+    fn ack(correlation_id: &str) -> CircuitDirectMessageAck {
+        let mut ack = CircuitDirectMessageAck::new();
+        ack.set_circuit("alpha".into());
+        ack.set_sender("abc".into());
+        ack.set_recipient("def".into());
+        ack.set_correlation_id(correlation_id.into());
+        ack
+    }
+
+    fn sent_correlation_ids(recv: &mpsc::Receiver<SendRequest>) -> Vec<String> {
+        recv.try_iter()
+            .map(|request| {
+                let network_msg: NetworkMessage =
+                    protobuf::parse_from_bytes(request.payload()).expect("invalid network message");
+                let circuit_msg: CircuitMessage =
+                    protobuf::parse_from_bytes(network_msg.get_payload())
+                        .expect("invalid circuit message");
+                assert_eq!(
+                    circuit_msg.get_message_type(),
+                    CircuitMessageType::CIRCUIT_DIRECT_MESSAGE
+                );
+                let message: CircuitDirectMessage =
+                    protobuf::parse_from_bytes(circuit_msg.get_payload())
+                        .expect("invalid direct message");
+                message.get_correlation_id().to_string()
+            })
+            .collect()
+    }
+
+    /// Tests that queued messages are redelivered until they are acknowledged, that a message is
+    /// only queued once, and that the queue status reports the depth of each node's queue.
+    #[test]
+    fn test_enqueue_redeliver_acknowledge() {
+        let queues = DurableQueues::new();
+        let (send, recv) = mpsc::channel();
+        let sender = ChannelSender(send);
+
+        queues
+            .enqueue("node-b", &direct_message("1"))
+            .expect("unable to enqueue");
+        queues
+            .enqueue("node-b", &direct_message("2"))
+            .expect("unable to enqueue");
+        queues
+            .enqueue("node-b", &direct_message("1"))
+            .expect("unable to enqueue");
+
+        let status = queues.status();
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].recipient(), "node-b");
+        assert_eq!(status[0].depth(), 2);
+        assert!(status[0].oldest_age().is_some());
+
+        assert_eq!(
+            queues
+                .redeliver("node-b", &sender)
+                .expect("unable to redeliver"),
+            2
+        );
+        assert_eq!(sent_correlation_ids(&recv), vec!["1", "2"]);
+
+        assert!(queues
+            .acknowledge("node-b", &ack("1"))
+            .expect("unable to acknowledge"));
+        assert!(!queues
+            .acknowledge("node-b", &ack("1"))
+            .expect("unable to acknowledge"));
+        assert!(!queues
+            .acknowledge("node-c", &ack("2"))
+            .expect("unable to acknowledge"));
+
+        assert_eq!(
+            queues
+                .redeliver("node-b", &sender)
+                .expect("unable to redeliver"),
+            1
+        );
+        assert_eq!(sent_correlation_ids(&recv), vec!["2"]);
+
+        // messages sent within the retry interval are not sent again
+        assert_eq!(
+            queues
+                .retry(&["node-b".to_string()], Duration::from_secs(60), &sender)
+                .expect("unable to retry"),
+            0
+        );
+        assert_eq!(
+            queues
+                .retry(&["node-b".to_string()], Duration::from_secs(0), &sender)
+                .expect("unable to retry"),
+            1
+        );
+        assert_eq!(sent_correlation_ids(&recv), vec!["2"]);
+
+        assert!(queues
+            .acknowledge("node-b", &ack("2"))
+            .expect("unable to acknowledge"));
+        assert!(queues.status().is_empty());
+    }
+
+    /// Tests that messages are refused once a node's queue is full.
+    #[test]
+    fn test_queue_full() {
+        let queues = DurableQueues::new().with_max_depth(1);
+
+        queues
+            .enqueue("node-b", &direct_message("1"))
+            .expect("unable to enqueue");
+        match queues.enqueue("node-b", &direct_message("2")) {
+            Err(DurableQueueError::QueueFull(recipient)) => assert_eq!(recipient, "node-b"),
+            res => panic!("expected QueueFull, got {:?}", res),
+        }
+        queues
+            .enqueue("node-c", &direct_message("2"))
+            .expect("unable to enqueue");
+    }
+
+    /// Tests that the unacknowledged messages in a queue directory are loaded when it is opened
+    /// again, and that a node's queue file is removed once all of its messages are acknowledged.
+    #[test]
+    fn test_persistence() {
+        let temp_dir = TempDir::new("test_durable_queues").expect("unable to create temp dir");
+
+        {
+            let queues = DurableQueues::open(temp_dir.path()).expect("unable to open queues");
+            queues
+                .enqueue("node-b", &direct_message("1"))
+                .expect("unable to enqueue");
+            queues
+                .enqueue("node-b", &direct_message("2"))
+                .expect("unable to enqueue");
+            queues
+                .enqueue("node-c", &direct_message("3"))
+                .expect("unable to enqueue");
+            queues
+                .acknowledge("node-b", &ack("1"))
+                .expect("unable to acknowledge");
+            queues
+                .acknowledge("node-c", &ack("3"))
+                .expect("unable to acknowledge");
+        }
+
+        let queues = DurableQueues::open(temp_dir.path()).expect("unable to reopen queues");
+        let status = queues.status();
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].recipient(), "node-b");
+        assert_eq!(status[0].depth(), 1);
+
+        let (send, recv) = mpsc::channel();
+        assert_eq!(
+            queues
+                .retry(
+                    &["node-b".to_string()],
+                    Duration::from_secs(60),
+                    &ChannelSender(send)
+                )
+                .expect("unable to retry"),
+            1
+        );
+        assert_eq!(sent_correlation_ids(&recv), vec!["2"]);
+
+        assert_eq!(
+            fs::read_dir(temp_dir.path())
+                .expect("unable to read temp dir")
+                .count(),
+            1
+        );
+    }
+
+    /// Tests that delivered messages are recognized by circuit, sender and correlation ID.
+    #[test]
+    fn test_delivered() {
+        let queues = DurableQueues::new();
+
+        assert!(!queues.is_delivered(&direct_message("1")));
+        queues
+            .mark_delivered(&direct_message("1"))
+            .expect("unable to mark delivered");
+        assert!(queues.is_delivered(&direct_message("1")));
+        assert!(!queues.is_delivered(&direct_message("2")));
+
+        let mut other_sender = direct_message("1");
+        other_sender.set_sender("ghi".into());
+        assert!(!queues.is_delivered(&other_sender));
+    }
+
+    /// Tests that delivered messages are remembered when a queue directory is opened again.
+    #[test]
+    fn test_delivered_persistence() {
+        let temp_dir = TempDir::new("test_durable_queues").expect("unable to create temp dir");
+
+        {
+            let queues = DurableQueues::open(temp_dir.path()).expect("unable to open queues");
+            queues
+                .mark_delivered(&direct_message("1"))
+                .expect("unable to mark delivered");
+        }
+
+        let queues = DurableQueues::open(temp_dir.path()).expect("unable to reopen queues");
+        assert!(queues.is_delivered(&direct_message("1")));
+        assert!(!queues.is_delivered(&direct_message("2")));
+    }
+
+    /// Tests that acknowledgements are appended to a queue file, and that the file is rewritten
+    /// without the acknowledged messages once there are enough of them.
+    #[test]
+    fn test_compaction() {
+        let temp_dir = TempDir::new("test_durable_queues").expect("unable to create temp dir");
+        let queues = DurableQueues::open(temp_dir.path()).expect("unable to open queues");
+        let path = mutex_lock_unwrap!(queues.shared)
+            .queue_path("node-b")
+            .expect("queues have no directory");
+
+        queues
+            .enqueue("node-b", &direct_message("keep"))
+            .expect("unable to enqueue");
+        for i in 0..COMPACTION_THRESHOLD / 2 {
+            if i == COMPACTION_THRESHOLD / 2 - 1 {
+                // each acknowledged message has left two records behind
+                assert_eq!(
+                    read_records::<QueueRecord>(&path)
+                        .expect("unable to read queue file")
+                        .len(),
+                    1 + 2 * i
+                );
+            }
+
+            queues
+                .enqueue("node-b", &direct_message(&i.to_string()))
+                .expect("unable to enqueue");
+            assert!(queues
+                .acknowledge("node-b", &ack(&i.to_string()))
+                .expect("unable to acknowledge"));
+        }
+
+        assert_eq!(
+            read_records::<QueueRecord>(&path)
+                .expect("unable to read queue file")
+                .len(),
+            1
+        );
+
+        let queues = DurableQueues::open(temp_dir.path()).expect("unable to reopen queues");
+        let status = queues.status();
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].depth(), 1);
+    }
+}
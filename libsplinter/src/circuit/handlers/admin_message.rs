@@ -14,6 +14,10 @@
 
 use crate::channel::Sender;
 use crate::circuit::handlers::create_message;
+#[cfg(feature = "circuit-relay")]
+use crate::circuit::handlers::relayed_message::{
+    create_relayed_message, next_hop, shared_relayed_circuit,
+};
 use crate::circuit::SplinterState;
 use crate::network::dispatch::{DispatchError, Handler, MessageContext};
 use crate::network::sender::SendRequest;
@@ -22,7 +26,7 @@ use crate::protos::circuit::{
 };
 use protobuf::Message;
 
-const ADMIN_SERVICE_ID_PREFIX: &str = "admin::";
+pub(crate) const ADMIN_SERVICE_ID_PREFIX: &str = "admin::";
 
 // Implements a handler that handles AdminDirectMessage
 pub struct AdminDirectMessageHandler {
@@ -119,6 +123,27 @@ impl AdminDirectMessageHandler {
             // If the service is on this node send message to the service, otherwise
             // send the message to the node the service is connected to
             let target_node = if node_id != self.node_id {
+                // Messages from the local admin service to a node it shares a relayed circuit
+                // with are sent through that circuit's relays
+                #[cfg(feature = "circuit-relay")]
+                {
+                    if msg_sender[ADMIN_SERVICE_ID_PREFIX.len()..] == self.node_id {
+                        if let Some((relayed_circuit, relays)) =
+                            shared_relayed_circuit(&self.state, &self.node_id, node_id)?
+                        {
+                            let next_hop = next_hop(&relays, &[self.node_id.clone()], node_id);
+                            let network_msg_bytes = create_relayed_message(
+                                &relayed_circuit,
+                                node_id,
+                                &self.node_id,
+                                CircuitMessageType::ADMIN_DIRECT_MESSAGE,
+                                context.message_bytes().to_vec(),
+                            )?;
+                            return Ok((network_msg_bytes, next_hop.to_string()));
+                        }
+                    }
+                }
+
                 node_id
             } else {
                 // The internal admin service is at the node id with an identical name
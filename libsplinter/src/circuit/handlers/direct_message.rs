@@ -13,13 +13,23 @@
 // limitations under the License.
 
 use crate::channel::Sender;
+#[cfg(feature = "circuit-durability")]
+use crate::circuit::durable::{DurableQueueError, DurableQueues};
 use crate::circuit::handlers::create_message;
+#[cfg(feature = "circuit-relay")]
+use crate::circuit::handlers::relayed_message::{create_relayed_message, next_hop};
+#[cfg(feature = "circuit-relay")]
+use crate::circuit::RouteType;
+#[cfg(feature = "circuit-durability")]
+use crate::circuit::{Circuit, DurabilityType};
 use crate::circuit::{ServiceId, SplinterState};
 use crate::network::dispatch::{DispatchError, Handler, MessageContext};
 use crate::network::sender::SendRequest;
 use crate::protos::circuit::{
     CircuitDirectMessage, CircuitError, CircuitError_Error, CircuitMessageType,
 };
+#[cfg(feature = "circuit-durability")]
+use crate::protos::circuit::{CircuitDirectMessageAck, NetworkError, NetworkError_Error};
 
 use protobuf::Message;
 
@@ -27,6 +37,8 @@ use protobuf::Message;
 pub struct CircuitDirectMessageHandler {
     node_id: String,
     state: SplinterState,
+    #[cfg(feature = "circuit-durability")]
+    durable_queues: Option<DurableQueues>,
 }
 
 impl Handler<CircuitMessageType, CircuitDirectMessage> for CircuitDirectMessageHandler {
@@ -56,6 +68,11 @@ impl Handler<CircuitMessageType, CircuitDirectMessage> for CircuitDirectMessageH
         let recipient_id = ServiceId::new(circuit_name.to_string(), recipient.to_string());
         let sender_id = ServiceId::new(circuit_name.to_string(), msg_sender.to_string());
 
+        // the acknowledgement to send to the node a message on a durable circuit came from, once
+        // it has been delivered
+        #[cfg(feature = "circuit-durability")]
+        let mut durable_ack = None;
+
         // msg bytes will either be message bytes of a direct message or an error message
         // the msg_recipient is either the service/node id to send the message to or is the
         // peer_id to send back the error message
@@ -115,6 +132,55 @@ impl Handler<CircuitMessageType, CircuitDirectMessage> for CircuitDirectMessageH
                         // If the service is on this node send message to the service, otherwise
                         // send the message to the node the service is connected to
                         if node_id != self.node_id {
+                            // Messages on relayed circuits are sent through the circuit's relays
+                            // by the node of their sender. Relayed circuits cannot be durable, so
+                            // these messages are never queued.
+                            #[cfg(feature = "circuit-relay")]
+                            {
+                                if let RouteType::Relayed(relays) = circuit.routes() {
+                                    let is_local_sender = self
+                                        .state
+                                        .get_service(&sender_id)
+                                        .map_err(|err| DispatchError::HandleError(err.context()))?
+                                        .map(|service| service.node().id() == self.node_id)
+                                        .unwrap_or(false);
+                                    if !is_local_sender {
+                                        warn!(
+                                            "Dropping message {} on relayed circuit {} from {}, \
+                                             which is not connected to this node",
+                                            msg.get_correlation_id(),
+                                            circuit_name,
+                                            msg_sender
+                                        );
+                                        return Ok(());
+                                    }
+
+                                    let next_hop =
+                                        next_hop(relays, &[self.node_id.clone()], &node_id);
+                                    let network_msg_bytes = create_relayed_message(
+                                        circuit_name,
+                                        &node_id,
+                                        &self.node_id,
+                                        CircuitMessageType::CIRCUIT_DIRECT_MESSAGE,
+                                        context.message_bytes().to_vec(),
+                                    )?;
+                                    sender.send(SendRequest::new(
+                                        next_hop.to_string(),
+                                        network_msg_bytes,
+                                    ))?;
+                                    return Ok(());
+                                }
+                            }
+
+                            #[cfg(feature = "circuit-durability")]
+                            {
+                                if !self.queue_durable_message(
+                                    &circuit, &msg, &node_id, context, sender,
+                                )? {
+                                    return Ok(());
+                                }
+                            }
+
                             let msg_bytes = context.message_bytes().to_vec();
                             let network_msg_bytes = create_message(
                                 msg_bytes,
@@ -122,6 +188,23 @@ impl Handler<CircuitMessageType, CircuitDirectMessage> for CircuitDirectMessageH
                             )?;
                             (network_msg_bytes, node_id)
                         } else {
+                            #[cfg(feature = "circuit-durability")]
+                            {
+                                durable_ack =
+                                    self.create_durable_ack(&circuit, &msg, &sender_id, context)?;
+                                if durable_ack.is_some() && self.is_delivered(&msg) {
+                                    debug!(
+                                        "Acknowledging message {} on {} that was already delivered",
+                                        msg.get_correlation_id(),
+                                        circuit_name
+                                    );
+                                    if let Some(ack) = durable_ack {
+                                        sender.send(ack)?;
+                                    }
+                                    return Ok(());
+                                }
+                            }
+
                             let msg_bytes = context.message_bytes().to_vec();
                             let network_msg_bytes = create_message(
                                 msg_bytes,
@@ -195,13 +278,145 @@ impl Handler<CircuitMessageType, CircuitDirectMessage> for CircuitDirectMessageH
         // either forward the direct message or send back an error message.
         let send_request = SendRequest::new(msg_recipient, msg_bytes);
         sender.send(send_request)?;
+
+        #[cfg(feature = "circuit-durability")]
+        {
+            if let Some(ack) = durable_ack {
+                if let Some(ref durable_queues) = self.durable_queues {
+                    if let Err(err) = durable_queues.mark_delivered(&msg) {
+                        error!(
+                            "Unable to record delivery of message {} on {}: {}",
+                            msg.get_correlation_id(),
+                            msg.get_circuit(),
+                            err
+                        );
+                    }
+                }
+                sender.send(ack)?;
+            }
+        }
+
         Ok(())
     }
 }
 
 impl CircuitDirectMessageHandler {
     pub fn new(node_id: String, state: SplinterState) -> Self {
-        CircuitDirectMessageHandler { node_id, state }
+        CircuitDirectMessageHandler {
+            node_id,
+            state,
+            #[cfg(feature = "circuit-durability")]
+            durable_queues: None,
+        }
+    }
+
+    /// Queues the direct messages sent on durable circuits to services on other nodes, and
+    /// acknowledges those received from other nodes, using the given queues.
+    #[cfg(feature = "circuit-durability")]
+    pub fn with_durable_queues(mut self, durable_queues: DurableQueues) -> Self {
+        self.durable_queues = Some(durable_queues);
+        self
+    }
+
+    /// Adds a message on a durable circuit to the queue of the node it is sent to. Returns false
+    /// if the queue is full, in which case a NetworkError is sent back instead.
+    #[cfg(feature = "circuit-durability")]
+    fn queue_durable_message(
+        &self,
+        circuit: &Circuit,
+        msg: &CircuitDirectMessage,
+        node_id: &str,
+        context: &MessageContext<CircuitMessageType>,
+        sender: &dyn Sender<SendRequest>,
+    ) -> Result<bool, DispatchError> {
+        let durable_queues = match self.durable_queues {
+            Some(ref durable_queues) if circuit.durability() == &DurabilityType::Durable => {
+                durable_queues
+            }
+            _ => return Ok(true),
+        };
+
+        if msg.get_correlation_id().is_empty() {
+            warn!(
+                "Sending message from {} on durable circuit {} without a correlation ID; it will \
+                 not be queued",
+                msg.get_sender(),
+                msg.get_circuit()
+            );
+            return Ok(true);
+        }
+
+        match durable_queues.enqueue(node_id, msg) {
+            Ok(()) => Ok(true),
+            Err(DurableQueueError::QueueFull(_)) => {
+                let mut error_message = NetworkError::new();
+                error_message.set_correlation_id(msg.get_correlation_id().to_string());
+                error_message.set_error(NetworkError_Error::ERROR_QUEUE_FULL);
+                error_message
+                    .set_error_message(format!("Queue of messages to node {} is full", node_id));
+
+                let msg_bytes = error_message.write_to_bytes()?;
+                let network_msg_bytes =
+                    create_message(msg_bytes, CircuitMessageType::NETWORK_ERROR_MESSAGE)?;
+                sender.send(SendRequest::new(
+                    context.source_peer_id().to_string(),
+                    network_msg_bytes,
+                ))?;
+                Ok(false)
+            }
+            Err(err) => Err(DispatchError::HandleError(err.to_string())),
+        }
+    }
+
+    /// Creates the acknowledgement of a message on a durable circuit received from another node,
+    /// or returns None if the message does not need to be acknowledged.
+    #[cfg(feature = "circuit-durability")]
+    fn create_durable_ack(
+        &self,
+        circuit: &Circuit,
+        msg: &CircuitDirectMessage,
+        sender_id: &ServiceId,
+        context: &MessageContext<CircuitMessageType>,
+    ) -> Result<Option<SendRequest>, DispatchError> {
+        if self.durable_queues.is_none()
+            || circuit.durability() != &DurabilityType::Durable
+            || msg.get_correlation_id().is_empty()
+        {
+            return Ok(None);
+        }
+
+        // Messages from services connected to this node are not queued
+        match self
+            .state
+            .get_service(sender_id)
+            .map_err(|err| DispatchError::HandleError(err.context()))?
+        {
+            Some(service) if service.node().id() != self.node_id => (),
+            _ => return Ok(None),
+        }
+
+        let mut ack = CircuitDirectMessageAck::new();
+        ack.set_circuit(msg.get_circuit().to_string());
+        ack.set_sender(msg.get_sender().to_string());
+        ack.set_recipient(msg.get_recipient().to_string());
+        ack.set_correlation_id(msg.get_correlation_id().to_string());
+
+        let network_msg_bytes = create_message(
+            ack.write_to_bytes()?,
+            CircuitMessageType::CIRCUIT_DIRECT_MESSAGE_ACK,
+        )?;
+        Ok(Some(SendRequest::new(
+            context.source_peer_id().to_string(),
+            network_msg_bytes,
+        )))
+    }
+
+    #[cfg(feature = "circuit-durability")]
+    fn is_delivered(&self, msg: &CircuitDirectMessage) -> bool {
+        self.durable_queues
+            .as_ref()
+            .map(|durable_queues| durable_queues.is_delivered(msg))
+            .unwrap_or(false)
     }
 }
 
@@ -212,6 +427,8 @@ mod tests {
     use super::*;
     use crate::channel::{SendError, Sender};
     use crate::circuit::directory::CircuitDirectory;
+    #[cfg(feature = "circuit-durability")]
+    use crate::circuit::handlers::CircuitDirectMessageAckHandler;
     use crate::circuit::service::{Service, SplinterNode};
     use crate::circuit::{AuthorizationType, Circuit, DurabilityType, PersistenceType, RouteType};
     use crate::network::dispatch::Dispatcher;
@@ -781,6 +998,169 @@ mod tests {
         assert_eq!(error_message.get_correlation_id(), "1234");
     }
 
+    // Test that a direct message on a durable circuit is queued for the node the recipient service
+    // is connected to, and that the receiving node delivers it to the service only once, and
+    // acknowledges it each time it is received
+    #[cfg(feature = "circuit-durability")]
+    #[test]
+    fn test_circuit_direct_message_handler_durable() {
+        let circuit = Circuit::builder()
+            .with_id("alpha".into())
+            .with_auth(AuthorizationType::Trust)
+            .with_members(vec!["123".into(), "345".into()])
+            .with_roster(vec!["abc".into(), "def".into()])
+            .with_persistence(PersistenceType::Any)
+            .with_durability(DurabilityType::Durable)
+            .with_routes(RouteType::Any)
+            .with_circuit_management_type("circuit_direct_test_app".into())
+            .build()
+            .expect("Should have built a correct circuit");
+
+        let node_123 = SplinterNode::new("123".to_string(), vec!["123.0.0.1:0".to_string()]);
+        let node_345 = SplinterNode::new("345".to_string(), vec!["123.0.0.1:0".to_string()]);
+        let abc_id = ServiceId::new("alpha".into(), "abc".into());
+        let def_id = ServiceId::new("alpha".into(), "def".into());
+
+        // set up the state of node 345, to which def is connected
+        let mut circuit_directory = CircuitDirectory::new();
+        circuit_directory.add_circuit("alpha".to_string(), circuit.clone());
+        let state_345 = SplinterState::new("memory".to_string(), circuit_directory);
+        state_345
+            .add_service(
+                abc_id.clone(),
+                Service::new("abc".to_string(), None, node_123.clone()),
+            )
+            .unwrap();
+        state_345
+            .add_service(
+                def_id.clone(),
+                Service::new(
+                    "def".to_string(),
+                    Some("def_network".to_string()),
+                    node_345.clone(),
+                ),
+            )
+            .unwrap();
+
+        // set up the state of node 123, to which abc is connected
+        let mut circuit_directory = CircuitDirectory::new();
+        circuit_directory.add_circuit("alpha".to_string(), circuit);
+        let state_123 = SplinterState::new("memory".to_string(), circuit_directory);
+        state_123
+            .add_service(
+                abc_id,
+                Service::new("abc".to_string(), Some("abc_network".to_string()), node_123),
+            )
+            .unwrap();
+        state_123
+            .add_service(def_id, Service::new("def".to_string(), None, node_345))
+            .unwrap();
+
+        let mut direct_message = CircuitDirectMessage::new();
+        direct_message.set_circuit("alpha".into());
+        direct_message.set_sender("def".into());
+        direct_message.set_recipient("abc".into());
+        direct_message.set_payload(b"test".to_vec());
+        direct_message.set_correlation_id("1234".into());
+        let direct_bytes = direct_message.write_to_bytes().unwrap();
+
+        // the message from def is queued for node 123 and sent to it
+        let sender_345 = Box::new(MockNetworkSender::default());
+        let mut dispatcher_345 = Dispatcher::new(sender_345.box_clone());
+        let queues_345 = DurableQueues::new();
+        dispatcher_345.set_handler(
+            CircuitMessageType::CIRCUIT_DIRECT_MESSAGE,
+            Box::new(
+                CircuitDirectMessageHandler::new("345".to_string(), state_345)
+                    .with_durable_queues(queues_345.clone()),
+            ),
+        );
+        dispatcher_345
+            .dispatch(
+                "def_network",
+                &CircuitMessageType::CIRCUIT_DIRECT_MESSAGE,
+                direct_bytes.clone(),
+            )
+            .unwrap();
+
+        assert_eq!(sender_345.sent().lock().unwrap()[0].recipient(), "123");
+        let status = queues_345.status();
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].recipient(), "123");
+        assert_eq!(status[0].depth(), 1);
+
+        // node 123 delivers the message to abc and acknowledges it, twice, but only delivers it
+        // once
+        let sender_123 = Box::new(MockNetworkSender::default());
+        let mut dispatcher_123 = Dispatcher::new(sender_123.box_clone());
+        dispatcher_123.set_handler(
+            CircuitMessageType::CIRCUIT_DIRECT_MESSAGE,
+            Box::new(
+                CircuitDirectMessageHandler::new("123".to_string(), state_123)
+                    .with_durable_queues(DurableQueues::new()),
+            ),
+        );
+        for _ in 0..2 {
+            dispatcher_123
+                .dispatch(
+                    "345",
+                    &CircuitMessageType::CIRCUIT_DIRECT_MESSAGE,
+                    direct_bytes.clone(),
+                )
+                .unwrap();
+        }
+
+        let sent = sender_123.sent().lock().unwrap().clone();
+        let sent = sent
+            .iter()
+            .map(|send_request| {
+                let network_msg: NetworkMessage =
+                    protobuf::parse_from_bytes(send_request.payload()).unwrap();
+                let circuit_msg: CircuitMessage =
+                    protobuf::parse_from_bytes(network_msg.get_payload()).unwrap();
+                (
+                    send_request.recipient().to_string(),
+                    circuit_msg.get_message_type(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sent,
+            vec![
+                (
+                    "abc_network".to_string(),
+                    CircuitMessageType::CIRCUIT_DIRECT_MESSAGE
+                ),
+                (
+                    "345".to_string(),
+                    CircuitMessageType::CIRCUIT_DIRECT_MESSAGE_ACK
+                ),
+                (
+                    "345".to_string(),
+                    CircuitMessageType::CIRCUIT_DIRECT_MESSAGE_ACK
+                ),
+            ]
+        );
+
+        // the acknowledgement removes the message from node 345's queue
+        let network_msg: NetworkMessage =
+            protobuf::parse_from_bytes(sender_123.sent().lock().unwrap()[1].payload()).unwrap();
+        let circuit_msg: CircuitMessage =
+            protobuf::parse_from_bytes(network_msg.get_payload()).unwrap();
+        dispatcher_345.set_handler(
+            CircuitMessageType::CIRCUIT_DIRECT_MESSAGE_ACK,
+            Box::new(CircuitDirectMessageAckHandler::new(queues_345.clone())),
+        );
+        dispatcher_345
+            .dispatch(
+                "123",
+                &CircuitMessageType::CIRCUIT_DIRECT_MESSAGE_ACK,
+                circuit_msg.get_payload().to_vec(),
+            )
+            .unwrap();
+        assert!(queues_345.status().is_empty());
+    }
+
     #[derive(Default)]
     struct MockNetworkSender {
         sent: Arc<Mutex<Vec<SendRequest>>>,
@@ -0,0 +1,65 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::channel::Sender;
+use crate::circuit::durable::DurableQueues;
+use crate::network::dispatch::{DispatchError, Handler, MessageContext};
+use crate::network::sender::SendRequest;
+use crate::protos::circuit::{CircuitDirectMessageAck, CircuitMessageType};
+
+// Implements a handler that handles CircuitDirectMessageAck messages, removing the acknowledged
+// message from the queue of the node that sent the acknowledgement
+pub struct CircuitDirectMessageAckHandler {
+    durable_queues: DurableQueues,
+}
+
+impl Handler<CircuitMessageType, CircuitDirectMessageAck> for CircuitDirectMessageAckHandler {
+    fn handle(
+        &self,
+        msg: CircuitDirectMessageAck,
+        context: &MessageContext<CircuitMessageType>,
+        _sender: &dyn Sender<SendRequest>,
+    ) -> Result<(), DispatchError> {
+        debug!(
+            "Handle Circuit Direct Message Ack {} on {} ({} => {}) from {}",
+            msg.get_correlation_id(),
+            msg.get_circuit(),
+            msg.get_sender(),
+            msg.get_recipient(),
+            context.source_peer_id()
+        );
+
+        let acknowledged = self
+            .durable_queues
+            .acknowledge(context.source_peer_id(), &msg)
+            .map_err(|err| DispatchError::HandleError(err.to_string()))?;
+
+        if !acknowledged {
+            // The message was already acknowledged, if it was redelivered
+            trace!(
+                "Message {} on {} was not queued for {}",
+                msg.get_correlation_id(),
+                msg.get_circuit(),
+                context.source_peer_id()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl CircuitDirectMessageAckHandler {
+    pub fn new(durable_queues: DurableQueues) -> Self {
+        CircuitDirectMessageAckHandler { durable_queues }
+    }
+}
@@ -0,0 +1,736 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::channel::Sender;
+use crate::circuit::handlers::admin_message::ADMIN_SERVICE_ID_PREFIX;
+use crate::circuit::handlers::create_message;
+use crate::circuit::{RouteType, ServiceId, SplinterState};
+use crate::network::dispatch::{DispatchError, Handler, MessageContext};
+use crate::network::sender::SendRequest;
+use crate::protos::circuit::{
+    AdminDirectMessage, CircuitDirectMessage, CircuitMessageType, CircuitRelayedMessage,
+};
+
+use protobuf::{Message, RepeatedField};
+
+/// The number of times a message on a relayed circuit may be relayed before it is dropped.
+pub(crate) const RELAYED_MESSAGE_TTL: u32 = 8;
+
+// Implements a handler that handles CircuitRelayedMessage, delivering the direct or admin message
+// it carries if this node is its destination, and otherwise relaying it to the next node on its
+// route. The carried message is not encrypted, so relays are able to read it.
+pub struct CircuitRelayedMessageHandler {
+    node_id: String,
+    state: SplinterState,
+}
+
+impl Handler<CircuitMessageType, CircuitRelayedMessage> for CircuitRelayedMessageHandler {
+    fn handle(
+        &self,
+        mut msg: CircuitRelayedMessage,
+        context: &MessageContext<CircuitMessageType>,
+        sender: &dyn Sender<SendRequest>,
+    ) -> Result<(), DispatchError> {
+        debug!(
+            "Handle Circuit Relayed Message on {} to {} from {} (route: {}, ttl: {})",
+            msg.get_circuit(),
+            msg.get_destination(),
+            context.source_peer_id(),
+            msg.get_route().join(" -> "),
+            msg.get_ttl()
+        );
+
+        let circuit = match self
+            .state
+            .circuit(msg.get_circuit())
+            .map_err(|err| DispatchError::HandleError(err.context()))?
+        {
+            Some(circuit) => circuit,
+            None => {
+                warn!(
+                    "Dropping relayed message on circuit {}, which does not exist",
+                    msg.get_circuit()
+                );
+                return Ok(());
+            }
+        };
+
+        let relays = match circuit.routes() {
+            RouteType::Relayed(relays) => relays,
+            _ => {
+                warn!(
+                    "Dropping relayed message on circuit {}, which is not relayed",
+                    msg.get_circuit()
+                );
+                return Ok(());
+            }
+        };
+
+        if !circuit.members().contains(msg.get_destination()) {
+            warn!(
+                "Dropping relayed message on circuit {} to {}, which is not a member",
+                msg.get_circuit(),
+                msg.get_destination()
+            );
+            return Ok(());
+        }
+
+        // The route is only trusted if it is the path the circuit's relays prescribe from its
+        // origin, and the message came from the last node on it. This bounds the number of times
+        // a message is relayed, independent of the ttl it carries.
+        let route = msg.get_route();
+        let valid_route = match route.first() {
+            Some(origin) if circuit.members().contains(origin) => {
+                let path = relay_path(relays, origin, msg.get_destination());
+                path.len() > route.len()
+                    && path[..route.len()] == route[..]
+                    && path[route.len()] == self.node_id
+                    && route.last().map(String::as_str) == Some(context.source_peer_id())
+            }
+            _ => false,
+        };
+        if !valid_route {
+            warn!(
+                "Dropping relayed message on circuit {} to {} from {} with an unexpected route: \
+                 {}",
+                msg.get_circuit(),
+                msg.get_destination(),
+                context.source_peer_id(),
+                route.join(" -> ")
+            );
+            return Ok(());
+        }
+
+        if msg.get_destination() == self.node_id {
+            return self.deliver(&msg, sender);
+        }
+
+        if msg.get_ttl() == 0 {
+            warn!(
+                "Dropping relayed message on circuit {} to {} that was relayed too many times: {}",
+                msg.get_circuit(),
+                msg.get_destination(),
+                msg.get_route().join(" -> ")
+            );
+            return Ok(());
+        }
+
+        msg.mut_route().push(self.node_id.clone());
+        let ttl = msg.get_ttl() - 1;
+        msg.set_ttl(ttl);
+
+        let next_hop = next_hop(relays, msg.get_route(), msg.get_destination()).to_string();
+        let network_msg_bytes = create_message(
+            msg.write_to_bytes()?,
+            CircuitMessageType::CIRCUIT_RELAYED_MESSAGE,
+        )?;
+        sender.send(SendRequest::new(next_hop, network_msg_bytes))?;
+
+        Ok(())
+    }
+}
+
+impl CircuitRelayedMessageHandler {
+    pub fn new(node_id: String, state: SplinterState) -> Self {
+        CircuitRelayedMessageHandler { node_id, state }
+    }
+
+    /// Sends the message carried by a relayed message to its recipient, which must be connected
+    /// to this node, if it was sent by a service on the node the message originated from.
+    fn deliver(
+        &self,
+        msg: &CircuitRelayedMessage,
+        sender: &dyn Sender<SendRequest>,
+    ) -> Result<(), DispatchError> {
+        // the route was checked to start with the origin before delivering
+        let origin = &msg.get_route()[0];
+        match msg.get_message_type() {
+            CircuitMessageType::CIRCUIT_DIRECT_MESSAGE => {
+                self.deliver_direct_message(msg, origin, sender)
+            }
+            CircuitMessageType::ADMIN_DIRECT_MESSAGE => {
+                self.deliver_admin_message(msg, origin, sender)
+            }
+            message_type => {
+                warn!(
+                    "Dropping relayed message on circuit {} carrying a {:?} message",
+                    msg.get_circuit(),
+                    message_type
+                );
+                Ok(())
+            }
+        }
+    }
+
+    fn deliver_direct_message(
+        &self,
+        msg: &CircuitRelayedMessage,
+        origin: &str,
+        sender: &dyn Sender<SendRequest>,
+    ) -> Result<(), DispatchError> {
+        let direct_message: CircuitDirectMessage = protobuf::parse_from_bytes(msg.get_message())?;
+        if direct_message.get_circuit() != msg.get_circuit() {
+            warn!(
+                "Dropping relayed message on circuit {} carrying a message on circuit {}",
+                msg.get_circuit(),
+                direct_message.get_circuit()
+            );
+            return Ok(());
+        }
+
+        let sender_id = ServiceId::new(
+            direct_message.get_circuit().to_string(),
+            direct_message.get_sender().to_string(),
+        );
+        match self
+            .state
+            .get_service(&sender_id)
+            .map_err(|err| DispatchError::HandleError(err.context()))?
+        {
+            Some(service) if service.node().id() == origin => (),
+            _ => {
+                warn!(
+                    "Dropping relayed message on circuit {} from {}, which is not connected to \
+                     {}",
+                    direct_message.get_circuit(),
+                    direct_message.get_sender(),
+                    origin
+                );
+                return Ok(());
+            }
+        }
+
+        let recipient_id = ServiceId::new(
+            direct_message.get_circuit().to_string(),
+            direct_message.get_recipient().to_string(),
+        );
+        let peer_id = match self
+            .state
+            .get_service(&recipient_id)
+            .map_err(|err| DispatchError::HandleError(err.context()))?
+        {
+            Some(service) if service.node().id() == self.node_id => match service.peer_id() {
+                Some(peer_id) => peer_id.clone(),
+                None => {
+                    warn!("No peer id for service:{} ", service.service_id());
+                    return Ok(());
+                }
+            },
+            _ => {
+                warn!(
+                    "Dropping relayed message on circuit {} to {}, which is not connected to \
+                     this node",
+                    direct_message.get_circuit(),
+                    direct_message.get_recipient()
+                );
+                return Ok(());
+            }
+        };
+
+        let network_msg_bytes = create_message(
+            msg.get_message().to_vec(),
+            CircuitMessageType::CIRCUIT_DIRECT_MESSAGE,
+        )?;
+        sender.send(SendRequest::new(peer_id, network_msg_bytes))?;
+
+        Ok(())
+    }
+
+    fn deliver_admin_message(
+        &self,
+        msg: &CircuitRelayedMessage,
+        origin: &str,
+        sender: &dyn Sender<SendRequest>,
+    ) -> Result<(), DispatchError> {
+        let admin_message: AdminDirectMessage = protobuf::parse_from_bytes(msg.get_message())?;
+        let expected_sender = format!("{}{}", ADMIN_SERVICE_ID_PREFIX, origin);
+        let expected_recipient = format!("{}{}", ADMIN_SERVICE_ID_PREFIX, self.node_id);
+        if admin_message.get_sender() != expected_sender
+            || admin_message.get_recipient() != expected_recipient
+        {
+            warn!(
+                "Dropping relayed admin message from {} to {} that did not come from {}",
+                admin_message.get_sender(),
+                admin_message.get_recipient(),
+                origin
+            );
+            return Ok(());
+        }
+
+        // The internal admin service is at the node id with an identical name
+        let network_msg_bytes = create_message(
+            msg.get_message().to_vec(),
+            CircuitMessageType::ADMIN_DIRECT_MESSAGE,
+        )?;
+        sender.send(SendRequest::new(expected_recipient, network_msg_bytes))?;
+
+        Ok(())
+    }
+}
+
+/// Returns the nodes a message on a relayed circuit passes through from its origin to its
+/// destination, including both.
+fn relay_path(relays: &[String], origin: &str, destination: &str) -> Vec<String> {
+    let mut path = vec![origin.to_string()];
+    if origin != destination {
+        loop {
+            let hop = next_hop(relays, &path, destination).to_string();
+            let done = hop == destination;
+            path.push(hop);
+            if done {
+                break;
+            }
+        }
+    }
+    path
+}
+
+/// Returns the id and relays of the first relayed circuit that both nodes are members of, which
+/// admin messages between the nodes are relayed on.
+pub(crate) fn shared_relayed_circuit(
+    state: &SplinterState,
+    node_id: &str,
+    other_node_id: &str,
+) -> Result<Option<(String, Vec<String>)>, DispatchError> {
+    let circuits = state
+        .circuits()
+        .map_err(|err| DispatchError::HandleError(err.context()))?;
+    Ok(circuits
+        .into_iter()
+        .find_map(|(circuit_id, circuit)| match circuit.routes() {
+            RouteType::Relayed(relays)
+                if circuit.members().contains(node_id)
+                    && circuit.members().contains(other_node_id) =>
+            {
+                Some((circuit_id, relays.clone()))
+            }
+            _ => None,
+        }))
+}
+
+/// Returns the node a message on a relayed circuit is sent to next: the first of the circuit's
+/// relays that is not on its route, or its destination once it has passed through all of them.
+pub(crate) fn next_hop<'a>(
+    relays: &'a [String],
+    route: &[String],
+    destination: &'a str,
+) -> &'a str {
+    relays
+        .iter()
+        .find(|relay| relay.as_str() != destination && !route.contains(relay))
+        .map(String::as_str)
+        .unwrap_or(destination)
+}
+
+/// Creates the CircuitRelayedMessage that carries the given direct or admin message bytes from
+/// this node to the node of its recipient.
+pub(crate) fn create_relayed_message(
+    circuit: &str,
+    destination: &str,
+    node_id: &str,
+    message_type: CircuitMessageType,
+    message: Vec<u8>,
+) -> Result<Vec<u8>, protobuf::error::ProtobufError> {
+    let mut relayed_message = CircuitRelayedMessage::new();
+    relayed_message.set_circuit(circuit.to_string());
+    relayed_message.set_destination(destination.to_string());
+    relayed_message.set_route(RepeatedField::from_vec(vec![node_id.to_string()]));
+    relayed_message.set_ttl(RELAYED_MESSAGE_TTL);
+    relayed_message.set_message_type(message_type);
+    relayed_message.set_message(message);
+
+    create_message(
+        relayed_message.write_to_bytes()?,
+        CircuitMessageType::CIRCUIT_RELAYED_MESSAGE,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::channel::{SendError, Sender};
+    use crate::circuit::directory::CircuitDirectory;
+    use crate::circuit::handlers::{AdminDirectMessageHandler, CircuitDirectMessageHandler};
+    use crate::circuit::service::{Service, SplinterNode};
+    use crate::circuit::{AuthorizationType, Circuit, DurabilityType, PersistenceType};
+    use crate::network::dispatch::Dispatcher;
+    use crate::protos::circuit::CircuitMessage;
+    use crate::protos::network::NetworkMessage;
+
+    // Test that the next hop of a relayed message is the first relay not on its route, or the
+    // destination
+    #[test]
+    fn test_next_hop() {
+        let relays = vec!["hub_a".to_string(), "hub_b".to_string()];
+
+        assert_eq!(next_hop(&relays, &["123".to_string()], "345"), "hub_a");
+        assert_eq!(
+            next_hop(&relays, &["123".to_string(), "hub_a".to_string()], "345"),
+            "hub_b"
+        );
+        assert_eq!(
+            next_hop(
+                &relays,
+                &["123".to_string(), "hub_a".to_string(), "hub_b".to_string()],
+                "345"
+            ),
+            "345"
+        );
+        assert_eq!(next_hop(&relays, &["hub_b".to_string()], "hub_a"), "hub_a");
+    }
+
+    // Test that a direct message on a relayed circuit is sent through the relay to the node of
+    // its recipient, which delivers it to the recipient service
+    #[test]
+    fn test_circuit_relayed_message_delivery() {
+        let direct_message = create_direct_message();
+
+        // node 123 relays the message to the hub
+        let sender_123 = Box::new(MockNetworkSender::default());
+        let mut dispatcher_123 = Dispatcher::new(sender_123.box_clone());
+        dispatcher_123.set_handler(
+            CircuitMessageType::CIRCUIT_DIRECT_MESSAGE,
+            Box::new(CircuitDirectMessageHandler::new(
+                "123".to_string(),
+                create_state(),
+            )),
+        );
+        dispatcher_123
+            .dispatch(
+                "def_network",
+                &CircuitMessageType::CIRCUIT_DIRECT_MESSAGE,
+                direct_message.clone(),
+            )
+            .unwrap();
+
+        let (recipient, relayed_message) = take_relayed_message(&sender_123);
+        assert_eq!(recipient, "hub");
+        assert_eq!(relayed_message.get_circuit(), "alpha");
+        assert_eq!(relayed_message.get_destination(), "345");
+        assert_eq!(relayed_message.get_route(), &["123".to_string()]);
+        assert_eq!(relayed_message.get_ttl(), RELAYED_MESSAGE_TTL);
+        assert_eq!(
+            relayed_message.get_message_type(),
+            CircuitMessageType::CIRCUIT_DIRECT_MESSAGE
+        );
+        assert_eq!(relayed_message.get_message(), direct_message.as_slice());
+
+        // the hub relays the message to node 345
+        let sender_hub = Box::new(MockNetworkSender::default());
+        let mut dispatcher_hub = Dispatcher::new(sender_hub.box_clone());
+        dispatcher_hub.set_handler(
+            CircuitMessageType::CIRCUIT_RELAYED_MESSAGE,
+            Box::new(CircuitRelayedMessageHandler::new(
+                "hub".to_string(),
+                create_state(),
+            )),
+        );
+        dispatcher_hub
+            .dispatch(
+                "123",
+                &CircuitMessageType::CIRCUIT_RELAYED_MESSAGE,
+                relayed_message.write_to_bytes().unwrap(),
+            )
+            .unwrap();
+
+        let (recipient, relayed_message) = take_relayed_message(&sender_hub);
+        assert_eq!(recipient, "345");
+        assert_eq!(
+            relayed_message.get_route(),
+            &["123".to_string(), "hub".to_string()]
+        );
+        assert_eq!(relayed_message.get_ttl(), RELAYED_MESSAGE_TTL - 1);
+
+        // node 345 delivers the message to abc
+        let sender_345 = Box::new(MockNetworkSender::default());
+        let mut dispatcher_345 = Dispatcher::new(sender_345.box_clone());
+        dispatcher_345.set_handler(
+            CircuitMessageType::CIRCUIT_RELAYED_MESSAGE,
+            Box::new(CircuitRelayedMessageHandler::new(
+                "345".to_string(),
+                create_state(),
+            )),
+        );
+        dispatcher_345
+            .dispatch(
+                "hub",
+                &CircuitMessageType::CIRCUIT_RELAYED_MESSAGE,
+                relayed_message.write_to_bytes().unwrap(),
+            )
+            .unwrap();
+
+        let send_request = sender_345.sent().lock().unwrap().remove(0);
+        assert_eq!(send_request.recipient(), "abc_network");
+        let network_msg: NetworkMessage =
+            protobuf::parse_from_bytes(send_request.payload()).unwrap();
+        let circuit_msg: CircuitMessage =
+            protobuf::parse_from_bytes(network_msg.get_payload()).unwrap();
+        assert_eq!(
+            circuit_msg.get_message_type(),
+            CircuitMessageType::CIRCUIT_DIRECT_MESSAGE
+        );
+        assert_eq!(circuit_msg.get_payload(), direct_message.as_slice());
+    }
+
+    // Test that a relayed message is dropped if it already passed through the relay, if its ttl
+    // has run out, or if it did not come from the last node on its route
+    #[test]
+    fn test_circuit_relayed_message_dropped() {
+        let sender = Box::new(MockNetworkSender::default());
+        let mut dispatcher = Dispatcher::new(sender.box_clone());
+        dispatcher.set_handler(
+            CircuitMessageType::CIRCUIT_RELAYED_MESSAGE,
+            Box::new(CircuitRelayedMessageHandler::new(
+                "hub".to_string(),
+                create_state(),
+            )),
+        );
+
+        let mut relayed_message = CircuitRelayedMessage::new();
+        relayed_message.set_circuit("alpha".into());
+        relayed_message.set_destination("345".into());
+        relayed_message.set_route(RepeatedField::from_vec(vec![
+            "123".to_string(),
+            "hub".to_string(),
+        ]));
+        relayed_message.set_ttl(RELAYED_MESSAGE_TTL);
+        relayed_message.set_message_type(CircuitMessageType::CIRCUIT_DIRECT_MESSAGE);
+        relayed_message.set_message(create_direct_message());
+
+        dispatcher
+            .dispatch(
+                "123",
+                &CircuitMessageType::CIRCUIT_RELAYED_MESSAGE,
+                relayed_message.write_to_bytes().unwrap(),
+            )
+            .unwrap();
+        assert!(sender.sent().lock().unwrap().is_empty());
+
+        relayed_message.set_route(RepeatedField::from_vec(vec!["123".to_string()]));
+        relayed_message.set_ttl(0);
+
+        dispatcher
+            .dispatch(
+                "123",
+                &CircuitMessageType::CIRCUIT_RELAYED_MESSAGE,
+                relayed_message.write_to_bytes().unwrap(),
+            )
+            .unwrap();
+        assert!(sender.sent().lock().unwrap().is_empty());
+
+        relayed_message.set_ttl(RELAYED_MESSAGE_TTL);
+
+        dispatcher
+            .dispatch(
+                "345",
+                &CircuitMessageType::CIRCUIT_RELAYED_MESSAGE,
+                relayed_message.write_to_bytes().unwrap(),
+            )
+            .unwrap();
+        assert!(sender.sent().lock().unwrap().is_empty());
+    }
+
+    // Test that a relayed message is not delivered if its sender is not connected to the node the
+    // message originated from
+    #[test]
+    fn test_circuit_relayed_message_spoofed_sender() {
+        let sender = Box::new(MockNetworkSender::default());
+        let mut dispatcher = Dispatcher::new(sender.box_clone());
+        dispatcher.set_handler(
+            CircuitMessageType::CIRCUIT_RELAYED_MESSAGE,
+            Box::new(CircuitRelayedMessageHandler::new(
+                "345".to_string(),
+                create_state(),
+            )),
+        );
+
+        // the hub claims to be the origin of a message from def, which is connected to 123
+        let mut relayed_message = CircuitRelayedMessage::new();
+        relayed_message.set_circuit("alpha".into());
+        relayed_message.set_destination("345".into());
+        relayed_message.set_route(RepeatedField::from_vec(vec!["hub".to_string()]));
+        relayed_message.set_ttl(RELAYED_MESSAGE_TTL);
+        relayed_message.set_message_type(CircuitMessageType::CIRCUIT_DIRECT_MESSAGE);
+        relayed_message.set_message(create_direct_message());
+
+        dispatcher
+            .dispatch(
+                "hub",
+                &CircuitMessageType::CIRCUIT_RELAYED_MESSAGE,
+                relayed_message.write_to_bytes().unwrap(),
+            )
+            .unwrap();
+        assert!(sender.sent().lock().unwrap().is_empty());
+    }
+
+    // Test that an admin message from the local admin service to a node it shares a relayed
+    // circuit with is sent through the relay, and delivered to the admin service of that node
+    #[test]
+    fn test_admin_message_relayed() {
+        let mut admin_message = AdminDirectMessage::new();
+        admin_message.set_circuit("admin".into());
+        admin_message.set_sender("admin::123".into());
+        admin_message.set_recipient("admin::345".into());
+        admin_message.set_payload(b"test".to_vec());
+        admin_message.set_correlation_id("1234".into());
+        let admin_message = admin_message.write_to_bytes().unwrap();
+
+        let sender_123 = Box::new(MockNetworkSender::default());
+        let mut dispatcher_123 = Dispatcher::new(sender_123.box_clone());
+        dispatcher_123.set_handler(
+            CircuitMessageType::ADMIN_DIRECT_MESSAGE,
+            Box::new(AdminDirectMessageHandler::new(
+                "123".to_string(),
+                create_state(),
+            )),
+        );
+        dispatcher_123
+            .dispatch(
+                "admin::123",
+                &CircuitMessageType::ADMIN_DIRECT_MESSAGE,
+                admin_message.clone(),
+            )
+            .unwrap();
+
+        let (recipient, relayed_message) = take_relayed_message(&sender_123);
+        assert_eq!(recipient, "hub");
+        assert_eq!(relayed_message.get_circuit(), "alpha");
+        assert_eq!(
+            relayed_message.get_message_type(),
+            CircuitMessageType::ADMIN_DIRECT_MESSAGE
+        );
+
+        // skip the hub by relaying the message as it would
+        let mut relayed_message = relayed_message;
+        relayed_message.mut_route().push("hub".into());
+
+        let sender_345 = Box::new(MockNetworkSender::default());
+        let mut dispatcher_345 = Dispatcher::new(sender_345.box_clone());
+        dispatcher_345.set_handler(
+            CircuitMessageType::CIRCUIT_RELAYED_MESSAGE,
+            Box::new(CircuitRelayedMessageHandler::new(
+                "345".to_string(),
+                create_state(),
+            )),
+        );
+        dispatcher_345
+            .dispatch(
+                "hub",
+                &CircuitMessageType::CIRCUIT_RELAYED_MESSAGE,
+                relayed_message.write_to_bytes().unwrap(),
+            )
+            .unwrap();
+
+        let send_request = sender_345.sent().lock().unwrap().remove(0);
+        assert_eq!(send_request.recipient(), "admin::345");
+        let network_msg: NetworkMessage =
+            protobuf::parse_from_bytes(send_request.payload()).unwrap();
+        let circuit_msg: CircuitMessage =
+            protobuf::parse_from_bytes(network_msg.get_payload()).unwrap();
+        assert_eq!(
+            circuit_msg.get_message_type(),
+            CircuitMessageType::ADMIN_DIRECT_MESSAGE
+        );
+        assert_eq!(circuit_msg.get_payload(), admin_message.as_slice());
+    }
+
+    // Creates the state of circuit alpha, on which service def on node 123 sends messages to
+    // service abc on node 345 through the hub
+    fn create_state() -> SplinterState {
+        let circuit = Circuit::builder()
+            .with_id("alpha".into())
+            .with_auth(AuthorizationType::Trust)
+            .with_members(vec!["123".into(), "345".into(), "hub".into()])
+            .with_roster(vec!["abc".into(), "def".into()])
+            .with_persistence(PersistenceType::Any)
+            .with_durability(DurabilityType::NoDurability)
+            .with_routes(RouteType::Relayed(vec!["hub".into()]))
+            .with_circuit_management_type("circuit_relayed_test_app".into())
+            .build()
+            .expect("Should have built a correct circuit");
+
+        let mut circuit_directory = CircuitDirectory::new();
+        circuit_directory.add_circuit("alpha".to_string(), circuit);
+
+        let state = SplinterState::new("memory".to_string(), circuit_directory);
+
+        let node_123 = SplinterNode::new("123".to_string(), vec!["123.0.0.1:0".to_string()]);
+        let node_345 = SplinterNode::new("345".to_string(), vec!["123.0.0.1:1".to_string()]);
+
+        let service_abc =
+            Service::new("abc".to_string(), Some("abc_network".to_string()), node_345);
+        let service_def =
+            Service::new("def".to_string(), Some("def_network".to_string()), node_123);
+        state
+            .add_service(ServiceId::new("alpha".into(), "abc".into()), service_abc)
+            .unwrap();
+        state
+            .add_service(ServiceId::new("alpha".into(), "def".into()), service_def)
+            .unwrap();
+
+        state
+    }
+
+    fn create_direct_message() -> Vec<u8> {
+        let mut direct_message = CircuitDirectMessage::new();
+        direct_message.set_circuit("alpha".into());
+        direct_message.set_sender("def".into());
+        direct_message.set_recipient("abc".into());
+        direct_message.set_payload(b"test".to_vec());
+        direct_message.set_correlation_id("1234".into());
+        direct_message.write_to_bytes().unwrap()
+    }
+
+    fn take_relayed_message(sender: &MockNetworkSender) -> (String, CircuitRelayedMessage) {
+        let send_request = sender.sent().lock().unwrap().remove(0);
+        let network_msg: NetworkMessage =
+            protobuf::parse_from_bytes(send_request.payload()).unwrap();
+        let circuit_msg: CircuitMessage =
+            protobuf::parse_from_bytes(network_msg.get_payload()).unwrap();
+        assert_eq!(
+            circuit_msg.get_message_type(),
+            CircuitMessageType::CIRCUIT_RELAYED_MESSAGE
+        );
+        (
+            send_request.recipient().to_string(),
+            protobuf::parse_from_bytes(circuit_msg.get_payload()).unwrap(),
+        )
+    }
+
+    #[derive(Default)]
+    struct MockNetworkSender {
+        sent: Arc<Mutex<Vec<SendRequest>>>,
+    }
+
+    impl MockNetworkSender {
+        pub fn sent(&self) -> &Arc<Mutex<Vec<SendRequest>>> {
+            &self.sent
+        }
+    }
+
+    impl Sender<SendRequest> for MockNetworkSender {
+        fn send(&self, message: SendRequest) -> Result<(), SendError> {
+            self.sent.lock().unwrap().push(message);
+            Ok(())
+        }
+
+        fn box_clone(&self) -> Box<dyn Sender<SendRequest>> {
+            Box::new(MockNetworkSender {
+                sent: self.sent.clone(),
+            })
+        }
+    }
+}
@@ -17,6 +17,7 @@ use crate::circuit::service::{Service, ServiceId, SplinterNode};
 use crate::circuit::{ServiceDefinition, SplinterState};
 use crate::network::dispatch::{DispatchError, Handler, MessageContext};
 use crate::network::sender::SendRequest;
+use crate::network::Network;
 use crate::protos::circuit::{
     CircuitMessageType, ServiceConnectRequest, ServiceConnectResponse,
     ServiceConnectResponse_Status, ServiceDisconnectRequest, ServiceDisconnectResponse,
@@ -30,6 +31,8 @@ pub struct ServiceConnectRequestHandler {
     node_id: String,
     endpoint: String,
     state: SplinterState,
+    network: Option<Network>,
+    allowed_uids: Vec<u32>,
 }
 
 impl Handler<CircuitMessageType, ServiceConnectRequest> for ServiceConnectRequestHandler {
@@ -82,10 +85,34 @@ impl Handler<CircuitMessageType, ServiceConnectRequest> for ServiceConnectReques
                     }
                 };
 
+                let credentials = self
+                    .network
+                    .as_ref()
+                    .and_then(|network| network.get_peer_credentials(context.source_peer_id()));
+
                 if !service.allowed_nodes.contains(&self.node_id) {
                     response.set_status(ServiceConnectResponse_Status::ERROR_NOT_AN_ALLOWED_NODE);
                     response.set_error_message(format!("{} is not allowed on this node", unique_id))
+                } else if let Some(uid) = credentials
+                    .as_ref()
+                    .map(|credentials| credentials.uid)
+                    .filter(|uid| !self.allowed_uids.is_empty() && !self.allowed_uids.contains(uid))
+                {
+                    warn!(
+                        "Service {} denied; it connected as uid {}, which is not allowed",
+                        unique_id, uid
+                    );
+                    response.set_status(ServiceConnectResponse_Status::ERROR_NOT_AN_ALLOWED_USER);
+                    response
+                        .set_error_message(format!("{} may not connect as uid {}", unique_id, uid))
                 } else {
+                    if let Some(credentials) = credentials {
+                        info!(
+                            "Service {} connected from process {} (uid {}, gid {})",
+                            unique_id, credentials.pid, credentials.uid, credentials.gid
+                        );
+                    }
+
                     let node = SplinterNode::new(
                         self.node_id.to_string(),
                         vec![self.endpoint.to_string()],
@@ -147,8 +174,25 @@ impl ServiceConnectRequestHandler {
             node_id,
             endpoint,
             state,
+            network: None,
+            allowed_uids: vec![],
         }
     }
+
+    /// Looks up the credentials of the processes that services connect from, for connections
+    /// that provide them, in the given network.
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// Only accepts services whose connections provide process credentials if they run as one
+    /// of the given user IDs. If no user IDs are given, any user may connect. Requires a network
+    /// to look up credentials in; see `with_network`.
+    pub fn with_allowed_uids(mut self, allowed_uids: Vec<u32>) -> Self {
+        self.allowed_uids = allowed_uids;
+        self
+    }
 }
 
 // Implements a handler that handles ServiceDisconnectRequest
@@ -428,6 +472,89 @@ mod tests {
         assert!(state.get_service(&id).unwrap().is_some());
     }
 
+    #[cfg(all(feature = "unix-transport", target_os = "linux"))]
+    #[test]
+    // Test that if the service connects over a unix socket as a user that is not allowed, a
+    // ServiceConnectResponse is returned with an ERROR_NOT_AN_ALLOWED_USER
+    fn test_service_connect_request_handler_not_an_allowed_user() {
+        use crate::mesh::Mesh;
+        use crate::transport::unix::UnixTransport;
+        use crate::transport::Transport;
+
+        let dir = tempdir::TempDir::new("service_handlers").expect("Unable to create temp dir");
+        let endpoint = format!("unix://{}", dir.path().join("service.sock").display());
+
+        let mut transport = UnixTransport::default();
+        let mut listener = transport.listen(&endpoint).expect("Unable to listen");
+        let handle = std::thread::spawn(move || {
+            UnixTransport::default()
+                .connect(&endpoint)
+                .expect("Unable to connect")
+        });
+        let connection = listener.accept().expect("Unable to accept");
+        let _service_connection = handle.join().unwrap();
+
+        let uid = connection
+            .peer_credentials()
+            .expect("Connection did not provide credentials")
+            .uid;
+
+        let network = Network::new(Mesh::new(5, 5), 0).unwrap();
+        network
+            .add_peer("PEER".to_string(), connection)
+            .expect("Unable to add peer");
+
+        let sender = Box::new(MockSender::default());
+        let mut dispatcher = Dispatcher::new(sender.box_clone());
+
+        let mut circuit_directory = CircuitDirectory::new();
+        circuit_directory.add_circuit("alpha".to_string(), build_circuit());
+
+        let state = SplinterState::new("memory".to_string(), circuit_directory);
+        let handler = ServiceConnectRequestHandler::new(
+            "123".to_string(),
+            "127.0.0.1:0".to_string(),
+            state.clone(),
+        )
+        .with_network(network)
+        .with_allowed_uids(vec![uid.wrapping_add(1)]);
+
+        dispatcher.set_handler(
+            CircuitMessageType::SERVICE_CONNECT_REQUEST,
+            Box::new(handler),
+        );
+        let mut connect_request = ServiceConnectRequest::new();
+        connect_request.set_circuit("alpha".into());
+        connect_request.set_service_id("abc".into());
+        let connect_bytes = connect_request.write_to_bytes().unwrap();
+
+        dispatcher
+            .dispatch(
+                "PEER",
+                &CircuitMessageType::SERVICE_CONNECT_REQUEST,
+                connect_bytes,
+            )
+            .unwrap();
+        let send_requests = sender.sent();
+        assert_eq!(send_requests.len(), 1);
+        let send_request = send_requests.get(0).unwrap().clone();
+
+        let network_msg: NetworkMessage =
+            protobuf::parse_from_bytes(send_request.payload()).unwrap();
+        let circuit_msg: CircuitMessage =
+            protobuf::parse_from_bytes(network_msg.get_payload()).unwrap();
+        let connect_response: ServiceConnectResponse =
+            protobuf::parse_from_bytes(circuit_msg.get_payload()).unwrap();
+
+        assert_eq!(
+            connect_response.get_status(),
+            ServiceConnectResponse_Status::ERROR_NOT_AN_ALLOWED_USER
+        );
+
+        let id = ServiceId::new("alpha".into(), "abc".into());
+        assert!(state.get_service(&id).unwrap().is_none());
+    }
+
     #[test]
     // Test that if the service is in a circuit and already connected, a ServiceConnectResponse is
     // returned with an ERROR_SERVICE_ALREADY_REGISTERED
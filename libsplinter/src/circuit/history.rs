@@ -0,0 +1,393 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Revision history of the circuits in splinter state.
+//!
+//! Every committed change to a circuit is recorded as a `CircuitRevision`, along with the
+//! proposal that produced it and the votes the proposal received. Each revision points to the
+//! circuit hash of the revision before it, so the history of a circuit forms a hash chain from its
+//! creation to its current definition. Each revision also carries a hash of its own contents, so
+//! a modified revision can be detected.
+//!
+//! Only the most recent `MAX_CIRCUIT_HISTORY_LEN` revisions of a circuit are kept.
+
+use openssl::sha::sha256;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::circuit::{Circuit, CircuitBuildError};
+use crate::hex::to_hex;
+
+/// The maximum number of revisions kept in the history of a circuit; older revisions are dropped.
+pub const MAX_CIRCUIT_HISTORY_LEN: usize = 100;
+
+/// A committed revision of a circuit.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct CircuitRevision {
+    revision: u64,
+    circuit_hash: String,
+    #[serde(default)]
+    previous_hash: Option<String>,
+    proposal_type: String,
+    requester: String,
+    requester_node_id: String,
+    votes: Vec<RevisionVote>,
+    committed_at: u64,
+    /// The circuit definition after the revision, or `None` if the circuit was removed
+    #[serde(default)]
+    circuit: Option<Circuit>,
+    #[serde(default)]
+    hash: String,
+}
+
+/// The contents of a revision that are covered by its hash.
+#[derive(Serialize)]
+struct HashedRevision<'a> {
+    revision: u64,
+    circuit_hash: &'a str,
+    previous_hash: Option<&'a str>,
+    proposal_type: &'a str,
+    requester: &'a str,
+    requester_node_id: &'a str,
+    votes: &'a [RevisionVote],
+    committed_at: u64,
+    circuit: Option<&'a Circuit>,
+}
+
+impl CircuitRevision {
+    pub fn builder() -> CircuitRevisionBuilder {
+        CircuitRevisionBuilder::default()
+    }
+
+    /// The position of the revision in the circuit's history, starting at 1.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// The hash of the circuit definition proposed by the proposal that produced the revision.
+    pub fn circuit_hash(&self) -> &str {
+        &self.circuit_hash
+    }
+
+    /// The circuit hash of the previous revision, or `None` for the first revision.
+    pub fn previous_hash(&self) -> Option<&str> {
+        self.previous_hash.as_deref()
+    }
+
+    pub fn proposal_type(&self) -> &str {
+        &self.proposal_type
+    }
+
+    /// The hex-encoded public key of the proposal's requester.
+    pub fn requester(&self) -> &str {
+        &self.requester
+    }
+
+    pub fn requester_node_id(&self) -> &str {
+        &self.requester_node_id
+    }
+
+    pub fn votes(&self) -> &[RevisionVote] {
+        &self.votes
+    }
+
+    /// The time the revision was committed, in seconds since the epoch.
+    pub fn committed_at(&self) -> u64 {
+        self.committed_at
+    }
+
+    pub fn circuit(&self) -> Option<&Circuit> {
+        self.circuit.as_ref()
+    }
+
+    /// The hex-encoded SHA-256 hash of the revision's contents, assigned when the revision is
+    /// added to a circuit's history.
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    fn compute_hash(&self) -> String {
+        let hashed_revision = HashedRevision {
+            revision: self.revision,
+            circuit_hash: &self.circuit_hash,
+            previous_hash: self.previous_hash.as_deref(),
+            proposal_type: &self.proposal_type,
+            requester: &self.requester,
+            requester_node_id: &self.requester_node_id,
+            votes: &self.votes,
+            committed_at: self.committed_at,
+            circuit: self.circuit.as_ref(),
+        };
+        // serializing the revision cannot fail, as it only contains maps with string keys
+        let bytes = serde_json::to_vec(&hashed_revision).unwrap_or_default();
+        to_hex(&sha256(&bytes))
+    }
+}
+
+#[derive(Default)]
+pub struct CircuitRevisionBuilder {
+    circuit_hash: Option<String>,
+    proposal_type: Option<String>,
+    requester: Option<String>,
+    requester_node_id: Option<String>,
+    votes: Vec<RevisionVote>,
+    committed_at: Option<u64>,
+    circuit: Option<Circuit>,
+}
+
+impl CircuitRevisionBuilder {
+    pub fn with_circuit_hash(mut self, circuit_hash: String) -> Self {
+        self.circuit_hash = Some(circuit_hash);
+        self
+    }
+
+    pub fn with_proposal_type(mut self, proposal_type: String) -> Self {
+        self.proposal_type = Some(proposal_type);
+        self
+    }
+
+    pub fn with_requester(mut self, requester: String) -> Self {
+        self.requester = Some(requester);
+        self
+    }
+
+    pub fn with_requester_node_id(mut self, requester_node_id: String) -> Self {
+        self.requester_node_id = Some(requester_node_id);
+        self
+    }
+
+    pub fn with_votes<I: IntoIterator<Item = RevisionVote>>(mut self, votes: I) -> Self {
+        self.votes = votes.into_iter().collect();
+        self
+    }
+
+    pub fn with_committed_at(mut self, committed_at: u64) -> Self {
+        self.committed_at = Some(committed_at);
+        self
+    }
+
+    pub fn with_circuit(mut self, circuit: Circuit) -> Self {
+        self.circuit = Some(circuit);
+        self
+    }
+
+    /// Builds the revision. The revision number, previous hash and hash are assigned when the
+    /// revision is added to a circuit's history.
+    pub fn build(self) -> Result<CircuitRevision, CircuitBuildError> {
+        let circuit_hash = self.circuit_hash.ok_or_else(|| {
+            CircuitBuildError("circuit hash is required to build a circuit revision".into())
+        })?;
+        let proposal_type = self.proposal_type.ok_or_else(|| {
+            CircuitBuildError("proposal type is required to build a circuit revision".into())
+        })?;
+        let requester = self.requester.ok_or_else(|| {
+            CircuitBuildError("requester is required to build a circuit revision".into())
+        })?;
+        let requester_node_id = self.requester_node_id.ok_or_else(|| {
+            CircuitBuildError("requester node id is required to build a circuit revision".into())
+        })?;
+        let committed_at = self.committed_at.ok_or_else(|| {
+            CircuitBuildError("commit time is required to build a circuit revision".into())
+        })?;
+
+        Ok(CircuitRevision {
+            revision: 0,
+            circuit_hash,
+            previous_hash: None,
+            proposal_type,
+            requester,
+            requester_node_id,
+            votes: self.votes,
+            committed_at,
+            circuit: self.circuit,
+            hash: String::new(),
+        })
+    }
+}
+
+/// A vote cast on the proposal that produced a revision.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct RevisionVote {
+    public_key: String,
+    voter_node_id: String,
+    accept: bool,
+}
+
+impl RevisionVote {
+    pub fn new(public_key: String, voter_node_id: String, accept: bool) -> Self {
+        RevisionVote {
+            public_key,
+            voter_node_id,
+            accept,
+        }
+    }
+
+    /// The hex-encoded public key of the voter.
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    pub fn voter_node_id(&self) -> &str {
+        &self.voter_node_id
+    }
+
+    pub fn accept(&self) -> bool {
+        self.accept
+    }
+}
+
+/// Appends a revision to the given history, linking it to the last revision in the history.
+pub(crate) fn append_revision(history: &mut Vec<CircuitRevision>, revision: CircuitRevision) {
+    let revision = link_revision(history, revision);
+    push_revision(history, revision);
+}
+
+/// Appends a revision that has already been numbered and linked, such as one loaded from storage,
+/// dropping the oldest revisions once the history is longer than `MAX_CIRCUIT_HISTORY_LEN`.
+pub(crate) fn push_revision(history: &mut Vec<CircuitRevision>, revision: CircuitRevision) {
+    history.push(revision);
+    if history.len() > MAX_CIRCUIT_HISTORY_LEN {
+        let excess = history.len() - MAX_CIRCUIT_HISTORY_LEN;
+        history.drain(..excess);
+    }
+}
+
+/// Numbers the revision after the last revision of the history, links it to that revision's
+/// circuit hash and assigns the revision's hash.
+pub(crate) fn link_revision(
+    history: &[CircuitRevision],
+    mut revision: CircuitRevision,
+) -> CircuitRevision {
+    revision.revision = history.last().map(|last| last.revision + 1).unwrap_or(1);
+    revision.previous_hash = history.last().map(|last| last.circuit_hash.clone());
+    revision.hash = revision.compute_hash();
+    revision
+}
+
+/// Returns whether each revision in the history matches its hash, is numbered in sequence and
+/// points to the circuit hash of the revision before it.
+///
+/// The oldest revisions of a long history are dropped, so the history may start after the first
+/// revision; the link from its first revision to the dropped revision cannot be verified.
+pub fn verify_history(history: &[CircuitRevision]) -> bool {
+    let first_revision = match history.first() {
+        Some(first) => first.revision,
+        None => return true,
+    };
+
+    first_revision > 0
+        && history.iter().enumerate().all(|(i, revision)| {
+            let linked = match i.checked_sub(1) {
+                Some(previous) => {
+                    revision.previous_hash.as_deref()
+                        == Some(history[previous].circuit_hash.as_str())
+                }
+                None => (revision.revision == 1) == revision.previous_hash.is_none(),
+            };
+
+            revision.revision == first_revision + i as u64
+                && linked
+                && revision.hash == revision.compute_hash()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // test that appended revisions are numbered in sequence and chained by circuit hash, and that
+    // a history with a broken link fails verification
+    fn test_append_and_verify_history() {
+        let mut history = vec![];
+        append_revision(&mut history, revision("hash_1", "Create"));
+        append_revision(&mut history, revision("hash_2", "AddNode"));
+        append_revision(&mut history, revision("hash_3", "Destroy"));
+
+        assert_eq!(history[0].revision(), 1);
+        assert_eq!(history[0].previous_hash(), None);
+        assert_eq!(history[1].revision(), 2);
+        assert_eq!(history[1].previous_hash(), Some("hash_1"));
+        assert_eq!(history[2].revision(), 3);
+        assert_eq!(history[2].previous_hash(), Some("hash_2"));
+        assert!(verify_history(&history));
+
+        history[1].circuit_hash = "tampered".into();
+        assert!(!verify_history(&history));
+
+        history.remove(1);
+        assert!(!verify_history(&history));
+    }
+
+    #[test]
+    // test that a revision whose contents are modified fails verification, even if its links are
+    // intact
+    fn test_verify_history_recomputes_hash() {
+        let mut history = vec![];
+        append_revision(&mut history, revision("hash_1", "Create"));
+        append_revision(&mut history, revision("hash_2", "AddNode"));
+        assert!(verify_history(&history));
+
+        history[1].requester_node_id = "node_c".into();
+        assert!(!verify_history(&history));
+    }
+
+    #[test]
+    // test that only the most recent revisions of a history are kept, and that the remaining
+    // history can still be verified
+    fn test_history_is_bounded() {
+        let mut history = vec![];
+        for i in 0..MAX_CIRCUIT_HISTORY_LEN + 10 {
+            append_revision(
+                &mut history,
+                revision(&format!("hash_{}", i), "UpdateRoster"),
+            );
+        }
+
+        assert_eq!(history.len(), MAX_CIRCUIT_HISTORY_LEN);
+        assert_eq!(history[0].revision(), 11);
+        assert_eq!(
+            history.last().map(CircuitRevision::revision),
+            Some(MAX_CIRCUIT_HISTORY_LEN as u64 + 10)
+        );
+        assert!(verify_history(&history));
+    }
+
+    #[test]
+    // test that a revision cannot be built without a circuit hash
+    fn test_build_revision_missing_hash() {
+        assert!(CircuitRevision::builder()
+            .with_proposal_type("Create".into())
+            .with_requester("0102".into())
+            .with_requester_node_id("node_a".into())
+            .with_committed_at(0)
+            .build()
+            .is_err());
+    }
+
+    fn revision(circuit_hash: &str, proposal_type: &str) -> CircuitRevision {
+        CircuitRevision::builder()
+            .with_circuit_hash(circuit_hash.into())
+            .with_proposal_type(proposal_type.into())
+            .with_requester("0102".into())
+            .with_requester_node_id("node_a".into())
+            .with_votes(vec![RevisionVote::new(
+                "0304".into(),
+                "node_b".into(),
+                true,
+            )])
+            .with_committed_at(1_577_836_800)
+            .build()
+            .expect("Unable to build revision")
+    }
+}
@@ -13,7 +13,10 @@
 // limitations under the License.
 
 pub mod directory;
+#[cfg(feature = "circuit-durability")]
+pub mod durable;
 pub mod handlers;
+pub mod history;
 pub mod service;
 pub mod store;
 #[cfg(feature = "circuit-template")]
@@ -25,11 +28,14 @@ use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
 
 use crate::circuit::directory::CircuitDirectory;
+use crate::circuit::history::CircuitRevision;
 use crate::circuit::service::{Service, ServiceId, SplinterNode};
-use crate::circuit::store::{CircuitFilter, CircuitIter, CircuitStore, CircuitStoreError};
+use crate::circuit::store::{
+    CircuitDirectoryStore, CircuitFilter, CircuitIter, CircuitStore, CircuitStoreError,
+};
 use crate::storage::get_storage;
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
@@ -45,6 +51,9 @@ pub struct Circuit {
 
     #[serde(default = "Circuit::default_management_type")]
     circuit_management_type: String,
+
+    #[serde(default)]
+    application_metadata: Vec<u8>,
 }
 
 impl Circuit {
@@ -66,6 +75,7 @@ impl Circuit {
             durability: DurabilityType::NoDurability,
             routes: RouteType::Any,
             circuit_management_type: "".into(),
+            application_metadata: vec![],
         }
     }
 
@@ -100,6 +110,10 @@ impl Circuit {
     pub fn circuit_management_type(&self) -> &str {
         &self.circuit_management_type
     }
+
+    pub fn application_metadata(&self) -> &[u8] {
+        &self.application_metadata
+    }
 }
 
 #[derive(Default)]
@@ -113,6 +127,7 @@ pub struct CircuitBuilder {
     routes: Option<RouteType>,
 
     circuit_management_type: Option<String>,
+    application_metadata: Vec<u8>,
 }
 
 impl CircuitBuilder {
@@ -163,6 +178,12 @@ impl CircuitBuilder {
         self
     }
 
+    pub fn with_application_metadata(mut self, application_metadata: Vec<u8>) -> Self {
+        self.application_metadata = application_metadata;
+
+        self
+    }
+
     pub fn build(self) -> Result<Circuit, CircuitBuildError> {
         if self.members.is_empty() {
             return Err(CircuitBuildError(
@@ -192,6 +213,7 @@ impl CircuitBuilder {
             circuit_management_type: self
                 .circuit_management_type
                 .unwrap_or_else(Circuit::default_management_type),
+            application_metadata: self.application_metadata,
         })
     }
 }
@@ -346,16 +368,20 @@ pub enum AuthorizationType {
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum PersistenceType {
     Any,
+    Persistent,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum DurabilityType {
     NoDurability,
+    Durable,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum RouteType {
     Any,
+    /// Messages are relayed through the given members, in order.
+    Relayed(Vec<String>),
 }
 
 pub enum RosterIter<'r> {
@@ -391,6 +417,10 @@ pub struct SplinterState {
     circuit_directory: Arc<RwLock<CircuitDirectory>>,
     // Service id to Service that contains the node the service is connected to. Not persisted.
     service_directory: Arc<RwLock<HashMap<ServiceId, Service>>>,
+    // Persists each change to the circuit directory, if the state is not stored in a file. Every
+    // change holds a read guard until it has been persisted, so the store can only be replaced
+    // between changes.
+    directory_store: Arc<RwLock<Option<Arc<dyn CircuitDirectoryStore>>>>,
 }
 
 impl SplinterState {
@@ -399,13 +429,125 @@ impl SplinterState {
             storage_location,
             circuit_directory: Arc::new(RwLock::new(circuit_directory)),
             service_directory: Arc::new(RwLock::new(HashMap::new())),
+            directory_store: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Creates a `SplinterState` that loads the circuit directory from the given store, and
+    /// persists each change to the store as it is made.
+    pub fn with_directory_store(
+        storage_location: String,
+        directory_store: Box<dyn CircuitDirectoryStore>,
+    ) -> Result<Self, SplinterStateError> {
+        let circuit_directory = directory_store.load_directory().map_err(|err| {
+            SplinterStateError::from_source("Unable to load circuit directory".into(), err)
+        })?;
+
+        Ok(SplinterState {
+            storage_location,
+            circuit_directory: Arc::new(RwLock::new(circuit_directory)),
+            service_directory: Arc::new(RwLock::new(HashMap::new())),
+            directory_store: Arc::new(RwLock::new(Some(directory_store.into()))),
+        })
+    }
+
+    /// Creates a `SplinterState` backed by the database at the given URL, either `postgres://` or
+    /// `sqlite://`. The circuit store migrations must have been run against the database.
+    #[cfg(feature = "database")]
+    pub fn from_database(database_url: &str) -> Result<Self, SplinterStateError> {
+        let directory_store =
+            store::create_circuit_directory_store(database_url).map_err(|err| {
+                SplinterStateError::from_source("Unable to open circuit store".into(), err)
+            })?;
+        Self::with_directory_store(database_url.into(), directory_store)
+    }
+
+    /// Adds the nodes, circuits and circuit history of the given directory to the state. This is
+    /// used to migrate a circuit directory from one kind of storage to another, so the history is
+    /// expected to be new to the state.
+    ///
+    /// If the state is backed by a store, the directory is added to the store in a single
+    /// transaction before it is added to the state.
+    pub fn import_directory(
+        &mut self,
+        directory: CircuitDirectory,
+    ) -> Result<(), SplinterStateError> {
+        let directory_store = self.directory_store()?;
+        if let Some(directory_store) = &*directory_store {
+            directory_store
+                .import_directory(&directory)
+                .map_err(|err| {
+                    SplinterStateError::from_source(
+                        "Unable to store imported circuit directory".into(),
+                        err,
+                    )
+                })?;
+        }
+        {
+            let mut circuit_directory = self.circuit_directory.write().map_err(|_| {
+                SplinterStateError::new("Failed to get write guard for circuit directory".into())
+            })?;
+            for (node_id, node) in directory.nodes() {
+                circuit_directory.add_node(node_id.clone(), node.clone());
+            }
+            for (circuit_id, circuit) in directory.circuits() {
+                circuit_directory.add_circuit(circuit_id.clone(), circuit.clone());
+            }
+            for (circuit_id, history) in directory.circuit_histories() {
+                for revision in history {
+                    circuit_directory
+                        .add_linked_circuit_revision(circuit_id.clone(), revision.clone());
+                }
+            }
+        }
+        if directory_store.is_none() {
+            self.commit_circuit_directory()?;
         }
+        Ok(())
+    }
+
+    /// Moves the persisted state into the given store while the state is in use. The current
+    /// circuit directory is added to the store in a single transaction, after which every change
+    /// is persisted to the store instead of the state's file.
+    ///
+    /// Changes to the state wait for the migration to complete, but the state can still be read.
+    pub fn migrate_to_store(
+        &self,
+        directory_store: Box<dyn CircuitDirectoryStore>,
+    ) -> Result<(), SplinterStateError> {
+        let mut current_store = self.directory_store.write().map_err(|_| {
+            SplinterStateError::new("Failed to get write guard for directory store".into())
+        })?;
+        if current_store.is_some() {
+            return Err(SplinterStateError::new(
+                "Circuit state is already backed by a store".into(),
+            ));
+        }
+
+        directory_store
+            .import_directory(&self.circuit_directory()?)
+            .map_err(|err| {
+                SplinterStateError::from_source("Unable to migrate circuit state".into(), err)
+            })?;
+        *current_store = Some(directory_store.into());
+
+        Ok(())
     }
 
     pub fn storage_location(&self) -> &str {
         &self.storage_location
     }
 
+    fn directory_store(
+        &self,
+    ) -> Result<RwLockReadGuard<Option<Arc<dyn CircuitDirectoryStore>>>, SplinterStateError> {
+        self.directory_store
+            .read()
+            .map_err(|_| SplinterStateError::new("Failed to read directory store".into()))
+    }
+
+    /// Writes the circuit directory to the state's file. This is only required if the state is not
+    /// backed by a directory store, which persists each change as it is made.
     fn commit_circuit_directory(&self) -> Result<(), SplinterStateError> {
         // Replace stored state with the current splinter state
 
@@ -481,6 +623,12 @@ impl SplinterState {
     }
 
     pub fn add_node(&mut self, id: String, node: SplinterNode) -> Result<(), SplinterStateError> {
+        let directory_store = self.directory_store()?;
+        if let Some(directory_store) = &*directory_store {
+            directory_store.add_node(&id, &node).map_err(|err| {
+                SplinterStateError::from_source("Unable to store node".into(), err)
+            })?;
+        }
         {
             let mut circuit_directory = self.circuit_directory.write().map_err(|_| {
                 SplinterStateError::new("Failed to get write guard for circuit directory".into())
@@ -488,7 +636,9 @@ impl SplinterState {
 
             circuit_directory.add_node(id, node);
         }
-        self.commit_circuit_directory()?;
+        if directory_store.is_none() {
+            self.commit_circuit_directory()?;
+        }
         Ok(())
     }
 
@@ -497,35 +647,94 @@ impl SplinterState {
         name: String,
         circuit: Circuit,
     ) -> Result<(), SplinterStateError> {
+        let directory_store = self.directory_store()?;
+        if let Some(directory_store) = &*directory_store {
+            directory_store
+                .add_circuit(&name, &circuit)
+                .map_err(|err| {
+                    SplinterStateError::from_source("Unable to store circuit".into(), err)
+                })?;
+        }
         {
             let mut circuit_directory = self.circuit_directory.write().map_err(|_| {
                 SplinterStateError::new("Failed to get write guard for circuit directory".into())
             })?;
             circuit_directory.add_circuit(name, circuit);
         }
-        self.commit_circuit_directory()?;
+        if directory_store.is_none() {
+            self.commit_circuit_directory()?;
+        }
+        Ok(())
+    }
+
+    pub fn add_circuit_revision(
+        &mut self,
+        circuit_id: String,
+        revision: CircuitRevision,
+    ) -> Result<(), SplinterStateError> {
+        let directory_store = self.directory_store()?;
+        {
+            let mut circuit_directory = self.circuit_directory.write().map_err(|_| {
+                SplinterStateError::new("Failed to get write guard for circuit directory".into())
+            })?;
+
+            if let Some(directory_store) = &*directory_store {
+                // the directory numbers the revision and links it to the previous revision; the
+                // revision is only added to the directory once it has been stored
+                let revision =
+                    circuit_directory.next_circuit_revision(&circuit_id, revision.clone());
+                directory_store
+                    .add_circuit_revision(&circuit_id, &revision)
+                    .map_err(|err| {
+                        SplinterStateError::from_source(
+                            "Unable to store circuit revision".into(),
+                            err,
+                        )
+                    })?;
+            }
+            circuit_directory.add_circuit_revision(circuit_id, revision);
+        }
+        if directory_store.is_none() {
+            self.commit_circuit_directory()?;
+        }
         Ok(())
     }
 
     pub fn remove_node(&mut self, id: &str) -> Result<(), SplinterStateError> {
+        let directory_store = self.directory_store()?;
+        if let Some(directory_store) = &*directory_store {
+            directory_store.remove_node(id).map_err(|err| {
+                SplinterStateError::from_source("Unable to remove stored node".into(), err)
+            })?;
+        }
         {
             let mut circuit_directory = self.circuit_directory.write().map_err(|_| {
                 SplinterStateError::new("Failed to get write guard for circuit directory".into())
             })?;
             circuit_directory.remove_node(id);
         }
-        self.commit_circuit_directory()?;
+        if directory_store.is_none() {
+            self.commit_circuit_directory()?;
+        }
         Ok(())
     }
 
     pub fn remove_circuit(&mut self, name: &str) -> Result<(), SplinterStateError> {
+        let directory_store = self.directory_store()?;
+        if let Some(directory_store) = &*directory_store {
+            directory_store.remove_circuit(name).map_err(|err| {
+                SplinterStateError::from_source("Unable to remove stored circuit".into(), err)
+            })?;
+        }
         {
             let mut circuit_directory = self.circuit_directory.write().map_err(|_| {
                 SplinterStateError::new("Failed to get write guard for circuit directory".into())
             })?;
             circuit_directory.remove_circuit(name);
         }
-        self.commit_circuit_directory()?;
+        if directory_store.is_none() {
+            self.commit_circuit_directory()?;
+        }
         Ok(())
     }
 
@@ -568,6 +777,22 @@ impl SplinterState {
             .map_err(|_| SplinterStateError::new("Failed to read circuit directory".into()))?;
         Ok(circuit_directory.has_circuit(circuit_name))
     }
+
+    /// Returns the revisions of the given circuit, oldest first. The history is empty if the
+    /// circuit has never been committed on this node.
+    pub fn circuit_history(
+        &self,
+        circuit_id: &str,
+    ) -> Result<Vec<CircuitRevision>, SplinterStateError> {
+        let circuit_directory = self
+            .circuit_directory
+            .read()
+            .map_err(|_| SplinterStateError::new("Failed to read circuit directory".into()))?;
+        Ok(circuit_directory
+            .circuit_history(circuit_id)
+            .map(<[CircuitRevision]>::to_vec)
+            .unwrap_or_default())
+    }
 }
 
 impl CircuitStore for SplinterState {
@@ -601,6 +826,11 @@ impl CircuitStore for SplinterState {
         self.circuit(circuit_name)
             .map_err(|err| CircuitStoreError::new(err.context()))
     }
+
+    fn circuit_history(&self, circuit_id: &str) -> Result<Vec<CircuitRevision>, CircuitStoreError> {
+        self.circuit_history(circuit_id)
+            .map_err(|err| CircuitStoreError::new(err.context()))
+    }
 }
 
 #[derive(Debug)]
@@ -0,0 +1,330 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A circuit directory store backed by a SQL database.
+
+mod models;
+mod operations;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+mod schema;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
+
+use crate::circuit::directory::CircuitDirectory;
+use crate::circuit::history::CircuitRevision;
+use crate::circuit::service::SplinterNode;
+use crate::circuit::Circuit;
+use crate::database::create_pool;
+
+use super::{CircuitDirectoryStore, CircuitStoreError};
+use operations::add_circuit::CircuitStoreAddCircuitOperation as _;
+use operations::add_circuit_revision::CircuitStoreAddCircuitRevisionOperation as _;
+use operations::add_node::CircuitStoreAddNodeOperation as _;
+use operations::import_directory::CircuitStoreImportDirectoryOperation as _;
+use operations::load_directory::CircuitStoreLoadDirectoryOperation as _;
+use operations::remove_circuit::CircuitStoreRemoveCircuitOperation as _;
+use operations::remove_node::CircuitStoreRemoveNodeOperation as _;
+use operations::CircuitStoreOperations;
+
+/// Creates a circuit directory store for the database at the given URL. The URL's scheme selects
+/// the backend: `postgres://` or `sqlite://`.
+///
+/// The circuit store migrations must have been run against the database.
+pub fn create_circuit_directory_store(
+    database_url: &str,
+) -> Result<Box<dyn CircuitDirectoryStore>, CircuitStoreError> {
+    #[cfg(feature = "postgres")]
+    {
+        if database_url.starts_with("postgres://") {
+            return Ok(Box::new(DieselCircuitDirectoryStore::<
+                diesel::pg::PgConnection,
+            >::from_url(database_url)?));
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    {
+        if database_url.starts_with("sqlite://") {
+            return Ok(Box::new(DieselCircuitDirectoryStore::<
+                diesel::sqlite::SqliteConnection,
+            >::from_url(database_url)?));
+        }
+    }
+
+    Err(CircuitStoreError::new(format!(
+        "Unsupported database URL: {}",
+        database_url
+    )))
+}
+
+/// Persists the circuit directory to a database, using a pool of connections of type `C`.
+pub struct DieselCircuitDirectoryStore<C: diesel::Connection + 'static> {
+    pool: Pool<ConnectionManager<C>>,
+}
+
+impl<C: diesel::Connection + 'static> DieselCircuitDirectoryStore<C> {
+    pub fn new(pool: Pool<ConnectionManager<C>>) -> Self {
+        DieselCircuitDirectoryStore { pool }
+    }
+
+    fn from_url(database_url: &str) -> Result<Self, CircuitStoreError> {
+        let pool = create_pool(database_url).map_err(|err| {
+            CircuitStoreError::new(format!("Unable to connect to circuit store: {}", err))
+        })?;
+        Ok(Self::new(pool))
+    }
+
+    fn connection(&self) -> Result<PooledConnection<ConnectionManager<C>>, CircuitStoreError> {
+        self.pool.get().map_err(|err| {
+            CircuitStoreError::from_source("Unable to get database connection".into(), err)
+        })
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl CircuitDirectoryStore for DieselCircuitDirectoryStore<diesel::pg::PgConnection> {
+    fn load_directory(&self) -> Result<CircuitDirectory, CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).load_directory()
+    }
+
+    fn add_node(&self, node_id: &str, node: &SplinterNode) -> Result<(), CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).add_node(node_id, node)
+    }
+
+    fn remove_node(&self, node_id: &str) -> Result<(), CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).remove_node(node_id)
+    }
+
+    fn add_circuit(&self, circuit_id: &str, circuit: &Circuit) -> Result<(), CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).add_circuit(circuit_id, circuit)
+    }
+
+    fn remove_circuit(&self, circuit_id: &str) -> Result<(), CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).remove_circuit(circuit_id)
+    }
+
+    fn add_circuit_revision(
+        &self,
+        circuit_id: &str,
+        revision: &CircuitRevision,
+    ) -> Result<(), CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).add_circuit_revision(circuit_id, revision)
+    }
+
+    fn import_directory(&self, directory: &CircuitDirectory) -> Result<(), CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).import_directory(directory)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl CircuitDirectoryStore for DieselCircuitDirectoryStore<diesel::sqlite::SqliteConnection> {
+    fn load_directory(&self) -> Result<CircuitDirectory, CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).load_directory()
+    }
+
+    fn add_node(&self, node_id: &str, node: &SplinterNode) -> Result<(), CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).add_node(node_id, node)
+    }
+
+    fn remove_node(&self, node_id: &str) -> Result<(), CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).remove_node(node_id)
+    }
+
+    fn add_circuit(&self, circuit_id: &str, circuit: &Circuit) -> Result<(), CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).add_circuit(circuit_id, circuit)
+    }
+
+    fn remove_circuit(&self, circuit_id: &str) -> Result<(), CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).remove_circuit(circuit_id)
+    }
+
+    fn add_circuit_revision(
+        &self,
+        circuit_id: &str,
+        revision: &CircuitRevision,
+    ) -> Result<(), CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).add_circuit_revision(circuit_id, revision)
+    }
+
+    fn import_directory(&self, directory: &CircuitDirectory) -> Result<(), CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).import_directory(directory)
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    use diesel::sqlite::SqliteConnection;
+
+    use crate::circuit::history::verify_history;
+    use crate::circuit::{
+        AuthorizationType, DurabilityType, PersistenceType, RouteType, ServiceDefinition,
+        SplinterState,
+    };
+
+    #[test]
+    // test that nodes, circuits and circuit history added to the store are loaded back into the
+    // circuit directory, and that removed nodes and circuits are not
+    fn test_sqlite_circuit_directory_store() {
+        let store = create_sqlite_store();
+
+        let node_a = SplinterNode::new("node_a".into(), vec!["tcp://127.0.0.1:8044".into()]);
+        let node_b = SplinterNode::new("node_b".into(), vec!["tcp://127.0.0.1:8045".into()]);
+        store
+            .add_node("node_a", &node_a)
+            .expect("Unable to add node");
+        store
+            .add_node("node_b", &node_b)
+            .expect("Unable to add node");
+
+        let circuit = test_circuit("alpha");
+        store
+            .add_circuit("alpha", &circuit)
+            .expect("Unable to add circuit");
+        // adding the circuit again replaces it
+        store
+            .add_circuit("alpha", &circuit)
+            .expect("Unable to replace circuit");
+        store
+            .add_circuit("beta", &test_circuit("beta"))
+            .expect("Unable to add circuit");
+
+        let mut history = CircuitDirectory::new();
+        history.add_circuit_revision("alpha".into(), test_revision("hash_1"));
+        history.add_circuit_revision("alpha".into(), test_revision("hash_2"));
+        for revision in history.circuit_history("alpha").unwrap() {
+            store
+                .add_circuit_revision("alpha", revision)
+                .expect("Unable to add revision");
+        }
+
+        store.remove_node("node_b").expect("Unable to remove node");
+        store
+            .remove_circuit("beta")
+            .expect("Unable to remove circuit");
+
+        let directory = store.load_directory().expect("Unable to load directory");
+        assert_eq!(directory.nodes().len(), 1);
+        assert_eq!(directory.node("node_a"), Some(&node_a));
+        assert_eq!(directory.circuits().len(), 1);
+        assert_eq!(directory.circuit("alpha"), Some(&circuit));
+
+        let loaded_history = directory
+            .circuit_history("alpha")
+            .expect("Circuit history not loaded");
+        assert_eq!(loaded_history.len(), 2);
+        assert_eq!(loaded_history[1].circuit_hash(), "hash_2");
+        assert_eq!(loaded_history[1].previous_hash(), Some("hash_1"));
+        assert!(verify_history(loaded_history));
+    }
+
+    #[test]
+    // test that the state migrated to the store while in use is added to the store in full, that
+    // changes made afterwards are persisted to the store, and that the state cannot be migrated
+    // again
+    fn test_migrate_state_to_store() {
+        let store = create_sqlite_store();
+
+        let mut directory = CircuitDirectory::new();
+        directory.add_node(
+            "node_a".into(),
+            SplinterNode::new("node_a".into(), vec!["tcp://127.0.0.1:8044".into()]),
+        );
+        directory.add_circuit("alpha".into(), test_circuit("alpha"));
+        directory.add_circuit_revision("alpha".into(), test_revision("hash_1"));
+        let mut state = SplinterState::new("memory".into(), directory);
+
+        state
+            .migrate_to_store(Box::new(DieselCircuitDirectoryStore::new(
+                store.pool.clone(),
+            )))
+            .expect("Unable to migrate state");
+        state
+            .add_circuit("beta".into(), test_circuit("beta"))
+            .expect("Unable to add circuit");
+        state
+            .add_circuit_revision("alpha".into(), test_revision("hash_2"))
+            .expect("Unable to add revision");
+
+        let directory = store.load_directory().expect("Unable to load directory");
+        assert_eq!(directory.nodes().len(), 1);
+        assert_eq!(directory.circuits().len(), 2);
+        let history = directory
+            .circuit_history("alpha")
+            .expect("Circuit history not loaded");
+        assert_eq!(history.len(), 2);
+        assert!(verify_history(history));
+
+        if let Ok(_) = state.migrate_to_store(Box::new(DieselCircuitDirectoryStore::new(
+            store.pool.clone(),
+        ))) {
+            panic!("Should have failed because the state is already backed by a store");
+        }
+    }
+
+    fn create_sqlite_store() -> DieselCircuitDirectoryStore<SqliteConnection> {
+        // An in-memory database only lives as long as its connection, so the pool must hold one
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+            .expect("Unable to create pool");
+        sqlite::run_migrations(&*pool.get().expect("Unable to get connection"))
+            .expect("Unable to run migrations");
+        DieselCircuitDirectoryStore::new(pool)
+    }
+
+    fn test_circuit(circuit_id: &str) -> Circuit {
+        let mut arguments = BTreeMap::new();
+        arguments.insert("peer_services".to_string(), "[\"def\"]".to_string());
+
+        Circuit::builder()
+            .with_id(circuit_id.into())
+            .with_auth(AuthorizationType::Trust)
+            .with_members(vec!["node_b".into(), "node_a".into()])
+            .with_roster(vec![
+                ServiceDefinition::builder("def".into(), "scabbard".into())
+                    .with_allowed_nodes(vec!["node_b".into()])
+                    .build(),
+                ServiceDefinition::builder("abc".into(), "scabbard".into())
+                    .with_allowed_nodes(vec!["node_a".into()])
+                    .with_arguments(arguments)
+                    .build(),
+            ])
+            .with_persistence(PersistenceType::Any)
+            .with_durability(DurabilityType::NoDurability)
+            .with_routes(RouteType::Any)
+            .with_circuit_management_type("test".into())
+            .with_application_metadata(vec![1, 2, 3])
+            .build()
+            .expect("Unable to build circuit")
+    }
+
+    fn test_revision(circuit_hash: &str) -> CircuitRevision {
+        CircuitRevision::builder()
+            .with_circuit_hash(circuit_hash.into())
+            .with_proposal_type("Create".into())
+            .with_requester("0102".into())
+            .with_requester_node_id("node_a".into())
+            .with_committed_at(1_577_836_800)
+            .with_circuit(test_circuit("alpha"))
+            .build()
+            .expect("Unable to build revision")
+    }
+}
@@ -0,0 +1,245 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+
+use crate::circuit::history::CircuitRevision;
+use crate::circuit::service::SplinterNode;
+use crate::circuit::{
+    AuthorizationType, Circuit, DurabilityType, PersistenceType, RouteType, ServiceDefinition,
+};
+use crate::hex::{parse_hex, to_hex};
+
+use super::super::CircuitStoreError;
+use super::schema::*;
+
+#[derive(Insertable, Queryable, PartialEq, Debug)]
+#[table_name = "splinter_nodes"]
+pub struct NodeModel {
+    pub node_id: String,
+    /// The node's endpoints, as a JSON array
+    pub endpoints: String,
+}
+
+impl NodeModel {
+    pub fn new(node_id: &str, node: &SplinterNode) -> Result<Self, CircuitStoreError> {
+        Ok(NodeModel {
+            node_id: node_id.to_string(),
+            endpoints: to_json(node.endpoints())?,
+        })
+    }
+
+    pub fn into_node(self) -> Result<SplinterNode, CircuitStoreError> {
+        let endpoints = from_json(&self.endpoints)?;
+        Ok(SplinterNode::new(self.node_id, endpoints))
+    }
+}
+
+#[derive(Insertable, Queryable, PartialEq, Debug)]
+#[table_name = "circuits"]
+pub struct CircuitModel {
+    pub circuit_id: String,
+    pub auth: String,
+    pub persistence: String,
+    pub durability: String,
+    pub routes: String,
+    pub circuit_management_type: String,
+    /// The circuit's application metadata, hex-encoded
+    pub application_metadata: String,
+    /// The nodes messages on the circuit are relayed through, as a JSON array
+    pub relays: String,
+}
+
+#[derive(Insertable, Queryable, PartialEq, Debug)]
+#[table_name = "circuit_members"]
+pub struct CircuitMemberModel {
+    pub circuit_id: String,
+    pub node_id: String,
+    pub position: i64,
+}
+
+#[derive(Insertable, Queryable, PartialEq, Debug)]
+#[table_name = "circuit_services"]
+pub struct CircuitServiceModel {
+    pub circuit_id: String,
+    pub service_id: String,
+    pub service_type: String,
+    /// The nodes the service is allowed to run on, as a JSON array
+    pub allowed_nodes: String,
+    /// The service's arguments, as a JSON object
+    pub arguments: String,
+    pub position: i64,
+}
+
+/// The rows that make up a circuit definition.
+pub struct CircuitModels {
+    pub circuit: CircuitModel,
+    pub members: Vec<CircuitMemberModel>,
+    pub services: Vec<CircuitServiceModel>,
+}
+
+impl CircuitModels {
+    pub fn new(circuit_id: &str, circuit: &Circuit) -> Result<Self, CircuitStoreError> {
+        let members = circuit
+            .members()
+            .to_vec()
+            .into_iter()
+            .enumerate()
+            .map(|(position, node_id)| CircuitMemberModel {
+                circuit_id: circuit_id.to_string(),
+                node_id,
+                position: position as i64,
+            })
+            .collect();
+
+        let services = circuit
+            .roster()
+            .iter()
+            .enumerate()
+            .map(|(position, service)| {
+                Ok(CircuitServiceModel {
+                    circuit_id: circuit_id.to_string(),
+                    service_id: service.service_id().to_string(),
+                    service_type: service.service_type().to_string(),
+                    allowed_nodes: to_json(service.allowed_nodes())?,
+                    arguments: to_json(service.arguments())?,
+                    position: position as i64,
+                })
+            })
+            .collect::<Result<_, CircuitStoreError>>()?;
+
+        let auth = match circuit.auth() {
+            AuthorizationType::Trust => "Trust",
+        };
+        let persistence = match circuit.persistence() {
+            PersistenceType::Any => "Any",
+            PersistenceType::Persistent => "Persistent",
+        };
+        let durability = match circuit.durability() {
+            DurabilityType::NoDurability => "NoDurability",
+            DurabilityType::Durable => "Durable",
+        };
+        let (routes, relays) = match circuit.routes() {
+            RouteType::Any => ("Any", vec![]),
+            RouteType::Relayed(relays) => ("Relayed", relays.clone()),
+        };
+
+        Ok(CircuitModels {
+            circuit: CircuitModel {
+                circuit_id: circuit_id.to_string(),
+                auth: auth.into(),
+                persistence: persistence.into(),
+                durability: durability.into(),
+                routes: routes.into(),
+                circuit_management_type: circuit.circuit_management_type().to_string(),
+                application_metadata: to_hex(circuit.application_metadata()),
+                relays: to_json(&relays)?,
+            },
+            members,
+            services,
+        })
+    }
+
+    /// Builds the circuit definition. Members and services must be in order of position.
+    pub fn into_circuit(self) -> Result<Circuit, CircuitStoreError> {
+        let auth = match self.circuit.auth.as_str() {
+            "Trust" => AuthorizationType::Trust,
+            auth => return Err(invalid_value("authorization type", auth)),
+        };
+        let persistence = match self.circuit.persistence.as_str() {
+            "Any" => PersistenceType::Any,
+            "Persistent" => PersistenceType::Persistent,
+            persistence => return Err(invalid_value("persistence type", persistence)),
+        };
+        let durability = match self.circuit.durability.as_str() {
+            "NoDurability" => DurabilityType::NoDurability,
+            "Durable" => DurabilityType::Durable,
+            durability => return Err(invalid_value("durability type", durability)),
+        };
+        let routes = match self.circuit.routes.as_str() {
+            "Any" => RouteType::Any,
+            "Relayed" => RouteType::Relayed(from_json(&self.circuit.relays)?),
+            routes => return Err(invalid_value("route type", routes)),
+        };
+        let application_metadata =
+            parse_hex(&self.circuit.application_metadata).map_err(|err| {
+                CircuitStoreError::new(format!("Invalid application metadata: {}", err))
+            })?;
+
+        let roster = self
+            .services
+            .into_iter()
+            .map(|service| {
+                let allowed_nodes: Vec<String> = from_json(&service.allowed_nodes)?;
+                let arguments: BTreeMap<String, String> = from_json(&service.arguments)?;
+                Ok(
+                    ServiceDefinition::builder(service.service_id, service.service_type)
+                        .with_allowed_nodes(allowed_nodes)
+                        .with_arguments(arguments)
+                        .build(),
+                )
+            })
+            .collect::<Result<Vec<_>, CircuitStoreError>>()?;
+
+        Circuit::builder()
+            .with_id(self.circuit.circuit_id)
+            .with_auth(auth)
+            .with_members(self.members.into_iter().map(|member| member.node_id))
+            .with_roster(roster)
+            .with_persistence(persistence)
+            .with_durability(durability)
+            .with_routes(routes)
+            .with_circuit_management_type(self.circuit.circuit_management_type)
+            .with_application_metadata(application_metadata)
+            .build()
+            .map_err(|err| CircuitStoreError::new(format!("Unable to build circuit: {}", err)))
+    }
+}
+
+#[derive(Insertable, Queryable, PartialEq, Debug)]
+#[table_name = "circuit_revisions"]
+pub struct CircuitRevisionModel {
+    pub circuit_id: String,
+    pub revision: i64,
+    /// The revision, as JSON
+    pub revision_data: String,
+}
+
+impl CircuitRevisionModel {
+    pub fn new(circuit_id: &str, revision: &CircuitRevision) -> Result<Self, CircuitStoreError> {
+        Ok(CircuitRevisionModel {
+            circuit_id: circuit_id.to_string(),
+            revision: revision.revision() as i64,
+            revision_data: to_json(revision)?,
+        })
+    }
+
+    pub fn into_revision(self) -> Result<CircuitRevision, CircuitStoreError> {
+        from_json(&self.revision_data)
+    }
+}
+
+fn to_json<T: serde::Serialize + ?Sized>(value: &T) -> Result<String, CircuitStoreError> {
+    serde_json::to_string(value)
+        .map_err(|err| CircuitStoreError::from_source("Unable to serialize column".into(), err))
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(value: &str) -> Result<T, CircuitStoreError> {
+    serde_json::from_str(value)
+        .map_err(|err| CircuitStoreError::from_source("Unable to deserialize column".into(), err))
+}
+
+fn invalid_value(name: &str, value: &str) -> CircuitStoreError {
+    CircuitStoreError::new(format!("Invalid {} in circuit store: {}", name, value))
+}
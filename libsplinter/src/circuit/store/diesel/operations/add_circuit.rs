@@ -0,0 +1,92 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::super::models::CircuitModels;
+use super::super::schema::{circuit_members, circuit_services, circuits};
+use super::super::CircuitStoreError;
+use super::CircuitStoreOperations;
+use crate::circuit::Circuit;
+use diesel::{dsl::delete, dsl::insert_into, prelude::*};
+
+pub(in super::super) trait CircuitStoreAddCircuitOperation {
+    fn add_circuit(&self, circuit_id: &str, circuit: &Circuit) -> Result<(), CircuitStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> CircuitStoreAddCircuitOperation for CircuitStoreOperations<'a, diesel::pg::PgConnection> {
+    fn add_circuit(&self, circuit_id: &str, circuit: &Circuit) -> Result<(), CircuitStoreError> {
+        let models = CircuitModels::new(circuit_id, circuit)?;
+        self.conn
+            .transaction::<_, diesel::result::Error, _>(|| {
+                delete_circuit_rows(self.conn, circuit_id)?;
+                insert_into(circuits::table)
+                    .values(&models.circuit)
+                    .execute(self.conn)?;
+                insert_into(circuit_members::table)
+                    .values(&models.members)
+                    .execute(self.conn)?;
+                insert_into(circuit_services::table)
+                    .values(&models.services)
+                    .execute(self.conn)?;
+                Ok(())
+            })
+            .map_err(|err| CircuitStoreError::from_source("Failed to add circuit".into(), err))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> CircuitStoreAddCircuitOperation
+    for CircuitStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn add_circuit(&self, circuit_id: &str, circuit: &Circuit) -> Result<(), CircuitStoreError> {
+        let models = CircuitModels::new(circuit_id, circuit)?;
+        self.conn
+            .transaction::<_, diesel::result::Error, _>(|| {
+                delete_circuit_rows(self.conn, circuit_id)?;
+                insert_into(circuits::table)
+                    .values(&models.circuit)
+                    .execute(self.conn)?;
+                // SQLite does not support batch inserts
+                for member in &models.members {
+                    insert_into(circuit_members::table)
+                        .values(member)
+                        .execute(self.conn)?;
+                }
+                for service in &models.services {
+                    insert_into(circuit_services::table)
+                        .values(service)
+                        .execute(self.conn)?;
+                }
+                Ok(())
+            })
+            .map_err(|err| CircuitStoreError::from_source("Failed to add circuit".into(), err))
+    }
+}
+
+/// Deletes the rows of the given circuit's definition, leaving its history in place.
+pub(in super::super) fn delete_circuit_rows<C>(
+    conn: &C,
+    circuit_id: &str,
+) -> Result<(), diesel::result::Error>
+where
+    C: diesel::Connection,
+    <C as diesel::Connection>::Backend: 'static,
+{
+    delete(circuit_services::table.filter(circuit_services::circuit_id.eq(circuit_id)))
+        .execute(conn)?;
+    delete(circuit_members::table.filter(circuit_members::circuit_id.eq(circuit_id)))
+        .execute(conn)?;
+    delete(circuits::table.filter(circuits::circuit_id.eq(circuit_id))).execute(conn)?;
+    Ok(())
+}
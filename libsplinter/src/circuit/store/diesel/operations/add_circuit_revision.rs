@@ -0,0 +1,96 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::super::models::CircuitRevisionModel;
+use super::super::schema::circuit_revisions;
+use super::super::CircuitStoreError;
+use super::CircuitStoreOperations;
+use crate::circuit::history::{CircuitRevision, MAX_CIRCUIT_HISTORY_LEN};
+use diesel::{dsl::delete, dsl::insert_into, prelude::*};
+
+pub(in super::super) trait CircuitStoreAddCircuitRevisionOperation {
+    fn add_circuit_revision(
+        &self,
+        circuit_id: &str,
+        revision: &CircuitRevision,
+    ) -> Result<(), CircuitStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> CircuitStoreAddCircuitRevisionOperation
+    for CircuitStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn add_circuit_revision(
+        &self,
+        circuit_id: &str,
+        revision: &CircuitRevision,
+    ) -> Result<(), CircuitStoreError> {
+        let model = CircuitRevisionModel::new(circuit_id, revision)?;
+        self.conn
+            .transaction::<_, diesel::result::Error, _>(|| {
+                insert_into(circuit_revisions::table)
+                    .values(&model)
+                    .execute(self.conn)?;
+                delete_dropped_revisions(self.conn, circuit_id, model.revision)?;
+                Ok(())
+            })
+            .map_err(|err| {
+                CircuitStoreError::from_source("Failed to add circuit revision".into(), err)
+            })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> CircuitStoreAddCircuitRevisionOperation
+    for CircuitStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn add_circuit_revision(
+        &self,
+        circuit_id: &str,
+        revision: &CircuitRevision,
+    ) -> Result<(), CircuitStoreError> {
+        let model = CircuitRevisionModel::new(circuit_id, revision)?;
+        self.conn
+            .transaction::<_, diesel::result::Error, _>(|| {
+                insert_into(circuit_revisions::table)
+                    .values(&model)
+                    .execute(self.conn)?;
+                delete_dropped_revisions(self.conn, circuit_id, model.revision)?;
+                Ok(())
+            })
+            .map_err(|err| {
+                CircuitStoreError::from_source("Failed to add circuit revision".into(), err)
+            })
+    }
+}
+
+/// Deletes the revisions of the circuit that are no longer kept in its history, now that the given
+/// revision has been added.
+fn delete_dropped_revisions<C>(
+    conn: &C,
+    circuit_id: &str,
+    revision: i64,
+) -> Result<(), diesel::result::Error>
+where
+    C: diesel::Connection,
+    <C as diesel::Connection>::Backend: 'static,
+{
+    delete(
+        circuit_revisions::table
+            .filter(circuit_revisions::circuit_id.eq(circuit_id))
+            .filter(circuit_revisions::revision.le(revision - MAX_CIRCUIT_HISTORY_LEN as i64)),
+    )
+    .execute(conn)?;
+    Ok(())
+}
@@ -0,0 +1,60 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::super::models::NodeModel;
+use super::super::schema::splinter_nodes;
+use super::super::CircuitStoreError;
+use super::CircuitStoreOperations;
+use crate::circuit::service::SplinterNode;
+use diesel::{dsl::delete, dsl::insert_into, prelude::*};
+
+pub(in super::super) trait CircuitStoreAddNodeOperation {
+    fn add_node(&self, node_id: &str, node: &SplinterNode) -> Result<(), CircuitStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> CircuitStoreAddNodeOperation for CircuitStoreOperations<'a, diesel::pg::PgConnection> {
+    fn add_node(&self, node_id: &str, node: &SplinterNode) -> Result<(), CircuitStoreError> {
+        let model = NodeModel::new(node_id, node)?;
+        self.conn
+            .transaction::<_, diesel::result::Error, _>(|| {
+                delete(splinter_nodes::table.filter(splinter_nodes::node_id.eq(node_id)))
+                    .execute(self.conn)?;
+                insert_into(splinter_nodes::table)
+                    .values(&model)
+                    .execute(self.conn)?;
+                Ok(())
+            })
+            .map_err(|err| CircuitStoreError::from_source("Failed to add node".into(), err))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> CircuitStoreAddNodeOperation
+    for CircuitStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn add_node(&self, node_id: &str, node: &SplinterNode) -> Result<(), CircuitStoreError> {
+        let model = NodeModel::new(node_id, node)?;
+        self.conn
+            .transaction::<_, diesel::result::Error, _>(|| {
+                delete(splinter_nodes::table.filter(splinter_nodes::node_id.eq(node_id)))
+                    .execute(self.conn)?;
+                insert_into(splinter_nodes::table)
+                    .values(&model)
+                    .execute(self.conn)?;
+                Ok(())
+            })
+            .map_err(|err| CircuitStoreError::from_source("Failed to add node".into(), err))
+    }
+}
@@ -0,0 +1,75 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::super::CircuitStoreError;
+use super::add_circuit::CircuitStoreAddCircuitOperation;
+use super::add_circuit_revision::CircuitStoreAddCircuitRevisionOperation;
+use super::add_node::CircuitStoreAddNodeOperation;
+use super::CircuitStoreOperations;
+use crate::circuit::directory::CircuitDirectory;
+use diesel::prelude::*;
+
+pub(in super::super) trait CircuitStoreImportDirectoryOperation {
+    fn import_directory(&self, directory: &CircuitDirectory) -> Result<(), CircuitStoreError>;
+}
+
+impl<'a, C> CircuitStoreImportDirectoryOperation for CircuitStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    CircuitStoreOperations<'a, C>: CircuitStoreAddNodeOperation
+        + CircuitStoreAddCircuitOperation
+        + CircuitStoreAddCircuitRevisionOperation,
+{
+    fn import_directory(&self, directory: &CircuitDirectory) -> Result<(), CircuitStoreError> {
+        // the error that caused the transaction to be rolled back
+        let mut import_error = None;
+        self.conn
+            .transaction::<_, diesel::result::Error, _>(|| {
+                if let Err(err) = import_directory_rows(self, directory) {
+                    import_error = Some(err);
+                    return Err(diesel::result::Error::RollbackTransaction);
+                }
+                Ok(())
+            })
+            .map_err(|err| match import_error.take() {
+                Some(import_error) => import_error,
+                None => {
+                    CircuitStoreError::from_source("Failed to import circuit directory".into(), err)
+                }
+            })
+    }
+}
+
+fn import_directory_rows<O>(
+    operations: &O,
+    directory: &CircuitDirectory,
+) -> Result<(), CircuitStoreError>
+where
+    O: CircuitStoreAddNodeOperation
+        + CircuitStoreAddCircuitOperation
+        + CircuitStoreAddCircuitRevisionOperation,
+{
+    for (node_id, node) in directory.nodes() {
+        operations.add_node(node_id, node)?;
+    }
+    for (circuit_id, circuit) in directory.circuits() {
+        operations.add_circuit(circuit_id, circuit)?;
+    }
+    for (circuit_id, history) in directory.circuit_histories() {
+        for revision in history {
+            operations.add_circuit_revision(circuit_id, revision)?;
+        }
+    }
+    Ok(())
+}
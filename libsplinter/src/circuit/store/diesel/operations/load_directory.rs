@@ -0,0 +1,100 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use super::super::models::{
+    CircuitMemberModel, CircuitModel, CircuitModels, CircuitRevisionModel, CircuitServiceModel,
+    NodeModel,
+};
+use super::super::schema::{
+    circuit_members, circuit_revisions, circuit_services, circuits, splinter_nodes,
+};
+use super::super::CircuitStoreError;
+use super::CircuitStoreOperations;
+use crate::circuit::directory::CircuitDirectory;
+use diesel::prelude::*;
+
+pub(in super::super) trait CircuitStoreLoadDirectoryOperation {
+    fn load_directory(&self) -> Result<CircuitDirectory, CircuitStoreError>;
+}
+
+impl<'a, C> CircuitStoreLoadDirectoryOperation for CircuitStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    <C as diesel::Connection>::Backend: 'static,
+    i64: diesel::deserialize::FromSql<diesel::sql_types::BigInt, C::Backend>,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+{
+    fn load_directory(&self) -> Result<CircuitDirectory, CircuitStoreError> {
+        let query_err =
+            |err| CircuitStoreError::from_source("Failed to load circuit directory".into(), err);
+
+        let nodes = splinter_nodes::table
+            .load::<NodeModel>(self.conn)
+            .map_err(query_err)?;
+        let circuit_rows = circuits::table
+            .load::<CircuitModel>(self.conn)
+            .map_err(query_err)?;
+        let member_rows = circuit_members::table
+            .order((circuit_members::circuit_id, circuit_members::position))
+            .load::<CircuitMemberModel>(self.conn)
+            .map_err(query_err)?;
+        let service_rows = circuit_services::table
+            .order((circuit_services::circuit_id, circuit_services::position))
+            .load::<CircuitServiceModel>(self.conn)
+            .map_err(query_err)?;
+        let revision_rows = circuit_revisions::table
+            .order((circuit_revisions::circuit_id, circuit_revisions::revision))
+            .load::<CircuitRevisionModel>(self.conn)
+            .map_err(query_err)?;
+
+        let mut members: HashMap<String, Vec<CircuitMemberModel>> = HashMap::new();
+        for member in member_rows {
+            members
+                .entry(member.circuit_id.clone())
+                .or_default()
+                .push(member);
+        }
+        let mut services: HashMap<String, Vec<CircuitServiceModel>> = HashMap::new();
+        for service in service_rows {
+            services
+                .entry(service.circuit_id.clone())
+                .or_default()
+                .push(service);
+        }
+
+        let mut directory = CircuitDirectory::new();
+        for node in nodes {
+            directory.add_node(node.node_id.clone(), node.into_node()?);
+        }
+        for circuit in circuit_rows {
+            let circuit_id = circuit.circuit_id.clone();
+            let models = CircuitModels {
+                members: members.remove(&circuit_id).unwrap_or_default(),
+                services: services.remove(&circuit_id).unwrap_or_default(),
+                circuit,
+            };
+            directory.add_circuit(circuit_id, models.into_circuit()?);
+        }
+        for revision in revision_rows {
+            directory.add_linked_circuit_revision(
+                revision.circuit_id.clone(),
+                revision.into_revision()?,
+            );
+        }
+
+        Ok(directory)
+    }
+}
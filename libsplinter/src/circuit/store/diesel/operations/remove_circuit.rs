@@ -0,0 +1,36 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::super::CircuitStoreError;
+use super::add_circuit::delete_circuit_rows;
+use super::CircuitStoreOperations;
+use diesel::prelude::*;
+
+pub(in super::super) trait CircuitStoreRemoveCircuitOperation {
+    fn remove_circuit(&self, circuit_id: &str) -> Result<(), CircuitStoreError>;
+}
+
+impl<'a, C> CircuitStoreRemoveCircuitOperation for CircuitStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    <C as diesel::Connection>::Backend: 'static,
+{
+    fn remove_circuit(&self, circuit_id: &str) -> Result<(), CircuitStoreError> {
+        self.conn
+            .transaction::<_, diesel::result::Error, _>(|| {
+                delete_circuit_rows(self.conn, circuit_id)
+            })
+            .map_err(|err| CircuitStoreError::from_source("Failed to remove circuit".into(), err))
+    }
+}
@@ -0,0 +1,35 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::super::schema::splinter_nodes;
+use super::super::CircuitStoreError;
+use super::CircuitStoreOperations;
+use diesel::{dsl::delete, prelude::*};
+
+pub(in super::super) trait CircuitStoreRemoveNodeOperation {
+    fn remove_node(&self, node_id: &str) -> Result<(), CircuitStoreError>;
+}
+
+impl<'a, C> CircuitStoreRemoveNodeOperation for CircuitStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    <C as diesel::Connection>::Backend: 'static,
+{
+    fn remove_node(&self, node_id: &str) -> Result<(), CircuitStoreError> {
+        delete(splinter_nodes::table.filter(splinter_nodes::node_id.eq(node_id)))
+            .execute(self.conn)
+            .map(|_| ())
+            .map_err(|err| CircuitStoreError::from_source("Failed to remove node".into(), err))
+    }
+}
@@ -0,0 +1,53 @@
+-- Copyright 2018-2020 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+CREATE TABLE IF NOT EXISTS splinter_nodes (
+  node_id                   TEXT        PRIMARY KEY,
+  endpoints                 TEXT        NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS circuits (
+  circuit_id                TEXT        PRIMARY KEY,
+  auth                      TEXT        NOT NULL,
+  persistence               TEXT        NOT NULL,
+  durability                TEXT        NOT NULL,
+  routes                    TEXT        NOT NULL,
+  circuit_management_type   TEXT        NOT NULL,
+  application_metadata      TEXT        NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS circuit_members (
+  circuit_id                TEXT        NOT NULL,
+  node_id                   TEXT        NOT NULL,
+  position                  BIGINT      NOT NULL,
+  PRIMARY KEY (circuit_id, node_id)
+);
+
+CREATE TABLE IF NOT EXISTS circuit_services (
+  circuit_id                TEXT        NOT NULL,
+  service_id                TEXT        NOT NULL,
+  service_type              TEXT        NOT NULL,
+  allowed_nodes             TEXT        NOT NULL,
+  arguments                 TEXT        NOT NULL,
+  position                  BIGINT      NOT NULL,
+  PRIMARY KEY (circuit_id, service_id)
+);
+
+CREATE TABLE IF NOT EXISTS circuit_revisions (
+  circuit_id                TEXT        NOT NULL,
+  revision                  BIGINT      NOT NULL,
+  revision_data             TEXT        NOT NULL,
+  PRIMARY KEY (circuit_id, revision)
+);
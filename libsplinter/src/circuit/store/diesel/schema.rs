@@ -0,0 +1,60 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+table! {
+    splinter_nodes (node_id) {
+        node_id -> Text,
+        endpoints -> Text,
+    }
+}
+
+table! {
+    circuits (circuit_id) {
+        circuit_id -> Text,
+        auth -> Text,
+        persistence -> Text,
+        durability -> Text,
+        routes -> Text,
+        circuit_management_type -> Text,
+        application_metadata -> Text,
+        relays -> Text,
+    }
+}
+
+table! {
+    circuit_members (circuit_id, node_id) {
+        circuit_id -> Text,
+        node_id -> Text,
+        position -> BigInt,
+    }
+}
+
+table! {
+    circuit_services (circuit_id, service_id) {
+        circuit_id -> Text,
+        service_id -> Text,
+        service_type -> Text,
+        allowed_nodes -> Text,
+        arguments -> Text,
+        position -> BigInt,
+    }
+}
+
+table! {
+    circuit_revisions (circuit_id, revision) {
+        circuit_id -> Text,
+        revision -> BigInt,
+        revision_data -> Text,
+    }
+}
@@ -0,0 +1,33 @@
+-- Copyright 2018-2020 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+-- SQLite cannot drop columns, so the table is rebuilt without the column
+CREATE TABLE circuits_without_relays (
+  circuit_id                TEXT        PRIMARY KEY,
+  auth                      TEXT        NOT NULL,
+  persistence               TEXT        NOT NULL,
+  durability                TEXT        NOT NULL,
+  routes                    TEXT        NOT NULL,
+  circuit_management_type   TEXT        NOT NULL,
+  application_metadata      TEXT        NOT NULL
+);
+
+INSERT INTO circuits_without_relays
+  SELECT circuit_id, auth, persistence, durability, routes, circuit_management_type,
+    application_metadata
+  FROM circuits;
+
+DROP TABLE circuits;
+ALTER TABLE circuits_without_relays RENAME TO circuits;
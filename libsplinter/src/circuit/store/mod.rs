@@ -12,10 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(feature = "database")]
+pub(crate) mod diesel;
+
 use std::convert::TryInto;
 
+use crate::circuit::directory::CircuitDirectory;
+use crate::circuit::history::CircuitRevision;
+use crate::circuit::service::SplinterNode;
 use crate::circuit::Circuit;
 
+#[cfg(all(feature = "database", feature = "postgres"))]
+pub use self::diesel::postgres::run_migrations as run_postgres_migrations;
+#[cfg(feature = "sqlite")]
+pub use self::diesel::sqlite::run_migrations as run_sqlite_migrations;
+#[cfg(feature = "database")]
+pub use self::diesel::{create_circuit_directory_store, DieselCircuitDirectoryStore};
+
 /// A filter that matches on aspects of a circuit definition.
 ///
 /// Each variant applies to a different field on the circuit defition.
@@ -42,6 +55,46 @@ pub trait CircuitStore: Send + Sync + Clone {
     fn circuits(&self, filter: Option<CircuitFilter>) -> Result<CircuitIter, CircuitStoreError>;
 
     fn circuit(&self, circuit_name: &str) -> Result<Option<Circuit>, CircuitStoreError>;
+
+    /// Return the revisions of the given circuit, oldest first.
+    ///
+    /// Stores that do not keep circuit history return an empty history.
+    fn circuit_history(
+        &self,
+        _circuit_id: &str,
+    ) -> Result<Vec<CircuitRevision>, CircuitStoreError> {
+        Ok(Vec::new())
+    }
+}
+
+/// A backing store for the circuit directory that persists each change as it is made, rather
+/// than rewriting the whole directory.
+pub trait CircuitDirectoryStore: Send + Sync {
+    /// Loads the persisted circuit directory.
+    fn load_directory(&self) -> Result<CircuitDirectory, CircuitStoreError>;
+
+    /// Adds a node, replacing any existing node with the same ID.
+    fn add_node(&self, node_id: &str, node: &SplinterNode) -> Result<(), CircuitStoreError>;
+
+    fn remove_node(&self, node_id: &str) -> Result<(), CircuitStoreError>;
+
+    /// Adds a circuit, replacing any existing circuit with the same ID.
+    fn add_circuit(&self, circuit_id: &str, circuit: &Circuit) -> Result<(), CircuitStoreError>;
+
+    fn remove_circuit(&self, circuit_id: &str) -> Result<(), CircuitStoreError>;
+
+    /// Adds a revision to the history of a circuit. The revision must already be numbered and
+    /// linked to the previous revision.
+    fn add_circuit_revision(
+        &self,
+        circuit_id: &str,
+        revision: &CircuitRevision,
+    ) -> Result<(), CircuitStoreError>;
+
+    /// Adds the nodes, circuits and circuit history of the given directory in a single
+    /// transaction, so either all of the directory is added or none of it is. The revisions must
+    /// already be numbered and linked.
+    fn import_directory(&self, directory: &CircuitDirectory) -> Result<(), CircuitStoreError>;
 }
 
 /// An iterator over circuits, with a well-known count of values.
@@ -37,6 +37,28 @@ pub fn create_connection_pool(database_url: &str) -> Result<ConnectionPool, Data
     })
 }
 
+/// Returns whether the given storage location is a database URL, rather than a file or `memory`.
+pub fn is_database_url(location: &str) -> bool {
+    location.starts_with("postgres://") || location.starts_with("sqlite://")
+}
+
+/// Creates a pool of connections of the given type to the database at the given URL. A
+/// `sqlite://` URL names the path of the SQLite database file.
+pub fn create_pool<C>(database_url: &str) -> Result<Pool<ConnectionManager<C>>, DatabaseError>
+where
+    C: diesel::Connection + Send + 'static,
+{
+    let connection_manager = if database_url.starts_with("sqlite://") {
+        ConnectionManager::<C>::new(&database_url["sqlite://".len()..])
+    } else {
+        ConnectionManager::<C>::new(database_url)
+    };
+
+    Pool::builder()
+        .build(connection_manager)
+        .map_err(|err| DatabaseError::ConnectionError(Box::new(err)))
+}
+
 pub fn run_migrations(conn: &PgConnection) -> Result<(), DatabaseError> {
     embedded_migrations::run(conn).map_err(|err| DatabaseError::ConnectionError(Box::new(err)))?;
 
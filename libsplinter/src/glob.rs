@@ -0,0 +1,59 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Returns whether the text matches the pattern, in which `*` matches any sequence of characters.
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    if !text.starts_with(first) {
+        return false;
+    }
+
+    let mut remaining = &text[first.len()..];
+    let mut parts = parts.collect::<Vec<_>>();
+    let last = match parts.pop() {
+        Some(last) => last,
+        // the pattern has no wildcards
+        None => return remaining.is_empty(),
+    };
+
+    for part in parts {
+        match remaining.find(part) {
+            Some(index) => remaining = &remaining[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    remaining.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // test that patterns match with and without wildcards
+    fn test_glob_matches() {
+        assert!(glob_matches("acme-node-000", "acme-node-000"));
+        assert!(!glob_matches("acme-node-000", "acme-node-0001"));
+        assert!(glob_matches("acme-*", "acme-node-000"));
+        assert!(glob_matches("*-000", "acme-node-000"));
+        assert!(glob_matches("acme-*-000", "acme-node-000"));
+        assert!(glob_matches("*", "acme-node-000"));
+        assert!(!glob_matches("bubba-*", "acme-node-000"));
+        assert!(!glob_matches("acme-*-001", "acme-node-000"));
+        assert!(glob_matches("acme-node-*0", "acme-node-0"));
+        assert!(!glob_matches("acme-*-000", "acme-000"));
+    }
+}
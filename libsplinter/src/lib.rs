@@ -67,6 +67,7 @@ pub mod consensus;
 pub mod database;
 #[cfg(feature = "events")]
 pub mod events;
+mod glob;
 mod hex;
 pub mod keys;
 #[cfg(feature = "matrix")]
@@ -75,6 +76,8 @@ pub mod mesh;
 pub mod network;
 pub mod node_registry;
 pub mod orchestrator;
+#[cfg(feature = "peer-manager")]
+pub mod peer;
 pub mod protocol;
 pub mod protos;
 #[cfg(feature = "rest-api")]
@@ -16,15 +16,17 @@ use protobuf::Message;
 
 use crate::channel::Sender;
 use crate::network::auth::{
-    AuthorizationAction, AuthorizationInquisitor, AuthorizationManager, AuthorizationState,
+    AuthorizationAction, AuthorizationActionError, AuthorizationInquisitor, AuthorizationManager,
+    AuthorizationState, ChallengeAnswer,
 };
 use crate::network::dispatch::{
     DispatchError, DispatchMessage, Dispatcher, FromMessageBytes, Handler, MessageContext,
 };
 use crate::network::sender::SendRequest;
 use crate::protos::authorization::{
-    AuthorizationError, AuthorizationMessage, AuthorizationMessageType, AuthorizedMessage,
-    ConnectRequest, ConnectRequest_HandshakeMode, ConnectResponse,
+    AuthorizationError, AuthorizationError_AuthorizationErrorType, AuthorizationMessage,
+    AuthorizationMessageType, AuthorizedMessage, ChallengeNonceRequest, ChallengeNonceResponse,
+    ChallengeSubmitRequest, ConnectRequest, ConnectRequest_HandshakeMode, ConnectResponse,
     ConnectResponse_AuthorizationType, TrustRequest,
 };
 use crate::protos::network::{NetworkMessage, NetworkMessageType};
@@ -35,7 +37,7 @@ use crate::protos::network::{NetworkMessage, NetworkMessageType};
 /// The dispatcher is provided the given network sender for response messages, and the network
 /// itself to handle updating identities (or removing connections with authorization failures).
 ///
-/// The identity provided is sent to connections for Trust authorizations.
+/// The identity provided is sent to connections for Trust and Challenge authorizations.
 pub fn create_authorization_dispatcher(
     auth_manager: AuthorizationManager,
     network_sender: Box<dyn Sender<SendRequest>>,
@@ -57,6 +59,21 @@ pub fn create_authorization_dispatcher(
         Box::new(TrustRequestHandler::new(auth_manager.clone())),
     );
 
+    auth_dispatcher.set_handler(
+        AuthorizationMessageType::CHALLENGE_NONCE_REQUEST,
+        Box::new(ChallengeNonceRequestHandler::new(auth_manager.clone())),
+    );
+
+    auth_dispatcher.set_handler(
+        AuthorizationMessageType::CHALLENGE_NONCE_RESPONSE,
+        Box::new(ChallengeNonceResponseHandler::new(auth_manager.clone())),
+    );
+
+    auth_dispatcher.set_handler(
+        AuthorizationMessageType::CHALLENGE_SUBMIT_REQUEST,
+        Box::new(ChallengeSubmitRequestHandler::new(auth_manager.clone())),
+    );
+
     auth_dispatcher.set_handler(
         AuthorizationMessageType::AUTHORIZE,
         Box::new(
@@ -194,6 +211,8 @@ impl Handler<AuthorizationMessageType, ConnectRequest> for ConnectRequestHandler
                 // Send a connect request of our own
 
                 if msg.get_handshake_mode() == ConnectRequest_HandshakeMode::BIDIRECTIONAL {
+                    // Only the peer that opened the connection requests a bidirectional handshake
+                    self.auth_manager.set_inbound(context.source_peer_id());
                     let mut connect_req = ConnectRequest::new();
                     connect_req.set_handshake_mode(ConnectRequest_HandshakeMode::UNIDIRECTIONAL);
                     sender.send(SendRequest::new(
@@ -210,9 +229,9 @@ impl Handler<AuthorizationMessageType, ConnectRequest> for ConnectRequestHandler
                 }
 
                 let mut response = ConnectResponse::new();
-                response.set_accepted_authorization_types(vec![
-                    ConnectResponse_AuthorizationType::TRUST,
-                ]);
+                response.set_accepted_authorization_types(
+                    self.auth_manager.accepted_authorization_types(),
+                );
                 sender.send(SendRequest::new(
                     context.source_peer_id().to_string(),
                     wrap_in_network_auth_envelopes(
@@ -262,11 +281,20 @@ impl Handler<AuthorizationMessageType, ConnectResponse> for ConnectResponseHandl
             context.source_peer_id(),
             msg
         );
-        if msg
-            .get_accepted_authorization_types()
-            .iter()
-            .any(|t| t == &ConnectResponse_AuthorizationType::TRUST)
+        let accepted_types = msg.get_accepted_authorization_types();
+        if self.auth_manager.supports_challenge()
+            && accepted_types.contains(&ConnectResponse_AuthorizationType::CHALLENGE)
         {
+            self.auth_manager
+                .request_challenge_nonce(context.source_peer_id());
+            sender.send(SendRequest::new(
+                context.source_peer_id().to_string(),
+                wrap_in_network_auth_envelopes(
+                    AuthorizationMessageType::CHALLENGE_NONCE_REQUEST,
+                    ChallengeNonceRequest::new(),
+                )?,
+            ))?;
+        } else if accepted_types.contains(&ConnectResponse_AuthorizationType::TRUST) {
             let mut trust_request = TrustRequest::new();
             trust_request.set_identity(self.auth_manager.identity.clone());
             sender.send(SendRequest::new(
@@ -276,6 +304,12 @@ impl Handler<AuthorizationMessageType, ConnectResponse> for ConnectResponseHandl
                     trust_request,
                 )?,
             ))?;
+        } else {
+            warn!(
+                "Unable to authorize with peer {}: none of its accepted authorization types are \
+                 supported",
+                context.source_peer_id()
+            );
         }
         Ok(())
     }
@@ -303,6 +337,11 @@ impl Handler<AuthorizationMessageType, TrustRequest> for TrustRequestHandler {
             context.source_peer_id(),
             AuthorizationAction::TrustIdentifying(msg.get_identity().to_string()),
         ) {
+            Err(err @ AuthorizationActionError::TrustNotAccepted)
+            | Err(err @ AuthorizationActionError::CertificateMismatch(_))
+            | Err(err @ AuthorizationActionError::PeerDenied(_)) => {
+                reject_peer(&self.auth_manager, context.source_peer_id(), err, sender)?;
+            }
             Err(err) => {
                 debug!(
                     "Ignoring trust request message from peer {}: {}",
@@ -321,6 +360,143 @@ impl Handler<AuthorizationMessageType, TrustRequest> for TrustRequestHandler {
                     msg.get_identity().to_string(),
                     wrap_in_network_auth_envelopes(AuthorizationMessageType::AUTHORIZE, auth_msg)?,
                 ))?;
+                answer_deferred_challenge(&self.auth_manager, msg.get_identity(), sender)?;
+            }
+            Ok(next_state) => panic!("Should not have been able to transition to {}", next_state),
+        }
+        Ok(())
+    }
+}
+
+/// Handler for the ChallengeNonceRequest Authorization Message Type
+struct ChallengeNonceRequestHandler {
+    auth_manager: AuthorizationManager,
+}
+
+impl ChallengeNonceRequestHandler {
+    fn new(auth_manager: AuthorizationManager) -> Self {
+        ChallengeNonceRequestHandler { auth_manager }
+    }
+}
+
+impl Handler<AuthorizationMessageType, ChallengeNonceRequest> for ChallengeNonceRequestHandler {
+    fn handle(
+        &self,
+        _: ChallengeNonceRequest,
+        context: &MessageContext<AuthorizationMessageType>,
+        sender: &dyn Sender<SendRequest>,
+    ) -> Result<(), DispatchError> {
+        match self
+            .auth_manager
+            .create_challenge_nonce(context.source_peer_id())
+        {
+            Ok(nonce) => {
+                let mut nonce_response = ChallengeNonceResponse::new();
+                nonce_response.set_nonce(nonce);
+                nonce_response.set_identity(self.auth_manager.identity.clone());
+                sender.send(SendRequest::new(
+                    context.source_peer_id().to_string(),
+                    wrap_in_network_auth_envelopes(
+                        AuthorizationMessageType::CHALLENGE_NONCE_RESPONSE,
+                        nonce_response,
+                    )?,
+                ))?;
+            }
+            Err(err) => reject_peer(&self.auth_manager, context.source_peer_id(), err, sender)?,
+        }
+        Ok(())
+    }
+}
+
+/// Handler for the ChallengeNonceResponse Authorization Message Type
+struct ChallengeNonceResponseHandler {
+    auth_manager: AuthorizationManager,
+}
+
+impl ChallengeNonceResponseHandler {
+    fn new(auth_manager: AuthorizationManager) -> Self {
+        ChallengeNonceResponseHandler { auth_manager }
+    }
+}
+
+impl Handler<AuthorizationMessageType, ChallengeNonceResponse> for ChallengeNonceResponseHandler {
+    fn handle(
+        &self,
+        msg: ChallengeNonceResponse,
+        context: &MessageContext<AuthorizationMessageType>,
+        sender: &dyn Sender<SendRequest>,
+    ) -> Result<(), DispatchError> {
+        match self.auth_manager.answer_challenge(
+            context.source_peer_id(),
+            msg.get_identity(),
+            msg.get_nonce(),
+        ) {
+            Ok(answer) => {
+                send_challenge_answer(&self.auth_manager, context.source_peer_id(), answer, sender)?
+            }
+            Err(err) => {
+                error!(
+                    "Unable to answer challenge from peer {}: {}",
+                    context.source_peer_id(),
+                    err
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Handler for the ChallengeSubmitRequest Authorization Message Type
+struct ChallengeSubmitRequestHandler {
+    auth_manager: AuthorizationManager,
+}
+
+impl ChallengeSubmitRequestHandler {
+    fn new(auth_manager: AuthorizationManager) -> Self {
+        ChallengeSubmitRequestHandler { auth_manager }
+    }
+}
+
+impl Handler<AuthorizationMessageType, ChallengeSubmitRequest> for ChallengeSubmitRequestHandler {
+    fn handle(
+        &self,
+        mut msg: ChallengeSubmitRequest,
+        context: &MessageContext<AuthorizationMessageType>,
+        sender: &dyn Sender<SendRequest>,
+    ) -> Result<(), DispatchError> {
+        let identity = msg.take_identity();
+        match self.auth_manager.next_state(
+            context.source_peer_id(),
+            AuthorizationAction::ChallengeIdentifying {
+                identity: identity.clone(),
+                public_key: msg.take_public_key(),
+                signature: msg.take_signature(),
+            },
+        ) {
+            Err(err @ AuthorizationActionError::ChallengeFailed(_))
+            | Err(err @ AuthorizationActionError::CertificateMismatch(_))
+            | Err(err @ AuthorizationActionError::PeerDenied(_)) => {
+                reject_peer(&self.auth_manager, context.source_peer_id(), err, sender)?;
+            }
+            Err(err) => {
+                debug!(
+                    "Ignoring challenge submit request from peer {}: {}",
+                    context.source_peer_id(),
+                    err
+                );
+            }
+            Ok(AuthorizationState::Authorized) => {
+                debug!(
+                    "Sending Authorized message to peer {} (formerly {})",
+                    identity,
+                    context.source_peer_id()
+                );
+                let auth_msg = AuthorizedMessage::new();
+                sender.send(SendRequest::new(
+                    identity.clone(),
+                    wrap_in_network_auth_envelopes(AuthorizationMessageType::AUTHORIZE, auth_msg)?,
+                ))?;
+                answer_deferred_challenge(&self.auth_manager, &identity, sender)?;
             }
             Ok(next_state) => panic!("Should not have been able to transition to {}", next_state),
         }
@@ -370,6 +546,86 @@ impl Handler<AuthorizationMessageType, AuthorizationError> for AuthorizationErro
     }
 }
 
+/// Sends the answer to a peer's challenge, if it was signed.
+fn send_challenge_answer(
+    auth_manager: &AuthorizationManager,
+    peer_id: &str,
+    answer: ChallengeAnswer,
+    sender: &dyn Sender<SendRequest>,
+) -> Result<(), DispatchError> {
+    match answer {
+        ChallengeAnswer::Signed {
+            public_key,
+            signature,
+        } => {
+            let mut submit_request = ChallengeSubmitRequest::new();
+            submit_request.set_identity(auth_manager.identity.clone());
+            submit_request.set_public_key(public_key);
+            submit_request.set_signature(signature);
+            sender.send(SendRequest::new(
+                peer_id.to_string(),
+                wrap_in_network_auth_envelopes(
+                    AuthorizationMessageType::CHALLENGE_SUBMIT_REQUEST,
+                    submit_request,
+                )?,
+            ))?;
+        }
+        ChallengeAnswer::Deferred => {
+            debug!(
+                "Deferring challenge from peer {} until it has authorized",
+                peer_id
+            );
+        }
+        ChallengeAnswer::Disabled => {
+            debug!(
+                "Ignoring challenge nonce from peer {}: challenge authorization is not enabled",
+                peer_id
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Answers the challenge deferred for a peer that has just authorized under the given identity.
+fn answer_deferred_challenge(
+    auth_manager: &AuthorizationManager,
+    identity: &str,
+    sender: &dyn Sender<SendRequest>,
+) -> Result<(), DispatchError> {
+    match auth_manager.take_deferred_challenge(identity) {
+        Ok(Some(answer)) => send_challenge_answer(auth_manager, identity, answer, sender),
+        Ok(None) => Ok(()),
+        Err(err) => reject_peer(auth_manager, identity, err, sender),
+    }
+}
+
+/// Sends an authorization error to a peer that failed authorization, and removes its connection.
+fn reject_peer(
+    auth_manager: &AuthorizationManager,
+    peer_id: &str,
+    err: AuthorizationActionError,
+    sender: &dyn Sender<SendRequest>,
+) -> Result<(), DispatchError> {
+    warn!("Rejecting authorization of peer {}: {}", peer_id, err);
+
+    let mut error_message = AuthorizationError::new();
+    error_message.set_error_type(AuthorizationError_AuthorizationErrorType::AUTHORIZATION_REJECTED);
+    error_message.set_error_message(err.to_string());
+    sender.send(SendRequest::new(
+        peer_id.to_string(),
+        wrap_in_network_auth_envelopes(
+            AuthorizationMessageType::AUTHORIZATION_ERROR,
+            error_message,
+        )?,
+    ))?;
+
+    if let Err(err) = auth_manager.next_state(peer_id, AuthorizationAction::Unauthorizing) {
+        warn!("Unable to unauthorize peer {}: {}", peer_id, err);
+    }
+
+    Ok(())
+}
+
 fn wrap_in_network_auth_envelopes<M: protobuf::Message>(
     msg_type: AuthorizationMessageType,
     auth_msg: M,
@@ -392,14 +648,18 @@ mod tests {
     use protobuf::Message;
 
     use crate::channel::mock::MockSender;
+    use crate::keys::{storage::StorageKeyRegistry, KeyInfo, KeyRegistry};
     use crate::mesh::Mesh;
+    use crate::network::auth::{challenge_payload, ChallengeAuthorization};
     use crate::network::Network;
     use crate::protos::authorization::{
         AuthorizationError, AuthorizationError_AuthorizationErrorType, AuthorizationMessage,
-        AuthorizedMessage, ConnectRequest, ConnectResponse, ConnectResponse_AuthorizationType,
-        TrustRequest,
+        AuthorizedMessage, ChallengeNonceRequest, ChallengeNonceResponse, ChallengeSubmitRequest,
+        ConnectRequest, ConnectResponse, ConnectResponse_AuthorizationType, TrustRequest,
     };
     use crate::protos::network::{NetworkMessage, NetworkMessageType};
+    use crate::signing::hash::{HashSigner, HashVerifier};
+    use crate::signing::Signer;
     use crate::transport::{
         ConnectError, Connection, DisconnectError, RecvError, SendError, Transport,
     };
@@ -596,6 +856,416 @@ mod tests {
         assert_eq!(0, network.peer_ids().len());
     }
 
+    // Test that a peer is authorized by challenge:
+    // 1. Dispatch a connect request and verify that only challenge authorization is accepted
+    // 2. Dispatch a nonce request and verify that a nonce is returned
+    // 3. Dispatch the signed nonce and verify that the peer is authorized under its identity
+    #[test]
+    fn challenge_dispatch() {
+        let (network, peer_id) = create_network_with_initial_temp_peer();
+
+        let auth_mgr = AuthorizationManager::new(network.clone(), "mock_identity".into())
+            .with_challenge_authorization(create_challenge_authorization("my_identity", true));
+        let network_sender = MockSender::default();
+        let dispatcher =
+            create_authorization_dispatcher(auth_mgr, Box::new(network_sender.clone()));
+
+        let mut msg = ConnectRequest::new();
+        msg.set_handshake_mode(ConnectRequest_HandshakeMode::UNIDIRECTIONAL);
+        let msg_bytes = msg.write_to_bytes().expect("Unable to serialize message");
+        assert_eq!(
+            Ok(()),
+            dispatcher.dispatch(
+                &peer_id,
+                &AuthorizationMessageType::CONNECT_REQUEST,
+                msg_bytes
+            )
+        );
+        let send_request = network_sender
+            .clear()
+            .pop()
+            .expect("A message should have been sent");
+        let connect_res_msg: ConnectResponse = expect_auth_message(
+            AuthorizationMessageType::CONNECT_RESPONSE,
+            send_request.payload(),
+        );
+        assert_eq!(
+            vec![ConnectResponse_AuthorizationType::CHALLENGE],
+            connect_res_msg.get_accepted_authorization_types().to_vec()
+        );
+
+        let msg_bytes = ChallengeNonceRequest::new()
+            .write_to_bytes()
+            .expect("Unable to serialize message");
+        assert_eq!(
+            Ok(()),
+            dispatcher.dispatch(
+                &peer_id,
+                &AuthorizationMessageType::CHALLENGE_NONCE_REQUEST,
+                msg_bytes
+            )
+        );
+        let send_request = network_sender
+            .clear()
+            .pop()
+            .expect("A message should have been sent");
+        let nonce_res_msg: ChallengeNonceResponse = expect_auth_message(
+            AuthorizationMessageType::CHALLENGE_NONCE_RESPONSE,
+            send_request.payload(),
+        );
+        assert!(!nonce_res_msg.get_nonce().is_empty());
+
+        let mut submit_req = ChallengeSubmitRequest::new();
+        submit_req.set_identity("my_identity".into());
+        submit_req.set_public_key(HashSigner.public_key().to_vec());
+        assert_eq!("mock_identity", nonce_res_msg.get_identity());
+        submit_req.set_signature(
+            HashSigner
+                .sign(&challenge_payload(
+                    "my_identity",
+                    "mock_identity",
+                    nonce_res_msg.get_nonce(),
+                ))
+                .expect("Unable to sign nonce"),
+        );
+        let msg_bytes = submit_req
+            .write_to_bytes()
+            .expect("Unable to serialize message");
+        assert_eq!(
+            Ok(()),
+            dispatcher.dispatch(
+                &peer_id,
+                &AuthorizationMessageType::CHALLENGE_SUBMIT_REQUEST,
+                msg_bytes
+            )
+        );
+        let send_request = network_sender
+            .clear()
+            .pop()
+            .expect("A message should have been sent");
+        assert_eq!("my_identity", send_request.recipient());
+        let _auth_msg: AuthorizedMessage =
+            expect_auth_message(AuthorizationMessageType::AUTHORIZE, send_request.payload());
+        assert_eq!(vec!["my_identity".to_string()], network.peer_ids());
+    }
+
+    // Test that the node answers a challenge when challenge authorization is accepted by the peer:
+    // a nonce is requested in response to the connect response, and the nonce is signed with the
+    // node's key
+    #[test]
+    fn challenge_response_dispatch() {
+        let (network, peer_id) = create_network_with_initial_temp_peer();
+
+        let auth_mgr = AuthorizationManager::new(network, "mock_identity".into())
+            .with_challenge_authorization(create_challenge_authorization("my_identity", false));
+        let network_sender = MockSender::default();
+        let dispatcher =
+            create_authorization_dispatcher(auth_mgr, Box::new(network_sender.clone()));
+
+        let mut msg = ConnectResponse::new();
+        msg.set_accepted_authorization_types(
+            vec![
+                ConnectResponse_AuthorizationType::CHALLENGE,
+                ConnectResponse_AuthorizationType::TRUST,
+            ]
+            .into(),
+        );
+        let msg_bytes = msg.write_to_bytes().expect("Unable to serialize message");
+        assert_eq!(
+            Ok(()),
+            dispatcher.dispatch(
+                &peer_id,
+                &AuthorizationMessageType::CONNECT_RESPONSE,
+                msg_bytes
+            )
+        );
+        let send_request = network_sender
+            .clear()
+            .pop()
+            .expect("A message should have been sent");
+        let _nonce_req: ChallengeNonceRequest = expect_auth_message(
+            AuthorizationMessageType::CHALLENGE_NONCE_REQUEST,
+            send_request.payload(),
+        );
+
+        dispatch_nonce_response(&dispatcher, &peer_id, "other_identity", b"nonce");
+        let send_request = network_sender
+            .clear()
+            .pop()
+            .expect("A message should have been sent");
+        let submit_req: ChallengeSubmitRequest = expect_auth_message(
+            AuthorizationMessageType::CHALLENGE_SUBMIT_REQUEST,
+            send_request.payload(),
+        );
+        assert_eq!("mock_identity", submit_req.get_identity());
+        assert_eq!(HashSigner.public_key(), submit_req.get_public_key());
+        assert_eq!(
+            HashSigner
+                .sign(&challenge_payload(
+                    "mock_identity",
+                    "other_identity",
+                    b"nonce"
+                ))
+                .expect("Unable to sign nonce"),
+            submit_req.get_signature()
+        );
+
+        // A second nonce is not signed, since only one was requested
+        dispatch_nonce_response(&dispatcher, &peer_id, "other_identity", b"nonce");
+        assert_eq!(0, network_sender.sent().len());
+    }
+
+    // Test that the node does not sign a nonce it did not request, so that it cannot be used to
+    // sign nonces sent to the peer by other nodes.
+    #[test]
+    fn challenge_unrequested_nonce_dispatch() {
+        let (network, peer_id) = create_network_with_initial_temp_peer();
+
+        let auth_mgr = AuthorizationManager::new(network, "mock_identity".into())
+            .with_challenge_authorization(create_challenge_authorization("my_identity", false));
+        let network_sender = MockSender::default();
+        let dispatcher =
+            create_authorization_dispatcher(auth_mgr, Box::new(network_sender.clone()));
+
+        dispatch_nonce_response(&dispatcher, &peer_id, "other_identity", b"nonce");
+        assert_eq!(0, network_sender.sent().len());
+    }
+
+    // Test that the node answers the challenge of a peer that opened its connection only once the
+    // peer has authorized under the identity it claimed with its challenge:
+    // 1. Dispatch a bidirectional connect request, then a connect response accepting challenges
+    // 2. Dispatch a nonce response, and verify that it is not answered yet
+    // 3. Authorize the peer by trust, and verify that the nonce is then signed for its identity
+    #[test]
+    fn challenge_inbound_deferred_dispatch() {
+        let (network, peer_id) = create_network_with_initial_temp_peer();
+
+        let auth_mgr = AuthorizationManager::new(network, "mock_identity".into())
+            .with_challenge_authorization(create_challenge_authorization("other_identity", false));
+        let network_sender = MockSender::default();
+        let dispatcher =
+            create_authorization_dispatcher(auth_mgr, Box::new(network_sender.clone()));
+
+        let mut msg = ConnectRequest::new();
+        msg.set_handshake_mode(ConnectRequest_HandshakeMode::BIDIRECTIONAL);
+        let msg_bytes = msg.write_to_bytes().expect("Unable to serialize message");
+        assert_eq!(
+            Ok(()),
+            dispatcher.dispatch(
+                &peer_id,
+                &AuthorizationMessageType::CONNECT_REQUEST,
+                msg_bytes
+            )
+        );
+
+        let mut msg = ConnectResponse::new();
+        msg.set_accepted_authorization_types(
+            vec![ConnectResponse_AuthorizationType::CHALLENGE].into(),
+        );
+        let msg_bytes = msg.write_to_bytes().expect("Unable to serialize message");
+        assert_eq!(
+            Ok(()),
+            dispatcher.dispatch(
+                &peer_id,
+                &AuthorizationMessageType::CONNECT_RESPONSE,
+                msg_bytes
+            )
+        );
+        network_sender.clear();
+
+        dispatch_nonce_response(&dispatcher, &peer_id, "other_identity", b"nonce");
+        assert_eq!(0, network_sender.sent().len());
+
+        let mut trust_req = TrustRequest::new();
+        trust_req.set_identity("other_identity".into());
+        let msg_bytes = trust_req
+            .write_to_bytes()
+            .expect("Unable to serialize message");
+        assert_eq!(
+            Ok(()),
+            dispatcher.dispatch(
+                &peer_id,
+                &AuthorizationMessageType::TRUST_REQUEST,
+                msg_bytes
+            )
+        );
+
+        let mut sent = network_sender.clear();
+        assert_eq!(2, sent.len());
+        let send_request = sent.pop().expect("A message should have been sent");
+        assert_eq!("other_identity", send_request.recipient());
+        let submit_req: ChallengeSubmitRequest = expect_auth_message(
+            AuthorizationMessageType::CHALLENGE_SUBMIT_REQUEST,
+            send_request.payload(),
+        );
+        assert_eq!(
+            HashSigner
+                .sign(&challenge_payload(
+                    "mock_identity",
+                    "other_identity",
+                    b"nonce"
+                ))
+                .expect("Unable to sign nonce"),
+            submit_req.get_signature()
+        );
+        let send_request = sent.pop().expect("A message should have been sent");
+        let _auth_msg: AuthorizedMessage =
+            expect_auth_message(AuthorizationMessageType::AUTHORIZE, send_request.payload());
+    }
+
+    fn dispatch_nonce_response(
+        dispatcher: &Dispatcher<AuthorizationMessageType>,
+        peer_id: &str,
+        identity: &str,
+        nonce: &[u8],
+    ) {
+        let mut msg = ChallengeNonceResponse::new();
+        msg.set_nonce(nonce.to_vec());
+        msg.set_identity(identity.into());
+        let msg_bytes = msg.write_to_bytes().expect("Unable to serialize message");
+        assert_eq!(
+            Ok(()),
+            dispatcher.dispatch(
+                peer_id,
+                &AuthorizationMessageType::CHALLENGE_NONCE_RESPONSE,
+                msg_bytes
+            )
+        );
+    }
+
+    // Test that a peer is rejected, and its connection removed, if it claims an identity its key
+    // is not registered to, or if it requests trust authorization while challenge authorization is
+    // required
+    #[test]
+    fn challenge_rejection_dispatch() {
+        let (network, peer_id) = create_network_with_initial_temp_peer();
+
+        let auth_mgr = AuthorizationManager::new(network.clone(), "mock_identity".into())
+            .with_challenge_authorization(create_challenge_authorization("my_identity", true));
+        let network_sender = MockSender::default();
+        let dispatcher =
+            create_authorization_dispatcher(auth_mgr, Box::new(network_sender.clone()));
+
+        connect_unidirectional(&dispatcher, &peer_id);
+        let msg_bytes = ChallengeNonceRequest::new()
+            .write_to_bytes()
+            .expect("Unable to serialize message");
+        assert_eq!(
+            Ok(()),
+            dispatcher.dispatch(
+                &peer_id,
+                &AuthorizationMessageType::CHALLENGE_NONCE_REQUEST,
+                msg_bytes
+            )
+        );
+        let send_request = network_sender
+            .clear()
+            .pop()
+            .expect("A message should have been sent");
+        let nonce_res_msg: ChallengeNonceResponse = expect_auth_message(
+            AuthorizationMessageType::CHALLENGE_NONCE_RESPONSE,
+            send_request.payload(),
+        );
+
+        let mut submit_req = ChallengeSubmitRequest::new();
+        submit_req.set_identity("other_identity".into());
+        submit_req.set_public_key(HashSigner.public_key().to_vec());
+        submit_req.set_signature(
+            HashSigner
+                .sign(&challenge_payload(
+                    "other_identity",
+                    "mock_identity",
+                    nonce_res_msg.get_nonce(),
+                ))
+                .expect("Unable to sign nonce"),
+        );
+        let msg_bytes = submit_req
+            .write_to_bytes()
+            .expect("Unable to serialize message");
+        assert_eq!(
+            Ok(()),
+            dispatcher.dispatch(
+                &peer_id,
+                &AuthorizationMessageType::CHALLENGE_SUBMIT_REQUEST,
+                msg_bytes
+            )
+        );
+        let send_request = network_sender
+            .clear()
+            .pop()
+            .expect("A message should have been sent");
+        let _error_msg: AuthorizationError = expect_auth_message(
+            AuthorizationMessageType::AUTHORIZATION_ERROR,
+            send_request.payload(),
+        );
+        assert_eq!(0, network.peer_ids().len());
+
+        let (network, peer_id) = create_network_with_initial_temp_peer();
+
+        let auth_mgr = AuthorizationManager::new(network.clone(), "mock_identity".into())
+            .with_challenge_authorization(create_challenge_authorization("my_identity", true));
+        let network_sender = MockSender::default();
+        let dispatcher =
+            create_authorization_dispatcher(auth_mgr, Box::new(network_sender.clone()));
+
+        connect_unidirectional(&dispatcher, &peer_id);
+        network_sender.clear();
+
+        let mut trust_req = TrustRequest::new();
+        trust_req.set_identity("my_identity".into());
+        let msg_bytes = trust_req
+            .write_to_bytes()
+            .expect("Unable to serialize message");
+        assert_eq!(
+            Ok(()),
+            dispatcher.dispatch(
+                &peer_id,
+                &AuthorizationMessageType::TRUST_REQUEST,
+                msg_bytes
+            )
+        );
+        let send_request = network_sender
+            .clear()
+            .pop()
+            .expect("A message should have been sent");
+        let _error_msg: AuthorizationError = expect_auth_message(
+            AuthorizationMessageType::AUTHORIZATION_ERROR,
+            send_request.payload(),
+        );
+        assert_eq!(0, network.peer_ids().len());
+    }
+
+    fn connect_unidirectional(dispatcher: &Dispatcher<AuthorizationMessageType>, peer_id: &str) {
+        let mut msg = ConnectRequest::new();
+        msg.set_handshake_mode(ConnectRequest_HandshakeMode::UNIDIRECTIONAL);
+        let msg_bytes = msg.write_to_bytes().expect("Unable to serialize message");
+        assert_eq!(
+            Ok(()),
+            dispatcher.dispatch(
+                peer_id,
+                &AuthorizationMessageType::CONNECT_REQUEST,
+                msg_bytes
+            )
+        );
+    }
+
+    /// Creates a ChallengeAuthorization using hash signatures, where the hash signer's key is
+    /// registered to the given node.
+    fn create_challenge_authorization(node_id: &str, required: bool) -> ChallengeAuthorization {
+        let mut key_registry =
+            StorageKeyRegistry::new("memory".to_string()).expect("Unable to create key registry");
+        key_registry
+            .save_key(KeyInfo::builder(HashSigner.public_key().to_vec(), node_id.into()).build())
+            .expect("Unable to save key");
+
+        ChallengeAuthorization::new(
+            Box::new(HashSigner),
+            Box::new(HashVerifier),
+            Box::new(key_registry),
+            required,
+        )
+    }
+
     fn expect_auth_message<M: protobuf::Message>(
         message_type: AuthorizationMessageType,
         msg_bytes: &[u8],
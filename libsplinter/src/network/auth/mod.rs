@@ -13,15 +13,60 @@
 // limitations under the License.
 
 pub mod handlers;
+pub mod policy;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::sync::{
     mpsc::{channel, Receiver},
     Arc, Mutex,
 };
 
+use openssl::rand::rand_bytes;
+
+use crate::keys::KeyRegistry;
 use crate::network::Network;
+use crate::node_registry::{NodeRegistryError, NodeRegistryReader};
+use crate::protos::authorization::ConnectResponse_AuthorizationType;
+use crate::signing::{SignatureVerifier, Signer};
+
+use self::policy::PeerPolicy;
+
+/// The length, in bytes, of the nonces sent to peers authorizing by challenge.
+const CHALLENGE_NONCE_LENGTH: usize = 32;
+
+/// The tag that begins each signed challenge. With the order of the identities that follow it, it
+/// binds the signature to its direction: from the node proving its identity to the node that sent
+/// the nonce.
+const CHALLENGE_SIGNATURE_TAG: &[u8] = b"splinter-challenge-response-v1";
+
+/// The prefix of the temporary peer ids that connections have until they complete authorization.
+const TEMPORARY_PEER_ID_PREFIX: &str = "temp-";
+
+/// Returns the bytes signed by a node proving its identity by challenge: the signature tag, the
+/// identity of the signing node, the identity of the node that sent the nonce, and the nonce, each
+/// prefixed by its length.
+///
+/// Binding the signature to both identities prevents a node from relaying a nonce it was sent by a
+/// third node to this node, to obtain a signature it could use to authorize as this node.
+fn challenge_payload(signer_identity: &str, challenger_identity: &str, nonce: &[u8]) -> Vec<u8> {
+    let mut payload = Vec::new();
+    for field in &[
+        CHALLENGE_SIGNATURE_TAG,
+        signer_identity.as_bytes(),
+        challenger_identity.as_bytes(),
+        nonce,
+    ] {
+        payload.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        payload.extend_from_slice(field);
+    }
+    payload
+}
+
+/// The node registry metadata key listing, comma-separated, the names a node's certificate may
+/// have when certificates are bound to node identities. If a node has no such entry, its
+/// certificate must be named after its identity.
+pub const CERTIFICATE_NAMES_METADATA_KEY: &str = "certificate_names";
 
 /// The states of a connection during authorization.
 #[derive(PartialEq, Debug, Clone)]
@@ -52,6 +97,11 @@ type Identity = String;
 enum AuthorizationAction {
     Connecting,
     TrustIdentifying(Identity),
+    ChallengeIdentifying {
+        identity: Identity,
+        public_key: Vec<u8>,
+        signature: Vec<u8>,
+    },
     Unauthorizing,
 }
 
@@ -60,6 +110,7 @@ impl fmt::Display for AuthorizationAction {
         f.write_str(match self {
             AuthorizationAction::Connecting => "Connecting",
             AuthorizationAction::TrustIdentifying(_) => "TrustIdentifying",
+            AuthorizationAction::ChallengeIdentifying { .. } => "ChallengeIdentifying",
             AuthorizationAction::Unauthorizing => "Unauthorizing",
         })
     }
@@ -71,6 +122,10 @@ enum AuthorizationActionError {
     AlreadyConnecting,
     InvalidMessageOrder(AuthorizationState, AuthorizationAction),
     ConnectionLost,
+    TrustNotAccepted,
+    ChallengeFailed(String),
+    CertificateMismatch(String),
+    PeerDenied(String),
 }
 
 impl fmt::Display for AuthorizationActionError {
@@ -85,6 +140,18 @@ impl fmt::Display for AuthorizationActionError {
             AuthorizationActionError::ConnectionLost => {
                 f.write_str("Connection lost while authorizing peer")
             }
+            AuthorizationActionError::TrustNotAccepted => {
+                f.write_str("Trust authorization is not accepted")
+            }
+            AuthorizationActionError::ChallengeFailed(msg) => {
+                write!(f, "Challenge authorization failed: {}", msg)
+            }
+            AuthorizationActionError::CertificateMismatch(msg) => {
+                write!(f, "Identity does not match certificate: {}", msg)
+            }
+            AuthorizationActionError::PeerDenied(msg) => {
+                write!(f, "Peer denied by policy: {}", msg)
+            }
         }
     }
 }
@@ -111,12 +178,89 @@ pub trait AuthorizationInquisitor: Send {
     fn is_authorized(&self, peer_id: &str) -> bool;
 }
 
+/// The keys used to authorize peers by challenge.
+///
+/// A peer authorizing by challenge proves that it holds the private key registered to its identity
+/// by signing a nonce sent to it. This node proves its own identity to peers in the same way, using
+/// the given signer; its public key must be registered to this node in the peers' key registries.
+pub struct ChallengeAuthorization {
+    signer: Box<dyn Signer + Send>,
+    verifier: Box<dyn SignatureVerifier>,
+    key_registry: Box<dyn KeyRegistry>,
+    required: bool,
+}
+
+impl ChallengeAuthorization {
+    /// Constructs a ChallengeAuthorization.
+    ///
+    /// Peers' signatures are checked with the given verifier, and their public keys must be
+    /// registered to their identities in the given key registry. If `required` is true, peers may
+    /// no longer be authorized by trust.
+    pub fn new(
+        signer: Box<dyn Signer + Send>,
+        verifier: Box<dyn SignatureVerifier>,
+        key_registry: Box<dyn KeyRegistry>,
+        required: bool,
+    ) -> Self {
+        ChallengeAuthorization {
+            signer,
+            verifier,
+            key_registry,
+            required,
+        }
+    }
+
+    /// Verifies that the signature is a valid signature, by the public key, of the challenge
+    /// payload for the given identity, this node's identity and the nonce, and that the public key
+    /// is registered to the given identity.
+    fn verify(
+        &self,
+        identity: &str,
+        challenger_identity: &str,
+        nonce: &[u8],
+        public_key: &[u8],
+        signature: &[u8],
+    ) -> Result<(), AuthorizationActionError> {
+        let valid = self
+            .verifier
+            .verify(
+                &challenge_payload(identity, challenger_identity, nonce),
+                signature,
+                public_key,
+            )
+            .map_err(|err| AuthorizationActionError::ChallengeFailed(err.to_string()))?;
+        if !valid {
+            return Err(AuthorizationActionError::ChallengeFailed(
+                "invalid signature".into(),
+            ));
+        }
+
+        match self
+            .key_registry
+            .get_key(public_key)
+            .map_err(|err| AuthorizationActionError::ChallengeFailed(err.to_string()))?
+        {
+            Some(key_info) if key_info.associated_node_id() == identity => Ok(()),
+            Some(_) => Err(AuthorizationActionError::ChallengeFailed(format!(
+                "public key is not registered to {}",
+                identity
+            ))),
+            None => Err(AuthorizationActionError::ChallengeFailed(
+                "public key is not registered".into(),
+            )),
+        }
+    }
+}
+
 /// Manages authorization states for connections on a network.
 #[derive(Clone)]
 pub struct AuthorizationManager {
     shared: Arc<Mutex<ManagedAuthorizations>>,
     network: Network,
     identity: Identity,
+    challenge: Option<Arc<Mutex<ChallengeAuthorization>>>,
+    certificate_registry: Option<Arc<dyn NodeRegistryReader>>,
+    peer_policy: Option<PeerPolicy>,
 }
 
 impl AuthorizationManager {
@@ -136,6 +280,328 @@ impl AuthorizationManager {
             shared,
             network,
             identity,
+            challenge: None,
+            certificate_registry: None,
+            peer_policy: None,
+        }
+    }
+
+    /// Binds the identities of peers to the certificates of their connections.
+    ///
+    /// A peer is only authorized if its connection was verified with a certificate that has one of
+    /// the names listed for its identity in the given node registry, under the
+    /// `CERTIFICATE_NAMES_METADATA_KEY` metadata key. Peers without a verified certificate, or
+    /// that are not in the registry, are rejected.
+    pub fn with_certificate_binding(mut self, node_registry: Box<dyn NodeRegistryReader>) -> Self {
+        self.certificate_registry = Some(Arc::from(node_registry));
+        self
+    }
+
+    /// Verifies that the certificate of the given peer's connection matches the identity it
+    /// claims, if certificates are bound to identities.
+    fn verify_certificate(
+        &self,
+        peer_id: &str,
+        identity: &str,
+    ) -> Result<(), AuthorizationActionError> {
+        let node_registry = match self.certificate_registry {
+            Some(ref node_registry) => node_registry,
+            None => return Ok(()),
+        };
+
+        let certificate = self.network.get_peer_certificate(peer_id).ok_or_else(|| {
+            AuthorizationActionError::CertificateMismatch(
+                "connection has no verified certificate".into(),
+            )
+        })?;
+
+        let node = node_registry
+            .fetch_node(identity)
+            .map_err(|err| match err {
+                NodeRegistryError::NotFoundError(_) => {
+                    AuthorizationActionError::CertificateMismatch(format!(
+                        "{} is not in the node registry",
+                        identity
+                    ))
+                }
+                err => AuthorizationActionError::CertificateMismatch(format!(
+                    "unable to fetch {} from the node registry: {}",
+                    identity, err
+                )),
+            })?;
+
+        let matches = match node.metadata.get(CERTIFICATE_NAMES_METADATA_KEY) {
+            Some(names) => names
+                .split(',')
+                .any(|name| certificate.has_name(name.trim())),
+            None => certificate.has_name(identity),
+        };
+
+        if matches {
+            Ok(())
+        } else {
+            Err(AuthorizationActionError::CertificateMismatch(format!(
+                "certificate is not registered to {}",
+                identity
+            )))
+        }
+    }
+
+    /// Enforces the given allow/deny policy on peers.
+    ///
+    /// A peer is only authorized if the policy allows both the identity it claims and the address
+    /// of its connection. Connections should also be checked against the policy before they are
+    /// added to the network, using `PeerPolicy::check_endpoint`.
+    pub fn with_peer_policy(mut self, peer_policy: PeerPolicy) -> Self {
+        self.peer_policy = Some(peer_policy);
+        self
+    }
+
+    /// Verifies that the given peer's connection and the identity it claims are allowed by the
+    /// peer policy, if there is one.
+    fn verify_policy(&self, peer_id: &str, identity: &str) -> Result<(), AuthorizationActionError> {
+        let peer_policy = match self.peer_policy {
+            Some(ref peer_policy) => peer_policy,
+            None => return Ok(()),
+        };
+
+        let endpoint = self.network.get_peer_endpoint(peer_id);
+        peer_policy
+            .check_peer(endpoint.as_deref(), identity)
+            .map_err(|err| AuthorizationActionError::PeerDenied(err.0))
+    }
+
+    /// Returns the authorized peers that the peer policy no longer allows, such as after its
+    /// rules are reloaded. These peers should be disconnected.
+    pub fn denied_peers(&self) -> Vec<String> {
+        let peer_policy = match self.peer_policy {
+            Some(ref peer_policy) => peer_policy,
+            None => return vec![],
+        };
+
+        let authorized = {
+            let mut shared = mutex_lock_unwrap!(self.shared);
+            shared.drain_removals();
+            shared
+                .states
+                .iter()
+                .filter(|(_, state)| *state == &AuthorizationState::Authorized)
+                .map(|(peer_id, _)| peer_id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        authorized
+            .into_iter()
+            .filter(|peer_id| {
+                let endpoint = self.network.get_peer_endpoint(peer_id);
+                !peer_policy.allows_peer(endpoint.as_deref(), peer_id)
+            })
+            .collect()
+    }
+
+    /// Enables authorization of peers by challenge, using the given keys.
+    pub fn with_challenge_authorization(mut self, challenge: ChallengeAuthorization) -> Self {
+        self.challenge = Some(Arc::new(Mutex::new(challenge)));
+        self
+    }
+
+    /// Returns the name of the given peer's authorization state: "Connecting", "Authorized",
+    /// "Unauthorized" or "Internal"; "Unknown", if the peer has not started authorizing.
+    pub fn authorization_state(&self, peer_id: &str) -> String {
+        let mut shared = mutex_lock_unwrap!(self.shared);
+        shared.drain_removals();
+
+        shared
+            .states
+            .get(peer_id)
+            .unwrap_or(&AuthorizationState::Unknown)
+            .to_string()
+    }
+
+    /// Returns the authorization types accepted from peers, in order of preference.
+    fn accepted_authorization_types(&self) -> Vec<ConnectResponse_AuthorizationType> {
+        match self.challenge {
+            Some(ref challenge) if mutex_lock_unwrap!(challenge).required => {
+                vec![ConnectResponse_AuthorizationType::CHALLENGE]
+            }
+            Some(_) => vec![
+                ConnectResponse_AuthorizationType::CHALLENGE,
+                ConnectResponse_AuthorizationType::TRUST,
+            ],
+            None => vec![ConnectResponse_AuthorizationType::TRUST],
+        }
+    }
+
+    /// Indicates whether or not this node can authorize itself by challenge.
+    fn supports_challenge(&self) -> bool {
+        self.challenge.is_some()
+    }
+
+    /// Creates a nonce for the given connecting peer to sign.
+    ///
+    /// Errors
+    ///
+    /// Returns an error if challenge authorization is not enabled, or if the peer is not
+    /// connecting.
+    fn create_challenge_nonce(&self, peer_id: &str) -> Result<Vec<u8>, AuthorizationActionError> {
+        if self.challenge.is_none() {
+            return Err(AuthorizationActionError::ChallengeFailed(
+                "challenge authorization is not accepted".into(),
+            ));
+        }
+
+        let mut shared = mutex_lock_unwrap!(self.shared);
+        match shared.states.get(peer_id) {
+            Some(AuthorizationState::Connecting) => (),
+            Some(state) => {
+                return Err(AuthorizationActionError::ChallengeFailed(format!(
+                    "peer is {}",
+                    state
+                )))
+            }
+            None => {
+                return Err(AuthorizationActionError::ChallengeFailed(
+                    "peer is not connecting".into(),
+                ))
+            }
+        }
+
+        let mut nonce = vec![0; CHALLENGE_NONCE_LENGTH];
+        rand_bytes(&mut nonce).map_err(|err| {
+            AuthorizationActionError::ChallengeFailed(format!("unable to create nonce: {}", err))
+        })?;
+        shared.nonces.insert(peer_id.to_string(), nonce.clone());
+
+        Ok(nonce)
+    }
+
+    /// Records that a nonce was requested from the given peer, so that the nonce it returns may be
+    /// signed.
+    fn request_challenge_nonce(&self, peer_id: &str) {
+        mutex_lock_unwrap!(self.shared)
+            .challenges_requested
+            .insert(peer_id.to_string());
+    }
+
+    /// Records that the given peer opened its connection to this node.
+    fn set_inbound(&self, peer_id: &str) {
+        mutex_lock_unwrap!(self.shared)
+            .inbound_peers
+            .insert(peer_id.to_string());
+    }
+
+    /// Answers a challenge sent by a peer claiming the given identity.
+    ///
+    /// Only the nonce returned by a peer that this node requested a nonce from is signed, and the
+    /// signature is bound to the peer's identity. If the peer has authorized, or this node
+    /// connected to it as a known node, it must be the identity the peer claims. A peer that
+    /// opened its connection to this node must authorize before its challenge is answered, so its
+    /// challenge is deferred until then; see `take_deferred_challenge`.
+    ///
+    /// Errors
+    ///
+    /// Returns an error if no nonce was requested from the peer, or if the identity it claims is
+    /// not its own.
+    fn answer_challenge(
+        &self,
+        peer_id: &str,
+        challenger_identity: &str,
+        nonce: &[u8],
+    ) -> Result<ChallengeAnswer, AuthorizationActionError> {
+        if self.challenge.is_none() {
+            return Ok(ChallengeAnswer::Disabled);
+        }
+
+        let mut shared = mutex_lock_unwrap!(self.shared);
+        shared.drain_removals();
+
+        if !shared.challenges_requested.remove(peer_id) {
+            return Err(AuthorizationActionError::ChallengeFailed(
+                "no nonce was requested".into(),
+            ));
+        }
+        if challenger_identity.is_empty() {
+            return Err(AuthorizationActionError::ChallengeFailed(
+                "the challenger's identity is missing".into(),
+            ));
+        }
+
+        if !peer_id.starts_with(TEMPORARY_PEER_ID_PREFIX) {
+            if peer_id != challenger_identity {
+                return Err(AuthorizationActionError::ChallengeFailed(format!(
+                    "peer {} claims identity {}",
+                    peer_id, challenger_identity
+                )));
+            }
+        } else if shared.inbound_peers.contains(peer_id) {
+            shared.deferred_challenges.insert(
+                peer_id.to_string(),
+                (challenger_identity.to_string(), nonce.to_vec()),
+            );
+            return Ok(ChallengeAnswer::Deferred);
+        }
+
+        self.sign_challenge(challenger_identity, nonce)
+    }
+
+    /// Answers the challenge deferred for a peer that opened its connection to this node, now
+    /// that the peer has authorized under the given identity, returning `None` if no challenge was
+    /// deferred.
+    ///
+    /// Errors
+    ///
+    /// Returns an error if the identity the peer claimed with its challenge is not the one it
+    /// authorized under.
+    fn take_deferred_challenge(
+        &self,
+        identity: &str,
+    ) -> Result<Option<ChallengeAnswer>, AuthorizationActionError> {
+        let deferred = mutex_lock_unwrap!(self.shared)
+            .deferred_challenges
+            .remove(identity);
+        match deferred {
+            Some((challenger_identity, nonce)) => {
+                if challenger_identity != identity {
+                    return Err(AuthorizationActionError::ChallengeFailed(format!(
+                        "peer {} claimed identity {}",
+                        identity, challenger_identity
+                    )));
+                }
+                self.sign_challenge(&challenger_identity, &nonce).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Signs the challenge payload for this node's identity, the given challenger identity and the
+    /// nonce.
+    fn sign_challenge(
+        &self,
+        challenger_identity: &str,
+        nonce: &[u8],
+    ) -> Result<ChallengeAnswer, AuthorizationActionError> {
+        match self.challenge {
+            Some(ref challenge) => {
+                let challenge = mutex_lock_unwrap!(challenge);
+                let signature = challenge
+                    .signer
+                    .sign(&challenge_payload(
+                        &self.identity,
+                        challenger_identity,
+                        nonce,
+                    ))
+                    .map_err(|err| {
+                        AuthorizationActionError::ChallengeFailed(format!(
+                            "unable to sign nonce: {}",
+                            err
+                        ))
+                    })?;
+                Ok(ChallengeAnswer::Signed {
+                    public_key: challenge.signer.public_key().to_vec(),
+                    signature,
+                })
+            }
+            None => Ok(ChallengeAnswer::Disabled),
         }
     }
 
@@ -150,12 +616,7 @@ impl AuthorizationManager {
         action: AuthorizationAction,
     ) -> Result<AuthorizationState, AuthorizationActionError> {
         let mut shared = mutex_lock_unwrap!(self.shared);
-
-        // drain the removals
-        let removals = shared.disconnect_receiver.try_iter().collect::<Vec<_>>();
-        for peer_id in removals.into_iter() {
-            shared.states.remove(&peer_id);
-        }
+        shared.drain_removals();
 
         let cur_state = shared
             .states
@@ -199,23 +660,40 @@ impl AuthorizationManager {
             AuthorizationState::Connecting => match action {
                 AuthorizationAction::Connecting => Err(AuthorizationActionError::AlreadyConnecting),
                 AuthorizationAction::TrustIdentifying(new_peer_id) => {
-                    // Verify pub key allowed
-                    shared.states.remove(peer_id);
-                    self.network
-                        .update_peer_id(peer_id.to_string(), new_peer_id.clone())
-                        .map_err(|_| AuthorizationActionError::ConnectionLost)?;
-                    shared
-                        .states
-                        .insert(new_peer_id.clone(), AuthorizationState::Authorized);
-                    Self::notify_callbacks(
-                        &shared.callbacks,
-                        &new_peer_id,
-                        PeerAuthorizationState::Authorized,
-                    );
-                    Ok(AuthorizationState::Authorized)
+                    if !self
+                        .accepted_authorization_types()
+                        .contains(&ConnectResponse_AuthorizationType::TRUST)
+                    {
+                        return Err(AuthorizationActionError::TrustNotAccepted);
+                    }
+                    self.authorize(&mut shared, peer_id, new_peer_id)
+                }
+                AuthorizationAction::ChallengeIdentifying {
+                    identity,
+                    public_key,
+                    signature,
+                } => {
+                    let challenge = self.challenge.as_ref().ok_or_else(|| {
+                        AuthorizationActionError::ChallengeFailed(
+                            "challenge authorization is not accepted".into(),
+                        )
+                    })?;
+                    // The nonce may only be used once
+                    let nonce = shared.nonces.remove(peer_id).ok_or_else(|| {
+                        AuthorizationActionError::ChallengeFailed("no nonce was requested".into())
+                    })?;
+                    mutex_lock_unwrap!(challenge).verify(
+                        &identity,
+                        &self.identity,
+                        &nonce,
+                        &public_key,
+                        &signature,
+                    )?;
+                    self.authorize(&mut shared, peer_id, identity)
                 }
                 AuthorizationAction::Unauthorizing => {
                     shared.states.remove(peer_id);
+                    shared.forget(peer_id);
                     self.network
                         .remove_connection(&peer_id.to_string())
                         .map_err(|_| AuthorizationActionError::ConnectionLost)?;
@@ -230,6 +708,7 @@ impl AuthorizationManager {
             AuthorizationState::Authorized => match action {
                 AuthorizationAction::Unauthorizing => {
                     shared.states.remove(peer_id);
+                    shared.forget(peer_id);
                     self.network
                         .remove_connection(&peer_id.to_string())
                         .map_err(|_| AuthorizationActionError::ConnectionLost)?;
@@ -252,6 +731,41 @@ impl AuthorizationManager {
         }
     }
 
+    /// Authorizes a connecting peer under its proven identity.
+    fn authorize(
+        &self,
+        shared: &mut ManagedAuthorizations,
+        peer_id: &str,
+        new_peer_id: Identity,
+    ) -> Result<AuthorizationState, AuthorizationActionError> {
+        self.verify_policy(peer_id, &new_peer_id)?;
+        self.verify_certificate(peer_id, &new_peer_id)?;
+
+        shared.states.remove(peer_id);
+        self.network
+            .update_peer_id(peer_id.to_string(), new_peer_id.clone())
+            .map_err(|_| AuthorizationActionError::ConnectionLost)?;
+        shared
+            .states
+            .insert(new_peer_id.clone(), AuthorizationState::Authorized);
+        // The peer's pending challenge, if any, is now answered under its identity
+        shared.inbound_peers.remove(peer_id);
+        if shared.challenges_requested.remove(peer_id) {
+            shared.challenges_requested.insert(new_peer_id.clone());
+        }
+        if let Some(deferred) = shared.deferred_challenges.remove(peer_id) {
+            shared
+                .deferred_challenges
+                .insert(new_peer_id.clone(), deferred);
+        }
+        Self::notify_callbacks(
+            &shared.callbacks,
+            &new_peer_id,
+            PeerAuthorizationState::Authorized,
+        );
+        Ok(AuthorizationState::Authorized)
+    }
+
     fn notify_callbacks(
         callbacks: &[Box<dyn AuthorizationCallback>],
         peer_id: &str,
@@ -282,12 +796,7 @@ impl AuthorizationInquisitor for AuthorizationManager {
 
     fn is_authorized(&self, peer_id: &str) -> bool {
         let mut shared = mutex_lock_unwrap!(self.shared);
-
-        // drain the removals
-        let removals = shared.disconnect_receiver.try_iter().collect::<Vec<_>>();
-        for peer_id in removals.into_iter() {
-            shared.states.remove(&peer_id);
-        }
+        shared.drain_removals();
 
         if let Some(state) = shared.states.get(peer_id) {
             state == &AuthorizationState::Authorized || state == &AuthorizationState::Internal
@@ -299,6 +808,15 @@ impl AuthorizationInquisitor for AuthorizationManager {
 
 struct ManagedAuthorizations {
     states: HashMap<String, AuthorizationState>,
+    /// The nonces sent to peers authorizing by challenge, by peer id
+    nonces: HashMap<String, Vec<u8>>,
+    /// The peers this node has requested a nonce from, to authorize with them by challenge
+    challenges_requested: HashSet<String>,
+    /// The peers that opened their connections to this node
+    inbound_peers: HashSet<String>,
+    /// The identities claimed and nonces sent by peers whose challenges are answered once they
+    /// have authorized, by peer id
+    deferred_challenges: HashMap<String, (Identity, Vec<u8>)>,
     callbacks: Vec<Box<dyn AuthorizationCallback>>,
     disconnect_receiver: Receiver<String>,
 }
@@ -307,10 +825,45 @@ impl ManagedAuthorizations {
     fn new(disconnect_receiver: Receiver<String>) -> Self {
         Self {
             states: Default::default(),
+            nonces: Default::default(),
+            challenges_requested: Default::default(),
+            inbound_peers: Default::default(),
+            deferred_challenges: Default::default(),
             callbacks: Default::default(),
             disconnect_receiver,
         }
     }
+
+    /// Removes the states of the peers that have disconnected.
+    fn drain_removals(&mut self) {
+        let removals = self.disconnect_receiver.try_iter().collect::<Vec<_>>();
+        for peer_id in removals.into_iter() {
+            self.states.remove(&peer_id);
+            self.forget(&peer_id);
+        }
+    }
+
+    /// Removes the challenge state of the given peer.
+    fn forget(&mut self, peer_id: &str) {
+        self.nonces.remove(peer_id);
+        self.challenges_requested.remove(peer_id);
+        self.inbound_peers.remove(peer_id);
+        self.deferred_challenges.remove(peer_id);
+    }
+}
+
+/// The answer to a challenge sent by a peer.
+#[derive(Debug, PartialEq)]
+enum ChallengeAnswer {
+    /// The challenge was signed with this node's key
+    Signed {
+        public_key: Vec<u8>,
+        signature: Vec<u8>,
+    },
+    /// The challenge will be answered once the peer has authorized
+    Deferred,
+    /// Challenge authorization is not enabled
+    Disabled,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -350,8 +903,9 @@ mod tests {
 
     use crate::mesh::Mesh;
     use crate::network::Network;
+    use crate::node_registry::{mock::MockNodeRegistry, Node};
     use crate::transport::{
-        ConnectError, Connection, DisconnectError, RecvError, SendError, Transport,
+        ConnectError, Connection, DisconnectError, PeerCertificate, RecvError, SendError, Transport,
     };
 
     /// This test runs through the trust authorization state machine happy path. It traverses
@@ -364,6 +918,7 @@ mod tests {
         let auth_manager = AuthorizationManager::new(network.clone(), "mock_identity".into());
 
         assert!(!auth_manager.is_authorized(&peer_id));
+        assert_eq!("Unknown", auth_manager.authorization_state(&peer_id));
 
         assert_eq!(
             Ok(AuthorizationState::Connecting),
@@ -371,6 +926,7 @@ mod tests {
         );
 
         assert!(!auth_manager.is_authorized(&peer_id));
+        assert_eq!("Connecting", auth_manager.authorization_state(&peer_id));
 
         // verify that it cannot be connected again.
         assert_eq!(
@@ -392,6 +948,7 @@ mod tests {
         assert!(!auth_manager.is_authorized(&peer_id));
         // but we now have the new identified peer
         assert!(auth_manager.is_authorized(&new_peer_id));
+        assert_eq!("Authorized", auth_manager.authorization_state(&new_peer_id));
         assert_eq!(vec![new_peer_id.clone()], network.peer_ids());
     }
 
@@ -562,6 +1119,176 @@ mod tests {
         );
     }
 
+    /// This test verifies that, when certificates are bound to identities, a peer is only
+    /// authorized under an identity whose registered certificate names include a name of the
+    /// peer's certificate; a node without registered names must have a certificate named after
+    /// its identity.
+    #[test]
+    fn certificate_binding_state_machine() {
+        let mut acme_node = Node::new("acme-node-000", "tls://127.0.0.1:8044");
+        acme_node.metadata.insert(
+            CERTIFICATE_NAMES_METADATA_KEY.into(),
+            "other.acme.com, node.acme.com".into(),
+        );
+        let node_registry = MockNodeRegistry(vec![
+            acme_node,
+            Node::new("bubba-node-000", "tls://127.0.0.1:8045"),
+        ]);
+
+        let network = Network::new(Mesh::new(5, 5), 0).unwrap();
+        let auth_manager = AuthorizationManager::new(network.clone(), "mock_identity".into())
+            .with_certificate_binding(Box::new(node_registry));
+
+        let peer_id = network
+            .add_connection(Box::new(MockCertificateConnection(PeerCertificate {
+                subject_common_name: Some("bubba-node-000".into()),
+                subject_alt_names: vec!["node.acme.com".into()],
+            })))
+            .expect("Unable to add connection to network");
+        assert_eq!(
+            Ok(AuthorizationState::Connecting),
+            auth_manager.next_state(&peer_id, AuthorizationAction::Connecting)
+        );
+        assert_eq!(
+            Ok(AuthorizationState::Authorized),
+            auth_manager.next_state(
+                &peer_id,
+                AuthorizationAction::TrustIdentifying("acme-node-000".into())
+            )
+        );
+
+        let peer_id = network
+            .add_connection(Box::new(MockCertificateConnection(PeerCertificate {
+                subject_common_name: Some("bubba-node-000".into()),
+                subject_alt_names: vec![],
+            })))
+            .expect("Unable to add connection to network");
+        assert_eq!(
+            Ok(AuthorizationState::Connecting),
+            auth_manager.next_state(&peer_id, AuthorizationAction::Connecting)
+        );
+        match auth_manager.next_state(
+            &peer_id,
+            AuthorizationAction::TrustIdentifying("acme-node-000".into()),
+        ) {
+            Err(AuthorizationActionError::CertificateMismatch(_)) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+        match auth_manager.next_state(
+            &peer_id,
+            AuthorizationAction::TrustIdentifying("unknown-node".into()),
+        ) {
+            Err(AuthorizationActionError::CertificateMismatch(_)) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+        assert_eq!(
+            Ok(AuthorizationState::Authorized),
+            auth_manager.next_state(
+                &peer_id,
+                AuthorizationAction::TrustIdentifying("bubba-node-000".into())
+            )
+        );
+
+        // a connection without a verified certificate is never authorized
+        let (network, peer_id) = create_network_with_initial_temp_peer();
+        let auth_manager = AuthorizationManager::new(network, "mock_identity".into())
+            .with_certificate_binding(Box::new(MockNodeRegistry(vec![Node::new(
+                "bubba-node-000",
+                "tls://127.0.0.1:8045",
+            )])));
+        assert_eq!(
+            Ok(AuthorizationState::Connecting),
+            auth_manager.next_state(&peer_id, AuthorizationAction::Connecting)
+        );
+        match auth_manager.next_state(
+            &peer_id,
+            AuthorizationAction::TrustIdentifying("bubba-node-000".into()),
+        ) {
+            Err(AuthorizationActionError::CertificateMismatch(_)) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+
+    /// Test that a peer is only authorized if the peer policy allows the identity it claims, and
+    /// that denied attempts are counted.
+    #[test]
+    fn peer_policy_state_machine() {
+        let peer_policy = PeerPolicy::new(policy::PeerPolicyRules {
+            allow: vec!["*-node-000".into()],
+            deny: vec!["bubba-*".into()],
+            require_registered: false,
+        })
+        .expect("Unable to create peer policy");
+
+        let (network, peer_id) = create_network_with_initial_temp_peer();
+        let auth_manager = AuthorizationManager::new(network, "mock_identity".into())
+            .with_peer_policy(peer_policy.clone());
+        assert_eq!(
+            Ok(AuthorizationState::Connecting),
+            auth_manager.next_state(&peer_id, AuthorizationAction::Connecting)
+        );
+        match auth_manager.next_state(
+            &peer_id,
+            AuthorizationAction::TrustIdentifying("bubba-node-000".into()),
+        ) {
+            Err(AuthorizationActionError::PeerDenied(_)) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+        match auth_manager.next_state(
+            &peer_id,
+            AuthorizationAction::TrustIdentifying("acme-node-001".into()),
+        ) {
+            Err(AuthorizationActionError::PeerDenied(_)) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+        assert_eq!(
+            Ok(AuthorizationState::Authorized),
+            auth_manager.next_state(
+                &peer_id,
+                AuthorizationAction::TrustIdentifying("acme-node-000".into())
+            )
+        );
+
+        assert_eq!(2, peer_policy.denied_attempts().by_identity);
+    }
+
+    /// Test that an authorized peer is listed as denied once the peer policy's rules are reloaded
+    /// to deny it, without counting a denied attempt.
+    #[test]
+    fn peer_policy_denied_peers() {
+        let peer_policy = PeerPolicy::new(policy::PeerPolicyRules::default())
+            .expect("Unable to create peer policy");
+
+        let (network, peer_id) = create_network_with_initial_temp_peer();
+        let auth_manager = AuthorizationManager::new(network, "mock_identity".into())
+            .with_peer_policy(peer_policy.clone());
+        assert_eq!(
+            Ok(AuthorizationState::Connecting),
+            auth_manager.next_state(&peer_id, AuthorizationAction::Connecting)
+        );
+        assert_eq!(
+            Ok(AuthorizationState::Authorized),
+            auth_manager.next_state(
+                &peer_id,
+                AuthorizationAction::TrustIdentifying("acme-node-000".into())
+            )
+        );
+        assert!(auth_manager.denied_peers().is_empty());
+
+        peer_policy
+            .reload(policy::PeerPolicyRules {
+                deny: vec!["acme-*".into()],
+                ..Default::default()
+            })
+            .expect("Unable to reload peer policy");
+
+        assert_eq!(
+            vec!["acme-node-000".to_string()],
+            auth_manager.denied_peers()
+        );
+        assert_eq!(0, peer_policy.denied_attempts().by_identity);
+    }
+
     fn create_network_with_initial_temp_peer() -> (Network, String) {
         let network = Network::new(Mesh::new(5, 5), 0).unwrap();
 
@@ -627,6 +1354,38 @@ mod tests {
         }
     }
 
+    struct MockCertificateConnection(PeerCertificate);
+
+    impl Connection for MockCertificateConnection {
+        fn send(&mut self, _message: &[u8]) -> Result<(), SendError> {
+            Ok(())
+        }
+
+        fn recv(&mut self) -> Result<Vec<u8>, RecvError> {
+            unimplemented!()
+        }
+
+        fn remote_endpoint(&self) -> String {
+            String::from("MockCertificateConnection")
+        }
+
+        fn local_endpoint(&self) -> String {
+            String::from("MockCertificateConnection")
+        }
+
+        fn disconnect(&mut self) -> Result<(), DisconnectError> {
+            Ok(())
+        }
+
+        fn evented(&self) -> &dyn mio::Evented {
+            &MockEvented
+        }
+
+        fn peer_certificate(&self) -> Option<PeerCertificate> {
+            Some(self.0.clone())
+        }
+    }
+
     struct MockEvented;
 
     impl mio::Evented for MockEvented {
@@ -0,0 +1,637 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Allow and deny lists for peers.
+//!
+//! A `PeerPolicy` decides which peers may connect to this node, by the address they connect from
+//! and by the identity they authorize with. Patterns are either IP addresses and CIDR blocks, such
+//! as `10.0.0.0/8`, or node IDs, which may contain `*` wildcards, such as `acme-node-*`.
+//!
+//! A peer is denied if its address or identity matches a deny pattern. If any address patterns
+//! are allowed, a peer's address must match one of them, and if any node ID patterns are allowed,
+//! its identity must match one of them. The policy may also require peers to be in the node
+//! registry. The rules of a policy can be replaced at runtime, and the number of denied attempts
+//! is counted.
+
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::glob::glob_matches;
+use crate::node_registry::NodeRegistryReader;
+
+/// The rules of a peer policy, as loaded from a policy file.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct PeerPolicyRules {
+    /// The address and node ID patterns peers must match, if any
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// The address and node ID patterns peers must not match
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Whether peers must be in the node registry
+    #[serde(default)]
+    pub require_registered: bool,
+}
+
+impl PeerPolicyRules {
+    /// Loads rules from the given YAML file.
+    pub fn from_yaml_file<P: AsRef<Path>>(path: P) -> Result<Self, PeerPolicyError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|err| {
+            PeerPolicyError::LoadError(format!("unable to open {}: {}", path.display(), err))
+        })?;
+        serde_yaml::from_reader(file).map_err(|err| {
+            PeerPolicyError::LoadError(format!("unable to parse {}: {}", path.display(), err))
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum PeerPolicyError {
+    /// This error is returned when a pattern is neither an address nor a node ID pattern.
+    InvalidPattern(String),
+    /// This error is returned when rules cannot be loaded from a policy file.
+    LoadError(String),
+}
+
+impl Error for PeerPolicyError {}
+
+impl fmt::Display for PeerPolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PeerPolicyError::InvalidPattern(msg) => write!(f, "invalid pattern: {}", msg),
+            PeerPolicyError::LoadError(msg) => write!(f, "unable to load policy: {}", msg),
+        }
+    }
+}
+
+/// The reason a peer was denied by a policy.
+#[derive(Debug, PartialEq)]
+pub struct PeerDenied(pub String);
+
+impl Error for PeerDenied {}
+
+impl fmt::Display for PeerDenied {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "peer denied: {}", self.0)
+    }
+}
+
+/// The number of attempts denied by a policy since it was created.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DeniedAttempts {
+    /// Connections denied by the address they came from
+    pub by_address: u64,
+    /// Authorizations denied by the identity they claimed
+    pub by_identity: u64,
+}
+
+/// A peer allow/deny policy.
+///
+/// Clones of a policy share its rules and counters, so rules reloaded through one clone apply to
+/// all of them.
+#[derive(Clone)]
+pub struct PeerPolicy {
+    rules: Arc<RwLock<CompiledRules>>,
+    node_registry: Option<Arc<dyn NodeRegistryReader>>,
+    denied_by_address: Arc<AtomicU64>,
+    denied_by_identity: Arc<AtomicU64>,
+}
+
+impl PeerPolicy {
+    /// Creates a policy enforcing the given rules.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PeerPolicyError` if any of the rules' patterns are invalid.
+    pub fn new(rules: PeerPolicyRules) -> Result<Self, PeerPolicyError> {
+        Ok(PeerPolicy {
+            rules: Arc::new(RwLock::new(CompiledRules::compile(&rules)?)),
+            node_registry: None,
+            denied_by_address: Arc::new(AtomicU64::new(0)),
+            denied_by_identity: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// Sets the node registry peers must be in when the rules require registration. If no node
+    /// registry is set, registration is not checked.
+    pub fn with_node_registry(mut self, node_registry: Box<dyn NodeRegistryReader>) -> Self {
+        self.node_registry = Some(Arc::from(node_registry));
+        self
+    }
+
+    /// Replaces the rules of the policy. The current rules are kept if any of the new rules'
+    /// patterns are invalid.
+    pub fn reload(&self, rules: PeerPolicyRules) -> Result<(), PeerPolicyError> {
+        let compiled = CompiledRules::compile(&rules)?;
+        *self
+            .rules
+            .write()
+            .map_err(|_| PeerPolicyError::LoadError("policy lock was poisoned".into()))? = compiled;
+        Ok(())
+    }
+
+    /// Checks whether a peer may connect from the given endpoint, such as `tcp://10.0.0.1:8044`.
+    ///
+    /// Endpoints that are not IP socket addresses do not match any address pattern.
+    pub fn check_endpoint(&self, endpoint: &str) -> Result<(), PeerDenied> {
+        let result = self
+            .read_rules()
+            .check_address(parse_endpoint_address(endpoint));
+        if result.is_err() {
+            self.denied_by_address.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    /// Checks whether a peer may authorize with the given identity.
+    pub fn check_identity(&self, identity: &str) -> Result<(), PeerDenied> {
+        self.check_peer(None, identity)
+    }
+
+    /// Checks whether a peer may authorize with the given identity from the given endpoint, if it
+    /// has one.
+    ///
+    /// The attempt is counted once if it is denied: by address, if its address is denied, and by
+    /// identity otherwise.
+    pub fn check_peer(&self, endpoint: Option<&str>, identity: &str) -> Result<(), PeerDenied> {
+        self.evaluate(endpoint, identity)
+            .map_err(|denial| match denial {
+                Denial::Address(denied) => {
+                    self.denied_by_address.fetch_add(1, Ordering::Relaxed);
+                    denied
+                }
+                Denial::Identity(denied) => {
+                    self.denied_by_identity.fetch_add(1, Ordering::Relaxed);
+                    denied
+                }
+            })
+    }
+
+    /// Returns whether the current rules allow a peer with the given identity and endpoint,
+    /// without counting a denied attempt. This is used to find connected peers that are no longer
+    /// allowed after the rules are reloaded.
+    pub fn allows_peer(&self, endpoint: Option<&str>, identity: &str) -> bool {
+        self.evaluate(endpoint, identity).is_ok()
+    }
+
+    /// Returns the number of attempts denied by the policy.
+    pub fn denied_attempts(&self) -> DeniedAttempts {
+        DeniedAttempts {
+            by_address: self.denied_by_address.load(Ordering::Relaxed),
+            by_identity: self.denied_by_identity.load(Ordering::Relaxed),
+        }
+    }
+
+    fn evaluate(&self, endpoint: Option<&str>, identity: &str) -> Result<(), Denial> {
+        let rules = self.read_rules();
+
+        if let Some(endpoint) = endpoint {
+            rules
+                .check_address(parse_endpoint_address(endpoint))
+                .map_err(Denial::Address)?;
+        }
+
+        rules.check_identity(identity).map_err(Denial::Identity)?;
+        if rules.require_registered {
+            self.check_registered(identity).map_err(Denial::Identity)?;
+        }
+
+        Ok(())
+    }
+
+    fn check_registered(&self, identity: &str) -> Result<(), PeerDenied> {
+        let node_registry = match self.node_registry {
+            Some(ref node_registry) => node_registry,
+            None => return Ok(()),
+        };
+
+        match node_registry.has_node(identity) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(PeerDenied(format!(
+                "{} is not in the node registry",
+                identity
+            ))),
+            Err(err) => Err(PeerDenied(format!(
+                "unable to check the node registry for {}: {}",
+                identity, err
+            ))),
+        }
+    }
+
+    fn read_rules(&self) -> std::sync::RwLockReadGuard<CompiledRules> {
+        // A writer cannot panic while holding the lock, so the rules are always intact
+        match self.rules.read() {
+            Ok(rules) => rules,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// Which check denied a peer.
+enum Denial {
+    Address(PeerDenied),
+    Identity(PeerDenied),
+}
+
+/// A pattern matched against peers.
+#[derive(Clone, Debug, PartialEq)]
+enum PeerPattern {
+    Network(IpNetwork),
+    NodeId(String),
+}
+
+impl FromStr for PeerPattern {
+    type Err = PeerPolicyError;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            return Err(PeerPolicyError::InvalidPattern("pattern is empty".into()));
+        }
+
+        if let Ok(address) = pattern.parse::<IpAddr>() {
+            return Ok(PeerPattern::Network(IpNetwork::host(address)));
+        }
+
+        if pattern.contains('/') {
+            return pattern.parse().map(PeerPattern::Network);
+        }
+
+        Ok(PeerPattern::NodeId(pattern.to_string()))
+    }
+}
+
+/// An IP address block, given in CIDR notation.
+#[derive(Clone, Debug, PartialEq)]
+struct IpNetwork {
+    address: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNetwork {
+    fn host(address: IpAddr) -> Self {
+        let prefix_len = match address {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        IpNetwork {
+            address,
+            prefix_len,
+        }
+    }
+
+    fn contains(&self, address: &IpAddr) -> bool {
+        match (self.address, address) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => {
+                let mask = u32::max_value()
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(*address) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                let mask = u128::max_value()
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(*address) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = PeerPolicyError;
+
+    fn from_str(cidr: &str) -> Result<Self, Self::Err> {
+        let mut parts = cidr.splitn(2, '/');
+        let address = parts
+            .next()
+            .unwrap_or_default()
+            .parse::<IpAddr>()
+            .map_err(|err| PeerPolicyError::InvalidPattern(format!("{}: {}", cidr, err)))?;
+        let prefix_len = parts
+            .next()
+            .unwrap_or_default()
+            .parse::<u8>()
+            .map_err(|err| PeerPolicyError::InvalidPattern(format!("{}: {}", cidr, err)))?;
+
+        if prefix_len > IpNetwork::host(address).prefix_len {
+            return Err(PeerPolicyError::InvalidPattern(format!(
+                "{}: prefix length is too long",
+                cidr
+            )));
+        }
+
+        Ok(IpNetwork {
+            address,
+            prefix_len,
+        })
+    }
+}
+
+#[derive(Default)]
+struct CompiledRules {
+    allow_networks: Vec<IpNetwork>,
+    allow_node_ids: Vec<String>,
+    deny_networks: Vec<IpNetwork>,
+    deny_node_ids: Vec<String>,
+    require_registered: bool,
+}
+
+impl CompiledRules {
+    fn compile(rules: &PeerPolicyRules) -> Result<Self, PeerPolicyError> {
+        let mut compiled = CompiledRules {
+            require_registered: rules.require_registered,
+            ..Default::default()
+        };
+
+        for pattern in &rules.allow {
+            match pattern.parse()? {
+                PeerPattern::Network(network) => compiled.allow_networks.push(network),
+                PeerPattern::NodeId(node_id) => compiled.allow_node_ids.push(node_id),
+            }
+        }
+        for pattern in &rules.deny {
+            match pattern.parse()? {
+                PeerPattern::Network(network) => compiled.deny_networks.push(network),
+                PeerPattern::NodeId(node_id) => compiled.deny_node_ids.push(node_id),
+            }
+        }
+
+        Ok(compiled)
+    }
+
+    fn check_address(&self, address: Option<IpAddr>) -> Result<(), PeerDenied> {
+        if self.allow_networks.is_empty() && self.deny_networks.is_empty() {
+            return Ok(());
+        }
+
+        let address = match address {
+            Some(address) => address,
+            None if self.allow_networks.is_empty() => return Ok(()),
+            None => return Err(PeerDenied("address is not an IP address".into())),
+        };
+
+        if self
+            .deny_networks
+            .iter()
+            .any(|network| network.contains(&address))
+        {
+            return Err(PeerDenied(format!("address {} is denied", address)));
+        }
+
+        if !self.allow_networks.is_empty()
+            && !self
+                .allow_networks
+                .iter()
+                .any(|network| network.contains(&address))
+        {
+            return Err(PeerDenied(format!("address {} is not allowed", address)));
+        }
+
+        Ok(())
+    }
+
+    fn check_identity(&self, identity: &str) -> Result<(), PeerDenied> {
+        if self
+            .deny_node_ids
+            .iter()
+            .any(|pattern| glob_matches(pattern, identity))
+        {
+            return Err(PeerDenied(format!("{} is denied", identity)));
+        }
+
+        if !self.allow_node_ids.is_empty()
+            && !self
+                .allow_node_ids
+                .iter()
+                .any(|pattern| glob_matches(pattern, identity))
+        {
+            return Err(PeerDenied(format!("{} is not allowed", identity)));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the IP address of an endpoint such as `tls://10.0.0.1:8044`, if it has one.
+fn parse_endpoint_address(endpoint: &str) -> Option<IpAddr> {
+    let address = match endpoint.find("://") {
+        Some(index) => &endpoint[index + 3..],
+        None => endpoint,
+    };
+
+    address
+        .parse::<SocketAddr>()
+        .map(|socket_addr| socket_addr.ip())
+        .or_else(|_| address.parse::<IpAddr>())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::node_registry::{mock::MockNodeRegistry, Node};
+
+    #[test]
+    // test that patterns are parsed as networks or node IDs, and that invalid networks are
+    // rejected
+    fn test_parse_patterns() {
+        assert_eq!(
+            "10.0.0.0/8".parse::<PeerPattern>().unwrap(),
+            PeerPattern::Network(IpNetwork {
+                address: "10.0.0.0".parse().unwrap(),
+                prefix_len: 8,
+            })
+        );
+        assert_eq!(
+            "::1".parse::<PeerPattern>().unwrap(),
+            PeerPattern::Network(IpNetwork {
+                address: "::1".parse().unwrap(),
+                prefix_len: 128,
+            })
+        );
+        assert_eq!(
+            "acme-*".parse::<PeerPattern>().unwrap(),
+            PeerPattern::NodeId("acme-*".into())
+        );
+        assert!("10.0.0.0/33".parse::<PeerPattern>().is_err());
+        assert!("10.0.0/8".parse::<PeerPattern>().is_err());
+        assert!("".parse::<PeerPattern>().is_err());
+    }
+
+    #[test]
+    // test that endpoints are checked against address patterns, with deny patterns taking
+    // precedence over allow patterns
+    fn test_check_endpoint() {
+        let policy = PeerPolicy::new(PeerPolicyRules {
+            allow: vec!["10.0.0.0/8".into(), "fd00::/8".into(), "acme-*".into()],
+            deny: vec!["10.0.0.13".into()],
+            require_registered: false,
+        })
+        .expect("Unable to create policy");
+
+        assert!(policy.check_endpoint("tcp://10.1.2.3:8044").is_ok());
+        assert!(policy.check_endpoint("tls://[fd00::1]:8044").is_ok());
+        assert!(policy.check_endpoint("tcp://10.0.0.13:8044").is_err());
+        assert!(policy.check_endpoint("tcp://192.168.0.1:8044").is_err());
+        assert!(policy.check_endpoint("inproc://admin").is_err());
+
+        assert_eq!(
+            policy.denied_attempts(),
+            DeniedAttempts {
+                by_address: 3,
+                by_identity: 0,
+            }
+        );
+    }
+
+    #[test]
+    // test that identities are checked against node ID patterns and, if required, the node
+    // registry
+    fn test_check_identity() {
+        let policy = PeerPolicy::new(PeerPolicyRules {
+            allow: vec!["acme-*".into(), "10.0.0.0/8".into()],
+            deny: vec!["acme-node-666".into()],
+            require_registered: true,
+        })
+        .expect("Unable to create policy")
+        .with_node_registry(Box::new(MockNodeRegistry(vec![
+            Node::new("acme-node-000", "tcp://10.0.0.1:8044"),
+            Node::new("acme-node-666", "tcp://10.0.0.6:8044"),
+        ])));
+
+        assert!(policy.check_identity("acme-node-000").is_ok());
+        assert!(policy.check_identity("acme-node-666").is_err());
+        assert!(policy.check_identity("acme-node-001").is_err());
+        assert!(policy.check_identity("bubba-node-000").is_err());
+
+        assert_eq!(
+            policy.denied_attempts(),
+            DeniedAttempts {
+                by_address: 0,
+                by_identity: 3,
+            }
+        );
+    }
+
+    #[test]
+    // test that a peer denied by both its address and its identity is only counted once, by
+    // address, and that checking whether a peer is allowed does not count it
+    fn test_check_peer() {
+        let policy = PeerPolicy::new(PeerPolicyRules {
+            allow: vec!["10.0.0.0/8".into()],
+            deny: vec!["bubba-*".into()],
+            require_registered: false,
+        })
+        .expect("Unable to create policy");
+
+        assert!(policy
+            .check_peer(Some("tcp://10.0.0.1:8044"), "acme-node-000")
+            .is_ok());
+        assert!(policy
+            .check_peer(Some("tcp://192.168.0.1:8044"), "bubba-node-000")
+            .is_err());
+        assert!(policy
+            .check_peer(Some("tcp://10.0.0.1:8044"), "bubba-node-000")
+            .is_err());
+        assert!(policy.check_peer(None, "acme-node-000").is_ok());
+
+        assert_eq!(
+            policy.denied_attempts(),
+            DeniedAttempts {
+                by_address: 1,
+                by_identity: 1,
+            }
+        );
+
+        assert!(policy.allows_peer(Some("tcp://10.0.0.1:8044"), "acme-node-000"));
+        assert!(!policy.allows_peer(Some("tcp://10.0.0.1:8044"), "bubba-node-000"));
+        assert!(!policy.allows_peer(Some("tcp://192.168.0.1:8044"), "acme-node-000"));
+
+        assert_eq!(
+            policy.denied_attempts(),
+            DeniedAttempts {
+                by_address: 1,
+                by_identity: 1,
+            }
+        );
+    }
+
+    #[test]
+    // test that reloaded rules apply to every clone of a policy, and that invalid rules leave the
+    // current rules in place
+    fn test_reload() {
+        let policy = PeerPolicy::new(PeerPolicyRules::default()).expect("Unable to create policy");
+        let policy_clone = policy.clone();
+
+        assert!(policy_clone.check_identity("bubba-node-000").is_ok());
+        assert!(policy_clone
+            .check_endpoint("tcp://192.168.0.1:8044")
+            .is_ok());
+
+        policy
+            .reload(PeerPolicyRules {
+                deny: vec!["bubba-*".into(), "192.168.0.0/16".into()],
+                ..Default::default()
+            })
+            .expect("Unable to reload policy");
+
+        assert!(policy_clone.check_identity("bubba-node-000").is_err());
+        assert!(policy_clone
+            .check_endpoint("tcp://192.168.0.1:8044")
+            .is_err());
+        assert!(policy_clone.check_identity("acme-node-000").is_ok());
+
+        assert!(policy
+            .reload(PeerPolicyRules {
+                allow: vec!["10.0.0.0/99".into()],
+                ..Default::default()
+            })
+            .is_err());
+        assert!(policy_clone.check_identity("bubba-node-000").is_err());
+    }
+
+    #[test]
+    // test that rules are loaded from a YAML policy file
+    fn test_rules_from_yaml_file() {
+        let temp_dir =
+            tempdir::TempDir::new("test_rules_from_yaml_file").expect("Unable to create temp dir");
+        let path = temp_dir.path().join("peer_policy.yaml");
+        std::fs::write(
+            &path,
+            "allow:\n  - 10.0.0.0/8\n  - acme-*\ndeny:\n  - acme-node-666\n",
+        )
+        .expect("Unable to write policy file");
+
+        assert_eq!(
+            PeerPolicyRules::from_yaml_file(&path).expect("Unable to load rules"),
+            PeerPolicyRules {
+                allow: vec!["10.0.0.0/8".into(), "acme-*".into()],
+                deny: vec!["acme-node-666".into()],
+                require_registered: false,
+            }
+        );
+    }
+}
@@ -0,0 +1,747 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Capture of the messages sent and received by a network, for debugging.
+//!
+//! When a `CaptureWriter` is given to `Network::start_capture`, every message the network sends
+//! or receives is written to it as a `CaptureRecord`, with the time, direction, peer ID and
+//! decoded message type. A `CaptureReader` reads the records back, so that they can be printed,
+//! or replayed against a dispatcher with `replay` to reproduce an incident offline.
+//!
+//! A capture file is a sequence of records, each of which is a big-endian u32 length followed by
+//! that many bytes of a `CaptureRecord` protobuf message. Since records hold the messages' full
+//! payloads, capture files are only readable by their owner, and may be rotated once they reach a
+//! maximum size.
+
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
+use protobuf::Message;
+
+use crate::network::dispatch::Dispatcher;
+use crate::protos::authorization::AuthorizationMessage;
+use crate::protos::capture::{
+    CaptureRecord as CaptureRecordProto, CaptureRecord_Direction as DirectionProto,
+};
+use crate::protos::circuit::{
+    AdminDirectMessage, CircuitDirectMessage, CircuitMessage, CircuitMessageType,
+};
+use crate::protos::network::{NetworkMessage, NetworkMessageType};
+
+/// Whether a captured message was received from or sent to a peer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Direction::Inbound => f.write_str("<-"),
+            Direction::Outbound => f.write_str("->"),
+        }
+    }
+}
+
+/// A message sent or received by a network.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CaptureRecord {
+    timestamp: u64,
+    direction: Direction,
+    peer_id: String,
+    message_type: String,
+    payload: Vec<u8>,
+}
+
+impl CaptureRecord {
+    /// Creates a record of the given `NetworkMessage` bytes, sent or received now.
+    pub fn new(direction: Direction, peer_id: &str, payload: &[u8]) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_micros() as u64)
+            .unwrap_or(0);
+
+        CaptureRecord {
+            timestamp,
+            direction,
+            peer_id: peer_id.to_string(),
+            message_type: message_type(payload),
+            payload: payload.to_vec(),
+        }
+    }
+
+    /// The time the message was sent or received, in microseconds since the UNIX epoch.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub fn peer_id(&self) -> &str {
+        &self.peer_id
+    }
+
+    /// The decoded type of the message, such as "CIRCUIT/ADMIN_DIRECT_MESSAGE".
+    pub fn message_type(&self) -> &str {
+        &self.message_type
+    }
+
+    /// The `NetworkMessage` bytes.
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    fn from_proto(mut proto: CaptureRecordProto) -> Result<Self, CaptureError> {
+        let direction = match proto.get_direction() {
+            DirectionProto::INBOUND => Direction::Inbound,
+            DirectionProto::OUTBOUND => Direction::Outbound,
+            DirectionProto::UNSET_DIRECTION => {
+                return Err(CaptureError::InvalidRecord(
+                    "record direction is not set".into(),
+                ))
+            }
+        };
+
+        Ok(CaptureRecord {
+            timestamp: proto.get_timestamp(),
+            direction,
+            peer_id: proto.take_peer_id(),
+            message_type: proto.take_message_type(),
+            payload: proto.take_payload(),
+        })
+    }
+
+    fn to_proto(&self) -> CaptureRecordProto {
+        let mut proto = CaptureRecordProto::new();
+        proto.set_timestamp(self.timestamp);
+        proto.set_direction(match self.direction {
+            Direction::Inbound => DirectionProto::INBOUND,
+            Direction::Outbound => DirectionProto::OUTBOUND,
+        });
+        proto.set_peer_id(self.peer_id.clone());
+        proto.set_message_type(self.message_type.clone());
+        proto.set_payload(self.payload.clone());
+        proto
+    }
+}
+
+/// Displays the record on a single line, with the circuit, sender and recipient of circuit and
+/// admin direct messages.
+impl fmt::Display for CaptureRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}.{:06} {} {} {} ({} bytes)",
+            self.timestamp / 1_000_000,
+            self.timestamp % 1_000_000,
+            self.direction,
+            self.peer_id,
+            self.message_type,
+            self.payload.len()
+        )?;
+
+        if let Some((circuit, sender, recipient)) = direct_message_routing(&self.payload) {
+            write!(
+                f,
+                " circuit={} sender={} recipient={}",
+                circuit, sender, recipient
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Decodes the type of the given `NetworkMessage` bytes, including the type of the message it
+/// contains, if any; for example, "NETWORK_HEARTBEAT" or "CIRCUIT/CIRCUIT_DIRECT_MESSAGE".
+pub fn message_type(payload: &[u8]) -> String {
+    let network_msg: NetworkMessage = match protobuf::parse_from_bytes(payload) {
+        Ok(msg) => msg,
+        Err(_) => return "INVALID".into(),
+    };
+
+    let network_type = network_msg.get_message_type();
+    let inner_type = match network_type {
+        NetworkMessageType::CIRCUIT => {
+            protobuf::parse_from_bytes::<CircuitMessage>(network_msg.get_payload())
+                .map(|msg| format!("{:?}", msg.get_message_type()))
+        }
+        NetworkMessageType::AUTHORIZATION => {
+            protobuf::parse_from_bytes::<AuthorizationMessage>(network_msg.get_payload())
+                .map(|msg| format!("{:?}", msg.get_message_type()))
+        }
+        _ => return format!("{:?}", network_type),
+    };
+
+    format!(
+        "{:?}/{}",
+        network_type,
+        inner_type.unwrap_or_else(|_| "INVALID".into())
+    )
+}
+
+/// Returns the circuit, sender and recipient of the circuit or admin direct message in the given
+/// `NetworkMessage` bytes, if it contains one.
+fn direct_message_routing(payload: &[u8]) -> Option<(String, String, String)> {
+    let network_msg: NetworkMessage = protobuf::parse_from_bytes(payload).ok()?;
+    if network_msg.get_message_type() != NetworkMessageType::CIRCUIT {
+        return None;
+    }
+
+    let circuit_msg: CircuitMessage = protobuf::parse_from_bytes(network_msg.get_payload()).ok()?;
+    match circuit_msg.get_message_type() {
+        CircuitMessageType::CIRCUIT_DIRECT_MESSAGE => {
+            let mut msg: CircuitDirectMessage =
+                protobuf::parse_from_bytes(circuit_msg.get_payload()).ok()?;
+            Some((msg.take_circuit(), msg.take_sender(), msg.take_recipient()))
+        }
+        CircuitMessageType::ADMIN_DIRECT_MESSAGE => {
+            let mut msg: AdminDirectMessage =
+                protobuf::parse_from_bytes(circuit_msg.get_payload()).ok()?;
+            Some((msg.take_circuit(), msg.take_sender(), msg.take_recipient()))
+        }
+        _ => None,
+    }
+}
+
+/// Writes capture records.
+pub struct CaptureWriter {
+    writer: Box<dyn Write + Send>,
+    path: Option<PathBuf>,
+    size: u64,
+    max_size: Option<u64>,
+}
+
+impl CaptureWriter {
+    /// Creates a writer that appends records to the given writer.
+    pub fn new(writer: Box<dyn Write + Send>) -> Self {
+        CaptureWriter {
+            writer,
+            path: None,
+            size: 0,
+            max_size: None,
+        }
+    }
+
+    /// Creates a writer that appends records to the capture file at the given path, creating it if
+    /// it does not exist. A new file is only readable and writable by its owner.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, CaptureError> {
+        let path = path.as_ref().to_path_buf();
+        let file = open_capture_file(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(CaptureWriter {
+            writer: Box::new(BufWriter::new(file)),
+            path: Some(path),
+            size,
+            max_size: None,
+        })
+    }
+
+    /// Rotates the capture file once writing a record would make it larger than the given number
+    /// of bytes: the file is renamed with a ".1" suffix, replacing the previous one, and a new
+    /// file is started. Only applies to writers created with `create`.
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Writes the given record. Records are buffered until `flush` is called.
+    pub fn write(&mut self, record: &CaptureRecord) -> Result<(), CaptureError> {
+        let bytes = record
+            .to_proto()
+            .write_to_bytes()
+            .map_err(|err| CaptureError::InvalidRecord(err.to_string()))?;
+
+        let length = 4 + bytes.len() as u64;
+        if let (Some(path), Some(max_size)) = (&self.path, self.max_size) {
+            if self.size > 0 && self.size + length > max_size {
+                self.writer.flush()?;
+                let mut rotated = path.clone().into_os_string();
+                rotated.push(".1");
+                fs::rename(path, rotated)?;
+                self.writer = Box::new(BufWriter::new(open_capture_file(path)?));
+                self.size = 0;
+            }
+        }
+
+        self.writer.write_u32::<BigEndian>(bytes.len() as u32)?;
+        self.writer.write_all(&bytes)?;
+        self.size += length;
+        Ok(())
+    }
+
+    /// Flushes the records written so far, so that the capture is complete if the process stops.
+    pub fn flush(&mut self) -> Result<(), CaptureError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+fn open_capture_file(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(0o600)
+        .open(path)
+}
+
+/// Writes capture records on a separate thread, so that capturing a message does not wait for it
+/// to be written.
+pub(crate) struct CaptureThread {
+    sender: Sender<CaptureRecord>,
+    join_handle: JoinHandle<()>,
+}
+
+impl CaptureThread {
+    /// Starts a thread that writes the records it is sent to the given writer, until it is
+    /// stopped or writing fails.
+    pub(crate) fn start(writer: CaptureWriter) -> Result<Self, CaptureError> {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let join_handle = thread::Builder::new()
+            .name("Network Capture Writer".into())
+            .spawn(move || {
+                if let Err(err) = write_records(writer, receiver) {
+                    error!("Unable to capture message, stopping capture: {}", err);
+                }
+            })?;
+
+        Ok(CaptureThread {
+            sender,
+            join_handle,
+        })
+    }
+
+    /// Sends the given record to be written. Records sent after writing has failed are dropped.
+    pub(crate) fn capture(&self, record: CaptureRecord) {
+        let _ = self.sender.send(record);
+    }
+
+    /// Stops the thread once it has written the records sent to it, and waits for it to finish.
+    pub(crate) fn stop(self) {
+        drop(self.sender);
+        if self.join_handle.join().is_err() {
+            error!("Network capture writer thread panicked");
+        }
+    }
+}
+
+/// Writes the records received until the channel is closed, flushing whenever there are no more
+/// records waiting.
+fn write_records(
+    mut writer: CaptureWriter,
+    receiver: Receiver<CaptureRecord>,
+) -> Result<(), CaptureError> {
+    while let Ok(record) = receiver.recv() {
+        writer.write(&record)?;
+        loop {
+            match receiver.try_recv() {
+                Ok(record) => writer.write(&record)?,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return writer.flush(),
+            }
+        }
+        writer.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Reads capture records, as an iterator.
+pub struct CaptureReader<R: Read> {
+    reader: R,
+}
+
+impl CaptureReader<BufReader<File>> {
+    /// Opens the capture file at the given path.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, CaptureError> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Creates a reader of the records in the given reader.
+    pub fn new(reader: R) -> Self {
+        CaptureReader { reader }
+    }
+
+    fn read_record(&mut self) -> Result<Option<CaptureRecord>, CaptureError> {
+        let length = match self.reader.read_u32::<BigEndian>() {
+            Ok(length) => length,
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut bytes = vec![];
+        self.reader
+            .by_ref()
+            .take(u64::from(length))
+            .read_to_end(&mut bytes)?;
+        if bytes.len() != length as usize {
+            return Err(CaptureError::InvalidRecord(
+                "capture file ends part way through a record".into(),
+            ));
+        }
+
+        let proto: CaptureRecordProto = protobuf::parse_from_bytes(&bytes)
+            .map_err(|err| CaptureError::InvalidRecord(err.to_string()))?;
+        CaptureRecord::from_proto(proto).map(Some)
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<CaptureRecord, CaptureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+/// Replays the inbound messages in the given records against the given dispatcher, in the same
+/// way that a node's network loop dispatches the messages it receives. Outbound messages are
+/// skipped; the messages sent by the dispatcher's handlers go to the dispatcher's sender.
+///
+/// If `preserve_timing` is true, the time between messages is the same as when they were
+/// captured; otherwise, the messages are replayed as fast as possible.
+///
+/// Messages the dispatcher is unable to handle are logged and skipped. Returns the number of
+/// messages that were handled.
+pub fn replay<I>(
+    records: I,
+    dispatcher: &Dispatcher<NetworkMessageType>,
+    preserve_timing: bool,
+) -> Result<usize, CaptureError>
+where
+    I: IntoIterator<Item = Result<CaptureRecord, CaptureError>>,
+{
+    let mut handled = 0;
+    let mut previous_timestamp = None;
+
+    for record in records {
+        let record = record?;
+        if record.direction() != Direction::Inbound {
+            continue;
+        }
+
+        if preserve_timing {
+            if let Some(previous) = previous_timestamp {
+                thread::sleep(Duration::from_micros(
+                    record.timestamp().saturating_sub(previous),
+                ));
+            }
+            previous_timestamp = Some(record.timestamp());
+        }
+
+        let mut msg: NetworkMessage = match protobuf::parse_from_bytes(record.payload()) {
+            Ok(msg) => msg,
+            Err(err) => {
+                warn!("Skipping invalid network message in capture: {}", err);
+                continue;
+            }
+        };
+
+        match dispatcher.dispatch(
+            record.peer_id(),
+            &msg.get_message_type(),
+            msg.take_payload(),
+        ) {
+            Ok(()) => handled += 1,
+            Err(err) => warn!("Unable to replay {}: {}", record, err),
+        }
+    }
+
+    Ok(handled)
+}
+
+#[derive(Debug)]
+pub enum CaptureError {
+    IoError(io::Error),
+    InvalidRecord(String),
+}
+
+impl Error for CaptureError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CaptureError::IoError(err) => Some(err),
+            CaptureError::InvalidRecord(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CaptureError::IoError(err) => write!(f, "unable to access capture: {}", err),
+            CaptureError::InvalidRecord(msg) => write!(f, "invalid capture record: {}", msg),
+        }
+    }
+}
+
+impl From<io::Error> for CaptureError {
+    fn from(err: io::Error) -> Self {
+        CaptureError::IoError(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::os::unix::fs::PermissionsExt;
+    use std::sync::mpsc::{self, channel};
+
+    use tempdir::TempDir;
+
+    use crate::channel::{SendError, Sender};
+    use crate::mesh::Mesh;
+    use crate::network::dispatch::{DispatchError, Handler, MessageContext};
+    use crate::network::sender::SendRequest;
+    use crate::network::Network;
+    use crate::protos::network::NetworkEcho;
+    use crate::transport::inproc::InprocTransport;
+    use crate::transport::Transport;
+
+    fn circuit_direct_message() -> Vec<u8> {
+        let mut direct_msg = CircuitDirectMessage::new();
+        direct_msg.set_circuit("alpha".into());
+        direct_msg.set_sender("abcd".into());
+        direct_msg.set_recipient("efgh".into());
+
+        let mut circuit_msg = CircuitMessage::new();
+        circuit_msg.set_message_type(CircuitMessageType::CIRCUIT_DIRECT_MESSAGE);
+        circuit_msg.set_payload(direct_msg.write_to_bytes().unwrap());
+
+        let mut network_msg = NetworkMessage::new();
+        network_msg.set_message_type(NetworkMessageType::CIRCUIT);
+        network_msg.set_payload(circuit_msg.write_to_bytes().unwrap());
+        network_msg.write_to_bytes().unwrap()
+    }
+
+    fn echo_message(payload: &[u8]) -> Vec<u8> {
+        let mut echo = NetworkEcho::new();
+        echo.set_payload(payload.to_vec());
+
+        let mut network_msg = NetworkMessage::new();
+        network_msg.set_message_type(NetworkMessageType::NETWORK_ECHO);
+        network_msg.set_payload(echo.write_to_bytes().unwrap());
+        network_msg.write_to_bytes().unwrap()
+    }
+
+    /// Test that message types are decoded, including the types of the messages they contain, and
+    /// that the routing of direct messages is displayed.
+    #[test]
+    fn test_message_type() {
+        assert_eq!(message_type(&echo_message(b"hello")), "NETWORK_ECHO");
+        assert_eq!(
+            message_type(&circuit_direct_message()),
+            "CIRCUIT/CIRCUIT_DIRECT_MESSAGE"
+        );
+        assert_eq!(message_type(&[0xff, 0xff]), "INVALID");
+
+        let record = CaptureRecord::new(Direction::Outbound, "peer", &circuit_direct_message());
+        let display = record.to_string();
+        assert!(display.contains("-> peer CIRCUIT/CIRCUIT_DIRECT_MESSAGE"));
+        assert!(display.ends_with("circuit=alpha sender=abcd recipient=efgh"));
+    }
+
+    /// Test that records written to a capture file are read back in order, and that records are
+    /// appended to an existing file.
+    #[test]
+    fn test_write_and_read() {
+        let temp_dir = TempDir::new("test_write_and_read").expect("Failed to create temp dir");
+        let path = temp_dir.path().join("capture.bin");
+
+        let first = CaptureRecord::new(Direction::Inbound, "peer", &echo_message(b"one"));
+        let second = CaptureRecord::new(Direction::Outbound, "peer", &circuit_direct_message());
+        let third = CaptureRecord::new(Direction::Inbound, "other", &echo_message(b"three"));
+
+        let mut writer = CaptureWriter::create(&path).expect("Unable to create capture");
+        writer.write(&first).expect("Unable to write record");
+        writer.write(&second).expect("Unable to write record");
+        writer.flush().expect("Unable to flush capture");
+        drop(writer);
+
+        let mut writer = CaptureWriter::create(&path).expect("Unable to create capture");
+        writer.write(&third).expect("Unable to write record");
+        writer.flush().expect("Unable to flush capture");
+        drop(writer);
+
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+
+        let records = CaptureReader::open(&path)
+            .expect("Unable to open capture")
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Unable to read records");
+        assert_eq!(records, vec![first, second, third]);
+    }
+
+    /// Test that a capture file is rotated once it would exceed its maximum size, keeping the
+    /// previous file.
+    #[test]
+    fn test_rotate() {
+        let temp_dir = TempDir::new("test_rotate").expect("Failed to create temp dir");
+        let path = temp_dir.path().join("capture.bin");
+        let rotated_path = temp_dir.path().join("capture.bin.1");
+
+        let records = (0..3)
+            .map(|i| CaptureRecord::new(Direction::Inbound, "peer", &echo_message(&[i; 100])))
+            .collect::<Vec<_>>();
+
+        // Each record is larger than half of the maximum, so every record starts a new file
+        let mut writer = CaptureWriter::create(&path)
+            .expect("Unable to create capture")
+            .with_max_size(200);
+        for record in &records {
+            writer.write(record).expect("Unable to write record");
+        }
+        writer.flush().expect("Unable to flush capture");
+
+        let read = |path: &Path| {
+            CaptureReader::open(path)
+                .expect("Unable to open capture")
+                .collect::<Result<Vec<_>, _>>()
+                .expect("Unable to read records")
+        };
+        assert_eq!(read(&rotated_path), vec![records[1].clone()]);
+        assert_eq!(read(&path), vec![records[2].clone()]);
+    }
+
+    /// Test that a record cut short is reported as invalid.
+    #[test]
+    fn test_read_truncated() {
+        let record = CaptureRecord::new(Direction::Inbound, "peer", &echo_message(b"one"));
+        let mut bytes = vec![];
+        bytes.write_u32::<BigEndian>(100).unwrap();
+        bytes.extend(record.to_proto().write_to_bytes().unwrap());
+
+        let mut reader = CaptureReader::new(&bytes[..]);
+        match reader.next() {
+            Some(Err(CaptureError::InvalidRecord(_))) => (),
+            res => panic!("Expected InvalidRecord, got {:?}", res),
+        }
+    }
+
+    struct EchoHandler {
+        tx: mpsc::Sender<(String, Vec<u8>)>,
+    }
+
+    impl Handler<NetworkMessageType, NetworkEcho> for EchoHandler {
+        fn handle(
+            &self,
+            mut message: NetworkEcho,
+            message_context: &MessageContext<NetworkMessageType>,
+            _: &dyn Sender<SendRequest>,
+        ) -> Result<(), DispatchError> {
+            self.tx
+                .send((
+                    message_context.source_peer_id().to_string(),
+                    message.take_payload(),
+                ))
+                .expect("Unable to send echo");
+            Ok(())
+        }
+    }
+
+    /// Test that the messages a network sends and receives are captured, and that replaying the
+    /// capture dispatches the received messages, but not the sent ones.
+    #[test]
+    fn test_capture_and_replay() {
+        let temp_dir = TempDir::new("test_capture_and_replay").expect("Failed to create temp dir");
+        let path = temp_dir.path().join("capture.bin");
+
+        let mut transport = InprocTransport::default();
+        let mut listener = transport
+            .listen("inproc://capture")
+            .expect("Unable to listen");
+
+        let network = Network::new(Mesh::new(5, 5), 0).expect("Unable to create network");
+        network.start_capture(CaptureWriter::create(&path).expect("Unable to create capture"));
+
+        let connection = transport
+            .connect("inproc://capture")
+            .expect("Unable to connect");
+        network
+            .add_peer("remote".into(), connection)
+            .expect("Unable to add peer");
+        let mut remote = listener.accept().expect("Unable to accept");
+
+        network
+            .send("remote", &echo_message(b"sent"))
+            .expect("Unable to send");
+        remote
+            .send(&echo_message(b"received"))
+            .expect("Unable to send");
+        network
+            .recv_timeout(Duration::from_secs(5))
+            .expect("Unable to receive");
+        network.stop_capture();
+
+        let records = CaptureReader::open(&path)
+            .expect("Unable to open capture")
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Unable to read records");
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].direction(), Direction::Outbound);
+        assert_eq!(records[0].payload(), &echo_message(b"sent")[..]);
+        assert_eq!(records[1].direction(), Direction::Inbound);
+        assert_eq!(records[1].peer_id(), "remote");
+        assert_eq!(records[1].message_type(), "NETWORK_ECHO");
+
+        let (tx, rx) = channel();
+        let mut dispatcher = Dispatcher::new(Box::new(NoopSender));
+        dispatcher.set_handler(
+            NetworkMessageType::NETWORK_ECHO,
+            Box::new(EchoHandler { tx }),
+        );
+
+        let handled = replay(
+            CaptureReader::open(&path).expect("Unable to open capture"),
+            &dispatcher,
+            true,
+        )
+        .expect("Unable to replay capture");
+        assert_eq!(handled, 1);
+        assert_eq!(
+            rx.try_recv().expect("No message replayed"),
+            ("remote".to_string(), b"received".to_vec())
+        );
+        assert!(rx.try_recv().is_err());
+    }
+
+    struct NoopSender;
+
+    impl Sender<SendRequest> for NoopSender {
+        fn send(&self, _: SendRequest) -> Result<(), SendError> {
+            Ok(())
+        }
+
+        fn box_clone(&self) -> Box<dyn Sender<SendRequest>> {
+            Box::new(NoopSender)
+        }
+    }
+}
@@ -19,18 +19,20 @@ mod pacemaker;
 use std;
 use std::cmp::min;
 use std::collections::HashMap;
-use std::sync::mpsc::{sync_channel, SyncSender};
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender};
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 pub use error::ConnectionManagerError;
 pub use notification::{ConnectionManagerNotification, NotificationIter};
 use pacemaker::Pacemaker;
 use protobuf::Message;
+use rand::Rng;
 
 use crate::matrix::{MatrixLifeCycle, MatrixSender};
+use crate::network::peer::create_connect_request;
 use crate::protos::network::{NetworkHeartbeat, NetworkMessage, NetworkMessageType};
-use crate::transport::Transport;
+use crate::transport::{ConnectError, Connection, Transport};
 
 const DEFAULT_HEARTBEAT_INTERVAL: u64 = 10;
 const CHANNEL_CAPACITY: usize = 15;
@@ -43,6 +45,8 @@ enum CmMessage {
     Subscribe(SyncSender<ConnectionManagerNotification>),
     Request(CmRequest),
     SendHeartbeats,
+    // Sent by the dialer once the result of a connection attempt is available
+    Dialed,
 }
 
 #[derive(Clone)]
@@ -51,13 +55,40 @@ enum CmRequest {
         endpoint: String,
         sender: SyncSender<Result<(), ConnectionManagerError>>,
     },
+    AddPersistentConnection {
+        endpoint: String,
+        sender: SyncSender<Result<(), ConnectionManagerError>>,
+    },
     RemoveConnection {
         endpoint: String,
         sender: SyncSender<Result<Option<String>, ConnectionManagerError>>,
     },
+    ForceRemoveConnection {
+        endpoint: String,
+        sender: SyncSender<Result<Option<String>, ConnectionManagerError>>,
+    },
     ListConnections {
         sender: SyncSender<Result<Vec<String>, ConnectionManagerError>>,
     },
+    ConnectionStatus {
+        endpoint: String,
+        sender: SyncSender<Result<Option<ConnectionStatus>, ConnectionManagerError>>,
+    },
+}
+
+/// The state of a connection requested from the connection manager.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConnectionStatus {
+    /// An attempt to connect to the endpoint is in progress.
+    Connecting,
+    /// The endpoint is connected.
+    Connected,
+    /// The last attempt to connect to the endpoint failed, and another will be made once the
+    /// backoff delay has passed.
+    BackingOff,
+    /// The endpoint could not be reconnected within the maximum number of retry attempts, and
+    /// will not be retried.
+    Failed,
 }
 
 pub struct ConnectionManager<T: 'static, U: 'static>
@@ -67,7 +98,9 @@ where
 {
     pacemaker: Pacemaker,
     connection_state: Option<ConnectionState<T, U>>,
+    dialer: Option<Dialer>,
     join_handle: Option<thread::JoinHandle<()>>,
+    dialer_join_handle: Option<thread::JoinHandle<()>>,
     sender: Option<SyncSender<CmMessage>>,
     shutdown_handle: Option<ShutdownHandle>,
 }
@@ -86,28 +119,58 @@ where
     ) -> Self {
         let heartbeat = heartbeat_interval.unwrap_or(DEFAULT_HEARTBEAT_INTERVAL);
         let retry_frequency = maximum_retry_frequency.unwrap_or(DEFAULT_MAXIMUM_RETRY_FREQUENCY);
+        let (dial_sender, dial_requests) = channel();
+        let (dial_results, dial_receiver) = channel();
         let connection_state = Some(ConnectionState::new(
             life_cycle,
             matrix_sender,
-            transport,
+            dial_sender,
+            dial_receiver,
             retry_frequency,
         ));
+        let dialer = Some(Dialer {
+            transport,
+            requests: dial_requests,
+            results: dial_results,
+        });
         let pacemaker = Pacemaker::new(heartbeat);
 
         Self {
             pacemaker,
             connection_state,
+            dialer,
             join_handle: None,
+            dialer_join_handle: None,
             sender: None,
             shutdown_handle: None,
         }
     }
 
+    /// Sets the number of consecutive failed attempts to reconnect after which a connection is
+    /// given up on and marked as failed. Persistent connections are retried indefinitely. By
+    /// default, all connections are retried indefinitely.
+    pub fn with_maximum_retry_attempts(mut self, maximum_retry_attempts: u64) -> Self {
+        if let Some(state) = self.connection_state.as_mut() {
+            state.maximum_retry_attempts = Some(maximum_retry_attempts);
+        }
+        self
+    }
+
     pub fn start(&mut self) -> Result<Connector, ConnectionManagerError> {
         let (sender, recv) = sync_channel(CHANNEL_CAPACITY);
         let mut state = self.connection_state.take().ok_or_else(|| {
             ConnectionManagerError::StartUpError("Service has already started".into())
         })?;
+        let dialer = self.dialer.take().ok_or_else(|| {
+            ConnectionManagerError::StartUpError("Service has already started".into())
+        })?;
+
+        // Connections are made on a separate thread, so that the connection manager keeps
+        // handling requests and sending heartbeats while an endpoint is slow to connect to
+        let dialer_sender = sender.clone();
+        let dialer_join_handle = thread::Builder::new()
+            .name("Connection Manager Dialer".into())
+            .spawn(move || dialer.run(dialer_sender))?;
 
         let join_handle = thread::Builder::new()
             .name("Connection Manager".into())
@@ -125,6 +188,7 @@ where
                         Ok(CmMessage::SendHeartbeats) => {
                             send_heartbeats(&mut state, &mut subscribers)
                         }
+                        Ok(CmMessage::Dialed) => state.handle_dial_results(&mut subscribers),
                         Err(_) => {
                             warn!("All senders have disconnected");
                             break;
@@ -136,6 +200,7 @@ where
         self.pacemaker
             .start(CmMessage::SendHeartbeats, sender.clone())?;
         self.join_handle = Some(join_handle);
+        self.dialer_join_handle = Some(dialer_join_handle);
         self.shutdown_handle = Some(ShutdownHandle {
             sender: sender.clone(),
             pacemaker_shutdown_handle: self.pacemaker.shutdown_handle().unwrap(),
@@ -164,6 +229,16 @@ where
                 err
             );
         }
+
+        // the dialer stops once the connection manager thread has dropped its requests channel
+        if let Some(dialer_join_handle) = self.dialer_join_handle {
+            if let Err(err) = dialer_join_handle.join() {
+                error!(
+                    "Connection manager dialer thread did not shutdown correctly: {:?}",
+                    err
+                );
+            }
+        }
     }
 
     pub fn shutdown_and_wait(self) {
@@ -203,6 +278,36 @@ impl Connector {
         })?
     }
 
+    /// Requests a connection that the connection manager keeps alive until it is removed.
+    ///
+    /// Unlike `request_connection`, this succeeds even if the endpoint cannot be connected to
+    /// yet; the connection is retried with an exponential backoff until it is established, and is
+    /// reconnected whenever it is lost. Each connection the connection manager establishes for
+    /// this request is authorized with the remote node. This returns once the first attempt to
+    /// connect has finished.
+    pub fn request_persistent_connection(
+        &self,
+        endpoint: &str,
+    ) -> Result<(), ConnectionManagerError> {
+        let (sender, recv) = sync_channel(1);
+        self.sender
+            .send(CmMessage::Request(CmRequest::AddPersistentConnection {
+                sender,
+                endpoint: endpoint.to_string(),
+            }))
+            .map_err(|_| {
+                ConnectionManagerError::SendMessageError(
+                    "The connection manager is no longer running".into(),
+                )
+            })?;
+
+        recv.recv().map_err(|_| {
+            ConnectionManagerError::SendMessageError(
+                "The connection manager is no longer running".into(),
+            )
+        })?
+    }
+
     // Removes a connection
     //
     // # Returns
@@ -235,6 +340,33 @@ impl Connector {
         })?
     }
 
+    /// Removes the connection to the endpoint, regardless of how many times it was requested, so
+    /// that it is no longer kept alive.
+    ///
+    /// Returns the endpoint, if the connection exists; None, otherwise.
+    pub fn force_remove_connection(
+        &self,
+        endpoint: &str,
+    ) -> Result<Option<String>, ConnectionManagerError> {
+        let (sender, recv) = sync_channel(1);
+        self.sender
+            .send(CmMessage::Request(CmRequest::ForceRemoveConnection {
+                sender,
+                endpoint: endpoint.to_string(),
+            }))
+            .map_err(|_| {
+                ConnectionManagerError::SendMessageError(
+                    "The connection manager is no longer running".into(),
+                )
+            })?;
+
+        recv.recv().map_err(|_| {
+            ConnectionManagerError::SendMessageError(
+                "The connection manager is no longer running".into(),
+            )
+        })?
+    }
+
     pub fn subscribe(&self) -> Result<NotificationIter, ConnectionManagerError> {
         let (send, recv) = sync_channel(CHANNEL_CAPACITY);
         match self.sender.send(CmMessage::Subscribe(send)) {
@@ -261,6 +393,31 @@ impl Connector {
             )
         })?
     }
+
+    /// Returns the status of the connection to the given endpoint, or None if no connection to it
+    /// has been requested.
+    pub fn connection_status(
+        &self,
+        endpoint: &str,
+    ) -> Result<Option<ConnectionStatus>, ConnectionManagerError> {
+        let (sender, recv) = sync_channel(1);
+        self.sender
+            .send(CmMessage::Request(CmRequest::ConnectionStatus {
+                sender,
+                endpoint: endpoint.to_string(),
+            }))
+            .map_err(|_| {
+                ConnectionManagerError::SendMessageError(
+                    "The connection manager is no longer running".into(),
+                )
+            })?;
+
+        recv.recv().map_err(|_| {
+            ConnectionManagerError::SendMessageError(
+                "The connection manager is no longer running".into(),
+            )
+        })?
+    }
 }
 
 #[derive(Clone)]
@@ -281,12 +438,40 @@ impl ShutdownHandle {
 
 #[derive(Clone, Debug)]
 struct ConnectionMetadata {
-    id: usize,
+    // The id of the connection in the life cycle; None while a persistent connection has not yet
+    // been established.
+    id: Option<usize>,
     endpoint: String,
     ref_count: u64,
-    reconnecting: bool,
+    persistent: bool,
+    status: ConnectionStatus,
+    retry_attempts: u64,
     retry_frequency: u64,
-    last_connection_attempt: Instant,
+    next_connection_attempt: Instant,
+}
+
+/// The result of an attempt to connect to an endpoint.
+type DialResult = (String, Result<Box<dyn Connection>, ConnectError>);
+
+/// Connects to the endpoints requested by the connection manager, one at a time, and sends the
+/// results back to it.
+struct Dialer {
+    transport: Box<dyn Transport + Send>,
+    requests: Receiver<String>,
+    results: Sender<DialResult>,
+}
+
+impl Dialer {
+    fn run(mut self, cm_sender: SyncSender<CmMessage>) {
+        for endpoint in self.requests.iter() {
+            let result = self.transport.connect(&endpoint);
+            if self.results.send((endpoint, result)).is_err()
+                || cm_sender.send(CmMessage::Dialed).is_err()
+            {
+                break;
+            }
+        }
+    }
 }
 
 struct ConnectionState<T, U>
@@ -295,10 +480,14 @@ where
     U: MatrixSender,
 {
     connections: HashMap<String, ConnectionMetadata>,
+    // The senders waiting for the first attempt to connect to a newly requested endpoint
+    pending_requests: HashMap<String, Vec<SyncSender<Result<(), ConnectionManagerError>>>>,
     life_cycle: T,
     matrix_sender: U,
-    transport: Box<dyn Transport>,
+    dial_sender: Sender<String>,
+    dial_receiver: Receiver<DialResult>,
     maximum_retry_frequency: u64,
+    maximum_retry_attempts: Option<u64>,
 }
 
 impl<T, U> ConnectionState<T, U>
@@ -309,44 +498,69 @@ where
     fn new(
         life_cycle: T,
         matrix_sender: U,
-        transport: Box<dyn Transport + Send>,
+        dial_sender: Sender<String>,
+        dial_receiver: Receiver<DialResult>,
         maximum_retry_frequency: u64,
     ) -> Self {
         Self {
             life_cycle,
             matrix_sender,
-            transport,
+            dial_sender,
+            dial_receiver,
             connections: HashMap::new(),
+            pending_requests: HashMap::new(),
             maximum_retry_frequency,
+            maximum_retry_attempts: None,
         }
     }
 
-    fn add_connection(&mut self, endpoint: &str) -> Result<(), ConnectionManagerError> {
+    /// Adds a reference to the connection to the endpoint. If there is no connection to it yet,
+    /// one is requested from the dialer, and the result is sent once the attempt has finished.
+    fn add_connection(
+        &mut self,
+        endpoint: &str,
+        persistent: bool,
+        sender: SyncSender<Result<(), ConnectionManagerError>>,
+    ) {
         if let Some(meta) = self.connections.get_mut(endpoint) {
             meta.ref_count += 1;
-        } else {
-            let connection = self.transport.connect(endpoint).map_err(|err| {
-                ConnectionManagerError::ConnectionCreationError(format!("{:?}", err))
-            })?;
+            if persistent {
+                meta.persistent = true;
+                // a connection that was given up on is retried now that it must be kept alive
+                if meta.status == ConnectionStatus::Failed {
+                    meta.status = ConnectionStatus::BackingOff;
+                    meta.next_connection_attempt = Instant::now();
+                }
+            }
 
-            let id = self.life_cycle.add(connection).map_err(|err| {
-                ConnectionManagerError::ConnectionCreationError(format!("{:?}", err))
-            })?;
+            if sender.send(Ok(())).is_err() {
+                warn!("connector dropped before receiving result of add connection");
+            }
+            return;
+        }
 
-            self.connections.insert(
-                endpoint.to_string(),
-                ConnectionMetadata {
-                    id,
-                    endpoint: endpoint.to_string(),
-                    ref_count: 1,
-                    reconnecting: false,
-                    retry_frequency: INITIAL_RETRY_FREQUENCY,
-                    last_connection_attempt: Instant::now(),
-                },
-            );
-        };
+        self.connections.insert(
+            endpoint.to_string(),
+            ConnectionMetadata {
+                id: None,
+                endpoint: endpoint.to_string(),
+                ref_count: 1,
+                persistent,
+                status: ConnectionStatus::Connecting,
+                retry_attempts: 0,
+                retry_frequency: self.initial_retry_frequency(),
+                next_connection_attempt: Instant::now(),
+            },
+        );
+        self.pending_requests
+            .entry(endpoint.to_string())
+            .or_insert_with(Vec::new)
+            .push(sender);
 
-        Ok(())
+        if let Err(err) = self.dial(endpoint) {
+            self.connections.remove(endpoint);
+            self.reply_pending(endpoint, Err(err));
+        }
     }
 
     fn remove_connection(
@@ -362,68 +576,240 @@ where
 
         if meta.ref_count < 1 {
             self.connections.remove(endpoint);
-            self.life_cycle.remove(meta.id).map_err(|err| {
-                ConnectionManagerError::ConnectionRemovalError(format!("{:?}", err))
-            })?;
+            if let Some(id) = meta.id {
+                self.life_cycle.remove(id).map_err(|err| {
+                    ConnectionManagerError::ConnectionRemovalError(format!("{:?}", err))
+                })?;
+            }
         }
 
         Ok(Some(meta))
     }
 
-    fn reconnect(
+    fn force_remove_connection(
         &mut self,
         endpoint: &str,
-        subscribers: &mut Vec<SyncSender<ConnectionManagerNotification>>,
-    ) -> Result<(), ConnectionManagerError> {
-        let mut meta = if let Some(meta) = self.connections.get_mut(endpoint) {
-            meta.clone()
+    ) -> Result<Option<ConnectionMetadata>, ConnectionManagerError> {
+        let meta = match self.connections.remove(endpoint) {
+            Some(meta) => meta,
+            None => return Ok(None),
+        };
+
+        self.reply_pending(
+            endpoint,
+            Err(ConnectionManagerError::ConnectionCreationError(
+                "Connection was removed before it was established".into(),
+            )),
+        );
+
+        if let Some(id) = meta.id {
+            self.life_cycle.remove(id).map_err(|err| {
+                ConnectionManagerError::ConnectionRemovalError(format!("{:?}", err))
+            })?;
+        }
+
+        Ok(Some(meta))
+    }
+
+    /// Removes the connection to the endpoint, if any, and requests a new one from the dialer.
+    fn reconnect(&mut self, endpoint: &str) -> Result<(), ConnectionManagerError> {
+        let meta = if let Some(meta) = self.connections.get_mut(endpoint) {
+            meta
         } else {
             return Err(ConnectionManagerError::ConnectionRemovalError(
                 "Cannot reconnect to endpoint without metadata".into(),
             ));
         };
 
-        if let Ok(connection) = self.transport.connect(endpoint) {
-            // remove old mesh id, this may happen before reconnection is attempted
-            if self.life_cycle.remove(meta.id).is_err() {
+        // remove old mesh id, this may happen before reconnection is attempted
+        if let Some(id) = meta.id.take() {
+            if self.life_cycle.remove(id).is_err() {
                 trace!(
                     "Connection was already removed from life_cycle: {}",
                     endpoint
                 );
             }
+        }
 
-            // add new connection to mesh
-            let id = self.life_cycle.add(connection).map_err(|err| {
-                ConnectionManagerError::ConnectionReconnectError(format!("{:?}", err))
-            })?;
+        meta.status = ConnectionStatus::Connecting;
+        meta.next_connection_attempt = Instant::now();
 
-            // replace mesh id and reset reconnecting fields
-            meta.id = id;
-            meta.reconnecting = false;
-            meta.retry_frequency = INITIAL_RETRY_FREQUENCY;
-            meta.last_connection_attempt = Instant::now();
-            self.connections.insert(endpoint.to_string(), meta);
+        self.dial(endpoint)
+    }
 
-            // Notify subscribers of success
-            notify_subscribers(
-                subscribers,
-                ConnectionManagerNotification::Connected {
-                    endpoint: endpoint.to_string(),
-                },
-            );
-        } else {
-            meta.reconnecting = true;
-            meta.retry_frequency = min(meta.retry_frequency * 2, self.maximum_retry_frequency);
-            meta.last_connection_attempt = Instant::now();
-            self.connections.insert(endpoint.to_string(), meta);
+    fn dial(&self, endpoint: &str) -> Result<(), ConnectionManagerError> {
+        self.dial_sender.send(endpoint.to_string()).map_err(|_| {
+            ConnectionManagerError::ConnectionCreationError(
+                "The connection manager's dialer is no longer running".into(),
+            )
+        })
+    }
+
+    fn handle_dial_results(
+        &mut self,
+        subscribers: &mut Vec<SyncSender<ConnectionManagerNotification>>,
+    ) {
+        while let Ok((endpoint, result)) = self.dial_receiver.try_recv() {
+            self.handle_dial_result(&endpoint, result, subscribers);
+        }
+    }
+
+    fn handle_dial_result(
+        &mut self,
+        endpoint: &str,
+        result: Result<Box<dyn Connection>, ConnectError>,
+        subscribers: &mut Vec<SyncSender<ConnectionManagerNotification>>,
+    ) {
+        // the connection may have been removed, or already re-established, while it was being
+        // dialed
+        let mut meta = match self.connections.get(endpoint) {
+            Some(meta) if meta.status == ConnectionStatus::Connecting => meta.clone(),
+            _ => {
+                if let Ok(mut connection) = result {
+                    if let Err(err) = connection.disconnect() {
+                        debug!("Unable to disconnect unused connection: {:?}", err);
+                    }
+                }
+                self.reply_pending(
+                    endpoint,
+                    Err(ConnectionManagerError::ConnectionCreationError(
+                        "Connection was removed before it was established".into(),
+                    )),
+                );
+                return;
+            }
+        };
+
+        // the first attempt to connect to an endpoint that is not kept alive is not retried; the
+        // requester is given the error instead
+        let first_attempt = self.pending_requests.contains_key(endpoint) && !meta.persistent;
+
+        let id = result
+            .map_err(|err| format!("{:?}", err))
+            .and_then(|connection| {
+                self.life_cycle
+                    .add(connection)
+                    .map_err(|err| format!("{:?}", err))
+            });
+
+        match id {
+            Ok(id) => {
+                // replace mesh id and reset reconnecting fields
+                meta.id = Some(id);
+                meta.status = ConnectionStatus::Connected;
+                meta.retry_attempts = 0;
+                meta.retry_frequency = self.initial_retry_frequency();
+                let persistent = meta.persistent;
+                self.connections.insert(endpoint.to_string(), meta);
+
+                // the remote node must authorize the new connection before it will accept
+                // messages on it; other connections are authorized by their requester
+                if persistent {
+                    if let Err(err) = self.authorize(id) {
+                        warn!("Unable to authorize connection to {}: {}", endpoint, err);
+                    }
+                }
+
+                self.reply_pending(endpoint, Ok(()));
+
+                if !first_attempt {
+                    // Notify subscribers of success
+                    notify_subscribers(
+                        subscribers,
+                        ConnectionManagerNotification::Connected {
+                            endpoint: endpoint.to_string(),
+                        },
+                    );
+                }
+            }
+            Err(err) if first_attempt => {
+                self.connections.remove(endpoint);
+                self.reply_pending(
+                    endpoint,
+                    Err(ConnectionManagerError::ConnectionCreationError(err)),
+                );
+            }
+            Err(err) => {
+                debug!("Unable to connect to {}: {}", endpoint, err);
+                meta.retry_attempts += 1;
+                let attempts = meta.retry_attempts;
+
+                let failed = !meta.persistent
+                    && self
+                        .maximum_retry_attempts
+                        .map(|maximum| attempts >= maximum)
+                        .unwrap_or(false);
+
+                if failed {
+                    meta.status = ConnectionStatus::Failed;
+                    self.connections.insert(endpoint.to_string(), meta);
+
+                    notify_subscribers(
+                        subscribers,
+                        ConnectionManagerNotification::Failed {
+                            endpoint: endpoint.to_string(),
+                            attempts,
+                        },
+                    );
+                } else {
+                    let retry_in = backoff_delay(meta.retry_frequency);
+                    meta.status = ConnectionStatus::BackingOff;
+                    meta.retry_frequency =
+                        min(meta.retry_frequency * 2, self.maximum_retry_frequency);
+                    meta.next_connection_attempt = Instant::now() + retry_in;
+                    self.connections.insert(endpoint.to_string(), meta);
+
+                    notify_subscribers(
+                        subscribers,
+                        ConnectionManagerNotification::BackingOff {
+                            endpoint: endpoint.to_string(),
+                            attempts,
+                            retry_in,
+                        },
+                    );
+                }
+
+                // a persistent connection is kept, even though it could not be established yet
+                self.reply_pending(endpoint, Ok(()));
+            }
         }
-        Ok(())
+    }
+
+    fn reply_pending(&mut self, endpoint: &str, result: Result<(), ConnectionManagerError>) {
+        for sender in self.pending_requests.remove(endpoint).unwrap_or_default() {
+            if sender.send(result.clone()).is_err() {
+                warn!("connector dropped before receiving result of add connection");
+            }
+        }
+    }
+
+    fn initial_retry_frequency(&self) -> u64 {
+        min(INITIAL_RETRY_FREQUENCY, self.maximum_retry_frequency)
+    }
+
+    fn authorize(&self, id: usize) -> Result<(), ConnectionManagerError> {
+        let connect_request = create_connect_request().map_err(|err| {
+            ConnectionManagerError::ConnectionReconnectError(format!(
+                "cannot create ConnectRequest message: {}",
+                err
+            ))
+        })?;
+
+        self.matrix_sender
+            .send(id, connect_request)
+            .map_err(|err| ConnectionManagerError::SendMessageError(err.to_string()))
     }
 
     fn connection_metadata(&self) -> HashMap<String, ConnectionMetadata> {
         self.connections.clone()
     }
 
+    fn connection_status(&self, endpoint: &str) -> Option<ConnectionStatus> {
+        self.connections
+            .get(endpoint)
+            .map(|meta| meta.status.clone())
+    }
+
     fn matrix_sender(&self) -> U {
         self.matrix_sender.clone()
     }
@@ -435,9 +821,10 @@ fn handle_request<T: MatrixLifeCycle, U: MatrixSender>(
 ) {
     match req {
         CmRequest::AddConnection { endpoint, sender } => {
-            if sender.send(state.add_connection(&endpoint)).is_err() {
-                warn!("connector dropped before receiving result of add connection");
-            }
+            state.add_connection(&endpoint, false, sender);
+        }
+        CmRequest::AddPersistentConnection { endpoint, sender } => {
+            state.add_connection(&endpoint, true, sender);
         }
         CmRequest::RemoveConnection { endpoint, sender } => {
             let response = state
@@ -448,6 +835,15 @@ fn handle_request<T: MatrixLifeCycle, U: MatrixSender>(
                 warn!("connector dropped before receiving result of remove connection");
             }
         }
+        CmRequest::ForceRemoveConnection { endpoint, sender } => {
+            let response = state
+                .force_remove_connection(&endpoint)
+                .map(|meta_opt| meta_opt.map(|meta| meta.endpoint));
+
+            if sender.send(response).is_err() {
+                warn!("connector dropped before receiving result of force remove connection");
+            }
+        }
         CmRequest::ListConnections { sender } => {
             if sender
                 .send(Ok(state
@@ -460,6 +856,11 @@ fn handle_request<T: MatrixLifeCycle, U: MatrixSender>(
                 warn!("connector dropped before receiving result of list connections");
             }
         }
+        CmRequest::ConnectionStatus { endpoint, sender } => {
+            if sender.send(Ok(state.connection_status(&endpoint))).is_err() {
+                warn!("connector dropped before receiving result of connection status");
+            }
+        }
     };
 }
 
@@ -470,6 +871,13 @@ fn notify_subscribers(
     subscribers.retain(|sender| sender.send(notification.clone()).is_ok());
 }
 
+/// Returns a random delay of between half of and the full retry frequency, so that nodes that
+/// lost their connections at the same time do not all retry them at once.
+fn backoff_delay(retry_frequency: u64) -> Duration {
+    let millis = retry_frequency * 1000;
+    Duration::from_millis(rand::thread_rng().gen_range(millis / 2, millis + 1))
+}
+
 fn send_heartbeats<T: MatrixLifeCycle, U: MatrixSender>(
     state: &mut ConnectionState<T, U>,
     subscribers: &mut Vec<SyncSender<ConnectionManagerNotification>>,
@@ -483,39 +891,45 @@ fn send_heartbeats<T: MatrixLifeCycle, U: MatrixSender>(
     };
 
     for (endpoint, metadata) in state.connection_metadata() {
-        if state
-            .matrix_sender()
-            .send(metadata.id, heartbeat_message.clone())
-            .is_err()
-        {
-            // if connection is already attempting reconnection, call reconnect
-            if metadata.reconnecting {
-                if metadata.last_connection_attempt.elapsed().as_secs() > metadata.retry_frequency {
-                    if let Err(err) = state.reconnect(&endpoint, subscribers) {
+        match metadata.status {
+            ConnectionStatus::Connected => {
+                let id = match metadata.id {
+                    Some(id) => id,
+                    None => continue,
+                };
+
+                if let Err(err) = state.matrix_sender().send(id, heartbeat_message.clone()) {
+                    error!(
+                        "failed to send heartbeat to {}: {:?} attempting reconnection",
+                        endpoint, err
+                    );
+                    notify_subscribers(
+                        subscribers,
+                        ConnectionManagerNotification::Disconnected {
+                            endpoint: endpoint.clone(),
+                        },
+                    );
+                    if let Err(err) = state.reconnect(&endpoint) {
                         error!("Reconnection attempt to {} failed: {:?}", endpoint, err);
                     }
                 }
-            } else {
-                info!("Sending heartbeat to {}", endpoint);
-                if let Err(err) = state
-                    .matrix_sender()
-                    .send(metadata.id, heartbeat_message.clone())
-                {
-                    error!(
-                        "failed to send heartbeat: {:?} attempting reconnection",
-                        err
-                    )
-                }
-                notify_subscribers(
-                    subscribers,
-                    ConnectionManagerNotification::Disconnected {
-                        endpoint: endpoint.clone(),
-                    },
-                );
-                if let Err(err) = state.reconnect(&endpoint, subscribers) {
-                    error!("Reconnection attempt to {} failed: {:?}", endpoint, err);
+            }
+            // if connection is already attempting reconnection, call reconnect once the backoff
+            // delay has passed
+            ConnectionStatus::BackingOff => {
+                if metadata.next_connection_attempt <= Instant::now() {
+                    notify_subscribers(
+                        subscribers,
+                        ConnectionManagerNotification::Connecting {
+                            endpoint: endpoint.clone(),
+                        },
+                    );
+                    if let Err(err) = state.reconnect(&endpoint) {
+                        error!("Reconnection attempt to {} failed: {:?}", endpoint, err);
+                    }
                 }
             }
+            ConnectionStatus::Connecting | ConnectionStatus::Failed => (),
         }
     }
 }
@@ -767,6 +1181,56 @@ pub mod tests {
         assert_eq!(None, endpoint_removed);
     }
 
+    /// Test that a force removed connection is removed even though it was requested more than
+    /// once.
+    #[test]
+    fn test_force_remove_connection() {
+        let mut transport = Box::new(RawTransport::default());
+        let mut listener = transport.listen("tcp://localhost:0").unwrap();
+        let endpoint = listener.endpoint();
+        let mesh = Mesh::new(512, 128);
+        let mesh_clone = mesh.clone();
+
+        thread::spawn(move || {
+            let conn = listener.accept().unwrap();
+            mesh_clone.add(conn).unwrap();
+        });
+
+        let mut cm = ConnectionManager::new(
+            mesh.get_life_cycle(),
+            mesh.get_sender(),
+            transport,
+            None,
+            None,
+        );
+        let connector = cm.start().unwrap();
+
+        connector
+            .request_connection(&endpoint)
+            .expect("A connection could not be created");
+        connector
+            .request_persistent_connection(&endpoint)
+            .expect("A connection could not be re-requested");
+
+        let endpoint_removed = connector
+            .force_remove_connection(&endpoint)
+            .expect("Unable to remove connection");
+
+        assert_eq!(Some(endpoint.clone()), endpoint_removed);
+        assert!(connector
+            .list_connections()
+            .expect("Unable to list connections")
+            .is_empty());
+        assert_eq!(
+            None,
+            connector
+                .connection_status(&endpoint)
+                .expect("Unable to get connection status")
+        );
+
+        cm.shutdown_and_wait();
+    }
+
     #[test]
     /// Tests that notifier iterator correctly exists when sender
     /// is dropped.
@@ -890,4 +1354,280 @@ pub mod tests {
                 }
         );
     }
+
+    /// test_persistent_connection_backoff
+    ///
+    /// Test that a persistent connection to an endpoint that is not listening is kept, backing
+    /// off between attempts to connect to it, and that subscribers are notified of each attempt.
+    #[test]
+    fn test_persistent_connection_backoff() {
+        let mut transport = Box::new(RawTransport::default());
+        let listener = transport
+            .listen("tcp://localhost:0")
+            .expect("Cannot listen for connections");
+        let endpoint = listener.endpoint();
+        // stop listening, so that connection attempts fail
+        drop(listener);
+
+        let mesh = Mesh::new(512, 128);
+        let mut cm = ConnectionManager::new(
+            mesh.get_life_cycle(),
+            mesh.get_sender(),
+            transport,
+            Some(1),
+            Some(1),
+        );
+        let connector = cm.start().expect("Unable to start ConnectionManager");
+        let mut subscriber = connector.subscribe().expect("Cannot get subscriber");
+
+        connector
+            .request_persistent_connection(&endpoint)
+            .expect("Unable to request persistent connection");
+
+        match subscriber.next() {
+            Some(ConnectionManagerNotification::BackingOff {
+                endpoint: notified_endpoint,
+                attempts: 1,
+                retry_in,
+            }) => {
+                assert_eq!(endpoint, notified_endpoint);
+                assert!(retry_in <= Duration::from_secs(1));
+            }
+            notification => panic!("Unexpected notification: {:?}", notification),
+        }
+
+        assert_eq!(
+            Some(ConnectionStatus::BackingOff),
+            connector
+                .connection_status(&endpoint)
+                .expect("Unable to get connection status")
+        );
+
+        assert_eq!(
+            Some(ConnectionManagerNotification::Connecting {
+                endpoint: endpoint.clone(),
+            }),
+            subscriber.next()
+        );
+
+        match subscriber.next() {
+            Some(ConnectionManagerNotification::BackingOff { attempts: 2, .. }) => (),
+            notification => panic!("Unexpected notification: {:?}", notification),
+        }
+
+        assert_eq!(
+            vec![endpoint.clone()],
+            connector
+                .list_connections()
+                .expect("Unable to list connections")
+        );
+
+        cm.shutdown_and_wait();
+    }
+
+    /// test_persistent_connection_authorization
+    ///
+    /// Test that the connection manager sends a connect request over a persistent connection once
+    /// it is established, to authorize it with the remote node.
+    #[test]
+    fn test_persistent_connection_authorization() {
+        let mut transport = Box::new(RawTransport::default());
+        let mut listener = transport
+            .listen("tcp://localhost:0")
+            .expect("Cannot listen for connections");
+        let endpoint = listener.endpoint();
+        let mesh1 = Mesh::new(512, 128);
+        let mesh2 = Mesh::new(512, 128);
+
+        let join_handle = thread::spawn(move || {
+            let conn = listener.accept().expect("Cannot accept connection");
+            mesh2.add(conn).expect("Cannot add connection to mesh");
+
+            let envelope = mesh2.recv().expect("Cannot receive message");
+            let network_msg: NetworkMessage = protobuf::parse_from_bytes(&envelope.payload())
+                .expect("Cannot parse NetworkMessage");
+            assert_eq!(
+                network_msg.get_message_type(),
+                NetworkMessageType::AUTHORIZATION
+            );
+            let auth_msg: AuthorizationMessage =
+                protobuf::parse_from_bytes(network_msg.get_payload())
+                    .expect("Cannot parse AuthorizationMessage");
+            assert_eq!(
+                auth_msg.get_message_type(),
+                AuthorizationMessageType::CONNECT_REQUEST
+            );
+        });
+
+        let mut cm = ConnectionManager::new(
+            mesh1.get_life_cycle(),
+            mesh1.get_sender(),
+            transport,
+            None,
+            None,
+        );
+        let connector = cm.start().expect("Unable to start ConnectionManager");
+
+        connector
+            .request_persistent_connection(&endpoint)
+            .expect("Unable to request persistent connection");
+
+        assert_eq!(
+            Some(ConnectionStatus::Connected),
+            connector
+                .connection_status(&endpoint)
+                .expect("Unable to get connection status")
+        );
+
+        join_handle.join().expect("Remote node failed");
+
+        cm.shutdown_and_wait();
+    }
+
+    /// test_reconnect_maximum_retry_attempts
+    ///
+    /// Test that a connection that cannot be reconnected within the maximum number of retry
+    /// attempts is marked as failed, and that subscribers are notified.
+    #[test]
+    fn test_reconnect_maximum_retry_attempts() {
+        let mut transport = Box::new(RawTransport::default());
+        let mut listener = transport
+            .listen("tcp://localhost:0")
+            .expect("Cannot listen for connections");
+        let endpoint = listener.endpoint();
+        let mesh1 = Mesh::new(512, 128);
+        let mesh2 = Mesh::new(512, 128);
+
+        thread::spawn(move || {
+            let conn = listener.accept().expect("Cannot accept connection");
+            let id = mesh2.add(conn).expect("Cannot add connection to mesh");
+
+            // wait for a heartbeat, then disconnect and stop listening
+            mesh2.recv().expect("Cannot receive message");
+            let mut connection = mesh2
+                .remove(id)
+                .expect("Cannot remove connection from mesh");
+            connection
+                .disconnect()
+                .expect("Connection failed to disconnect");
+        });
+
+        let mut cm = ConnectionManager::new(
+            mesh1.get_life_cycle(),
+            mesh1.get_sender(),
+            transport,
+            Some(1),
+            None,
+        )
+        .with_maximum_retry_attempts(1);
+        let connector = cm.start().expect("Unable to start ConnectionManager");
+
+        connector
+            .request_connection(&endpoint)
+            .expect("Unable to request connection");
+
+        let mut subscriber = connector.subscribe().expect("Cannot get subscriber");
+        assert_eq!(
+            Some(ConnectionManagerNotification::Disconnected {
+                endpoint: endpoint.clone(),
+            }),
+            subscriber.next()
+        );
+        assert_eq!(
+            Some(ConnectionManagerNotification::Failed {
+                endpoint: endpoint.clone(),
+                attempts: 1,
+            }),
+            subscriber.next()
+        );
+
+        assert_eq!(
+            Some(ConnectionStatus::Failed),
+            connector
+                .connection_status(&endpoint)
+                .expect("Unable to get connection status")
+        );
+
+        cm.shutdown_and_wait();
+    }
+
+    /// test_dialing_does_not_block_requests
+    ///
+    /// Test that the connection manager keeps handling requests while a connection is being made,
+    /// and that the requester of the connection receives the result once the attempt finishes.
+    #[test]
+    fn test_dialing_does_not_block_requests() {
+        let (unblock_sender, unblock_receiver) = channel();
+        let transport = Box::new(BlockingTransport {
+            unblock: unblock_receiver,
+        });
+        let mesh = Mesh::new(512, 128);
+
+        let mut cm = ConnectionManager::new(
+            mesh.get_life_cycle(),
+            mesh.get_sender(),
+            transport,
+            None,
+            None,
+        );
+        let connector = cm.start().expect("Unable to start ConnectionManager");
+
+        let requester = connector.clone();
+        let join_handle = thread::spawn(move || requester.request_connection("tcp://blocked:8080"));
+
+        // wait for the request to reach the connection manager
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while connector
+            .connection_status("tcp://blocked:8080")
+            .expect("Unable to get connection status")
+            .is_none()
+        {
+            assert!(Instant::now() < deadline, "connection was never requested");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        assert_eq!(
+            Some(ConnectionStatus::Connecting),
+            connector
+                .connection_status("tcp://blocked:8080")
+                .expect("Unable to get connection status")
+        );
+
+        unblock_sender
+            .send(())
+            .expect("Unable to unblock transport");
+        assert!(join_handle
+            .join()
+            .expect("Requester thread panicked")
+            .is_err());
+        assert!(connector
+            .list_connections()
+            .expect("Unable to list connections")
+            .is_empty());
+
+        cm.shutdown_and_wait();
+    }
+
+    /// A transport whose connection attempts block until they are unblocked, then fail.
+    struct BlockingTransport {
+        unblock: Receiver<()>,
+    }
+
+    impl Transport for BlockingTransport {
+        fn accepts(&self, _: &str) -> bool {
+            true
+        }
+
+        fn connect(&mut self, _: &str) -> Result<Box<dyn Connection>, ConnectError> {
+            let _ = self.unblock.recv();
+            Err(ConnectError::ProtocolError("connection refused".into()))
+        }
+
+        fn listen(
+            &mut self,
+            _: &str,
+        ) -> Result<Box<dyn crate::transport::Listener>, crate::transport::ListenError> {
+            unimplemented!()
+        }
+    }
 }
@@ -15,6 +15,7 @@
 use std::sync::mpsc::Receiver;
 #[cfg(feature = "connection-manager-notification-iter-try-next")]
 use std::sync::mpsc::TryRecvError;
+use std::time::Duration;
 
 #[cfg(feature = "connection-manager-notification-iter-try-next")]
 use super::error::ConnectionManagerError;
@@ -23,8 +24,29 @@ use super::error::ConnectionManagerError;
 /// subscription handlers
 #[derive(Debug, PartialEq, Clone)]
 pub enum ConnectionManagerNotification {
-    Connected { endpoint: String },
-    Disconnected { endpoint: String },
+    Connected {
+        endpoint: String,
+    },
+    Disconnected {
+        endpoint: String,
+    },
+    /// Another attempt is being made to connect to the endpoint, after backing off.
+    Connecting {
+        endpoint: String,
+    },
+    /// An attempt to connect to the endpoint failed; the next attempt will be made after
+    /// `retry_in`.
+    BackingOff {
+        endpoint: String,
+        attempts: u64,
+        retry_in: Duration,
+    },
+    /// The endpoint could not be reconnected within the maximum number of retry attempts, and
+    /// will not be retried.
+    Failed {
+        endpoint: String,
+        attempts: u64,
+    },
 }
 
 pub struct NotificationIter {
@@ -14,6 +14,7 @@
 use crate::channel::Sender;
 use crate::network::dispatch::{DispatchError, Handler, MessageContext};
 use crate::network::sender::SendRequest;
+use crate::network::Network;
 use crate::protos::network::{NetworkEcho, NetworkHeartbeat, NetworkMessage, NetworkMessageType};
 
 use protobuf::Message;
@@ -68,7 +69,9 @@ impl NetworkEchoHandler {
 
 // Implements a handler that handles NetworkHeartbeat Messages
 #[derive(Default)]
-pub struct NetworkHeartbeatHandler {}
+pub struct NetworkHeartbeatHandler {
+    network: Option<Network>,
+}
 
 impl Handler<NetworkMessageType, NetworkHeartbeat> for NetworkHeartbeatHandler {
     fn handle(
@@ -78,13 +81,22 @@ impl Handler<NetworkMessageType, NetworkHeartbeat> for NetworkHeartbeatHandler {
         _sender: &dyn Sender<SendRequest>,
     ) -> Result<(), DispatchError> {
         trace!("Received Heartbeat from {}", context.source_peer_id());
+        if let Some(network) = self.network.as_ref() {
+            network.record_heartbeat(context.source_peer_id());
+        }
         Ok(())
     }
 }
 
 impl NetworkHeartbeatHandler {
     pub fn new() -> Self {
-        NetworkHeartbeatHandler {}
+        NetworkHeartbeatHandler { network: None }
+    }
+
+    /// Records each heartbeat received in the given network's peer stats.
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = Some(network);
+        self
     }
 }
 
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 pub mod auth;
+#[cfg(feature = "network-capture")]
+pub mod capture;
 #[cfg(feature = "connection-manager")]
 pub mod connection_manager;
 pub mod dispatch;
@@ -25,17 +27,25 @@ use protobuf::Message;
 use uuid::Uuid;
 
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::collections::BiHashMap;
+#[cfg(feature = "matrix")]
+use crate::matrix::{
+    MatrixAddError, MatrixLifeCycle, MatrixRemoveError, MatrixSendError, MatrixSender,
+};
 use crate::mesh::{
     AddError, Envelope, Mesh, RecvError as MeshRecvError, RecvTimeoutError as MeshRecvTimeoutError,
     RemoveError, SendError as MeshSendError,
 };
 use crate::protos::network::{NetworkHeartbeat, NetworkMessage, NetworkMessageType};
-use crate::transport::Connection;
+use crate::transport::{Connection, PeerCertificate, PeerCredentials};
+
+#[cfg(feature = "network-capture")]
+use self::capture::{CaptureRecord, CaptureThread, CaptureWriter, Direction};
 
 #[derive(Debug)]
 pub struct NetworkMessageWrapper {
@@ -75,6 +85,11 @@ struct PeerMap {
     peers: BiHashMap<String, usize>,
     redirects: HashMap<String, String>,
     endpoints: BiHashMap<String, String>,
+    certificates: HashMap<String, PeerCertificate>,
+    credentials: HashMap<String, PeerCredentials>,
+    // The statistics of each connection, by mesh id, which does not change when a peer's id is
+    // updated
+    stats: HashMap<usize, Arc<ConnectionStats>>,
 }
 
 /// A map of Peer IDs to mesh IDs, which also maintains a redirect table for updated peer ids.
@@ -84,6 +99,9 @@ impl PeerMap {
             peers: BiHashMap::new(),
             redirects: HashMap::new(),
             endpoints: BiHashMap::new(),
+            certificates: HashMap::new(),
+            credentials: HashMap::new(),
+            stats: HashMap::new(),
         }
     }
 
@@ -98,8 +116,22 @@ impl PeerMap {
     }
 
     /// Insert a new peer id for a given mesh id
-    fn insert(&mut self, peer_id: String, mesh_id: usize, endpoint: String) {
+    fn insert(
+        &mut self,
+        peer_id: String,
+        mesh_id: usize,
+        endpoint: String,
+        certificate: Option<PeerCertificate>,
+        credentials: Option<PeerCredentials>,
+    ) {
         self.peers.insert(peer_id.clone(), mesh_id);
+        self.stats.insert(mesh_id, Arc::new(ConnectionStats::new()));
+        if let Some(certificate) = certificate {
+            self.certificates.insert(peer_id.clone(), certificate);
+        }
+        if let Some(credentials) = credentials {
+            self.credentials.insert(peer_id.clone(), credentials);
+        }
         self.endpoints.insert(peer_id, endpoint);
     }
 
@@ -110,9 +142,16 @@ impl PeerMap {
         self.redirects
             .retain(|_, target_peer_id| target_peer_id != peer_id);
         self.endpoints.remove_by_key(&peer_id_key);
-        self.peers
+        self.certificates.remove(peer_id);
+        self.credentials.remove(peer_id);
+        let mesh_id = self
+            .peers
             .remove_by_key(&peer_id_key)
-            .map(|(_, mesh_id)| mesh_id)
+            .map(|(_, mesh_id)| mesh_id);
+        if let Some(mesh_id) = mesh_id {
+            self.stats.remove(&mesh_id);
+        }
+        mesh_id
     }
 
     /// Updates a peer id, and creates a redirect for the old id to the given new one.
@@ -125,6 +164,12 @@ impl PeerMap {
             if let Some((_, endpoint)) = self.endpoints.remove_by_key(&old_peer_id) {
                 self.endpoints.insert(new_peer_id.clone(), endpoint);
             }
+            if let Some(certificate) = self.certificates.remove(&old_peer_id) {
+                self.certificates.insert(new_peer_id.clone(), certificate);
+            }
+            if let Some(credentials) = self.credentials.remove(&old_peer_id) {
+                self.credentials.insert(new_peer_id.clone(), credentials);
+            }
             // update the old forwards
             for (_, v) in self
                 .redirects
@@ -158,6 +203,11 @@ impl PeerMap {
         self.peers.get_by_value(&mesh_id)
     }
 
+    /// Returns the statistics of the connection with the given mesh id
+    fn get_stats(&self, mesh_id: usize) -> Option<Arc<ConnectionStats>> {
+        self.stats.get(&mesh_id).cloned()
+    }
+
     /// Returns the endpoint for the given peer id
     fn get_peer_endpoint(&self, peer_id: &str) -> Option<String> {
         let endpoint_opt = self
@@ -172,6 +222,97 @@ impl PeerMap {
     fn get_peer_by_endpoint(&self, endpoint: &str) -> Option<String> {
         self.endpoints.get_by_value(&endpoint.to_string()).cloned()
     }
+
+    /// Returns the verified certificate for the given peer id, following redirects if necessary
+    fn get_peer_certificate(&self, peer_id: &str) -> Option<PeerCertificate> {
+        self.redirects
+            .get(peer_id)
+            .and_then(|target_peer_id| self.certificates.get(target_peer_id))
+            .or_else(|| self.certificates.get(peer_id))
+            .cloned()
+    }
+
+    /// Returns the process credentials for the given peer id, following redirects if necessary
+    fn get_peer_credentials(&self, peer_id: &str) -> Option<PeerCredentials> {
+        self.redirects
+            .get(peer_id)
+            .and_then(|target_peer_id| self.credentials.get(target_peer_id))
+            .or_else(|| self.credentials.get(peer_id))
+            .cloned()
+    }
+}
+
+/// The counters of a connection, which are updated as messages are sent and received without
+/// taking any lock.
+struct ConnectionStats {
+    connected_since: SystemTime,
+    // Milliseconds since the epoch at which the last heartbeat was received, or 0 if none has
+    last_heartbeat: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+}
+
+impl ConnectionStats {
+    fn new() -> Self {
+        ConnectionStats {
+            connected_since: SystemTime::now(),
+            last_heartbeat: AtomicU64::new(0),
+            bytes_sent: AtomicU64::new(0),
+            bytes_received: AtomicU64::new(0),
+        }
+    }
+
+    fn record_heartbeat(&self) {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or(0);
+        self.last_heartbeat.store(millis, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> PeerStats {
+        let last_heartbeat = match self.last_heartbeat.load(Ordering::Relaxed) {
+            0 => None,
+            millis => Some(UNIX_EPOCH + Duration::from_millis(millis)),
+        };
+        PeerStats {
+            connected_since: self.connected_since,
+            last_heartbeat,
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The statistics of a peer's connection.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeerStats {
+    connected_since: SystemTime,
+    last_heartbeat: Option<SystemTime>,
+    bytes_sent: u64,
+    bytes_received: u64,
+}
+
+impl PeerStats {
+    /// Returns the time the peer's connection was added to the network.
+    pub fn connected_since(&self) -> SystemTime {
+        self.connected_since
+    }
+
+    /// Returns the time the last heartbeat was received from the peer, if any.
+    pub fn last_heartbeat(&self) -> Option<SystemTime> {
+        self.last_heartbeat
+    }
+
+    /// Returns the number of message bytes sent to the peer.
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent
+    }
+
+    /// Returns the number of message bytes received from the peer.
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received
+    }
 }
 
 #[derive(Clone)]
@@ -179,6 +320,8 @@ pub struct Network {
     peers: Arc<RwLock<PeerMap>>,
     mesh: Mesh,
     disconnect_listeners: Arc<Mutex<Vec<Box<dyn DisconnectListener>>>>,
+    #[cfg(feature = "network-capture")]
+    capture: Arc<RwLock<Option<CaptureThread>>>,
 }
 
 impl Network {
@@ -187,6 +330,8 @@ impl Network {
             peers: Arc::new(RwLock::new(PeerMap::new())),
             mesh,
             disconnect_listeners: Arc::new(Mutex::new(vec![])),
+            #[cfg(feature = "network-capture")]
+            capture: Arc::new(RwLock::new(None)),
         };
 
         if heartbeat_interval != 0 {
@@ -232,6 +377,35 @@ impl Network {
         rwlock_read_unwrap!(self.peers).get_peer_by_endpoint(endpoint)
     }
 
+    /// Returns the certificate the given peer's connection was verified with, if any.
+    pub fn get_peer_certificate(&self, peer_id: &str) -> Option<PeerCertificate> {
+        rwlock_read_unwrap!(self.peers).get_peer_certificate(peer_id)
+    }
+
+    /// Returns the credentials of the process on the other end of the given peer's connection, if
+    /// the connection is local and provides them.
+    pub fn get_peer_credentials(&self, peer_id: &str) -> Option<PeerCredentials> {
+        rwlock_read_unwrap!(self.peers).get_peer_credentials(peer_id)
+    }
+
+    /// Returns the statistics of the given peer's connection, following redirects if necessary.
+    pub fn get_peer_stats(&self, peer_id: &str) -> Option<PeerStats> {
+        let peers = rwlock_read_unwrap!(self.peers);
+        let mesh_id = *peers.get_mesh_id(peer_id)?;
+        peers.get_stats(mesh_id).map(|stats| stats.snapshot())
+    }
+
+    /// Records that a heartbeat was received from the given peer.
+    pub fn record_heartbeat(&self, peer_id: &str) {
+        let peers = rwlock_read_unwrap!(self.peers);
+        if let Some(stats) = peers
+            .get_mesh_id(peer_id)
+            .and_then(|mesh_id| peers.get_stats(*mesh_id))
+        {
+            stats.record_heartbeat();
+        }
+    }
+
     pub fn add_disconnect_listener(&self, listener: Box<dyn DisconnectListener>) {
         match self.disconnect_listeners.lock() {
             Ok(mut listeners) => {
@@ -243,6 +417,40 @@ impl Network {
         }
     }
 
+    /// Starts writing every message sent or received by the network to the given writer,
+    /// replacing the current capture, if any. The messages are written on a separate thread.
+    #[cfg(feature = "network-capture")]
+    pub fn start_capture(&self, writer: CaptureWriter) {
+        let capture_thread = match CaptureThread::start(writer) {
+            Ok(capture_thread) => capture_thread,
+            Err(err) => {
+                error!("Unable to start capture: {}", err);
+                return;
+            }
+        };
+
+        let previous = rwlock_write_unwrap!(self.capture).replace(capture_thread);
+        if let Some(previous) = previous {
+            previous.stop();
+        }
+    }
+
+    /// Stops the current capture, if any, once the messages captured so far have been written.
+    #[cfg(feature = "network-capture")]
+    pub fn stop_capture(&self) {
+        let capture = rwlock_write_unwrap!(self.capture).take();
+        if let Some(capture) = capture {
+            capture.stop();
+        }
+    }
+
+    #[cfg(feature = "network-capture")]
+    fn capture_message(&self, direction: Direction, peer_id: &str, msg: &[u8]) {
+        if let Some(capture) = rwlock_read_unwrap!(self.capture).as_ref() {
+            capture.capture(CaptureRecord::new(direction, peer_id, msg));
+        }
+    }
+
     fn notify_disconnect_listeners(&self, peer_id: &str) {
         match self.disconnect_listeners.lock() {
             Ok(listeners) => {
@@ -260,10 +468,12 @@ impl Network {
     ) -> Result<String, ConnectionError> {
         let mut peers = rwlock_write_unwrap!(self.peers);
         let endpoint = connection.remote_endpoint();
+        let certificate = connection.peer_certificate();
+        let credentials = connection.peer_credentials();
         let mesh_id = self.mesh.add(connection)?;
         // Temp peer id until the connection has completed authorization
         let peer_id = format!("temp-{}", Uuid::new_v4());
-        peers.insert(peer_id.clone(), mesh_id, endpoint);
+        peers.insert(peer_id.clone(), mesh_id, endpoint, certificate, credentials);
         Ok(peer_id)
     }
 
@@ -294,8 +504,10 @@ impl Network {
         // we already know the peers unique id
         let mut peers = rwlock_write_unwrap!(self.peers);
         let endpoint = connection.remote_endpoint();
+        let certificate = connection.peer_certificate();
+        let credentials = connection.peer_credentials();
         let mesh_id = self.mesh.add(connection)?;
-        peers.insert(peer_id, mesh_id, endpoint);
+        peers.insert(peer_id, mesh_id, endpoint, certificate, credentials);
         Ok(())
     }
 
@@ -304,10 +516,13 @@ impl Network {
     }
 
     pub fn send(&self, peer_id: &str, msg: &[u8]) -> Result<(), SendError> {
-        let mesh_id = match rwlock_read_unwrap!(self.peers).get_mesh_id(peer_id) {
-            Some(mesh_id) => *mesh_id,
-            None => {
-                return Err(SendError::NoPeerError(peer_id.to_string()));
+        let (mesh_id, stats) = {
+            let peers = rwlock_read_unwrap!(self.peers);
+            match peers.get_mesh_id(peer_id) {
+                Some(mesh_id) => (*mesh_id, peers.get_stats(*mesh_id)),
+                None => {
+                    return Err(SendError::NoPeerError(peer_id.to_string()));
+                }
             }
         };
 
@@ -321,21 +536,42 @@ impl Network {
             Err(err) => return Err(SendError::from(err)),
         }
 
+        if let Some(stats) = stats {
+            stats
+                .bytes_sent
+                .fetch_add(msg.len() as u64, Ordering::Relaxed);
+        }
+
+        #[cfg(feature = "network-capture")]
+        self.capture_message(Direction::Outbound, peer_id, msg);
+
         Ok(())
     }
 
     pub fn recv(&self) -> Result<NetworkMessageWrapper, RecvError> {
         let envelope = self.mesh.recv()?;
-        let peer_id = match rwlock_read_unwrap!(self.peers).get_peer_id(envelope.id()) {
-            Some(peer_id) => peer_id.to_string(),
-            None => {
-                return Err(RecvError::NoPeerError(format!(
-                    "Recv Error: No Peer with mesh id {} found",
-                    envelope.id()
-                )));
+        let (peer_id, stats) = {
+            let peers = rwlock_read_unwrap!(self.peers);
+            match peers.get_peer_id(envelope.id()) {
+                Some(peer_id) => (peer_id.to_string(), peers.get_stats(envelope.id())),
+                None => {
+                    return Err(RecvError::NoPeerError(format!(
+                        "Recv Error: No Peer with mesh id {} found",
+                        envelope.id()
+                    )));
+                }
             }
         };
 
+        if let Some(stats) = stats {
+            stats
+                .bytes_received
+                .fetch_add(envelope.payload().len() as u64, Ordering::Relaxed);
+        }
+
+        #[cfg(feature = "network-capture")]
+        self.capture_message(Direction::Inbound, &peer_id, envelope.payload());
+
         Ok(NetworkMessageWrapper::new(peer_id, envelope.take_payload()))
     }
 
@@ -344,20 +580,102 @@ impl Network {
         timeout: Duration,
     ) -> Result<NetworkMessageWrapper, RecvTimeoutError> {
         let envelope = self.mesh.recv_timeout(timeout)?;
-        let peer_id = match rwlock_read_unwrap!(self.peers).get_peer_id(envelope.id()) {
-            Some(peer_id) => peer_id.to_string(),
-            None => {
-                return Err(RecvTimeoutError::NoPeerError(format!(
-                    "Recv Error: No Peer with mesh id {} found",
-                    envelope.id()
-                )));
+        let (peer_id, stats) = {
+            let peers = rwlock_read_unwrap!(self.peers);
+            match peers.get_peer_id(envelope.id()) {
+                Some(peer_id) => (peer_id.to_string(), peers.get_stats(envelope.id())),
+                None => {
+                    return Err(RecvTimeoutError::NoPeerError(format!(
+                        "Recv Error: No Peer with mesh id {} found",
+                        envelope.id()
+                    )));
+                }
             }
         };
 
+        if let Some(stats) = stats {
+            stats
+                .bytes_received
+                .fetch_add(envelope.payload().len() as u64, Ordering::Relaxed);
+        }
+
+        #[cfg(feature = "network-capture")]
+        self.capture_message(Direction::Inbound, &peer_id, envelope.payload());
+
         Ok(NetworkMessageWrapper::new(peer_id, envelope.take_payload()))
     }
 }
 
+/// Allows a connection manager to add connections to the network, which are given temporary peer
+/// ids until they complete authorization, like those added by `add_connection`.
+#[cfg(feature = "matrix")]
+impl MatrixLifeCycle for Network {
+    fn add(&self, connection: Box<dyn Connection>) -> Result<usize, MatrixAddError> {
+        let peer_id = self.add_connection(connection).map_err(|err| {
+            MatrixAddError::new(
+                "Unable to add connection to network.".to_string(),
+                Some(Box::new(err)),
+            )
+        })?;
+
+        rwlock_read_unwrap!(self.peers)
+            .get_mesh_id(&peer_id)
+            .copied()
+            .ok_or_else(|| {
+                MatrixAddError::new(
+                    format!(
+                        "Connection for peer {} was removed while it was added",
+                        peer_id
+                    ),
+                    None,
+                )
+            })
+    }
+
+    fn remove(&self, id: usize) -> Result<Box<dyn Connection>, MatrixRemoveError> {
+        let peer_id = {
+            let mut peers = rwlock_write_unwrap!(self.peers);
+            let peer_id = peers.get_peer_id(id).cloned();
+            if let Some(peer_id) = peer_id.as_ref() {
+                peers.remove(peer_id);
+            }
+            peer_id
+        };
+
+        let connection = self.mesh.remove(id).map_err(|err| {
+            MatrixRemoveError::new(
+                "Unable to remove connection from network.".to_string(),
+                Some(Box::new(err)),
+            )
+        })?;
+
+        if let Some(peer_id) = peer_id {
+            self.notify_disconnect_listeners(&peer_id);
+        }
+
+        Ok(connection)
+    }
+}
+
+#[cfg(feature = "matrix")]
+impl MatrixSender for Network {
+    fn send(&self, id: usize, message: Vec<u8>) -> Result<(), MatrixSendError> {
+        let peer_id = rwlock_read_unwrap!(self.peers)
+            .get_peer_id(id)
+            .cloned()
+            .ok_or_else(|| {
+                MatrixSendError::new(format!("No peer with mesh id {} found", id), None)
+            })?;
+
+        Network::send(self, &peer_id, &message).map_err(|err| {
+            MatrixSendError::new(
+                "Unable to send message to peer.".to_string(),
+                Some(Box::new(err)),
+            )
+        })
+    }
+}
+
 // -------------- Errors --------------
 
 #[derive(Debug)]
@@ -546,5 +864,21 @@ pub mod tests {
         let message = assert_ok(network_one.recv());
         assert_eq!("123", message.peer_id());
         assert_eq!(heartbeat_bytes, message.payload());
+
+        // verify the stats of peer 123
+        network_one.record_heartbeat("123");
+        let stats = network_one
+            .get_peer_stats("123")
+            .expect("no stats for peer 123");
+        assert_eq!(3, stats.bytes_sent());
+        assert_eq!(
+            (b"hello_world".len() + heartbeat_bytes.len()) as u64,
+            stats.bytes_received()
+        );
+        assert!(stats.last_heartbeat().is_some());
+        assert!(stats.connected_since() <= SystemTime::now());
+
+        assert_ok(network_one.remove_connection("123"));
+        assert_eq!(None, network_one.get_peer_stats("123"));
     }
 }
@@ -166,7 +166,7 @@ impl PeerConnector {
     }
 }
 
-fn create_connect_request() -> Result<Vec<u8>, protobuf::ProtobufError> {
+pub(crate) fn create_connect_request() -> Result<Vec<u8>, protobuf::ProtobufError> {
     let mut connect_request = ConnectRequest::new();
     connect_request.set_handshake_mode(ConnectRequest_HandshakeMode::BIDIRECTIONAL);
 
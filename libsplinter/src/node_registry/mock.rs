@@ -0,0 +1,39 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{MetadataPredicate, Node, NodeRegistryError, NodeRegistryReader};
+
+/// An in-memory node registry reader for tests that need to look up a fixed set of nodes.
+pub struct MockNodeRegistry(pub Vec<Node>);
+
+impl NodeRegistryReader for MockNodeRegistry {
+    fn list_nodes<'a, 'b: 'a>(
+        &'b self,
+        _predicates: &'a [MetadataPredicate],
+    ) -> Result<Box<dyn Iterator<Item = Node> + Send + 'a>, NodeRegistryError> {
+        Ok(Box::new(self.0.clone().into_iter()))
+    }
+
+    fn count_nodes(&self, _predicates: &[MetadataPredicate]) -> Result<u32, NodeRegistryError> {
+        Ok(self.0.len() as u32)
+    }
+
+    fn fetch_node(&self, identity: &str) -> Result<Node, NodeRegistryError> {
+        self.0
+            .iter()
+            .find(|node| node.identity == identity)
+            .cloned()
+            .ok_or_else(|| NodeRegistryError::NotFoundError(identity.into()))
+    }
+}
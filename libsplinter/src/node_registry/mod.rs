@@ -13,6 +13,8 @@
 // limitations under the License.
 
 pub mod error;
+#[cfg(test)]
+pub mod mock;
 pub mod noop;
 #[cfg(feature = "rest-api")]
 pub mod rest_api;
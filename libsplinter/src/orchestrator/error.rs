@@ -125,6 +125,37 @@ impl std::fmt::Display for ShutdownServiceError {
     }
 }
 
+#[derive(Debug)]
+pub enum UpdateServiceError {
+    LockPoisoned,
+    UpdateFailed((ServiceDefinition, Box<dyn Error + Send>)),
+    UnknownService,
+}
+
+impl Error for UpdateServiceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            UpdateServiceError::LockPoisoned => None,
+            UpdateServiceError::UpdateFailed((_, err)) => Some(&**err),
+            UpdateServiceError::UnknownService => None,
+        }
+    }
+}
+
+impl std::fmt::Display for UpdateServiceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            UpdateServiceError::LockPoisoned => write!(f, "internal lock poisoned"),
+            UpdateServiceError::UpdateFailed((service, err)) => write!(
+                f,
+                "failed to update service {:?} with error {}",
+                service, err
+            ),
+            UpdateServiceError::UnknownService => write!(f, "specified service not found"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ListServicesError {
     LockPoisoned,
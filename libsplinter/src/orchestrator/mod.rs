@@ -43,7 +43,7 @@ use crate::transport::Connection;
 
 pub use self::error::{
     InitializeServiceError, ListServicesError, NewOrchestratorError, OrchestratorError,
-    ShutdownServiceError,
+    ShutdownServiceError, UpdateServiceError,
 };
 
 // Recv timeout in secs
@@ -269,6 +269,53 @@ impl ServiceOrchestrator {
         Ok(())
     }
 
+    /// Shut down the specified service and remove any persistent state it holds. The service is
+    /// stopped, purged and then destroyed.
+    pub fn purge_service(
+        &self,
+        service_definition: &ServiceDefinition,
+    ) -> Result<(), ShutdownServiceError> {
+        let ManagedService {
+            mut service,
+            registry,
+        } = self
+            .services
+            .lock()
+            .map_err(|_| ShutdownServiceError::LockPoisoned)?
+            .remove(service_definition)
+            .ok_or(ShutdownServiceError::UnknownService)?;
+
+        service.stop(&registry).map_err(|err| {
+            ShutdownServiceError::ShutdownFailed((service_definition.clone(), Box::new(err)))
+        })?;
+        service.purge().map_err(|err| {
+            ShutdownServiceError::ShutdownFailed((service_definition.clone(), Box::new(err)))
+        })?;
+        service.destroy().map_err(|err| {
+            ShutdownServiceError::ShutdownFailed((service_definition.clone(), Box::new(err)))
+        })?;
+
+        Ok(())
+    }
+
+    /// Update the peer services of a running service.
+    pub fn update_peer_services(
+        &self,
+        service_definition: &ServiceDefinition,
+        peer_services: &[String],
+    ) -> Result<(), UpdateServiceError> {
+        self.services
+            .lock()
+            .map_err(|_| UpdateServiceError::LockPoisoned)?
+            .get_mut(service_definition)
+            .ok_or(UpdateServiceError::UnknownService)?
+            .service
+            .update_peer_services(peer_services)
+            .map_err(|err| {
+                UpdateServiceError::UpdateFailed((service_definition.clone(), Box::new(err)))
+            })
+    }
+
     /// List services managed by this `ServiceOrchestrator`; filters may be provided to only show
     /// services on specified circuit(s) and of given service type(s).
     pub fn list_services(
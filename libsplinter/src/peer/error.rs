@@ -0,0 +1,103 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::circuit::SplinterStateError;
+use crate::network::connection_manager::ConnectionManagerError;
+use crate::network::ConnectionError;
+use crate::node_registry::NodeRegistryError;
+
+#[derive(Debug)]
+pub enum PeerManagerError {
+    /// The peer manager could not be started.
+    StartUpError(String),
+    /// The circuits could not be read from the splinter state.
+    StateError(SplinterStateError),
+    /// A peer's endpoint could not be read from the node registry.
+    NodeRegistryError(NodeRegistryError),
+    /// A connection could not be requested from or removed from the connection manager.
+    ConnectionError(ConnectionManagerError),
+    /// A connection that is no longer required could not be removed from the network.
+    NetworkError(ConnectionError),
+    /// The members of the open circuit proposals could not be read.
+    ProposalError(String),
+}
+
+impl Error for PeerManagerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PeerManagerError::StartUpError(_) => None,
+            PeerManagerError::StateError(err) => Some(err),
+            PeerManagerError::NodeRegistryError(err) => Some(err),
+            PeerManagerError::ConnectionError(err) => Some(err),
+            PeerManagerError::NetworkError(err) => Some(err),
+            PeerManagerError::ProposalError(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for PeerManagerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PeerManagerError::StartUpError(msg) => {
+                write!(f, "unable to start peer manager: {}", msg)
+            }
+            PeerManagerError::StateError(err) => write!(f, "unable to read circuits: {}", err),
+            PeerManagerError::NodeRegistryError(err) => {
+                write!(f, "unable to read node registry: {}", err)
+            }
+            PeerManagerError::ConnectionError(err) => {
+                write!(f, "unable to update peer connection: {}", err)
+            }
+            PeerManagerError::NetworkError(err) => {
+                write!(f, "unable to remove peer connection: {}", err)
+            }
+            PeerManagerError::ProposalError(msg) => {
+                write!(f, "unable to read open proposals: {}", msg)
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for PeerManagerError {
+    fn from(err: std::io::Error) -> Self {
+        PeerManagerError::StartUpError(err.to_string())
+    }
+}
+
+impl From<SplinterStateError> for PeerManagerError {
+    fn from(err: SplinterStateError) -> Self {
+        PeerManagerError::StateError(err)
+    }
+}
+
+impl From<NodeRegistryError> for PeerManagerError {
+    fn from(err: NodeRegistryError) -> Self {
+        PeerManagerError::NodeRegistryError(err)
+    }
+}
+
+impl From<ConnectionManagerError> for PeerManagerError {
+    fn from(err: ConnectionManagerError) -> Self {
+        PeerManagerError::ConnectionError(err)
+    }
+}
+
+impl From<ConnectionError> for PeerManagerError {
+    fn from(err: ConnectionError) -> Self {
+        PeerManagerError::NetworkError(err)
+    }
+}
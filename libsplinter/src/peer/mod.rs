@@ -0,0 +1,767 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Keeps this node connected to the other members of its circuits.
+//!
+//! The peer manager derives the peers this node requires from every circuit in its
+//! `SplinterState`, and resolves their endpoints through the node registry, falling back to the
+//! endpoints in the circuit directory for nodes that are not in the registry. The connections to
+//! required peers are requested from a connection manager, which keeps them up, and those that
+//! are no longer required by any circuit are removed. Since the required peers are derived from
+//! the stored circuits, they are reconnected when the node restarts.
+//!
+//! Peers that are already connected when they become required, such as those that connected to
+//! this node, are left to their existing connections, which are removed once the peers are no
+//! longer required.
+//!
+//! A peer whose connection is removed by an operator, rather than by the peer manager, is not
+//! reconnected until its endpoint changes or it stops being required.
+//!
+//! Connections to statically configured peers, and to the members of open circuit proposals,
+//! are kept when no circuit requires them.
+
+mod error;
+#[cfg(feature = "rest-api")]
+pub mod rest_api;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+use crate::circuit::SplinterState;
+use crate::network::connection_manager::Connector;
+use crate::network::Network;
+use crate::node_registry::{NodeRegistryError, NodeRegistryReader};
+
+pub use error::PeerManagerError;
+
+const DEFAULT_CHECK_INTERVAL_SEC: u64 = 10;
+
+enum PeerManagerMessage {
+    Shutdown,
+}
+
+/// Provides the members of the open circuit proposals, whose connections the peer manager keeps
+/// even if no circuit requires them.
+pub trait ProposalMembers: Send {
+    /// Returns the node IDs of the members of every open circuit proposal.
+    fn proposal_members(&self) -> Result<HashSet<String>, PeerManagerError>;
+}
+
+pub struct PeerManager {
+    peer_state: Option<PeerState>,
+    check_interval: Duration,
+    join_handle: Option<thread::JoinHandle<()>>,
+    shutdown_handle: Option<ShutdownHandle>,
+}
+
+impl PeerManager {
+    /// Creates a peer manager for the node with the given ID, which requests the connections to
+    /// its peers from the given connector. The given network is used to check which peers are
+    /// already connected.
+    pub fn new(
+        node_id: String,
+        state: SplinterState,
+        node_registry: Box<dyn NodeRegistryReader>,
+        network: Network,
+        connector: Connector,
+    ) -> Self {
+        PeerManager {
+            peer_state: Some(PeerState {
+                node_id,
+                state,
+                node_registry,
+                network,
+                connector,
+                peers: HashMap::new(),
+                connected_peers: HashSet::new(),
+                removed_peers: HashMap::new(),
+                static_peers: HashSet::new(),
+                proposal_members: None,
+            }),
+            check_interval: Duration::from_secs(DEFAULT_CHECK_INTERVAL_SEC),
+            join_handle: None,
+            shutdown_handle: None,
+        }
+    }
+
+    /// Sets how often the circuits are checked for changes to the required peers. The default is
+    /// every 10 seconds.
+    pub fn with_check_interval(mut self, check_interval: Duration) -> Self {
+        self.check_interval = check_interval;
+        self
+    }
+
+    /// Sets the endpoints of the statically configured peers, whose connections are kept when no
+    /// circuit requires them.
+    pub fn with_static_peers(mut self, static_peers: Vec<String>) -> Self {
+        if let Some(ref mut peer_state) = self.peer_state {
+            peer_state.static_peers = static_peers.into_iter().collect();
+        }
+        self
+    }
+
+    /// Sets the source of the members of the open circuit proposals, whose connections are kept
+    /// when no circuit requires them.
+    pub fn with_proposal_members(mut self, proposal_members: Box<dyn ProposalMembers>) -> Self {
+        if let Some(ref mut peer_state) = self.peer_state {
+            peer_state.proposal_members = Some(proposal_members);
+        }
+        self
+    }
+
+    /// Starts the peer manager, which updates the connections to the required peers immediately,
+    /// and then at each check interval until it is shut down.
+    pub fn start(&mut self) -> Result<(), PeerManagerError> {
+        let mut peer_state = self.peer_state.take().ok_or_else(|| {
+            PeerManagerError::StartUpError("Peer manager has already started".into())
+        })?;
+
+        let (sender, recv) = channel();
+        let check_interval = self.check_interval;
+        let join_handle = thread::Builder::new()
+            .name("Peer Manager".into())
+            .spawn(move || loop {
+                if let Err(err) = peer_state.update_peers() {
+                    error!("Unable to update peer connections: {}", err);
+                }
+
+                match recv.recv_timeout(check_interval) {
+                    Ok(PeerManagerMessage::Shutdown) => break,
+                    Err(RecvTimeoutError::Timeout) => (),
+                    Err(RecvTimeoutError::Disconnected) => {
+                        warn!("All senders have disconnected");
+                        break;
+                    }
+                }
+            })?;
+
+        self.join_handle = Some(join_handle);
+        self.shutdown_handle = Some(ShutdownHandle { sender });
+
+        Ok(())
+    }
+
+    pub fn shutdown_handle(&self) -> Option<ShutdownHandle> {
+        self.shutdown_handle.clone()
+    }
+
+    pub fn await_shutdown(self) {
+        if let Some(join_handle) = self.join_handle {
+            if let Err(err) = join_handle.join() {
+                error!("Peer manager thread did not shutdown correctly: {:?}", err);
+            }
+        }
+    }
+
+    pub fn shutdown_and_wait(self) {
+        if let Some(sh) = self.shutdown_handle.clone() {
+            sh.shutdown();
+        } else {
+            return;
+        }
+
+        self.await_shutdown();
+    }
+}
+
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    sender: Sender<PeerManagerMessage>,
+}
+
+impl ShutdownHandle {
+    pub fn shutdown(&self) {
+        if self.sender.send(PeerManagerMessage::Shutdown).is_err() {
+            warn!("Peer manager is no longer running");
+        }
+    }
+}
+
+struct PeerState {
+    node_id: String,
+    state: SplinterState,
+    node_registry: Box<dyn NodeRegistryReader>,
+    network: Network,
+    connector: Connector,
+    // The endpoints of the peers whose connections were requested, by node ID
+    peers: HashMap<String, String>,
+    // The node IDs of the required peers that were already connected, whose connections were
+    // not requested
+    connected_peers: HashSet<String>,
+    // The endpoints of the peers whose connections were removed by an operator, by node ID
+    removed_peers: HashMap<String, String>,
+    // The endpoints of the statically configured peers, which are never removed
+    static_peers: HashSet<String>,
+    // The source of the members of open proposals, which are not removed while the proposals
+    // are open
+    proposal_members: Option<Box<dyn ProposalMembers>>,
+}
+
+impl PeerState {
+    /// Returns the endpoints of the other members of every circuit, by node ID. Members whose
+    /// endpoints are unknown are left out.
+    fn required_peers(&self) -> Result<HashMap<String, String>, PeerManagerError> {
+        let mut peers = HashMap::new();
+        for circuit in self.state.circuits()?.values() {
+            for member in circuit.members().to_vec() {
+                if member == self.node_id || peers.contains_key(&member) {
+                    continue;
+                }
+
+                match self.resolve_endpoint(&member)? {
+                    Some(endpoint) => {
+                        peers.insert(member, endpoint);
+                    }
+                    None => debug!("node {} has no known endpoints", member),
+                }
+            }
+        }
+
+        Ok(peers)
+    }
+
+    /// Returns the endpoint of the given node from the node registry, or from the circuit
+    /// directory if the node is not in the registry.
+    fn resolve_endpoint(&self, node_id: &str) -> Result<Option<String>, PeerManagerError> {
+        match self.node_registry.fetch_node(node_id) {
+            Ok(node) => return Ok(Some(node.endpoint)),
+            Err(NodeRegistryError::NotFoundError(_)) => (),
+            Err(err) => return Err(err.into()),
+        }
+
+        Ok(self
+            .state
+            .node(node_id)?
+            .and_then(|node| node.endpoints().get(0).cloned()))
+    }
+
+    /// Returns whether the connection to the given peer, with the given endpoint, must be kept
+    /// although no circuit requires the peer, because the peer is statically configured or a
+    /// member of an open proposal.
+    fn is_retained(
+        &self,
+        node_id: &str,
+        endpoint: &str,
+        proposal_members: &HashSet<String>,
+    ) -> bool {
+        proposal_members.contains(node_id) || self.static_peers.contains(endpoint)
+    }
+
+    /// Requests connections to the required peers that have not been requested yet, and removes
+    /// those to peers that are no longer required, or whose endpoints have changed. Peers that
+    /// are already connected are left to their existing connections until those are lost, and
+    /// those connections are removed once the peers are no longer required. Connections to
+    /// static peers and members of open proposals are not removed.
+    fn update_peers(&mut self) -> Result<(), PeerManagerError> {
+        let required = self.required_peers()?;
+        let proposal_members = match self.proposal_members {
+            Some(ref proposal_members) => proposal_members.proposal_members()?,
+            None => HashSet::new(),
+        };
+
+        // connections that the connection manager no longer has were removed by an operator
+        let mut operator_removed = Vec::new();
+        for (node_id, endpoint) in self.peers.iter() {
+            if self.connector.connection_status(endpoint)?.is_none() {
+                operator_removed.push(node_id.clone());
+            }
+        }
+        for node_id in operator_removed {
+            if let Some(endpoint) = self.peers.remove(&node_id) {
+                debug!(
+                    "Connection to peer {} at {} was removed; it will not be reconnected",
+                    node_id, endpoint
+                );
+                self.removed_peers.insert(node_id, endpoint);
+            }
+        }
+        self.removed_peers
+            .retain(|node_id, endpoint| required.get(node_id) == Some(endpoint));
+
+        let removed: Vec<(String, String)> = self
+            .peers
+            .iter()
+            .filter(|(node_id, endpoint)| match required.get(*node_id) {
+                Some(required_endpoint) => required_endpoint != *endpoint,
+                None => !self.is_retained(node_id, endpoint, &proposal_members),
+            })
+            .map(|(node_id, endpoint)| (node_id.clone(), endpoint.clone()))
+            .collect();
+        for (node_id, endpoint) in removed {
+            debug!("Removing connection to peer {} at {}", node_id, endpoint);
+            self.connector.remove_connection(&endpoint)?;
+            self.peers.remove(&node_id);
+        }
+
+        let unrequired: Vec<String> = self
+            .connected_peers
+            .iter()
+            .filter(|node_id| !required.contains_key(*node_id))
+            .cloned()
+            .collect();
+        for node_id in unrequired {
+            // a retained connection is removed once the peer is no longer retained
+            let endpoint = match self.network.get_peer_endpoint(&node_id) {
+                Some(endpoint) => endpoint,
+                None => {
+                    self.connected_peers.remove(&node_id);
+                    continue;
+                }
+            };
+            if self.is_retained(&node_id, &endpoint, &proposal_members) {
+                continue;
+            }
+
+            debug!("Removing connection to peer {}", node_id);
+            self.network.remove_connection(&node_id)?;
+            self.connected_peers.remove(&node_id);
+        }
+
+        let connected = self.network.peer_ids();
+        for (node_id, endpoint) in required {
+            if self.peers.contains_key(&node_id) || self.removed_peers.contains_key(&node_id) {
+                continue;
+            }
+
+            if connected.contains(&node_id) {
+                self.connected_peers.insert(node_id);
+                continue;
+            }
+            // a peer whose existing connection was lost is reconnected by the connection manager
+            self.connected_peers.remove(&node_id);
+
+            debug!("Requesting connection to peer {} at {}", node_id, endpoint);
+            self.connector.request_persistent_connection(&endpoint)?;
+            self.peers.insert(node_id, endpoint);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use crate::circuit::directory::CircuitDirectory;
+    use crate::circuit::service::SplinterNode;
+    use crate::circuit::{AuthorizationType, Circuit, DurabilityType, PersistenceType, RouteType};
+    use crate::mesh::Mesh;
+    use crate::network::connection_manager::ConnectionManager;
+    use crate::node_registry::{mock::MockNodeRegistry, Node};
+    use crate::transport::inproc::InprocTransport;
+    use crate::transport::Transport;
+
+    // Test that the peer manager requests connections to the other members of every circuit,
+    // using the endpoints in the node registry before those in the circuit directory, and removes
+    // the connections that are no longer required once the circuits are removed
+    #[test]
+    fn test_peer_manager_circuit_members() {
+        let mut transport = InprocTransport::default();
+        for endpoint in &["inproc://node-345", "inproc://node-678"] {
+            let mut listener = transport.listen(endpoint).unwrap();
+            thread::spawn(move || while listener.accept().is_ok() {});
+        }
+
+        let mut circuit_directory = CircuitDirectory::new();
+        circuit_directory.add_circuit("alpha".into(), create_circuit("alpha", "345"));
+        circuit_directory.add_circuit("beta".into(), create_circuit("beta", "678"));
+        circuit_directory.add_node(
+            "345".into(),
+            SplinterNode::new("345".into(), vec!["inproc://node-345".into()]),
+        );
+        circuit_directory.add_node(
+            "678".into(),
+            SplinterNode::new("678".into(), vec!["inproc://unknown".into()]),
+        );
+        let mut state = SplinterState::new("memory".into(), circuit_directory);
+
+        let registry = MockNodeRegistry(vec![Node::new("678", "inproc://node-678")]);
+
+        let network = Network::new(Mesh::new(512, 128), 0).unwrap();
+        let mut cm = ConnectionManager::new(
+            network.clone(),
+            network.clone(),
+            Box::new(transport),
+            None,
+            None,
+        );
+        let connector = cm.start().unwrap();
+
+        let mut peer_state = PeerState {
+            node_id: "123".into(),
+            state: state.clone(),
+            node_registry: Box::new(registry),
+            network,
+            connector: connector.clone(),
+            peers: HashMap::new(),
+            connected_peers: HashSet::new(),
+            removed_peers: HashMap::new(),
+            static_peers: HashSet::new(),
+            proposal_members: None,
+        };
+
+        peer_state.update_peers().unwrap();
+        let mut connections = connector.list_connections().unwrap();
+        connections.sort();
+        assert_eq!(
+            connections,
+            vec![
+                "inproc://node-345".to_string(),
+                "inproc://node-678".to_string()
+            ]
+        );
+
+        state.remove_circuit("beta").unwrap();
+        peer_state.update_peers().unwrap();
+        assert_eq!(
+            connector.list_connections().unwrap(),
+            vec!["inproc://node-345".to_string()]
+        );
+
+        state.remove_circuit("alpha").unwrap();
+        peer_state.update_peers().unwrap();
+        assert!(connector.list_connections().unwrap().is_empty());
+
+        cm.shutdown_and_wait();
+    }
+
+    // Test that the peer manager does not reconnect to a peer whose connection was removed by an
+    // operator, until the peer is required again after it stopped being required
+    #[test]
+    fn test_peer_manager_operator_removed_peer() {
+        let mut transport = InprocTransport::default();
+        let mut listener = transport.listen("inproc://node-345").unwrap();
+        thread::spawn(move || while listener.accept().is_ok() {});
+
+        let mut circuit_directory = CircuitDirectory::new();
+        circuit_directory.add_circuit("alpha".into(), create_circuit("alpha", "345"));
+        circuit_directory.add_node(
+            "345".into(),
+            SplinterNode::new("345".into(), vec!["inproc://node-345".into()]),
+        );
+        let mut state = SplinterState::new("memory".into(), circuit_directory);
+
+        let network = Network::new(Mesh::new(512, 128), 0).unwrap();
+        let mut cm = ConnectionManager::new(
+            network.clone(),
+            network.clone(),
+            Box::new(transport),
+            None,
+            None,
+        );
+        let connector = cm.start().unwrap();
+
+        let mut peer_state = PeerState {
+            node_id: "123".into(),
+            state: state.clone(),
+            node_registry: Box::new(MockNodeRegistry(vec![])),
+            network,
+            connector: connector.clone(),
+            peers: HashMap::new(),
+            connected_peers: HashSet::new(),
+            removed_peers: HashMap::new(),
+            static_peers: HashSet::new(),
+            proposal_members: None,
+        };
+
+        peer_state.update_peers().unwrap();
+        assert_eq!(
+            connector.list_connections().unwrap(),
+            vec!["inproc://node-345".to_string()]
+        );
+
+        connector
+            .force_remove_connection("inproc://node-345")
+            .unwrap();
+        peer_state.update_peers().unwrap();
+        assert!(connector.list_connections().unwrap().is_empty());
+
+        let circuit = state.circuit("alpha").unwrap().unwrap();
+        state.remove_circuit("alpha").unwrap();
+        peer_state.update_peers().unwrap();
+        state.add_circuit("alpha".into(), circuit).unwrap();
+        peer_state.update_peers().unwrap();
+        assert_eq!(
+            connector.list_connections().unwrap(),
+            vec!["inproc://node-345".to_string()]
+        );
+
+        cm.shutdown_and_wait();
+    }
+
+    // Test that the peer manager leaves a required peer that is already connected to its existing
+    // connection, and removes that connection once the peer is no longer required
+    #[test]
+    fn test_peer_manager_already_connected_peer() {
+        let mut transport = InprocTransport::default();
+        let mut listener = transport.listen("inproc://node-345").unwrap();
+        let join_handle = thread::spawn(move || listener.accept().unwrap());
+
+        let network = Network::new(Mesh::new(512, 128), 0).unwrap();
+        network
+            .add_peer(
+                "345".into(),
+                transport.connect("inproc://node-345").unwrap(),
+            )
+            .unwrap();
+        let _remote = join_handle.join().unwrap();
+
+        let mut circuit_directory = CircuitDirectory::new();
+        circuit_directory.add_circuit("alpha".into(), create_circuit("alpha", "345"));
+        circuit_directory.add_node(
+            "345".into(),
+            SplinterNode::new("345".into(), vec!["inproc://node-345".into()]),
+        );
+        let mut state = SplinterState::new("memory".into(), circuit_directory);
+
+        let mut cm = ConnectionManager::new(
+            network.clone(),
+            network.clone(),
+            Box::new(transport),
+            None,
+            None,
+        );
+        let connector = cm.start().unwrap();
+
+        let mut peer_state = PeerState {
+            node_id: "123".into(),
+            state: state.clone(),
+            node_registry: Box::new(MockNodeRegistry(vec![])),
+            network: network.clone(),
+            connector: connector.clone(),
+            peers: HashMap::new(),
+            connected_peers: HashSet::new(),
+            removed_peers: HashMap::new(),
+            static_peers: HashSet::new(),
+            proposal_members: None,
+        };
+
+        peer_state.update_peers().unwrap();
+        assert!(connector.list_connections().unwrap().is_empty());
+        assert_eq!(network.peer_ids(), vec!["345".to_string()]);
+
+        state.remove_circuit("alpha").unwrap();
+        peer_state.update_peers().unwrap();
+        assert!(network.peer_ids().is_empty());
+
+        cm.shutdown_and_wait();
+    }
+
+    // Test that the peer manager keeps the existing connection to a statically configured peer
+    // once the peer is no longer required by a circuit
+    #[test]
+    fn test_peer_manager_static_peer() {
+        let mut transport = InprocTransport::default();
+        let mut listener = transport.listen("inproc://node-345").unwrap();
+        let join_handle = thread::spawn(move || listener.accept().unwrap());
+
+        let network = Network::new(Mesh::new(512, 128), 0).unwrap();
+        network
+            .add_peer(
+                "345".into(),
+                transport.connect("inproc://node-345").unwrap(),
+            )
+            .unwrap();
+        let _remote = join_handle.join().unwrap();
+
+        let mut circuit_directory = CircuitDirectory::new();
+        circuit_directory.add_circuit("alpha".into(), create_circuit("alpha", "345"));
+        circuit_directory.add_node(
+            "345".into(),
+            SplinterNode::new("345".into(), vec!["inproc://node-345".into()]),
+        );
+        let mut state = SplinterState::new("memory".into(), circuit_directory);
+
+        let mut cm = ConnectionManager::new(
+            network.clone(),
+            network.clone(),
+            Box::new(transport),
+            None,
+            None,
+        );
+        let connector = cm.start().unwrap();
+
+        let mut peer_state = PeerState {
+            node_id: "123".into(),
+            state: state.clone(),
+            node_registry: Box::new(MockNodeRegistry(vec![])),
+            network: network.clone(),
+            connector: connector.clone(),
+            peers: HashMap::new(),
+            connected_peers: HashSet::new(),
+            removed_peers: HashMap::new(),
+            static_peers: vec!["inproc://node-345".to_string()].into_iter().collect(),
+            proposal_members: None,
+        };
+
+        peer_state.update_peers().unwrap();
+        assert_eq!(network.peer_ids(), vec!["345".to_string()]);
+
+        state.remove_circuit("alpha").unwrap();
+        peer_state.update_peers().unwrap();
+        assert_eq!(network.peer_ids(), vec!["345".to_string()]);
+
+        cm.shutdown_and_wait();
+    }
+
+    // Test that the peer manager keeps the connection to a peer that is no longer required by a
+    // circuit while the peer is a member of an open proposal, and removes it once it is not
+    #[test]
+    fn test_peer_manager_proposal_member() {
+        let mut transport = InprocTransport::default();
+        let mut listener = transport.listen("inproc://node-345").unwrap();
+        thread::spawn(move || while listener.accept().is_ok() {});
+
+        let mut circuit_directory = CircuitDirectory::new();
+        circuit_directory.add_circuit("alpha".into(), create_circuit("alpha", "345"));
+        circuit_directory.add_node(
+            "345".into(),
+            SplinterNode::new("345".into(), vec!["inproc://node-345".into()]),
+        );
+        let mut state = SplinterState::new("memory".into(), circuit_directory);
+
+        let network = Network::new(Mesh::new(512, 128), 0).unwrap();
+        let mut cm = ConnectionManager::new(
+            network.clone(),
+            network.clone(),
+            Box::new(transport),
+            None,
+            None,
+        );
+        let connector = cm.start().unwrap();
+
+        let proposal_members = Arc::new(Mutex::new(
+            vec!["345".to_string()].into_iter().collect::<HashSet<_>>(),
+        ));
+        let mut peer_state = PeerState {
+            node_id: "123".into(),
+            state: state.clone(),
+            node_registry: Box::new(MockNodeRegistry(vec![])),
+            network,
+            connector: connector.clone(),
+            peers: HashMap::new(),
+            connected_peers: HashSet::new(),
+            removed_peers: HashMap::new(),
+            static_peers: HashSet::new(),
+            proposal_members: Some(Box::new(MockProposalMembers(proposal_members.clone()))),
+        };
+
+        peer_state.update_peers().unwrap();
+        assert_eq!(
+            connector.list_connections().unwrap(),
+            vec!["inproc://node-345".to_string()]
+        );
+
+        state.remove_circuit("alpha").unwrap();
+        peer_state.update_peers().unwrap();
+        assert_eq!(
+            connector.list_connections().unwrap(),
+            vec!["inproc://node-345".to_string()]
+        );
+
+        proposal_members.lock().unwrap().clear();
+        peer_state.update_peers().unwrap();
+        assert!(connector.list_connections().unwrap().is_empty());
+
+        cm.shutdown_and_wait();
+    }
+
+    // Test that the peer manager replaces the connection to a peer whose endpoint has changed
+    #[test]
+    fn test_peer_manager_endpoint_change() {
+        let mut transport = InprocTransport::default();
+        for endpoint in &["inproc://node-345", "inproc://node-345-moved"] {
+            let mut listener = transport.listen(endpoint).unwrap();
+            thread::spawn(move || while listener.accept().is_ok() {});
+        }
+
+        let mut circuit_directory = CircuitDirectory::new();
+        circuit_directory.add_circuit("alpha".into(), create_circuit("alpha", "345"));
+        circuit_directory.add_node(
+            "345".into(),
+            SplinterNode::new("345".into(), vec!["inproc://node-345".into()]),
+        );
+        let mut state = SplinterState::new("memory".into(), circuit_directory);
+
+        let network = Network::new(Mesh::new(512, 128), 0).unwrap();
+        let mut cm = ConnectionManager::new(
+            network.clone(),
+            network.clone(),
+            Box::new(transport),
+            None,
+            None,
+        );
+        let connector = cm.start().unwrap();
+
+        let mut peer_state = PeerState {
+            node_id: "123".into(),
+            state: state.clone(),
+            node_registry: Box::new(MockNodeRegistry(vec![])),
+            network,
+            connector: connector.clone(),
+            peers: HashMap::new(),
+            connected_peers: HashSet::new(),
+            removed_peers: HashMap::new(),
+            static_peers: HashSet::new(),
+            proposal_members: None,
+        };
+
+        peer_state.update_peers().unwrap();
+        assert_eq!(
+            connector.list_connections().unwrap(),
+            vec!["inproc://node-345".to_string()]
+        );
+
+        state
+            .add_node(
+                "345".into(),
+                SplinterNode::new("345".into(), vec!["inproc://node-345-moved".into()]),
+            )
+            .unwrap();
+        peer_state.update_peers().unwrap();
+        assert_eq!(
+            connector.list_connections().unwrap(),
+            vec!["inproc://node-345-moved".to_string()]
+        );
+
+        cm.shutdown_and_wait();
+    }
+
+    struct MockProposalMembers(Arc<Mutex<HashSet<String>>>);
+
+    impl ProposalMembers for MockProposalMembers {
+        fn proposal_members(&self) -> Result<HashSet<String>, PeerManagerError> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+    }
+
+    fn create_circuit(circuit_id: &str, member: &str) -> Circuit {
+        Circuit::builder()
+            .with_id(circuit_id.into())
+            .with_auth(AuthorizationType::Trust)
+            .with_members(vec!["123".into(), member.into()])
+            .with_roster(vec!["abc".into()])
+            .with_persistence(PersistenceType::Any)
+            .with_durability(DurabilityType::NoDurability)
+            .with_routes(RouteType::Any)
+            .with_circuit_management_type("peer_manager_test_app".into())
+            .build()
+            .expect("Should have built a correct circuit")
+    }
+}
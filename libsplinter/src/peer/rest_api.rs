@@ -0,0 +1,410 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! REST API endpoints for viewing and managing the peers of a running node.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::actix_web::{error::BlockingError, web, Error, HttpRequest, HttpResponse};
+use crate::futures::{future::IntoFuture, stream::Stream, Future};
+use crate::network::auth::AuthorizationManager;
+use crate::network::connection_manager::Connector;
+use crate::network::Network;
+use crate::protocol;
+use crate::rest_api::{Method, ProtocolVersionRangeGuard, Resource};
+
+/// A peer of this node, with the state and statistics of its connection.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PeerResponse {
+    pub node_id: String,
+    pub endpoint: Option<String>,
+    pub auth_state: String,
+    /// Seconds since the epoch at which the connection was added
+    pub connected_since: Option<u64>,
+    /// Seconds since the epoch at which the last heartbeat was received
+    pub last_heartbeat: Option<u64>,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ListPeersResponse {
+    pub data: Vec<PeerResponse>,
+}
+
+/// The body of a request to connect to a peer.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ConnectPeerRequest {
+    pub endpoint: String,
+}
+
+/// Makes the `/admin/peers` resource, which lists the node's peers on GET and connects to the
+/// endpoint given on POST.
+///
+/// Peers are connected to by the connection manager of the given connector, which keeps the
+/// connection alive until it is disconnected.
+pub fn make_peers_resource(
+    network: Network,
+    auth_manager: AuthorizationManager,
+    connector: Connector,
+) -> Resource {
+    Resource::build("/admin/peers")
+        .add_request_guard(ProtocolVersionRangeGuard::new(
+            protocol::ADMIN_LIST_PEERS_MIN,
+            protocol::ADMIN_PROTOCOL_VERSION,
+        ))
+        .add_method(Method::Get, move |_, _| {
+            list_peers(
+                web::Data::new(network.clone()),
+                web::Data::new(auth_manager.clone()),
+            )
+        })
+        .add_method(Method::Post, move |_, p| {
+            connect_peer(p, web::Data::new(connector.clone()))
+        })
+}
+
+/// Makes the `/admin/peers/{id}` resource, which disconnects the given peer on DELETE.
+pub fn make_peer_resource(network: Network, connector: Connector) -> Resource {
+    Resource::build("/admin/peers/{id}")
+        .add_request_guard(ProtocolVersionRangeGuard::new(
+            protocol::ADMIN_REMOVE_PEER_MIN,
+            protocol::ADMIN_PROTOCOL_VERSION,
+        ))
+        .add_method(Method::Delete, move |r, _| {
+            disconnect_peer(
+                r,
+                web::Data::new(network.clone()),
+                web::Data::new(connector.clone()),
+            )
+        })
+}
+
+fn list_peers(
+    network: web::Data<Network>,
+    auth_manager: web::Data<AuthorizationManager>,
+) -> Box<dyn Future<Item = HttpResponse, Error = Error>> {
+    let mut peer_ids = network.peer_ids();
+    peer_ids.sort();
+
+    let data = peer_ids
+        .into_iter()
+        .map(|node_id| peer_response(&network, &auth_manager, node_id))
+        .collect();
+
+    Box::new(
+        HttpResponse::Ok()
+            .json(ListPeersResponse { data })
+            .into_future(),
+    )
+}
+
+fn peer_response(
+    network: &Network,
+    auth_manager: &AuthorizationManager,
+    node_id: String,
+) -> PeerResponse {
+    let stats = network.get_peer_stats(&node_id);
+    PeerResponse {
+        endpoint: network.get_peer_endpoint(&node_id),
+        auth_state: auth_manager.authorization_state(&node_id),
+        connected_since: stats
+            .as_ref()
+            .map(|stats| to_unix_seconds(stats.connected_since())),
+        last_heartbeat: stats
+            .as_ref()
+            .and_then(|stats| stats.last_heartbeat())
+            .map(to_unix_seconds),
+        bytes_sent: stats.as_ref().map(|stats| stats.bytes_sent()).unwrap_or(0),
+        bytes_received: stats
+            .as_ref()
+            .map(|stats| stats.bytes_received())
+            .unwrap_or(0),
+        node_id,
+    }
+}
+
+fn to_unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn connect_peer(
+    payload: web::Payload,
+    connector: web::Data<Connector>,
+) -> Box<dyn Future<Item = HttpResponse, Error = Error>> {
+    Box::new(
+        payload
+            .from_err::<Error>()
+            .fold(web::BytesMut::new(), move |mut body, chunk| {
+                body.extend_from_slice(&chunk);
+                Ok::<_, Error>(body)
+            })
+            .into_future()
+            .and_then(
+                move |body| match serde_json::from_slice::<ConnectPeerRequest>(&body) {
+                    Ok(request) if request.endpoint.is_empty() => Box::new(
+                        HttpResponse::BadRequest()
+                            .json(json!({ "message": "endpoint must not be empty" }))
+                            .into_future(),
+                    ),
+                    Ok(request) => Box::new(
+                        web::block(move || {
+                            connector.request_persistent_connection(&request.endpoint)
+                        })
+                        .then(|res| {
+                            Ok(match res {
+                                Ok(()) => HttpResponse::Accepted().finish(),
+                                Err(err) => match err {
+                                    BlockingError::Error(err) => HttpResponse::InternalServerError()
+                                        .json(json!({ "message": format!("unable to connect to peer: {}", err) })),
+                                    _ => HttpResponse::InternalServerError()
+                                        .json(json!({ "message": format!("{}", err) })),
+                                },
+                            })
+                        }),
+                    )
+                        as Box<dyn Future<Item = HttpResponse, Error = Error>>,
+                    Err(err) => Box::new(
+                        HttpResponse::BadRequest()
+                            .json(json!({ "message": format!("invalid request: {}", err) }))
+                            .into_future(),
+                    ),
+                },
+            ),
+    )
+}
+
+fn disconnect_peer(
+    request: HttpRequest,
+    network: web::Data<Network>,
+    connector: web::Data<Connector>,
+) -> Box<dyn Future<Item = HttpResponse, Error = Error>> {
+    let peer_id = request.match_info().get("id").unwrap_or("").to_string();
+    let endpoint = match network.get_peer_endpoint(&peer_id) {
+        Some(endpoint) => endpoint,
+        None => {
+            return Box::new(
+                HttpResponse::NotFound()
+                    .json(json!({ "message": format!("peer {} not found", peer_id) }))
+                    .into_future(),
+            )
+        }
+    };
+
+    Box::new(
+        web::block(move || {
+            // Stop the connection manager from reconnecting to the peer, however many times its
+            // connection was requested, then remove the peer's connection from the network if it
+            // was not managed by the connection manager.
+            connector
+                .force_remove_connection(&endpoint)
+                .map_err(|err| err.to_string())?;
+            if network.get_peer_endpoint(&peer_id).is_some() {
+                network
+                    .remove_connection(&peer_id)
+                    .map_err(|err| err.to_string())?;
+            }
+            Ok::<_, String>(())
+        })
+        .then(|res| {
+            Ok(match res {
+                Ok(()) => HttpResponse::Ok().finish(),
+                Err(err) => match err {
+                    BlockingError::Error(err) => HttpResponse::InternalServerError()
+                        .json(json!({ "message": format!("unable to disconnect peer: {}", err) })),
+                    _ => HttpResponse::InternalServerError()
+                        .json(json!({ "message": format!("{}", err) })),
+                },
+            })
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    use crate::actix_web::{
+        http::{header, StatusCode},
+        test, App,
+    };
+    use crate::mesh::Mesh;
+    use crate::network::connection_manager::ConnectionManager;
+    use crate::transport::inproc::InprocTransport;
+    use crate::transport::{Connection, Transport};
+
+    #[test]
+    /// Tests a GET /admin/peers request returns the connected peers.
+    fn test_list_peers() {
+        let mut transport = InprocTransport::default();
+        let network = Network::new(Mesh::new(512, 128), 0).unwrap();
+        let auth_manager = AuthorizationManager::new(network.clone(), "local".into());
+        let _remote = add_peer(&mut transport, &network, "123", "inproc://peer-123");
+
+        let mut app = test::init_service(
+            App::new()
+                .data(network.clone())
+                .data(auth_manager.clone())
+                .service(web::resource("/admin/peers").route(web::get().to_async(list_peers))),
+        );
+
+        let req = test::TestRequest::get().uri("/admin/peers").to_request();
+
+        let resp = test::call_service(&mut app, req);
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let peers: ListPeersResponse = serde_json::from_slice(&test::read_body(resp)).unwrap();
+        assert_eq!(peers.data.len(), 1);
+        assert_eq!(peers.data[0].node_id, "123");
+        assert_eq!(
+            peers.data[0].endpoint,
+            Some("inproc://peer-123".to_string())
+        );
+        assert_eq!(peers.data[0].auth_state, "Unknown");
+        assert!(peers.data[0].connected_since.is_some());
+    }
+
+    #[test]
+    /// Test the POST /admin/peers route for connecting to a peer.
+    fn test_connect_peer() {
+        let mut transport = InprocTransport::default();
+        let mut listener = transport.listen("inproc://peer-123").unwrap();
+        thread::spawn(move || while listener.accept().is_ok() {});
+
+        let network = Network::new(Mesh::new(512, 128), 0).unwrap();
+        let mut cm = ConnectionManager::new(
+            network.clone(),
+            network.clone(),
+            Box::new(transport),
+            None,
+            None,
+        );
+        let connector = cm.start().unwrap();
+
+        let mut app = test::init_service(
+            App::new()
+                .data(connector.clone())
+                .service(web::resource("/admin/peers").route(web::post().to_async(connect_peer))),
+        );
+
+        // Verify an invalid request gets a BAD_REQUEST response
+        let req = test::TestRequest::post()
+            .uri("/admin/peers")
+            .header(header::CONTENT_TYPE, "application/json")
+            .to_request();
+
+        let resp = test::call_service(&mut app, req);
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // Verify an empty endpoint gets a BAD_REQUEST response
+        let req = test::TestRequest::post()
+            .uri("/admin/peers")
+            .header(header::CONTENT_TYPE, "application/json")
+            .set_json(&ConnectPeerRequest {
+                endpoint: "".into(),
+            })
+            .to_request();
+
+        let resp = test::call_service(&mut app, req);
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // Verify a valid request gets an ACCEPTED response, and the connection is requested
+        let req = test::TestRequest::post()
+            .uri("/admin/peers")
+            .header(header::CONTENT_TYPE, "application/json")
+            .set_json(&ConnectPeerRequest {
+                endpoint: "inproc://peer-123".into(),
+            })
+            .to_request();
+
+        let resp = test::call_service(&mut app, req);
+
+        assert_eq!(resp.status(), StatusCode::ACCEPTED);
+        assert_eq!(
+            connector.list_connections().unwrap(),
+            vec!["inproc://peer-123".to_string()]
+        );
+
+        cm.shutdown_and_wait();
+    }
+
+    #[test]
+    /// Test the DELETE /admin/peers/{id} route for disconnecting a peer.
+    fn test_disconnect_peer() {
+        let mut transport = InprocTransport::default();
+        let network = Network::new(Mesh::new(512, 128), 0).unwrap();
+        let _remote = add_peer(&mut transport, &network, "123", "inproc://peer-123");
+
+        let mut cm = ConnectionManager::new(
+            network.clone(),
+            network.clone(),
+            Box::new(transport),
+            None,
+            None,
+        );
+        let connector = cm.start().unwrap();
+
+        let mut app = test::init_service(
+            App::new()
+                .data(network.clone())
+                .data(connector.clone())
+                .service(
+                    web::resource("/admin/peers/{id}")
+                        .route(web::delete().to_async(disconnect_peer)),
+                ),
+        );
+
+        // Verify that an existing peer gets an OK response, and is disconnected
+        let req = test::TestRequest::delete()
+            .uri("/admin/peers/123")
+            .to_request();
+
+        let resp = test::call_service(&mut app, req);
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(network.peer_ids().is_empty());
+
+        // Verify that a non-existent peer gets a NOT_FOUND response
+        let req = test::TestRequest::delete()
+            .uri("/admin/peers/123")
+            .to_request();
+
+        let resp = test::call_service(&mut app, req);
+
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        cm.shutdown_and_wait();
+    }
+
+    /// Adds a connection to the given endpoint to the network as the peer with the given id, and
+    /// returns the remote end of the connection.
+    fn add_peer(
+        transport: &mut InprocTransport,
+        network: &Network,
+        peer_id: &str,
+        endpoint: &str,
+    ) -> Box<dyn Connection> {
+        let mut listener = transport.listen(endpoint).unwrap();
+        let join_handle = thread::spawn(move || listener.accept().unwrap());
+        let connection = transport.connect(endpoint).unwrap();
+        network.add_peer(peer_id.into(), connection).unwrap();
+        join_handle.join().unwrap()
+    }
+}
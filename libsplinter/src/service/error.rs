@@ -202,6 +202,57 @@ impl std::fmt::Display for ServiceDestroyError {
     }
 }
 
+#[derive(Debug)]
+pub enum ServicePurgeError {
+    NotStopped,
+    Internal(Box<dyn Error + Send>),
+    PoisonedLock(String),
+}
+
+impl Error for ServicePurgeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ServicePurgeError::NotStopped => None,
+            ServicePurgeError::Internal(err) => Some(&**err),
+            ServicePurgeError::PoisonedLock(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ServicePurgeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ServicePurgeError::NotStopped => write!(f, "service not stopped"),
+            ServicePurgeError::Internal(err) => write!(f, "unable to purge service: {}", err),
+            ServicePurgeError::PoisonedLock(msg) => write!(f, "a lock was poisoned: {}", msg),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ServiceUpdateError {
+    Internal(Box<dyn Error + Send>),
+    PoisonedLock(String),
+}
+
+impl Error for ServiceUpdateError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ServiceUpdateError::Internal(err) => Some(&**err),
+            ServiceUpdateError::PoisonedLock(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ServiceUpdateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ServiceUpdateError::Internal(err) => write!(f, "unable to update service: {}", err),
+            ServiceUpdateError::PoisonedLock(msg) => write!(f, "a lock was poisoned: {}", msg),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ServiceError {
     /// Returned if an error is detected when creating a service
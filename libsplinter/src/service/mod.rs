@@ -56,7 +56,8 @@ pub use registry::StandardServiceNetworkRegistry;
 
 pub use error::{
     FactoryCreateError, ServiceConnectionError, ServiceDestroyError, ServiceDisconnectionError,
-    ServiceError, ServiceProcessorError, ServiceSendError, ServiceStartError, ServiceStopError,
+    ServiceError, ServiceProcessorError, ServicePurgeError, ServiceSendError, ServiceStartError,
+    ServiceStopError, ServiceUpdateError,
 };
 
 /// The ServiceMessageContext is a struct that provides information about an incoming message.
@@ -140,6 +141,27 @@ pub trait Service: Send {
     /// this must take a boxed Service instance).
     fn destroy(self: Box<Self>) -> Result<(), ServiceDestroyError>;
 
+    /// Remove any persistent state held by the service.
+    ///
+    /// This is called when the circuit the service belongs to is destroyed or abandoned, after
+    /// the service has been stopped and before it is destroyed. Services that do not persist any
+    /// state do not need to override the default implementation.
+    fn purge(&mut self) -> Result<(), ServicePurgeError> {
+        Ok(())
+    }
+
+    /// Update the set of peer services while the service is running.
+    ///
+    /// Peer services are the other services of the same type on the service's circuit. This is
+    /// called when the circuit's roster or membership changes. Services that do not communicate
+    /// with their peers do not need to override the default implementation.
+    fn update_peer_services(
+        &mut self,
+        _peer_services: &[String],
+    ) -> Result<(), ServiceUpdateError> {
+        Ok(())
+    }
+
     /// Handle any incoming message intended for this service instance.
     ///
     /// Messages recevied by this service are provided in raw bytes.  The format of the service
@@ -18,7 +18,7 @@ use std::sync::{Arc, Mutex};
 use std::thread::{Builder, JoinHandle};
 use std::time::Duration;
 
-use protobuf::Message;
+use protobuf::{Message, RepeatedField};
 use transact::protos::IntoBytes;
 
 use crate::consensus::two_phase::TwoPhaseEngine;
@@ -28,6 +28,7 @@ use crate::consensus::{
     ProposalManager, ProposalUpdate, StartupState,
 };
 use crate::protos::scabbard::{ProposedBatch, ScabbardMessage, ScabbardMessage_Type};
+use crate::protos::two_phase::RequiredVerifiers;
 
 use super::error::{ScabbardConsensusManagerError, ScabbardError};
 use super::shared::ScabbardShared;
@@ -181,6 +182,20 @@ impl ProposalManager for ScabbardProposalManager {
             proposal.id = expected_hash.as_bytes().into();
             proposal.summary = expected_hash.as_bytes().into();
 
+            // The peer services may change while the service is running, so the current set of
+            // verifiers is included with the proposal rather than relying on the startup peers.
+            let mut verifiers = shared
+                .peer_services()
+                .iter()
+                .map(|service| service.as_bytes().to_vec())
+                .collect::<Vec<_>>();
+            verifiers.push(self.service_id.as_bytes().to_vec());
+            let mut required_verifiers = RequiredVerifiers::new();
+            required_verifiers.set_verifiers(RepeatedField::from_vec(verifiers));
+            proposal.consensus_data = required_verifiers
+                .write_to_bytes()
+                .map_err(|err| ProposalManagerError::Internal(Box::new(err)))?;
+
             shared.add_proposed_batch(proposal.id.clone(), batch.clone());
 
             // Send the proposal to the other services
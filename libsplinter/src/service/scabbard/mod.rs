@@ -29,7 +29,8 @@ mod state;
 use std::any::Any;
 use std::collections::{HashSet, VecDeque};
 use std::convert::TryFrom;
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
@@ -44,7 +45,7 @@ use crate::signing::SignatureVerifier;
 
 use super::{
     Service, ServiceDestroyError, ServiceError, ServiceMessageContext, ServiceNetworkRegistry,
-    ServiceStartError, ServiceStopError,
+    ServicePurgeError, ServiceStartError, ServiceStopError, ServiceUpdateError,
 };
 
 use consensus::ScabbardConsensusManager;
@@ -72,6 +73,9 @@ pub struct Scabbard {
     /// The coordinator timeout for the two-phase commit consensus engine
     coordinator_timeout: Duration,
     consensus: Arc<Mutex<Option<ScabbardConsensusManager>>>,
+    /// The LMDB files backing this service's state and receipt stores
+    state_db_path: PathBuf,
+    receipt_db_path: PathBuf,
 }
 
 impl Scabbard {
@@ -126,6 +130,8 @@ impl Scabbard {
             state: Arc::new(Mutex::new(state)),
             coordinator_timeout,
             consensus: Arc::new(Mutex::new(None)),
+            state_db_path,
+            receipt_db_path,
         })
     }
 
@@ -311,6 +317,42 @@ impl Service for Scabbard {
         }
     }
 
+    /// Removes the LMDB files (and their lock files) that back the state and receipt stores.
+    fn purge(&mut self) -> Result<(), ServicePurgeError> {
+        if self
+            .consensus
+            .lock()
+            .map_err(|_| ServicePurgeError::PoisonedLock("consensus lock poisoned".into()))?
+            .is_some()
+        {
+            return Err(ServicePurgeError::NotStopped);
+        }
+
+        for db_path in &[&self.state_db_path, &self.receipt_db_path] {
+            let lock_path = PathBuf::from(format!("{}-lock", db_path.display()));
+            for path in &[db_path.to_path_buf(), lock_path] {
+                if path.exists() {
+                    debug!("Removing scabbard database file {}", path.display());
+                    fs::remove_file(path)
+                        .map_err(|err| ServicePurgeError::Internal(Box::new(err)))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the set of scabbard services that this service shares state with. Proposals made
+    /// after the update require verification from the new set of peers.
+    fn update_peer_services(&mut self, peer_services: &[String]) -> Result<(), ServiceUpdateError> {
+        self.shared
+            .lock()
+            .map_err(|_| ServiceUpdateError::PoisonedLock("shared lock poisoned".into()))?
+            .set_peer_services(peer_services.iter().cloned().collect());
+
+        Ok(())
+    }
+
     fn handle_message(
         &self,
         message_bytes: &[u8],
@@ -414,6 +456,76 @@ pub mod tests {
         service.stop(&registry).expect("failed to stop service");
     }
 
+    /// Tests that purging a stopped scabbard service removes its LMDB files.
+    #[test]
+    fn purge_removes_db_files() {
+        let mut service = Scabbard::new(
+            "purge_removes_db_files".into(),
+            "test_circuit",
+            HashSet::new(),
+            Path::new("/tmp"),
+            1024 * 1024,
+            Path::new("/tmp"),
+            1024 * 1024,
+            Box::new(HashVerifier),
+            vec![],
+            None,
+        )
+        .expect("failed to create service");
+        assert!(service.state_db_path.exists());
+        assert!(service.receipt_db_path.exists());
+
+        let registry = MockServiceNetworkRegistry::new();
+        service.start(&registry).expect("failed to start service");
+        match service.purge() {
+            Err(ServicePurgeError::NotStopped) => (),
+            res => panic!("Expected Err(NotStopped), got {:?}", res),
+        }
+        service.stop(&registry).expect("failed to stop service");
+
+        service.purge().expect("failed to purge service");
+        assert!(!service.state_db_path.exists());
+        assert!(!service.receipt_db_path.exists());
+    }
+
+    /// Tests that updating a scabbard service's peer services replaces the set of peers.
+    #[test]
+    fn update_peer_services() {
+        let mut peer_services = HashSet::new();
+        peer_services.insert("1".to_string());
+        peer_services.insert("2".to_string());
+
+        let mut service = Scabbard::new(
+            "update_peer_services".into(),
+            "test_circuit",
+            peer_services,
+            Path::new("/tmp"),
+            1024 * 1024,
+            Path::new("/tmp"),
+            1024 * 1024,
+            Box::new(HashVerifier),
+            vec![],
+            None,
+        )
+        .expect("failed to create service");
+
+        service
+            .update_peer_services(&["2".to_string(), "3".to_string()])
+            .expect("failed to update peer services");
+
+        let mut expected = HashSet::new();
+        expected.insert("2".to_string());
+        expected.insert("3".to_string());
+        assert_eq!(
+            service
+                .shared
+                .lock()
+                .expect("shared lock poisoned")
+                .peer_services(),
+            &expected
+        );
+    }
+
     /// Tests that the service properly connects and disconnects using the network registry.
     #[test]
     fn connect_and_disconnect() {
@@ -81,6 +81,10 @@ impl ScabbardShared {
         &self.peer_services
     }
 
+    pub fn set_peer_services(&mut self, peer_services: HashSet<String>) {
+        self.peer_services = peer_services
+    }
+
     pub fn add_proposed_batch(
         &mut self,
         proposal_id: ProposalId,
@@ -0,0 +1,534 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Database-backed implementations of the DurableSet traits.
+//!
+//! The items of every set are stored in a single table, as JSON, keyed by the name of the set and
+//! the byte encoding of their index.
+
+#[cfg(feature = "postgres")]
+pub mod postgres;
+mod schema;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+use std::ops::Bound;
+
+use diesel::{
+    dsl::{delete, insert_into},
+    prelude::*,
+    r2d2::{ConnectionManager, Pool, PooledConnection},
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{encode_range, DurableIndex, DurableOrderedSet, DurableRange};
+use super::{DurableSet, DurableSetError};
+use schema::durable_set_items;
+
+/// The number of items loaded at a time by the iterators of a DieselOrderedSet.
+const PAGE_SIZE: i64 = 100;
+
+/// Creates the ordered set with the given name, stored in the database at the given URL. The
+/// URL's scheme selects the backend: `postgres://` or `sqlite://`.
+///
+/// The durable set migrations must have been run against the database.
+pub fn create_ordered_set<V, Index>(
+    database_url: &str,
+    set_name: &str,
+) -> Result<Box<dyn DurableOrderedSet<V, Index>>, DurableSetError>
+where
+    Index: DurableIndex + 'static,
+    V: Send + Ord + Borrow<Index> + Serialize + DeserializeOwned + 'static,
+{
+    #[cfg(feature = "postgres")]
+    {
+        if database_url.starts_with("postgres://") {
+            return Ok(
+                DieselOrderedSet::<diesel::pg::PgConnection, V, Index>::new_boxed(
+                    create_set_pool(database_url)?,
+                    set_name,
+                ),
+            );
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    {
+        if database_url.starts_with("sqlite://") {
+            return Ok(
+                DieselOrderedSet::<diesel::sqlite::SqliteConnection, V, Index>::new_boxed(
+                    create_set_pool(database_url)?,
+                    set_name,
+                ),
+            );
+        }
+    }
+
+    Err(DurableSetError::new(&format!(
+        "Unsupported database URL: {}",
+        database_url
+    )))
+}
+
+fn create_set_pool<C>(database_url: &str) -> Result<Pool<ConnectionManager<C>>, DurableSetError>
+where
+    C: diesel::Connection + Send + 'static,
+{
+    crate::database::create_pool(database_url)
+        .map_err(|err| DurableSetError::new(&format!("Unable to connect to database: {}", err)))
+}
+
+#[derive(Insertable, Queryable)]
+#[table_name = "durable_set_items"]
+pub struct ItemModel {
+    set_name: String,
+    index_key: Vec<u8>,
+    /// The item, as JSON
+    item: String,
+}
+
+/// A database connection that can store the items of a DieselOrderedSet.
+pub trait DurableSetConnection: diesel::Connection + Send + 'static {
+    /// Stores the given item, replacing any item of the same set with the same index.
+    fn replace_item(&self, item: &ItemModel) -> QueryResult<()>;
+}
+
+#[cfg(feature = "postgres")]
+impl DurableSetConnection for diesel::pg::PgConnection {
+    fn replace_item(&self, item: &ItemModel) -> QueryResult<()> {
+        self.transaction(|| {
+            delete_item(self, &item.set_name, &item.index_key)?;
+            insert_into(durable_set_items::table)
+                .values(item)
+                .execute(self)
+                .map(|_| ())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl DurableSetConnection for diesel::sqlite::SqliteConnection {
+    fn replace_item(&self, item: &ItemModel) -> QueryResult<()> {
+        self.transaction(|| {
+            delete_item(self, &item.set_name, &item.index_key)?;
+            insert_into(durable_set_items::table)
+                .values(item)
+                .execute(self)
+                .map(|_| ())
+        })
+    }
+}
+
+fn delete_item<C>(conn: &C, set_name: &str, index_key: &[u8]) -> QueryResult<usize>
+where
+    C: diesel::Connection,
+    <C as diesel::Connection>::Backend: 'static,
+{
+    delete(
+        durable_set_items::table.filter(
+            durable_set_items::set_name
+                .eq(set_name)
+                .and(durable_set_items::index_key.eq(index_key)),
+        ),
+    )
+    .execute(conn)
+}
+
+/// A persistent DurableOrderedSet, backed by a table in a database.
+///
+/// Several sets may share a database, as long as each has a distinct name. The set holds one item
+/// per index value; adding an item replaces any item with the same index. This set is unbounded.
+pub struct DieselOrderedSet<C, V, Index>
+where
+    C: DurableSetConnection,
+{
+    pool: Pool<ConnectionManager<C>>,
+    set_name: String,
+    _types: PhantomData<fn() -> (V, Index)>,
+}
+
+impl<C, V, Index> DieselOrderedSet<C, V, Index>
+where
+    C: DurableSetConnection,
+    <C as diesel::Connection>::Backend: 'static,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+    Vec<u8>: diesel::deserialize::FromSql<diesel::sql_types::Binary, C::Backend>,
+    Index: DurableIndex + 'static,
+    V: Send + Ord + Borrow<Index> + Serialize + DeserializeOwned + 'static,
+{
+    /// Constructs the set with the given name, stored in the database of the given pool. The
+    /// durable set migrations must have been run against the database.
+    pub fn new(pool: Pool<ConnectionManager<C>>, set_name: &str) -> Self {
+        Self {
+            pool,
+            set_name: set_name.into(),
+            _types: PhantomData,
+        }
+    }
+
+    /// Constructs the set with the given name, as a boxed DurableOrderedSet.
+    pub fn new_boxed(
+        pool: Pool<ConnectionManager<C>>,
+        set_name: &str,
+    ) -> Box<dyn DurableOrderedSet<V, Index>> {
+        Box::new(Self::new(pool, set_name))
+    }
+
+    fn connection(&self) -> Result<PooledConnection<ConnectionManager<C>>, DurableSetError> {
+        self.pool.get().map_err(|err| {
+            DurableSetError::with_source("Unable to get database connection", Box::new(err))
+        })
+    }
+
+    /// Returns the encoded index and the item of at most `PAGE_SIZE` entries of the set, in index
+    /// order, whose encoded indexes are at least `start` and, if given, less than `end`.
+    fn load_page(
+        &self,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> Result<Vec<(Vec<u8>, V)>, DurableSetError> {
+        let query = durable_set_items::table
+            .filter(durable_set_items::set_name.eq(&self.set_name))
+            .filter(durable_set_items::index_key.ge(start))
+            .order(durable_set_items::index_key.asc())
+            .limit(PAGE_SIZE);
+        let conn = self.connection()?;
+        match end {
+            Some(end) => query
+                .filter(durable_set_items::index_key.lt(end))
+                .load::<ItemModel>(&*conn),
+            None => query.load::<ItemModel>(&*conn),
+        }
+        .map_err(|err| DurableSetError::with_source("Unable to load items", Box::new(err)))?
+        .into_iter()
+        .map(|model| Ok((model.index_key, decode(&model.item)?)))
+        .collect()
+    }
+
+    /// Returns the item with the lowest encoded index if `ascending`, or the highest otherwise.
+    fn end_item(&self, ascending: bool) -> Result<Option<V>, DurableSetError> {
+        let query = durable_set_items::table.filter(durable_set_items::set_name.eq(&self.set_name));
+        let conn = self.connection()?;
+        if ascending {
+            query
+                .order(durable_set_items::index_key.asc())
+                .first::<ItemModel>(&*conn)
+        } else {
+            query
+                .order(durable_set_items::index_key.desc())
+                .first::<ItemModel>(&*conn)
+        }
+        .optional()
+        .map_err(|err| DurableSetError::with_source("Unable to get item", Box::new(err)))?
+        .map(|model| decode(&model.item))
+        .transpose()
+    }
+
+    fn get_by_key(&self, index_key: &[u8]) -> Result<Option<V>, DurableSetError> {
+        durable_set_items::table
+            .filter(
+                durable_set_items::set_name
+                    .eq(&self.set_name)
+                    .and(durable_set_items::index_key.eq(index_key)),
+            )
+            .first::<ItemModel>(&*self.connection()?)
+            .optional()
+            .map_err(|err| DurableSetError::with_source("Unable to get item", Box::new(err)))?
+            .map(|model| decode(&model.item))
+            .transpose()
+    }
+}
+
+impl<C, V, Index> DurableSet for DieselOrderedSet<C, V, Index>
+where
+    C: DurableSetConnection,
+    <C as diesel::Connection>::Backend: 'static,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+    Vec<u8>: diesel::deserialize::FromSql<diesel::sql_types::Binary, C::Backend>,
+    i64: diesel::deserialize::FromSql<diesel::sql_types::BigInt, C::Backend>,
+    Index: DurableIndex + 'static,
+    V: Send + Ord + Borrow<Index> + Serialize + DeserializeOwned + 'static,
+{
+    type Item = V;
+
+    fn add(&mut self, item: Self::Item) -> Result<(), DurableSetError> {
+        let model = ItemModel {
+            set_name: self.set_name.clone(),
+            index_key: item.borrow().to_index_bytes(),
+            item: serde_json::to_string(&item).map_err(|err| {
+                DurableSetError::with_source("Unable to encode item", Box::new(err))
+            })?,
+        };
+
+        self.connection()?
+            .replace_item(&model)
+            .map_err(|err| DurableSetError::with_source("Unable to insert item", Box::new(err)))
+    }
+
+    fn remove(&mut self, item: &Self::Item) -> Result<Option<Self::Item>, DurableSetError> {
+        let index_key = item.borrow().to_index_bytes();
+        let existing = self.get_by_key(&index_key)?;
+        if existing.is_some() {
+            delete_item(&*self.connection()?, &self.set_name, &index_key).map_err(|err| {
+                DurableSetError::with_source("Unable to remove item", Box::new(err))
+            })?;
+        }
+
+        Ok(existing)
+    }
+
+    fn contains(&self, item: &Self::Item) -> Result<bool, DurableSetError> {
+        Ok(self.get_by_key(&item.borrow().to_index_bytes())?.is_some())
+    }
+
+    fn iter<'a>(&'a self) -> Result<Box<(dyn Iterator<Item = Self::Item> + 'a)>, DurableSetError> {
+        self.range_iter((..).into())
+    }
+
+    fn len(&self) -> Result<u64, DurableSetError> {
+        durable_set_items::table
+            .filter(durable_set_items::set_name.eq(&self.set_name))
+            .count()
+            .get_result::<i64>(&*self.connection()?)
+            .map(|count| count as u64)
+            .map_err(|err| DurableSetError::with_source("Unable to count items", Box::new(err)))
+    }
+}
+
+impl<C, V, Index> DurableOrderedSet<V, Index> for DieselOrderedSet<C, V, Index>
+where
+    C: DurableSetConnection,
+    <C as diesel::Connection>::Backend: 'static,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+    Vec<u8>: diesel::deserialize::FromSql<diesel::sql_types::Binary, C::Backend>,
+    i64: diesel::deserialize::FromSql<diesel::sql_types::BigInt, C::Backend>,
+    Index: DurableIndex + 'static,
+    V: Send + Ord + Borrow<Index> + Serialize + DeserializeOwned + 'static,
+{
+    fn get_by_index(&self, index_value: &Index) -> Result<Option<Self::Item>, DurableSetError> {
+        self.get_by_key(&index_value.to_index_bytes())
+    }
+
+    fn contains_by_index(&self, index_value: &Index) -> Result<bool, DurableSetError> {
+        Ok(self.get_by_index(index_value)?.is_some())
+    }
+
+    fn range_iter<'a>(
+        &'a self,
+        range: DurableRange<&Index>,
+    ) -> Result<Box<(dyn Iterator<Item = Self::Item> + 'a)>, DurableSetError> {
+        // Every bound is expressed as an inclusive start and an exclusive end, as the smallest
+        // encoded index after a given one is the given one followed by a zero byte
+        let range = encode_range(&range);
+        let start = match range.start {
+            Bound::Included(start) => start,
+            Bound::Excluded(start) => successor(start),
+            Bound::Unbounded => vec![],
+        };
+        let end = match range.end {
+            Bound::Included(end) => Some(successor(end)),
+            Bound::Excluded(end) => Some(end),
+            Bound::Unbounded => None,
+        };
+
+        let mut iter = PagedIter {
+            set: self,
+            page: vec![].into_iter(),
+            next_start: Some(start),
+            end,
+        };
+        // the first page is loaded now, so that errors reading the set are returned
+        iter.load_next_page()?;
+
+        Ok(Box::new(iter))
+    }
+
+    fn first(&self) -> Result<Option<Self::Item>, DurableSetError> {
+        self.end_item(true)
+    }
+
+    fn last(&self) -> Result<Option<Self::Item>, DurableSetError> {
+        self.end_item(false)
+    }
+
+    fn clone_boxed_ordered_set(&self) -> Box<dyn DurableOrderedSet<V, Index>> {
+        Box::new(Self {
+            pool: self.pool.clone(),
+            set_name: self.set_name.clone(),
+            _types: PhantomData,
+        })
+    }
+}
+
+/// Iterates over the items of a DieselOrderedSet within a range, loading them a page at a time.
+struct PagedIter<'a, C, V, Index>
+where
+    C: DurableSetConnection,
+{
+    set: &'a DieselOrderedSet<C, V, Index>,
+    page: std::vec::IntoIter<V>,
+    /// The encoded index the next page starts at, or `None` if the last page has been loaded
+    next_start: Option<Vec<u8>>,
+    end: Option<Vec<u8>>,
+}
+
+impl<'a, C, V, Index> PagedIter<'a, C, V, Index>
+where
+    C: DurableSetConnection,
+    <C as diesel::Connection>::Backend: 'static,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+    Vec<u8>: diesel::deserialize::FromSql<diesel::sql_types::Binary, C::Backend>,
+    Index: DurableIndex + 'static,
+    V: Send + Ord + Borrow<Index> + Serialize + DeserializeOwned + 'static,
+{
+    fn load_next_page(&mut self) -> Result<(), DurableSetError> {
+        let start = match self.next_start.take() {
+            Some(start) => start,
+            None => return Ok(()),
+        };
+
+        let entries = self.set.load_page(&start, self.end.as_deref())?;
+        if entries.len() as i64 == PAGE_SIZE {
+            self.next_start = entries.last().map(|(key, _)| successor(key.clone()));
+        }
+        self.page = entries
+            .into_iter()
+            .map(|(_, item)| item)
+            .collect::<Vec<_>>()
+            .into_iter();
+
+        Ok(())
+    }
+}
+
+impl<'a, C, V, Index> Iterator for PagedIter<'a, C, V, Index>
+where
+    C: DurableSetConnection,
+    <C as diesel::Connection>::Backend: 'static,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+    Vec<u8>: diesel::deserialize::FromSql<diesel::sql_types::Binary, C::Backend>,
+    Index: DurableIndex + 'static,
+    V: Send + Ord + Borrow<Index> + Serialize + DeserializeOwned + 'static,
+{
+    type Item = V;
+
+    fn next(&mut self) -> Option<V> {
+        if let Some(item) = self.page.next() {
+            return Some(item);
+        }
+        if self.next_start.is_none() {
+            return None;
+        }
+
+        if let Err(err) = self.load_next_page() {
+            error!(
+                "Unable to load the next items of durable set {}: {}",
+                self.set.set_name, err
+            );
+            return None;
+        }
+        self.page.next()
+    }
+}
+
+/// Returns the smallest encoded index that sorts after the given one.
+fn successor(mut index_bytes: Vec<u8>) -> Vec<u8> {
+    index_bytes.push(0);
+    index_bytes
+}
+
+fn decode<V: DeserializeOwned>(item: &str) -> Result<V, DurableSetError> {
+    serde_json::from_str(item)
+        .map_err(|err| DurableSetError::with_source("Unable to decode item", Box::new(err)))
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    use diesel::sqlite::SqliteConnection;
+
+    /// Test that items added to a SQLite-backed set are returned in index order, and that sets
+    /// sharing a database do not see each other's items.
+    #[test]
+    fn test_sqlite_ordered_set() {
+        // An in-memory database only lives as long as its connection, so the pool must hold one
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+            .expect("Unable to create pool");
+        sqlite::run_migrations(&*pool.get().expect("Unable to get connection"))
+            .expect("Unable to run migrations");
+
+        let mut set = DieselOrderedSet::<_, u64, u64>::new(pool.clone(), "numbers");
+        let mut other = DieselOrderedSet::<_, u64, u64>::new(pool, "other");
+        for i in &[300, 2, 10, 10] {
+            set.add(*i).expect("Unable to add item");
+        }
+        other.add(5).expect("Unable to add item");
+
+        assert_eq!(3, set.len().expect("Unable to get len"));
+        assert_eq!(
+            vec![2, 10, 300],
+            set.iter().expect("Unable to iterate").collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![10],
+            set.range_iter((&3..&300).into())
+                .expect("Unable to iterate")
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some(300), set.last().expect("Unable to get last"));
+
+        assert_eq!(Some(10), set.remove(&10).expect("Unable to remove"));
+        assert!(!set.contains_by_index(&10).expect("Unable to check index"));
+        assert_eq!(
+            vec![5],
+            other.iter().expect("Unable to iterate").collect::<Vec<_>>()
+        );
+    }
+
+    /// Test that iterating over a set with more items than fit in a page returns every item in
+    /// the range, in index order.
+    #[test]
+    fn test_sqlite_ordered_set_pages() {
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+            .expect("Unable to create pool");
+        sqlite::run_migrations(&*pool.get().expect("Unable to get connection"))
+            .expect("Unable to run migrations");
+
+        let mut set = DieselOrderedSet::<_, u64, u64>::new(pool, "numbers");
+        for i in 0..250 {
+            set.add(i).expect("Unable to add item");
+        }
+
+        assert_eq!(250, set.len().expect("Unable to get len"));
+        assert_eq!(
+            (0..250).collect::<Vec<_>>(),
+            set.iter().expect("Unable to iterate").collect::<Vec<_>>()
+        );
+        assert_eq!(
+            (6..=220).collect::<Vec<_>>(),
+            set.range_iter((Bound::Excluded(&5), Bound::Included(&220)).into())
+                .expect("Unable to iterate")
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some(0), set.first().expect("Unable to get first"));
+        assert_eq!(Some(249), set.last().expect("Unable to get last"));
+    }
+}
@@ -0,0 +1,312 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! LMDB-backed implementations of the DurableSet traits.
+//!
+//! Items are stored as JSON, keyed by the byte encoding of their index, so they survive a restart
+//! of the process that stored them.
+
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+use std::path::Path;
+
+use serde::{de::DeserializeOwned, Serialize};
+use transact::database::{
+    lmdb::{LmdbContext, LmdbDatabase},
+    Database,
+};
+
+use super::{
+    encode_range, is_after_range_start, is_before_range_end, DurableIndex, DurableOrderedSet,
+    DurableRange,
+};
+use super::{DurableSet, DurableSetError};
+
+/// The default maximum size of the LMDB file, in bytes.
+pub const DEFAULT_LMDB_SIZE: usize = 1 << 30; // 1024 ** 3
+
+/// A persistent DurableOrderedSet, backed by an LMDB file.
+///
+/// The set holds one item per index value; adding an item replaces any item with the same index.
+/// This set is unbounded.
+pub struct LmdbOrderedSet<V, Index> {
+    db: Box<dyn Database>,
+    _types: PhantomData<fn() -> (V, Index)>,
+}
+
+impl<V, Index> LmdbOrderedSet<V, Index>
+where
+    Index: DurableIndex + 'static,
+    V: Send + Ord + Borrow<Index> + Serialize + DeserializeOwned + 'static,
+{
+    /// Opens the set stored in the LMDB file at the given path, creating the file if it does not
+    /// exist. The file may grow to `size` bytes, or to `DEFAULT_LMDB_SIZE` if `None`.
+    pub fn new(path: &Path, size: Option<usize>) -> Result<Self, DurableSetError> {
+        let context =
+            LmdbContext::new(path, 0, Some(size.unwrap_or(DEFAULT_LMDB_SIZE))).map_err(|err| {
+                DurableSetError::new(&format!("Unable to open LMDB file {:?}: {}", path, err))
+            })?;
+        let db = LmdbDatabase::new(context, &[]).map_err(|err| {
+            DurableSetError::new(&format!("Unable to open LMDB database {:?}: {}", path, err))
+        })?;
+
+        Ok(Self {
+            db: Box::new(db),
+            _types: PhantomData,
+        })
+    }
+
+    /// Opens the set stored in the LMDB file at the given path, as a boxed DurableOrderedSet.
+    pub fn new_boxed(
+        path: &Path,
+        size: Option<usize>,
+    ) -> Result<Box<dyn DurableOrderedSet<V, Index>>, DurableSetError> {
+        Ok(Box::new(Self::new(path, size)?))
+    }
+
+    fn key(item: &V) -> Vec<u8> {
+        item.borrow().to_index_bytes()
+    }
+
+    /// Returns the encoded index and the item of each entry in the set, in index order.
+    fn entries(&self) -> Result<Vec<(Vec<u8>, V)>, DurableSetError> {
+        let reader = self.db.get_reader().map_err(|err| {
+            DurableSetError::new(&format!("Unable to read LMDB database: {}", err))
+        })?;
+        let cursor = reader.cursor().map_err(|err| {
+            DurableSetError::new(&format!("Unable to iterate over LMDB database: {}", err))
+        })?;
+
+        cursor
+            .map(|(key, value)| Ok((key, decode(&value)?)))
+            .collect()
+    }
+
+    fn get_by_key(&self, key: &[u8]) -> Result<Option<V>, DurableSetError> {
+        let reader = self.db.get_reader().map_err(|err| {
+            DurableSetError::new(&format!("Unable to read LMDB database: {}", err))
+        })?;
+
+        reader.get(key).map(|value| decode(&value)).transpose()
+    }
+}
+
+impl<V, Index> DurableSet for LmdbOrderedSet<V, Index>
+where
+    Index: DurableIndex + 'static,
+    V: Send + Ord + Borrow<Index> + Serialize + DeserializeOwned + 'static,
+{
+    type Item = V;
+
+    fn add(&mut self, item: Self::Item) -> Result<(), DurableSetError> {
+        let value = serde_json::to_vec(&item)
+            .map_err(|err| DurableSetError::with_source("Unable to encode item", Box::new(err)))?;
+
+        let mut writer = self.db.get_writer().map_err(|err| {
+            DurableSetError::new(&format!("Unable to write LMDB database: {}", err))
+        })?;
+        writer
+            .overwrite(&Self::key(&item), &value)
+            .map_err(|err| DurableSetError::new(&format!("Unable to insert item: {}", err)))?;
+        writer
+            .commit()
+            .map_err(|err| DurableSetError::new(&format!("Unable to commit item: {}", err)))
+    }
+
+    fn remove(&mut self, item: &Self::Item) -> Result<Option<Self::Item>, DurableSetError> {
+        let key = Self::key(item);
+        let existing = self.get_by_key(&key)?;
+        if existing.is_none() {
+            return Ok(None);
+        }
+
+        let mut writer = self.db.get_writer().map_err(|err| {
+            DurableSetError::new(&format!("Unable to write LMDB database: {}", err))
+        })?;
+        writer
+            .delete(&key)
+            .map_err(|err| DurableSetError::new(&format!("Unable to remove item: {}", err)))?;
+        writer
+            .commit()
+            .map_err(|err| DurableSetError::new(&format!("Unable to commit removal: {}", err)))?;
+
+        Ok(existing)
+    }
+
+    fn contains(&self, item: &Self::Item) -> Result<bool, DurableSetError> {
+        Ok(self.get_by_key(&Self::key(item))?.is_some())
+    }
+
+    fn iter<'a>(&'a self) -> Result<Box<(dyn Iterator<Item = Self::Item> + 'a)>, DurableSetError> {
+        Ok(Box::new(self.entries()?.into_iter().map(|(_, item)| item)))
+    }
+
+    fn len(&self) -> Result<u64, DurableSetError> {
+        self.db
+            .get_reader()
+            .and_then(|reader| reader.count())
+            .map(|count| count as u64)
+            .map_err(|err| DurableSetError::new(&format!("Unable to count items: {}", err)))
+    }
+}
+
+impl<V, Index> DurableOrderedSet<V, Index> for LmdbOrderedSet<V, Index>
+where
+    Index: DurableIndex + 'static,
+    V: Send + Ord + Borrow<Index> + Serialize + DeserializeOwned + 'static,
+{
+    fn get_by_index(&self, index_value: &Index) -> Result<Option<Self::Item>, DurableSetError> {
+        self.get_by_key(&index_value.to_index_bytes())
+    }
+
+    fn contains_by_index(&self, index_value: &Index) -> Result<bool, DurableSetError> {
+        Ok(self.get_by_index(index_value)?.is_some())
+    }
+
+    fn range_iter<'a>(
+        &'a self,
+        range: DurableRange<&Index>,
+    ) -> Result<Box<(dyn Iterator<Item = Self::Item> + 'a)>, DurableSetError> {
+        let range = encode_range(&range);
+        let reader = self.db.get_reader().map_err(|err| {
+            DurableSetError::new(&format!("Unable to read LMDB database: {}", err))
+        })?;
+        let cursor = reader.cursor().map_err(|err| {
+            DurableSetError::new(&format!("Unable to iterate over LMDB database: {}", err))
+        })?;
+
+        // The cursor visits the entries in key order, so only the entries in the range are
+        // decoded, and the entries after its end are not visited at all
+        let items = cursor
+            .skip_while(|(key, _)| !is_after_range_start(&range, key))
+            .take_while(|(key, _)| is_before_range_end(&range, key))
+            .map(|(_, value)| decode(&value))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Box::new(items.into_iter()))
+    }
+
+    fn first(&self) -> Result<Option<Self::Item>, DurableSetError> {
+        let reader = self.db.get_reader().map_err(|err| {
+            DurableSetError::new(&format!("Unable to read LMDB database: {}", err))
+        })?;
+        let mut cursor = reader.cursor().map_err(|err| {
+            DurableSetError::new(&format!("Unable to iterate over LMDB database: {}", err))
+        })?;
+
+        cursor
+            .seek_first()
+            .map(|(_, value)| decode(&value))
+            .transpose()
+    }
+
+    fn last(&self) -> Result<Option<Self::Item>, DurableSetError> {
+        let reader = self.db.get_reader().map_err(|err| {
+            DurableSetError::new(&format!("Unable to read LMDB database: {}", err))
+        })?;
+        let mut cursor = reader.cursor().map_err(|err| {
+            DurableSetError::new(&format!("Unable to iterate over LMDB database: {}", err))
+        })?;
+
+        cursor
+            .seek_last()
+            .map(|(_, value)| decode(&value))
+            .transpose()
+    }
+
+    fn clone_boxed_ordered_set(&self) -> Box<dyn DurableOrderedSet<V, Index>> {
+        Box::new(Self {
+            db: self.db.clone_box(),
+            _types: PhantomData,
+        })
+    }
+}
+
+fn decode<V: DeserializeOwned>(value: &[u8]) -> Result<V, DurableSetError> {
+    serde_json::from_slice(value)
+        .map_err(|err| DurableSetError::with_source("Unable to decode item", Box::new(err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::ops::Bound;
+
+    use tempdir::TempDir;
+
+    /// Test that items added to an LMDB set are returned in index order, replace items with the
+    /// same index, and are still present when the set is reopened.
+    #[test]
+    fn test_lmdb_add_and_reopen() {
+        let temp_dir = TempDir::new("test_lmdb_add_and_reopen").expect("Unable to create dir");
+        let path = temp_dir.path().join("set.lmdb");
+
+        {
+            let mut set = LmdbOrderedSet::<u64, u64>::new(&path, Some(1024 * 1024))
+                .expect("Unable to open set");
+            assert!(set.is_empty().expect("Unable to get is_empty"));
+            set.add(300).expect("Unable to add item");
+            set.add(2).expect("Unable to add item");
+            set.add(10).expect("Unable to add item");
+            set.add(10).expect("Unable to add item");
+        }
+
+        let set = LmdbOrderedSet::<u64, u64>::new(&path, Some(1024 * 1024))
+            .expect("Unable to reopen set");
+        assert_eq!(3, set.len().expect("Unable to get len"));
+        assert_eq!(
+            vec![2, 10, 300],
+            set.iter().expect("Unable to iterate").collect::<Vec<_>>()
+        );
+        assert_eq!(Some(2), set.first().expect("Unable to get first"));
+        assert_eq!(Some(300), set.last().expect("Unable to get last"));
+        assert!(set.contains_by_index(&10).expect("Unable to check index"));
+    }
+
+    /// Test that an LMDB set returns the items within a range, and that removed items are no
+    /// longer returned.
+    #[test]
+    fn test_lmdb_range_and_remove() {
+        let temp_dir = TempDir::new("test_lmdb_range_and_remove").expect("Unable to create dir");
+        let mut set =
+            LmdbOrderedSet::<u64, u64>::new(&temp_dir.path().join("set.lmdb"), Some(1024 * 1024))
+                .expect("Unable to open set");
+        for i in 0..10 {
+            set.add(i).expect("Unable to add item");
+        }
+
+        assert_eq!(
+            vec![3, 4, 5],
+            set.range_iter((&3..&6).into())
+                .expect("Unable to iterate")
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![8, 9],
+            set.range_iter((Bound::Excluded(&7), Bound::Unbounded).into())
+                .expect("Unable to iterate")
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(Some(4), set.remove(&4).expect("Unable to remove"));
+        assert_eq!(None, set.remove(&4).expect("Unable to remove"));
+        assert_eq!(
+            vec![3, 5],
+            set.range_iter((&3..&6).into())
+                .expect("Unable to iterate")
+                .collect::<Vec<_>>()
+        );
+    }
+}
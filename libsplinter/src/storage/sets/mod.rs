@@ -14,6 +14,10 @@
 
 //! Durable sets, both ordered and unordered. Implementations of these sets must be thread-safe.
 
+#[cfg(feature = "database")]
+pub mod diesel;
+#[cfg(feature = "lmdb-sets")]
+pub mod lmdb;
 pub mod mem;
 
 use std::borrow::Borrow;
@@ -21,6 +25,7 @@ use std::cmp::Ord;
 use std::error::Error;
 use std::fmt;
 use std::ops::{Bound, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// A Durable Set.
 ///
@@ -93,6 +98,77 @@ where
     }
 }
 
+/// An index that can be stored as a key in a persistent DurableOrderedSet.
+///
+/// The byte encoding of an index must sort in the same order as the index itself, as persistent
+/// sets order their items by the encoded index.
+pub trait DurableIndex: Ord + Send {
+    /// Encodes the index as bytes.
+    fn to_index_bytes(&self) -> Vec<u8>;
+}
+
+impl DurableIndex for u64 {
+    fn to_index_bytes(&self) -> Vec<u8> {
+        self.to_be_bytes().to_vec()
+    }
+}
+
+impl DurableIndex for String {
+    fn to_index_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+impl DurableIndex for SystemTime {
+    /// Encodes the time as the big-endian seconds and nanoseconds since the UNIX epoch; times
+    /// before the epoch are encoded as the epoch.
+    fn to_index_bytes(&self) -> Vec<u8> {
+        let since_epoch = self
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_else(|_| Duration::from_secs(0));
+        let mut bytes = since_epoch.as_secs().to_be_bytes().to_vec();
+        bytes.extend_from_slice(&since_epoch.subsec_nanos().to_be_bytes());
+        bytes
+    }
+}
+
+/// Returns the given range with its bounds encoded as index bytes.
+#[cfg(any(feature = "database", feature = "lmdb-sets"))]
+pub(crate) fn encode_range<Index: DurableIndex>(
+    range: &DurableRange<&Index>,
+) -> DurableRange<Vec<u8>> {
+    let encode = |bound: &Bound<&Index>| match bound {
+        Bound::Included(index) => Bound::Included(index.to_index_bytes()),
+        Bound::Excluded(index) => Bound::Excluded(index.to_index_bytes()),
+        Bound::Unbounded => Bound::Unbounded,
+    };
+
+    DurableRange {
+        start: encode(&range.start),
+        end: encode(&range.end),
+    }
+}
+
+/// Returns whether the given encoded index is at or after the start of the given encoded range.
+#[cfg(feature = "lmdb-sets")]
+pub(crate) fn is_after_range_start(range: &DurableRange<Vec<u8>>, index_bytes: &[u8]) -> bool {
+    match range.start {
+        Bound::Included(ref start) => index_bytes >= start.as_slice(),
+        Bound::Excluded(ref start) => index_bytes > start.as_slice(),
+        Bound::Unbounded => true,
+    }
+}
+
+/// Returns whether the given encoded index is at or before the end of the given encoded range.
+#[cfg(feature = "lmdb-sets")]
+pub(crate) fn is_before_range_end(range: &DurableRange<Vec<u8>>, index_bytes: &[u8]) -> bool {
+    match range.end {
+        Bound::Included(ref end) => index_bytes <= end.as_slice(),
+        Bound::Excluded(ref end) => index_bytes < end.as_slice(),
+        Bound::Unbounded => true,
+    }
+}
+
 /// A Range describing the start and end bounds for a range iterator on a DurableOrderedSet.
 ///
 /// This struct is similar to the various implementations of the RangeBounds trait in the standard
@@ -0,0 +1,583 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A transport wrapper that injects faults, for testing how nodes and services behave on an
+//! unreliable network.
+//!
+//! A `ChaosTransport` wraps another transport, such as `InprocTransport`, and applies the faults
+//! configured on its `ChaosControl` to the connections it creates. Faults are configured per
+//! endpoint: an outbound connection uses the endpoint it connected to, and an accepted connection
+//! uses the endpoint of the listener that accepted it. Transports that share a control therefore
+//! apply the same faults to both ends of a connection, which allows a multi-node network to be
+//! tested in a single process:
+//!
+//! ```ignore
+//! let control = ChaosControl::default();
+//! let inproc = InprocTransport::default();
+//! let mut node_a = ChaosTransport::new(inproc.clone()).with_control(control.clone());
+//! let mut node_b = ChaosTransport::new(inproc).with_control(control.clone());
+//!
+//! control.set_faults("inproc://node-b", Faults::default().with_drop_probability(0.2));
+//! control.partition("inproc://node-b");
+//! control.heal("inproc://node-b");
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use mio::Evented;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::{
+    AcceptError, ConnectError, Connection, DisconnectError, ListenError, Listener, PeerCertificate,
+    PeerCredentials, RecvError, SendError, Transport,
+};
+
+/// The faults injected into messages sent on a connection.
+///
+/// Each message sent is, in order: disconnected with the disconnect probability, dropped with the
+/// drop probability, delayed by the delay, and sent twice with the duplicate probability.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Faults {
+    delay: Duration,
+    drop_probability: f64,
+    duplicate_probability: f64,
+    disconnect_probability: f64,
+}
+
+impl Faults {
+    /// Delay each message sent by the given duration.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Drop messages sent with the given probability, between 0.0 and 1.0.
+    pub fn with_drop_probability(mut self, probability: f64) -> Self {
+        self.drop_probability = clamp_probability(probability);
+        self
+    }
+
+    /// Send messages twice with the given probability, between 0.0 and 1.0.
+    pub fn with_duplicate_probability(mut self, probability: f64) -> Self {
+        self.duplicate_probability = clamp_probability(probability);
+        self
+    }
+
+    /// Disconnect the connection when a message is sent with the given probability, between 0.0
+    /// and 1.0.
+    pub fn with_disconnect_probability(mut self, probability: f64) -> Self {
+        self.disconnect_probability = clamp_probability(probability);
+        self
+    }
+
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    pub fn drop_probability(&self) -> f64 {
+        self.drop_probability
+    }
+
+    pub fn duplicate_probability(&self) -> f64 {
+        self.duplicate_probability
+    }
+
+    pub fn disconnect_probability(&self) -> f64 {
+        self.disconnect_probability
+    }
+}
+
+fn clamp_probability(probability: f64) -> f64 {
+    if probability.is_nan() {
+        0.0
+    } else {
+        probability.max(0.0).min(1.0)
+    }
+}
+
+/// What happens to a message that is sent.
+enum Outcome {
+    Deliver { delay: Duration, duplicate: bool },
+    Drop,
+    Disconnect,
+}
+
+struct ChaosState {
+    default_faults: Faults,
+    faults: HashMap<String, Faults>,
+    partitions: HashSet<String>,
+    // The number of times the connections to each endpoint have been disconnected by the control
+    disconnects: HashMap<String, u64>,
+    rng: StdRng,
+}
+
+/// A handle for changing the faults injected by one or more `ChaosTransport`s at runtime.
+///
+/// Clones of a control share the same faults and partitions.
+#[derive(Clone)]
+pub struct ChaosControl {
+    state: Arc<Mutex<ChaosState>>,
+}
+
+impl Default for ChaosControl {
+    fn default() -> Self {
+        Self::with_rng(StdRng::from_entropy())
+    }
+}
+
+impl ChaosControl {
+    /// Creates a control whose faults are chosen using the given seed, so that a test injects the
+    /// same faults each time it runs with the same sequence of messages.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(rng: StdRng) -> Self {
+        ChaosControl {
+            state: Arc::new(Mutex::new(ChaosState {
+                default_faults: Faults::default(),
+                faults: HashMap::new(),
+                partitions: HashSet::new(),
+                disconnects: HashMap::new(),
+                rng,
+            })),
+        }
+    }
+
+    /// Sets the faults injected into connections to endpoints that have no faults of their own.
+    pub fn set_default_faults(&self, faults: Faults) {
+        self.lock().default_faults = faults;
+    }
+
+    /// Sets the faults injected into connections to the given endpoint.
+    pub fn set_faults(&self, endpoint: &str, faults: Faults) {
+        self.lock().faults.insert(endpoint.to_string(), faults);
+    }
+
+    /// Removes the faults of the given endpoint, so that the default faults are injected into its
+    /// connections.
+    pub fn clear_faults(&self, endpoint: &str) {
+        self.lock().faults.remove(endpoint);
+    }
+
+    /// Partitions the given endpoint from the network.
+    ///
+    /// While it is partitioned, new connections to the endpoint are refused, and messages sent
+    /// or received on its existing connections are silently dropped; the connections themselves
+    /// remain open, as they would on a real network.
+    pub fn partition(&self, endpoint: &str) {
+        self.lock().partitions.insert(endpoint.to_string());
+    }
+
+    /// Heals the partition of the given endpoint, if it is partitioned.
+    pub fn heal(&self, endpoint: &str) {
+        self.lock().partitions.remove(endpoint);
+    }
+
+    /// Heals the partitions of all endpoints.
+    pub fn heal_all(&self) {
+        self.lock().partitions.clear();
+    }
+
+    /// Indicates whether or not the given endpoint is partitioned.
+    pub fn is_partitioned(&self, endpoint: &str) -> bool {
+        self.lock().partitions.contains(endpoint)
+    }
+
+    /// Disconnects all existing connections to the given endpoint.
+    ///
+    /// Sending or receiving on a disconnected connection returns a `Disconnected` error. New
+    /// connections to the endpoint are not affected.
+    pub fn disconnect(&self, endpoint: &str) {
+        *self
+            .lock()
+            .disconnects
+            .entry(endpoint.to_string())
+            .or_insert(0) += 1;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ChaosState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn disconnect_count(&self, endpoint: &str) -> u64 {
+        self.lock().disconnects.get(endpoint).copied().unwrap_or(0)
+    }
+
+    fn send_outcome(&self, endpoint: &str) -> Outcome {
+        let mut state = self.lock();
+        if state.partitions.contains(endpoint) {
+            return Outcome::Drop;
+        }
+
+        let faults = state
+            .faults
+            .get(endpoint)
+            .unwrap_or(&state.default_faults)
+            .clone();
+
+        if state.rng.gen_bool(faults.disconnect_probability) {
+            Outcome::Disconnect
+        } else if state.rng.gen_bool(faults.drop_probability) {
+            Outcome::Drop
+        } else {
+            Outcome::Deliver {
+                delay: faults.delay,
+                duplicate: state.rng.gen_bool(faults.duplicate_probability),
+            }
+        }
+    }
+}
+
+/// A transport that injects the faults configured on its `ChaosControl` into the connections
+/// created by the transport it wraps.
+pub struct ChaosTransport<T: Transport> {
+    inner: T,
+    control: ChaosControl,
+}
+
+impl<T: Transport> ChaosTransport<T> {
+    /// Wraps the given transport, with a new control that injects no faults.
+    pub fn new(inner: T) -> Self {
+        ChaosTransport {
+            inner,
+            control: ChaosControl::default(),
+        }
+    }
+
+    /// Uses the given control, which may be shared with other `ChaosTransport`s.
+    pub fn with_control(mut self, control: ChaosControl) -> Self {
+        self.control = control;
+        self
+    }
+
+    /// Returns the control of this transport.
+    pub fn control(&self) -> ChaosControl {
+        self.control.clone()
+    }
+}
+
+impl<T: Transport> Transport for ChaosTransport<T> {
+    fn accepts(&self, address: &str) -> bool {
+        self.inner.accepts(address)
+    }
+
+    fn connect(&mut self, endpoint: &str) -> Result<Box<dyn Connection>, ConnectError> {
+        if self.control.is_partitioned(endpoint) {
+            return Err(ConnectError::IoError(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("Endpoint {} is partitioned", endpoint),
+            )));
+        }
+
+        let connection = self.inner.connect(endpoint)?;
+        Ok(Box::new(ChaosConnection::new(
+            connection,
+            endpoint.to_string(),
+            self.control.clone(),
+        )))
+    }
+
+    fn listen(&mut self, bind: &str) -> Result<Box<dyn Listener>, ListenError> {
+        let listener = self.inner.listen(bind)?;
+        Ok(Box::new(ChaosListener {
+            inner: listener,
+            control: self.control.clone(),
+        }))
+    }
+}
+
+struct ChaosListener {
+    inner: Box<dyn Listener>,
+    control: ChaosControl,
+}
+
+impl Listener for ChaosListener {
+    fn accept(&mut self) -> Result<Box<dyn Connection>, AcceptError> {
+        let connection = self.inner.accept()?;
+        Ok(Box::new(ChaosConnection::new(
+            connection,
+            self.inner.endpoint(),
+            self.control.clone(),
+        )))
+    }
+
+    fn endpoint(&self) -> String {
+        self.inner.endpoint()
+    }
+}
+
+struct ChaosConnection {
+    inner: Box<dyn Connection>,
+    // The endpoint whose faults are injected into this connection
+    endpoint: String,
+    control: ChaosControl,
+    // The endpoint's disconnect count when this connection was created
+    disconnect_count: u64,
+    disconnected: bool,
+}
+
+impl ChaosConnection {
+    fn new(inner: Box<dyn Connection>, endpoint: String, control: ChaosControl) -> Self {
+        let disconnect_count = control.disconnect_count(&endpoint);
+        ChaosConnection {
+            inner,
+            endpoint,
+            control,
+            disconnect_count,
+            disconnected: false,
+        }
+    }
+
+    /// Checks whether or not the connection has been disconnected, disconnecting the wrapped
+    /// connection if it has been disconnected by the control since it was last checked.
+    fn check_disconnected(&mut self) -> bool {
+        if !self.disconnected
+            && self.control.disconnect_count(&self.endpoint) != self.disconnect_count
+        {
+            self.force_disconnect();
+        }
+        self.disconnected
+    }
+
+    fn force_disconnect(&mut self) {
+        self.disconnected = true;
+        if let Err(err) = self.inner.disconnect() {
+            debug!(
+                "Unable to disconnect connection to {}: {:?}",
+                self.endpoint, err
+            );
+        }
+    }
+}
+
+impl Connection for ChaosConnection {
+    fn send(&mut self, message: &[u8]) -> Result<(), SendError> {
+        if self.check_disconnected() {
+            return Err(SendError::Disconnected);
+        }
+
+        match self.control.send_outcome(&self.endpoint) {
+            Outcome::Disconnect => {
+                self.force_disconnect();
+                Err(SendError::Disconnected)
+            }
+            Outcome::Drop => Ok(()),
+            Outcome::Deliver { delay, duplicate } => {
+                if delay > Duration::from_secs(0) {
+                    thread::sleep(delay);
+                }
+                self.inner.send(message)?;
+                if duplicate {
+                    self.inner.send(message)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn recv(&mut self) -> Result<Vec<u8>, RecvError> {
+        if self.check_disconnected() {
+            return Err(RecvError::Disconnected);
+        }
+
+        let message = self.inner.recv()?;
+        if self.control.is_partitioned(&self.endpoint) {
+            // The message was lost in the partition
+            Err(RecvError::WouldBlock)
+        } else {
+            Ok(message)
+        }
+    }
+
+    fn remote_endpoint(&self) -> String {
+        self.inner.remote_endpoint()
+    }
+
+    fn local_endpoint(&self) -> String {
+        self.inner.local_endpoint()
+    }
+
+    fn disconnect(&mut self) -> Result<(), DisconnectError> {
+        self.disconnected = true;
+        self.inner.disconnect()
+    }
+
+    fn evented(&self) -> &dyn Evented {
+        self.inner.evented()
+    }
+
+    fn peer_certificate(&self) -> Option<PeerCertificate> {
+        self.inner.peer_certificate()
+    }
+
+    fn peer_credentials(&self) -> Option<PeerCredentials> {
+        self.inner.peer_credentials()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Instant;
+
+    use crate::transport::inproc::InprocTransport;
+    use crate::transport::tests;
+
+    /// Creates a pair of connected connections, using transports that share the given control.
+    fn connect_pair(
+        control: &ChaosControl,
+        bind: &str,
+    ) -> (Box<dyn Connection>, Box<dyn Connection>) {
+        let inproc = InprocTransport::default();
+        let mut server = ChaosTransport::new(inproc.clone()).with_control(control.clone());
+        let mut client = ChaosTransport::new(inproc).with_control(control.clone());
+
+        let mut listener = server.listen(bind).expect("Unable to listen");
+        let endpoint = listener.endpoint();
+        let handle = thread::spawn(move || listener.accept().expect("Unable to accept"));
+        let client_connection = client.connect(&endpoint).expect("Unable to connect");
+        let server_connection = handle.join().expect("Accept thread panicked");
+
+        (client_connection, server_connection)
+    }
+
+    /// Receives all of the messages that arrive on the connection within a short time.
+    fn recv_all(connection: &mut dyn Connection) -> Vec<Vec<u8>> {
+        let mut messages = vec![];
+        for _ in 0..5 {
+            loop {
+                match connection.recv() {
+                    Ok(message) => messages.push(message),
+                    Err(RecvError::WouldBlock) => break,
+                    Err(err) => panic!("Unexpected error: {:?}", err),
+                }
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        messages
+    }
+
+    /// Test that a ChaosTransport without faults behaves like the transport it wraps.
+    #[test]
+    fn test_transport() {
+        let transport = ChaosTransport::new(InprocTransport::default());
+        tests::test_transport(transport, "test");
+    }
+
+    /// Test that messages are dropped, duplicated and delayed according to the faults of the
+    /// connection's endpoint, and that other endpoints use the default faults.
+    #[test]
+    fn test_faults() {
+        let control = ChaosControl::with_seed(7);
+
+        let (mut client, mut server) = connect_pair(&control, "inproc://drop");
+        control.set_faults(
+            "inproc://drop",
+            Faults::default().with_drop_probability(1.0),
+        );
+        client.send(b"dropped").expect("Unable to send");
+        assert!(recv_all(&mut *server).is_empty());
+
+        control.clear_faults("inproc://drop");
+        client.send(b"delivered").expect("Unable to send");
+        assert_eq!(recv_all(&mut *server), vec![b"delivered".to_vec()]);
+
+        control.set_default_faults(
+            Faults::default()
+                .with_duplicate_probability(1.0)
+                .with_delay(Duration::from_millis(50)),
+        );
+        let (mut client, mut server) = connect_pair(&control, "inproc://duplicate");
+        let start = Instant::now();
+        server.send(b"duplicated").expect("Unable to send");
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert_eq!(
+            recv_all(&mut *client),
+            vec![b"duplicated".to_vec(), b"duplicated".to_vec()]
+        );
+    }
+
+    /// Test that a partitioned endpoint refuses new connections and loses the messages of its
+    /// existing connections until the partition is healed.
+    #[test]
+    fn test_partition() {
+        let control = ChaosControl::default();
+        let (mut client, mut server) = connect_pair(&control, "inproc://partition");
+
+        control.partition("inproc://partition");
+        assert!(control.is_partitioned("inproc://partition"));
+
+        let mut transport =
+            ChaosTransport::new(InprocTransport::default()).with_control(control.clone());
+        match transport.connect("inproc://partition") {
+            Err(ConnectError::IoError(err)) => {
+                assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused)
+            }
+            Err(err) => panic!("Unexpected error: {:?}", err),
+            Ok(_) => panic!("Connected to a partitioned endpoint"),
+        }
+
+        client.send(b"lost").expect("Unable to send");
+        assert!(recv_all(&mut *server).is_empty());
+
+        control.heal("inproc://partition");
+        client.send(b"found").expect("Unable to send");
+        assert_eq!(recv_all(&mut *server), vec![b"found".to_vec()]);
+    }
+
+    /// Test that disconnecting an endpoint disconnects both ends of its existing connections, but
+    /// not new connections, and that connections are disconnected with the disconnect
+    /// probability.
+    #[test]
+    fn test_disconnect() {
+        let control = ChaosControl::default();
+        let (mut client, mut server) = connect_pair(&control, "inproc://disconnect");
+
+        control.disconnect("inproc://disconnect");
+        match client.send(b"message") {
+            Err(SendError::Disconnected) => (),
+            res => panic!("Expected Disconnected, got {:?}", res),
+        }
+        match server.recv() {
+            Err(RecvError::Disconnected) => (),
+            res => panic!("Expected Disconnected, got {:?}", res),
+        }
+
+        let (mut client, mut server) = connect_pair(&control, "inproc://disconnect");
+        client.send(b"message").expect("Unable to send");
+        assert_eq!(recv_all(&mut *server), vec![b"message".to_vec()]);
+
+        control.set_faults(
+            "inproc://disconnect",
+            Faults::default().with_disconnect_probability(1.0),
+        );
+        match client.send(b"message") {
+            Err(SendError::Disconnected) => (),
+            res => panic!("Expected Disconnected, got {:?}", res),
+        }
+        match client.recv() {
+            Err(RecvError::Disconnected) => (),
+            res => panic!("Expected Disconnected, got {:?}", res),
+        }
+    }
+}
@@ -19,13 +19,26 @@ use std::time::Duration;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 
 const HEADER_LENGTH: usize = 6;
+// version, flags, reserved byte, length and the payload's CRC-32 checksum, which is checked on
+// every version 2 frame
+const V2_HEADER_LENGTH: usize = 12;
+
+/// The largest frame that will be received by default, in bytes.
+pub const DEFAULT_MAX_FRAME_SIZE: u32 = 64 * 1024 * 1024;
+
+// Payloads smaller than this are never compressed, as the savings would not be worth the work.
+const MIN_COMPRESSION_LENGTH: usize = 1024;
 
 /// An error that may be returned during frame-related operations
 #[derive(Debug)]
 pub enum FrameError {
     IoError(io::Error),
+    CompressionError(String),
+    FrameTooLarge { length: usize, max: usize },
     InvalidChecksum,
     InvalidHeaderLength(usize),
+    InvalidPayloadChecksum,
+    UnsupportedCompression(u8),
     UnsupportedVersion,
 }
 
@@ -33,12 +46,24 @@ impl std::fmt::Display for FrameError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             FrameError::IoError(err) => f.write_str(&err.to_string()),
+            FrameError::CompressionError(msg) => {
+                write!(f, "Unable to compress or decompress frame payload: {}", msg)
+            }
+            FrameError::FrameTooLarge { length, max } => write!(
+                f,
+                "Frame of {} bytes exceeds the maximum frame size of {} bytes",
+                length, max
+            ),
             FrameError::InvalidChecksum => f.write_str("Invalid checksum in frame header"),
             FrameError::InvalidHeaderLength(n) => write!(
                 f,
                 "Invalid header length expected {} but was {}",
                 HEADER_LENGTH, n
             ),
+            FrameError::InvalidPayloadChecksum => f.write_str("Invalid frame payload checksum"),
+            FrameError::UnsupportedCompression(flag) => {
+                write!(f, "Unsupported frame compression: {}", flag)
+            }
             FrameError::UnsupportedVersion => f.write_str("Unsupported frame version"),
         }
     }
@@ -48,8 +73,12 @@ impl std::error::Error for FrameError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             FrameError::IoError(err) => Some(&*err),
+            FrameError::CompressionError(_) => None,
+            FrameError::FrameTooLarge { .. } => None,
             FrameError::InvalidChecksum => None,
             FrameError::InvalidHeaderLength(_) => None,
+            FrameError::InvalidPayloadChecksum => None,
+            FrameError::UnsupportedCompression(_) => None,
             FrameError::UnsupportedVersion => None,
         }
     }
@@ -68,6 +97,147 @@ impl From<io::Error> for FrameError {
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum FrameVersion {
     V1 = 1,
+    /// Adds a CRC32 checksum of the payload and optional payload compression.
+    V2 = 2,
+}
+
+impl FrameVersion {
+    fn from_u16(version: u16) -> Option<Self> {
+        match version {
+            1 => Some(FrameVersion::V1),
+            2 => Some(FrameVersion::V2),
+            _ => None,
+        }
+    }
+}
+
+/// The compression algorithm applied to a frame's payload.
+///
+/// Compressed frames may only be sent with version 2 frames, and only if both ends of the
+/// connection support the algorithm, which requires the `frame-compression` feature.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum FrameCompression {
+    Deflate = 1,
+    Zstd = 2,
+}
+
+impl FrameCompression {
+    fn from_flag(flag: u8) -> Option<Self> {
+        match flag {
+            1 => Some(FrameCompression::Deflate),
+            2 => Some(FrameCompression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// The bit for this algorithm, when exchanging supported algorithms during negotiation.
+    fn bit(self) -> u16 {
+        1 << self as u16
+    }
+}
+
+/// Returns the bits of the compression algorithms this build is able to compress and decompress.
+fn supported_compression() -> u16 {
+    if cfg!(feature = "frame-compression") {
+        FrameCompression::Deflate.bit() | FrameCompression::Zstd.bit()
+    } else {
+        0
+    }
+}
+
+/// The frame settings requested by one end of a connection.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct FrameOptions {
+    max_frame_size: u32,
+    compression: Option<FrameCompression>,
+}
+
+impl Default for FrameOptions {
+    fn default() -> Self {
+        FrameOptions {
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            compression: None,
+        }
+    }
+}
+
+impl FrameOptions {
+    /// Sets the size, in bytes, of the largest frame payload that will be received. Frames that
+    /// declare a larger payload are rejected before it is read.
+    pub fn with_max_frame_size(mut self, max_frame_size: u32) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    /// Sets the compression algorithm used for the payloads of sent frames, if the remote end
+    /// supports it as well.
+    pub fn with_compression(mut self, compression: FrameCompression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    pub fn max_frame_size(&self) -> u32 {
+        self.max_frame_size
+    }
+
+    pub fn compression(&self) -> Option<FrameCompression> {
+        self.compression
+    }
+}
+
+/// The framing used for a connection, as agreed on by frame negotiation.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Framing {
+    version: FrameVersion,
+    compression: Option<FrameCompression>,
+    max_frame_size: u32,
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Framing {
+            version: FrameVersion::V1,
+            compression: None,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+        }
+    }
+}
+
+impl Framing {
+    /// Construct the framing for the given version, compressing sent frames with the requested
+    /// algorithm only if both ends support it.
+    fn new(version: FrameVersion, options: FrameOptions, remote_compression: u16) -> Self {
+        let compression = options.compression.filter(|compression| {
+            version != FrameVersion::V1
+                && supported_compression() & remote_compression & compression.bit() != 0
+        });
+
+        Framing {
+            version,
+            compression,
+            max_frame_size: options.max_frame_size,
+        }
+    }
+
+    pub fn version(&self) -> FrameVersion {
+        self.version
+    }
+
+    pub fn compression(&self) -> Option<FrameCompression> {
+        self.compression
+    }
+
+    /// Read a frame from the given reader, rejecting frames larger than the maximum frame size.
+    pub fn read<R: Read>(&self, reader: &mut R) -> Result<Frame, FrameError> {
+        Frame::read_with_max_size(reader, self.max_frame_size)
+    }
+
+    /// Write the given data to the given writer as a frame.
+    pub fn write<W: Write>(&self, writer: &mut W, data: &[u8]) -> Result<(), FrameError> {
+        FrameRef::new(self.version, data)
+            .with_compression(self.compression)
+            .write(writer)
+    }
 }
 
 /// A complete Frame of transmitted data.
@@ -83,7 +253,7 @@ impl Frame {
         self.data
     }
 
-    /// Read a frame from the given reader.
+    /// Read a frame from the given reader, with at most the default maximum frame size.
     ///
     /// # Errors
     ///
@@ -91,8 +261,23 @@ impl Frame {
     ///
     /// - the header is malformed
     /// - the data length doesn't match the header length
+    /// - the data length exceeds the maximum frame size
+    /// - the data doesn't match the header's checksum
     /// - an IO error occurs
     pub fn read<R: Read>(reader: &mut R) -> Result<Self, FrameError> {
+        Self::read_with_max_size(reader, DEFAULT_MAX_FRAME_SIZE)
+    }
+
+    /// Read a frame from the given reader, rejecting frames whose data, before or after
+    /// decompression, is larger than the given maximum frame size.
+    ///
+    /// # Errors
+    ///
+    /// This function returns the same errors as `Frame::read`.
+    pub fn read_with_max_size<R: Read>(
+        reader: &mut R,
+        max_frame_size: u32,
+    ) -> Result<Self, FrameError> {
         let frame_header = loop {
             match FrameHeader::read(reader) {
                 Err(FrameError::IoError(ref e)) if e.kind() == io::ErrorKind::WouldBlock => {
@@ -104,61 +289,182 @@ impl Frame {
             };
         };
 
+        // The declared length is checked before any buffer is allocated for it
+        let length = frame_header.length();
+        if length > max_frame_size {
+            return Err(FrameError::FrameTooLarge {
+                length: length as usize,
+                max: max_frame_size as usize,
+            });
+        }
+
+        let data = read_data(reader, length as usize)?;
+
         match frame_header {
-            FrameHeader::V1 { length } => {
-                let mut buffer = vec![0; length as usize];
-                let mut remaining = &mut buffer[..];
-
-                while !remaining.is_empty() {
-                    match reader.read(remaining) {
-                        Ok(0) => break,
-                        Ok(n) => {
-                            let tmp = remaining;
-                            remaining = &mut tmp[n..];
-                        }
-                        Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => {}
-                        Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                            thread::sleep(Duration::from_millis(100));
-                        }
-                        Err(e) => return Err(FrameError::IoError(e)),
-                    }
+            FrameHeader::V1 { .. } => Ok(Self { data }),
+            FrameHeader::V2 {
+                compression, crc, ..
+            } => {
+                if crc32fast::hash(&data) != crc {
+                    return Err(FrameError::InvalidPayloadChecksum);
                 }
-                if !remaining.is_empty() {
-                    Err(FrameError::IoError(io::Error::new(
-                        io::ErrorKind::UnexpectedEof,
-                        "Could not receive complete frame",
-                    )))
-                } else {
-                    Ok(Self { data: buffer })
+
+                match compression {
+                    Some(compression) => Ok(Self {
+                        data: decompress(compression, &data, max_frame_size)?,
+                    }),
+                    None => Ok(Self { data }),
                 }
             }
         }
     }
 }
 
+/// Read exactly the given number of bytes of frame data from the given reader.
+fn read_data<R: Read>(reader: &mut R, length: usize) -> Result<Vec<u8>, FrameError> {
+    let mut buffer = vec![0; length];
+    let mut remaining = &mut buffer[..];
+
+    while !remaining.is_empty() {
+        match reader.read(remaining) {
+            Ok(0) => break,
+            Ok(n) => {
+                let tmp = remaining;
+                remaining = &mut tmp[n..];
+            }
+            Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(100));
+            }
+            Err(e) => return Err(FrameError::IoError(e)),
+        }
+    }
+    if !remaining.is_empty() {
+        Err(FrameError::IoError(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Could not receive complete frame",
+        )))
+    } else {
+        Ok(buffer)
+    }
+}
+
+/// Compress the given data with the given algorithm.
+#[cfg(feature = "frame-compression")]
+fn compress(compression: FrameCompression, data: &[u8]) -> Result<Vec<u8>, FrameError> {
+    match compression {
+        FrameCompression::Deflate => {
+            let mut encoder =
+                flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder
+                .write_all(data)
+                .and_then(|_| encoder.finish())
+                .map_err(|err| FrameError::CompressionError(err.to_string()))
+        }
+        FrameCompression::Zstd => zstd::stream::encode_all(data, 0)
+            .map_err(|err| FrameError::CompressionError(err.to_string())),
+    }
+}
+
+#[cfg(not(feature = "frame-compression"))]
+fn compress(compression: FrameCompression, _data: &[u8]) -> Result<Vec<u8>, FrameError> {
+    Err(FrameError::UnsupportedCompression(compression as u8))
+}
+
+/// Decompress the given data with the given algorithm, failing if it would decompress to more
+/// than the given maximum frame size.
+#[cfg(feature = "frame-compression")]
+fn decompress(
+    compression: FrameCompression,
+    data: &[u8],
+    max_frame_size: u32,
+) -> Result<Vec<u8>, FrameError> {
+    let decoder: Box<dyn Read> = match compression {
+        FrameCompression::Deflate => Box::new(flate2::read::DeflateDecoder::new(data)),
+        FrameCompression::Zstd => Box::new(
+            zstd::stream::read::Decoder::new(data)
+                .map_err(|err| FrameError::CompressionError(err.to_string()))?,
+        ),
+    };
+
+    let mut decompressed = vec![];
+    decoder
+        .take(u64::from(max_frame_size) + 1)
+        .read_to_end(&mut decompressed)
+        .map_err(|err| FrameError::CompressionError(err.to_string()))?;
+
+    if decompressed.len() > max_frame_size as usize {
+        return Err(FrameError::FrameTooLarge {
+            length: decompressed.len(),
+            max: max_frame_size as usize,
+        });
+    }
+
+    Ok(decompressed)
+}
+
+#[cfg(not(feature = "frame-compression"))]
+fn decompress(
+    compression: FrameCompression,
+    _data: &[u8],
+    _max_frame_size: u32,
+) -> Result<Vec<u8>, FrameError> {
+    Err(FrameError::UnsupportedCompression(compression as u8))
+}
+
 /// A Frame of referenced data to be transmitted using a specified version.
 ///
 /// This struct references the data that has been transmitted.  It is essentially a sending frame.
 pub struct FrameRef<'a> {
     version: FrameVersion,
     data: &'a [u8],
+    compression: Option<FrameCompression>,
 }
 
 impl<'a> FrameRef<'a> {
     /// Construct a FrameRef for the given byte slice, which will be transmitted using the given
     /// frame version.
     pub fn new<'b: 'a>(version: FrameVersion, data: &'b [u8]) -> FrameRef<'a> {
-        Self { version, data }
+        Self {
+            version,
+            data,
+            compression: None,
+        }
+    }
+
+    /// Compress the data with the given algorithm, if any, when it is transmitted using a frame
+    /// version that supports compression.
+    pub fn with_compression(mut self, compression: Option<FrameCompression>) -> Self {
+        self.compression = compression;
+        self
     }
 
     /// Write the frame to the given writer.
     ///
     /// # Errors
     ///
-    /// Returns a FrameError if an IO error occurs.
+    /// Returns a FrameError if an IO error occurs, or if the data cannot be compressed.
     pub fn write<W: Write>(self, writer: &mut W) -> Result<(), FrameError> {
-        let frame_header = match self.version {
-            FrameVersion::V1 => FrameHeader::v1(self.data.len() as u32),
+        let compressed = match self.compression {
+            Some(compression)
+                if self.version == FrameVersion::V2
+                    && self.data.len() >= MIN_COMPRESSION_LENGTH =>
+            {
+                // Data that does not compress well is sent as is
+                Some(compress(compression, self.data)?)
+                    .filter(|compressed| compressed.len() < self.data.len())
+                    .map(|compressed| (compression, compressed))
+            }
+            _ => None,
+        };
+
+        let (frame_header, data) = match (self.version, compressed.as_ref()) {
+            (FrameVersion::V1, _) => (FrameHeader::v1(self.data.len() as u32), self.data),
+            (FrameVersion::V2, Some((compression, compressed))) => (
+                FrameHeader::v2(&compressed[..], Some(*compression)),
+                &compressed[..],
+            ),
+            (FrameVersion::V2, None) => (FrameHeader::v2(self.data, None), self.data),
         };
         loop {
             match frame_header.write(writer) {
@@ -171,7 +477,7 @@ impl<'a> FrameRef<'a> {
             }
         }
 
-        let mut buffer = &self.data[..];
+        let mut buffer = data;
         while !buffer.is_empty() {
             match writer.write(buffer) {
                 Ok(0) => {
@@ -198,7 +504,14 @@ impl<'a> FrameRef<'a> {
 /// Each variant corresponds to the implementation for a given version.
 #[derive(Debug, PartialEq)]
 enum FrameHeader {
-    V1 { length: u32 },
+    V1 {
+        length: u32,
+    },
+    V2 {
+        length: u32,
+        compression: Option<FrameCompression>,
+        crc: u32,
+    },
 }
 
 impl FrameHeader {
@@ -207,6 +520,23 @@ impl FrameHeader {
         FrameHeader::V1 { length }
     }
 
+    /// Construct a version 2 frame header for the given data, as it will be transmitted.
+    fn v2(data: &[u8], compression: Option<FrameCompression>) -> Self {
+        FrameHeader::V2 {
+            length: data.len() as u32,
+            compression,
+            crc: crc32fast::hash(data),
+        }
+    }
+
+    /// The length of the data that follows the header.
+    fn length(&self) -> u32 {
+        match *self {
+            FrameHeader::V1 { length } => length,
+            FrameHeader::V2 { length, .. } => length,
+        }
+    }
+
     /// Read a FrameHeader from the given reader.
     ///
     /// This function uses the first 2 bytes of the stream to read the version, and constructs the
@@ -243,6 +573,39 @@ impl FrameHeader {
                     length: cursor.read_u32::<BigEndian>()?,
                 })
             }
+            2 => {
+                // Header length + checksum byte
+                let mut buffer = [0u8; V2_HEADER_LENGTH + 1];
+                let mut cursor = Cursor::new(&mut buffer[..]);
+                cursor.write_u16::<BigEndian>(2u16)?;
+
+                let n = reader.read(&mut cursor.get_mut()[std::mem::size_of::<u16>()..])?;
+                if n != V2_HEADER_LENGTH + 1 - std::mem::size_of::<u16>() {
+                    return Err(FrameError::InvalidHeaderLength(n));
+                }
+
+                let checksum = compute_checksum(&cursor.get_ref()[..V2_HEADER_LENGTH]);
+                if checksum != cursor.get_ref()[V2_HEADER_LENGTH] {
+                    return Err(FrameError::InvalidChecksum);
+                }
+
+                let flags = cursor.read_u8()?;
+                let compression = match flags {
+                    0 => None,
+                    flag => Some(
+                        FrameCompression::from_flag(flag)
+                            .ok_or(FrameError::UnsupportedCompression(flag))?,
+                    ),
+                };
+                // skip the reserved byte
+                cursor.read_u8()?;
+
+                Ok(FrameHeader::V2 {
+                    length: cursor.read_u32::<BigEndian>()?,
+                    compression,
+                    crc: cursor.read_u32::<BigEndian>()?,
+                })
+            }
             _ => Err(FrameError::UnsupportedVersion),
         }
     }
@@ -270,6 +633,26 @@ impl FrameHeader {
 
                 writer.write_all(&cursor.into_inner()[..])?;
             }
+            FrameHeader::V2 {
+                length,
+                compression,
+                crc,
+            } => {
+                let mut header_bytes = [0u8; V2_HEADER_LENGTH + 1];
+                let mut cursor = Cursor::new(&mut header_bytes[..]);
+
+                cursor.write_u16::<BigEndian>(2)?;
+                cursor.write_u8(compression.map(|c| c as u8).unwrap_or(0))?;
+                // reserved
+                cursor.write_u8(0)?;
+                cursor.write_u32::<BigEndian>(length)?;
+                cursor.write_u32::<BigEndian>(crc)?;
+
+                cursor.get_mut()[V2_HEADER_LENGTH] =
+                    compute_checksum(&cursor.get_ref()[..V2_HEADER_LENGTH]);
+
+                writer.write_all(&cursor.into_inner()[..])?;
+            }
         }
 
         Ok(())
@@ -296,6 +679,9 @@ fn compute_checksum(buffer: &[u8]) -> u8 {
 }
 
 /// Negotiate the frame version for a given socket connection.
+///
+/// When both ends agree on version 2 or later, they then exchange the compression algorithms they
+/// support, each sending a bit field of the algorithms as a u16.
 pub enum FrameNegotiation {
     /// The Outbound variant transmits the min and max supported version, and expects to receive
     /// either a version in that range, or `0` if the other end cannot support the a version in
@@ -303,24 +689,48 @@ pub enum FrameNegotiation {
     Outbound {
         min: FrameVersion,
         max: FrameVersion,
+        options: FrameOptions,
+    },
+    /// The Inbound variant transmits receives the min and max and decides if it should send the
+    /// highest version it supports within that range, or `0` if it supports none of them. Every
+    /// version up to the given version is supported.
+    Inbound {
+        version: FrameVersion,
+        options: FrameOptions,
     },
-    /// The Inbound variant transmits receives the min and max and decides if it should send its
-    /// version or `0`, depending on whether or not it falls in the range.
-    Inbound { version: FrameVersion },
 }
 
 impl FrameNegotiation {
     /// Construct the outbound side of a negotiation with the given min,max.
     pub fn outbound(min: FrameVersion, max: FrameVersion) -> Self {
-        FrameNegotiation::Outbound { min, max }
+        FrameNegotiation::Outbound {
+            min,
+            max,
+            options: FrameOptions::default(),
+        }
     }
 
     /// Construct the inbound side of a negotiation with the given version.
     pub fn inbound(version: FrameVersion) -> Self {
-        FrameNegotiation::Inbound { version }
+        FrameNegotiation::Inbound {
+            version,
+            options: FrameOptions::default(),
+        }
+    }
+
+    /// Use the given options for the framing that is negotiated.
+    pub fn with_options(self, options: FrameOptions) -> Self {
+        match self {
+            FrameNegotiation::Outbound { min, max, .. } => {
+                FrameNegotiation::Outbound { min, max, options }
+            }
+            FrameNegotiation::Inbound { version, .. } => {
+                FrameNegotiation::Inbound { version, options }
+            }
+        }
     }
 
-    /// Negotiate frame version to use for future communications over the given stream.
+    /// Negotiate the framing to use for future communications over the given stream.
     ///
     /// # Errors
     ///
@@ -328,30 +738,53 @@ impl FrameNegotiation {
     ///
     /// - either end cannot agree on a version
     /// - an IO error, if one occurs
-    pub fn negotiate<S: Read + Write>(self, stream: &mut S) -> Result<FrameVersion, FrameError> {
+    pub fn negotiate<S: Read + Write>(self, stream: &mut S) -> Result<Framing, FrameError> {
         match self {
-            FrameNegotiation::Outbound { min, max } => {
+            FrameNegotiation::Outbound { min, max, options } => {
                 stream.write_u16::<BigEndian>(min as u16)?;
                 stream.write_u16::<BigEndian>(max as u16)?;
 
                 let frame_version = stream.read_u16::<BigEndian>()?;
 
-                match frame_version {
-                    0 => Err(FrameError::UnsupportedVersion),
-                    1 => Ok(FrameVersion::V1),
-                    _ => Err(FrameError::UnsupportedVersion),
-                }
+                let version = match FrameVersion::from_u16(frame_version) {
+                    Some(version) if frame_version >= min as u16 && frame_version <= max as u16 => {
+                        version
+                    }
+                    _ => return Err(FrameError::UnsupportedVersion),
+                };
+
+                let remote_compression = if version == FrameVersion::V1 {
+                    0
+                } else {
+                    stream.write_u16::<BigEndian>(supported_compression())?;
+                    stream.read_u16::<BigEndian>()?
+                };
+
+                Ok(Framing::new(version, options, remote_compression))
             }
-            FrameNegotiation::Inbound { version } => {
+            FrameNegotiation::Inbound { version, options } => {
                 let min = stream.read_u16::<BigEndian>()?;
                 let max = stream.read_u16::<BigEndian>()?;
-                if min > version as u16 || max < version as u16 {
-                    stream.write_u16::<BigEndian>(0)?;
-                    Err(FrameError::UnsupportedVersion)
+
+                let negotiated = std::cmp::min(version as u16, max);
+                let version = match FrameVersion::from_u16(negotiated) {
+                    Some(version) if negotiated >= min => version,
+                    _ => {
+                        stream.write_u16::<BigEndian>(0)?;
+                        return Err(FrameError::UnsupportedVersion);
+                    }
+                };
+                stream.write_u16::<BigEndian>(version as u16)?;
+
+                let remote_compression = if version == FrameVersion::V1 {
+                    0
                 } else {
-                    stream.write_u16::<BigEndian>(version as u16)?;
-                    Ok(version)
-                }
+                    let remote_compression = stream.read_u16::<BigEndian>()?;
+                    stream.write_u16::<BigEndian>(supported_compression())?;
+                    remote_compression
+                };
+
+                Ok(Framing::new(version, options, remote_compression))
             }
         }
     }
@@ -471,8 +904,10 @@ mod tests {
             .expect("Unable to write frame header");
 
         header_cursor.set_position(0);
-        let FrameHeader::V1 { length } =
-            FrameHeader::read(&mut header_cursor).expect("Unable to read header");
+        let length = match FrameHeader::read(&mut header_cursor).expect("Unable to read header") {
+            FrameHeader::V1 { length } => length,
+            header => panic!("Unexpected header: {:?}", header),
+        };
 
         assert_eq!(100, length);
     }
@@ -501,13 +936,13 @@ mod tests {
             .negotiate(&mut tx)
             .expect("Unable to negotiate a valid version");
 
-        assert_eq!(FrameVersion::V1, version);
+        assert_eq!(FrameVersion::V1, version.version());
 
         done_tx.send(1u8).expect("unable to send stop signal");
 
         let remote_res = join_handle.join().expect("Unable to join thread");
 
-        assert_eq!(FrameVersion::V1, remote_res);
+        assert_eq!(FrameVersion::V1, remote_res.version());
     }
 
     /// Test that outbound frame version negotiation works:
@@ -618,6 +1053,187 @@ mod tests {
         assert_eq!(input.to_vec(), frame.data);
     }
 
+    /// Test a round-trip write and read of a version 2 FrameHeader, including its compression
+    /// flag and payload checksum.
+    #[test]
+    fn round_trip_v2() {
+        let mut header_cursor = Cursor::new(vec![0u8; V2_HEADER_LENGTH + 1]);
+
+        let frame_header = FrameHeader::v2(b"hello", Some(FrameCompression::Zstd));
+
+        frame_header
+            .write(&mut header_cursor)
+            .expect("Unable to write frame header");
+
+        header_cursor.set_position(0);
+        let read_header = FrameHeader::read(&mut header_cursor).expect("Unable to read header");
+
+        assert_eq!(frame_header, read_header);
+        assert_eq!(
+            FrameHeader::V2 {
+                length: 5,
+                compression: Some(FrameCompression::Zstd),
+                crc: 0x3610_a686,
+            },
+            read_header
+        );
+    }
+
+    /// Test that a version 2 frame is written and read back, and that a frame whose payload was
+    /// altered fails its payload checksum.
+    #[test]
+    fn frame_round_trip_v2() {
+        let input = b"hello world";
+
+        let mut cursor = Cursor::new(vec![]);
+        FrameRef::new(FrameVersion::V2, input)
+            .write(&mut cursor)
+            .expect("Unable to write data");
+
+        cursor.set_position(0);
+        let frame = Frame::read(&mut cursor).expect("Unable to read frame");
+        assert_eq!(input.to_vec(), frame.data);
+
+        let last = cursor.get_ref().len() - 1;
+        cursor.get_mut()[last] ^= 0xff;
+        cursor.set_position(0);
+        match Frame::read(&mut cursor) {
+            Err(FrameError::InvalidPayloadChecksum) => (),
+            res => panic!("Unexpected result: {:?}", res.map(Frame::into_inner)),
+        }
+    }
+
+    /// Test that frames declaring data larger than the maximum frame size are rejected, for both
+    /// frame versions.
+    #[test]
+    fn frame_too_large() {
+        let input = vec![1u8; 100];
+
+        for version in &[FrameVersion::V1, FrameVersion::V2] {
+            let mut cursor = Cursor::new(vec![]);
+            FrameRef::new(*version, &input)
+                .write(&mut cursor)
+                .expect("Unable to write data");
+
+            cursor.set_position(0);
+            match Frame::read_with_max_size(&mut cursor, 99) {
+                Err(FrameError::FrameTooLarge { length, max }) => {
+                    assert_eq!(100, length);
+                    assert_eq!(99, max);
+                }
+                res => panic!("Unexpected result: {:?}", res.map(Frame::into_inner)),
+            }
+
+            cursor.set_position(0);
+            let frame = Frame::read_with_max_size(&mut cursor, 100).expect("Unable to read frame");
+            assert_eq!(input, frame.data);
+        }
+    }
+
+    /// Test that version 2 is negotiated when both ends support it, and that either end falls
+    /// back to version 1 when the other end only supports version 1.
+    #[test]
+    fn negotiate_v2_with_fallback() {
+        let cases = vec![
+            (FrameVersion::V2, FrameVersion::V2, FrameVersion::V2),
+            (FrameVersion::V2, FrameVersion::V1, FrameVersion::V1),
+            (FrameVersion::V1, FrameVersion::V2, FrameVersion::V1),
+        ];
+
+        for (outbound_max, inbound_version, expected) in cases {
+            let (mut tx, mut rx) = stream::byte_stream_pair();
+
+            let join_handle = thread::spawn(move || {
+                FrameNegotiation::inbound(inbound_version)
+                    .negotiate(&mut rx)
+                    .expect("Should have successfully negotiated")
+            });
+
+            let framing = FrameNegotiation::outbound(FrameVersion::V1, outbound_max)
+                .negotiate(&mut tx)
+                .expect("Unable to negotiate a valid version");
+            let remote_framing = join_handle.join().expect("Unable to join thread");
+
+            assert_eq!(expected, framing.version());
+            assert_eq!(expected, remote_framing.version());
+        }
+    }
+
+    /// Test that compressed version 2 frames are written and read back with each algorithm, and
+    /// that payloads too small to be worth compressing are sent as is.
+    #[cfg(feature = "frame-compression")]
+    #[test]
+    fn frame_round_trip_compressed() {
+        let input = b"hello world ".repeat(1000);
+
+        for compression in &[FrameCompression::Deflate, FrameCompression::Zstd] {
+            let mut cursor = Cursor::new(vec![]);
+            FrameRef::new(FrameVersion::V2, &input)
+                .with_compression(Some(*compression))
+                .write(&mut cursor)
+                .expect("Unable to write data");
+            assert!(cursor.get_ref().len() < input.len());
+
+            cursor.set_position(0);
+            let frame = Frame::read(&mut cursor).expect("Unable to read frame");
+            assert_eq!(input, frame.data);
+        }
+
+        let mut cursor = Cursor::new(vec![]);
+        FrameRef::new(FrameVersion::V2, b"hello world")
+            .with_compression(Some(FrameCompression::Zstd))
+            .write(&mut cursor)
+            .expect("Unable to write data");
+        cursor.set_position(0);
+        match FrameHeader::read(&mut cursor).expect("Unable to read header") {
+            FrameHeader::V2 { compression, .. } => assert_eq!(None, compression),
+            header => panic!("Unexpected header: {:?}", header),
+        }
+    }
+
+    /// Test that a compressed frame that would decompress to more than the maximum frame size is
+    /// rejected.
+    #[cfg(feature = "frame-compression")]
+    #[test]
+    fn compressed_frame_too_large() {
+        let input = vec![0u8; 1024 * 1024];
+
+        let mut cursor = Cursor::new(vec![]);
+        FrameRef::new(FrameVersion::V2, &input)
+            .with_compression(Some(FrameCompression::Deflate))
+            .write(&mut cursor)
+            .expect("Unable to write data");
+
+        cursor.set_position(0);
+        match Frame::read_with_max_size(&mut cursor, 64 * 1024) {
+            Err(FrameError::FrameTooLarge { .. }) => (),
+            res => panic!("Unexpected result: {:?}", res.map(Frame::into_inner)),
+        }
+    }
+
+    /// Test that the compression requested by one end is used once both ends have exchanged the
+    /// algorithms they support.
+    #[cfg(feature = "frame-compression")]
+    #[test]
+    fn negotiate_compression() {
+        let (mut tx, mut rx) = stream::byte_stream_pair();
+
+        let join_handle = thread::spawn(move || {
+            FrameNegotiation::inbound(FrameVersion::V2)
+                .negotiate(&mut rx)
+                .expect("Should have successfully negotiated")
+        });
+
+        let framing = FrameNegotiation::outbound(FrameVersion::V1, FrameVersion::V2)
+            .with_options(FrameOptions::default().with_compression(FrameCompression::Zstd))
+            .negotiate(&mut tx)
+            .expect("Unable to negotiate a valid version");
+        let remote_framing = join_handle.join().expect("Unable to join thread");
+
+        assert_eq!(Some(FrameCompression::Zstd), framing.compression());
+        assert_eq!(None, remote_framing.compression());
+    }
+
     #[cfg(not(target_os = "unix"))]
     mod stream {
         use std::io::{Error as IoError, Read, Write};
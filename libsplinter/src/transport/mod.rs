@@ -12,16 +12,26 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(feature = "chaos-transport")]
+pub mod chaos;
 mod frame;
 pub mod inproc;
 pub mod multi;
+#[cfg(feature = "transport-proxy")]
+pub mod proxy;
 pub mod raw;
 pub mod tls;
+#[cfg(feature = "unix-transport")]
+pub mod unix;
+#[cfg(feature = "ws-transport")]
+pub mod ws;
 #[cfg(feature = "zmq-transport")]
 pub mod zmq;
 
 use mio::Evented;
 
+pub use frame::{FrameCompression, FrameOptions};
+
 use std::error::Error;
 use std::io;
 
@@ -30,6 +40,39 @@ pub enum Status {
     Disconnected,
 }
 
+/// The names in a peer's certificate, which has been verified by the transport.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeerCertificate {
+    /// The common name of the certificate's subject, if any.
+    pub subject_common_name: Option<String>,
+    /// The DNS names and URIs among the certificate's subject alternative names.
+    pub subject_alt_names: Vec<String>,
+}
+
+impl PeerCertificate {
+    /// Indicates whether or not the given name is the certificate's subject common name or one of
+    /// its subject alternative names.
+    pub fn has_name(&self, name: &str) -> bool {
+        self.subject_common_name.as_deref() == Some(name)
+            || self
+                .subject_alt_names
+                .iter()
+                .any(|alt_name| alt_name == name)
+    }
+}
+
+/// The credentials of the process on the other end of a local connection, as reported by the
+/// operating system.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PeerCredentials {
+    /// The process ID of the peer process.
+    pub pid: i32,
+    /// The effective user ID of the peer process.
+    pub uid: u32,
+    /// The effective group ID of the peer process.
+    pub gid: u32,
+}
+
 /// A bi-directional connection between two nodes
 pub trait Connection: Send {
     /// Attempt to send a message consisting of bytes across the connection.
@@ -58,6 +101,22 @@ pub trait Connection: Send {
 
     /// Returns a `mio::event::Evented` for this connection which can be used for polling.
     fn evented(&self) -> &dyn Evented;
+
+    /// Return the remote peer's certificate, if the connection type verifies one.
+    ///
+    /// For TLS connections, this is the peer's certificate when it has been verified against the
+    /// configured CA certificates.
+    fn peer_certificate(&self) -> Option<PeerCertificate> {
+        None
+    }
+
+    /// Return the credentials of the remote peer process, if the connection type provides them.
+    ///
+    /// For Unix domain socket connections, these are the credentials the peer process had when
+    /// the connection was established.
+    fn peer_credentials(&self) -> Option<PeerCredentials> {
+        None
+    }
 }
 
 pub trait Listener: Send {
@@ -107,6 +166,29 @@ impl<'a> Iterator for IncomingIter<'a> {
     }
 }
 
+/// Connects to the first of the address's socket addresses that accepts a connection within the
+/// timeout.
+pub(crate) fn connect_with_timeout(
+    address: &str,
+    timeout: std::time::Duration,
+) -> io::Result<std::net::TcpStream> {
+    use std::net::ToSocketAddrs;
+
+    let mut last_err = None;
+    for socket_addr in address.to_socket_addrs()? {
+        match std::net::TcpStream::connect_timeout(&socket_addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_err = Some(err),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} did not resolve to any addresses", address),
+        )
+    }))
+}
+
 // -- Errors --
 
 macro_rules! impl_from_io_error {
@@ -15,18 +15,48 @@
 use mio::{net::TcpStream as MioTcpStream, Evented};
 
 use std::net::{Shutdown, TcpListener, TcpStream};
+use std::time::Duration;
 
 use crate::transport::{
     AcceptError, ConnectError, Connection, DisconnectError, ListenError, Listener, RecvError,
     SendError, Transport,
 };
 
-use super::frame::{Frame, FrameError, FrameNegotiation, FrameRef, FrameVersion};
+use super::frame::{FrameError, FrameNegotiation, FrameOptions, FrameVersion, Framing};
+#[cfg(feature = "transport-proxy")]
+use super::proxy::ProxySettings;
 
 const PROTOCOL_PREFIX: &str = "tcp://";
 
-#[derive(Default)]
-pub struct RawTransport {}
+#[derive(Clone, Default)]
+pub struct RawTransport {
+    frame_options: FrameOptions,
+    connect_timeout: Option<Duration>,
+    #[cfg(feature = "transport-proxy")]
+    proxy_settings: ProxySettings,
+}
+
+impl RawTransport {
+    /// Use the given options for the frames of this transport's connections.
+    pub fn with_frame_options(mut self, frame_options: FrameOptions) -> Self {
+        self.frame_options = frame_options;
+        self
+    }
+
+    /// Gives up on connecting to an endpoint if it does not accept the connection within the
+    /// timeout. By default, connecting waits for as long as the operating system allows.
+    pub fn with_connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Use the given settings to decide whether connections are made through a proxy.
+    #[cfg(feature = "transport-proxy")]
+    pub fn with_proxy_settings(mut self, proxy_settings: ProxySettings) -> Self {
+        self.proxy_settings = proxy_settings;
+        self
+    }
+}
 
 impl Transport for RawTransport {
     fn accepts(&self, address: &str) -> bool {
@@ -47,9 +77,16 @@ impl Transport for RawTransport {
             endpoint
         };
         // Connect a std::net::TcpStream to make sure connect() block
-        let mut stream = TcpStream::connect(address)?;
+        #[cfg(feature = "transport-proxy")]
+        let mut stream = self.proxy_settings.connect(address, self.connect_timeout)?;
+        #[cfg(not(feature = "transport-proxy"))]
+        let mut stream = match self.connect_timeout {
+            Some(timeout) => super::connect_with_timeout(address, timeout)?,
+            None => TcpStream::connect(address)?,
+        };
 
-        let frame_version = FrameNegotiation::outbound(FrameVersion::V1, FrameVersion::V1)
+        let framing = FrameNegotiation::outbound(FrameVersion::V1, FrameVersion::V2)
+            .with_options(self.frame_options)
             .negotiate(&mut stream)
             .map_err(|err| match err {
                 FrameError::UnsupportedVersion => ConnectError::ProtocolError(
@@ -61,7 +98,7 @@ impl Transport for RawTransport {
 
         let mio_stream = MioTcpStream::from_stream(stream)?;
         Ok(Box::new(RawConnection {
-            frame_version,
+            framing,
             stream: mio_stream,
         }))
     }
@@ -82,19 +119,22 @@ impl Transport for RawTransport {
 
         Ok(Box::new(RawListener {
             listener: TcpListener::bind(address)?,
+            frame_options: self.frame_options,
         }))
     }
 }
 
 pub struct RawListener {
     listener: TcpListener,
+    frame_options: FrameOptions,
 }
 
 impl Listener for RawListener {
     fn accept(&mut self) -> Result<Box<dyn Connection>, AcceptError> {
         let (mut stream, _) = self.listener.accept()?;
 
-        let frame_version = FrameNegotiation::inbound(FrameVersion::V1)
+        let framing = FrameNegotiation::inbound(FrameVersion::V2)
+            .with_options(self.frame_options)
             .negotiate(&mut stream)
             .map_err(|err| match err {
                 FrameError::UnsupportedVersion => AcceptError::ProtocolError(
@@ -105,7 +145,7 @@ impl Listener for RawListener {
             })?;
 
         let connection = RawConnection {
-            frame_version,
+            framing,
             stream: MioTcpStream::from_stream(stream)?,
         };
         Ok(Box::new(connection))
@@ -117,13 +157,13 @@ impl Listener for RawListener {
 }
 
 pub struct RawConnection {
-    frame_version: FrameVersion,
+    framing: Framing,
     stream: MioTcpStream,
 }
 
 impl Connection for RawConnection {
     fn send(&mut self, message: &[u8]) -> Result<(), SendError> {
-        match FrameRef::new(self.frame_version, message).write(&mut self.stream) {
+        match self.framing.write(&mut self.stream, message) {
             Err(FrameError::IoError(e)) => Err(SendError::IoError(e)),
             Err(err) => Err(SendError::ProtocolError(err.to_string())),
             Ok(_) => Ok(()),
@@ -131,7 +171,7 @@ impl Connection for RawConnection {
     }
 
     fn recv(&mut self) -> Result<Vec<u8>, RecvError> {
-        match Frame::read(&mut self.stream) {
+        match self.framing.read(&mut self.stream) {
             Err(FrameError::IoError(e)) => Err(RecvError::IoError(e)),
             Err(err) => Err(RecvError::ProtocolError(err.to_string())),
             Ok(frame) => Ok(frame.into_inner()),
@@ -0,0 +1,427 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A transport over Unix domain sockets.
+//!
+//! Endpoints are of the form `unix://<path>`, where the path is the file system path of the
+//! socket, such as `unix:///var/run/splinter/service.sock`. Since only processes that may write
+//! to the socket file can connect, the file's permissions control which local processes can use
+//! the listener. Connections also provide the credentials of the process on the other end, where
+//! the operating system supports it.
+
+use mio::unix::EventedFd;
+use mio::{Evented, Poll, PollOpt, Ready, Token};
+
+use std::fs;
+use std::io;
+use std::net::Shutdown;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::{UnixListener as StdUnixListener, UnixStream};
+use std::path::PathBuf;
+
+use crate::transport::{
+    AcceptError, ConnectError, Connection, DisconnectError, ListenError, Listener, PeerCredentials,
+    RecvError, SendError, Transport,
+};
+
+use super::frame::{FrameError, FrameNegotiation, FrameOptions, FrameVersion, Framing};
+
+const PROTOCOL_PREFIX: &str = "unix://";
+
+#[derive(Default)]
+pub struct UnixTransport {
+    frame_options: FrameOptions,
+}
+
+impl UnixTransport {
+    /// Use the given options for the frames of this transport's connections.
+    pub fn with_frame_options(mut self, frame_options: FrameOptions) -> Self {
+        self.frame_options = frame_options;
+        self
+    }
+}
+
+impl Transport for UnixTransport {
+    fn accepts(&self, address: &str) -> bool {
+        address.starts_with(PROTOCOL_PREFIX) && address.len() > PROTOCOL_PREFIX.len()
+    }
+
+    fn connect(&mut self, endpoint: &str) -> Result<Box<dyn Connection>, ConnectError> {
+        if !self.accepts(endpoint) {
+            return Err(ConnectError::ProtocolError(format!(
+                "Invalid protocol \"{}\"",
+                endpoint
+            )));
+        }
+
+        let path = &endpoint[PROTOCOL_PREFIX.len()..];
+        let mut stream = UnixStream::connect(path)?;
+
+        let framing = FrameNegotiation::outbound(FrameVersion::V1, FrameVersion::V2)
+            .with_options(self.frame_options)
+            .negotiate(&mut stream)
+            .map_err(|err| match err {
+                FrameError::UnsupportedVersion => ConnectError::ProtocolError(
+                    "Unable to connect; remote version is not with in range".into(),
+                ),
+                FrameError::IoError(err) => ConnectError::IoError(err),
+                e => ConnectError::ProtocolError(format!("Unexpected protocol error: {}", e)),
+            })?;
+
+        stream.set_nonblocking(true)?;
+        let credentials = peer_credentials(&stream);
+
+        Ok(Box::new(UnixConnection {
+            framing,
+            stream,
+            remote_endpoint: endpoint.to_string(),
+            local_endpoint: PROTOCOL_PREFIX.to_string(),
+            credentials,
+        }))
+    }
+
+    fn listen(&mut self, bind: &str) -> Result<Box<dyn Listener>, ListenError> {
+        if !self.accepts(bind) {
+            return Err(ListenError::ProtocolError(format!(
+                "Invalid protocol \"{}\"",
+                bind
+            )));
+        }
+
+        let path = PathBuf::from(&bind[PROTOCOL_PREFIX.len()..]);
+
+        // A socket file left behind by a listener that is no longer running would prevent
+        // binding; it is only removed if nothing is accepting connections on it. Any other kind
+        // of file at the path is left alone.
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) if !metadata.file_type().is_socket() => {
+                return Err(ListenError::ProtocolError(format!(
+                    "{} exists and is not a socket",
+                    path.display()
+                )));
+            }
+            Ok(_) if UnixStream::connect(&path).is_err() => {
+                debug!("Removing stale socket file {}", path.display());
+                fs::remove_file(&path)?;
+            }
+            Ok(_) => (),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => return Err(err.into()),
+        }
+
+        Ok(Box::new(UnixListener {
+            listener: StdUnixListener::bind(&path)?,
+            path,
+            accepted: 0,
+            frame_options: self.frame_options,
+        }))
+    }
+}
+
+pub struct UnixListener {
+    listener: StdUnixListener,
+    path: PathBuf,
+    accepted: u64,
+    frame_options: FrameOptions,
+}
+
+impl Listener for UnixListener {
+    fn accept(&mut self) -> Result<Box<dyn Connection>, AcceptError> {
+        let (mut stream, _) = self.listener.accept()?;
+
+        let framing = FrameNegotiation::inbound(FrameVersion::V2)
+            .with_options(self.frame_options)
+            .negotiate(&mut stream)
+            .map_err(|err| match err {
+                FrameError::UnsupportedVersion => AcceptError::ProtocolError(
+                    "Unable to connect; local version not supported by remote".into(),
+                ),
+                FrameError::IoError(err) => AcceptError::IoError(err),
+                err => AcceptError::ProtocolError(format!("Unexpected protocol error: {}", err)),
+            })?;
+
+        stream.set_nonblocking(true)?;
+        let credentials = peer_credentials(&stream);
+
+        // Connecting sockets are not bound to a path of their own, so accepted connections are
+        // told apart by the order in which they were accepted.
+        self.accepted += 1;
+        let connection = UnixConnection {
+            framing,
+            stream,
+            remote_endpoint: format!("{}#{}", self.endpoint(), self.accepted),
+            local_endpoint: self.endpoint(),
+            credentials,
+        };
+        Ok(Box::new(connection))
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}{}", PROTOCOL_PREFIX, self.path.display())
+    }
+}
+
+impl Drop for UnixListener {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            debug!(
+                "Unable to remove socket file {}: {}",
+                self.path.display(),
+                err
+            );
+        }
+    }
+}
+
+pub struct UnixConnection {
+    framing: Framing,
+    stream: UnixStream,
+    remote_endpoint: String,
+    local_endpoint: String,
+    credentials: Option<PeerCredentials>,
+}
+
+impl Connection for UnixConnection {
+    fn send(&mut self, message: &[u8]) -> Result<(), SendError> {
+        match self.framing.write(&mut self.stream, message) {
+            Err(FrameError::IoError(e)) => Err(SendError::IoError(e)),
+            Err(err) => Err(SendError::ProtocolError(err.to_string())),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    fn recv(&mut self) -> Result<Vec<u8>, RecvError> {
+        match self.framing.read(&mut self.stream) {
+            Err(FrameError::IoError(e)) => Err(RecvError::IoError(e)),
+            Err(err) => Err(RecvError::ProtocolError(err.to_string())),
+            Ok(frame) => Ok(frame.into_inner()),
+        }
+    }
+
+    fn remote_endpoint(&self) -> String {
+        self.remote_endpoint.clone()
+    }
+
+    fn local_endpoint(&self) -> String {
+        self.local_endpoint.clone()
+    }
+
+    fn disconnect(&mut self) -> Result<(), DisconnectError> {
+        self.stream
+            .shutdown(Shutdown::Both)
+            .map_err(DisconnectError::from)
+    }
+
+    fn evented(&self) -> &dyn Evented {
+        self
+    }
+
+    fn peer_credentials(&self) -> Option<PeerCredentials> {
+        self.credentials
+    }
+}
+
+impl AsRawFd for UnixConnection {
+    fn as_raw_fd(&self) -> RawFd {
+        self.stream.as_raw_fd()
+    }
+}
+
+impl Evented for UnixConnection {
+    fn register(
+        &self,
+        poll: &Poll,
+        token: Token,
+        interest: Ready,
+        opts: PollOpt,
+    ) -> io::Result<()> {
+        EventedFd(&self.as_raw_fd()).register(poll, token, interest, opts)
+    }
+
+    fn reregister(
+        &self,
+        poll: &Poll,
+        token: Token,
+        interest: Ready,
+        opts: PollOpt,
+    ) -> io::Result<()> {
+        EventedFd(&self.as_raw_fd()).reregister(poll, token, interest, opts)
+    }
+
+    fn deregister(&self, poll: &Poll) -> io::Result<()> {
+        EventedFd(&self.as_raw_fd()).deregister(poll)
+    }
+}
+
+/// Looks up the credentials of the process on the other end of the given stream.
+#[cfg(target_os = "linux")]
+fn peer_credentials(stream: &UnixStream) -> Option<PeerCredentials> {
+    let mut ucred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+
+    // Safe, as the buffer and its length describe a valid ucred struct, which is what
+    // SO_PEERCRED writes.
+    let res = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut ucred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+
+    if res == 0 {
+        Some(PeerCredentials {
+            pid: ucred.pid,
+            uid: ucred.uid,
+            gid: ucred.gid,
+        })
+    } else {
+        warn!(
+            "Unable to look up peer credentials: {}",
+            io::Error::last_os_error()
+        );
+        None
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn peer_credentials(_stream: &UnixStream) -> Option<PeerCredentials> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::tests;
+
+    use tempdir::TempDir;
+
+    fn socket_endpoint(dir: &TempDir) -> String {
+        format!("unix://{}", dir.path().join("test.sock").display())
+    }
+
+    #[test]
+    fn test_accepts() {
+        let transport = UnixTransport::default();
+        assert!(transport.accepts("unix:///var/run/splinter/service.sock"));
+        assert!(transport.accepts("unix://service.sock"));
+
+        assert!(!transport.accepts("unix://"));
+        assert!(!transport.accepts("127.0.0.1:0"));
+        assert!(!transport.accepts("tcp://127.0.0.1:0"));
+        assert!(!transport.accepts("tls://somewhere.example.com:4000"));
+    }
+
+    #[test]
+    fn test_transport() {
+        let dir = TempDir::new("unix_transport").expect("Unable to create temp dir");
+        let transport = UnixTransport::default();
+
+        tests::test_transport(transport, &socket_endpoint(&dir));
+    }
+
+    #[test]
+    fn test_poll() {
+        let dir = TempDir::new("unix_transport").expect("Unable to create temp dir");
+        let transport = UnixTransport::default();
+
+        tests::test_poll(
+            transport,
+            &socket_endpoint(&dir),
+            Ready::readable() | Ready::writable(),
+        );
+    }
+
+    /// Test that a listener replaces a stale socket file, and removes its socket file when it is
+    /// dropped.
+    #[test]
+    fn test_socket_file_lifecycle() {
+        let dir = TempDir::new("unix_transport").expect("Unable to create temp dir");
+        let endpoint = socket_endpoint(&dir);
+        let path = dir.path().join("test.sock");
+
+        // Leave a socket file behind, as a listener that did not shut down cleanly would
+        drop(StdUnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let mut transport = UnixTransport::default();
+        let listener = transport.listen(&endpoint).expect("Unable to listen");
+        assert_eq!(endpoint, listener.endpoint());
+        assert!(path.exists());
+
+        // A socket file with an active listener must not be replaced
+        assert!(transport.listen(&endpoint).is_err());
+
+        drop(listener);
+        assert!(!path.exists());
+    }
+
+    /// Test that a listener does not remove a file at its path that is not a socket.
+    #[test]
+    fn test_listen_on_regular_file() {
+        let dir = TempDir::new("unix_transport").expect("Unable to create temp dir");
+        let endpoint = socket_endpoint(&dir);
+        let path = dir.path().join("test.sock");
+
+        fs::write(&path, b"not a socket").expect("Unable to write file");
+
+        let mut transport = UnixTransport::default();
+        match transport.listen(&endpoint) {
+            Err(ListenError::ProtocolError(_)) => (),
+            Err(err) => panic!("Unexpected error: {:?}", err),
+            Ok(_) => panic!("Listened on a regular file"),
+        }
+
+        assert_eq!(b"not a socket".to_vec(), fs::read(&path).unwrap());
+    }
+
+    /// Test that both ends of a connection report the credentials of this process, and that
+    /// accepted connections have distinct remote endpoints.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_peer_credentials() {
+        let dir = TempDir::new("unix_transport").expect("Unable to create temp dir");
+        let endpoint = socket_endpoint(&dir);
+
+        let mut transport = UnixTransport::default();
+        let mut listener = transport.listen(&endpoint).expect("Unable to listen");
+
+        let handle = std::thread::spawn(move || {
+            let mut transport = UnixTransport::default();
+            let first = transport.connect(&endpoint).expect("Unable to connect");
+            let second = transport.connect(&endpoint).expect("Unable to connect");
+            (first.peer_credentials(), second.peer_credentials())
+        });
+
+        let first = listener.accept().expect("Unable to accept");
+        let second = listener.accept().expect("Unable to accept");
+        let (client_credentials, _) = handle.join().unwrap();
+
+        let expected = PeerCredentials {
+            pid: std::process::id() as i32,
+            uid: unsafe { libc::getuid() },
+            gid: unsafe { libc::getgid() },
+        };
+        assert_eq!(Some(expected), first.peer_credentials());
+        assert_eq!(Some(expected), client_credentials);
+        assert_ne!(first.remote_endpoint(), second.remote_endpoint());
+    }
+}
@@ -0,0 +1,1405 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A transport carrying the frame protocol over WebSocket connections.
+//!
+//! Connections are made to `ws://` endpoints and, if the transport is given a TLS configuration,
+//! `wss://` endpoints. An endpoint may include a path, such as `wss://acme.com:443/splinter`,
+//! which is requested during the handshake; listeners accept upgrades on any path. Each frame of
+//! the frame protocol is sent as a single binary WebSocket message, so that connections can pass
+//! through proxies and load balancers that only forward HTTP traffic.
+
+use mio::{unix::EventedFd, Evented, Poll, PollOpt, Ready, Token};
+use openssl::base64;
+use openssl::rand::rand_bytes;
+use openssl::sha::sha1;
+use openssl::ssl::SslStream;
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::transport::{
+    AcceptError, ConnectError, Connection, DisconnectError, ListenError, Listener, PeerCertificate,
+    RecvError, SendError, Transport,
+};
+
+use super::frame::{FrameError, FrameNegotiation, FrameOptions, FrameVersion, Framing};
+#[cfg(feature = "transport-proxy")]
+use super::proxy::ProxySettings;
+use super::tls::{endpoint_to_dns_name, verified_peer_certificate, TlsContexts, TlsTransport};
+
+const WS_PROTOCOL_PREFIX: &str = "ws://";
+const WSS_PROTOCOL_PREFIX: &str = "wss://";
+
+/// The GUID appended to a handshake key to compute its accept key, as given by RFC 6455.
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// The maximum length, in bytes, of a handshake request or response.
+const MAX_HANDSHAKE_LENGTH: usize = 8192;
+/// The length, in bytes, of a version 2 frame header, which a message carries before the frame's
+/// payload.
+const FRAME_HEADER_LENGTH: u64 = 13;
+/// The number of bytes of a payload read at a time, so that no more memory is allocated for a
+/// payload than the bytes that have arrived require.
+const READ_CHUNK_LENGTH: u64 = 64 * 1024;
+/// The default time allowed to connect, to complete the opening handshake, and to finish reading
+/// or writing a frame once it has started.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+/// The default time allowed for an accepted connection to complete its TLS handshake, opening
+/// handshake, and frame negotiation, during which the listener accepts no other connection.
+const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
+/// The maximum length, in bytes, of the payload of a control message.
+const MAX_CONTROL_PAYLOAD_LENGTH: usize = 125;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// A transport for `ws://` and `wss://` endpoints.
+///
+/// The default transport only accepts `ws://` endpoints.
+#[derive(Clone)]
+pub struct WsTransport {
+    tls: Option<TlsContexts>,
+    frame_options: FrameOptions,
+    timeout: Duration,
+    handshake_timeout: Duration,
+    #[cfg(feature = "transport-proxy")]
+    proxy_settings: ProxySettings,
+}
+
+impl Default for WsTransport {
+    fn default() -> Self {
+        WsTransport {
+            tls: None,
+            frame_options: FrameOptions::default(),
+            timeout: DEFAULT_TIMEOUT,
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+            #[cfg(feature = "transport-proxy")]
+            proxy_settings: ProxySettings::default(),
+        }
+    }
+}
+
+impl WsTransport {
+    /// Constructs a transport that accepts both `ws://` and `wss://` endpoints, securing `wss://`
+    /// connections with the certificates and keys of the given TLS transport.
+    /// The transport also uses the TLS transport's frame options and proxy settings, and its
+    /// certificates and keys are replaced whenever the TLS transport's are reloaded.
+    pub fn with_tls(tls_transport: &TlsTransport) -> Self {
+        WsTransport {
+            tls: Some(tls_transport.contexts.clone()),
+            frame_options: tls_transport.frame_options,
+            #[cfg(feature = "transport-proxy")]
+            proxy_settings: tls_transport.proxy_settings.clone(),
+            ..WsTransport::default()
+        }
+    }
+
+    /// Use the given options for the frames of this transport's connections. Messages longer than
+    /// a frame of the maximum frame size are rejected before they are read.
+    pub fn with_frame_options(mut self, frame_options: FrameOptions) -> Self {
+        self.frame_options = frame_options;
+        self
+    }
+
+    /// Sets the time allowed to connect, to complete the opening handshake, and to finish reading
+    /// or writing a message once it has started. Defaults to 10 seconds. The handshakes of the
+    /// connections a listener accepts are bounded by the handshake timeout instead.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the time allowed for a connection accepted by a listener to complete all of its
+    /// handshakes. A listener accepts no other connection until then, so this is kept short.
+    /// Defaults to 2 seconds.
+    pub fn with_handshake_timeout(mut self, handshake_timeout: Duration) -> Self {
+        self.handshake_timeout = handshake_timeout;
+        self
+    }
+
+    /// Use the given settings to decide whether connections are made through a proxy. The
+    /// WebSocket handshake, and the TLS handshake of `wss://` connections, take place through the
+    /// proxy's tunnel.
+    #[cfg(feature = "transport-proxy")]
+    pub fn with_proxy_settings(mut self, proxy_settings: ProxySettings) -> Self {
+        self.proxy_settings = proxy_settings;
+        self
+    }
+
+    fn max_message_length(&self) -> u64 {
+        max_message_length(&self.frame_options)
+    }
+}
+
+/// The parts of a `ws://` or `wss://` endpoint.
+struct WsEndpoint<'a> {
+    secure: bool,
+    address: &'a str,
+    path: &'a str,
+}
+
+fn parse_endpoint(endpoint: &str) -> Option<WsEndpoint> {
+    let (secure, rest) = if endpoint.starts_with(WSS_PROTOCOL_PREFIX) {
+        (true, &endpoint[WSS_PROTOCOL_PREFIX.len()..])
+    } else if endpoint.starts_with(WS_PROTOCOL_PREFIX) {
+        (false, &endpoint[WS_PROTOCOL_PREFIX.len()..])
+    } else {
+        return None;
+    };
+
+    let (address, path) = match rest.find('/') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, "/"),
+    };
+
+    Some(WsEndpoint {
+        secure,
+        address,
+        path,
+    })
+}
+
+impl Transport for WsTransport {
+    fn accepts(&self, address: &str) -> bool {
+        address.starts_with(WS_PROTOCOL_PREFIX)
+            || (self.tls.is_some() && address.starts_with(WSS_PROTOCOL_PREFIX))
+    }
+
+    fn connect(&mut self, endpoint: &str) -> Result<Box<dyn Connection>, ConnectError> {
+        let ws_endpoint = match parse_endpoint(endpoint) {
+            Some(ws_endpoint) if self.accepts(endpoint) => ws_endpoint,
+            _ => {
+                return Err(ConnectError::ProtocolError(format!(
+                    "Invalid protocol \"{}\"",
+                    endpoint
+                )))
+            }
+        };
+
+        #[cfg(feature = "transport-proxy")]
+        let stream = self
+            .proxy_settings
+            .connect(ws_endpoint.address, Some(self.timeout))?;
+        #[cfg(not(feature = "transport-proxy"))]
+        let stream = super::connect_with_timeout(ws_endpoint.address, self.timeout)?;
+        // bounds the handshakes, which are made while the stream is blocking
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        let (stream, peer_certificate) = match self.tls {
+            Some(ref contexts) if ws_endpoint.secure => {
+                let dns_name = endpoint_to_dns_name(ws_endpoint.address)?;
+                let tls_stream = contexts.connector().connect(&dns_name, stream)?;
+                let peer_certificate = verified_peer_certificate(&tls_stream);
+                (WsStream::Tls(tls_stream), peer_certificate)
+            }
+            _ => (WsStream::Plain(stream), None),
+        };
+
+        let mut socket = WsSocket::new(stream, Role::Client)
+            .with_max_message_length(self.max_message_length())
+            .with_timeout(self.timeout);
+        socket
+            .client_handshake(ws_endpoint.address, ws_endpoint.path)
+            .map_err(|err| match err {
+                WsError::Io(err) => ConnectError::IoError(err),
+                err => ConnectError::ProtocolError(format!("WebSocket handshake failed: {}", err)),
+            })?;
+
+        let framing = negotiate(
+            &mut socket,
+            FrameNegotiation::outbound(FrameVersion::V1, FrameVersion::V2)
+                .with_options(self.frame_options),
+        )
+        .map_err(|err| match err {
+            FrameError::UnsupportedVersion => ConnectError::ProtocolError(
+                "Unable to connect; remote version is not with in range".into(),
+            ),
+            FrameError::IoError(err) => ConnectError::IoError(err),
+            e => ConnectError::ProtocolError(format!("Unexpected protocol error: {}", e)),
+        })?;
+
+        socket.stream.tcp_stream().set_nonblocking(true)?;
+        Ok(Box::new(WsConnection {
+            framing,
+            socket,
+            peer_certificate,
+        }))
+    }
+
+    fn listen(&mut self, bind: &str) -> Result<Box<dyn Listener>, ListenError> {
+        let ws_endpoint = match parse_endpoint(bind) {
+            Some(ws_endpoint) if self.accepts(bind) => ws_endpoint,
+            _ => {
+                return Err(ListenError::ProtocolError(format!(
+                    "Invalid protocol \"{}\"",
+                    bind
+                )))
+            }
+        };
+
+        let acceptor = match self.tls {
+            Some(ref contexts) if ws_endpoint.secure => Some(contexts.clone()),
+            _ => None,
+        };
+
+        Ok(Box::new(WsListener {
+            listener: TcpListener::bind(ws_endpoint.address)?,
+            acceptor,
+            frame_options: self.frame_options,
+            max_message_length: self.max_message_length(),
+            timeout: self.timeout,
+            handshake_timeout: self.handshake_timeout,
+        }))
+    }
+}
+
+pub struct WsListener {
+    listener: TcpListener,
+    /// The contexts for TLS connections, if the listener is for a `wss://` endpoint
+    acceptor: Option<TlsContexts>,
+    frame_options: FrameOptions,
+    max_message_length: u64,
+    timeout: Duration,
+    handshake_timeout: Duration,
+}
+
+impl Listener for WsListener {
+    fn accept(&mut self) -> Result<Box<dyn Connection>, AcceptError> {
+        let (stream, _) = self.listener.accept()?;
+        // every handshake shares one deadline, so that a stalled client cannot hold up the
+        // listener for longer than the handshake timeout
+        let deadline = Instant::now() + self.handshake_timeout;
+        stream.set_read_timeout(Some(self.handshake_timeout))?;
+        stream.set_write_timeout(Some(self.handshake_timeout))?;
+
+        let (stream, peer_certificate) = match self.acceptor {
+            Some(ref contexts) => {
+                let tls_stream = contexts.acceptor().accept(stream)?;
+                let peer_certificate = verified_peer_certificate(&tls_stream);
+                (WsStream::Tls(tls_stream), peer_certificate)
+            }
+            None => (WsStream::Plain(stream), None),
+        };
+
+        let remaining = time_remaining(deadline)?;
+        stream.tcp_stream().set_read_timeout(Some(remaining))?;
+        stream.tcp_stream().set_write_timeout(Some(remaining))?;
+        let mut socket = WsSocket::new(stream, Role::Server)
+            .with_max_message_length(self.max_message_length)
+            .with_timeout(remaining);
+        socket.server_handshake().map_err(|err| match err {
+            WsError::Io(err) => AcceptError::IoError(err),
+            err => AcceptError::ProtocolError(format!("WebSocket handshake failed: {}", err)),
+        })?;
+
+        let remaining = time_remaining(deadline)?;
+        let tcp_stream = socket.stream.tcp_stream();
+        tcp_stream.set_read_timeout(Some(remaining))?;
+        tcp_stream.set_write_timeout(Some(remaining))?;
+        socket.timeout = remaining;
+        let framing = negotiate(
+            &mut socket,
+            FrameNegotiation::inbound(FrameVersion::V2).with_options(self.frame_options),
+        )
+        .map_err(|err| match err {
+            FrameError::UnsupportedVersion => AcceptError::ProtocolError(
+                "Unable to connect; local version not supported by remote".into(),
+            ),
+            FrameError::IoError(err) => AcceptError::IoError(err),
+            err => AcceptError::ProtocolError(format!("Unexpected protocol error: {}", err)),
+        })?;
+
+        socket.timeout = self.timeout;
+        socket.stream.tcp_stream().set_nonblocking(true)?;
+        Ok(Box::new(WsConnection {
+            framing,
+            socket,
+            peer_certificate,
+        }))
+    }
+
+    fn endpoint(&self) -> String {
+        let prefix = if self.acceptor.is_some() {
+            WSS_PROTOCOL_PREFIX
+        } else {
+            WS_PROTOCOL_PREFIX
+        };
+        format!("{}{}", prefix, self.listener.local_addr().unwrap())
+    }
+}
+
+pub struct WsConnection {
+    framing: Framing,
+    socket: WsSocket<WsStream>,
+    peer_certificate: Option<PeerCertificate>,
+}
+
+impl Connection for WsConnection {
+    fn send(&mut self, message: &[u8]) -> Result<(), SendError> {
+        let mut buffer = vec![];
+        self.framing
+            .write(&mut buffer, message)
+            .map_err(|err| SendError::ProtocolError(err.to_string()))?;
+
+        self.socket
+            .send_message(OPCODE_BINARY, &buffer)
+            .map_err(|err| match err {
+                WsError::WouldBlock => SendError::WouldBlock,
+                WsError::Closed => SendError::Disconnected,
+                WsError::Io(err) => SendError::IoError(err),
+                WsError::Protocol(msg) => SendError::ProtocolError(msg),
+            })
+    }
+
+    fn recv(&mut self) -> Result<Vec<u8>, RecvError> {
+        let message = self.socket.recv_message().map_err(|err| match err {
+            WsError::WouldBlock => RecvError::WouldBlock,
+            WsError::Closed => RecvError::Disconnected,
+            WsError::Io(err) => RecvError::IoError(err),
+            WsError::Protocol(msg) => RecvError::ProtocolError(msg),
+        })?;
+
+        match self.framing.read(&mut &message[..]) {
+            Err(err) => Err(RecvError::ProtocolError(err.to_string())),
+            Ok(frame) => Ok(frame.into_inner()),
+        }
+    }
+
+    fn remote_endpoint(&self) -> String {
+        format!(
+            "{}{}",
+            self.socket.stream.protocol_prefix(),
+            self.socket.stream.tcp_stream().peer_addr().unwrap()
+        )
+    }
+
+    fn local_endpoint(&self) -> String {
+        format!(
+            "{}{}",
+            self.socket.stream.protocol_prefix(),
+            self.socket.stream.tcp_stream().local_addr().unwrap()
+        )
+    }
+
+    fn disconnect(&mut self) -> Result<(), DisconnectError> {
+        // the close message is a courtesy to the remote end, which will see the shutdown either way
+        let _ = self.socket.close();
+        if let WsStream::Tls(ref mut tls_stream) = self.socket.stream {
+            let _ = tls_stream.shutdown();
+        }
+        self.socket
+            .stream
+            .tcp_stream()
+            .shutdown(Shutdown::Both)
+            .map_err(DisconnectError::from)
+    }
+
+    fn evented(&self) -> &dyn Evented {
+        self
+    }
+
+    fn peer_certificate(&self) -> Option<PeerCertificate> {
+        self.peer_certificate.clone()
+    }
+}
+
+impl AsRawFd for WsConnection {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.stream.tcp_stream().as_raw_fd()
+    }
+}
+
+impl Evented for WsConnection {
+    fn register(
+        &self,
+        poll: &Poll,
+        token: Token,
+        interest: Ready,
+        opts: PollOpt,
+    ) -> io::Result<()> {
+        EventedFd(&self.as_raw_fd()).register(poll, token, interest, opts)
+    }
+
+    fn reregister(
+        &self,
+        poll: &Poll,
+        token: Token,
+        interest: Ready,
+        opts: PollOpt,
+    ) -> io::Result<()> {
+        EventedFd(&self.as_raw_fd()).reregister(poll, token, interest, opts)
+    }
+
+    fn deregister(&self, poll: &Poll) -> io::Result<()> {
+        EventedFd(&self.as_raw_fd()).deregister(poll)
+    }
+}
+
+/// The stream a WebSocket connection is made over.
+enum WsStream {
+    Plain(TcpStream),
+    Tls(SslStream<TcpStream>),
+}
+
+impl WsStream {
+    fn tcp_stream(&self) -> &TcpStream {
+        match self {
+            WsStream::Plain(stream) => stream,
+            WsStream::Tls(tls_stream) => tls_stream.get_ref(),
+        }
+    }
+
+    fn protocol_prefix(&self) -> &'static str {
+        match self {
+            WsStream::Plain(_) => WS_PROTOCOL_PREFIX,
+            WsStream::Tls(_) => WSS_PROTOCOL_PREFIX,
+        }
+    }
+}
+
+impl Read for WsStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            WsStream::Plain(stream) => stream.read(buf),
+            WsStream::Tls(tls_stream) => tls_stream.read(buf),
+        }
+    }
+}
+
+impl Write for WsStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            WsStream::Plain(stream) => stream.write(buf),
+            WsStream::Tls(tls_stream) => tls_stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            WsStream::Plain(stream) => stream.flush(),
+            WsStream::Tls(tls_stream) => tls_stream.flush(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum WsError {
+    /// No message is available yet on a non-blocking stream.
+    WouldBlock,
+    /// The connection has been closed.
+    Closed,
+    Io(io::Error),
+    Protocol(String),
+}
+
+impl fmt::Display for WsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WsError::WouldBlock => f.write_str("operation would block"),
+            WsError::Closed => f.write_str("connection closed"),
+            WsError::Io(err) => write!(f, "io error occurred: {}", err),
+            WsError::Protocol(msg) => write!(f, "protocol error occurred: {}", msg),
+        }
+    }
+}
+
+impl From<io::Error> for WsError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::WouldBlock => WsError::WouldBlock,
+            io::ErrorKind::UnexpectedEof => WsError::Closed,
+            _ => WsError::Io(err),
+        }
+    }
+}
+
+impl From<WsError> for io::Error {
+    fn from(err: WsError) -> Self {
+        match err {
+            WsError::WouldBlock => io::Error::from(io::ErrorKind::WouldBlock),
+            WsError::Closed => io::Error::from(io::ErrorKind::UnexpectedEof),
+            WsError::Io(err) => err,
+            WsError::Protocol(msg) => io::Error::new(io::ErrorKind::InvalidData, msg),
+        }
+    }
+}
+
+/// The end of a WebSocket connection; clients mask the messages they send.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Role {
+    Client,
+    Server,
+}
+
+/// A WebSocket frame, which is either a message or a fragment of one.
+struct WsFrame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+/// The sending and receiving of binary messages over a stream, as defined by RFC 6455.
+///
+/// Frames are read exactly, so that no bytes beyond the current message are buffered; a
+/// connection is therefore only readable while its stream is.
+struct WsSocket<S: Read + Write> {
+    stream: S,
+    role: Role,
+    close_sent: bool,
+    max_message_length: u64,
+    timeout: Duration,
+}
+
+impl<S: Read + Write> WsSocket<S> {
+    fn new(stream: S, role: Role) -> Self {
+        WsSocket {
+            stream,
+            role,
+            close_sent: false,
+            max_message_length: max_message_length(&FrameOptions::default()),
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Sets the length, in bytes, of the longest message that will be received.
+    fn with_max_message_length(mut self, max_message_length: u64) -> Self {
+        self.max_message_length = max_message_length;
+        self
+    }
+
+    /// Sets the time allowed to finish reading or writing a frame once it has started.
+    fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Performs the client's half of the opening handshake.
+    fn client_handshake(&mut self, host: &str, path: &str) -> Result<(), WsError> {
+        let mut nonce = [0u8; 16];
+        rand_bytes(&mut nonce)
+            .map_err(|err| WsError::Protocol(format!("unable to generate key: {}", err)))?;
+        let key = base64::encode_block(&nonce);
+
+        let request = format!(
+            "GET {} HTTP/1.1\r\n\
+             Host: {}\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Key: {}\r\n\
+             Sec-WebSocket-Version: 13\r\n\r\n",
+            path, host, key
+        );
+        write_full(&mut self.stream, request.as_bytes(), self.timeout)?;
+
+        let response = read_http_head(&mut self.stream, self.timeout)?;
+        if !response.start_line.starts_with("HTTP/1.1 101") {
+            return Err(WsError::Protocol(format!(
+                "upgrade was refused: {}",
+                response.start_line
+            )));
+        }
+        if response.header("Sec-WebSocket-Accept") != Some(accept_key(&key).as_str()) {
+            return Err(WsError::Protocol("invalid Sec-WebSocket-Accept".into()));
+        }
+
+        Ok(())
+    }
+
+    /// Performs the server's half of the opening handshake.
+    fn server_handshake(&mut self) -> Result<(), WsError> {
+        let request = read_http_head(&mut self.stream, self.timeout)?;
+        let key = match upgrade_request_key(&request) {
+            Ok(key) => key,
+            Err(msg) => {
+                let _ = write_full(
+                    &mut self.stream,
+                    b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n",
+                    self.timeout,
+                );
+                return Err(WsError::Protocol(msg));
+            }
+        };
+
+        let response = format!(
+            "HTTP/1.1 101 Switching Protocols\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Accept: {}\r\n\r\n",
+            accept_key(key)
+        );
+        write_full(&mut self.stream, response.as_bytes(), self.timeout)
+    }
+
+    /// Sends a complete message with the given opcode.
+    fn send_message(&mut self, opcode: u8, payload: &[u8]) -> Result<(), WsError> {
+        if self.close_sent {
+            return Err(WsError::Closed);
+        }
+        write_frame(
+            &mut self.stream,
+            true,
+            opcode,
+            payload,
+            self.role == Role::Client,
+            self.timeout,
+        )
+    }
+
+    /// Receives the next binary message, answering any pings and closes received before it.
+    ///
+    /// Returns `WsError::WouldBlock` if the stream is non-blocking and no message has started to
+    /// arrive.
+    fn recv_message(&mut self) -> Result<Vec<u8>, WsError> {
+        let mut message: Option<Vec<u8>> = None;
+        loop {
+            // clients mask the frames they send, and servers do not
+            let frame = read_frame(
+                &mut self.stream,
+                message.is_none(),
+                self.role == Role::Server,
+                self.max_message_length,
+                self.timeout,
+            )?;
+            match frame.opcode {
+                OPCODE_BINARY if message.is_none() => {
+                    if frame.fin {
+                        return Ok(frame.payload);
+                    }
+                    message = Some(frame.payload);
+                }
+                OPCODE_CONTINUATION if message.is_some() => {
+                    let mut fragments = message.take().unwrap_or_default();
+                    if (fragments.len() + frame.payload.len()) as u64 > self.max_message_length {
+                        return Err(WsError::Protocol("message is too long".into()));
+                    }
+                    fragments.extend(frame.payload);
+                    if frame.fin {
+                        return Ok(fragments);
+                    }
+                    message = Some(fragments);
+                }
+                OPCODE_PING => self.send_message(OPCODE_PONG, &frame.payload)?,
+                OPCODE_PONG => (),
+                OPCODE_CLOSE => {
+                    // echo the status code, completing the closing handshake
+                    let _ = self
+                        .send_message(OPCODE_CLOSE, &frame.payload[..frame.payload.len().min(2)]);
+                    self.close_sent = true;
+                    return Err(WsError::Closed);
+                }
+                OPCODE_TEXT => {
+                    return Err(WsError::Protocol("text messages are not supported".into()))
+                }
+                opcode => {
+                    return Err(WsError::Protocol(format!(
+                        "unexpected frame with opcode {}",
+                        opcode
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Starts the closing handshake.
+    fn close(&mut self) -> Result<(), WsError> {
+        self.send_message(OPCODE_CLOSE, &[])?;
+        self.close_sent = true;
+        Ok(())
+    }
+}
+
+/// Carries the frame version negotiation over binary messages, treating their payloads as a
+/// stream of bytes. Written bytes are sent as one message when the stream is flushed or read.
+struct NegotiationStream<'a, S: Read + Write> {
+    socket: &'a mut WsSocket<S>,
+    input: Vec<u8>,
+    output: Vec<u8>,
+}
+
+impl<'a, S: Read + Write> Read for NegotiationStream<'a, S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.input.is_empty() {
+            self.flush()?;
+            self.input = self.socket.recv_message()?;
+        }
+        let n = buf.len().min(self.input.len());
+        buf[..n].copy_from_slice(&self.input[..n]);
+        self.input.drain(..n);
+        Ok(n)
+    }
+}
+
+impl<'a, S: Read + Write> Write for NegotiationStream<'a, S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.output.is_empty() {
+            self.socket.send_message(OPCODE_BINARY, &self.output)?;
+            self.output.clear();
+        }
+        Ok(())
+    }
+}
+
+/// Negotiates the framing over the given socket.
+fn negotiate<S: Read + Write>(
+    socket: &mut WsSocket<S>,
+    negotiation: FrameNegotiation,
+) -> Result<Framing, FrameError> {
+    let mut stream = NegotiationStream {
+        socket,
+        input: vec![],
+        output: vec![],
+    };
+    let result = negotiation.negotiate(&mut stream);
+    // the inbound side's reply, including a refusal, is sent once negotiation is complete
+    stream.flush()?;
+    result
+}
+
+/// Returns the length, in bytes, of the longest message carrying a frame with the given options.
+fn max_message_length(frame_options: &FrameOptions) -> u64 {
+    u64::from(frame_options.max_frame_size()) + FRAME_HEADER_LENGTH
+}
+
+/// Returns the Sec-WebSocket-Accept value for the given Sec-WebSocket-Key.
+fn accept_key(key: &str) -> String {
+    base64::encode_block(&sha1(format!("{}{}", key, WEBSOCKET_GUID).as_bytes()))
+}
+
+/// The start line and headers of an HTTP request or response.
+struct HttpHead {
+    start_line: String,
+    headers: Vec<(String, String)>,
+}
+
+impl HttpHead {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Indicates whether or not the given header lists the given token.
+    fn header_has_token(&self, name: &str, token: &str) -> bool {
+        self.header(name)
+            .map(|value| {
+                value
+                    .split(',')
+                    .any(|item| item.trim().eq_ignore_ascii_case(token))
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// Returns the Sec-WebSocket-Key of the given upgrade request, if it is a valid one.
+fn upgrade_request_key(request: &HttpHead) -> Result<&str, String> {
+    if !request.start_line.starts_with("GET ") {
+        return Err(format!("not an upgrade request: {}", request.start_line));
+    }
+    if !request.header_has_token("Upgrade", "websocket") {
+        return Err("missing Upgrade: websocket".into());
+    }
+    if !request.header_has_token("Connection", "upgrade") {
+        return Err("missing Connection: Upgrade".into());
+    }
+    if request.header("Sec-WebSocket-Version") != Some("13") {
+        return Err("unsupported Sec-WebSocket-Version".into());
+    }
+    request
+        .header("Sec-WebSocket-Key")
+        .ok_or_else(|| "missing Sec-WebSocket-Key".to_string())
+}
+
+/// Reads the head of an HTTP request or response, leaving anything after it in the stream.
+fn read_http_head<R: Read>(reader: &mut R, timeout: Duration) -> Result<HttpHead, WsError> {
+    let deadline = Instant::now() + timeout;
+    let mut head = vec![];
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_HANDSHAKE_LENGTH {
+            return Err(WsError::Protocol("handshake is too long".into()));
+        }
+        read_full(reader, &mut byte, false, deadline)?;
+        head.push(byte[0]);
+    }
+
+    let head = String::from_utf8(head)
+        .map_err(|_| WsError::Protocol("handshake is not valid UTF-8".into()))?;
+    let mut lines = head.split("\r\n").filter(|line| !line.is_empty());
+    let start_line = lines.next().unwrap_or_default().to_string();
+    let headers = lines
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, ':');
+            Some((
+                parts.next()?.trim().to_string(),
+                parts.next()?.trim().to_string(),
+            ))
+        })
+        .collect();
+
+    Ok(HttpHead {
+        start_line,
+        headers,
+    })
+}
+
+/// Reads a frame from the given reader, unmasking its payload. The frame must be masked if
+/// `expect_masked` is set, and unmasked otherwise, and its payload may not be longer than
+/// `max_length`.
+///
+/// If `nonblocking` is set and no bytes of the frame are available, `WsError::WouldBlock` is
+/// returned; otherwise, this waits for the whole frame, for at most `timeout` once it has started.
+fn read_frame<R: Read>(
+    reader: &mut R,
+    nonblocking: bool,
+    expect_masked: bool,
+    max_length: u64,
+    timeout: Duration,
+) -> Result<WsFrame, WsError> {
+    let mut head = [0u8; 2];
+    read_full(reader, &mut head, nonblocking, Instant::now() + timeout)?;
+    let deadline = Instant::now() + timeout;
+
+    if head[0] & 0x70 != 0 {
+        return Err(WsError::Protocol("reserved bits are set".into()));
+    }
+    let fin = head[0] & 0x80 != 0;
+    let opcode = head[0] & 0x0F;
+    let masked = head[1] & 0x80 != 0;
+    if masked != expect_masked {
+        return Err(WsError::Protocol(if expect_masked {
+            "client frame is not masked".into()
+        } else {
+            "server frame is masked".into()
+        }));
+    }
+
+    let length = match head[1] & 0x7F {
+        126 => {
+            let mut length = [0u8; 2];
+            read_full(reader, &mut length, false, deadline)?;
+            u64::from(u16::from_be_bytes(length))
+        }
+        127 => {
+            let mut length = [0u8; 8];
+            read_full(reader, &mut length, false, deadline)?;
+            u64::from_be_bytes(length)
+        }
+        length => u64::from(length),
+    };
+    if length > max_length {
+        return Err(WsError::Protocol("message is too long".into()));
+    }
+    if opcode & 0x8 != 0 && (!fin || length > MAX_CONTROL_PAYLOAD_LENGTH as u64) {
+        return Err(WsError::Protocol("invalid control frame".into()));
+    }
+
+    let mut mask = [0u8; 4];
+    if masked {
+        read_full(reader, &mut mask, false, deadline)?;
+    }
+
+    // the payload is read in chunks, as its length has not been checked against what arrives
+    let mut payload = Vec::with_capacity(length.min(READ_CHUNK_LENGTH) as usize);
+    while (payload.len() as u64) < length {
+        let start = payload.len();
+        let chunk_length = (length - start as u64).min(READ_CHUNK_LENGTH) as usize;
+        payload.resize(start + chunk_length, 0);
+        read_full(reader, &mut payload[start..], false, deadline)?;
+    }
+    if masked {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+    }
+
+    Ok(WsFrame {
+        fin,
+        opcode,
+        payload,
+    })
+}
+
+/// Writes a frame to the given writer, masking its payload if `masked` is set, waiting at most
+/// `timeout` for the writer to accept it.
+fn write_frame<W: Write>(
+    writer: &mut W,
+    fin: bool,
+    opcode: u8,
+    payload: &[u8],
+    masked: bool,
+    timeout: Duration,
+) -> Result<(), WsError> {
+    let mut buffer = Vec::with_capacity(payload.len() + 14);
+    buffer.push(if fin { 0x80 | opcode } else { opcode });
+
+    let mask_bit = if masked { 0x80 } else { 0 };
+    if payload.len() < 126 {
+        buffer.push(mask_bit | payload.len() as u8);
+    } else if payload.len() <= u16::max_value() as usize {
+        buffer.push(mask_bit | 126);
+        buffer.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+    } else {
+        buffer.push(mask_bit | 127);
+        buffer.extend_from_slice(&(payload.len() as u64).to_be_bytes());
+    }
+
+    if masked {
+        let mut mask = [0u8; 4];
+        rand_bytes(&mut mask)
+            .map_err(|err| WsError::Protocol(format!("unable to generate mask: {}", err)))?;
+        buffer.extend_from_slice(&mask);
+        buffer.extend(
+            payload
+                .iter()
+                .enumerate()
+                .map(|(i, byte)| byte ^ mask[i % 4]),
+        );
+    } else {
+        buffer.extend_from_slice(payload);
+    }
+
+    // the frame is written at once, so that it is sent in as few TLS records as possible
+    write_full(writer, &buffer, timeout)
+}
+
+/// Fills the buffer from the reader, waiting until the deadline for bytes that are not yet
+/// available.
+///
+/// If `nonblocking` is set and no bytes are available at all, `WsError::WouldBlock` is returned.
+fn read_full<R: Read>(
+    reader: &mut R,
+    buffer: &mut [u8],
+    nonblocking: bool,
+    deadline: Instant,
+) -> Result<(), WsError> {
+    let mut read = 0;
+    while read < buffer.len() {
+        match reader.read(&mut buffer[read..]) {
+            Ok(0) => return Err(WsError::Closed),
+            Ok(n) => read += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                if nonblocking && read == 0 {
+                    return Err(WsError::WouldBlock);
+                }
+                wait_until(deadline)?;
+            }
+            Err(e) => return Err(WsError::from(e)),
+        }
+    }
+    Ok(())
+}
+
+/// Writes the whole buffer to the writer, waiting at most `timeout` for the writer to accept it.
+fn write_full<W: Write>(
+    writer: &mut W,
+    mut buffer: &[u8],
+    timeout: Duration,
+) -> Result<(), WsError> {
+    let deadline = Instant::now() + timeout;
+    while !buffer.is_empty() {
+        match writer.write(buffer) {
+            Ok(0) => {
+                return Err(WsError::Io(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                )))
+            }
+            Ok(n) => buffer = &buffer[n..],
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => wait_until(deadline)?,
+            Err(e) => return Err(WsError::from(e)),
+        }
+    }
+    writer.flush().map_err(WsError::from)
+}
+
+/// Returns the time left until the given deadline, or a timed out error if it has passed.
+fn time_remaining(deadline: Instant) -> io::Result<Duration> {
+    let now = Instant::now();
+    if now >= deadline {
+        return Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "timed out waiting for the handshake",
+        ));
+    }
+    Ok(deadline - now)
+}
+
+/// Waits briefly for a stream to become ready again, unless the deadline has passed.
+fn wait_until(deadline: Instant) -> Result<(), WsError> {
+    let now = Instant::now();
+    if now >= deadline {
+        return Err(WsError::Io(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "timed out waiting for the stream",
+        )));
+    }
+    thread::sleep((deadline - now).min(Duration::from_millis(10)));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    use mio::Ready;
+
+    use crate::transport::tests;
+    use crate::transport::tls::tests::create_test_tls_transport;
+
+    #[test]
+    fn test_accepts() {
+        let transport = WsTransport::default();
+        assert!(transport.accepts("ws://127.0.0.1:0"));
+        assert!(transport.accepts("ws://somewhere.example.com:4000/splinter"));
+
+        assert!(!transport.accepts("wss://somewhere.example.com:4000"));
+        assert!(!transport.accepts("tls://somewhere.example.com:4000"));
+        assert!(!transport.accepts("127.0.0.1:0"));
+
+        let transport = WsTransport::with_tls(&create_test_tls_transport(true));
+        assert!(transport.accepts("ws://127.0.0.1:0"));
+        assert!(transport.accepts("wss://somewhere.example.com:4000"));
+    }
+
+    #[test]
+    fn test_transport() {
+        let transport = WsTransport::default();
+        tests::test_transport(transport, "ws://127.0.0.1:0");
+    }
+
+    #[test]
+    fn test_poll() {
+        let transport = WsTransport::default();
+        tests::test_poll(
+            transport,
+            "ws://127.0.0.1:0",
+            Ready::readable() | Ready::writable(),
+        );
+    }
+
+    #[test]
+    fn test_transport_tls() {
+        let transport = WsTransport::with_tls(&create_test_tls_transport(true));
+        tests::test_transport(transport, "wss://127.0.0.1:0");
+    }
+
+    #[test]
+    fn test_poll_tls() {
+        let transport = WsTransport::with_tls(&create_test_tls_transport(false));
+        tests::test_poll(
+            transport,
+            "wss://127.0.0.1:0",
+            Ready::readable() | Ready::writable(),
+        );
+    }
+
+    /// Test that the accept key is computed as in the example of RFC 6455.
+    #[test]
+    fn test_accept_key() {
+        assert_eq!(
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=",
+            accept_key("dGhlIHNhbXBsZSBub25jZQ==")
+        );
+    }
+
+    /// Test that a fragmented message is reassembled, that a ping received between its fragments
+    /// is answered, and that a close ends the connection.
+    #[test]
+    fn test_recv_fragmented_message() {
+        let mut input = vec![];
+        write_frame(
+            &mut input,
+            false,
+            OPCODE_BINARY,
+            &[0, 1],
+            true,
+            DEFAULT_TIMEOUT,
+        )
+        .unwrap();
+        write_frame(
+            &mut input,
+            true,
+            OPCODE_PING,
+            b"ping",
+            true,
+            DEFAULT_TIMEOUT,
+        )
+        .unwrap();
+        write_frame(
+            &mut input,
+            false,
+            OPCODE_CONTINUATION,
+            &[2; 200],
+            true,
+            DEFAULT_TIMEOUT,
+        )
+        .unwrap();
+        write_frame(
+            &mut input,
+            true,
+            OPCODE_CONTINUATION,
+            &[3],
+            true,
+            DEFAULT_TIMEOUT,
+        )
+        .unwrap();
+        write_frame(
+            &mut input,
+            true,
+            OPCODE_CLOSE,
+            &[3, 232],
+            true,
+            DEFAULT_TIMEOUT,
+        )
+        .unwrap();
+
+        let mut socket = WsSocket::new(MockStream::new(input), Role::Server);
+
+        let mut expected = vec![0, 1];
+        expected.extend_from_slice(&[2; 200]);
+        expected.push(3);
+        assert_eq!(expected, socket.recv_message().unwrap());
+
+        match socket.recv_message() {
+            Err(WsError::Closed) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+
+        let mut output = Cursor::new(socket.stream.output);
+        let pong = read_frame(
+            &mut output,
+            false,
+            false,
+            max_message_length(&FrameOptions::default()),
+            DEFAULT_TIMEOUT,
+        )
+        .unwrap();
+        assert_eq!(OPCODE_PONG, pong.opcode);
+        assert_eq!(b"ping".to_vec(), pong.payload);
+        let close = read_frame(
+            &mut output,
+            false,
+            false,
+            max_message_length(&FrameOptions::default()),
+            DEFAULT_TIMEOUT,
+        )
+        .unwrap();
+        assert_eq!(OPCODE_CLOSE, close.opcode);
+        assert_eq!(vec![3, 232], close.payload);
+    }
+
+    /// Test that a server rejects unmasked frames, and that a client rejects masked ones.
+    #[test]
+    fn test_recv_masking() {
+        let mut input = vec![];
+        write_frame(
+            &mut input,
+            true,
+            OPCODE_BINARY,
+            &[0, 1],
+            false,
+            DEFAULT_TIMEOUT,
+        )
+        .unwrap();
+        let mut server = WsSocket::new(MockStream::new(input.clone()), Role::Server);
+        match server.recv_message() {
+            Err(WsError::Protocol(_)) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+        let mut client = WsSocket::new(MockStream::new(input), Role::Client);
+        assert_eq!(vec![0, 1], client.recv_message().unwrap());
+
+        let mut input = vec![];
+        write_frame(
+            &mut input,
+            true,
+            OPCODE_BINARY,
+            &[0, 1],
+            true,
+            DEFAULT_TIMEOUT,
+        )
+        .unwrap();
+        let mut client = WsSocket::new(MockStream::new(input), Role::Client);
+        match client.recv_message() {
+            Err(WsError::Protocol(_)) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+
+    /// Test that a frame that declares a long payload, but ends early, is read only as far as its
+    /// bytes arrive.
+    #[test]
+    fn test_recv_truncated_frame() {
+        let mut input = vec![0x80 | OPCODE_BINARY, 0x80 | 127];
+        input.extend_from_slice(&u64::from(FrameOptions::default().max_frame_size()).to_be_bytes());
+        input.extend_from_slice(&[0; 4]);
+        input.extend_from_slice(&[1; 16]);
+
+        let mut socket = WsSocket::new(MockStream::new(input), Role::Server);
+        match socket.recv_message() {
+            Err(WsError::Closed) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+
+    /// Test that messages longer than the maximum message length are rejected, whether they are
+    /// sent in one frame or in fragments.
+    #[test]
+    fn test_recv_message_too_long() {
+        let mut input = vec![];
+        write_frame(
+            &mut input,
+            true,
+            OPCODE_BINARY,
+            &[1; 20],
+            true,
+            DEFAULT_TIMEOUT,
+        )
+        .unwrap();
+        let mut socket =
+            WsSocket::new(MockStream::new(input), Role::Server).with_max_message_length(16);
+        match socket.recv_message() {
+            Err(WsError::Protocol(_)) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+
+        let mut input = vec![];
+        write_frame(
+            &mut input,
+            false,
+            OPCODE_BINARY,
+            &[1; 10],
+            true,
+            DEFAULT_TIMEOUT,
+        )
+        .unwrap();
+        write_frame(
+            &mut input,
+            true,
+            OPCODE_CONTINUATION,
+            &[1; 10],
+            true,
+            DEFAULT_TIMEOUT,
+        )
+        .unwrap();
+        let mut socket =
+            WsSocket::new(MockStream::new(input), Role::Server).with_max_message_length(16);
+        match socket.recv_message() {
+            Err(WsError::Protocol(_)) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+
+    /// Test that the handshake succeeds between a client and a server, and that a request that is
+    /// not an upgrade is refused.
+    #[test]
+    fn test_handshake() {
+        let mut client = WsSocket::new(MockStream::new(vec![]), Role::Client);
+        client
+            .client_handshake("127.0.0.1:8044", "/splinter")
+            .unwrap_err();
+        let request = client.stream.output.clone();
+        assert!(request.starts_with(b"GET /splinter HTTP/1.1\r\n"));
+
+        let mut server = WsSocket::new(MockStream::new(request), Role::Server);
+        server.server_handshake().unwrap();
+        let response = server.stream.output;
+
+        let mut client = WsSocket::new(MockStream::new(response), Role::Client);
+        // the key differs from the request's, so the response's accept key is rejected
+        match client.client_handshake("127.0.0.1:8044", "/splinter") {
+            Err(WsError::Protocol(_)) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+
+        let mut server = WsSocket::new(
+            MockStream::new(b"GET / HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n".to_vec()),
+            Role::Server,
+        );
+        match server.server_handshake() {
+            Err(WsError::Protocol(_)) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+        assert!(server.stream.output.starts_with(b"HTTP/1.1 400"));
+    }
+
+    /// Test that a client that stalls during the handshake only holds up the listener until the
+    /// handshake timeout, after which another client is accepted.
+    #[test]
+    fn test_accept_handshake_timeout() {
+        let mut transport =
+            WsTransport::default().with_handshake_timeout(Duration::from_millis(100));
+        let mut listener = transport.listen("ws://127.0.0.1:0").unwrap();
+        let address = listener.endpoint()[WS_PROTOCOL_PREFIX.len()..].to_string();
+
+        let _stalled = TcpStream::connect(&address).unwrap();
+        let start = Instant::now();
+        assert!(listener.accept().is_err());
+        assert!(start.elapsed() < DEFAULT_TIMEOUT);
+
+        let endpoint = listener.endpoint();
+        let join_handle = thread::spawn(move || transport.connect(&endpoint).unwrap());
+        listener.accept().unwrap();
+        join_handle.join().unwrap();
+    }
+
+    struct MockStream {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl MockStream {
+        fn new(input: Vec<u8>) -> Self {
+            MockStream {
+                input: Cursor::new(input),
+                output: vec![],
+            }
+        }
+    }
+
+    impl Read for MockStream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for MockStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+}
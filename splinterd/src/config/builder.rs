@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
 use std::path::Path;
 
 use crate::config::error::ConfigError;
@@ -138,6 +139,26 @@ impl ConfigBuilder {
                 None => None,
             })
             .ok_or_else(|| ConfigError::MissingValue("server key".to_string()))?;
+        // circuit management types without an approval policy require unanimous approval
+        let admin_service_approval_policies = self
+            .partial_configs
+            .iter()
+            .find_map(|p| match p.admin_service_approval_policies() {
+                Some(v) => Some((v, p.source())),
+                None => None,
+            })
+            .unwrap_or_else(|| (HashMap::new(), ConfigSource::Default));
+        // a threshold of 0 would approve proposals without any votes
+        if let Some((circuit_management_type, _)) = admin_service_approval_policies
+            .0
+            .iter()
+            .find(|(_, policy)| policy.threshold() == Some(0))
+        {
+            return Err(ConfigError::InvalidValue(format!(
+                "approval policy threshold for {} must be at least 1",
+                circuit_management_type
+            )));
+        }
         // Iterates over the list of PartialConfig objects to find the first config with a value
         // for the specific field. If no value is found, an error is returned.
         Ok(Config {
@@ -246,6 +267,161 @@ impl ConfigBuilder {
                 .ok_or_else(|| {
                     ConfigError::MissingValue("admin service coordinator timeout".to_string())
                 })?,
+            // proposals only expire if a ttl is configured
+            admin_service_proposal_ttl: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.admin_service_proposal_ttl() {
+                    Some(v) => Some((Some(v), p.source())),
+                    None => None,
+                })
+                .unwrap_or_else(|| (None, ConfigSource::Default)),
+            admin_service_approval_policies,
+            // proposals are only voted on automatically if a key is configured
+            admin_service_auto_vote_key: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.admin_service_auto_vote_key() {
+                    Some(v) => Some((Some(v), p.source())),
+                    None => None,
+                })
+                .unwrap_or_else(|| (None, ConfigSource::Default)),
+            admin_service_voting_rules: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.admin_service_voting_rules() {
+                    Some(v) => Some((v, p.source())),
+                    None => None,
+                })
+                .unwrap_or_else(|| (vec![], ConfigSource::Default)),
+            admin_service_event_retention_count: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.admin_service_event_retention_count() {
+                    Some(v) => Some((v, p.source())),
+                    None => None,
+                })
+                .ok_or_else(|| {
+                    ConfigError::MissingValue("admin service event retention count".to_string())
+                })?,
+            admin_service_event_retention_age: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.admin_service_event_retention_age() {
+                    Some(v) => Some((v, p.source())),
+                    None => None,
+                })
+                .ok_or_else(|| {
+                    ConfigError::MissingValue("admin service event retention age".to_string())
+                })?,
+            // peers are only authorized by challenge if a key is configured
+            challenge_authorization_key: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.challenge_authorization_key() {
+                    Some(v) => Some((Some(v), p.source())),
+                    None => None,
+                })
+                .unwrap_or_else(|| (None, ConfigSource::Default)),
+            require_challenge_authorization: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.require_challenge_authorization() {
+                    Some(v) => Some((v, p.source())),
+                    None => None,
+                })
+                .ok_or_else(|| {
+                    ConfigError::MissingValue("require challenge authorization".to_string())
+                })?,
+            require_certificate_identity: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.require_certificate_identity() {
+                    Some(v) => Some((v, p.source())),
+                    None => None,
+                })
+                .ok_or_else(|| {
+                    ConfigError::MissingValue("require certificate identity".to_string())
+                })?,
+            // peers are only checked against a policy if a policy file is configured
+            peer_policy_file: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.peer_policy_file() {
+                    Some(v) => Some((Some(v), p.source())),
+                    None => None,
+                })
+                .unwrap_or_else(|| (None, ConfigSource::Default)),
+            // services are only checked by user ID if user IDs are configured
+            service_uids: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.service_uids() {
+                    Some(v) => Some((v, p.source())),
+                    None => None,
+                })
+                .unwrap_or_else(|| (vec![], ConfigSource::Default)),
+            // the transport's default maximum frame size is used if none is configured
+            max_frame_size: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.max_frame_size() {
+                    Some(v) => Some((Some(v), p.source())),
+                    None => None,
+                })
+                .unwrap_or_else(|| (None, ConfigSource::Default)),
+            // messages are only compressed if an algorithm is configured
+            frame_compression: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.frame_compression() {
+                    Some(v) => Some((Some(v), p.source())),
+                    None => None,
+                })
+                .unwrap_or_else(|| (None, ConfigSource::Default)),
+            // peer certificates are only checked for revocation if a CRL file is configured
+            crl_file: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.crl_file() {
+                    Some(v) => Some((Some(v), p.source())),
+                    None => None,
+                })
+                .unwrap_or_else(|| (None, ConfigSource::Default)),
+            // network messages are only captured if a capture file is configured
+            capture_file: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.capture_file() {
+                    Some(v) => Some((Some(v), p.source())),
+                    None => None,
+                })
+                .unwrap_or_else(|| (None, ConfigSource::Default)),
+            // peers are connected to directly unless a proxy is configured
+            proxy: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.proxy() {
+                    Some(v) => Some((Some(v), p.source())),
+                    None => None,
+                })
+                .unwrap_or_else(|| (None, ConfigSource::Default)),
+            proxy_endpoints: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.proxy_endpoints() {
+                    Some(v) => Some((v, p.source())),
+                    None => None,
+                })
+                .unwrap_or_else(|| (vec![], ConfigSource::Default)),
+            no_proxy: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.no_proxy() {
+                    Some(v) => Some((Some(v), p.source())),
+                    None => None,
+                })
+                .unwrap_or_else(|| (None, ConfigSource::Default)),
 
             state_dir: self
                 .partial_configs
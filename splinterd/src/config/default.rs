@@ -24,6 +24,8 @@ const SERVER_KEY: &str = "private/server.key";
 const CA_PEM: &str = "ca.pem";
 const HEARTBEAT_DEFAULT: u64 = 30;
 const DEFAULT_ADMIN_SERVICE_COORDINATOR_TIMEOUT_MILLIS: u64 = 30000;
+const DEFAULT_ADMIN_SERVICE_EVENT_RETENTION_COUNT: usize = 10_000;
+const DEFAULT_ADMIN_SERVICE_EVENT_RETENTION_AGE_SECS: u64 = 604_800; // 7 days
 
 /// Holds the default configuration values.
 pub struct DefaultPartialConfigBuilder;
@@ -57,6 +59,14 @@ impl PartialConfigBuilder for DefaultPartialConfigBuilder {
             .with_admin_service_coordinator_timeout(Some(
                 DEFAULT_ADMIN_SERVICE_COORDINATOR_TIMEOUT_MILLIS,
             ))
+            .with_admin_service_event_retention_count(Some(
+                DEFAULT_ADMIN_SERVICE_EVENT_RETENTION_COUNT,
+            ))
+            .with_admin_service_event_retention_age(Some(
+                DEFAULT_ADMIN_SERVICE_EVENT_RETENTION_AGE_SECS,
+            ))
+            .with_require_challenge_authorization(Some(false))
+            .with_require_certificate_identity(Some(false))
             .with_state_dir(Some(String::from(DEFAULT_STATE_DIR)))
             .with_insecure(Some(false));
 
@@ -115,6 +125,19 @@ mod tests {
                 DEFAULT_ADMIN_SERVICE_COORDINATOR_TIMEOUT_MILLIS
             ))
         );
+        assert_eq!(config.admin_service_proposal_ttl(), None);
+        assert_eq!(
+            config.admin_service_event_retention_count(),
+            Some(DEFAULT_ADMIN_SERVICE_EVENT_RETENTION_COUNT)
+        );
+        assert_eq!(
+            config.admin_service_event_retention_age(),
+            Some(Duration::from_secs(
+                DEFAULT_ADMIN_SERVICE_EVENT_RETENTION_AGE_SECS
+            ))
+        );
+        assert_eq!(config.require_challenge_authorization(), Some(false));
+        assert_eq!(config.require_certificate_identity(), Some(false));
         assert_eq!(config.state_dir(), Some(String::from(DEFAULT_STATE_DIR)));
         assert_eq!(config.insecure(), Some(false));
         #[cfg(feature = "biome")]
@@ -18,6 +18,7 @@ use crate::config::{ConfigError, ConfigSource, PartialConfig, PartialConfigBuild
 
 const STATE_DIR_ENV: &str = "SPLINTER_STATE_DIR";
 const CERT_DIR_ENV: &str = "SPLINTER_CERT_DIR";
+const NO_PROXY_ENV: &str = "NO_PROXY";
 
 /// Holds configuration values defined as environment variables.
 pub struct EnvPartialConfigBuilder;
@@ -32,7 +33,12 @@ impl PartialConfigBuilder for EnvPartialConfigBuilder {
     fn build(self) -> Result<PartialConfig, ConfigError> {
         Ok(PartialConfig::new(ConfigSource::Environment)
             .with_cert_dir(env::var(CERT_DIR_ENV).ok())
-            .with_state_dir(env::var(STATE_DIR_ENV).ok()))
+            .with_state_dir(env::var(STATE_DIR_ENV).ok())
+            .with_no_proxy(
+                env::var(NO_PROXY_ENV)
+                    .or_else(|_| env::var(NO_PROXY_ENV.to_lowercase()))
+                    .ok(),
+            ))
     }
 }
 
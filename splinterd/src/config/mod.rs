@@ -24,8 +24,12 @@ mod partial;
 #[cfg(feature = "config-toml")]
 mod toml;
 
+use std::collections::HashMap;
 use std::time::Duration;
 
+use splinter::admin::messages::ApprovalPolicy;
+use splinter::admin::service::VotingRule;
+
 #[cfg(feature = "config-command-line")]
 pub use crate::config::clap::ClapPartialConfigBuilder;
 #[cfg(feature = "config-default")]
@@ -36,7 +40,7 @@ pub use crate::config::env::EnvPartialConfigBuilder;
 pub use crate::config::toml::TomlPartialConfigBuilder;
 pub use builder::{ConfigBuilder, PartialConfigBuilder};
 pub use error::ConfigError;
-pub use partial::{ConfigSource, PartialConfig};
+pub use partial::{ConfigSource, PartialConfig, ProxyEndpoint};
 
 /// Config is the final representation of configuration values. This final config object assembles
 /// values from PartialConfig objects generated from various sources.
@@ -61,6 +65,24 @@ pub struct Config {
     registry_file: (String, ConfigSource),
     heartbeat_interval: (u64, ConfigSource),
     admin_service_coordinator_timeout: (Duration, ConfigSource),
+    admin_service_proposal_ttl: (Option<Duration>, ConfigSource),
+    admin_service_approval_policies: (HashMap<String, ApprovalPolicy>, ConfigSource),
+    admin_service_auto_vote_key: (Option<String>, ConfigSource),
+    admin_service_voting_rules: (Vec<VotingRule>, ConfigSource),
+    admin_service_event_retention_count: (usize, ConfigSource),
+    admin_service_event_retention_age: (Duration, ConfigSource),
+    challenge_authorization_key: (Option<String>, ConfigSource),
+    require_challenge_authorization: (bool, ConfigSource),
+    require_certificate_identity: (bool, ConfigSource),
+    peer_policy_file: (Option<String>, ConfigSource),
+    service_uids: (Vec<u32>, ConfigSource),
+    max_frame_size: (Option<u32>, ConfigSource),
+    frame_compression: (Option<String>, ConfigSource),
+    crl_file: (Option<String>, ConfigSource),
+    capture_file: (Option<String>, ConfigSource),
+    proxy: (Option<String>, ConfigSource),
+    proxy_endpoints: (Vec<ProxyEndpoint>, ConfigSource),
+    no_proxy: (Option<String>, ConfigSource),
     state_dir: (String, ConfigSource),
     insecure: (bool, ConfigSource),
     #[cfg(feature = "biome")]
@@ -141,6 +163,78 @@ impl Config {
         self.admin_service_coordinator_timeout.0
     }
 
+    pub fn admin_service_proposal_ttl(&self) -> Option<Duration> {
+        self.admin_service_proposal_ttl.0
+    }
+
+    pub fn admin_service_approval_policies(&self) -> &HashMap<String, ApprovalPolicy> {
+        &self.admin_service_approval_policies.0
+    }
+
+    pub fn admin_service_auto_vote_key(&self) -> Option<&str> {
+        self.admin_service_auto_vote_key.0.as_deref()
+    }
+
+    pub fn admin_service_voting_rules(&self) -> &[VotingRule] {
+        &self.admin_service_voting_rules.0
+    }
+
+    pub fn admin_service_event_retention_count(&self) -> usize {
+        self.admin_service_event_retention_count.0
+    }
+
+    pub fn admin_service_event_retention_age(&self) -> Duration {
+        self.admin_service_event_retention_age.0
+    }
+
+    pub fn challenge_authorization_key(&self) -> Option<&str> {
+        self.challenge_authorization_key.0.as_deref()
+    }
+
+    pub fn require_challenge_authorization(&self) -> bool {
+        self.require_challenge_authorization.0
+    }
+
+    pub fn require_certificate_identity(&self) -> bool {
+        self.require_certificate_identity.0
+    }
+
+    pub fn peer_policy_file(&self) -> Option<&str> {
+        self.peer_policy_file.0.as_deref()
+    }
+
+    pub fn service_uids(&self) -> &[u32] {
+        &self.service_uids.0
+    }
+
+    pub fn max_frame_size(&self) -> Option<u32> {
+        self.max_frame_size.0
+    }
+
+    pub fn frame_compression(&self) -> Option<&str> {
+        self.frame_compression.0.as_deref()
+    }
+
+    pub fn crl_file(&self) -> Option<&str> {
+        self.crl_file.0.as_deref()
+    }
+
+    pub fn capture_file(&self) -> Option<&str> {
+        self.capture_file.0.as_deref()
+    }
+
+    pub fn proxy(&self) -> Option<&str> {
+        self.proxy.0.as_deref()
+    }
+
+    pub fn proxy_endpoints(&self) -> &[ProxyEndpoint] {
+        &self.proxy_endpoints.0
+    }
+
+    pub fn no_proxy(&self) -> Option<&str> {
+        self.no_proxy.0.as_deref()
+    }
+
     pub fn state_dir(&self) -> &str {
         &self.state_dir.0
     }
@@ -227,6 +321,78 @@ impl Config {
         &self.admin_service_coordinator_timeout.1
     }
 
+    fn admin_service_proposal_ttl_source(&self) -> &ConfigSource {
+        &self.admin_service_proposal_ttl.1
+    }
+
+    fn admin_service_approval_policies_source(&self) -> &ConfigSource {
+        &self.admin_service_approval_policies.1
+    }
+
+    fn admin_service_auto_vote_key_source(&self) -> &ConfigSource {
+        &self.admin_service_auto_vote_key.1
+    }
+
+    fn admin_service_voting_rules_source(&self) -> &ConfigSource {
+        &self.admin_service_voting_rules.1
+    }
+
+    fn admin_service_event_retention_count_source(&self) -> &ConfigSource {
+        &self.admin_service_event_retention_count.1
+    }
+
+    fn admin_service_event_retention_age_source(&self) -> &ConfigSource {
+        &self.admin_service_event_retention_age.1
+    }
+
+    fn challenge_authorization_key_source(&self) -> &ConfigSource {
+        &self.challenge_authorization_key.1
+    }
+
+    fn require_challenge_authorization_source(&self) -> &ConfigSource {
+        &self.require_challenge_authorization.1
+    }
+
+    fn require_certificate_identity_source(&self) -> &ConfigSource {
+        &self.require_certificate_identity.1
+    }
+
+    fn peer_policy_file_source(&self) -> &ConfigSource {
+        &self.peer_policy_file.1
+    }
+
+    fn service_uids_source(&self) -> &ConfigSource {
+        &self.service_uids.1
+    }
+
+    fn max_frame_size_source(&self) -> &ConfigSource {
+        &self.max_frame_size.1
+    }
+
+    fn frame_compression_source(&self) -> &ConfigSource {
+        &self.frame_compression.1
+    }
+
+    fn crl_file_source(&self) -> &ConfigSource {
+        &self.crl_file.1
+    }
+
+    fn capture_file_source(&self) -> &ConfigSource {
+        &self.capture_file.1
+    }
+
+    fn proxy_source(&self) -> &ConfigSource {
+        &self.proxy.1
+    }
+
+    fn proxy_endpoints_source(&self) -> &ConfigSource {
+        &self.proxy_endpoints.1
+    }
+
+    fn no_proxy_source(&self) -> &ConfigSource {
+        &self.no_proxy.1
+    }
+
     fn state_dir_source(&self) -> &ConfigSource {
         &self.state_dir.1
     }
@@ -332,6 +498,103 @@ impl Config {
             self.admin_service_coordinator_timeout(),
             self.admin_service_coordinator_timeout_source()
         );
+        debug!(
+            "Config: admin_service_proposal_ttl: {:?} (source: {:?})",
+            self.admin_service_proposal_ttl(),
+            self.admin_service_proposal_ttl_source()
+        );
+        debug!(
+            "Config: admin_service_approval_policies: {:?} (source: {:?})",
+            self.admin_service_approval_policies(),
+            self.admin_service_approval_policies_source()
+        );
+        debug!(
+            "Config: admin_service_auto_vote_key: {:?} (source: {:?})",
+            self.admin_service_auto_vote_key(),
+            self.admin_service_auto_vote_key_source()
+        );
+        debug!(
+            "Config: admin_service_voting_rules: {:?} (source: {:?})",
+            self.admin_service_voting_rules(),
+            self.admin_service_voting_rules_source()
+        );
+        debug!(
+            "Config: admin_service_event_retention_count: {:?} (source: {:?})",
+            self.admin_service_event_retention_count(),
+            self.admin_service_event_retention_count_source()
+        );
+        debug!(
+            "Config: admin_service_event_retention_age: {:?} (source: {:?})",
+            self.admin_service_event_retention_age(),
+            self.admin_service_event_retention_age_source()
+        );
+        debug!(
+            "Config: challenge_authorization_key: {:?} (source: {:?})",
+            self.challenge_authorization_key(),
+            self.challenge_authorization_key_source()
+        );
+        debug!(
+            "Config: require_challenge_authorization: {:?} (source: {:?})",
+            self.require_challenge_authorization(),
+            self.require_challenge_authorization_source()
+        );
+        debug!(
+            "Config: require_certificate_identity: {:?} (source: {:?})",
+            self.require_certificate_identity(),
+            self.require_certificate_identity_source()
+        );
+        debug!(
+            "Config: peer_policy_file: {:?} (source: {:?})",
+            self.peer_policy_file(),
+            self.peer_policy_file_source()
+        );
+        debug!(
+            "Config: service_uids: {:?} (source: {:?})",
+            self.service_uids(),
+            self.service_uids_source()
+        );
+        debug!(
+            "Config: max_frame_size: {:?} (source: {:?})",
+            self.max_frame_size(),
+            self.max_frame_size_source()
+        );
+        debug!(
+            "Config: frame_compression: {:?} (source: {:?})",
+            self.frame_compression(),
+            self.frame_compression_source()
+        );
+        debug!(
+            "Config: crl_file: {:?} (source: {:?})",
+            self.crl_file(),
+            self.crl_file_source()
+        );
+        debug!(
+            "Config: capture_file: {:?} (source: {:?})",
+            self.capture_file(),
+            self.capture_file_source()
+        );
+        // proxy URLs may contain passwords, so only their hosts are logged
+        debug!(
+            "Config: proxy: {:?} (source: {:?})",
+            self.proxy().map(without_credentials),
+            self.proxy_source()
+        );
+        debug!(
+            "Config: proxy_endpoints: {:?} (source: {:?})",
+            self.proxy_endpoints()
+                .iter()
+                .map(|proxy_endpoint| (
+                    proxy_endpoint.endpoint.as_str(),
+                    without_credentials(&proxy_endpoint.proxy)
+                ))
+                .collect::<Vec<_>>(),
+            self.proxy_endpoints_source()
+        );
+        debug!(
+            "Config: no_proxy: {:?} (source: {:?})",
+            self.no_proxy(),
+            self.no_proxy_source()
+        );
         #[cfg(feature = "database")]
         debug!(
             "database: {} (source: {:?})",
@@ -352,6 +615,16 @@ impl Config {
     }
 }
 
+/// Removes the username and password, if any, from a proxy URL.
+fn without_credentials(url: &str) -> String {
+    match (url.find("://"), url.rfind('@')) {
+        (Some(scheme_end), Some(credentials_end)) if credentials_end > scheme_end => {
+            format!("{}{}", &url[..scheme_end + 3], &url[credentials_end + 1..])
+        }
+        _ => url.to_string(),
+    }
+}
+
 #[cfg(feature = "default")]
 #[cfg(test)]
 mod tests {
@@ -946,4 +1219,61 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    /// This test verifies that a finalized Config object cannot be built if an approval policy
+    /// has a threshold of 0, which would approve proposals without any votes, in the following
+    /// steps:
+    ///
+    /// 1. An empty ConfigBuilder object is created.
+    /// 2. PartialConfigs built from a ClapPartialConfigBuilder, a TomlPartialConfigBuilder with
+    ///    a threshold policy of 0 and a DefaultPartialConfigBuilder are added to the ConfigBuilder.
+    ///
+    /// This test then verifies the final Config object built from the ConfigBuilder object has
+    /// resulted in an invalid value error.
+    fn test_final_config_zero_approval_threshold_err() {
+        // Create a new ConfigBuilder object.
+        let builder = ConfigBuilder::new();
+        // Arguments to be used to create a ClapPartialConfigBuilder object.
+        let args = vec![
+            "configtest",
+            "--node-id",
+            "123",
+            "--registry-file",
+            "/etc/splinter/test.yaml",
+        ];
+        let matches = create_arg_matches(args);
+        let command_config = ClapPartialConfigBuilder::new(matches)
+            .build()
+            .expect("Unable to build ClapPartialConfigBuilder");
+
+        let toml_string = r#"
+            [admin_service_approval_policies]
+            gameroom = { type = "threshold", threshold = 0 }
+        "#;
+        let toml_config =
+            TomlPartialConfigBuilder::new(toml_string.to_string(), TEST_TOML.to_string())
+                .expect(&format!(
+                    "Unable to create TomlPartialConfigBuilder from: {}",
+                    TEST_TOML
+                ))
+                .build()
+                .expect("Unable to build TomlPartialConfigBuilder");
+
+        let default_config = DefaultPartialConfigBuilder::new()
+            .build()
+            .expect("Unable to build DefaultPartialConfigBuilder");
+
+        // Add the PartialConfigs to the final ConfigBuilder in the order of precedence.
+        let final_config = builder
+            .with_partial_config(command_config)
+            .with_partial_config(toml_config)
+            .with_partial_config(default_config)
+            .build();
+
+        match final_config {
+            Err(ConfigError::InvalidValue(_)) => (),
+            res => panic!("Expected InvalidValue, got {:?}", res),
+        }
+    }
 }
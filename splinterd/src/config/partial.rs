@@ -12,8 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
 use std::time::Duration;
 
+use splinter::admin::messages::ApprovalPolicy;
+use splinter::admin::service::VotingRule;
+
 /// ConfigSource displays the source of configuration values, used to identify which of the various
 /// config modules were used to create a particular PartialConfig object.
 #[derive(Deserialize, Debug, Clone, PartialEq)]
@@ -24,6 +28,15 @@ pub enum ConfigSource {
     CommandLine,
 }
 
+/// A proxy used to connect to the peer endpoints that match a pattern.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ProxyEndpoint {
+    /// The pattern endpoints are matched against, in which `*` matches any characters.
+    pub endpoint: String,
+    /// The URL of the proxy.
+    pub proxy: String,
+}
+
 /// PartialConfig is an intermediate representation of configuration values, used when combining
 /// several sources. As such, all values of the PartialConfig are options as it is not necessary
 /// to provide all values from a single source.
@@ -49,6 +62,24 @@ pub struct PartialConfig {
     registry_file: Option<String>,
     heartbeat_interval: Option<u64>,
     admin_service_coordinator_timeout: Option<Duration>,
+    admin_service_proposal_ttl: Option<Duration>,
+    admin_service_approval_policies: Option<HashMap<String, ApprovalPolicy>>,
+    admin_service_auto_vote_key: Option<String>,
+    admin_service_voting_rules: Option<Vec<VotingRule>>,
+    admin_service_event_retention_count: Option<usize>,
+    admin_service_event_retention_age: Option<Duration>,
+    challenge_authorization_key: Option<String>,
+    require_challenge_authorization: Option<bool>,
+    require_certificate_identity: Option<bool>,
+    peer_policy_file: Option<String>,
+    service_uids: Option<Vec<u32>>,
+    max_frame_size: Option<u32>,
+    frame_compression: Option<String>,
+    crl_file: Option<String>,
+    capture_file: Option<String>,
+    proxy: Option<String>,
+    proxy_endpoints: Option<Vec<ProxyEndpoint>>,
+    no_proxy: Option<String>,
     state_dir: Option<String>,
     insecure: Option<bool>,
     #[cfg(feature = "biome")]
@@ -79,6 +110,24 @@ impl PartialConfig {
             registry_file: None,
             heartbeat_interval: None,
             admin_service_coordinator_timeout: None,
+            admin_service_proposal_ttl: None,
+            admin_service_approval_policies: None,
+            admin_service_auto_vote_key: None,
+            admin_service_voting_rules: None,
+            admin_service_event_retention_count: None,
+            admin_service_event_retention_age: None,
+            challenge_authorization_key: None,
+            require_challenge_authorization: None,
+            require_certificate_identity: None,
+            peer_policy_file: None,
+            service_uids: None,
+            max_frame_size: None,
+            frame_compression: None,
+            crl_file: None,
+            capture_file: None,
+            proxy: None,
+            proxy_endpoints: None,
+            no_proxy: None,
             state_dir: None,
             insecure: None,
             #[cfg(feature = "biome")]
@@ -163,6 +212,78 @@ impl PartialConfig {
         self.admin_service_coordinator_timeout
     }
 
+    pub fn admin_service_proposal_ttl(&self) -> Option<Duration> {
+        self.admin_service_proposal_ttl
+    }
+
+    pub fn admin_service_approval_policies(&self) -> Option<HashMap<String, ApprovalPolicy>> {
+        self.admin_service_approval_policies.clone()
+    }
+
+    pub fn admin_service_auto_vote_key(&self) -> Option<String> {
+        self.admin_service_auto_vote_key.clone()
+    }
+
+    pub fn admin_service_voting_rules(&self) -> Option<Vec<VotingRule>> {
+        self.admin_service_voting_rules.clone()
+    }
+
+    pub fn admin_service_event_retention_count(&self) -> Option<usize> {
+        self.admin_service_event_retention_count
+    }
+
+    pub fn admin_service_event_retention_age(&self) -> Option<Duration> {
+        self.admin_service_event_retention_age
+    }
+
+    pub fn challenge_authorization_key(&self) -> Option<String> {
+        self.challenge_authorization_key.clone()
+    }
+
+    pub fn require_challenge_authorization(&self) -> Option<bool> {
+        self.require_challenge_authorization
+    }
+
+    pub fn require_certificate_identity(&self) -> Option<bool> {
+        self.require_certificate_identity
+    }
+
+    pub fn peer_policy_file(&self) -> Option<String> {
+        self.peer_policy_file.clone()
+    }
+
+    pub fn service_uids(&self) -> Option<Vec<u32>> {
+        self.service_uids.clone()
+    }
+
+    pub fn max_frame_size(&self) -> Option<u32> {
+        self.max_frame_size
+    }
+
+    pub fn frame_compression(&self) -> Option<String> {
+        self.frame_compression.clone()
+    }
+
+    pub fn crl_file(&self) -> Option<String> {
+        self.crl_file.clone()
+    }
+
+    pub fn capture_file(&self) -> Option<String> {
+        self.capture_file.clone()
+    }
+
+    pub fn proxy(&self) -> Option<String> {
+        self.proxy.clone()
+    }
+
+    pub fn proxy_endpoints(&self) -> Option<Vec<ProxyEndpoint>> {
+        self.proxy_endpoints.clone()
+    }
+
+    pub fn no_proxy(&self) -> Option<String> {
+        self.no_proxy.clone()
+    }
+
     pub fn state_dir(&self) -> Option<String> {
         self.state_dir.clone()
     }
@@ -399,6 +520,237 @@ impl PartialConfig {
         self
     }
 
+    #[allow(dead_code)]
+    /// Adds a `ttl` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `ttl` - How long admin service proposals remain open before they expire (in seconds).
+    ///
+    pub fn with_admin_service_proposal_ttl(mut self, ttl: Option<u64>) -> Self {
+        self.admin_service_proposal_ttl = ttl.map(Duration::from_secs);
+        self
+    }
+
+    #[allow(dead_code)]
+    /// Adds an `approval_policies` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `approval_policies` - The approval policies of admin service proposals, by circuit
+    ///    management type.
+    ///
+    pub fn with_admin_service_approval_policies(
+        mut self,
+        approval_policies: Option<HashMap<String, ApprovalPolicy>>,
+    ) -> Self {
+        self.admin_service_approval_policies = approval_policies;
+        self
+    }
+
+    #[allow(dead_code)]
+    /// Adds an `auto_vote_key` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `auto_vote_key` - File path to the private key used to sign automatic votes on admin
+    ///    service proposals.
+    ///
+    pub fn with_admin_service_auto_vote_key(mut self, auto_vote_key: Option<String>) -> Self {
+        self.admin_service_auto_vote_key = auto_vote_key;
+        self
+    }
+
+    #[allow(dead_code)]
+    /// Adds a `voting_rules` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `voting_rules` - The rules used to vote automatically on admin service proposals, in the
+    ///    order they are evaluated.
+    ///
+    pub fn with_admin_service_voting_rules(
+        mut self,
+        voting_rules: Option<Vec<VotingRule>>,
+    ) -> Self {
+        self.admin_service_voting_rules = voting_rules;
+        self
+    }
+
+    #[allow(dead_code)]
+    /// Adds an `event_retention_count` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - The maximum number of admin events kept in the event mailbox.
+    ///
+    pub fn with_admin_service_event_retention_count(mut self, count: Option<usize>) -> Self {
+        self.admin_service_event_retention_count = count;
+        self
+    }
+
+    #[allow(dead_code)]
+    /// Adds an `event_retention_age` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `age` - How long admin events are kept in the event mailbox (in seconds).
+    ///
+    pub fn with_admin_service_event_retention_age(mut self, age: Option<u64>) -> Self {
+        self.admin_service_event_retention_age = age.map(Duration::from_secs);
+        self
+    }
+
+    #[allow(dead_code)]
+    /// Adds a `challenge_authorization_key` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - File path to the private key used to authorize with peers by challenge.
+    ///
+    pub fn with_challenge_authorization_key(mut self, key: Option<String>) -> Self {
+        self.challenge_authorization_key = key;
+        self
+    }
+
+    #[allow(dead_code)]
+    /// Adds a `require_challenge_authorization` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `required` - Whether peers must authorize by challenge, rather than by trust.
+    ///
+    pub fn with_require_challenge_authorization(mut self, required: Option<bool>) -> Self {
+        self.require_challenge_authorization = required;
+        self
+    }
+
+    #[allow(dead_code)]
+    /// Adds a `require_certificate_identity` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `required` - Whether peers' identities must match the names of their TLS certificates, as
+    ///    registered in the node registry.
+    ///
+    pub fn with_require_certificate_identity(mut self, required: Option<bool>) -> Self {
+        self.require_certificate_identity = required;
+        self
+    }
+
+    #[allow(dead_code)]
+    /// Adds a `peer_policy_file` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The location of the YAML file listing the peers allowed and denied to connect.
+    ///
+    pub fn with_peer_policy_file(mut self, file: Option<String>) -> Self {
+        self.peer_policy_file = file;
+        self
+    }
+
+    #[allow(dead_code)]
+    /// Adds a `service_uids` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `uids` - The user IDs of the local processes allowed to connect as services.
+    ///
+    pub fn with_service_uids(mut self, uids: Option<Vec<u32>>) -> Self {
+        self.service_uids = uids;
+        self
+    }
+
+    #[allow(dead_code)]
+    /// Adds a `max_frame_size` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_frame_size` - The size, in bytes, of the largest message that will be received.
+    ///
+    pub fn with_max_frame_size(mut self, max_frame_size: Option<u32>) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    #[allow(dead_code)]
+    /// Adds a `frame_compression` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `compression` - The compression algorithm used for messages sent to peers.
+    ///
+    pub fn with_frame_compression(mut self, compression: Option<String>) -> Self {
+        self.frame_compression = compression;
+        self
+    }
+
+    #[allow(dead_code)]
+    /// Adds a `crl_file` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `crl_file` - The location of the certificate revocation lists checked by the TLS
+    ///   transport.
+    ///
+    pub fn with_crl_file(mut self, crl_file: Option<String>) -> Self {
+        self.crl_file = crl_file;
+        self
+    }
+
+    #[allow(dead_code)]
+    /// Adds a `capture_file` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `capture_file` - The location of the file the messages sent and received by the network
+    ///   are captured to.
+    ///
+    pub fn with_capture_file(mut self, capture_file: Option<String>) -> Self {
+        self.capture_file = capture_file;
+        self
+    }
+
+    #[allow(dead_code)]
+    /// Adds a `proxy` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `proxy` - The URL of the proxy used to connect to peers whose endpoints match no proxy
+    ///   endpoint pattern.
+    ///
+    pub fn with_proxy(mut self, proxy: Option<String>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    #[allow(dead_code)]
+    /// Adds a `proxy_endpoints` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `proxy_endpoints` - The proxies used to connect to peers whose endpoints match a pattern,
+    ///    in the order they are checked.
+    ///
+    pub fn with_proxy_endpoints(mut self, proxy_endpoints: Option<Vec<ProxyEndpoint>>) -> Self {
+        self.proxy_endpoints = proxy_endpoints;
+        self
+    }
+
+    #[allow(dead_code)]
+    /// Adds a `no_proxy` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `no_proxy` - A comma-separated list of the hosts and domains of peers that are connected
+    ///   to directly, rather than through a proxy.
+    ///
+    pub fn with_no_proxy(mut self, no_proxy: Option<String>) -> Self {
+        self.no_proxy = no_proxy;
+        self
+    }
+
     #[allow(dead_code)]
     /// Adds a `state_dir` value to the PartialConfig object.
     ///
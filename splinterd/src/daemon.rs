@@ -12,11 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-#[cfg(feature = "service-arg-validation")]
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
 use std::fs;
+#[cfg(feature = "database")]
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
@@ -26,16 +27,27 @@ use crossbeam_channel;
 
 #[cfg(feature = "health")]
 use health::HealthService;
+use splinter::admin::messages::ApprovalPolicy;
 #[cfg(feature = "circuit-read")]
 use splinter::admin::rest_api::CircuitResourceProvider;
-use splinter::admin::service::{admin_service_id, AdminService};
+use splinter::admin::service::{
+    admin_service_id, AdminService, AdminServiceBuilder, AutoVoter, EventRetention, VotingRule,
+};
 #[cfg(feature = "biome")]
 use splinter::biome::rest_api::{BiomeRestResourceManager, BiomeRestResourceManagerBuilder};
 use splinter::circuit::directory::CircuitDirectory;
+#[cfg(feature = "circuit-durability")]
+use splinter::circuit::durable::DurableQueues;
+#[cfg(feature = "circuit-durability")]
+use splinter::circuit::handlers::CircuitDirectMessageAckHandler;
+#[cfg(feature = "circuit-relay")]
+use splinter::circuit::handlers::CircuitRelayedMessageHandler;
 use splinter::circuit::handlers::{
     AdminDirectMessageHandler, CircuitDirectMessageHandler, CircuitErrorHandler,
     CircuitMessageHandler, ServiceConnectRequestHandler, ServiceDisconnectRequestHandler,
 };
+#[cfg(feature = "database")]
+use splinter::circuit::store::{create_circuit_directory_store, CircuitDirectoryStore};
 use splinter::circuit::{SplinterState, SplinterStateError};
 #[cfg(feature = "biome")]
 use splinter::database::{self, ConnectionPool};
@@ -47,7 +59,18 @@ use splinter::mesh::Mesh;
 use splinter::network::auth::handlers::{
     create_authorization_dispatcher, AuthorizationMessageHandler, NetworkAuthGuardHandler,
 };
-use splinter::network::auth::AuthorizationManager;
+use splinter::network::auth::policy::{PeerPolicy, PeerPolicyRules};
+#[cfg(feature = "circuit-durability")]
+use splinter::network::auth::{
+    AuthorizationCallbackError, AuthorizationInquisitor, PeerAuthorizationState,
+};
+use splinter::network::auth::{AuthorizationManager, ChallengeAuthorization};
+#[cfg(feature = "network-capture")]
+use splinter::network::capture::CaptureWriter;
+#[cfg(feature = "peer-manager")]
+use splinter::network::connection_manager::{
+    ConnectionManager, ConnectionManagerNotification, Connector,
+};
 use splinter::network::dispatch::{DispatchLoop, DispatchMessage, Dispatcher};
 use splinter::network::handlers::{NetworkEchoHandler, NetworkHeartbeatHandler};
 use splinter::network::peer::PeerConnector;
@@ -59,6 +82,11 @@ use splinter::node_registry::{
     RwNodeRegistry,
 };
 use splinter::orchestrator::{NewOrchestratorError, ServiceOrchestrator};
+#[cfg(feature = "peer-manager")]
+use splinter::peer::{
+    rest_api::{make_peer_resource, make_peers_resource},
+    PeerManager,
+};
 use splinter::protos::authorization::AuthorizationMessageType;
 use splinter::protos::circuit::CircuitMessageType;
 use splinter::protos::network::{NetworkMessage, NetworkMessageType};
@@ -71,8 +99,11 @@ use splinter::service::scabbard::ScabbardFactory;
 #[cfg(feature = "service-arg-validation")]
 use splinter::service::validation::ServiceArgValidator;
 use splinter::service::{self, ServiceProcessor, ShutdownHandle};
-use splinter::signing::sawtooth::SawtoothSecp256k1SignatureVerifier;
+use splinter::signing::sawtooth::{SawtoothSecp256k1SignatureVerifier, SawtoothSecp256k1Signer};
 use splinter::storage::get_storage;
+use splinter::transport::tls::TlsReloader;
+#[cfg(feature = "unix-transport")]
+use splinter::transport::unix::UnixTransport;
 use splinter::transport::{
     inproc::InprocTransport, multi::MultiTransport, AcceptError, ConnectError, Incoming,
     ListenError, Listener, Transport,
@@ -80,10 +111,17 @@ use splinter::transport::{
 
 use crate::registry_config::{RegistryConfig, RegistryConfigBuilder, RegistryConfigError};
 use crate::routes;
+use crate::transport::SharedTransport;
 
 // Recv timeout in secs
 const TIMEOUT_SEC: u64 = 2;
 const ADMIN_SERVICE_ADDRESS: &str = "inproc://admin-service";
+const PEER_POLICY_RELOAD_INTERVAL_SEC: u64 = 10;
+const TLS_RELOAD_INTERVAL_SEC: u64 = 10;
+#[cfg(feature = "network-capture")]
+const CAPTURE_FILE_MAX_SIZE: u64 = 100 * 1024 * 1024;
+#[cfg(feature = "circuit-durability")]
+const DURABLE_RETRY_INTERVAL_SEC: u64 = 30;
 
 const ORCHESTRATOR_INCOMING_CAPACITY: usize = 8;
 const ORCHESTRATOR_OUTGOING_CAPACITY: usize = 8;
@@ -118,12 +156,35 @@ pub struct SplinterDaemon {
     registry_config: RegistryConfig,
     storage_type: String,
     admin_service_coordinator_timeout: Duration,
+    admin_service_proposal_ttl: Option<Duration>,
+    admin_service_approval_policies: HashMap<String, ApprovalPolicy>,
+    admin_service_auto_vote_key: Option<String>,
+    admin_service_voting_rules: Vec<VotingRule>,
+    admin_service_event_retention: EventRetention,
+    challenge_authorization_key: Option<String>,
+    require_challenge_authorization: bool,
+    require_certificate_identity: bool,
+    peer_policy_file: Option<String>,
+    service_uids: Vec<u32>,
+    tls_reloader: Option<TlsReloader>,
+    #[cfg(feature = "circuit-durability")]
+    durable_queue_dir: Option<String>,
+    #[cfg(feature = "database")]
+    circuit_state_migration_location: Option<String>,
 }
 
 impl SplinterDaemon {
-    pub fn start(&mut self, transport: Box<dyn Transport + Send>) -> Result<(), StartError> {
+    pub fn start(&mut self, transport: SharedTransport) -> Result<(), StartError> {
         let mut inproc_transport = InprocTransport::default();
-        let mut transports = vec![transport, Box::new(inproc_transport.clone())];
+        let mut transports: Vec<Box<dyn Transport + Send>> = vec![
+            Box::new(transport.clone()),
+            Box::new(inproc_transport.clone()),
+        ];
+
+        // Services on this host may connect over a unix:// service endpoint, whose socket file's
+        // permissions control which processes may connect
+        #[cfg(feature = "unix-transport")]
+        transports.push(Box::new(UnixTransport::default()));
 
         // Allowing unused_variable because health_inproc must be available later if feature
         // health is enabled
@@ -136,31 +197,27 @@ impl SplinterDaemon {
             None
         };
 
-        let mut transport = MultiTransport::new(transports);
+        let mut listening_transport = MultiTransport::new(transports);
 
         // Setup up ctrlc handling
         let running = Arc::new(AtomicBool::new(true));
 
         // Load initial state from the configured storage location and create the new
         // SplinterState from the retrieved circuit directory
-        let storage = get_storage(&self.storage_location, CircuitDirectory::new)
-            .map_err(StartError::StorageError)?;
-
-        let circuit_directory = storage.read().clone();
-        let state = SplinterState::new(self.storage_location.to_string(), circuit_directory);
+        let state = self.load_state()?;
 
         // set up the listeners on the transport
-        let mut network_listener = transport.listen(&self.network_endpoint)?;
+        let mut network_listener = listening_transport.listen(&self.network_endpoint)?;
         debug!(
             "Listening for peer connections on {}",
             network_listener.endpoint()
         );
-        let service_listener = transport.listen(&self.service_endpoint)?;
+        let service_listener = listening_transport.listen(&self.service_endpoint)?;
         debug!(
             "Listening for service connections on {}",
             service_listener.endpoint()
         );
-        let admin_service_listener = transport.listen(ADMIN_SERVICE_ADDRESS)?;
+        let admin_service_listener = listening_transport.listen(ADMIN_SERVICE_ADDRESS)?;
 
         // Listen for services
         Self::listen_for_services(
@@ -173,8 +230,81 @@ impl SplinterDaemon {
             service_listener,
         );
 
+        // peers are only connected to over the network transport
+        #[cfg(feature = "peer-manager")]
+        let (connection_manager, connector) =
+            Self::start_connection_manager(self.network.clone(), Box::new(transport.clone()))?;
+
         let peer_connector = PeerConnector::new(self.network.clone(), Box::new(transport));
-        let auth_manager = AuthorizationManager::new(self.network.clone(), self.node_id.clone());
+
+        let key_registry = Box::new(
+            StorageKeyRegistry::new(self.key_registry_location.clone())
+                .map_err(|err| StartError::StorageError(format!("{}", err)))?,
+        );
+
+        let node_registry = create_node_registry(&self.registry_config)?;
+
+        let mut auth_manager =
+            AuthorizationManager::new(self.network.clone(), self.node_id.clone());
+        if let Some(ref private_key) = self.challenge_authorization_key {
+            let signer = SawtoothSecp256k1Signer::from_hex(private_key).map_err(|err| {
+                StartError::NetworkError(format!("invalid challenge authorization key: {}", err))
+            })?;
+            auth_manager = auth_manager.with_challenge_authorization(ChallengeAuthorization::new(
+                Box::new(signer),
+                Box::new(SawtoothSecp256k1SignatureVerifier::new()),
+                Box::new((*key_registry).clone()),
+                self.require_challenge_authorization,
+            ));
+        }
+        if self.require_certificate_identity {
+            auth_manager = auth_manager.with_certificate_binding(Box::new(node_registry.clone()));
+        }
+        let peer_policy = match self.peer_policy_file {
+            Some(ref path) => {
+                let rules = PeerPolicyRules::from_yaml_file(path)
+                    .map_err(|err| StartError::NetworkError(err.to_string()))?;
+                let peer_policy = PeerPolicy::new(rules)
+                    .map_err(|err| {
+                        StartError::NetworkError(format!("invalid peer policy: {}", err))
+                    })?
+                    .with_node_registry(Box::new(node_registry.clone()));
+                auth_manager = auth_manager.with_peer_policy(peer_policy.clone());
+
+                let network = self.network.clone();
+                #[cfg(feature = "peer-manager")]
+                let connector = connector.clone();
+                let disconnect = move |peer_id: &str| {
+                    // stop the connection manager from reconnecting to the peer
+                    #[cfg(feature = "peer-manager")]
+                    {
+                        if let Some(endpoint) = network.get_peer_endpoint(peer_id) {
+                            if let Err(err) = connector.force_remove_connection(&endpoint) {
+                                error!("Unable to remove connection to {}: {}", endpoint, err);
+                            }
+                        }
+                    }
+                    if network.get_peer_endpoint(peer_id).is_some() {
+                        if let Err(err) = network.remove_connection(peer_id) {
+                            error!("Unable to disconnect peer {}: {}", peer_id, err);
+                        }
+                    }
+                };
+                Self::watch_peer_policy(
+                    path.clone(),
+                    peer_policy.clone(),
+                    auth_manager.clone(),
+                    disconnect,
+                    running.clone(),
+                );
+                Some(peer_policy)
+            }
+            None => None,
+        };
+
+        if let Some(tls_reloader) = self.tls_reloader.take() {
+            Self::watch_tls_files(tls_reloader, running.clone());
+        }
 
         info!("Starting SpinterNode with ID {}", self.node_id);
 
@@ -186,6 +316,17 @@ impl SplinterDaemon {
             network_sender.run()
         });
 
+        #[cfg(feature = "circuit-durability")]
+        let durable_queues = self.open_durable_queues()?;
+        #[cfg(feature = "circuit-durability")]
+        Self::start_durable_redelivery(
+            durable_queues.clone(),
+            &auth_manager,
+            self.network.clone(),
+            send.clone(),
+            running.clone(),
+        )?;
+
         // Set up the Circuit dispatcher
         let (circuit_dispatch_send, circuit_dispatch_recv) = crossbeam_channel::bounded(5);
         let circuit_dispatcher = set_up_circuit_dispatcher(
@@ -193,6 +334,10 @@ impl SplinterDaemon {
             &self.node_id,
             &self.network_endpoint,
             state.clone(),
+            self.network.clone(),
+            self.service_uids.clone(),
+            #[cfg(feature = "circuit-durability")]
+            durable_queues.clone(),
         );
         let circuit_dispatch_loop = DispatchLoop::new(
             Box::new(circuit_dispatch_recv),
@@ -217,6 +362,7 @@ impl SplinterDaemon {
         let network_dispatcher = set_up_network_dispatcher(
             send,
             &self.node_id,
+            self.network.clone(),
             auth_manager.clone(),
             circuit_dispatch_send,
             auth_dispatch_send,
@@ -249,6 +395,17 @@ impl SplinterDaemon {
                     }
                 };
                 debug!("Received connection from {}", connection.remote_endpoint());
+                if let Some(ref peer_policy) = peer_policy {
+                    // the connection is closed when it is dropped
+                    if let Err(err) = peer_policy.check_endpoint(&connection.remote_endpoint()) {
+                        warn!(
+                            "Rejecting connection from {}: {}",
+                            connection.remote_endpoint(),
+                            err
+                        );
+                        continue;
+                    }
+                }
                 match network_clone.add_connection(connection) {
                     Ok(peer_id) => debug!("Added connection with ID {}", peer_id),
                     Err(err) => error!("Failed to add connection to network: {}", err),
@@ -264,7 +421,9 @@ impl SplinterDaemon {
             }
         }
 
-        // For each node in the circuit_directory, try to connect and add them to the network
+        // For each node in the circuit_directory, try to connect and add them to the network; the
+        // peer manager keeps them connected instead, if it is enabled
+        #[cfg(not(feature = "peer-manager"))]
         for (node_id, node) in state.nodes()?.iter() {
             if node_id != &self.node_id {
                 if let Some(endpoint) = node.endpoints().get(0) {
@@ -350,39 +509,72 @@ impl SplinterDaemon {
 
         let signature_verifier = SawtoothSecp256k1SignatureVerifier::new();
 
-        let key_registry = Box::new(
-            StorageKeyRegistry::new(self.key_registry_location.clone())
-                .map_err(|err| StartError::StorageError(format!("{}", err)))?,
-        );
+        // Allowing possibly redundant clone of `auth_manager` since it will be needed again if the
+        // `peer-manager` feature is enabled
+        #[allow(clippy::redundant_clone)]
+        let admin_auth_inquisitor = Box::new(auth_manager.clone());
+        // Allowing possibly redundant clone of `state` since it will be needed again if the
+        // `circuit-read` feature is enabled
+        #[allow(clippy::redundant_clone)]
+        let admin_state = state.clone();
+        let mut admin_service_builder = AdminServiceBuilder::new()
+            .with_node_id(&self.node_id)
+            .with_orchestrator(orchestrator)
+            .with_peer_connector(peer_connector)
+            .with_authorization_inquisitor(admin_auth_inquisitor)
+            .with_splinter_state(admin_state)
+            .with_signature_verifier(Box::new(signature_verifier))
+            .with_key_registry(key_registry.clone())
+            .with_key_permission_manager(Box::new(AllowAllKeyPermissionManager))
+            .with_storage_type(self.proposal_storage_type())
+            .with_approval_policies(self.admin_service_approval_policies.clone())
+            .with_coordinator_timeout(self.admin_service_coordinator_timeout)
+            .with_event_retention(self.admin_service_event_retention.clone());
+
+        #[cfg(feature = "service-arg-validation")]
+        {
+            let mut validators: HashMap<String, Box<dyn ServiceArgValidator + Send>> =
+                HashMap::new();
+            validators.insert("scabbard".into(), Box::new(ScabbardArgValidator));
+            admin_service_builder = admin_service_builder.with_service_arg_validators(validators);
+        }
 
-        let admin_service = AdminService::new(
-            &self.node_id,
-            orchestrator,
-            #[cfg(feature = "service-arg-validation")]
-            {
-                let mut validators: HashMap<String, Box<dyn ServiceArgValidator + Send>> =
-                    HashMap::new();
-                validators.insert("scabbard".into(), Box::new(ScabbardArgValidator));
-                validators
-            },
-            peer_connector,
-            Box::new(auth_manager),
-            // Allowing possibly redundant clone of `state` since it will be needed again if the
-            // `circuit-read` feature is enabled
-            #[allow(clippy::redundant_clone)]
-            state.clone(),
-            Box::new(signature_verifier),
-            key_registry.clone(),
-            Box::new(AllowAllKeyPermissionManager),
-            &self.storage_type,
-            Some(self.admin_service_coordinator_timeout),
-        )
-        .map_err(|err| {
+        if let Some(proposal_ttl) = self.admin_service_proposal_ttl {
+            admin_service_builder = admin_service_builder.with_proposal_ttl(proposal_ttl);
+        }
+
+        if let Some(ref private_key) = self.admin_service_auto_vote_key {
+            let signer = SawtoothSecp256k1Signer::from_hex(private_key).map_err(|err| {
+                StartError::AdminServiceError(format!("invalid auto vote key: {}", err))
+            })?;
+            admin_service_builder = admin_service_builder.with_auto_voter(AutoVoter::new(
+                self.node_id.clone(),
+                self.admin_service_voting_rules.clone(),
+                Box::new(signer),
+            ));
+        }
+
+        let admin_service = admin_service_builder.build().map_err(|err| {
             StartError::AdminServiceError(format!("unable to create admin service: {}", err))
         })?;
-        let key_registry_manager = KeyRegistryManager::new(key_registry);
 
-        let node_registry = create_node_registry(&self.registry_config)?;
+        // the peer manager keeps the connections to the configured peers and to the members of
+        // the admin service's open proposals
+        #[cfg(feature = "peer-manager")]
+        let mut peer_manager = PeerManager::new(
+            self.node_id.clone(),
+            state.clone(),
+            Box::new(node_registry.clone()),
+            self.network.clone(),
+            connector.clone(),
+        )
+        .with_static_peers(self.initial_peers.clone())
+        .with_proposal_members(Box::new(admin_service.proposal_members()));
+        #[cfg(feature = "peer-manager")]
+        peer_manager
+            .start()
+            .map_err(|err| StartError::NetworkError(err.to_string()))?;
+        let key_registry_manager = KeyRegistryManager::new(key_registry);
 
         let node_id = self.node_id.clone();
         let service_endpoint = self.service_endpoint.clone();
@@ -412,6 +604,26 @@ impl SplinterDaemon {
             rest_api_builder = rest_api_builder.add_resources(circuit_resource.resources());
         }
 
+        #[cfg(feature = "circuit-durability")]
+        {
+            rest_api_builder = rest_api_builder.add_resource(
+                Resource::build("/durable_queues").add_method(Method::Get, move |_, _| {
+                    routes::get_durable_queues(&durable_queues)
+                }),
+            );
+        }
+
+        #[cfg(feature = "peer-manager")]
+        {
+            rest_api_builder = rest_api_builder
+                .add_resource(make_peers_resource(
+                    self.network.clone(),
+                    auth_manager,
+                    connector.clone(),
+                ))
+                .add_resource(make_peer_resource(self.network.clone(), connector));
+        }
+
         #[cfg(feature = "biome")]
         {
             if self.biome_enabled {
@@ -452,6 +664,11 @@ impl SplinterDaemon {
             .join()
             .map_err(|_| StartError::ThreadError("Unable to join main loop".into()))?;
 
+        #[cfg(feature = "peer-manager")]
+        peer_manager.shutdown_and_wait();
+        #[cfg(feature = "peer-manager")]
+        connection_manager.shutdown_and_wait();
+
         #[cfg(feature = "health")]
         {
             let health_service = HealthService::new(&self.node_id);
@@ -469,9 +686,341 @@ impl SplinterDaemon {
         let _ = rest_api_join_handle.join();
         let _ = service_processor_join_handle.join_all();
 
+        // Write out any messages captured while shutting down
+        #[cfg(feature = "network-capture")]
+        self.network.stop_capture();
+
+        Ok(())
+    }
+
+    /// Loads the circuit state from the configured storage location.
+    fn load_state(&self) -> Result<SplinterState, StartError> {
+        #[cfg(feature = "database")]
+        {
+            if self.storage_type == "database" {
+                let directory_store = create_circuit_directory_store(&self.storage_location)
+                    .map_err(|err| StartError::StorageError(err.to_string()))?;
+
+                if let Some(yaml_location) = &self.circuit_state_migration_location {
+                    if Path::new(yaml_location).is_file() {
+                        let stored_directory = directory_store
+                            .load_directory()
+                            .map_err(|err| StartError::StorageError(err.to_string()))?;
+                        if stored_directory.nodes().is_empty()
+                            && stored_directory.circuits().is_empty()
+                        {
+                            return Self::migrate_state(yaml_location, directory_store);
+                        }
+                        warn!(
+                            "Not migrating {}; the database already contains circuit state",
+                            yaml_location
+                        );
+                    }
+                }
+
+                return SplinterState::with_directory_store(
+                    self.storage_location.clone(),
+                    directory_store,
+                )
+                .map_err(|err| StartError::StorageError(err.to_string()));
+            }
+        }
+
+        // Load initial state from the configured storage location and create the new
+        // SplinterState from the retrieved circuit directory
+        let storage = get_storage(&self.storage_location, CircuitDirectory::new)
+            .map_err(StartError::StorageError)?;
+
+        let circuit_directory = storage.read().clone();
+        Ok(SplinterState::new(
+            self.storage_location.to_string(),
+            circuit_directory,
+        ))
+    }
+
+    /// Loads the circuit state from the given YAML file and migrates it into the given database
+    /// store in the background, so the daemon does not wait for a large state to be migrated.
+    /// Until the migration is complete, changes are written to the YAML file; the file is renamed
+    /// once the state has been migrated, so it is only migrated once.
+    #[cfg(feature = "database")]
+    fn migrate_state(
+        yaml_location: &str,
+        directory_store: Box<dyn CircuitDirectoryStore>,
+    ) -> Result<SplinterState, StartError> {
+        let storage =
+            get_storage(yaml_location, CircuitDirectory::new).map_err(StartError::StorageError)?;
+        let state = SplinterState::new(yaml_location.to_string(), storage.read().clone());
+
+        let migrating_state = state.clone();
+        let yaml_location = yaml_location.to_string();
+        thread::Builder::new()
+            .name("Circuit State Migration".into())
+            .spawn(move || {
+                info!(
+                    "Migrating circuit state from {} to the database",
+                    yaml_location
+                );
+                if let Err(err) = migrating_state.migrate_to_store(directory_store) {
+                    error!(
+                        "Unable to migrate circuit state from {}: {}",
+                        yaml_location, err
+                    );
+                    return;
+                }
+
+                let migrated_location = format!("{}.migrated", yaml_location);
+                match fs::rename(&yaml_location, &migrated_location) {
+                    Ok(()) => info!(
+                        "Migrated circuit state from {}; the file has been renamed to {}",
+                        yaml_location, migrated_location
+                    ),
+                    Err(err) => error!(
+                        "Migrated circuit state from {}, but unable to rename it to {}: {}",
+                        yaml_location, migrated_location, err
+                    ),
+                }
+            })
+            .map_err(|err| {
+                StartError::StorageError(format!(
+                    "Unable to start circuit state migration: {}",
+                    err
+                ))
+            })?;
+
+        Ok(state)
+    }
+
+    /// Returns the storage type of the admin service's proposals; proposals are stored in the
+    /// same database as the circuit state when database storage is used.
+    fn proposal_storage_type(&self) -> &str {
+        if self.storage_type == "database" {
+            &self.storage_location
+        } else {
+            &self.storage_type
+        }
+    }
+
+    /// Opens the queues of the messages sent on durable circuits, kept in the durable queue
+    /// directory if one is set, or in memory otherwise.
+    #[cfg(feature = "circuit-durability")]
+    fn open_durable_queues(&self) -> Result<DurableQueues, StartError> {
+        match self.durable_queue_dir {
+            Some(ref dir) => DurableQueues::open(dir).map_err(|err| {
+                StartError::StorageError(format!(
+                    "unable to open durable queues in {}: {}",
+                    dir, err
+                ))
+            }),
+            None => Ok(DurableQueues::new()),
+        }
+    }
+
+    /// Redelivers the messages queued on durable circuits for a peer when it is authorized, and
+    /// resends those that a connected peer has not acknowledged within the retry interval, until
+    /// the daemon shuts down.
+    #[cfg(feature = "circuit-durability")]
+    fn start_durable_redelivery(
+        durable_queues: DurableQueues,
+        auth_manager: &AuthorizationManager,
+        network: Network,
+        send: crossbeam_channel::Sender<SendRequest>,
+        running: Arc<AtomicBool>,
+    ) -> Result<(), StartError> {
+        let callback_queues = durable_queues.clone();
+        let callback_send = send.clone();
+        auth_manager
+            .register_callback(Box::new(
+                move |peer_id: &str,
+                      state: PeerAuthorizationState|
+                      -> Result<(), AuthorizationCallbackError> {
+                    if state == PeerAuthorizationState::Authorized {
+                        if let Err(err) = callback_queues.redeliver(peer_id, &callback_send) {
+                            error!(
+                                "Unable to redeliver queued messages to {}: {}",
+                                peer_id, err
+                            );
+                        }
+                    }
+                    Ok(())
+                },
+            ))
+            .map_err(|err| StartError::NetworkError(err.to_string()))?;
+
+        let retry_interval = Duration::from_secs(DURABLE_RETRY_INTERVAL_SEC);
+        let _ = thread::spawn(move || {
+            while running.load(Ordering::SeqCst) {
+                thread::sleep(retry_interval);
+
+                if let Err(err) = durable_queues.retry(&network.peer_ids(), retry_interval, &send) {
+                    error!("Unable to resend queued messages: {}", err);
+                }
+
+                for status in durable_queues.status() {
+                    debug!(
+                        "{} unacknowledged message{} queued for {}, the oldest for {}s",
+                        status.depth(),
+                        if status.depth() == 1 { "" } else { "s" },
+                        status.recipient(),
+                        status.oldest_age().unwrap_or_default().as_secs()
+                    );
+                }
+            }
+        });
+
         Ok(())
     }
 
+    /// Starts the connection manager that keeps the connections to peers alive, reconnecting them
+    /// with an exponential backoff when they are lost and authorizing each new connection, and
+    /// logs the changes to the connections.
+    #[cfg(feature = "peer-manager")]
+    fn start_connection_manager(
+        network: Network,
+        transport: Box<dyn Transport + Send>,
+    ) -> Result<(ConnectionManager<Network, Network>, Connector), StartError> {
+        let mut connection_manager =
+            ConnectionManager::new(network.clone(), network, transport, None, None);
+        let connector = connection_manager
+            .start()
+            .map_err(|err| StartError::NetworkError(err.to_string()))?;
+
+        let notifications = connector
+            .subscribe()
+            .map_err(|err| StartError::NetworkError(err.to_string()))?;
+        let _ = thread::spawn(move || {
+            for notification in notifications {
+                match notification {
+                    ConnectionManagerNotification::Connected { endpoint } => {
+                        info!("Connected to peer at {}", endpoint)
+                    }
+                    ConnectionManagerNotification::Disconnected { endpoint } => {
+                        warn!("Lost connection to peer at {}", endpoint)
+                    }
+                    ConnectionManagerNotification::Connecting { endpoint } => {
+                        debug!("Reconnecting to peer at {}", endpoint)
+                    }
+                    ConnectionManagerNotification::BackingOff {
+                        endpoint,
+                        attempts,
+                        retry_in,
+                    } => debug!(
+                        "Unable to connect to peer at {} after {} attempt{}; retrying in {}s",
+                        endpoint,
+                        attempts,
+                        if attempts == 1 { "" } else { "s" },
+                        retry_in.as_secs()
+                    ),
+                    ConnectionManagerNotification::Failed { endpoint, attempts } => error!(
+                        "Gave up connecting to {} after {} attempts",
+                        endpoint, attempts
+                    ),
+                }
+            }
+        });
+
+        Ok((connection_manager, connector))
+    }
+
+    /// Reloads the peer policy whenever its file is modified, until the daemon shuts down, and
+    /// disconnects the authorized peers that the reloaded policy denies. If the modified file is
+    /// invalid, the current policy is kept. The number of denied attempts is logged whenever it
+    /// changes.
+    fn watch_peer_policy<F>(
+        path: String,
+        peer_policy: PeerPolicy,
+        auth_manager: AuthorizationManager,
+        disconnect: F,
+        running: Arc<AtomicBool>,
+    ) where
+        F: Fn(&str) + Send + 'static,
+    {
+        let mut last_modified = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        let mut last_denied = peer_policy.denied_attempts();
+
+        let _ = thread::spawn(move || {
+            while running.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_secs(PEER_POLICY_RELOAD_INTERVAL_SEC));
+
+                let denied = peer_policy.denied_attempts();
+                if denied != last_denied {
+                    info!(
+                        "Peer policy has denied {} connections and {} authorizations so far",
+                        denied.by_address, denied.by_identity
+                    );
+                    last_denied = denied;
+                }
+
+                let modified = fs::metadata(&path)
+                    .and_then(|metadata| metadata.modified())
+                    .ok();
+                if modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+
+                match PeerPolicyRules::from_yaml_file(&path)
+                    .and_then(|rules| peer_policy.reload(rules))
+                {
+                    Ok(()) => {
+                        info!("Reloaded peer policy from {}", path);
+                        for peer_id in auth_manager.denied_peers() {
+                            warn!(
+                                "Disconnecting peer {}; it is no longer allowed by the peer policy",
+                                peer_id
+                            );
+                            disconnect(&peer_id);
+                        }
+                    }
+                    Err(err) => error!(
+                        "Unable to reload peer policy, keeping the current policy: {}",
+                        err
+                    ),
+                }
+            }
+        });
+    }
+
+    /// Reloads the TLS transport's certificates, keys and certificate revocation lists whenever
+    /// any of their files are modified, until the daemon shuts down. If the modified files are
+    /// invalid, such as while they are only partially replaced, the current ones are kept until
+    /// the files are modified again.
+    fn watch_tls_files(tls_reloader: TlsReloader, running: Arc<AtomicBool>) {
+        let paths = tls_reloader.paths();
+        let modified_times = move || {
+            paths
+                .iter()
+                .map(|path| {
+                    fs::metadata(path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut last_modified = modified_times();
+
+        let _ = thread::spawn(move || {
+            while running.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_secs(TLS_RELOAD_INTERVAL_SEC));
+
+                let modified = modified_times();
+                if modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+
+                match tls_reloader.reload() {
+                    Ok(()) => info!("Reloaded TLS certificates and keys"),
+                    Err(err) => error!(
+                        "Unable to reload TLS certificates and keys, keeping the current ones: {}",
+                        err
+                    ),
+                }
+            }
+        });
+    }
+
     fn listen_for_services(
         network: Network,
         mut internal_service_listener: Box<dyn Listener>,
@@ -681,6 +1230,23 @@ pub struct SplinterDaemonBuilder {
     storage_type: Option<String>,
     heartbeat_interval: Option<u64>,
     admin_service_coordinator_timeout: Duration,
+    admin_service_proposal_ttl: Option<Duration>,
+    admin_service_approval_policies: HashMap<String, ApprovalPolicy>,
+    admin_service_auto_vote_key: Option<String>,
+    admin_service_voting_rules: Vec<VotingRule>,
+    admin_service_event_retention: EventRetention,
+    challenge_authorization_key: Option<String>,
+    require_challenge_authorization: bool,
+    require_certificate_identity: bool,
+    peer_policy_file: Option<String>,
+    service_uids: Vec<u32>,
+    tls_reloader: Option<TlsReloader>,
+    #[cfg(feature = "network-capture")]
+    capture_file: Option<String>,
+    #[cfg(feature = "circuit-durability")]
+    durable_queue_dir: Option<String>,
+    #[cfg(feature = "database")]
+    circuit_state_migration_location: Option<String>,
 }
 
 impl SplinterDaemonBuilder {
@@ -760,6 +1326,84 @@ impl SplinterDaemonBuilder {
         self
     }
 
+    pub fn with_admin_service_proposal_ttl(mut self, value: Option<Duration>) -> Self {
+        self.admin_service_proposal_ttl = value;
+        self
+    }
+
+    pub fn with_admin_service_approval_policies(
+        mut self,
+        value: HashMap<String, ApprovalPolicy>,
+    ) -> Self {
+        self.admin_service_approval_policies = value;
+        self
+    }
+
+    pub fn with_admin_service_auto_vote_key(mut self, value: Option<String>) -> Self {
+        self.admin_service_auto_vote_key = value;
+        self
+    }
+
+    pub fn with_admin_service_voting_rules(mut self, value: Vec<VotingRule>) -> Self {
+        self.admin_service_voting_rules = value;
+        self
+    }
+
+    pub fn with_admin_service_event_retention(mut self, value: EventRetention) -> Self {
+        self.admin_service_event_retention = value;
+        self
+    }
+
+    pub fn with_challenge_authorization_key(mut self, value: Option<String>) -> Self {
+        self.challenge_authorization_key = value;
+        self
+    }
+
+    pub fn with_require_challenge_authorization(mut self, value: bool) -> Self {
+        self.require_challenge_authorization = value;
+        self
+    }
+
+    pub fn with_require_certificate_identity(mut self, value: bool) -> Self {
+        self.require_certificate_identity = value;
+        self
+    }
+
+    pub fn with_peer_policy_file(mut self, value: Option<String>) -> Self {
+        self.peer_policy_file = value;
+        self
+    }
+
+    pub fn with_service_uids(mut self, value: Vec<u32>) -> Self {
+        self.service_uids = value;
+        self
+    }
+
+    pub fn with_tls_reloader(mut self, value: Option<TlsReloader>) -> Self {
+        self.tls_reloader = value;
+        self
+    }
+
+    #[cfg(feature = "network-capture")]
+    pub fn with_capture_file(mut self, value: Option<String>) -> Self {
+        self.capture_file = value;
+        self
+    }
+
+    #[cfg(feature = "circuit-durability")]
+    pub fn with_durable_queue_dir(mut self, value: Option<String>) -> Self {
+        self.durable_queue_dir = value;
+        self
+    }
+
+    /// Sets the YAML circuit state file to migrate into the database, when database storage is
+    /// used and the database does not contain any circuit state yet.
+    #[cfg(feature = "database")]
+    pub fn with_circuit_state_migration_location(mut self, value: Option<String>) -> Self {
+        self.circuit_state_migration_location = value;
+        self
+    }
+
     pub fn build(self) -> Result<SplinterDaemon, CreateError> {
         let heartbeat_interval = self.heartbeat_interval.ok_or_else(|| {
             CreateError::MissingRequiredField("Missing field: heartbeat_interval".to_string())
@@ -769,6 +1413,22 @@ impl SplinterDaemonBuilder {
         let network = Network::new(mesh, heartbeat_interval)
             .map_err(|err| CreateError::NetworkError(err.to_string()))?;
 
+        #[cfg(feature = "network-capture")]
+        {
+            if let Some(capture_file) = &self.capture_file {
+                let writer = CaptureWriter::create(capture_file)
+                    .map_err(|err| {
+                        CreateError::NetworkError(format!(
+                            "unable to open capture file {}: {}",
+                            capture_file, err
+                        ))
+                    })?
+                    .with_max_size(CAPTURE_FILE_MAX_SIZE);
+                info!("Capturing network messages to {}", capture_file);
+                network.start_capture(writer);
+            }
+        }
+
         let storage_location = self.storage_location.ok_or_else(|| {
             CreateError::MissingRequiredField("Missing field: storage_location".to_string())
         })?;
@@ -839,6 +1499,21 @@ impl SplinterDaemonBuilder {
             key_registry_location,
             storage_type,
             admin_service_coordinator_timeout: self.admin_service_coordinator_timeout,
+            admin_service_proposal_ttl: self.admin_service_proposal_ttl,
+            admin_service_approval_policies: self.admin_service_approval_policies,
+            admin_service_auto_vote_key: self.admin_service_auto_vote_key,
+            admin_service_voting_rules: self.admin_service_voting_rules,
+            admin_service_event_retention: self.admin_service_event_retention,
+            challenge_authorization_key: self.challenge_authorization_key,
+            require_challenge_authorization: self.require_challenge_authorization,
+            require_certificate_identity: self.require_certificate_identity,
+            peer_policy_file: self.peer_policy_file,
+            service_uids: self.service_uids,
+            tls_reloader: self.tls_reloader,
+            #[cfg(feature = "circuit-durability")]
+            durable_queue_dir: self.durable_queue_dir,
+            #[cfg(feature = "database")]
+            circuit_state_migration_location: self.circuit_state_migration_location,
         })
     }
 }
@@ -846,6 +1521,7 @@ impl SplinterDaemonBuilder {
 fn set_up_network_dispatcher(
     send: crossbeam_channel::Sender<SendRequest>,
     node_id: &str,
+    network: Network,
     auth_manager: AuthorizationManager,
     circuit_sender: crossbeam_channel::Sender<DispatchMessage<CircuitMessageType>>,
     auth_sender: crossbeam_channel::Sender<DispatchMessage<AuthorizationMessageType>>,
@@ -861,7 +1537,7 @@ fn set_up_network_dispatcher(
         )),
     );
 
-    let network_heartbeat_handler = NetworkHeartbeatHandler::new();
+    let network_heartbeat_handler = NetworkHeartbeatHandler::new().with_network(network);
     // do not add auth guard
     dispatcher.set_handler(
         NetworkMessageType::NETWORK_HEARTBEAT,
@@ -891,11 +1567,16 @@ fn set_up_circuit_dispatcher(
     node_id: &str,
     endpoint: &str,
     state: SplinterState,
+    network: Network,
+    service_uids: Vec<u32>,
+    #[cfg(feature = "circuit-durability")] durable_queues: DurableQueues,
 ) -> Dispatcher<CircuitMessageType> {
     let mut dispatcher = Dispatcher::<CircuitMessageType>::new(Box::new(send));
 
     let service_connect_request_handler =
-        ServiceConnectRequestHandler::new(node_id.to_string(), endpoint.to_string(), state.clone());
+        ServiceConnectRequestHandler::new(node_id.to_string(), endpoint.to_string(), state.clone())
+            .with_network(network)
+            .with_allowed_uids(service_uids);
     dispatcher.set_handler(
         CircuitMessageType::SERVICE_CONNECT_REQUEST,
         Box::new(service_connect_request_handler),
@@ -909,11 +1590,32 @@ fn set_up_circuit_dispatcher(
 
     let direct_message_handler =
         CircuitDirectMessageHandler::new(node_id.to_string(), state.clone());
+    #[cfg(feature = "circuit-durability")]
+    let direct_message_handler = direct_message_handler.with_durable_queues(durable_queues.clone());
     dispatcher.set_handler(
         CircuitMessageType::CIRCUIT_DIRECT_MESSAGE,
         Box::new(direct_message_handler),
     );
 
+    #[cfg(feature = "circuit-durability")]
+    {
+        let direct_message_ack_handler = CircuitDirectMessageAckHandler::new(durable_queues);
+        dispatcher.set_handler(
+            CircuitMessageType::CIRCUIT_DIRECT_MESSAGE_ACK,
+            Box::new(direct_message_ack_handler),
+        );
+    }
+
+    #[cfg(feature = "circuit-relay")]
+    {
+        let relayed_message_handler =
+            CircuitRelayedMessageHandler::new(node_id.to_string(), state.clone());
+        dispatcher.set_handler(
+            CircuitMessageType::CIRCUIT_RELAYED_MESSAGE,
+            Box::new(relayed_message_handler),
+        );
+    }
+
     let circuit_error_handler = CircuitErrorHandler::new(node_id.to_string(), state.clone());
     dispatcher.set_handler(
         CircuitMessageType::CIRCUIT_ERROR_MESSAGE,
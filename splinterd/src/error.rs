@@ -16,6 +16,8 @@ use std::error::Error;
 use std::fmt;
 use std::io;
 
+#[cfg(feature = "transport-proxy")]
+use splinter::transport::proxy::ProxyError;
 use splinter::transport::tls::TlsInitError;
 
 use crate::config::ConfigError;
@@ -113,6 +115,8 @@ pub enum GetTransportError {
     NotSupportedError(String),
     TlsTransportError(TlsInitError),
     IoError(io::Error),
+    #[cfg(feature = "transport-proxy")]
+    ProxyError(ProxyError),
 }
 
 impl Error for GetTransportError {
@@ -122,6 +126,8 @@ impl Error for GetTransportError {
             GetTransportError::NotSupportedError(_) => None,
             GetTransportError::TlsTransportError(err) => Some(err),
             GetTransportError::IoError(err) => Some(err),
+            #[cfg(feature = "transport-proxy")]
+            GetTransportError::ProxyError(err) => Some(err),
         }
     }
 }
@@ -141,6 +147,8 @@ impl fmt::Display for GetTransportError {
             GetTransportError::IoError(err) => {
                 write!(f, "unable to get transport due to IoError: {}", err)
             }
+            #[cfg(feature = "transport-proxy")]
+            GetTransportError::ProxyError(err) => write!(f, "invalid proxy settings: {}", err),
         }
     }
 }
@@ -151,6 +159,13 @@ impl From<TlsInitError> for GetTransportError {
     }
 }
 
+#[cfg(feature = "transport-proxy")]
+impl From<ProxyError> for GetTransportError {
+    fn from(proxy_error: ProxyError) -> Self {
+        GetTransportError::ProxyError(proxy_error)
+    }
+}
+
 impl From<io::Error> for GetTransportError {
     fn from(io_error: io::Error) -> Self {
         GetTransportError::IoError(io_error)
@@ -44,6 +44,7 @@ use crate::config::{Config, ConfigBuilder, ConfigError};
 use crate::daemon::SplinterDaemonBuilder;
 use clap::{clap_app, crate_version};
 use clap::{Arg, ArgMatches};
+use splinter::admin::service::EventRetention;
 
 use std::env;
 use std::fs;
@@ -221,11 +222,21 @@ fn start_daemon(matches: ArgMatches) -> Result<(), UserError> {
 
     let config = create_config(config_file_path, matches.clone())?;
 
-    let transport = get_transport(&config)?;
+    let (transport, tls_reloader) = get_transport(&config)?;
 
     let storage_location = match &config.storage() as &str {
         "yaml" => format!("{}{}", config.state_dir(), "circuits.yaml"),
         "memory" => "memory".to_string(),
+        #[cfg(feature = "database")]
+        "database" => {
+            if !splinter::database::is_database_url(config.database()) {
+                return Err(UserError::InvalidArgument(format!(
+                    "database storage requires a postgres:// or sqlite:// database URL: {}",
+                    config.database()
+                )));
+            }
+            config.database().to_string()
+        }
         _ => {
             return Err(UserError::InvalidArgument(format!(
                 "storage type is not supported: {}",
@@ -237,6 +248,9 @@ fn start_daemon(matches: ArgMatches) -> Result<(), UserError> {
     let key_registry_location = match &config.storage() as &str {
         "yaml" => format!("{}{}", config.state_dir(), "keys.yaml"),
         "memory" => "memory".to_string(),
+        // the key registry is not stored in the database
+        #[cfg(feature = "database")]
+        "database" => format!("{}{}", config.state_dir(), "keys.yaml"),
         _ => {
             return Err(UserError::InvalidArgument(format!(
                 "storage type is not supported: {}",
@@ -266,7 +280,100 @@ fn start_daemon(matches: ArgMatches) -> Result<(), UserError> {
         .with_rest_api_endpoint(String::from(rest_api_endpoint))
         .with_storage_type(String::from(config.storage()))
         .with_heartbeat_interval(config.heartbeat_interval())
-        .with_admin_service_coordinator_timeout(admin_service_coordinator_timeout);
+        .with_admin_service_coordinator_timeout(admin_service_coordinator_timeout)
+        .with_admin_service_proposal_ttl(config.admin_service_proposal_ttl())
+        .with_admin_service_approval_policies(config.admin_service_approval_policies().clone())
+        .with_admin_service_voting_rules(config.admin_service_voting_rules().to_vec())
+        .with_admin_service_event_retention(EventRetention {
+            max_events: Some(config.admin_service_event_retention_count()),
+            max_age: Some(config.admin_service_event_retention_age()),
+        });
+
+    match config.admin_service_auto_vote_key() {
+        Some(key_file) => {
+            let private_key = fs::read_to_string(key_file)?.trim().to_string();
+            daemon_builder = daemon_builder.with_admin_service_auto_vote_key(Some(private_key));
+        }
+        None if !config.admin_service_voting_rules().is_empty() => {
+            warn!("Voting rules are configured without an auto vote key; they will not be used");
+        }
+        None => (),
+    }
+
+    if config.require_certificate_identity() {
+        if config.transport() != "tls" || config.insecure() {
+            return Err(UserError::InvalidArgument(
+                "require_certificate_identity requires the tls transport, without insecure mode"
+                    .into(),
+            ));
+        }
+        daemon_builder = daemon_builder.with_require_certificate_identity(true);
+    }
+
+    if let Some(peer_policy_file) = config.peer_policy_file() {
+        daemon_builder = daemon_builder.with_peer_policy_file(Some(peer_policy_file.into()));
+    }
+
+    if config.network_endpoint().starts_with("unix://") {
+        return Err(UserError::InvalidArgument(
+            "network_endpoint may not be a unix:// endpoint; peers cannot connect to it".into(),
+        ));
+    }
+
+    daemon_builder = daemon_builder.with_service_uids(config.service_uids().to_vec());
+
+    // The YAML circuit state of a node that has switched to database storage is migrated into the
+    // database while the node runs
+    #[cfg(feature = "database")]
+    {
+        if config.storage() == "database" {
+            daemon_builder = daemon_builder.with_circuit_state_migration_location(Some(format!(
+                "{}{}",
+                config.state_dir(),
+                "circuits.yaml"
+            )));
+        }
+    }
+
+    daemon_builder = daemon_builder.with_tls_reloader(tls_reloader);
+
+    if let Some(capture_file) = config.capture_file() {
+        #[cfg(feature = "network-capture")]
+        {
+            daemon_builder = daemon_builder.with_capture_file(Some(capture_file.into()));
+        }
+        #[cfg(not(feature = "network-capture"))]
+        warn!(
+            "Network capture to {} requires the network-capture feature; it will not be used",
+            capture_file
+        );
+    }
+
+    // The messages queued on durable circuits are kept in the state directory, unless circuits are
+    // only kept in memory
+    #[cfg(feature = "circuit-durability")]
+    {
+        let durable_queue_dir = match &config.storage() as &str {
+            "memory" => None,
+            _ => Some(format!("{}{}", config.state_dir(), "durable_queues")),
+        };
+        daemon_builder = daemon_builder.with_durable_queue_dir(durable_queue_dir);
+    }
+
+    match config.challenge_authorization_key() {
+        Some(key_file) => {
+            let private_key = fs::read_to_string(key_file)?.trim().to_string();
+            daemon_builder = daemon_builder
+                .with_challenge_authorization_key(Some(private_key))
+                .with_require_challenge_authorization(config.require_challenge_authorization());
+        }
+        None if config.require_challenge_authorization() => {
+            return Err(UserError::MissingArgument(
+                "challenge_authorization_key, since challenge authorization is required".into(),
+            ));
+        }
+        None => (),
+    }
 
     #[cfg(feature = "database")]
     {
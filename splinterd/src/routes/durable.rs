@@ -0,0 +1,49 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use splinter::actix_web::{Error, HttpResponse};
+use splinter::circuit::durable::DurableQueues;
+use splinter::futures::{Future, IntoFuture};
+
+#[derive(Debug, Serialize)]
+struct DurableQueueStatus {
+    node_id: String,
+    depth: usize,
+    /// The age of the oldest message in the queue, in seconds
+    oldest_age: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct DurableQueuesResponse {
+    queues: Vec<DurableQueueStatus>,
+}
+
+pub fn get_durable_queues(
+    durable_queues: &DurableQueues,
+) -> Box<dyn Future<Item = HttpResponse, Error = Error>> {
+    let queues = durable_queues
+        .status()
+        .into_iter()
+        .map(|status| DurableQueueStatus {
+            node_id: status.recipient().to_string(),
+            depth: status.depth(),
+            oldest_age: status.oldest_age().map(|age| age.as_secs()),
+        })
+        .collect();
+
+    Box::new(
+        HttpResponse::Ok()
+            .json(DurableQueuesResponse { queues })
+            .into_future(),
+    )
+}
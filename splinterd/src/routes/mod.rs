@@ -13,6 +13,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(feature = "circuit-durability")]
+mod durable;
 mod status;
 
+#[cfg(feature = "circuit-durability")]
+pub use durable::*;
 pub use status::*;
@@ -14,15 +14,42 @@
 
 use std::fs;
 use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
+#[cfg(feature = "ws-transport")]
+use splinter::transport::multi::MultiTransport;
+#[cfg(feature = "transport-proxy")]
+use splinter::transport::proxy::{Proxy, ProxySettings};
 use splinter::transport::raw::RawTransport;
-use splinter::transport::tls::TlsTransport;
-use splinter::transport::Transport;
+use splinter::transport::tls::{TlsReloader, TlsTransport};
+#[cfg(feature = "ws-transport")]
+use splinter::transport::ws::WsTransport;
+use splinter::transport::{
+    ConnectError, Connection, FrameCompression, FrameOptions, ListenError, Listener, Transport,
+};
 
 use crate::config::Config;
 use crate::error::GetTransportError;
 
-pub fn get_transport(config: &Config) -> Result<Box<dyn Transport + Send>, GetTransportError> {
+/// The time allowed for a peer to accept a connection, so that an unreachable peer does not hold
+/// up connecting to the others.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Returns the transport for the configuration, along with the reloader for its TLS certificates
+/// and keys, if it uses TLS.
+pub fn get_transport(
+    config: &Config,
+) -> Result<(SharedTransport, Option<TlsReloader>), GetTransportError> {
+    let frame_options = get_frame_options(config)?;
+
+    #[cfg(not(feature = "transport-proxy"))]
+    {
+        if config.proxy().is_some() || !config.proxy_endpoints().is_empty() {
+            warn!("Proxies require the transport-proxy feature; they will not be used");
+        }
+    }
+
     match config.transport() {
         "tls" => {
             let client_cert = config.client_cert();
@@ -108,14 +135,167 @@ pub fn get_transport(config: &Config) -> Result<Box<dyn Transport + Send>, GetTr
                 String::from(client_cert),
                 String::from(server_key_file),
                 String::from(server_cert),
-            )?;
+            )?
+            .with_frame_options(frame_options);
+
+            #[cfg(feature = "transport-proxy")]
+            let transport = transport.with_proxy_settings(get_proxy_settings(config)?);
+
+            let transport = match config.crl_file() {
+                Some(crl_file) if insecure => {
+                    warn!(
+                        "Ignoring certificate revocation list {} in insecure mode",
+                        crl_file
+                    );
+                    transport
+                }
+                Some(crl_file) => {
+                    debug!("Using certificate revocation list file: {:?}", crl_file);
+                    transport.with_crl_file(crl_file.into())?
+                }
+                None => transport,
+            };
+
+            let transport = transport.with_connect_timeout(CONNECT_TIMEOUT);
+
+            let tls_reloader = transport.reloader();
+
+            let transport = SharedTransport::new(move || {
+                // ws:// and wss:// endpoints are also accepted, with wss:// connections secured by
+                // the same certificates
+                #[cfg(feature = "ws-transport")]
+                let transport = MultiTransport::new(vec![
+                    Box::new(WsTransport::with_tls(&transport)),
+                    Box::new(transport.clone()),
+                ]);
+                #[cfg(not(feature = "ws-transport"))]
+                let transport = transport.clone();
+
+                Box::new(transport)
+            });
 
-            Ok(Box::new(transport))
+            Ok((transport, Some(tls_reloader)))
+        }
+        "raw" => {
+            #[cfg(feature = "transport-proxy")]
+            let proxy_settings = get_proxy_settings(config)?;
+
+            let transport = RawTransport::default().with_frame_options(frame_options);
+
+            #[cfg(feature = "transport-proxy")]
+            let transport = transport.with_proxy_settings(proxy_settings.clone());
+
+            let transport = transport.with_connect_timeout(CONNECT_TIMEOUT);
+
+            #[cfg(feature = "ws-transport")]
+            let ws_transport = WsTransport::default().with_frame_options(frame_options);
+
+            #[cfg(all(feature = "ws-transport", feature = "transport-proxy"))]
+            let ws_transport = ws_transport.with_proxy_settings(proxy_settings);
+
+            let transport = SharedTransport::new(move || {
+                #[cfg(feature = "ws-transport")]
+                let transport = MultiTransport::new(vec![
+                    Box::new(ws_transport.clone()),
+                    Box::new(transport.clone()),
+                ]);
+                #[cfg(not(feature = "ws-transport"))]
+                let transport = transport.clone();
+
+                Box::new(transport)
+            });
+
+            Ok((transport, None))
         }
-        "raw" => Ok(Box::new(RawTransport::default())),
         _ => Err(GetTransportError::NotSupportedError(format!(
             "Transport type {} is not supported",
             config.transport()
         ))),
     }
 }
+
+/// A transport that may be used by several components at once, such as the listeners, the peer
+/// connector and the connection manager.
+///
+/// Each use is made with a new transport, created from the same configuration, certificates and
+/// keys, so that a connection that is slow to be made does not hold up any others.
+#[derive(Clone)]
+pub struct SharedTransport {
+    create_transport: Arc<dyn Fn() -> Box<dyn Transport + Send> + Send + Sync>,
+}
+
+impl SharedTransport {
+    pub fn new<F>(create_transport: F) -> Self
+    where
+        F: Fn() -> Box<dyn Transport + Send> + Send + Sync + 'static,
+    {
+        Self {
+            create_transport: Arc::new(create_transport),
+        }
+    }
+}
+
+impl Transport for SharedTransport {
+    fn accepts(&self, address: &str) -> bool {
+        (self.create_transport)().accepts(address)
+    }
+
+    fn connect(&mut self, endpoint: &str) -> Result<Box<dyn Connection>, ConnectError> {
+        (self.create_transport)().connect(endpoint)
+    }
+
+    fn listen(&mut self, bind: &str) -> Result<Box<dyn Listener>, ListenError> {
+        (self.create_transport)().listen(bind)
+    }
+}
+
+/// Returns the settings that decide which proxy, if any, is used to connect to each peer.
+#[cfg(feature = "transport-proxy")]
+fn get_proxy_settings(config: &Config) -> Result<ProxySettings, GetTransportError> {
+    let mut proxy_settings = ProxySettings::default();
+
+    if let Some(proxy) = config.proxy() {
+        proxy_settings = proxy_settings.with_proxy(Proxy::from_url(proxy)?);
+    }
+
+    for proxy_endpoint in config.proxy_endpoints() {
+        proxy_settings = proxy_settings.with_endpoint_proxy(
+            &proxy_endpoint.endpoint,
+            Proxy::from_url(&proxy_endpoint.proxy)?,
+        );
+    }
+
+    if let Some(no_proxy) = config.no_proxy() {
+        proxy_settings = proxy_settings.with_no_proxy(no_proxy);
+    }
+
+    debug!("Using proxy settings: {:?}", proxy_settings);
+    Ok(proxy_settings)
+}
+
+fn get_frame_options(config: &Config) -> Result<FrameOptions, GetTransportError> {
+    let mut frame_options = FrameOptions::default();
+
+    if let Some(max_frame_size) = config.max_frame_size() {
+        frame_options = frame_options.with_max_frame_size(max_frame_size);
+    }
+
+    if let Some(compression) = config.frame_compression() {
+        let compression = match compression {
+            "deflate" => FrameCompression::Deflate,
+            "zstd" => FrameCompression::Zstd,
+            _ => {
+                return Err(GetTransportError::NotSupportedError(format!(
+                    "Frame compression {} is not supported",
+                    compression
+                )))
+            }
+        };
+        if !cfg!(feature = "frame-compression") {
+            warn!("Frame compression requires the frame-compression feature; it will not be used");
+        }
+        frame_options = frame_options.with_compression(compression);
+    }
+
+    Ok(frame_options)
+}